@@ -33,7 +33,20 @@ public enum ResultCode implements IntEnum<ResultCode> {
     TableNotExistError,
     TagNotExistError,
     UnSupported,
-    Others;
+    Others,
+    SubmitJobError,
+    RetriableError,
+    NodeNotExistError,
+    NullPointerError,
+    Utf8Error,
+    ComplexityExceededError,
+    EvalError,
+    InvalidHandleError,
+    InternalError,
+    HopRangeExceededError,
+    PlanFrozenError,
+    InvalidPlanError,
+    AccessDeniedError;
 
     @Override
     public int getInt() {
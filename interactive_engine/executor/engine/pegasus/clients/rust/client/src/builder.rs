@@ -550,6 +550,7 @@ impl JobBuilder {
                 }
                 ServerConf::All => Some(pegasus_pb::job_config::Servers::All(pegasus_pb::Empty {})),
             },
+            priority: self.conf.priority,
         };
 
         let source = pb::Source { resource: self.source };
@@ -78,4 +78,9 @@ pub trait SortLimitBy<D: Data> {
     fn sort_limit_by<F>(self, size: u32, cmp: F) -> Result<Stream<D>, BuildJobError>
     where
         F: Fn(&D, &D) -> Ordering + Send + 'static;
+
+    /// Apply `sort_limit_by()` per each partition.
+    fn sort_limit_by_partition<F>(self, size: u32, cmp: F) -> Result<Stream<D>, BuildJobError>
+    where
+        F: Fn(&D, &D) -> Ordering + Send + 'static;
 }
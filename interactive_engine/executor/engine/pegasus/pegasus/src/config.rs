@@ -87,6 +87,9 @@ lazy_static! {
     pub static ref BRANCH_OPT: bool = configure_with_default!(bool, "BRANCH_OPT", true);
 }
 
+/// the most times a worker steps its dataflow once being scheduled, however high the priority is;
+pub const MAX_SCHEDULE_STEPS: usize = 8;
+
 #[derive(Debug, Clone)]
 pub enum ServerConf {
     Local,
@@ -136,6 +139,8 @@ pub struct JobConf {
     pub trace_enable: bool,
     /// optimization factors of early-stop
     pub debug: bool,
+    /// the priority of the job, where a larger value means a higher priority;
+    pub priority: i32,
 }
 
 impl JobConf {
@@ -177,6 +182,17 @@ impl JobConf {
             self.servers.len() * self.workers as usize
         }
     }
+
+    /// The times each worker of the job steps its dataflow once being scheduled, which grows with
+    /// the priority up to `MAX_SCHEDULE_STEPS`, such that a job of a higher priority takes a larger
+    /// share of the executor than the jobs of lower priorities running alongside;
+    pub fn schedule_steps(&self) -> usize {
+        if self.priority <= 0 {
+            1
+        } else {
+            (self.priority as usize + 1).min(MAX_SCHEDULE_STEPS)
+        }
+    }
 }
 
 impl Default for JobConf {
@@ -198,6 +214,7 @@ impl Default for JobConf {
             servers: ServerConf::Local,
             trace_enable: false,
             debug: false,
+            priority: 0,
         }
     }
 }
@@ -55,7 +55,7 @@ use std::collections::HashSet;
 use std::fmt::Debug;
 use std::net::SocketAddr;
 
-pub use config::{read_from, Configuration, JobConf, ServerConf};
+pub use config::{read_from, Configuration, JobConf, ServerConf, MAX_SCHEDULE_STEPS};
 pub use data::Data;
 pub use pegasus_common::codec;
 pub use pegasus_memory::alloc::check_current_task_memory;
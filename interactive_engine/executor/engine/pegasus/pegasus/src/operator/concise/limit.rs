@@ -84,6 +84,13 @@ impl<D: Data> SortLimitBy<D> for Stream<D> {
         let local_sort = sort_limit_by_partition(self, "sort_limit_by_partition_locally", size, cmp_clone)?;
         sort_limit_by_partition(local_sort.aggregate(), "sort_limit_by_partition_globally", size, cmp)
     }
+
+    fn sort_limit_by_partition<F>(self, size: u32, cmp: F) -> Result<Stream<D>, BuildJobError>
+    where
+        F: Fn(&D, &D) -> Ordering + Send + 'static,
+    {
+        sort_limit_by_partition(self, "sort_limit_by_partition", size, ShadeCmp { cmp: Arc::new(cmp) })
+    }
 }
 
 type Cmp<D> = Arc<dyn Fn(&D, &D) -> Ordering + Send + 'static>;
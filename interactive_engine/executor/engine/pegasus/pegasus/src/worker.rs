@@ -141,19 +141,20 @@ enum WorkerTask {
 }
 
 impl WorkerTask {
-    pub fn execute(&mut self) -> Result<TaskState, JobExecError> {
+    pub fn execute(&mut self, steps: usize) -> Result<TaskState, JobExecError> {
         match self {
             WorkerTask::Empty => Ok(TaskState::Finished),
             WorkerTask::Dataflow(df, sch) => {
-                sch.step(df)?;
-                if df.check_finish() {
-                    sch.close()?;
-                    Ok(TaskState::Finished)
-                } else if df.is_idle()? {
-                    Ok(TaskState::NotReady)
-                } else {
-                    Ok(TaskState::Ready)
+                for _ in 0..steps {
+                    sch.step(df)?;
+                    if df.check_finish() {
+                        sch.close()?;
+                        return Ok(TaskState::Finished);
+                    } else if df.is_idle()? {
+                        return Ok(TaskState::NotReady);
+                    }
                 }
+                Ok(TaskState::Ready)
             }
         }
     }
@@ -224,7 +225,7 @@ impl<D: Data, T: Debug + Send + 'static> Task for Worker<D, T> {
 
         let _ctx = WorkerContext::new(&mut self.resources, &mut self.keyed_resources);
 
-        match self.task.execute() {
+        match self.task.execute(self.conf.schedule_steps()) {
             Ok(state) => {
                 if TaskState::Finished == state {
                     info_worker!(
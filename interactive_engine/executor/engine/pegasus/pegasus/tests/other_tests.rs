@@ -25,3 +25,30 @@ fn flatmap_x_repartition_x_filtermap_x_broadcast_x_test() {
         println!("{}", n);
     }
 }
+
+#[test]
+fn prioritized_job_test() {
+    let mut conf = JobConf::new("prioritized_job_test");
+    conf.set_workers(2);
+    conf.priority = 100;
+    assert_eq!(conf.schedule_steps(), pegasus::MAX_SCHEDULE_STEPS);
+
+    let mut results = pegasus::run(conf, || {
+        |input, output| {
+            let worker_id = input.get_worker_index();
+            input
+                .input_from(0..1000u64)?
+                .repartition(move |id| Ok(*id + worker_id as u64))
+                .map(|id| Ok(id + 1))?
+                .sink_into(output)
+        }
+    })
+    .expect("run job fail;");
+
+    let mut count = 0;
+    while let Some(next) = results.next() {
+        next.unwrap();
+        count += 1;
+    }
+    assert_eq!(count, 2000);
+}
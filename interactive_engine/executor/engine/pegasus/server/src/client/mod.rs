@@ -5,9 +5,12 @@ use std::path::Path;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use futures::stream::{BoxStream, SelectAll};
+use futures::stream::{BoxStream, Peekable, SelectAll};
 use futures::{Stream, StreamExt};
 use pegasus::{JobConf, ServerConf};
+use tonic::metadata::{Ascii, MetadataValue};
+pub use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+use tonic::transport::{Channel, Endpoint};
 
 use crate::job::JobDesc;
 use crate::pb::job_config::Servers;
@@ -19,6 +22,18 @@ pub enum JobError {
     RPCError(tonic::Status),
 }
 
+impl JobError {
+    /// Whether the error is transient, i.e., the server is temporarily unavailable, such that the
+    /// request may succeed if retried. The errors after the server may have accepted the request,
+    /// e.g., `DeadlineExceeded` and `Aborted`, are not transient, as a retried job may run twice.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            JobError::InvalidConfig(_) => false,
+            JobError::RPCError(status) => status.code() == tonic::Code::Unavailable,
+        }
+    }
+}
+
 impl Debug for JobError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -34,6 +49,123 @@ impl Display for JobError {
     }
 }
 
+/// The credential of a job, which is attached to the RPCs of submitting the job as the
+/// `authorization` metadata, such that the server can check the permissions of the user.
+#[derive(Clone)]
+pub enum JobAuth {
+    /// A bearer token
+    Token(String),
+    /// The user name and the password in the basic scheme
+    Basic { user: String, password: String },
+}
+
+impl Debug for JobAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // never expose the credential in logs
+        match self {
+            JobAuth::Token(_) => write!(f, "Token(***)"),
+            JobAuth::Basic { user, .. } => write!(f, "Basic({}, ***)", user),
+        }
+    }
+}
+
+impl JobAuth {
+    fn to_metadata(&self) -> Result<MetadataValue<Ascii>, JobError> {
+        use base64::Engine;
+
+        let value = match self {
+            JobAuth::Token(token) => format!("Bearer {}", token),
+            JobAuth::Basic { user, password } => format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password))
+            ),
+        };
+        value
+            .parse()
+            .map_err(|_| JobError::InvalidConfig("invalid characters in the credential".to_string()))
+    }
+}
+
+/// A cursor over the results of a submitted job, which keeps how far the results have been
+/// consumed, such that the caller can fetch the results batch by batch instead of buffering all.
+pub struct JobCursor {
+    stream: Peekable<BoxStream<'static, Result<Vec<u8>, tonic::Status>>>,
+    fetched: u64,
+}
+
+impl JobCursor {
+    pub fn new(stream: BoxStream<'static, Result<Vec<u8>, tonic::Status>>) -> Self {
+        JobCursor { stream: stream.peekable(), fetched: 0 }
+    }
+
+    /// Create a cursor that reads at most `capacity` results from the server ahead of being fetched.
+    /// The reading is suspended once the buffer is full, so that a slow consumer holds a bounded
+    /// number of results in memory. Must be called within a tokio runtime.
+    pub fn with_buffer(
+        stream: BoxStream<'static, Result<Vec<u8>, tonic::Status>>, capacity: usize,
+    ) -> Self {
+        let (tx, rx) = tokio::sync::mpsc::channel(capacity);
+        tokio::spawn(async move {
+            let mut stream = stream;
+            while let Some(res) = stream.next().await {
+                if tx.send(res).await.is_err() {
+                    // the cursor has been dropped
+                    break;
+                }
+            }
+        });
+        Self::new(tokio_stream::wrappers::ReceiverStream::new(rx).boxed())
+    }
+
+    /// Fetch at most `max_rows` results, where fewer results are returned only if the job has
+    /// no more results.
+    pub async fn fetch(&mut self, max_rows: usize) -> Result<Vec<Vec<u8>>, JobError> {
+        let mut batch = Vec::with_capacity(std::cmp::min(max_rows, 1024));
+        while batch.len() < max_rows {
+            match self.stream.next().await {
+                Some(Ok(res)) => batch.push(res),
+                Some(Err(status)) => return Err(JobError::RPCError(status)),
+                None => break,
+            }
+        }
+        self.fetched += batch.len() as u64;
+        Ok(batch)
+    }
+
+    /// Fetch at most `max_rows` results as [`JobCursor::fetch`], except that the results
+    /// fetched before an error are returned along with the error, rather than being discarded.
+    pub async fn fetch_until_error(&mut self, max_rows: usize) -> (Vec<Vec<u8>>, Option<JobError>) {
+        let mut batch = Vec::with_capacity(std::cmp::min(max_rows, 1024));
+        let mut error = None;
+        while batch.len() < max_rows {
+            match self.stream.next().await {
+                Some(Ok(res)) => batch.push(res),
+                Some(Err(status)) => {
+                    error = Some(JobError::RPCError(status));
+                    break;
+                }
+                None => break,
+            }
+        }
+        self.fetched += batch.len() as u64;
+        (batch, error)
+    }
+
+    /// Whether the job has more results to fetch, which waits until the next result arrives,
+    /// or the job finishes.
+    pub async fn has_more(&mut self) -> bool {
+        Pin::new(&mut self.stream)
+            .peek()
+            .await
+            .is_some()
+    }
+
+    /// The number of results that have been fetched.
+    pub fn fetched(&self) -> u64 {
+        self.fetched
+    }
+}
+
 pub struct RPCJobClient {
     conns: Vec<Option<RefCell<JobServiceClient<tonic::transport::Channel>>>>,
 }
@@ -49,11 +181,36 @@ impl RPCJobClient {
         D::Error: Into<tonic::codegen::StdError>,
     {
         let client = JobServiceClient::connect(url).await?;
+        self.add_conn(server_id, client);
+        Ok(())
+    }
+
+    /// Connect to the server via TLS as the `tls` config, or in plaintext if `tls` is `None`.
+    /// An invalid url or TLS config is reported as `InvalidConfig`, while a failure of connecting
+    /// to the server is reported as an `Unavailable` status, which is transient.
+    pub async fn connect_with_tls(
+        &mut self, server_id: u64, url: String, tls: Option<ClientTlsConfig>,
+    ) -> Result<(), JobError> {
+        let mut endpoint = Endpoint::from_shared(url)
+            .map_err(|e| JobError::InvalidConfig(format!("invalid url: {}", e)))?;
+        if let Some(tls) = tls {
+            endpoint = endpoint
+                .tls_config(tls)
+                .map_err(|e| JobError::InvalidConfig(format!("invalid tls config: {}", e)))?;
+        }
+        let channel = endpoint
+            .connect()
+            .await
+            .map_err(|e| JobError::RPCError(tonic::Status::unavailable(e.to_string())))?;
+        self.add_conn(server_id, JobServiceClient::new(channel));
+        Ok(())
+    }
+
+    fn add_conn(&mut self, server_id: u64, client: JobServiceClient<Channel>) {
         while server_id as usize >= self.conns.len() {
             self.conns.push(None);
         }
         self.conns[server_id as usize] = Some(RefCell::new(client));
-        Ok(())
     }
 
     pub async fn add_library<P: AsRef<Path>>(&mut self, name: &str, path: P) -> Result<(), JobError> {
@@ -105,6 +262,23 @@ impl RPCJobClient {
     pub async fn submit(
         &mut self, config: JobConf, job: JobDesc,
     ) -> Result<BoxStream<'static, Result<Vec<u8>, tonic::Status>>, JobError> {
+        self.submit_with_auth(config, job, None).await
+    }
+
+    /// Submit a job with the credential attached to the RPCs, if any.
+    pub async fn submit_with_auth(
+        &mut self, config: JobConf, job: JobDesc, auth: Option<&JobAuth>,
+    ) -> Result<BoxStream<'static, Result<Vec<u8>, tonic::Status>>, JobError> {
+        let auth = auth.map(JobAuth::to_metadata).transpose()?;
+        let to_request = |req: JobRequest| {
+            let mut request = tonic::Request::new(req);
+            if let Some(auth) = &auth {
+                request
+                    .metadata_mut()
+                    .insert("authorization", auth.clone());
+            }
+            request
+        };
         let mut remotes = vec![];
         let servers = match config.servers() {
             ServerConf::Local => {
@@ -160,11 +334,16 @@ impl RPCJobClient {
             memory_limit: config.memory_limit,
             trace_enable: config.trace_enable,
             servers: Some(servers),
+            priority: config.priority,
         };
         let req = JobRequest { conf: Some(conf), source: input, plan, resource };
 
         if r_size == 1 {
-            match remotes[0].borrow_mut().submit(req).await {
+            match remotes[0]
+                .borrow_mut()
+                .submit(to_request(req))
+                .await
+            {
                 Ok(resp) => Ok(resp
                     .into_inner()
                     .map(|r| r.map(|jr| jr.resp))
@@ -174,7 +353,7 @@ impl RPCJobClient {
         } else {
             let mut tasks = Vec::with_capacity(r_size);
             for r in remotes {
-                let req = req.clone();
+                let req = to_request(req.clone());
                 tasks.push(async move {
                     let mut conn = r.borrow_mut();
                     conn.submit(req).await
@@ -198,6 +377,19 @@ impl RPCJobClient {
                 .boxed())
         }
     }
+
+    /// Submit a job, and return a cursor to fetch its results, which buffers at most
+    /// `buffer_size` results ahead of being fetched, or none if `buffer_size` is 0.
+    pub async fn submit_with_cursor(
+        &mut self, config: JobConf, job: JobDesc, auth: Option<&JobAuth>, buffer_size: usize,
+    ) -> Result<JobCursor, JobError> {
+        let stream = self.submit_with_auth(config, job, auth).await?;
+        if buffer_size > 0 {
+            Ok(JobCursor::with_buffer(stream, buffer_size))
+        } else {
+            Ok(JobCursor::new(stream))
+        }
+    }
 }
 
 pub enum Either<T: Stream + Unpin> {
@@ -373,6 +373,8 @@ fn parse_conf_req(mut req: pb::JobConfig) -> JobConf {
         conf.plan_print = true;
     }
 
+    conf.priority = req.priority;
+
     if let Some(servers) = req.servers.take() {
         match servers {
             Servers::Local(_) => conf.reset_servers(ServerConf::Local),
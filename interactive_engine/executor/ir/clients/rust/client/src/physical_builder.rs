@@ -28,11 +28,31 @@ use prost::Message;
 #[derive(Clone, Debug, PartialEq)]
 pub struct PlanBuilder {
     plan: Vec<pb::PhysicalOpr>,
+    /// Whether the plan may return partial results rather than failing
+    allow_partial: bool,
+    /// The policy applied by the expansions of the plan while expanding from a super node
+    super_node_policy: Option<algebra_pb::SuperNodePolicy>,
+    /// Where the plan originates from, e.g., the text of the query
+    origin: Option<algebra_pb::PlanOrigin>,
+    /// The mode of the plan if it is a continuous query
+    continuous: Option<algebra_pb::ContinuousMode>,
+    /// The names of the registered results to release once the plan has read them
+    released_results: Vec<String>,
+    /// The scope of the results registered and scanned by the plan
+    result_scope: u64,
 }
 
 impl Default for PlanBuilder {
     fn default() -> Self {
-        PlanBuilder { plan: vec![] }
+        PlanBuilder {
+            plan: vec![],
+            allow_partial: false,
+            super_node_policy: None,
+            origin: None,
+            continuous: None,
+            released_results: vec![],
+            result_scope: 0,
+        }
     }
 }
 
@@ -60,13 +80,23 @@ impl PlanBuilder {
     /// Scan as the source, when the data come from the scan operator.
     /// If the plan is single source, scan would be the root op;
     /// Otherwise, the root is the dummy node, while the real sources are multiple scans.
-    pub fn add_scan_source(&mut self, mut scan: algebra_pb::Scan) -> &mut Self {
+    pub fn add_scan_source(&mut self, scan: algebra_pb::Scan) -> &mut Self {
+        self.add_partitioned_scan_source(scan, vec![])
+    }
+
+    /// Scan as the source as [`Self::add_scan_source`], while the scan is only done by the workers
+    /// that access the given partitions, or by all the workers if empty.
+    pub fn add_partitioned_scan_source(
+        &mut self, mut scan: algebra_pb::Scan, partitions: Vec<u32>,
+    ) -> &mut Self {
         let meta_data = scan
             .meta_data
             .take()
             .map(|meta| vec![meta.into()])
             .unwrap_or_default();
-        let op = pb::physical_opr::operator::OpKind::Scan(scan.into());
+        let mut scan: pb::Scan = scan.into();
+        scan.partitions = partitions;
+        let op = pb::physical_opr::operator::OpKind::Scan(scan);
         self.plan.push((op, meta_data).into());
         self
     }
@@ -91,6 +121,12 @@ impl PlanBuilder {
         self.repartition(repartition)
     }
 
+    pub fn gather(&mut self) -> &mut Self {
+        let repartition =
+            pb::Repartition { strategy: Some(pb::repartition::Strategy::ToOne(pb::repartition::Gather {})) };
+        self.repartition(repartition)
+    }
+
     pub fn project(&mut self, project: algebra_pb::Project) -> &mut Self {
         let meta_data = project
             .meta_data
@@ -121,6 +157,12 @@ impl PlanBuilder {
         self
     }
 
+    pub fn count(&mut self, count: algebra_pb::Count) -> &mut Self {
+        let op = pb::physical_opr::operator::OpKind::Count(count);
+        self.plan.push(op.into());
+        self
+    }
+
     pub fn order(&mut self, order: algebra_pb::OrderBy) -> &mut Self {
         let op = pb::physical_opr::operator::OpKind::OrderBy(order);
         self.plan.push(op.into());
@@ -158,8 +200,17 @@ impl PlanBuilder {
         let apply = pb::Apply {
             join_kind: unsafe { ::std::mem::transmute(join_kind) },
             keys: vec![],
-            sub_plan: Some(pb::PhysicalPlan { plan: sub_plan.take() }),
+            sub_plan: Some(pb::PhysicalPlan {
+                plan: sub_plan.take(),
+                allow_partial: false,
+                super_node_policy: None,
+                origin: None,
+                continuous: None,
+                released_results: vec![],
+                result_scope: 0,
+            }),
             alias,
+            timeout_ms: 0,
         };
         let op = pb::physical_opr::operator::OpKind::Apply(apply);
         self.plan.push(op.into());
@@ -186,8 +237,17 @@ impl PlanBuilder {
         let apply = pb::Apply {
             join_kind: unsafe { ::std::mem::transmute(join_kind) },
             keys,
-            sub_plan: Some(pb::PhysicalPlan { plan: sub_plan.take() }),
+            sub_plan: Some(pb::PhysicalPlan {
+                plan: sub_plan.take(),
+                allow_partial: false,
+                super_node_policy: None,
+                origin: None,
+                continuous: None,
+                released_results: vec![],
+                result_scope: 0,
+            }),
             alias,
+            timeout_ms: 0,
         };
         let op = pb::physical_opr::operator::OpKind::Apply(apply);
         self.plan.push(op.into());
@@ -209,13 +269,38 @@ impl PlanBuilder {
     pub fn join(
         &mut self, join_kind: algebra_pb::join::JoinKind, left_plan: PlanBuilder, right_plan: PlanBuilder,
         left_keys: Vec<common_pb::Variable>, right_keys: Vec<common_pb::Variable>,
+    ) -> &mut Self {
+        self.join_with_renames(join_kind, left_plan, right_plan, left_keys, right_keys, vec![])
+    }
+
+    pub fn join_with_renames(
+        &mut self, join_kind: algebra_pb::join::JoinKind, left_plan: PlanBuilder, right_plan: PlanBuilder,
+        left_keys: Vec<common_pb::Variable>, right_keys: Vec<common_pb::Variable>,
+        renames: Vec<algebra_pb::join::Rename>,
     ) -> &mut Self {
         let join = pb::Join {
             left_keys,
             right_keys,
             join_kind: unsafe { ::std::mem::transmute(join_kind) },
-            left_plan: Some(pb::PhysicalPlan { plan: left_plan.take() }),
-            right_plan: Some(pb::PhysicalPlan { plan: right_plan.take() }),
+            left_plan: Some(pb::PhysicalPlan {
+                plan: left_plan.take(),
+                allow_partial: false,
+                super_node_policy: None,
+                origin: None,
+                continuous: None,
+                released_results: vec![],
+                result_scope: 0,
+            }),
+            right_plan: Some(pb::PhysicalPlan {
+                plan: right_plan.take(),
+                allow_partial: false,
+                super_node_policy: None,
+                origin: None,
+                continuous: None,
+                released_results: vec![],
+                result_scope: 0,
+            }),
+            renames,
         };
         let op = pb::physical_opr::operator::OpKind::Join(join);
         self.plan.push(op.into());
@@ -240,7 +325,15 @@ impl PlanBuilder {
     pub fn union(&mut self, mut plans: Vec<PlanBuilder>) -> &mut Self {
         let mut sub_plans = vec![];
         for plan in plans.drain(..) {
-            sub_plans.push(pb::PhysicalPlan { plan: plan.take() });
+            sub_plans.push(pb::PhysicalPlan {
+                plan: plan.take(),
+                allow_partial: false,
+                super_node_policy: None,
+                origin: None,
+                continuous: None,
+                released_results: vec![],
+                result_scope: 0,
+            });
         }
         let union = pb::Union { sub_plans };
         let op = pb::physical_opr::operator::OpKind::Union(union);
@@ -252,7 +345,15 @@ impl PlanBuilder {
         let key = key.try_into().unwrap();
         let mut sub_plans = vec![];
         for plan in plans.drain(..) {
-            sub_plans.push(pb::PhysicalPlan { plan: plan.take() });
+            sub_plans.push(pb::PhysicalPlan {
+                plan: plan.take(),
+                allow_partial: false,
+                super_node_policy: None,
+                origin: None,
+                continuous: None,
+                released_results: vec![],
+                result_scope: 0,
+            });
         }
         let intersect = pb::Intersect { sub_plans, key };
         let op = pb::physical_opr::operator::OpKind::Intersect(intersect);
@@ -318,8 +419,97 @@ impl PlanBuilder {
         self.plan.last_mut()
     }
 
-    pub fn build(self) -> pb::PhysicalPlan {
-        pb::PhysicalPlan { plan: self.plan }
+    pub fn allow_partial(&mut self, allow_partial: bool) {
+        self.allow_partial = allow_partial;
+    }
+
+    pub fn super_node_policy(&mut self, policy: algebra_pb::SuperNodePolicy) {
+        self.super_node_policy = Some(policy);
+    }
+
+    pub fn origin(&mut self, origin: algebra_pb::PlanOrigin) {
+        self.origin = Some(origin);
+    }
+
+    pub fn continuous(&mut self, mode: algebra_pb::ContinuousMode) {
+        self.continuous = Some(mode);
+    }
+
+    pub fn release_results(&mut self, names: Vec<String>) {
+        self.released_results = names;
+    }
+
+    pub fn result_scope(&mut self, scope: u64) {
+        self.result_scope = scope;
+    }
+
+    /// Build the physical plan, where the super node policy (if any) is applied to each `EdgeExpand`
+    /// of the plan (including those of the sub-plans) that has none of its own.
+    pub fn build(mut self) -> pb::PhysicalPlan {
+        if let Some(policy) = self.super_node_policy.as_ref() {
+            apply_super_node_policy(&mut self.plan, policy);
+        }
+        pb::PhysicalPlan {
+            plan: self.plan,
+            allow_partial: self.allow_partial,
+            super_node_policy: self.super_node_policy,
+            origin: self.origin,
+            continuous: self.continuous,
+            released_results: self.released_results,
+            result_scope: self.result_scope,
+        }
+    }
+}
+
+fn apply_super_node_policy(plan: &mut [pb::PhysicalOpr], policy: &algebra_pb::SuperNodePolicy) {
+    use pb::physical_opr::operator::OpKind;
+    let apply_to_edge = |edge: &mut pb::EdgeExpand| {
+        if edge.super_node_policy.is_none() {
+            edge.super_node_policy = Some(policy.clone());
+        }
+    };
+    for opr in plan.iter_mut() {
+        match opr
+            .opr
+            .as_mut()
+            .and_then(|opr| opr.op_kind.as_mut())
+        {
+            Some(OpKind::Edge(edge)) => apply_to_edge(edge),
+            Some(OpKind::Path(path)) => {
+                if let Some(edge) = path
+                    .base
+                    .as_mut()
+                    .and_then(|base| base.edge_expand.as_mut())
+                {
+                    apply_to_edge(edge);
+                }
+            }
+            Some(OpKind::Apply(apply)) => {
+                if let Some(sub_plan) = apply.sub_plan.as_mut() {
+                    apply_super_node_policy(&mut sub_plan.plan, policy);
+                }
+            }
+            Some(OpKind::Join(join)) => {
+                for sub_plan in join
+                    .left_plan
+                    .iter_mut()
+                    .chain(join.right_plan.iter_mut())
+                {
+                    apply_super_node_policy(&mut sub_plan.plan, policy);
+                }
+            }
+            Some(OpKind::Union(union)) => {
+                for sub_plan in union.sub_plans.iter_mut() {
+                    apply_super_node_policy(&mut sub_plan.plan, policy);
+                }
+            }
+            Some(OpKind::Intersect(intersect)) => {
+                for sub_plan in intersect.sub_plans.iter_mut() {
+                    apply_super_node_policy(&mut sub_plan.plan, policy);
+                }
+            }
+            _ => {}
+        }
     }
 }
 
@@ -367,6 +557,14 @@ impl JobBuilder {
         self
     }
 
+    pub fn add_partitioned_scan_source(
+        &mut self, scan: algebra_pb::Scan, partitions: Vec<u32>,
+    ) -> &mut Self {
+        self.plan
+            .add_partitioned_scan_source(scan, partitions);
+        self
+    }
+
     pub fn repartition(&mut self, route: pb::Repartition) -> &mut Self {
         self.plan.repartition(route);
         self
@@ -382,6 +580,11 @@ impl JobBuilder {
         self
     }
 
+    pub fn gather(&mut self) -> &mut Self {
+        self.plan.gather();
+        self
+    }
+
     pub fn project(&mut self, project: algebra_pb::Project) -> &mut Self {
         self.plan.project(project);
         self
@@ -397,6 +600,11 @@ impl JobBuilder {
         self
     }
 
+    pub fn count(&mut self, count: algebra_pb::Count) -> &mut Self {
+        self.plan.count(count);
+        self
+    }
+
     pub fn order(&mut self, order: algebra_pb::OrderBy) -> &mut Self {
         self.plan.order(order);
         self
@@ -465,6 +673,16 @@ impl JobBuilder {
         self
     }
 
+    pub fn join_with_renames(
+        &mut self, join_kind: algebra_pb::join::JoinKind, left_plan: PlanBuilder, right_plan: PlanBuilder,
+        left_keys: Vec<common_pb::Variable>, right_keys: Vec<common_pb::Variable>,
+        renames: Vec<algebra_pb::join::Rename>,
+    ) -> &mut Self {
+        self.plan
+            .join_with_renames(join_kind, left_plan, right_plan, left_keys, right_keys, renames);
+        self
+    }
+
     pub fn join_func<FL, FR>(
         &mut self, join_kind: algebra_pb::join::JoinKind, left_task: FL, right_task: FR,
         left_keys: Vec<common_pb::Variable>, right_keys: Vec<common_pb::Variable>,
@@ -530,6 +748,7 @@ impl JobBuilder {
                 }
                 ServerConf::All => Some(pegasus_pb::job_config::Servers::All(pegasus_pb::Empty {})),
             },
+            priority: self.conf.priority,
         };
 
         let plan = self.plan.build();
@@ -556,6 +775,8 @@ mod test {
             params: None,
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
         let sink_pb = algebra_pb::Sink { tags: vec![], sink_target: None };
         builder
@@ -563,7 +784,7 @@ mod test {
             .select(algebra_pb::Select { predicate: None })
             .repartition(pb::Repartition { strategy: None })
             .project(algebra_pb::Project { mappings: vec![], is_append: false, meta_data: vec![] })
-            .limit(algebra_pb::Limit { range: None })
+            .limit(algebra_pb::Limit { range: None, per_partition: false })
             .sink(sink_pb.clone());
         let plan_len = builder.plan.len();
         // source, select, repartition, project, limit, sink
@@ -579,6 +800,8 @@ mod test {
             params: None,
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
         let scan2_pb = scan1_pb.clone();
         let project_pb = algebra_pb::Project { mappings: vec![], is_append: false, meta_data: vec![] };
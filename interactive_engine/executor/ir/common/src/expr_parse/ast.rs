@@ -0,0 +1,283 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+
+use std::convert::TryFrom;
+
+use crate::expr_parse::error::{ExprError, ExprResult};
+use crate::expr_parse::to_suffix_expr;
+use crate::expr_parse::token::tokenize;
+use crate::generated::common as pb;
+
+/// The binary operators of an [`Expr`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Exp,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitLShift,
+    BitRShift,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Within,
+    Without,
+    StartsWith,
+    EndsWith,
+}
+
+impl From<BinaryOp> for pb::ExprOpr {
+    fn from(op: BinaryOp) -> Self {
+        match op {
+            BinaryOp::Add => pb::Arithmetic::Add.into(),
+            BinaryOp::Sub => pb::Arithmetic::Sub.into(),
+            BinaryOp::Mul => pb::Arithmetic::Mul.into(),
+            BinaryOp::Div => pb::Arithmetic::Div.into(),
+            BinaryOp::Mod => pb::Arithmetic::Mod.into(),
+            BinaryOp::Exp => pb::Arithmetic::Exp.into(),
+            BinaryOp::BitAnd => pb::Arithmetic::Bitand.into(),
+            BinaryOp::BitOr => pb::Arithmetic::Bitor.into(),
+            BinaryOp::BitXor => pb::Arithmetic::Bitxor.into(),
+            BinaryOp::BitLShift => pb::Arithmetic::Bitlshift.into(),
+            BinaryOp::BitRShift => pb::Arithmetic::Bitrshift.into(),
+            BinaryOp::Eq => pb::Logical::Eq.into(),
+            BinaryOp::Ne => pb::Logical::Ne.into(),
+            BinaryOp::Lt => pb::Logical::Lt.into(),
+            BinaryOp::Le => pb::Logical::Le.into(),
+            BinaryOp::Gt => pb::Logical::Gt.into(),
+            BinaryOp::Ge => pb::Logical::Ge.into(),
+            BinaryOp::And => pb::Logical::And.into(),
+            BinaryOp::Or => pb::Logical::Or.into(),
+            BinaryOp::Within => pb::Logical::Within.into(),
+            BinaryOp::Without => pb::Logical::Without.into(),
+            BinaryOp::StartsWith => pb::Logical::Startswith.into(),
+            BinaryOp::EndsWith => pb::Logical::Endswith.into(),
+        }
+    }
+}
+
+impl TryFrom<&pb::expr_opr::Item> for BinaryOp {
+    type Error = ExprError;
+
+    fn try_from(item: &pb::expr_opr::Item) -> ExprResult<Self> {
+        match item {
+            pb::expr_opr::Item::Arith(arith) => match pb::Arithmetic::from_i32(*arith) {
+                Some(pb::Arithmetic::Add) => Ok(BinaryOp::Add),
+                Some(pb::Arithmetic::Sub) => Ok(BinaryOp::Sub),
+                Some(pb::Arithmetic::Mul) => Ok(BinaryOp::Mul),
+                Some(pb::Arithmetic::Div) => Ok(BinaryOp::Div),
+                Some(pb::Arithmetic::Mod) => Ok(BinaryOp::Mod),
+                Some(pb::Arithmetic::Exp) => Ok(BinaryOp::Exp),
+                Some(pb::Arithmetic::Bitand) => Ok(BinaryOp::BitAnd),
+                Some(pb::Arithmetic::Bitor) => Ok(BinaryOp::BitOr),
+                Some(pb::Arithmetic::Bitxor) => Ok(BinaryOp::BitXor),
+                Some(pb::Arithmetic::Bitlshift) => Ok(BinaryOp::BitLShift),
+                Some(pb::Arithmetic::Bitrshift) => Ok(BinaryOp::BitRShift),
+                None => Err(ExprError::unsupported(format!("arithmetic operator {:?}", arith))),
+            },
+            pb::expr_opr::Item::Logical(logical) => match pb::Logical::from_i32(*logical) {
+                Some(pb::Logical::Eq) => Ok(BinaryOp::Eq),
+                Some(pb::Logical::Ne) => Ok(BinaryOp::Ne),
+                Some(pb::Logical::Lt) => Ok(BinaryOp::Lt),
+                Some(pb::Logical::Le) => Ok(BinaryOp::Le),
+                Some(pb::Logical::Gt) => Ok(BinaryOp::Gt),
+                Some(pb::Logical::Ge) => Ok(BinaryOp::Ge),
+                Some(pb::Logical::And) => Ok(BinaryOp::And),
+                Some(pb::Logical::Or) => Ok(BinaryOp::Or),
+                Some(pb::Logical::Within) => Ok(BinaryOp::Within),
+                Some(pb::Logical::Without) => Ok(BinaryOp::Without),
+                Some(pb::Logical::Startswith) => Ok(BinaryOp::StartsWith),
+                Some(pb::Logical::Endswith) => Ok(BinaryOp::EndsWith),
+                _ => Err(ExprError::unsupported(format!("binary logical operator {:?}", logical))),
+            },
+            _ => Err(ExprError::unsupported(format!("binary operator {:?}", item))),
+        }
+    }
+}
+
+/// An expression as a tree, which the frontends that already have their own ASTs build via the
+/// constructors, rather than printing their ASTs as strings to be parsed again. It is lowered into
+/// the pb-[`Expression`] via `From`, in which the operands are braced as needed.
+///
+/// [`Expression`]: crate::generated::common::Expression
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// A variable, e.g., `@a.name`
+    Var(pb::Variable),
+    /// A constant value
+    Const(pb::Value),
+    /// An array of variables, e.g., `[@a, @a.name]`
+    Vars(Vec<pb::Variable>),
+    /// A map of variables, e.g., `{@a, @a.name}`
+    VarMap(Vec<pb::Variable>),
+    /// The logical negation of the expression
+    Not(Box<Expr>),
+    /// A binary operation of the left and right expressions
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// A variable given as a string, e.g., `@a.name`
+    pub fn var(var: &str) -> Self {
+        Expr::Var(pb::Variable::from(var.to_string()))
+    }
+
+    pub fn constant<T: Into<pb::Value>>(value: T) -> Self {
+        Expr::Const(value.into())
+    }
+
+    pub fn not(expr: Expr) -> Self {
+        Expr::Not(Box::new(expr))
+    }
+
+    pub fn binary(op: BinaryOp, left: Expr, right: Expr) -> Self {
+        Expr::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    /// Parse the expression from a string, as [`str_to_expr_pb`] does, into a tree.
+    ///
+    /// [`str_to_expr_pb`]: crate::expr_parse::str_to_expr_pb
+    pub fn parse(expr_str: &str) -> ExprResult<Self> {
+        let mut stack: Vec<Expr> = vec![];
+        let pop = |stack: &mut Vec<Expr>| {
+            stack
+                .pop()
+                .ok_or_else(|| ExprError::from(format!("missing operand in {:?}", expr_str).as_str()))
+        };
+        for token in to_suffix_expr(tokenize(expr_str)?)? {
+            let expr = match pb::ExprOpr::try_from(token)?.item {
+                Some(pb::expr_opr::Item::Var(var)) => Expr::Var(var),
+                Some(pb::expr_opr::Item::Const(value)) => Expr::Const(value),
+                Some(pb::expr_opr::Item::Vars(vars)) => Expr::Vars(vars.keys),
+                Some(pb::expr_opr::Item::VarMap(vars)) => Expr::VarMap(vars.keys),
+                Some(pb::expr_opr::Item::Logical(logical)) if logical == pb::Logical::Not as i32 => {
+                    Expr::not(pop(&mut stack)?)
+                }
+                Some(item) => {
+                    let op = BinaryOp::try_from(&item)?;
+                    let right = pop(&mut stack)?;
+                    let left = pop(&mut stack)?;
+                    Expr::binary(op, left, right)
+                }
+                None => return Err(ExprError::from("empty expression operator")),
+            };
+            stack.push(expr);
+        }
+        let expr = pop(&mut stack)?;
+        if !stack.is_empty() {
+            return Err(format!("redundant operands in {:?}", expr_str)
+                .as_str()
+                .into());
+        }
+
+        Ok(expr)
+    }
+
+    fn push_operators(&self, operators: &mut Vec<pb::ExprOpr>) {
+        match self {
+            Expr::Var(var) => operators.push(var.clone().into()),
+            Expr::Const(value) => operators.push(value.clone().into()),
+            Expr::Vars(vars) => operators.push((pb::VariableKeys { keys: vars.clone() }, false).into()),
+            Expr::VarMap(vars) => operators.push((pb::VariableKeys { keys: vars.clone() }, true).into()),
+            Expr::Not(expr) => {
+                operators.push(pb::Logical::Not.into());
+                expr.push_operand(operators);
+            }
+            Expr::Binary(op, left, right) => {
+                left.push_operand(operators);
+                operators.push((*op).into());
+                right.push_operand(operators);
+            }
+        }
+    }
+
+    /// Push the expression as an operand, which is braced unless it is a single operator
+    fn push_operand(&self, operators: &mut Vec<pb::ExprOpr>) {
+        if matches!(self, Expr::Not(_) | Expr::Binary(..)) {
+            operators.push(pb::ExprOpr { node_type: None, item: Some(pb::expr_opr::Item::Brace(0)) });
+            self.push_operators(operators);
+            operators.push(pb::ExprOpr { node_type: None, item: Some(pb::expr_opr::Item::Brace(1)) });
+        } else {
+            self.push_operators(operators);
+        }
+    }
+}
+
+impl From<Expr> for pb::Expression {
+    fn from(expr: Expr) -> Self {
+        let mut operators = vec![];
+        expr.push_operators(&mut operators);
+
+        pb::Expression { operators }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr_parse::str_to_expr_pb;
+
+    #[test]
+    fn build_expr() {
+        // @a.age > 10 && @a.name == "x"
+        let expr = Expr::binary(
+            BinaryOp::And,
+            Expr::binary(BinaryOp::Gt, Expr::var("@a.age"), Expr::constant(10_i64)),
+            Expr::binary(BinaryOp::Eq, Expr::var("@a.name"), Expr::constant("x".to_string())),
+        );
+        assert_eq!(
+            pb::Expression::from(expr.clone()),
+            str_to_expr_pb("(@a.age > 10) && (@a.name == \"x\")".to_string()).unwrap()
+        );
+        assert_eq!(Expr::parse("@a.age > 10 && @a.name == \"x\"").unwrap(), expr);
+
+        // !(1 + 2 * 3 == 7)
+        let expr = Expr::not(Expr::binary(
+            BinaryOp::Eq,
+            Expr::binary(
+                BinaryOp::Add,
+                Expr::constant(1_i64),
+                Expr::binary(BinaryOp::Mul, Expr::constant(2_i64), Expr::constant(3_i64)),
+            ),
+            Expr::constant(7_i64),
+        ));
+        assert_eq!(Expr::parse("!(1 + 2 * 3 == 7)").unwrap(), expr);
+        assert_eq!(
+            pb::Expression::from(expr),
+            str_to_expr_pb("!((1 + (2 * 3)) == 7)".to_string()).unwrap()
+        );
+
+        assert_eq!(
+            Expr::parse("[@a, @a.name]").unwrap(),
+            Expr::Vars(vec![
+                pb::Variable::from("@a".to_string()),
+                pb::Variable::from("@a.name".to_string())
+            ])
+        );
+        assert!(Expr::parse("1 +").is_err());
+        assert!(Expr::parse("1 2").is_err());
+    }
+}
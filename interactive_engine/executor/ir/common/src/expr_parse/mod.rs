@@ -14,15 +14,18 @@
 //! limitations under the License.
 //!
 
+pub mod ast;
 pub mod error;
 pub mod token;
 
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 
 use crate::expr_parse::error::{ExprError, ExprResult};
-use crate::expr_parse::token::{tokenize, Token};
+use crate::expr_parse::token::{tokenize_with_offsets, Token};
 use crate::generated::common as pb;
-use crate::VAR_PREFIX;
+use crate::{
+    ALL_KEY, ELEMENT_KEY_PREFIX, ID_KEY, LABEL_KEY, LENGTH_KEY, SPLITTER, VAR_PREFIX, VERTICES_KEY,
+};
 
 fn idents_to_vars(idents: Vec<String>) -> ExprResult<pb::VariableKeys> {
     let mut vars = Vec::with_capacity(idents.len());
@@ -215,14 +218,187 @@ pub fn to_suffix_expr<E: ExprToken + std::fmt::Debug>(expr: Vec<E>) -> ExprResul
 }
 
 pub fn str_to_expr_pb(expr_str: String) -> ExprResult<pb::Expression> {
+    str_to_expr_pb_with_offset(expr_str).map_err(|(err, _)| err)
+}
+
+/// Parse the string into an expression as [`str_to_expr_pb`], while an error comes with the
+/// byte offset of the token that fails in the string.
+pub fn str_to_expr_pb_with_offset(expr_str: String) -> Result<pb::Expression, (ExprError, usize)> {
     let mut operators = vec![];
-    for token in tokenize(&expr_str)? {
-        operators.push(token.try_into()?);
+    for (token, offset) in tokenize_with_offsets(&expr_str)? {
+        operators.push(pb::ExprOpr::try_from(token).map_err(|err| (err, offset))?);
     }
 
     Ok(pb::Expression { operators })
 }
 
+/// Print the expression as a string, which is the inverse of [`str_to_expr_pb`], e.g., for explaining
+/// the plans. The operators are printed in order, with the variables as `@tag.property`, and the
+/// strings quoted and escaped, such that the string is parsed back into the same expression.
+pub fn expr_pb_to_str(expr: &pb::Expression) -> String {
+    let mut result = String::new();
+    // whether the recent operator is followed by the next one without a whitespace, e.g., `(`
+    let mut is_glued = true;
+    for opr in &expr.operators {
+        let (token, is_prefix) = match &opr.item {
+            Some(pb::expr_opr::Item::Logical(logical)) => {
+                let token = match pb::Logical::from_i32(*logical) {
+                    Some(pb::Logical::Eq) => "==",
+                    Some(pb::Logical::Ne) => "!=",
+                    Some(pb::Logical::Lt) => "<",
+                    Some(pb::Logical::Le) => "<=",
+                    Some(pb::Logical::Gt) => ">",
+                    Some(pb::Logical::Ge) => ">=",
+                    Some(pb::Logical::Within) => "within",
+                    Some(pb::Logical::Without) => "without",
+                    Some(pb::Logical::Startswith) => "startsWith",
+                    Some(pb::Logical::Endswith) => "endsWith",
+                    Some(pb::Logical::And) => "&&",
+                    Some(pb::Logical::Or) => "||",
+                    Some(pb::Logical::Not) => "!",
+                    None => "?",
+                };
+                (token.to_string(), token == "!")
+            }
+            Some(pb::expr_opr::Item::Arith(arith)) => {
+                let token = match pb::Arithmetic::from_i32(*arith) {
+                    Some(pb::Arithmetic::Add) => "+",
+                    Some(pb::Arithmetic::Sub) => "-",
+                    Some(pb::Arithmetic::Mul) => "*",
+                    Some(pb::Arithmetic::Div) => "/",
+                    Some(pb::Arithmetic::Mod) => "%",
+                    Some(pb::Arithmetic::Exp) => "^^",
+                    Some(pb::Arithmetic::Bitand) => "&",
+                    Some(pb::Arithmetic::Bitor) => "|",
+                    Some(pb::Arithmetic::Bitxor) => "^",
+                    Some(pb::Arithmetic::Bitlshift) => "<<",
+                    Some(pb::Arithmetic::Bitrshift) => ">>",
+                    None => "?",
+                };
+                (token.to_string(), false)
+            }
+            Some(pb::expr_opr::Item::Const(value)) => (value_to_str(value), false),
+            Some(pb::expr_opr::Item::Var(var)) => (var_to_str(var), false),
+            Some(pb::expr_opr::Item::Vars(vars)) => (format!("[{}]", vars_to_str(&vars.keys)), false),
+            Some(pb::expr_opr::Item::VarMap(vars)) => (format!("{{{}}}", vars_to_str(&vars.keys)), false),
+            Some(pb::expr_opr::Item::Brace(0)) => ("(".to_string(), true),
+            Some(pb::expr_opr::Item::Brace(_)) => {
+                result.push(')');
+                is_glued = false;
+                continue;
+            }
+            Some(pb::expr_opr::Item::Param(param)) => (format!("${}", param.name), false),
+            Some(pb::expr_opr::Item::Case(case)) => (case_to_str(case), false),
+            None => continue,
+        };
+        if !is_glued {
+            result.push(' ');
+        }
+        result.push_str(&token);
+        is_glued = is_prefix;
+    }
+
+    result
+}
+
+fn value_to_str(value: &pb::Value) -> String {
+    fn str_to_literal(str: &str) -> String {
+        format!("\"{}\"", str.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+    fn join<T, F: Fn(&T) -> String>(items: &[T], f: F) -> String {
+        format!(
+            "[{}]",
+            items
+                .iter()
+                .map(f)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    match &value.item {
+        Some(pb::value::Item::Boolean(b)) => b.to_string(),
+        Some(pb::value::Item::I32(i)) => i.to_string(),
+        Some(pb::value::Item::I64(i)) => i.to_string(),
+        // the debug format keeps the decimal point of an integral float, e.g., `1.0`
+        Some(pb::value::Item::F64(f)) => format!("{:?}", f),
+        Some(pb::value::Item::Str(str)) => str_to_literal(str),
+        Some(pb::value::Item::I32Array(array)) => join(&array.item, |i| i.to_string()),
+        Some(pb::value::Item::I64Array(array)) => join(&array.item, |i| i.to_string()),
+        Some(pb::value::Item::F64Array(array)) => join(&array.item, |f| format!("{:?}", f)),
+        Some(pb::value::Item::StrArray(array)) => join(&array.item, |str| str_to_literal(str)),
+        Some(pb::value::Item::Blob(blob)) => format!("{:?}", blob),
+        Some(pb::value::Item::PairArray(array)) => format!("{:?}", array),
+        Some(pb::value::Item::None(_)) | None => "null".to_string(),
+    }
+}
+
+fn var_to_str(var: &pb::Variable) -> String {
+    let mut result = VAR_PREFIX.to_string();
+    match var
+        .tag
+        .as_ref()
+        .and_then(|tag| tag.item.as_ref())
+    {
+        Some(pb::name_or_id::Item::Name(name)) => result.push_str(name),
+        Some(pb::name_or_id::Item::Id(id)) => result.push_str(&id.to_string()),
+        None => {}
+    }
+    if let Some(property) = var
+        .property
+        .as_ref()
+        .and_then(|property| property.item.as_ref())
+    {
+        let property = match property {
+            pb::property::Item::Id(_) => ID_KEY.to_string(),
+            pb::property::Item::Label(_) => LABEL_KEY.to_string(),
+            pb::property::Item::Len(_) => LENGTH_KEY.to_string(),
+            pb::property::Item::All(_) => ALL_KEY.to_string(),
+            pb::property::Item::Vertices(_) => VERTICES_KEY.to_string(),
+            pb::property::Item::Element(element) => format!("{}{}", ELEMENT_KEY_PREFIX, element.index),
+            pb::property::Item::Key(key) => match &key.item {
+                Some(pb::name_or_id::Item::Name(name)) => name.clone(),
+                Some(pb::name_or_id::Item::Id(id)) => id.to_string(),
+                None => String::new(),
+            },
+        };
+        result.push_str(SPLITTER);
+        result.push_str(&property);
+    }
+
+    result
+}
+
+fn vars_to_str(vars: &[pb::Variable]) -> String {
+    vars.iter()
+        .map(var_to_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn case_to_str(case: &pb::Case) -> String {
+    let mut result = "CASE".to_string();
+    for when_then in &case.when_then_expressions {
+        let when = when_then
+            .when_expression
+            .as_ref()
+            .map(expr_pb_to_str)
+            .unwrap_or_default();
+        let then = when_then
+            .then_result_expression
+            .as_ref()
+            .map(expr_pb_to_str)
+            .unwrap_or_default();
+        result.push_str(&format!(" WHEN {} THEN {}", when, then));
+    }
+    if let Some(else_expr) = &case.else_result_expression {
+        result.push_str(&format!(" ELSE {}", expr_pb_to_str(else_expr)));
+    }
+    result.push_str(" END");
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -293,4 +469,20 @@ mod tests {
         ];
         assert_eq!(to_suffix_expr(case7).unwrap(), expected_case7);
     }
+
+    #[test]
+    fn test_expr_pb_to_str() {
+        let cases = vec![
+            "@a.name == \"John\"",
+            "!(@a.age > 10 && @a.~label within [1, 2]) || @.name startsWith \"J\\\"o\"",
+            "(1 + 2) * 3 ^^ 2 - @a.~len % 4",
+            "[@a, @b.name]",
+            "{@a.~id, @0}",
+            "@a.weight >= 1.0",
+        ];
+        for case in cases {
+            let expr = str_to_expr_pb(case.to_string()).unwrap();
+            assert_eq!(expr_pb_to_str(&expr), case);
+        }
+    }
 }
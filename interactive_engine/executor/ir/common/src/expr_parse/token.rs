@@ -226,26 +226,44 @@ fn parse_string_literal<Iter: Iterator<Item = char>>(
 
 /// Converts a string to a vector of partial tokens.
 fn str_to_partial_tokens(string: &str) -> ExprResult<Vec<PartialToken>> {
-    let mut result = Vec::new();
-    let mut iter = string.chars().peekable();
-    while let Some(c) = iter.next() {
+    str_to_located_partial_tokens(string)
+        .map(|tokens| {
+            tokens
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect()
+        })
+        .map_err(|(err, _)| err)
+}
+
+/// Converts a string to a vector of partial tokens, each of which comes with the byte offset
+/// where it starts in the string. An error comes with the byte offset of the partial token
+/// that fails, e.g., the left bracket of an unclosed array.
+fn str_to_located_partial_tokens(string: &str) -> Result<Vec<(PartialToken, usize)>, (ExprError, usize)> {
+    let mut result: Vec<(PartialToken, usize)> = Vec::new();
+    let mut iter = string.char_indices().peekable();
+    while let Some((offset, c)) = iter.next() {
         if c == '"' {
-            result.push(parse_string_literal(&mut iter, false)?);
-        } else if c == '[' {
-            result.push(PartialToken::LBracket);
-            result.push(parse_string_literal(&mut iter, true)?);
-            // must have right bracket to escape from `parse_string_literal()`
-            result.push(PartialToken::RBracket);
-        } else if c == '{' {
-            result.push(PartialToken::LCBracket);
-            result.push(parse_string_literal(&mut iter, true)?);
+            let literal = parse_string_literal(&mut iter.by_ref().map(|(_, c)| c), false)
+                .map_err(|err| (err, offset))?;
+            result.push((literal, offset));
+        } else if c == '[' || c == '{' {
+            let literal = parse_string_literal(&mut iter.by_ref().map(|(_, c)| c), true)
+                .map_err(|err| (err, offset))?;
+            let (left, right) = if c == '[' {
+                (PartialToken::LBracket, PartialToken::RBracket)
+            } else {
+                (PartialToken::LCBracket, PartialToken::RCBracket)
+            };
+            result.push((left, offset));
+            result.push((literal, offset));
             // must have right bracket to escape from `parse_string_literal()`
-            result.push(PartialToken::RCBracket);
+            result.push((right, offset));
         } else {
             let partial_token = char_to_partial_token(c);
 
             let if_let_successful =
-                if let (Some(PartialToken::Literal(last)), PartialToken::Literal(literal)) =
+                if let (Some((PartialToken::Literal(last), _)), PartialToken::Literal(literal)) =
                     (result.last_mut(), &partial_token)
                 {
                     last.push_str(literal);
@@ -255,7 +273,7 @@ fn str_to_partial_tokens(string: &str) -> ExprResult<Vec<PartialToken>> {
                 };
 
             if !if_let_successful {
-                result.push(partial_token);
+                result.push((partial_token, offset));
             }
         }
     }
@@ -331,213 +349,267 @@ fn token_array_to_token(token_array: Vec<Token>) -> ExprResult<Token> {
 }
 
 /// Resolves all partial tokens by converting them to complex tokens.
-fn partial_tokens_to_tokens(mut tokens: &[PartialToken]) -> ExprResult<Vec<Token>> {
+fn partial_tokens_to_tokens(tokens: &[PartialToken]) -> ExprResult<Vec<Token>> {
+    partial_tokens_to_indexed_tokens(tokens)
+        .map(|tokens| {
+            tokens
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect()
+        })
+        .map_err(|(err, _)| err)
+}
+
+/// Resolves all partial tokens as [`partial_tokens_to_tokens`], while each complex token comes
+/// with the index of the partial token where it starts, and an error with the index of the
+/// partial token that fails.
+fn partial_tokens_to_indexed_tokens(
+    mut tokens: &[PartialToken],
+) -> Result<Vec<(Token, usize)>, (ExprError, usize)> {
     let mut result = Vec::new();
     let mut recent_token: Option<Token> = None;
+    let mut index = 0;
     while !tokens.is_empty() {
-        let first = tokens[0].clone();
-        let second = tokens.get(1).cloned();
-        let third = tokens.get(2).cloned();
-        let mut cutoff = 2;
+        let (curr_token, cutoff) = next_token(tokens, &recent_token).map_err(|err| (err, index))?;
+        if let Some(token) = curr_token {
+            recent_token = Some(token.clone());
+            result.push((token, index));
+        }
 
-        let curr_token = match first {
-            PartialToken::Token(token) => {
-                cutoff = 1;
-                Some(token)
-            }
-            PartialToken::Literal(literal) => {
-                cutoff = 1;
-                if let Ok(number) = literal.parse::<i64>() {
-                    Some(Token::Int(number))
-                } else if let Ok(number) = literal.parse::<f64>() {
-                    Some(Token::Float(number))
-                } else if let Ok(boolean) = literal.parse::<bool>() {
-                    Some(Token::Boolean(boolean))
-                } else if literal.to_lowercase().as_str() == "within" {
-                    Some(Token::Within)
-                } else if literal.to_lowercase().as_str() == "without" {
-                    Some(Token::Without)
-                } else if literal.to_lowercase().as_str() == "startswith" {
-                    Some(Token::StartsWith)
-                } else if literal.to_lowercase().as_str() == "endswith" {
-                    Some(Token::EndsWith)
-                } else {
-                    // To parse the float of the form `<coefficient>e{+,-}<exponent>`,
-                    // for example [Literal("10e"), Minus, Literal("3")] => "1e-3".parse().
-                    match (second, third) {
-                        (Some(second), Some(third))
-                            if second == PartialToken::Minus
-                                || second == PartialToken::Token(Token::Plus) =>
+        tokens = &tokens[cutoff..];
+        index += cutoff;
+    }
+    Ok(result)
+}
+
+/// Resolves the leading partial tokens into a complex token, if any, e.g., none for the
+/// whitespaces, together with the number of the partial tokens that it consumes.
+fn next_token(tokens: &[PartialToken], recent_token: &Option<Token>) -> ExprResult<(Option<Token>, usize)> {
+    let first = tokens[0].clone();
+    let second = tokens.get(1).cloned();
+    let third = tokens.get(2).cloned();
+    let mut cutoff = 2;
+
+    let curr_token = match first {
+        PartialToken::Token(token) => {
+            cutoff = 1;
+            Some(token)
+        }
+        PartialToken::Literal(literal) => {
+            cutoff = 1;
+            if let Ok(number) = literal.parse::<i64>() {
+                Some(Token::Int(number))
+            } else if let Ok(number) = literal.parse::<f64>() {
+                Some(Token::Float(number))
+            } else if let Ok(boolean) = literal.parse::<bool>() {
+                Some(Token::Boolean(boolean))
+            } else if literal.to_lowercase().as_str() == "within" {
+                Some(Token::Within)
+            } else if literal.to_lowercase().as_str() == "without" {
+                Some(Token::Without)
+            } else if literal.to_lowercase().as_str() == "startswith" {
+                Some(Token::StartsWith)
+            } else if literal.to_lowercase().as_str() == "endswith" {
+                Some(Token::EndsWith)
+            } else {
+                // To parse the float of the form `<coefficient>e{+,-}<exponent>`,
+                // for example [Literal("10e"), Minus, Literal("3")] => "1e-3".parse().
+                match (second, third) {
+                    (Some(second), Some(third))
+                        if second == PartialToken::Minus || second == PartialToken::Token(Token::Plus) =>
+                    {
+                        let second_sign = match second {
+                            PartialToken::Minus => "-",
+                            _ => "+",
+                        };
+                        let third_num = match third {
+                            PartialToken::Literal(s) => s,
+                            _ => "".to_string(),
+                        };
+                        if let Ok(number) =
+                            format!("{}{}{}", literal, second_sign, third_num).parse::<f64>()
                         {
-                            let second_sign = match second {
-                                PartialToken::Minus => "-",
-                                _ => "+",
-                            };
-                            let third_num = match third {
-                                PartialToken::Literal(s) => s,
-                                _ => "".to_string(),
-                            };
-                            if let Ok(number) =
-                                format!("{}{}{}", literal, second_sign, third_num).parse::<f64>()
-                            {
-                                cutoff = 3;
-                                Some(Token::Float(number))
-                            } else {
-                                Some(Token::Identifier(literal.to_string()))
-                            }
+                            cutoff = 3;
+                            Some(Token::Float(number))
+                        } else {
+                            Some(Token::Identifier(literal.to_string()))
+                        }
+                    }
+                    _ => Some(Token::Identifier(literal.to_string())),
+                }
+            }
+        }
+        PartialToken::Whitespace => {
+            cutoff = 1;
+            None
+        }
+        PartialToken::Minus => {
+            // Should we consider minus as a negative sign
+            let is_negative_sign =
+                { recent_token.is_none() || !recent_token.as_ref().unwrap().is_operand() };
+            if is_negative_sign {
+                match &second {
+                    // Be aware that minus can represent both subtraction or negative sign
+                    // if it is a negative sign, it must be directly trailed by a number.
+                    // However, we can not actually tell whether the case "x -y", is actually
+                    // subtracting x by y, or -y must be treated as a number.
+                    Some(PartialToken::Literal(literal)) => {
+                        // Must check whether previous is what
+                        if let Ok(number) = literal.parse::<i64>() {
+                            Some(Token::Int(-number))
+                        } else if let Ok(number) = literal.parse::<f64>() {
+                            Some(Token::Float(-number))
+                        } else {
+                            return Err(ExprError::unmatched_partial_token(first, second));
                         }
-                        _ => Some(Token::Identifier(literal.to_string())),
+                    }
+                    _ => {
+                        cutoff = 1;
+                        Some(Token::Minus)
                     }
                 }
+            } else {
+                cutoff = 1;
+                Some(Token::Minus)
+            }
+        }
+        PartialToken::Eq => match second {
+            Some(PartialToken::Eq) => Some(Token::Eq),
+            _ => {
+                return Err(ExprError::unmatched_partial_token(first, second));
+            }
+        },
+        PartialToken::ExclamationMark => match second {
+            Some(PartialToken::Eq) => Some(Token::Ne),
+            _ => {
+                cutoff = 1;
+                Some(Token::Not)
+            }
+        },
+        PartialToken::Gt => match second {
+            Some(PartialToken::Eq) => Some(Token::Ge),
+            Some(PartialToken::Gt) => Some(Token::BitRShift), // >>
+            _ => {
+                cutoff = 1;
+                Some(Token::Gt)
+            }
+        },
+        PartialToken::Lt => match second {
+            Some(PartialToken::Eq) => Some(Token::Le),
+            Some(PartialToken::Lt) => Some(Token::BitLShift), // <<
+            _ => {
+                cutoff = 1;
+                Some(Token::Lt)
+            }
+        },
+        PartialToken::Ampersand => match second {
+            Some(PartialToken::Ampersand) => Some(Token::And),
+            // _ => return Err(ExprError::unmatched_partial_token(first, second)),
+            _ => {
+                cutoff = 1;
+                Some(Token::BitAnd)
             }
-            PartialToken::Whitespace => {
+        },
+        PartialToken::VerticalBar => match second {
+            Some(PartialToken::VerticalBar) => Some(Token::Or),
+            // _ => return Err(ExprError::unmatched_partial_token(first, second)),
+            _ => {
                 cutoff = 1;
-                None
+                Some(Token::BitOr)
             }
-            PartialToken::Minus => {
-                // Should we consider minus as a negative sign
-                let is_negative_sign =
-                    { recent_token.is_none() || !recent_token.as_ref().unwrap().is_operand() };
-                if is_negative_sign {
-                    match &second {
-                        // Be aware that minus can represent both subtraction or negative sign
-                        // if it is a negative sign, it must be directly trailed by a number.
-                        // However, we can not actually tell whether the case "x -y", is actually
-                        // subtracting x by y, or -y must be treated as a number.
-                        Some(PartialToken::Literal(literal)) => {
-                            // Must check whether previous is what
-                            if let Ok(number) = literal.parse::<i64>() {
-                                Some(Token::Int(-number))
-                            } else if let Ok(number) = literal.parse::<f64>() {
-                                Some(Token::Float(-number))
+        },
+        PartialToken::LBracket | PartialToken::LCBracket => {
+            let is_bracket = first == PartialToken::LBracket;
+            cutoff = 3;
+            if (is_bracket && third != Some(PartialToken::RBracket))
+                || (!is_bracket && third != Some(PartialToken::RCBracket))
+            {
+                return Err(ExprError::UnmatchedLRBrackets);
+            } else {
+                let mut token_array: Vec<Token> = Vec::new();
+                match second {
+                    Some(PartialToken::Token(Token::String(ref s))) => {
+                        let elements = s.split(",");
+                        for e in elements {
+                            let t = partial_tokens_to_tokens(&str_to_partial_tokens(e)?)?;
+                            if t.is_empty() {
+                                // do nothing
+                            } else if t.len() == 1 {
+                                token_array.push(t[0].clone())
                             } else {
-                                return Err(ExprError::unmatched_partial_token(first, second));
+                                return Err(format!("invalid token: {:?}", second)
+                                    .as_str()
+                                    .into());
                             }
                         }
-                        _ => {
-                            cutoff = 1;
-                            Some(Token::Minus)
-                        }
                     }
-                } else {
-                    cutoff = 1;
-                    Some(Token::Minus)
-                }
-            }
-            PartialToken::Eq => match second {
-                Some(PartialToken::Eq) => Some(Token::Eq),
-                _ => {
-                    return Err(ExprError::unmatched_partial_token(first, second));
-                }
-            },
-            PartialToken::ExclamationMark => match second {
-                Some(PartialToken::Eq) => Some(Token::Ne),
-                _ => {
-                    cutoff = 1;
-                    Some(Token::Not)
-                }
-            },
-            PartialToken::Gt => match second {
-                Some(PartialToken::Eq) => Some(Token::Ge),
-                Some(PartialToken::Gt) => Some(Token::BitRShift), // >>
-                _ => {
-                    cutoff = 1;
-                    Some(Token::Gt)
-                }
-            },
-            PartialToken::Lt => match second {
-                Some(PartialToken::Eq) => Some(Token::Le),
-                Some(PartialToken::Lt) => Some(Token::BitLShift), // <<
-                _ => {
-                    cutoff = 1;
-                    Some(Token::Lt)
-                }
-            },
-            PartialToken::Ampersand => match second {
-                Some(PartialToken::Ampersand) => Some(Token::And),
-                // _ => return Err(ExprError::unmatched_partial_token(first, second)),
-                _ => {
-                    cutoff = 1;
-                    Some(Token::BitAnd)
-                }
-            },
-            PartialToken::VerticalBar => match second {
-                Some(PartialToken::VerticalBar) => Some(Token::Or),
-                // _ => return Err(ExprError::unmatched_partial_token(first, second)),
-                _ => {
-                    cutoff = 1;
-                    Some(Token::BitOr)
+                    _ => {
+                        return Err(format!("invalid token: {:?}", second)
+                            .as_str()
+                            .into())
+                    }
                 }
-            },
-            PartialToken::LBracket | PartialToken::LCBracket => {
-                let is_bracket = first == PartialToken::LBracket;
-                cutoff = 3;
-                if (is_bracket && third != Some(PartialToken::RBracket))
-                    || (!is_bracket && third != Some(PartialToken::RCBracket))
-                {
-                    return Err(ExprError::UnmatchedLRBrackets);
+                let result = token_array_to_token(token_array)?;
+                if is_bracket {
+                    Some(result)
                 } else {
-                    let mut token_array: Vec<Token> = Vec::new();
-                    match second {
-                        Some(PartialToken::Token(Token::String(ref s))) => {
-                            let elements = s.split(",");
-                            for e in elements {
-                                let t = partial_tokens_to_tokens(&str_to_partial_tokens(e)?)?;
-                                if t.is_empty() {
-                                    // do nothing
-                                } else if t.len() == 1 {
-                                    token_array.push(t[0].clone())
-                                } else {
-                                    return Err(format!("invalid token: {:?}", second)
-                                        .as_str()
-                                        .into());
-                                }
-                            }
-                        }
-                        _ => {
-                            return Err(format!("invalid token: {:?}", second)
-                                .as_str()
-                                .into())
-                        }
-                    }
-                    let result = token_array_to_token(token_array)?;
-                    if is_bracket {
-                        Some(result)
+                    if let Token::IdentArray(vec) = result {
+                        Some(Token::IdentMap(vec))
                     } else {
-                        if let Token::IdentArray(vec) = result {
-                            Some(Token::IdentMap(vec))
-                        } else {
-                            unreachable!()
-                        }
+                        unreachable!()
                     }
                 }
             }
-            PartialToken::Hat => match second {
-                Some(PartialToken::Hat) => Some(Token::Power),
-                _ => {
-                    cutoff = 1;
-                    Some(Token::BitXor)
-                }
-            },
+        }
+        PartialToken::Hat => match second {
+            Some(PartialToken::Hat) => Some(Token::Power),
             _ => {
-                return Err(format!("invalid token: {:?}", first)
-                    .as_str()
-                    .into());
+                cutoff = 1;
+                Some(Token::BitXor)
             }
-        };
-
-        if let Some(token) = curr_token.clone() {
-            result.push(token);
-            recent_token = curr_token.clone();
+        },
+        _ => {
+            return Err(format!("invalid token: {:?}", first)
+                .as_str()
+                .into());
         }
+    };
 
-        tokens = &tokens[cutoff..];
-    }
-    Ok(result)
+    Ok((curr_token, cutoff))
 }
 
 pub fn tokenize(string: &str) -> ExprResult<Vec<Token>> {
-    partial_tokens_to_tokens(&str_to_partial_tokens(string)?)
+    tokenize_with_offsets(string)
+        .map(|tokens| {
+            tokens
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect()
+        })
+        .map_err(|(err, _)| err)
+}
+
+/// Tokenize the string as [`tokenize`], while each token comes with the byte offset where it
+/// starts in the string, and an error with the byte offset of the token that fails, such that
+/// the callers can point out the offending part of the expression.
+pub fn tokenize_with_offsets(string: &str) -> Result<Vec<(Token, usize)>, (ExprError, usize)> {
+    let (partial_tokens, offsets): (Vec<PartialToken>, Vec<usize>) = str_to_located_partial_tokens(string)?
+        .into_iter()
+        .unzip();
+    let offset_of = |index: usize| {
+        offsets
+            .get(index)
+            .cloned()
+            .unwrap_or(string.len())
+    };
+    partial_tokens_to_indexed_tokens(&partial_tokens)
+        .map(|tokens| {
+            tokens
+                .into_iter()
+                .map(|(token, index)| (token, offset_of(index)))
+                .collect()
+        })
+        .map_err(|(err, index)| (err, offset_of(index)))
 }
 
 #[cfg(test)]
@@ -700,4 +772,41 @@ mod tests {
             ExprError::unsupported("array of various type unsupported".to_string())
         );
     }
+
+    #[test]
+    fn test_tokenize_with_offsets() {
+        let case1 = tokenize_with_offsets("@a.age >= 10 && \"ab\" == [1, 2]");
+        let expected_case1 = vec![
+            (Token::Identifier("@a.age".to_string()), 0),
+            (Token::Ge, 7),
+            (Token::Int(10), 10),
+            (Token::And, 13),
+            (Token::String("ab".to_string()), 16),
+            (Token::Eq, 21),
+            (Token::IntArray(vec![1, 2]), 24),
+        ];
+        assert_eq!(case1.unwrap(), expected_case1);
+
+        let case2 = tokenize_with_offsets("@a.age = 10");
+        assert_eq!(
+            case2.err().unwrap(),
+            (ExprError::unmatched_partial_token(PartialToken::Eq, Some(PartialToken::Whitespace)), 7)
+        );
+
+        let case3 = tokenize_with_offsets("1 + [1, 2");
+        assert_eq!(case3.err().unwrap(), (ExprError::UnmatchedLRBrackets, 4));
+
+        // the offsets are of bytes rather than chars
+        let case4 = tokenize_with_offsets("\"名字\" == -a");
+        assert_eq!(
+            case4.err().unwrap(),
+            (
+                ExprError::unmatched_partial_token(
+                    PartialToken::Minus,
+                    Some(PartialToken::Literal("a".to_string()))
+                ),
+                12
+            )
+        );
+    }
 }
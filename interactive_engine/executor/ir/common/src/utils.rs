@@ -205,6 +205,9 @@ pub const ID_KEY: &'static str = "~id";
 pub const LABEL_KEY: &'static str = "~label";
 pub const LENGTH_KEY: &'static str = "~len";
 pub const ALL_KEY: &'static str = "~all";
+pub const VERTICES_KEY: &'static str = "~vertices";
+/// The prefix of the key of a path element, e.g., "~elem_0" for the first element of the path
+pub const ELEMENT_KEY_PREFIX: &'static str = "~elem_";
 
 impl From<String> for common_pb::Property {
     fn from(str: String) -> Self {
@@ -216,6 +219,17 @@ impl From<String> for common_pb::Property {
             common_pb::Property { item: Some(common_pb::property::Item::Len(common_pb::LengthKey {})) }
         } else if str == ALL_KEY {
             common_pb::Property { item: Some(common_pb::property::Item::All(common_pb::AllKey {})) }
+        } else if str == VERTICES_KEY {
+            common_pb::Property {
+                item: Some(common_pb::property::Item::Vertices(common_pb::PathVerticesKey {})),
+            }
+        } else if let Some(index) = str
+            .strip_prefix(ELEMENT_KEY_PREFIX)
+            .and_then(|index| index.parse::<i32>().ok())
+        {
+            common_pb::Property {
+                item: Some(common_pb::property::Item::Element(common_pb::PathElementKey { index })),
+            }
         } else {
             common_pb::Property { item: Some(common_pb::property::Item::Key(str.into())) }
         }
@@ -263,6 +277,7 @@ impl From<i64> for pb::index_predicate::AndPredicate {
                 }),
                 value: Some(id.into()),
                 cmp: None,
+                param: None,
             }],
         }
     }
@@ -286,6 +301,7 @@ impl From<String> for pb::index_predicate::AndPredicate {
                 }),
                 value: Some(label.into()),
                 cmp: None,
+                param: None,
             }],
         }
     }
@@ -336,6 +352,17 @@ impl TryFrom<common_pb::Value> for Object {
     }
 }
 
+/// The error of a triplet without a value, of which the parameter is not bound, if any
+fn missing_value_error(triplet: &pb::index_predicate::Triplet) -> ParsePbError {
+    match &triplet.param {
+        Some(param) => ParsePbError::from(format!(
+            "the parameter {:?} in kv_pair in indexed_scan is not bound",
+            param.name
+        )),
+        None => ParsePbError::from("value is empty in kv_pair in indexed_scan"),
+    }
+}
+
 impl TryFrom<pb::IndexPredicate> for Vec<i64> {
     type Error = ParsePbError;
 
@@ -350,7 +377,7 @@ impl TryFrom<pb::IndexPredicate> for Vec<i64> {
             let (key, value) = (predicate.key.as_ref(), predicate.value.as_ref());
             let key = key.ok_or("key is empty in kv_pair in indexed_scan")?;
             if let Some(common_pb::property::Item::Id(_id_key)) = key.item.as_ref() {
-                let value = value.ok_or("value is empty in kv_pair in indexed_scan")?;
+                let value = value.ok_or_else(|| missing_value_error(predicate))?;
 
                 match &value.item {
                     Some(common_pb::value::Item::I64(v)) => {
@@ -393,7 +420,7 @@ impl TryFrom<pb::IndexPredicate> for Vec<(NameOrId, Object)> {
             let value = predicate
                 .value
                 .clone()
-                .ok_or("value is empty in kv_pair in indexed_scan")?;
+                .ok_or_else(|| missing_value_error(predicate))?;
             let key = match key_pb.item {
                 Some(common_pb::property::Item::Key(prop_key)) => prop_key.try_into()?,
                 _ => Err(ParsePbError::Unsupported(
@@ -443,6 +470,12 @@ impl From<pb::GroupBy> for pb::logical_plan::Operator {
     }
 }
 
+impl From<pb::Count> for pb::logical_plan::Operator {
+    fn from(opr: pb::Count) -> Self {
+        pb::logical_plan::Operator { opr: Some(pb::logical_plan::operator::Opr::Count(opr)) }
+    }
+}
+
 impl From<pb::OrderBy> for pb::logical_plan::Operator {
     fn from(opr: pb::OrderBy) -> Self {
         pb::logical_plan::Operator { opr: Some(pb::logical_plan::operator::Opr::OrderBy(opr)) }
@@ -617,7 +650,8 @@ impl pb::QueryParams {
             && self.limit.is_none()
             && self.sample_ratio == 1.0
             && self.columns.is_empty()
-            && !self.is_all_columns)
+            && !self.is_all_columns
+            && self.degree_predicates.is_empty())
     }
 
     pub fn is_empty(&self) -> bool {
@@ -629,6 +663,7 @@ impl pb::QueryParams {
             predicate: None,
             sample_ratio: 1.0,
             extra: HashMap::new(),
+            degree_predicates: vec![],
         })
     }
 }
@@ -734,9 +769,12 @@ impl From<pb::GroupBy> for physical_pb::GroupBy {
                 alias: agg_func
                     .alias
                     .map(|tag| tag.try_into().unwrap()),
+                equality: agg_func.equality,
+                overflow: agg_func.overflow,
+                approximation: agg_func.approximation,
             })
             .collect();
-        physical_pb::GroupBy { mappings, functions }
+        physical_pb::GroupBy { mappings, functions, null_key_opt: group.null_key_opt }
     }
 }
 
@@ -756,6 +794,10 @@ impl From<pb::GetV> for physical_pb::GetV {
             opt: get_v.opt,
             params: get_v.params,
             alias: get_v.alias.map(|tag| tag.try_into().unwrap()),
+            is_optional: get_v.is_optional,
+            end_alias: get_v
+                .end_alias
+                .map(|tag| tag.try_into().unwrap()),
         }
     }
 }
@@ -768,6 +810,13 @@ impl From<pb::EdgeExpand> for physical_pb::EdgeExpand {
             params: edge.params,
             alias: edge.alias.map(|tag| tag.try_into().unwrap()),
             expand_opt: edge.expand_opt,
+            dedup_both: edge.dedup_both,
+            exclude_self_loops: edge.exclude_self_loops,
+            collapse_parallel_edges: edge.collapse_parallel_edges,
+            vertex_tables: edge.vertex_tables,
+            super_node_policy: None,
+            is_optional: edge.is_optional,
+            is_distinct: false,
         }
     }
 }
@@ -790,6 +839,7 @@ impl From<pb::PathExpand> for physical_pb::PathExpand {
             path_opt: path.path_opt,
             result_opt: path.result_opt,
             condition: path.condition,
+            timeout_ms: path.timeout_ms,
         }
     }
 }
@@ -801,6 +851,9 @@ impl From<pb::Scan> for physical_pb::Scan {
             alias: scan.alias.map(|tag| tag.try_into().unwrap()),
             params: scan.params,
             idx_predicate: scan.idx_predicate,
+            connector: scan.connector,
+            named_result: scan.named_result,
+            partitions: vec![],
         }
     }
 }
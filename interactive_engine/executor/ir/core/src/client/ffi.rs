@@ -0,0 +1,489 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The C-like apis for the Gaia client to submit the physical plans to the engine, and to
+//! fetch the results of the submitted jobs page by page, as:
+//!
+//! # Example
+//!
+//! # const void* ptr_client = NULL;
+//! # init_job_client(&ptr_client);
+//! # connect_job_server(ptr_client, 0, "http://localhost:1234");
+//! # FfiData plan = build_physical_plan(ptr_plan, 1, 1);
+//! # const void* ptr_job = NULL;
+//! # submit_job(ptr_client, default_job_conf(), { plan.ptr, plan.len }, &ptr_job);
+//! # bool has_more = true;
+//! # while (has_more) {
+//! #     FfiData results = fetch_results(ptr_job, 100, &has_more);
+//! #     // decode the `results::CollectiveResults` from results.ptr
+//! #     destroy_ffi_data(results);
+//! # }
+//! # // only if the plan allows partial results via `set_plan_allow_partial()`
+//! # bool partial = is_job_partial(ptr_job);
+//! # destroy_job(ptr_job);
+//! # destroy_job_client(ptr_client);
+//!
+//! Alternatively, the results can be pushed to a callback as they arrive,
+//! via [`submit_job_with_callback`].
+//!
+//! The job clients and the jobs are given to the caller as handles (see `plan::ffi::handle`), such
+//! that a handle that is destroyed, or is of another type, is reported as `InvalidHandleError`.
+
+use std::convert::TryFrom;
+use std::ffi::c_void;
+use std::os::raw::c_char;
+use std::time::Duration;
+
+use ir_common::generated::results as result_pb;
+use pegasus::{JobConf, ServerConf};
+use prost::Message;
+
+use crate::client::{Job, JobAuth, JobClient, ResultPage, RetryPolicy, SubmitOptions, TlsConfig};
+use crate::error::IrError;
+use crate::plan::ffi::{
+    catch_panic, check_not_null, cstr_to_string, destroy_handle, handle, set_last_error, set_output,
+    FfiData, FfiPbPointer, FfiResult, FromPanic, ResultCode,
+};
+
+/// The configuration of a job, where the job runs on all the connected servers
+/// if `num_servers` is 0, or on the `num_servers` servers given by `servers` otherwise.
+/// At most `buffer_size` results are buffered in the client ahead of being fetched,
+/// where 0 means the results are read from the server only when being fetched.
+/// The workers of the job are stepped more times once being scheduled with a larger `priority`,
+/// such that the interactive queries can be favored over the batch ones.
+/// The job is submitted with the credential of either `auth_token`, or `auth_user` and
+/// `auth_password`, or none if all of them are null.
+#[repr(C)]
+pub struct FfiJobConf {
+    job_id: u64,
+    job_name: *const c_char,
+    workers: u32,
+    time_limit: u64,
+    batch_size: u32,
+    batch_capacity: u32,
+    memory_limit: u32,
+    trace_enable: bool,
+    servers: *const u64,
+    num_servers: usize,
+    buffer_size: u32,
+    auth_token: *const c_char,
+    auth_user: *const c_char,
+    auth_password: *const c_char,
+    priority: i32,
+}
+
+impl FromPanic for FfiJobConf {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        FfiJobConf {
+            job_id: 0,
+            job_name: std::ptr::null(),
+            workers: 0,
+            time_limit: 0,
+            batch_size: 0,
+            batch_capacity: 0,
+            memory_limit: 0,
+            trace_enable: false,
+            servers: std::ptr::null(),
+            num_servers: 0,
+            buffer_size: 0,
+            auth_token: std::ptr::null(),
+            auth_user: std::ptr::null(),
+            auth_password: std::ptr::null(),
+            priority: 0,
+        }
+    }
+}
+
+impl TryFrom<FfiJobConf> for (JobConf, SubmitOptions) {
+    type Error = FfiResult;
+
+    fn try_from(ffi: FfiJobConf) -> Result<Self, Self::Error> {
+        let job_name = optional_cstr(ffi.job_name)?.unwrap_or_default();
+        let mut conf = JobConf::with_id(ffi.job_id, job_name, ffi.workers);
+        conf.time_limit = ffi.time_limit;
+        conf.batch_size = ffi.batch_size;
+        conf.batch_capacity = ffi.batch_capacity;
+        conf.memory_limit = ffi.memory_limit;
+        conf.trace_enable = ffi.trace_enable;
+        conf.priority = ffi.priority;
+        if ffi.num_servers == 0 || ffi.servers.is_null() {
+            conf.reset_servers(ServerConf::All);
+        } else {
+            let servers = unsafe { std::slice::from_raw_parts(ffi.servers, ffi.num_servers) };
+            conf.reset_servers(ServerConf::Partial(servers.to_vec()));
+        }
+        let auth = match (
+            optional_cstr(ffi.auth_token)?,
+            optional_cstr(ffi.auth_user)?,
+            optional_cstr(ffi.auth_password)?,
+        ) {
+            (None, None, None) => None,
+            (Some(token), None, None) => Some(JobAuth::Token(token)),
+            (None, Some(user), password) => {
+                Some(JobAuth::Basic { user, password: password.unwrap_or_default() })
+            }
+            _ => Err(FfiResult::new(
+                ResultCode::InvalidRangeError,
+                "either the auth token, or the auth user and password can be given".to_string(),
+            ))?,
+        };
+        let options = SubmitOptions { buffer_size: ffi.buffer_size as usize, auth };
+
+        Ok((conf, options))
+    }
+}
+
+/// The default configuration of a job, which is expected to be modified as needed.
+#[no_mangle]
+pub extern "C" fn default_job_conf() -> FfiJobConf {
+    catch_panic(|| {
+        let conf = JobConf::default();
+        FfiJobConf {
+            job_id: conf.job_id,
+            job_name: std::ptr::null(),
+            workers: conf.workers,
+            time_limit: conf.time_limit,
+            batch_size: conf.batch_size,
+            batch_capacity: conf.batch_capacity,
+            memory_limit: conf.memory_limit,
+            trace_enable: conf.trace_enable,
+            servers: std::ptr::null(),
+            num_servers: 0,
+            buffer_size: 0,
+            auth_token: std::ptr::null(),
+            auth_user: std::ptr::null(),
+            auth_password: std::ptr::null(),
+            priority: conf.priority,
+        }
+    })
+}
+
+/// Initialize a job client, of which the pointer is owned by Rust, and must be released
+/// via [`destroy_job_client`]. The client can be shared by multiple threads, of which the
+/// calls are serialized.
+#[no_mangle]
+pub extern "C" fn init_job_client(ptr_client: *mut *const c_void) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(ptr_client, "output job client") {
+            return e;
+        }
+        let result = JobClient::new()
+            .map(|client| handle::register_concurrent::<_, c_void>(client))
+            .map_err(|e| e.into());
+        set_output(ptr_client, result)
+    })
+}
+
+/// To destroy a job client.
+#[no_mangle]
+pub extern "C" fn destroy_job_client(ptr_client: *const c_void) {
+    catch_panic(|| destroy_handle::<JobClient>(ptr_client))
+}
+
+/// Refer to the job client of the handle without taking its ownership.
+fn borrow_client(ptr_client: *const c_void) -> Result<handle::HandleRefMut<JobClient>, FfiResult> {
+    handle::borrow_mut::<JobClient>(ptr_client)
+}
+
+/// Refer to the job of the handle without taking its ownership.
+fn borrow_job(ptr_job: *const c_void) -> Result<handle::HandleRefMut<Job>, FfiResult> {
+    handle::borrow_mut::<Job>(ptr_job)
+}
+
+/// Set the policy of retrying the submissions (and connections) on transient failures, which are
+/// attempted at most `max_attempts` times, with a backoff starting from `initial_backoff_ms` and
+/// doubled for each retry up to `max_backoff_ms`, which must not be less than `initial_backoff_ms`.
+/// A `RetriableError` is returned if the failure persists after all the attempts.
+#[no_mangle]
+pub extern "C" fn set_job_client_retry(
+    ptr_client: *const c_void, max_attempts: u32, initial_backoff_ms: u64, max_backoff_ms: u64,
+) -> FfiResult {
+    catch_panic(|| {
+        if max_attempts == 0 {
+            return FfiResult::new(
+                ResultCode::InvalidRangeError,
+                "max attempts must be positive".to_string(),
+            );
+        }
+        if max_backoff_ms < initial_backoff_ms {
+            return FfiResult::new(
+                ResultCode::InvalidRangeError,
+                format!(
+                    "max backoff {:?}ms is less than initial backoff {:?}ms",
+                    max_backoff_ms, initial_backoff_ms
+                ),
+            );
+        }
+        let mut client = match borrow_client(ptr_client) {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        client.set_retry_policy(RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(initial_backoff_ms),
+            max_backoff: Duration::from_millis(max_backoff_ms),
+        });
+
+        FfiResult::success()
+    })
+}
+
+fn optional_cstr(cstr: *const c_char) -> Result<Option<String>, FfiResult> {
+    if cstr.is_null() {
+        return Ok(None);
+    }
+    let s = cstr_to_string(cstr)?;
+    Ok(if s.is_empty() { None } else { Some(s) })
+}
+
+/// Connect the job client to the servers via TLS in the subsequent connections, where the
+/// certificates and the key are given as PEM-encoded strings, and any of them can be null (empty):
+/// the system roots are used without `ca_cert`, the client is not authenticated without the
+/// `client_cert` and `client_key`, which must be given together, and the host of the url is
+/// used as the `domain_name` (SNI) if not given.
+#[no_mangle]
+pub extern "C" fn set_job_client_tls(
+    ptr_client: *const c_void, ca_cert: *const c_char, client_cert: *const c_char,
+    client_key: *const c_char, domain_name: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        let tls = (|| {
+            let client_identity = match (optional_cstr(client_cert)?, optional_cstr(client_key)?) {
+                (Some(cert), Some(key)) => Some((cert.into_bytes(), key.into_bytes())),
+                (None, None) => None,
+                _ => Err(FfiResult::new(
+                    ResultCode::MissingDataError,
+                    "the client certificate and key must be given together".to_string(),
+                ))?,
+            };
+            Ok(TlsConfig {
+                ca_cert: optional_cstr(ca_cert)?.map(String::into_bytes),
+                client_identity,
+                domain_name: optional_cstr(domain_name)?,
+            })
+        })();
+        match tls {
+            Ok(tls) => {
+                let mut client = match borrow_client(ptr_client) {
+                    Ok(client) => client,
+                    Err(e) => return e,
+                };
+                client.set_tls_config(tls);
+                FfiResult::success()
+            }
+            Err(e) => e,
+        }
+    })
+}
+
+/// Connect the job client to the server of `server_id`, which can be accessed via the `cstr_url`.
+#[no_mangle]
+pub extern "C" fn connect_job_server(
+    ptr_client: *const c_void, server_id: u64, cstr_url: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut client = match borrow_client(ptr_client) {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let result = cstr_to_string(cstr_url).and_then(|url| Ok(client.connect(server_id, url)?));
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    })
+}
+
+/// Submit a physical plan, given as the bytes built via `build_physical_plan()`, as a job.
+/// The pointer of the job is owned by Rust, and must be released via [`destroy_job`].
+#[no_mangle]
+pub extern "C" fn submit_job(
+    ptr_client: *const c_void, conf: FfiJobConf, plan: FfiPbPointer, ptr_job: *mut *const c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(ptr_job, "output job") {
+            return e;
+        }
+        let mut client = match borrow_client(ptr_client) {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let result = <(JobConf, SubmitOptions)>::try_from(conf).and_then(|(conf, options)| {
+            let job = client.submit(conf, options, plan.as_bytes()?.to_vec())?;
+            Ok(handle::register::<_, c_void>(job))
+        });
+        set_output(ptr_job, result)
+    })
+}
+
+/// Submit the bundle of physical plans (the bytes of a `PhysicalPlanBundle`) built by
+/// `build_physical_bundle()`, where the statements are run in order, and the job of the last
+/// statement is written to `ptr_job`, which must be destroyed via `destroy_job()`.
+#[no_mangle]
+pub extern "C" fn submit_job_bundle(
+    ptr_client: *const c_void, conf: FfiJobConf, bundle: FfiPbPointer, ptr_job: *mut *const c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(ptr_job, "output job") {
+            return e;
+        }
+        let mut client = match borrow_client(ptr_client) {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let result = <(JobConf, SubmitOptions)>::try_from(conf).and_then(|(conf, options)| {
+            let job = client.submit_bundle(conf, options, bundle.as_bytes()?.to_vec())?;
+            Ok(handle::register::<_, c_void>(job))
+        });
+        set_output(ptr_job, result)
+    })
+}
+
+/// To destroy a job, which stops fetching its remaining results.
+#[no_mangle]
+pub extern "C" fn destroy_job(ptr_job: *const c_void) {
+    catch_panic(|| destroy_handle::<Job>(ptr_job))
+}
+
+/// Collect a page of results, each of which is the bytes of a `results::Results`,
+/// into the bytes of a `results::CollectiveResults`, with the metadata if the results are partial.
+fn page_to_ffi_data(page: ResultPage) -> FfiData {
+    let results = page
+        .results
+        .iter()
+        .map(|bytes| result_pb::Results::decode(bytes.as_slice()))
+        .collect::<Result<Vec<_>, _>>();
+    let meta = page
+        .partial
+        .map(|reason| result_pb::ResultsMeta { partial: true, reason });
+    match results {
+        Ok(results) => result_pb::CollectiveResults { results, meta }
+            .encode_to_vec()
+            .into(),
+        Err(e) => IrError::from(e).into(),
+    }
+}
+
+fn check_batch_size(batch_size: i32) -> Result<usize, FfiResult> {
+    if batch_size <= 0 {
+        Err(FfiResult::new(ResultCode::InvalidRangeError, format!("invalid batch size {:?}", batch_size)))
+    } else {
+        Ok(batch_size as usize)
+    }
+}
+
+fn fetch_job_results(ptr_job: *const c_void, max_rows: i32, has_more: *mut bool) -> FfiData {
+    let max_rows = match check_batch_size(max_rows) {
+        Ok(max_rows) => max_rows,
+        Err(e) => return e.into(),
+    };
+    let mut job = match borrow_job(ptr_job) {
+        Ok(job) => job,
+        Err(e) => return e.into(),
+    };
+    if let Err(e) = check_not_null(has_more, "output of whether there are more results") {
+        return e.into();
+    }
+    match job.fetch(max_rows) {
+        Ok(page) => {
+            unsafe { *has_more = page.has_more };
+            page_to_ffi_data(page)
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Fetch at most `max_rows` results of a job, returned as the bytes of a `results::CollectiveResults`,
+/// which can be further decoded via `decode_result_batch()`. Fewer than `max_rows` results are
+/// returned only if the job has finished, while `has_more` indicates whether there are more
+/// results to fetch.
+#[no_mangle]
+pub extern "C" fn fetch_results(ptr_job: *const c_void, max_rows: i32, has_more: *mut bool) -> FfiData {
+    catch_panic(|| fetch_job_results(ptr_job, max_rows, has_more))
+}
+
+/// Whether the results fetched from a job are partial, i.e., the job failed (e.g., a worker failed,
+/// or the job timed out) while its plan allows partial results via `set_plan_allow_partial()`,
+/// in which case no more results are fetched. The results are complete if the job has no more
+/// results to fetch, and they are not partial.
+#[no_mangle]
+pub extern "C" fn is_job_partial(ptr_job: *const c_void) -> bool {
+    catch_panic(|| {
+        // an invalid job, e.g., a null one, is never partial
+        match borrow_job(ptr_job) {
+            Ok(job) => job.is_partial(),
+            Err(e) => {
+                e.discard();
+                false
+            }
+        }
+    })
+}
+
+/// The callback to receive the results of a job, given the `user_data` registered at submission,
+/// a batch of results as the bytes of a `results::CollectiveResults` (or the error if any), and
+/// whether it is the last batch. The last batch carries the metadata of the results if they are partial. The batch is owned by the callee, and must be released via
+/// `destroy_ffi_data()`.
+pub type FfiResultsCallback = extern "C" fn(user_data: *mut c_void, results: FfiData, is_last: bool);
+
+/// The `user_data` is passed through to the callback, which is invoked in another thread.
+struct CallbackData(*mut c_void);
+
+unsafe impl Send for CallbackData {}
+
+/// Submit a physical plan as a job, of which the results are pushed to the `callback` in batches
+/// of at most `batch_size` results as they arrive. The callback is invoked in a background thread,
+/// and this function returns as soon as the job is submitted. The job client **must not** be
+/// destroyed until the last batch is received, otherwise the remaining results are discarded.
+#[no_mangle]
+pub extern "C" fn submit_job_with_callback(
+    ptr_client: *const c_void, conf: FfiJobConf, plan: FfiPbPointer, batch_size: i32,
+    callback: FfiResultsCallback, user_data: *mut c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        let batch_size = match check_batch_size(batch_size) {
+            Ok(batch_size) => batch_size,
+            Err(e) => return e,
+        };
+        let mut client = match borrow_client(ptr_client) {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let user_data = CallbackData(user_data);
+        let result = <(JobConf, SubmitOptions)>::try_from(conf).and_then(|(conf, options)| {
+            Ok(client.submit_with_callback(
+                conf,
+                options,
+                plan.as_bytes()?.to_vec(),
+                batch_size,
+                move |page| {
+                    let (data, is_last) = match page {
+                        Ok(page) => {
+                            let is_last = !page.has_more;
+                            (page_to_ffi_data(page), is_last)
+                        }
+                        Err(e) => (e.into(), true),
+                    };
+                    callback(user_data.0, data, is_last)
+                },
+            )?)
+        });
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    })
+}
@@ -0,0 +1,397 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The client module submits the physical plans to the engine, and fetches the results
+//! of the submitted jobs in a blocking manner, which is what the C-like callers expect.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ir_common::generated::physical as physical_pb;
+use pegasus::JobConf;
+pub use pegasus_server::client::JobAuth;
+use pegasus_server::client::{Certificate, ClientTlsConfig, Identity, JobCursor, JobError, RPCJobClient};
+use pegasus_server::job::JobDesc;
+use prost::Message;
+use tokio::runtime::Runtime;
+
+use crate::error::{IrError, IrResult};
+
+pub mod ffi;
+
+/// The options of submitting a job, which take effect in the client rather than in the engine.
+#[derive(Clone, Debug, Default)]
+pub struct SubmitOptions {
+    /// The maximum number of results buffered ahead of being fetched (consumed), which
+    /// bounds the memory held for a slow consumer, where 0 means no buffering.
+    pub buffer_size: usize,
+    /// The credential attached to the submission, which the engine checks against
+    /// the permissions of the user
+    pub auth: Option<JobAuth>,
+}
+
+/// The policy of retrying the requests to the engine on transient failures (e.g., the server is
+/// temporarily unavailable), with an exponential backoff between the attempts.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts of a request, including the first attempt
+    pub max_attempts: u32,
+    /// The backoff before the first retry, which is doubled for each further retry
+    pub initial_backoff: Duration,
+    /// The upper bound of the backoff
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The backoff after the `attempt`-th (starting from 1) attempt fails, which is capped by the
+    /// `max_backoff` rather than overflowing however large the `initial_backoff` is.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1_u32 << std::cmp::min(attempt.saturating_sub(1), 16);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map(|backoff| std::cmp::min(backoff, self.max_backoff))
+            .unwrap_or(self.max_backoff)
+    }
+}
+
+/// The TLS config of connecting to the engine, where the certificates and the key are PEM-encoded.
+#[derive(Clone, Debug, Default)]
+pub struct TlsConfig {
+    /// The CA certificate to verify the servers, or the system roots are used if not given
+    pub ca_cert: Option<Vec<u8>>,
+    /// The certificate and the private key of the client, given for mutual TLS
+    pub client_identity: Option<(Vec<u8>, Vec<u8>)>,
+    /// The domain name (SNI) to verify the servers against, or the host of the url if not given
+    pub domain_name: Option<String>,
+}
+
+impl From<&TlsConfig> for ClientTlsConfig {
+    fn from(tls: &TlsConfig) -> Self {
+        let mut config = ClientTlsConfig::new();
+        if let Some(ca_cert) = &tls.ca_cert {
+            config = config.ca_certificate(Certificate::from_pem(ca_cert));
+        }
+        if let Some((cert, key)) = &tls.client_identity {
+            config = config.identity(Identity::from_pem(cert, key));
+        }
+        if let Some(domain_name) = &tls.domain_name {
+            config = config.domain_name(domain_name.clone());
+        }
+        config
+    }
+}
+
+/// A blocking client to submit jobs to the engine.
+pub struct JobClient {
+    runtime: Arc<Runtime>,
+    client: RPCJobClient,
+    /// The urls of the connected servers, which are used to reconnect the servers
+    servers: BTreeMap<u64, String>,
+    retry: RetryPolicy,
+    tls: Option<TlsConfig>,
+}
+
+impl JobClient {
+    pub fn new() -> IrResult<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| IrError::SubmitJobError(e.to_string()))?;
+        Ok(JobClient {
+            runtime: Arc::new(runtime),
+            client: RPCJobClient::new(),
+            servers: BTreeMap::new(),
+            retry: RetryPolicy::default(),
+            tls: None,
+        })
+    }
+
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// Connect to the servers via TLS as the `tls` config, which takes effect in the
+    /// subsequent connections, while plaintext connections are used if not set.
+    pub fn set_tls_config(&mut self, tls: TlsConfig) {
+        self.tls = Some(tls);
+    }
+
+    async fn connect_server(
+        client: &mut RPCJobClient, server_id: u64, url: String, tls: Option<&TlsConfig>,
+    ) -> Result<(), JobError> {
+        client
+            .connect_with_tls(server_id, url, tls.map(ClientTlsConfig::from))
+            .await
+    }
+
+    /// Run the `request` until it succeeds, or fails with a non-transient error, or the attempts
+    /// are exhausted, in which case a `RetriableError` is returned. The `request` is given the
+    /// number of the current attempt, and returns the error with whether it is transient.
+    fn with_retry<T, F>(&mut self, mut request: F) -> IrResult<T>
+    where
+        F: FnMut(&mut Self, u32) -> Result<T, (IrError, bool)>,
+    {
+        let mut attempt = 1;
+        loop {
+            match request(self, attempt) {
+                Ok(t) => return Ok(t),
+                Err((e, true)) if attempt < self.retry.max_attempts => {
+                    let backoff = self.retry.backoff(attempt);
+                    warn!("attempt {} failed due to {}, retry after {:?}", attempt, e, backoff);
+                    std::thread::sleep(backoff);
+                    attempt += 1;
+                }
+                Err((e, true)) => {
+                    return Err(IrError::RetriableError(format!("{} after {} attempt(s)", e, attempt)))
+                }
+                Err((e, false)) => return Err(e),
+            }
+        }
+    }
+
+    /// Connect to the server of `server_id`, which can be accessed via the `url`. The connection is
+    /// retried only if the server is unavailable, rather than, e.g., the url is invalid. The url is
+    /// kept to reconnect the server only once it is connected.
+    pub fn connect(&mut self, server_id: u64, url: String) -> IrResult<()> {
+        self.with_retry(|this, _| {
+            this.runtime
+                .block_on(Self::connect_server(&mut this.client, server_id, url.clone(), this.tls.as_ref()))
+                .map_err(|e| {
+                    let is_transient = e.is_transient();
+                    (e.into(), is_transient)
+                })
+        })?;
+        self.servers.insert(server_id, url);
+
+        Ok(())
+    }
+
+    /// Reconnect all the connected servers, as the connections may have been broken.
+    fn reconnect(&mut self) {
+        for (server_id, url) in self.servers.iter() {
+            if let Err(e) = self.runtime.block_on(Self::connect_server(
+                &mut self.client,
+                *server_id,
+                url.clone(),
+                self.tls.as_ref(),
+            )) {
+                warn!("reconnect server {} error: {}", server_id, e);
+            }
+        }
+    }
+
+    fn submit_with_cursor(
+        &mut self, conf: JobConf, options: &SubmitOptions, plan: Vec<u8>,
+    ) -> IrResult<JobCursor> {
+        self.with_retry(|this, attempt| {
+            if attempt > 1 {
+                this.reconnect();
+            }
+            let mut job = JobDesc::default();
+            job.set_plan(plan.clone());
+            this.runtime
+                .block_on(this.client.submit_with_cursor(
+                    conf.clone(),
+                    job,
+                    options.auth.as_ref(),
+                    options.buffer_size,
+                ))
+                .map_err(|e| {
+                    let is_transient = e.is_transient();
+                    (e.into(), is_transient)
+                })
+        })
+    }
+
+    /// Submit the physical plan as a job, of which the results can be fetched via the returned job.
+    /// The submission is retried on transient failures as the retry policy, i.e., only if the server
+    /// is unavailable, in which case the job is not run, such that a job never runs twice. The failures
+    /// after the job is submitted (e.g., while fetching the results) are not retried.
+    pub fn submit(&mut self, conf: JobConf, options: SubmitOptions, plan: Vec<u8>) -> IrResult<Job> {
+        let allow_partial = physical_pb::PhysicalPlan::decode(plan.as_slice())?.allow_partial;
+        let cursor = self.submit_with_cursor(conf, &options, plan)?;
+
+        Ok(Job { runtime: self.runtime.clone(), cursor, allow_partial, partial: None })
+    }
+
+    /// Submit the physical plan as a job, of which the results are pushed to the `callback` in
+    /// pages of at most `batch_size` results as they arrive, instead of being fetched by the caller.
+    /// The callback is invoked in a background thread with each page, until the last one.
+    pub fn submit_with_callback<F>(
+        &mut self, conf: JobConf, options: SubmitOptions, plan: Vec<u8>, batch_size: usize, mut callback: F,
+    ) -> IrResult<()>
+    where
+        F: FnMut(IrResult<ResultPage>) + Send + 'static,
+    {
+        let allow_partial = physical_pb::PhysicalPlan::decode(plan.as_slice())?.allow_partial;
+        let mut cursor = self.submit_with_cursor(conf, &options, plan)?;
+        self.runtime.spawn(async move {
+            loop {
+                let page = fetch_page(&mut cursor, batch_size, allow_partial).await;
+                let is_last = page
+                    .as_ref()
+                    .map(|page| !page.has_more)
+                    .unwrap_or(true);
+                callback(page);
+                if is_last {
+                    break;
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Submit the bundle of physical plans (the bytes of a `PhysicalPlanBundle`), where the statements
+    /// are run one by one in order, which respects their dependencies. The results of the preceding
+    /// statements are discarded, and the job of the last statement is returned. The statements share
+    /// a scope of the registered results of their own (see `PhysicalPlan.result_scope`), which is
+    /// unique per submission, such that the bundles submitted alongside never read the results of
+    /// each other.
+    pub fn submit_bundle(
+        &mut self, conf: JobConf, options: SubmitOptions, bundle: Vec<u8>,
+    ) -> IrResult<Job> {
+        let mut statements = physical_pb::PhysicalPlanBundle::decode(bundle.as_slice())?.statements;
+        let last = statements
+            .pop()
+            .ok_or_else(|| IrError::InvalidBundle("the bundle is empty".to_string()))?;
+        // 0 refers to the default scope shared by the plans out of a bundle
+        let result_scope = rand::random::<u64>().max(1);
+        let encode_plan = |plan: Option<physical_pb::PhysicalPlan>| {
+            let mut plan = plan.unwrap_or_default();
+            plan.result_scope = result_scope;
+            plan.encode_to_vec()
+        };
+        for statement in statements {
+            let name = statement.name;
+            let plan = encode_plan(statement.plan);
+            let into_err = |e| IrError::NodeError(format!("statement {:?}", name), Box::new(e));
+            let mut job = self
+                .submit(conf.clone(), options.clone(), plan)
+                .map_err(into_err)?;
+            // drain the results of the statement to wait for its completion
+            loop {
+                let page = job.fetch(usize::MAX).map_err(into_err)?;
+                if !page.has_more {
+                    break;
+                }
+            }
+        }
+
+        self.submit(conf, options, encode_plan(last.plan))
+    }
+}
+
+/// A page of the results of a job.
+#[derive(Debug, Default)]
+pub struct ResultPage {
+    /// The results, each of which is the bytes of a `results::Results`
+    pub results: Vec<Vec<u8>>,
+    /// Whether the job has more results to fetch
+    pub has_more: bool,
+    /// The reason why the results of the job are partial, i.e., the job failed after returning
+    /// these results, which is only given if the plan allows partial results, as the job fails otherwise
+    pub partial: Option<String>,
+}
+
+/// Fetch a page of the results from the cursor, where a failure is turned into a partial page if
+/// `allow_partial` is set.
+async fn fetch_page(cursor: &mut JobCursor, max_rows: usize, allow_partial: bool) -> IrResult<ResultPage> {
+    match cursor.fetch_until_error(max_rows).await {
+        (results, None) => {
+            let has_more = cursor.has_more().await;
+            Ok(ResultPage { results, has_more, partial: None })
+        }
+        (results, Some(e)) if allow_partial => {
+            warn!("job failed with {} result(s) fetched, which are partial: {}", cursor.fetched(), e);
+            Ok(ResultPage { results, has_more: false, partial: Some(e.to_string()) })
+        }
+        (_, Some(e)) => Err(e.into()),
+    }
+}
+
+/// A submitted job, which maintains the cursor of its results.
+pub struct Job {
+    runtime: Arc<Runtime>,
+    cursor: JobCursor,
+    /// Whether the plan of the job allows partial results
+    allow_partial: bool,
+    /// The reason why the results are partial, once the job failed while allowing partial results
+    partial: Option<String>,
+}
+
+impl Job {
+    /// Fetch a page of at most `max_rows` results. Once the job fails while its plan allows partial
+    /// results, the results fetched so far are kept, and the page is marked as partial instead.
+    pub fn fetch(&mut self, max_rows: usize) -> IrResult<ResultPage> {
+        if let Some(reason) = &self.partial {
+            return Ok(ResultPage { results: vec![], has_more: false, partial: Some(reason.clone()) });
+        }
+        let page = self
+            .runtime
+            .block_on(fetch_page(&mut self.cursor, max_rows, self.allow_partial))?;
+        self.partial = page.partial.clone();
+        Ok(page)
+    }
+
+    /// Whether the results of the job are partial, as it failed while allowing partial results.
+    pub fn is_partial(&self) -> bool {
+        self.partial.is_some()
+    }
+
+    /// The number of results that have been fetched.
+    pub fn fetched(&self) -> u64 {
+        self.cursor.fetched()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn retry_backoff() {
+        let retry = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(retry.backoff(1), Duration::from_millis(100));
+        assert_eq!(retry.backoff(2), Duration::from_millis(200));
+        assert_eq!(retry.backoff(3), Duration::from_millis(400));
+        assert_eq!(retry.backoff(4), Duration::from_millis(500));
+        assert_eq!(retry.backoff(100), Duration::from_millis(500));
+
+        // the backoff is capped rather than overflowing
+        let retry = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(u64::MAX / 2),
+            max_backoff: Duration::from_secs(u64::MAX),
+        };
+        assert_eq!(retry.backoff(1), Duration::from_secs(u64::MAX / 2));
+        assert_eq!(retry.backoff(3), Duration::from_secs(u64::MAX));
+    }
+}
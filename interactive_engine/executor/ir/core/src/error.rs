@@ -19,6 +19,7 @@ use std::fmt;
 use ir_common::error::ParsePbError;
 use ir_common::expr_parse::error::ExprError;
 use ir_common::NameOrId;
+use pegasus_server::client::JobError;
 use prost::{DecodeError, EncodeError};
 
 use crate::glogue::error::IrPatternError;
@@ -34,15 +35,40 @@ pub enum IrError {
     TableNotExist(NameOrId),
     ColumnNotExist(NameOrId),
     ParentNodeNotExist(u32),
+    NodeNotExist(u32),
+    /// An error raised while processing the described node
+    NodeError(String, Box<IrError>),
     TagNotExist(NameOrId),
     ParsePbError(ParsePbError),
     ParseExprError(ExprError),
     InvalidPattern(String),
     InvalidExtendPattern(IrPatternError),
+    /// An invalid bundle of plans, e.g., a statement depends on a subsequent one
+    InvalidBundle(String),
+    /// The branches of a union that produce different tags, while the tags are strictly aligned
+    UnalignedUnion(String),
+    /// The estimated complexity of a plan exceeds the threshold, as (complexity, threshold)
+    ComplexityExceeded(f64, f64),
+    /// The hop range of a `PathExpand` exceeds the maximal number of hops, as (lower, upper, maximum)
+    HopRangeExceeded(i32, i32, i32),
+    /// A frozen plan is modified, e.g., an operator is appended to it
+    PlanFrozen,
+    /// A node that breaks the structure of a plan, e.g., it is in a cycle, as (node, reason)
+    InvalidNode(u32, String),
+    /// A label or a property out of the read scope of the plan is accessed
+    AccessDenied(String),
 
     // Physical Errors
     MissingData(String),
     InvalidRange(i32, i32),
+    /// A global id that is out of the id ranges of the labels it is looked up in
+    InvalidGlobalId(i64),
+    /// An error while evaluating an expression over the sample records
+    EvalError(String),
+
+    // Job Errors
+    SubmitJobError(String),
+    RetriableError(String),
 
     // Common Errors
     Unsupported(String),
@@ -63,18 +89,44 @@ impl fmt::Display for IrError {
             IrError::ParentNodeNotExist(node) => {
                 write!(f, "the given parent node: {:?} does not exist", node)
             }
+            IrError::NodeNotExist(node) => write!(f, "the given node: {:?} does not exist", node),
+            IrError::NodeError(node, err) => write!(f, "{}: {}", node, err),
             IrError::ParsePbError(err) => write!(f, "parse pb error: {:?}", err),
             IrError::ParseExprError(err) => write!(f, "parse expression error: {:?}", err),
             IrError::InvalidPattern(s) => write!(f, "invalid pattern: {:?}", s),
             IrError::InvalidExtendPattern(err) => {
                 write!(f, "invalid pattern with ExtendStrategy: {:?}", err)
             }
+            IrError::InvalidBundle(s) => write!(f, "invalid bundle: {}", s),
+            IrError::UnalignedUnion(s) => write!(f, "unaligned union: {}", s),
+            IrError::ComplexityExceeded(score, threshold) => write!(
+                f,
+                "the estimated complexity {:.2} of the plan exceeds the threshold {:.2}",
+                score, threshold
+            ),
+            IrError::HopRangeExceeded(lo, up, max_hops) if *up == i32::MAX => write!(
+                f,
+                "the unbounded hop range from {:?} exceeds the maximum of {:?} hops",
+                lo, max_hops
+            ),
+            IrError::HopRangeExceeded(lo, up, max_hops) => {
+                write!(f, "the hop range ({:?}, {:?}) exceeds the maximum of {:?} hops", lo, up, max_hops)
+            }
+            IrError::PlanFrozen => write!(f, "the plan is frozen, and can no longer be modified"),
+            IrError::InvalidNode(node, reason) => write!(f, "the node {:?} is invalid: {}", node, reason),
+            IrError::AccessDenied(s) => write!(f, "access denied: {}", s),
             IrError::PbEncodeError(err) => write!(f, "encoding protobuf error: {:?}", err),
             IrError::PbDecodeError(err) => write!(f, "decoding protobuf error: {:?}", err),
             IrError::MissingData(s) => write!(f, "missing required data: {:?}", s),
             IrError::InvalidRange(lo, up) => {
                 write!(f, "invalid range ({:?}, {:?})", lo, up)
             }
+            IrError::InvalidGlobalId(id) => {
+                write!(f, "the global id {:?} is not of the given label(s)", id)
+            }
+            IrError::EvalError(s) => write!(f, "evaluation error: {}", s),
+            IrError::SubmitJobError(s) => write!(f, "submit job error: {}", s),
+            IrError::RetriableError(s) => write!(f, "retriable error: {}", s),
             IrError::Unsupported(s) => write!(f, "{:?}: is not supported", s),
         }
     }
@@ -106,6 +158,12 @@ impl From<ExprError> for IrError {
     }
 }
 
+impl From<JobError> for IrError {
+    fn from(err: JobError) -> Self {
+        Self::SubmitJobError(err.to_string())
+    }
+}
+
 impl From<IrPatternError> for IrError {
     fn from(err: IrPatternError) -> Self {
         Self::InvalidExtendPattern(err)
@@ -44,13 +44,22 @@ pub(crate) fn query_params(
         predicate,
         sample_ratio: 1.0,
         extra: HashMap::new(),
+        degree_predicates: vec![],
     }
 }
 
 pub(crate) fn query_params_to_get_v(
     params: Option<pb::QueryParams>, alias: Option<KeyId>, opt: i32,
 ) -> pb::GetV {
-    pb::GetV { tag: None, opt, params, alias: alias.map(|id| id.into()), meta_data: None }
+    pb::GetV {
+        tag: None,
+        opt,
+        params,
+        alias: alias.map(|id| id.into()),
+        meta_data: None,
+        is_optional: false,
+        end_alias: None,
+    }
 }
 
 pub fn combine_query_params(params1: pb::QueryParams, params2: pb::QueryParams) -> pb::QueryParams {
@@ -86,6 +95,9 @@ pub fn combine_query_params(params1: pb::QueryParams, params2: pb::QueryParams)
     }
     params.extra.extend(params2.extra);
     params
+        .degree_predicates
+        .extend(params2.degree_predicates);
+    params
 }
 
 pub fn combine_exprs(expr1: common_pb::Expression, expr2: common_pb::Expression) -> common_pb::Expression {
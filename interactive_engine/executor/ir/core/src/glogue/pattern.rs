@@ -30,7 +30,7 @@ use crate::glogue::{
     combine_query_params, query_params, DynIter, PatternDirection, PatternId, PatternLabelId,
     PatternOrderTrait, PatternWeightTrait,
 };
-use crate::plan::meta::{PlanMeta, TagId, STORE_META};
+use crate::plan::meta::{PlanMeta, TagId};
 
 #[derive(Debug, Clone)]
 pub struct PatternVertex {
@@ -398,6 +398,7 @@ impl Pattern {
                             if i == last_expand_index.unwrap() { end_tag_label.clone() } else { None };
                         //  infer src/dst vertex label with information of required_src_vertex_label, required_dst_vertex_label, edge_labels and pattern_meta
                         let (src_vertex_label, dst_vertex_label) = assign_src_dst_vertex_labels(
+                            plan_meta,
                             edge_labels.clone(),
                             edge_direction,
                             required_src_vertex_label,
@@ -625,9 +626,14 @@ fn append_opr(
     if match_plan_len > 0 {
         set_last_node_children(match_plan, vec![match_plan_len as KeyId])?;
     }
-    match_plan
-        .nodes
-        .push(pb::logical_plan::Node { opr: Some(new_opr), children: vec![] });
+    match_plan.nodes.push(pb::logical_plan::Node {
+        opr: Some(new_opr),
+        children: vec![],
+        annotations: vec![],
+        display_name: "".to_string(),
+        accesses: vec![],
+        attributes: HashMap::new(),
+    });
     Ok(())
 }
 
@@ -679,6 +685,8 @@ fn generate_source_operator(
         params: Some(source_vertex_param),
         idx_predicate: None,
         meta_data: None,
+        connector: None,
+        named_result: String::new(),
     };
     Ok(source_scan.into())
 }
@@ -1056,7 +1064,7 @@ fn assign_expand_dst_vertex_id(
 /// Based on the vertex labels candidates and required src/dst vertex label,
 /// assign the src and dst vertex with vertex labels meeting the requirement
 fn assign_src_dst_vertex_labels(
-    edge_labels: Vec<PatternLabelId>, edge_direction: pb::edge_expand::Direction,
+    plan_meta: &PlanMeta, edge_labels: Vec<PatternLabelId>, edge_direction: pb::edge_expand::Direction,
     required_src_labels: Option<BTreeSet<PatternLabelId>>,
     required_dst_labels: Option<BTreeSet<PatternLabelId>>,
 ) -> IrPatternResult<(BTreeSet<PatternLabelId>, BTreeSet<PatternLabelId>)> {
@@ -1071,7 +1079,7 @@ fn assign_src_dst_vertex_labels(
     let mut candi_src_vertex_labels = BTreeSet::new();
     let mut candi_dst_vertex_labels = BTreeSet::new();
 
-    if let Ok(store_meta) = STORE_META.read() {
+    plan_meta.with_store_meta(|store_meta| {
         if let Some(schema) = store_meta.schema.as_ref() {
             for edge_label in edge_labels {
                 if let Some(bound_labels) = schema.get_bound_labels(edge_label) {
@@ -1098,7 +1106,7 @@ fn assign_src_dst_vertex_labels(
                 }
             }
         }
-    }
+    });
 
     // For a chosen candidates:
     // - if the required src label is some, its src vertex label must match the requirement
@@ -15,11 +15,17 @@
 
 use std::io;
 
+pub use crate::client::ffi::*;
 pub use crate::plan::ffi::*;
+pub use crate::result::ffi::*;
+pub use crate::session::ffi::*;
 
+pub mod client;
 pub mod error;
 pub mod glogue;
 pub mod plan;
+pub mod result;
+pub mod session;
 
 #[macro_use]
 extern crate lazy_static;
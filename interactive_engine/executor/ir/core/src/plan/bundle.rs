@@ -0,0 +1,419 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! A bundle of several dependent plans (statements) that is submitted as a unit, e.g., to create
+//! a temporary result and then query it, such that the ETL-style pipelines need not orchestrate
+//! the steps in the client. A statement can only depend on the preceding ones, and thus running
+//! the statements in order respects their dependencies. A statement can scan the results registered
+//! by the statements that it depends on (see `SinkResult`).
+
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+use ir_common::error::ParsePbError;
+use ir_common::generated::algebra as pb;
+use ir_common::generated::physical as physical_pb;
+use ir_physical_client::physical_builder::PlanBuilder;
+
+use crate::error::{IrError, IrResult};
+use crate::plan::logical::LogicalPlan;
+use crate::plan::physical::AsPhysical;
+
+/// A statement of a bundle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statement {
+    pub(crate) name: String,
+    pub(crate) plan: LogicalPlan,
+    /// The indices of the preceding statements that this statement depends on
+    pub(crate) dependencies: Vec<usize>,
+}
+
+impl Statement {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_plan(&self) -> &LogicalPlan {
+        &self.plan
+    }
+
+    pub fn get_dependencies(&self) -> &[usize] {
+        &self.dependencies
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlanBundle {
+    statements: Vec<Statement>,
+}
+
+impl PlanBundle {
+    /// Add the `plan` as a statement named `name`, which depends on the statements of the given
+    /// indices, and return its index. The name must be unique in the bundle, and the statement can
+    /// only depend on the preceding ones.
+    pub fn add_statement(
+        &mut self, name: String, plan: LogicalPlan, mut dependencies: Vec<usize>,
+    ) -> IrResult<usize> {
+        if name.is_empty() {
+            return Err(IrError::InvalidBundle("the name of a statement is empty".to_string()));
+        }
+        if self.get_statement_index(&name).is_some() {
+            return Err(IrError::InvalidBundle(format!("the statement {:?} already exists", name)));
+        }
+        let index = self.statements.len();
+        if let Some(&dependency) = dependencies.iter().find(|&&dep| dep >= index) {
+            return Err(IrError::InvalidBundle(format!(
+                "the statement {:?} depends on {:?}, which does not precede it",
+                name, dependency
+            )));
+        }
+        dependencies.sort_unstable();
+        dependencies.dedup();
+        // a registered result can only be scanned by the statements depending on the registering one
+        for result in plan.get_scanned_results() {
+            let is_registered = dependencies.iter().any(|&dep| {
+                self.statements[dep]
+                    .plan
+                    .get_registered_results()
+                    .contains(&result)
+            });
+            if !is_registered {
+                return Err(IrError::InvalidBundle(format!(
+                    "the statement {:?} scans the result {:?}, which is not registered by its dependencies",
+                    name, result
+                )));
+            }
+        }
+        self.statements
+            .push(Statement { name, plan, dependencies });
+
+        Ok(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    pub fn get_statement(&self, index: usize) -> Option<&Statement> {
+        self.statements.get(index)
+    }
+
+    pub fn get_statement_index(&self, name: &str) -> Option<usize> {
+        self.statements
+            .iter()
+            .position(|statement| statement.name == name)
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    /// Build the physical plans of the statements, where the plans are partitioned if `is_partition`.
+    /// A registered result is released by the last statement that scans it, as long as the statement
+    /// scans it only once and as the source, which reads the result before the others do.
+    pub fn build_physical(&self, is_partition: bool) -> IrResult<physical_pb::PhysicalPlanBundle> {
+        let mut last_scans = HashMap::new();
+        for (index, statement) in self.statements.iter().enumerate() {
+            for result in statement.plan.get_scanned_results() {
+                last_scans.insert(result, index);
+            }
+        }
+        let mut statements_pb = Vec::with_capacity(self.statements.len());
+        for (index, statement) in self.statements.iter().enumerate() {
+            let mut plan = statement.plan.clone();
+            if is_partition {
+                plan.meta = plan.meta.clone().with_partition();
+            }
+            let mut plan_meta = plan.meta.clone();
+            let mut builder = PlanBuilder::default();
+            plan.add_job_builder(&mut builder, &mut plan_meta)
+                .map_err(|err| {
+                    IrError::NodeError(format!("statement {:?}", statement.name), Box::new(err))
+                })?;
+            let scanned_results = statement.plan.get_scanned_results();
+            let released_results = scanned_results
+                .iter()
+                .filter(|&result| {
+                    last_scans.get(result) == Some(&index)
+                        && scanned_results
+                            .iter()
+                            .filter(|&other| other == result)
+                            .count()
+                            == 1
+                        && is_source_scan(&builder, result)
+                })
+                .cloned()
+                .collect();
+            builder.release_results(released_results);
+            statements_pb.push(physical_pb::physical_plan_bundle::Statement {
+                name: statement.name.clone(),
+                plan: Some(builder.build()),
+                dependencies: statement
+                    .dependencies
+                    .iter()
+                    .map(|&dep| dep as i32)
+                    .collect(),
+            });
+        }
+
+        Ok(physical_pb::PhysicalPlanBundle { statements: statements_pb })
+    }
+}
+
+/// Whether the source of the physical plan scans the registered result `name`.
+fn is_source_scan(builder: &PlanBuilder, name: &str) -> bool {
+    match builder
+        .first()
+        .and_then(|op| op.opr.as_ref())
+        .and_then(|opr| opr.op_kind.as_ref())
+    {
+        Some(physical_pb::physical_opr::operator::OpKind::Scan(scan)) => scan.named_result == name,
+        _ => false,
+    }
+}
+
+impl From<PlanBundle> for pb::PlanBundle {
+    fn from(bundle: PlanBundle) -> Self {
+        let statements = bundle
+            .statements
+            .into_iter()
+            .map(|statement| pb::plan_bundle::Statement {
+                name: statement.name,
+                plan: Some(statement.plan.into()),
+                dependencies: statement
+                    .dependencies
+                    .into_iter()
+                    .map(|dep| dep as i32)
+                    .collect(),
+            })
+            .collect();
+
+        pb::PlanBundle { statements }
+    }
+}
+
+impl TryFrom<pb::PlanBundle> for PlanBundle {
+    type Error = IrError;
+
+    fn try_from(pb: pb::PlanBundle) -> Result<Self, Self::Error> {
+        let mut bundle = PlanBundle::default();
+        for statement in pb.statements {
+            let plan: LogicalPlan = statement
+                .plan
+                .ok_or_else(|| ParsePbError::EmptyFieldError("PlanBundle::Statement::plan".to_string()))?
+                .try_into()?;
+            let name = statement.name;
+            let dependencies = statement
+                .dependencies
+                .into_iter()
+                .map(|dep| {
+                    usize::try_from(dep).map_err(|_| {
+                        IrError::InvalidBundle(format!("invalid dependency {:?} of {:?}", dep, name))
+                    })
+                })
+                .collect::<IrResult<Vec<usize>>>()?;
+            bundle.add_statement(name, plan, dependencies)?;
+        }
+
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ir_common::expr_parse::str_to_expr_pb;
+
+    use super::*;
+
+    fn scan_plan(label: &str) -> LogicalPlan {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec![label.into()],
+                columns: vec![],
+                is_all_columns: false,
+                limit: None,
+                predicate: None,
+                sample_ratio: 1.0,
+                extra: Default::default(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut plan = LogicalPlan::default();
+        let id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let select = pb::Select { predicate: str_to_expr_pb("@.age > 20".to_string()).ok() };
+        plan.append_operator_as_node(select.into(), vec![id])
+            .unwrap();
+        plan
+    }
+
+    #[test]
+    fn bundle_add_statements() {
+        let mut bundle = PlanBundle::default();
+        assert_eq!(
+            bundle
+                .add_statement("person".to_string(), scan_plan("person"), vec![])
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            bundle
+                .add_statement("software".to_string(), scan_plan("software"), vec![])
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            bundle
+                .add_statement("query".to_string(), scan_plan("person"), vec![1, 0, 1])
+                .unwrap(),
+            2
+        );
+        assert_eq!(bundle.len(), 3);
+        assert_eq!(bundle.get_statement_index("query"), Some(2));
+        assert_eq!(
+            bundle
+                .get_statement(2)
+                .unwrap()
+                .get_dependencies(),
+            &[0, 1]
+        );
+
+        // duplicate names
+        assert!(bundle
+            .add_statement("person".to_string(), scan_plan("person"), vec![])
+            .is_err());
+        // depends on itself
+        assert!(bundle
+            .add_statement("self".to_string(), scan_plan("person"), vec![3])
+            .is_err());
+        // depends on an absent statement
+        assert!(bundle
+            .add_statement("absent".to_string(), scan_plan("person"), vec![5])
+            .is_err());
+        assert!(bundle
+            .add_statement("".to_string(), scan_plan("person"), vec![])
+            .is_err());
+        assert_eq!(bundle.len(), 3);
+    }
+
+    #[test]
+    fn bundle_into_pb() {
+        let mut bundle = PlanBundle::default();
+        bundle
+            .add_statement("person".to_string(), scan_plan("person"), vec![])
+            .unwrap();
+        bundle
+            .add_statement("query".to_string(), scan_plan("software"), vec![0])
+            .unwrap();
+
+        let bundle_pb: pb::PlanBundle = bundle.clone().into();
+        assert_eq!(bundle_pb.statements.len(), 2);
+        assert_eq!(bundle_pb.statements[1].dependencies, vec![0]);
+        assert_eq!(PlanBundle::try_from(bundle_pb.clone()).unwrap(), bundle);
+
+        // a statement depends on a subsequent one
+        let mut invalid_pb = bundle_pb;
+        invalid_pb.statements[0].dependencies = vec![1];
+        assert!(PlanBundle::try_from(invalid_pb).is_err());
+
+        let physical_bundle = bundle.build_physical(false).unwrap();
+        assert_eq!(physical_bundle.statements.len(), 2);
+        assert_eq!(physical_bundle.statements[1].name, "query");
+        assert_eq!(physical_bundle.statements[1].dependencies, vec![0]);
+        assert!(!physical_bundle.statements[1]
+            .plan
+            .as_ref()
+            .unwrap()
+            .plan
+            .is_empty());
+    }
+
+    #[test]
+    fn bundle_named_results() {
+        let mut register = scan_plan("person");
+        let sink = pb::Sink {
+            tags: vec![],
+            sink_target: Some(pb::sink::SinkTarget {
+                inner: Some(pb::sink::sink_target::Inner::SinkResult(pb::SinkResult {
+                    name: "adults".to_string(),
+                })),
+            }),
+        };
+        register
+            .append_operator_as_node(sink.into(), vec![1])
+            .unwrap();
+        let scan = pb::Scan {
+            scan_opt: 2,
+            alias: None,
+            params: None,
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: "adults".to_string(),
+        };
+        let mut query = LogicalPlan::default();
+        query
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+
+        let mut bundle = PlanBundle::default();
+        // the result is not registered yet
+        assert!(bundle
+            .add_statement("query".to_string(), query.clone(), vec![])
+            .is_err());
+        bundle
+            .add_statement("register".to_string(), register, vec![])
+            .unwrap();
+        bundle
+            .add_statement("other".to_string(), scan_plan("software"), vec![])
+            .unwrap();
+        // the result is not registered by the dependencies
+        assert!(bundle
+            .add_statement("query".to_string(), query.clone(), vec![1])
+            .is_err());
+        assert_eq!(
+            bundle
+                .add_statement("query".to_string(), query.clone(), vec![0])
+                .unwrap(),
+            2
+        );
+        let released_results = |bundle: &PlanBundle| -> Vec<Vec<String>> {
+            bundle
+                .build_physical(false)
+                .unwrap()
+                .statements
+                .into_iter()
+                .map(|statement| statement.plan.unwrap().released_results)
+                .collect()
+        };
+        // the result is released by the last statement that scans it
+        assert_eq!(released_results(&bundle), vec![vec![], vec![], vec!["adults".to_string()]]);
+        bundle
+            .add_statement("again".to_string(), query, vec![0])
+            .unwrap();
+        assert_eq!(released_results(&bundle), vec![vec![], vec![], vec![], vec!["adults".to_string()]]);
+    }
+}
@@ -0,0 +1,518 @@
+//
+//! Copyright 2023 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! A small interpreter that evaluates the expressions of `Select`, `Project` and `GroupBy` over
+//! the sample records given by the caller, following the semantics of the runtime, such that the
+//! compilers can test the semantics of the expressions (e.g., the predicates) without a running
+//! cluster. It is meant for a dry run on a handful of records, rather than an efficient execution.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use dyn_type::arith::{BitOperand, Exp};
+use dyn_type::Object;
+use ir_common::expr_parse::to_suffix_expr;
+use ir_common::generated::algebra as pb;
+use ir_common::generated::common as common_pb;
+use ir_common::NameOrId;
+
+use crate::error::{IrError, IrResult};
+
+/// An entry of a sample record, which is either a graph element of the given id, label and
+/// properties, or a value, e.g., the one computed by a `Project`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampleEntry {
+    pub id: Option<i64>,
+    pub label: Option<NameOrId>,
+    pub properties: BTreeMap<NameOrId, Object>,
+    /// The value of the entry, if it is not a graph element
+    pub value: Option<Object>,
+}
+
+impl SampleEntry {
+    pub fn element(id: i64, label: Option<NameOrId>) -> Self {
+        SampleEntry { id: Some(id), label, properties: BTreeMap::new(), value: None }
+    }
+
+    pub fn value(value: Object) -> Self {
+        SampleEntry { id: None, label: None, properties: BTreeMap::new(), value: Some(value) }
+    }
+
+    pub fn with_property(mut self, key: NameOrId, value: Object) -> Self {
+        self.properties.insert(key, value);
+        self
+    }
+
+    /// The entry itself as a value, namely, its value, or its id if it is a graph element.
+    fn as_object(&self) -> Object {
+        self.value
+            .clone()
+            .or_else(|| self.id.map(Object::from))
+            .unwrap_or(Object::None)
+    }
+}
+
+/// A sample record, which maps the tags to the entries, where the entry of no tag is the head.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampleRecord {
+    entries: BTreeMap<Option<NameOrId>, SampleEntry>,
+}
+
+impl SampleRecord {
+    pub fn new(head: SampleEntry) -> Self {
+        let mut record = SampleRecord::default();
+        record.entries.insert(None, head);
+        record
+    }
+
+    /// Set the entry of the tag, or the head if the tag is `None`.
+    pub fn set_entry(&mut self, tag: Option<NameOrId>, entry: SampleEntry) {
+        self.entries.insert(tag, entry);
+    }
+
+    pub fn get_entry(&self, tag: Option<&NameOrId>) -> Option<&SampleEntry> {
+        self.entries.get(&tag.cloned())
+    }
+
+    pub fn get_entry_mut(&mut self, tag: Option<&NameOrId>) -> Option<&mut SampleEntry> {
+        self.entries.get_mut(&tag.cloned())
+    }
+}
+
+/// Evaluate the expression over the sample record. A property that is absent from the entry is
+/// evaluated as `Object::None`, while a tag that is absent from the record is an error.
+pub fn eval_expression(expr: &common_pb::Expression, record: &SampleRecord) -> IrResult<Object> {
+    use common_pb::expr_opr::Item;
+
+    let suffix_oprs = to_suffix_expr(expr.operators.clone())?;
+    let mut stack: Vec<Object> = Vec::with_capacity(suffix_oprs.len());
+    for opr in suffix_oprs {
+        match opr.item {
+            Some(Item::Const(value)) => stack.push(Object::try_from(value)?),
+            Some(Item::Var(var)) => stack.push(eval_variable(&var, record)?),
+            Some(Item::Vars(vars)) => stack.push(Object::Vector(
+                vars.keys
+                    .iter()
+                    .map(|var| eval_variable(var, record))
+                    .collect::<IrResult<Vec<Object>>>()?,
+            )),
+            Some(Item::Logical(logical)) => {
+                let logical = common_pb::Logical::from_i32(logical)
+                    .ok_or_else(|| IrError::EvalError(format!("unknown logical operator {}", logical)))?;
+                let right = pop_operand(&mut stack, &format!("{:?}", logical))?;
+                let result = if logical == common_pb::Logical::Not {
+                    Object::from(!as_bool(&right)?)
+                } else {
+                    let left = pop_operand(&mut stack, &format!("{:?}", logical))?;
+                    apply_logical(logical, &left, &right)?
+                };
+                stack.push(result);
+            }
+            Some(Item::Arith(arith)) => {
+                let arith = common_pb::Arithmetic::from_i32(arith)
+                    .ok_or_else(|| IrError::EvalError(format!("unknown arithmetic operator {}", arith)))?;
+                let right = pop_operand(&mut stack, &format!("{:?}", arith))?;
+                let left = pop_operand(&mut stack, &format!("{:?}", arith))?;
+                stack.push(apply_arith(arith, &left, &right)?);
+            }
+            Some(Item::Param(param)) => {
+                return Err(IrError::EvalError(format!("the parameter {:?} is not bound", param.name)))
+            }
+            Some(item) => {
+                return Err(IrError::Unsupported(format!("evaluating {:?} over the sample records", item)))
+            }
+            None => return Err(IrError::MissingData("ExprOpr::item".to_string())),
+        }
+    }
+    if stack.len() == 1 {
+        Ok(stack.pop().unwrap())
+    } else {
+        Err(IrError::EvalError(format!("invalid expression of {} operands left", stack.len())))
+    }
+}
+
+/// Evaluate the expression as a predicate, in which `Object::None` (e.g., an absent property)
+/// is taken as `false`, as the runtime does.
+pub fn eval_predicate(expr: &common_pb::Expression, record: &SampleRecord) -> IrResult<bool> {
+    as_bool(&eval_expression(expr, record)?)
+}
+
+/// Filter the sample records by the predicate of the `Select`.
+pub fn eval_select(select: &pb::Select, records: &[SampleRecord]) -> IrResult<Vec<SampleRecord>> {
+    let predicate = select
+        .predicate
+        .as_ref()
+        .ok_or_else(|| IrError::MissingData("Select::predicate".to_string()))?;
+    let mut results = vec![];
+    for record in records {
+        if eval_predicate(predicate, record)? {
+            results.push(record.clone());
+        }
+    }
+
+    Ok(results)
+}
+
+/// Project the sample records by the mappings of the `Project`, where each projected value is set
+/// as the entry of its alias, or the head if there is no alias.
+pub fn eval_project(project: &pb::Project, records: &[SampleRecord]) -> IrResult<Vec<SampleRecord>> {
+    let mut results = Vec::with_capacity(records.len());
+    for record in records {
+        let mut result = if project.is_append { record.clone() } else { SampleRecord::default() };
+        for mapping in &project.mappings {
+            let expr = mapping
+                .expr
+                .as_ref()
+                .ok_or_else(|| IrError::MissingData("ExprAlias::expr".to_string()))?;
+            let value = eval_expression(expr, record)?;
+            result.set_entry(to_tag(mapping.alias.as_ref())?, SampleEntry::value(value));
+        }
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+/// Group the sample records by the keys of the `GroupBy`, and aggregate each group by its
+/// functions, where a record is produced per group, of which the keys and the aggregated values
+/// are set as the entries of their aliases. The groups are ordered by their keys.
+pub fn eval_group_by(group: &pb::GroupBy, records: &[SampleRecord]) -> IrResult<Vec<SampleRecord>> {
+    let mut groups: BTreeMap<Vec<Object>, Vec<&SampleRecord>> = BTreeMap::new();
+    for record in records {
+        let keys = group
+            .mappings
+            .iter()
+            .map(|mapping| {
+                mapping
+                    .key
+                    .as_ref()
+                    .ok_or_else(|| IrError::MissingData("KeyAlias::key".to_string()))
+                    .and_then(|key| eval_variable(key, record))
+            })
+            .collect::<IrResult<Vec<Object>>>()?;
+        groups.entry(keys).or_default().push(record);
+    }
+    // a group of no key always produces a record, even if there is no record to aggregate
+    if group.mappings.is_empty() && groups.is_empty() {
+        groups.insert(vec![], vec![]);
+    }
+
+    let mut results = Vec::with_capacity(groups.len());
+    for (keys, records) in groups {
+        let mut result = SampleRecord::default();
+        for (mapping, key) in group.mappings.iter().zip(keys.into_iter()) {
+            result.set_entry(to_tag(mapping.alias.as_ref())?, SampleEntry::value(key));
+        }
+        for agg_fn in &group.functions {
+            let values = records
+                .iter()
+                .map(|record| {
+                    if agg_fn.vars.len() == 1 {
+                        eval_variable(&agg_fn.vars[0], record)
+                    } else {
+                        agg_fn
+                            .vars
+                            .iter()
+                            .map(|var| eval_variable(var, record))
+                            .collect::<IrResult<Vec<Object>>>()
+                            .map(Object::Vector)
+                    }
+                })
+                .collect::<IrResult<Vec<Object>>>()?;
+            let value = aggregate(agg_fn.aggregate, values)?;
+            result.set_entry(to_tag(agg_fn.alias.as_ref())?, SampleEntry::value(value));
+        }
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+fn to_tag(tag: Option<&common_pb::NameOrId>) -> IrResult<Option<NameOrId>> {
+    Ok(tag
+        .cloned()
+        .map(NameOrId::try_from)
+        .transpose()?)
+}
+
+fn eval_variable(var: &common_pb::Variable, record: &SampleRecord) -> IrResult<Object> {
+    use common_pb::property::Item;
+
+    let tag = to_tag(var.tag.as_ref())?;
+    let entry = record.get_entry(tag.as_ref()).ok_or_else(|| {
+        IrError::TagNotExist(
+            tag.clone()
+                .unwrap_or(NameOrId::Str("HEAD".to_string())),
+        )
+    })?;
+    match var
+        .property
+        .as_ref()
+        .and_then(|property| property.item.as_ref())
+    {
+        None => Ok(entry.as_object()),
+        Some(Item::Id(_)) => Ok(entry
+            .id
+            .map(Object::from)
+            .unwrap_or(Object::None)),
+        Some(Item::Label(_)) => Ok(match &entry.label {
+            Some(NameOrId::Str(name)) => Object::from(name.as_str()),
+            Some(NameOrId::Id(id)) => Object::from(*id),
+            None => Object::None,
+        }),
+        Some(Item::Len(_)) => Ok(match entry.as_object() {
+            Object::Vector(values) => Object::from(values.len() as u64),
+            Object::String(s) => Object::from(s.len() as u64),
+            Object::None => Object::None,
+            _ => Object::from(1_u64),
+        }),
+        Some(Item::Key(key)) => {
+            let key = NameOrId::try_from(key.clone())?;
+            Ok(entry
+                .properties
+                .get(&key)
+                .cloned()
+                .unwrap_or(Object::None))
+        }
+        Some(Item::All(_)) => Ok(Object::KV(
+            entry
+                .properties
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        NameOrId::Str(name) => Object::from(name.as_str()),
+                        NameOrId::Id(id) => Object::from(*id),
+                    };
+                    (key, value.clone())
+                })
+                .collect(),
+        )),
+        Some(item) => {
+            Err(IrError::Unsupported(format!("evaluating the property {:?} over the sample records", item)))
+        }
+    }
+}
+
+fn pop_operand(stack: &mut Vec<Object>, opr: &str) -> IrResult<Object> {
+    stack
+        .pop()
+        .ok_or_else(|| IrError::EvalError(format!("missing operands of {}", opr)))
+}
+
+fn as_bool(value: &Object) -> IrResult<bool> {
+    match value {
+        Object::None => Ok(false),
+        _ => value
+            .as_bool()
+            .map_err(|e| IrError::EvalError(e.to_string())),
+    }
+}
+
+fn as_str(value: &Object) -> IrResult<String> {
+    value
+        .as_str()
+        .map(|s| s.into_owned())
+        .map_err(|e| IrError::EvalError(e.to_string()))
+}
+
+fn apply_logical(logical: common_pb::Logical, left: &Object, right: &Object) -> IrResult<Object> {
+    use common_pb::Logical::*;
+
+    Ok(match logical {
+        Eq => (left == right).into(),
+        Ne => (left != right).into(),
+        Lt => (left < right).into(),
+        Le => (left <= right).into(),
+        Gt => (left > right).into(),
+        Ge => (left >= right).into(),
+        And => (as_bool(left)? && as_bool(right)?).into(),
+        Or => (as_bool(left)? || as_bool(right)?).into(),
+        Within => right.contains(left).into(),
+        Without => (!right.contains(left)).into(),
+        Startswith => as_str(left)?
+            .starts_with(&as_str(right)?)
+            .into(),
+        Endswith => as_str(left)?.ends_with(&as_str(right)?).into(),
+        Not => (!as_bool(right)?).into(),
+    })
+}
+
+fn apply_arith(arith: common_pb::Arithmetic, left: &Object, right: &Object) -> IrResult<Object> {
+    use common_pb::Arithmetic::*;
+
+    let to_eval_error = |e: dyn_type::CastError| IrError::EvalError(e.to_string());
+    let (a, b) =
+        (left.as_primitive().map_err(to_eval_error)?, right.as_primitive().map_err(to_eval_error)?);
+    Ok(Object::Primitive(match arith {
+        Add => a + b,
+        Sub => a - b,
+        Mul => a * b,
+        Div => a / b,
+        Mod => a % b,
+        Exp => a.exp(b),
+        Bitand => a.bit_and(b),
+        Bitor => a.bit_or(b),
+        Bitxor => a.bit_xor(b),
+        Bitlshift => a.bit_left_shift(b),
+        Bitrshift => a.bit_right_shift(b),
+    }))
+}
+
+fn aggregate(aggregate: i32, values: Vec<Object>) -> IrResult<Object> {
+    use pb::group_by::agg_func::Aggregate;
+
+    let aggregate = Aggregate::from_i32(aggregate)
+        .ok_or_else(|| IrError::EvalError(format!("unknown aggregate function {}", aggregate)))?;
+    let count = values.len();
+    Ok(match aggregate {
+        Aggregate::Count => Object::from(count as u64),
+        Aggregate::CountDistinct => {
+            let mut values = values;
+            values.sort();
+            values.dedup();
+            Object::from(values.len() as u64)
+        }
+        Aggregate::ToList => Object::Vector(values),
+        Aggregate::ToSet => {
+            let mut values = values;
+            values.sort();
+            values.dedup();
+            Object::Vector(values)
+        }
+        Aggregate::Min => values.into_iter().min().unwrap_or(Object::None),
+        Aggregate::Max => values.into_iter().max().unwrap_or(Object::None),
+        Aggregate::Sum | Aggregate::Avg => {
+            let mut sum: Option<Object> = None;
+            for value in values {
+                sum = Some(match sum {
+                    Some(sum) => apply_arith(common_pb::Arithmetic::Add, &sum, &value)?,
+                    None => value,
+                });
+            }
+            match (aggregate, sum) {
+                (Aggregate::Avg, Some(sum)) => {
+                    let sum = sum
+                        .as_f64()
+                        .map_err(|e| IrError::EvalError(e.to_string()))?;
+                    Object::from(sum / count as f64)
+                }
+                (_, Some(sum)) => sum,
+                (_, None) => Object::None,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use ir_common::expr_parse::str_to_expr_pb;
+
+    use super::*;
+
+    fn person(id: i64, name: &str, age: i32) -> SampleEntry {
+        SampleEntry::element(id, Some("person".into()))
+            .with_property("name".into(), name.into())
+            .with_property("age".into(), age.into())
+    }
+
+    fn eval(expr: &str, record: &SampleRecord) -> IrResult<Object> {
+        eval_expression(&str_to_expr_pb(expr.to_string()).unwrap(), record)
+    }
+
+    #[test]
+    fn eval_expression_over_record() {
+        let mut record = SampleRecord::new(person(1, "marko", 29));
+        record.set_entry(Some("b".into()), person(2, "vadas", 27));
+
+        assert_eq!(eval("@.age + 1", &record).unwrap(), Object::from(30));
+        assert_eq!(eval("@b.age * (2 + 1)", &record).unwrap(), Object::from(81));
+        assert_eq!(eval("@.name", &record).unwrap(), Object::from("marko"));
+        assert_eq!(eval("@b.~id", &record).unwrap(), Object::from(2_i64));
+        assert_eq!(eval("@b.~label", &record).unwrap(), Object::from("person"));
+        assert!(eval_predicate(
+            &str_to_expr_pb("@.age > @b.age && @.name == \"marko\"".to_string()).unwrap(),
+            &record
+        )
+        .unwrap());
+        assert!(eval_predicate(&str_to_expr_pb("@b.age within [27, 28]".to_string()).unwrap(), &record)
+            .unwrap());
+        assert!(eval_predicate(&str_to_expr_pb("@.name startswith \"ma\"".to_string()).unwrap(), &record)
+            .unwrap());
+        // an absent property is none, which is taken as false by a predicate
+        assert_eq!(eval("@.email", &record).unwrap(), Object::None);
+        assert!(!eval_predicate(&str_to_expr_pb("@.email".to_string()).unwrap(), &record).unwrap());
+        // an absent tag is an error
+        match eval("@c.age", &record) {
+            Err(IrError::TagNotExist(_)) => {}
+            _ => panic!("the tag c does not exist"),
+        }
+    }
+
+    #[test]
+    fn eval_operators_over_records() {
+        let records = vec![
+            SampleRecord::new(person(1, "marko", 29)),
+            SampleRecord::new(person(2, "vadas", 27)),
+            SampleRecord::new(person(4, "josh", 32)),
+        ];
+        let select = pb::Select { predicate: str_to_expr_pb("@.age > 28".to_string()).ok() };
+        let selected = eval_select(&select, &records).unwrap();
+        assert_eq!(selected, vec![records[0].clone(), records[2].clone()]);
+
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@.age + 1".to_string()).ok(),
+                alias: Some("a".into()),
+            }],
+            is_append: false,
+            meta_data: vec![],
+        };
+        let projected = eval_project(&project, &records).unwrap();
+        assert_eq!(
+            projected[1]
+                .get_entry(Some(&"a".into()))
+                .unwrap()
+                .value,
+            Some(Object::from(28))
+        );
+        assert!(projected[1].get_entry(None).is_none());
+
+        let group = pb::GroupBy {
+            mappings: vec![pb::group_by::KeyAlias {
+                key: Some(common_pb::Variable::from("@.~label".to_string())),
+                alias: Some("k".into()),
+            }],
+            functions: vec![pb::group_by::AggFunc {
+                vars: vec![common_pb::Variable::from("@.age".to_string())],
+                aggregate: 0, // sum
+                alias: Some("s".into()),
+                equality: 0,
+                overflow: 0,
+                approximation: None,
+            }],
+            meta_data: vec![],
+            null_key_opt: 0,
+        };
+        let grouped = eval_group_by(&group, &records).unwrap();
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(
+            grouped[0]
+                .get_entry(Some(&"s".into()))
+                .unwrap()
+                .value,
+            Some(Object::from(88))
+        );
+    }
+}
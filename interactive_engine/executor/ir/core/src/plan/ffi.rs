@@ -32,14 +32,14 @@
 //! # #include<ir_core.h>
 //! # using namespace std;
 //! # int main(int argc, char** argv) {
-//! #    const void* ptr_plan = init_logical_plan();
-//! #    const void* ptr_project = init_project_operator();
+//! #    const FfiLogicalPlan* ptr_plan = init_logical_plan();
+//! #    const FfiProjectOpr* ptr_project = init_project_operator();
 //! #    add_project_expr_alias(ptr_project, "@name", int_as_name_or_id(0));
 //! #    int opr_id = 0;
 //! #    append_project_operator(ptr_plan, ptr_project, 0, &opr_id);
 //! #    cout << "the id is: " << opr_id << endl;
 //!
-//! #    const void* ptr_select = init_select_operator();
+//! #    const FfiSelectOpr* ptr_select = init_select_operator();
 //! #    set_select_predicate(ptr_select, "@age > 20 && @name == \"John\"");
 //! #    append_select_operator(ptr_plan, ptr_select, opr_id, &opr_id);
 //! #    cout << "the id is: " << opr_id << endl;
@@ -50,23 +50,37 @@
 //!
 //! Save the codes as </path/to/c-caller/test.cc>, and build like:
 //! `g++ -o test test.cc -std=c++11 -L. -lir_core`
+//!
+//! The apis of the plan lifecycle have been superseded by the ones in [`v2`], which refer to the
+//! plan via a typed handle. The superseded ones are deprecated, and will be removed in the future.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
-use std::ffi::{c_void, CStr};
+use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
-use ir_common::expr_parse::str_to_expr_pb;
+use ir_common::expr_parse::ast::Expr;
+use ir_common::expr_parse::str_to_expr_pb_with_offset;
 use ir_common::generated::algebra as pb;
 use ir_common::generated::common as common_pb;
-use ir_physical_client::physical_builder::PlanBuilder;
+use ir_common::generated::physical as physical_pb;
 use pegasus::BuildJobError;
 use prost::Message;
 
-use crate::error::IrError;
-use crate::plan::logical::{LogicalPlan, NodeId};
-use crate::plan::meta::{set_schema_from_json, KeyType};
-use crate::plan::physical::AsPhysical;
+use crate::error::{IrError, IrResult};
+use crate::plan::logical::{default_display_name, LogicalPlan, NodeId, PlanWarning};
+use crate::plan::meta::{set_schema_from_json, IdEncoding, KeyType};
+use crate::plan::physical::{self, PlanParams};
+
+pub(crate) mod handle;
+pub mod v2;
 
+/// The code of the result of an api, which is mirrored by the `ResultCode` of the compiler (in
+/// `common/jna/type/ResultCode.java`) by the order of the codes, and thus a new code must be
+/// appended to both.
 #[repr(i32)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ResultCode {
@@ -97,6 +111,125 @@ pub enum ResultCode {
     TagNotExistError = 12,
     UnSupported = 13,
     Others = 14,
+    /// Submit a job to the engine, or fetch its results error
+    SubmitJobError = 15,
+    /// A transient failure persists after exhausting the retries, which may succeed if retried later
+    RetriableError = 16,
+    /// A node of the given id cannot be found in the plan
+    NodeNotExistError = 17,
+    /// A pointer given to the apis, e.g., a C string, a handle or an output, is null
+    NullPointerError = 18,
+    /// A C string is not a valid UTF-8 string
+    Utf8Error = 19,
+    /// The estimated complexity of a plan exceeds the threshold
+    ComplexityExceededError = 20,
+    /// Evaluate an expression over the sample records error
+    EvalError = 21,
+    /// A handle is invalid, e.g., it has been destroyed or consumed, or is of another type
+    InvalidHandleError = 22,
+    /// An api panics, which is a bug rather than a misuse of the api, and the objects that the api
+    /// was modifying, e.g., the logical plan, may be left inconsistent
+    InternalError = 23,
+    /// The hop range of a path expansion exceeds the maximal number of hops of the plan
+    HopRangeExceededError = 24,
+    /// A frozen plan is modified, see `freeze_plan()`
+    PlanFrozenError = 25,
+    /// The structure of a plan is invalid, e.g., there is a cycle, see `validate_plan()`
+    InvalidPlanError = 26,
+    /// A plan reads a label or a property out of its read scope, see `allow_plan_read_label()`
+    AccessDeniedError = 27,
+}
+
+/// The category of an error, which tells the callers what to blame for the error, e.g., a
+/// malformed expression versus a plan referring to a missing parent, regardless of its code.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FfiErrorCategory {
+    /// There is no error
+    None = 0,
+    /// An expression, or the bytes of a protobuf, is malformed
+    Parse = 1,
+    /// A label (table) or a property (column) does not exist in the schema
+    Schema = 2,
+    /// The plan is invalid, e.g., an operator refers to a missing parent or tag
+    Plan = 3,
+    /// The apis are misused, e.g., given a null pointer or an invalid handle
+    Api = 4,
+    /// The engine fails to run a job, or to fetch its results
+    Engine = 5,
+    /// A bug of the library
+    Internal = 6,
+}
+
+impl From<ResultCode> for FfiErrorCategory {
+    fn from(code: ResultCode) -> Self {
+        match code {
+            ResultCode::Success => FfiErrorCategory::None,
+            ResultCode::ParseExprError | ResultCode::ParsePbError | ResultCode::UnknownTypeError => {
+                FfiErrorCategory::Parse
+            }
+            ResultCode::ColumnNotExistError | ResultCode::TableNotExistError => FfiErrorCategory::Schema,
+            ResultCode::MissingDataError
+            | ResultCode::InvalidRangeError
+            | ResultCode::NegativeIndexError
+            | ResultCode::BuildJobError
+            | ResultCode::ParentNotFoundError
+            | ResultCode::TagNotExistError
+            | ResultCode::UnSupported
+            | ResultCode::Others
+            | ResultCode::NodeNotExistError
+            | ResultCode::ComplexityExceededError
+            | ResultCode::EvalError
+            | ResultCode::HopRangeExceededError
+            | ResultCode::InvalidPlanError
+            | ResultCode::AccessDeniedError => FfiErrorCategory::Plan,
+            ResultCode::CStringError
+            | ResultCode::NullPointerError
+            | ResultCode::Utf8Error
+            | ResultCode::InvalidHandleError
+            | ResultCode::PlanFrozenError => FfiErrorCategory::Api,
+            ResultCode::SubmitJobError | ResultCode::RetriableError => FfiErrorCategory::Engine,
+            ResultCode::InternalError => FfiErrorCategory::Internal,
+        }
+    }
+}
+
+/// The details of the last error returned by the apis on the current thread, such that the
+/// callers can tell the users where the error is, see [`get_last_error_detail`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FfiErrorDetail {
+    pub code: ResultCode,
+    pub category: FfiErrorCategory,
+    /// The id of the node that the error concerns, i.e., the missing node for `NodeNotExistError`
+    /// and `ParentNotFoundError`, or the id that the operator failed to be appended would have been
+    /// given, or -1 if the error concerns no node
+    pub node_id: i32,
+    /// The byte offset of the token that fails in an expression given as a string, or -1 if the
+    /// error is not of parsing such an expression
+    pub offset: i32,
+}
+
+impl Default for FfiErrorDetail {
+    fn default() -> Self {
+        FfiErrorDetail {
+            code: ResultCode::Success,
+            category: FfiErrorCategory::None,
+            node_id: -1,
+            offset: -1,
+        }
+    }
+}
+
+impl FromPanic for FfiErrorDetail {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        FfiErrorDetail {
+            code: ResultCode::InternalError,
+            category: FfiErrorCategory::Internal,
+            ..Default::default()
+        }
+    }
 }
 
 #[repr(C)]
@@ -107,7 +240,12 @@ pub struct FfiResult {
 }
 
 impl FfiResult {
+    /// Create a result of the given code and message, which, unless it is a success, is also kept
+    /// as the last error of the current thread (see [`get_last_error_msg`]).
     pub fn new(code: ResultCode, msg: String) -> Self {
+        if code != ResultCode::Success {
+            set_last_error(code, &msg);
+        }
         let result = string_to_cstr(msg);
         match result {
             Ok(msg) => Self { code, msg },
@@ -118,13 +256,263 @@ impl FfiResult {
     pub fn success() -> Self {
         Self { code: ResultCode::Success, msg: std::ptr::null::<c_char>() }
     }
+
+    /// Prefix the message of an error with the context in which it is raised, e.g., the operator
+    /// being appended, which leaves a success as it is.
+    pub(crate) fn with_context(self, context: &str) -> Self {
+        if self.code == ResultCode::Success {
+            return self;
+        }
+        let msg = msg_to_string(self.msg);
+        if !self.msg.is_null() {
+            let _ = unsafe { std::ffi::CString::from_raw(self.msg as *mut c_char) };
+        }
+        // the location of the error, if any, is kept, as it is still the same error
+        let location = LAST_ERROR.with(|last_error| {
+            last_error
+                .borrow()
+                .as_ref()
+                .map(|error| (error.node_id, error.offset))
+        });
+        let result = FfiResult::new(self.code, format!("{}: {}", context, msg));
+        if let Some((node_id, offset)) = location {
+            update_last_error(|error| {
+                error.node_id = node_id;
+                error.offset = offset;
+            });
+        }
+        result
+    }
+
+    /// Free the message of an error that is not returned to the caller, e.g., by an api returning a
+    /// plain value, while the error is still kept as the last error.
+    pub(crate) fn discard(self) {
+        if !self.msg.is_null() {
+            let _ = unsafe { CString::from_raw(self.msg as *mut c_char) };
+        }
+    }
+
+    /// Record the node that an error concerns in the last error, unless a node has been recorded,
+    /// e.g., the missing parent of the operator being appended, which leaves a success as it is.
+    pub(crate) fn at_node(self, node_id: NodeId) -> Self {
+        if self.code != ResultCode::Success {
+            update_last_error(|error| {
+                error.node_id.get_or_insert(node_id);
+            });
+        }
+        self
+    }
+
+    /// Record the byte offset of the token that fails in an expression in the last error, which
+    /// leaves a success as it is.
+    pub(crate) fn at_offset(self, offset: usize) -> Self {
+        if self.code != ResultCode::Success {
+            update_last_error(|error| error.offset = Some(offset));
+        }
+        self
+    }
+}
+
+/// An error returned by the apis, together with where it is, if known.
+struct LastError {
+    code: ResultCode,
+    msg: CString,
+    node_id: Option<NodeId>,
+    offset: Option<usize>,
+}
+
+thread_local! {
+    /// The last error returned by the apis on the current thread.
+    static LAST_ERROR: RefCell<Option<LastError>> = RefCell::new(None);
+}
+
+pub(crate) fn set_last_error(code: ResultCode, msg: &str) {
+    // the interior nul bytes, if any, would otherwise truncate the message
+    let msg = CString::new(msg.replace('\0', "\\0")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| {
+        *last_error.borrow_mut() = Some(LastError { code, msg, node_id: None, offset: None })
+    });
+}
+
+fn update_last_error<F: FnOnce(&mut LastError)>(f: F) {
+    LAST_ERROR.with(|last_error| {
+        if let Some(error) = last_error.borrow_mut().as_mut() {
+            f(error)
+        }
+    });
+}
+
+/// Get the message of the last error returned by the apis on the current thread, e.g., why an
+/// expression fails to be parsed, or an operator fails to be appended, or null if there is none.
+/// The message is owned by Rust, and remains valid until the next error on the same thread, or
+/// [`clear_last_error`], and thus must **not** be released by the caller.
+#[no_mangle]
+pub extern "C" fn get_last_error_msg() -> *const c_char {
+    catch_panic(|| {
+        LAST_ERROR.with(|last_error| {
+            last_error
+                .borrow()
+                .as_ref()
+                .map(|error| error.msg.as_ptr())
+                .unwrap_or_else(std::ptr::null)
+        })
+    })
+}
+
+/// Get the code of the last error returned by the apis on the current thread, or `Success` if
+/// there is none.
+#[no_mangle]
+pub extern "C" fn get_last_error_code() -> ResultCode {
+    catch_panic(|| {
+        LAST_ERROR.with(|last_error| {
+            last_error
+                .borrow()
+                .as_ref()
+                .map(|error| error.code)
+                .unwrap_or(ResultCode::Success)
+        })
+    })
+}
+
+/// Get the details of the last error returned by the apis on the current thread, e.g., its
+/// category, and the node or the offset in an expression where it is, or the details of a
+/// `Success` if there is none.
+#[no_mangle]
+pub extern "C" fn get_last_error_detail() -> FfiErrorDetail {
+    catch_panic(|| {
+        LAST_ERROR.with(|last_error| {
+            last_error
+                .borrow()
+                .as_ref()
+                .map(|error| FfiErrorDetail {
+                    code: error.code,
+                    category: error.code.into(),
+                    node_id: error.node_id.map(|id| id as i32).unwrap_or(-1),
+                    offset: error
+                        .offset
+                        .map(|offset| offset as i32)
+                        .unwrap_or(-1),
+                })
+                .unwrap_or_default()
+        })
+    })
+}
+
+/// Clear the last error of the current thread.
+#[no_mangle]
+pub extern "C" fn clear_last_error() {
+    catch_panic(|| {
+        LAST_ERROR.with(|last_error| *last_error.borrow_mut() = None);
+    })
+}
+
+/// The major version of the abi of the apis, which is bumped once the existing apis change
+/// incompatibly, e.g., the layout of a `#[repr(C)]` struct, or the signature of an api, changes.
+pub const IR_CORE_ABI_MAJOR: u32 = 2;
+/// The minor version of the abi of the apis, which is bumped once the apis are extended compatibly,
+/// e.g., by a new api or a new `ResultCode`, and is reset once the major version is bumped.
+pub const IR_CORE_ABI_MINOR: u32 = 3;
+
+/// Get the version of the library, e.g., "0.1.0", as a c-string that is owned by Rust, and
+/// thus must **not** be released by the caller.
+#[no_mangle]
+pub extern "C" fn ir_core_version() -> *const c_char {
+    catch_panic(|| concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char)
+}
+
+/// Whether the library is compatible with the abi of the given version, which is expected to be
+/// the `IR_CORE_ABI_MAJOR` and `IR_CORE_ABI_MINOR` of the header that the caller is built against,
+/// such that the caller can check it once the library is loaded, instead of mis-parsing the
+/// structs whose layout has changed. It is compatible if the major versions are the same, and the
+/// given minor version is not newer than that of the library, i.e., the apis of the header are all
+/// provided by the library.
+#[no_mangle]
+pub extern "C" fn ir_core_abi_compatible(major: u32, minor: u32) -> bool {
+    catch_panic(|| major == IR_CORE_ABI_MAJOR && minor <= IR_CORE_ABI_MINOR)
+}
+
+/// The level of the logs of the library, where the logs of a level are emitted together with those
+/// of the more severe levels, e.g., `Warn` also emits the errors.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FfiLogLevel {
+    Off = 0,
+    Error = 1,
+    /// The fallbacks while building the plans, e.g., a column that is absent from the schema
+    Warn = 2,
+    Info = 3,
+    /// The conversions while building the plans, e.g., from the names of the columns to their ids
+    Debug = 4,
+    Trace = 5,
+}
+
+impl From<FfiLogLevel> for log::LevelFilter {
+    fn from(level: FfiLogLevel) -> Self {
+        match level {
+            FfiLogLevel::Off => log::LevelFilter::Off,
+            FfiLogLevel::Error => log::LevelFilter::Error,
+            FfiLogLevel::Warn => log::LevelFilter::Warn,
+            FfiLogLevel::Info => log::LevelFilter::Info,
+            FfiLogLevel::Debug => log::LevelFilter::Debug,
+            FfiLogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// Initialize the logger of the library of the given level, which appends the logs to the file of
+/// `file_path`, or writes them to the stderr if the path is null, such that the warnings while
+/// building the plans can be captured by the caller rather than lost.
+///
+/// The logger can only be installed once per process. Once it is installed, either by this api or
+/// by another library of the process, the subsequent calls only change the level, and fail with
+/// `Others` if a file is given, as the logs cannot be redirected to it.
+#[no_mangle]
+pub extern "C" fn init_ir_logger(level: FfiLogLevel, file_path: *const c_char) -> FfiResult {
+    catch_panic(|| {
+        let mut builder = env_logger::Builder::new();
+        // The level is controlled by `log::set_max_level()`, such that it can be changed afterwards
+        builder
+            .filter_level(log::LevelFilter::Trace)
+            .format_timestamp_millis();
+        let with_file = !file_path.is_null();
+        if with_file {
+            let path = match cstr_to_string(file_path) {
+                Ok(path) => path,
+                Err(e) => return e,
+            };
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path);
+            match file {
+                Ok(file) => {
+                    builder.target(env_logger::Target::Pipe(Box::new(file)));
+                }
+                Err(e) => {
+                    return FfiResult::new(
+                        ResultCode::Others,
+                        format!("open the log file {:?} error: {}", path, e),
+                    )
+                }
+            }
+        }
+        let result = builder.try_init();
+        log::set_max_level(level.into());
+        match result {
+            Err(_) if with_file => FfiResult::new(
+                ResultCode::Others,
+                "the logger has been installed, where only its level is changed".to_string(),
+            ),
+            _ => FfiResult::success(),
+        }
+    })
 }
 
 impl std::fmt::Display for FfiResult {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("FfiResult")
             .field("code", &self.code)
-            .field("msg", &cstr_to_string(self.msg))
+            .field("msg", &msg_to_string(self.msg))
             .finish()
     }
 }
@@ -143,7 +531,13 @@ impl From<IrError> for FfiResult {
             IrError::ParentNodeNotExist(p) => FfiResult::new(
                 ResultCode::ParentNotFoundError,
                 format!("parent node {:?} does not exist", p),
-            ),
+            )
+            .at_node(p),
+            IrError::NodeNotExist(n) => {
+                FfiResult::new(ResultCode::NodeNotExistError, format!("node {:?} does not exist", n))
+                    .at_node(n)
+            }
+            IrError::NodeError(node, err) => FfiResult::from(*err).with_context(&node),
             IrError::TagNotExist(t) => FfiResult::new(
                 ResultCode::TagNotExistError,
                 format!("the queried tag {:?} is not specified", t),
@@ -152,6 +546,24 @@ impl From<IrError> for FfiResult {
             IrError::ParseExprError(err) => FfiResult::new(ResultCode::ParseExprError, err.to_string()),
             IrError::InvalidPattern(s) => FfiResult::new(ResultCode::Others, s),
             IrError::InvalidExtendPattern(err) => FfiResult::new(ResultCode::Others, err.to_string()),
+            IrError::InvalidBundle(s) => FfiResult::new(ResultCode::Others, s),
+            IrError::UnalignedUnion(s) => FfiResult::new(ResultCode::Others, s),
+            IrError::ComplexityExceeded(score, threshold) => FfiResult::new(
+                ResultCode::ComplexityExceededError,
+                format!("the estimated complexity {:.2} exceeds the threshold {:.2}", score, threshold),
+            ),
+            err @ IrError::HopRangeExceeded(..) => {
+                FfiResult::new(ResultCode::HopRangeExceededError, err.to_string())
+            }
+            err @ IrError::PlanFrozen => FfiResult::new(ResultCode::PlanFrozenError, err.to_string()),
+            err @ IrError::AccessDenied(_) => {
+                FfiResult::new(ResultCode::AccessDeniedError, err.to_string())
+            }
+            IrError::InvalidNode(node, reason) => FfiResult::new(
+                ResultCode::InvalidPlanError,
+                format!("node {:?} is invalid: {}", node, reason),
+            )
+            .at_node(node),
             IrError::PbEncodeError(err) => FfiResult::new(ResultCode::ParsePbError, err.to_string()),
             IrError::PbDecodeError(err) => FfiResult::new(ResultCode::ParsePbError, err.to_string()),
             IrError::MissingData(d) => {
@@ -161,6 +573,13 @@ impl From<IrError> for FfiResult {
                 ResultCode::InvalidRangeError,
                 format!("the range ({:?}, {:?}) is invalid", l, u),
             ),
+            IrError::InvalidGlobalId(id) => FfiResult::new(
+                ResultCode::InvalidRangeError,
+                format!("the global id {:?} is not of the given label(s)", id),
+            ),
+            IrError::EvalError(err) => FfiResult::new(ResultCode::EvalError, err),
+            IrError::SubmitJobError(err) => FfiResult::new(ResultCode::SubmitJobError, err),
+            IrError::RetriableError(err) => FfiResult::new(ResultCode::RetriableError, err),
             IrError::Unsupported(err) => FfiResult::new(ResultCode::UnSupported, err.to_string()),
         }
     }
@@ -194,25 +613,78 @@ impl From<BuildJobError> for FfiData {
     }
 }
 
+impl From<Vec<u8>> for FfiData {
+    fn from(bytes: Vec<u8>) -> Self {
+        let mut bytes = bytes.into_boxed_slice();
+        let data = FfiData {
+            ptr: bytes.as_mut_ptr() as *mut c_void,
+            len: bytes.len(),
+            error: FfiResult::success(),
+        };
+        std::mem::forget(bytes);
+
+        data
+    }
+}
+
 impl From<FfiResult> for FfiData {
     fn from(error: FfiResult) -> Self {
         FfiData { ptr: std::ptr::null_mut(), len: 0, error }
     }
 }
 
+/// Whether the C strings are converted in the strict mode, see [`set_cstr_strict_mode`].
+static CSTR_STRICT_MODE: AtomicBool = AtomicBool::new(false);
+/// Whether the C strings are converted in the lossy mode, see [`set_cstr_lossy_mode`].
+static CSTR_LOSSY_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Opt in (or out) the strict mode of converting the C strings given to the apis, in which a null
+/// pointer fails with `NullPointerError`, rather than being taken as an empty string as by default.
+#[no_mangle]
+pub extern "C" fn set_cstr_strict_mode(strict: bool) {
+    catch_panic(|| {
+        CSTR_STRICT_MODE.store(strict, Ordering::Relaxed);
+    })
+}
+
+/// Opt in (or out) the lossy mode of converting the C strings given to the apis, in which the
+/// invalid UTF-8 sequences are replaced by `U+FFFD`, instead of failing with `Utf8Error`.
+#[no_mangle]
+pub extern "C" fn set_cstr_lossy_mode(lossy: bool) {
+    catch_panic(|| {
+        CSTR_LOSSY_MODE.store(lossy, Ordering::Relaxed);
+    })
+}
+
+/// Convert a C string given to the apis into a Rust string, which is the only way of doing so,
+/// such that every string parameter follows the same policy regarding null pointers and
+/// invalid UTF-8.
 pub(crate) fn cstr_to_string(cstr: *const c_char) -> Result<String, FfiResult> {
-    if !cstr.is_null() {
-        let str_result = unsafe { CStr::from_ptr(cstr) }.to_str();
-        if let Ok(str) = str_result {
-            Ok(str.to_string())
+    let lossy = CSTR_LOSSY_MODE.load(Ordering::Relaxed);
+    if cstr.is_null() {
+        if CSTR_STRICT_MODE.load(Ordering::Relaxed) {
+            Err(FfiResult::new(ResultCode::NullPointerError, "the C string is a null pointer".to_string()))
         } else {
-            Err(FfiResult::new(
-                ResultCode::CStringError,
-                "error parsing C string into Rust string".to_string(),
-            ))
+            Ok("".to_string())
+        }
+    } else {
+        let cstr = unsafe { CStr::from_ptr(cstr) };
+        match cstr.to_str() {
+            Ok(str) => Ok(str.to_string()),
+            Err(_) if lossy => Ok(cstr.to_string_lossy().into_owned()),
+            Err(e) => Err(FfiResult::new(ResultCode::Utf8Error, format!("invalid C string: {}", e))),
         }
+    }
+}
+
+/// Read the message of a `FfiResult`, which is null for a success.
+fn msg_to_string(msg: *const c_char) -> String {
+    if msg.is_null() {
+        "".to_string()
     } else {
-        Ok("".to_string())
+        unsafe { CStr::from_ptr(msg) }
+            .to_string_lossy()
+            .into_owned()
     }
 }
 
@@ -233,7 +705,11 @@ pub(crate) fn cstr_to_expr_pb(cstr: *const c_char) -> Result<common_pb::Expressi
     let str = cstr_to_string(cstr);
     match str {
         Ok(s) => {
-            let expr = str_to_expr_pb(s).map_err(|err| IrError::from(err))?;
+            let expr = str_to_expr_pb_with_offset(s.clone()).map_err(|(err, offset)| {
+                FfiResult::from(IrError::from(err))
+                    .with_context(&format!("expression {:?}", s))
+                    .at_offset(offset)
+            })?;
             Ok(expr)
         }
         Err(e) => Err(e),
@@ -256,9 +732,9 @@ impl Default for FfiNameIdOpt {
 
 #[repr(C)]
 pub struct FfiNameOrId {
-    opt: FfiNameIdOpt,
-    name: *const c_char,
-    name_id: i32,
+    pub(crate) opt: FfiNameIdOpt,
+    pub(crate) name: *const c_char,
+    pub(crate) name_id: i32,
 }
 
 impl Default for FfiNameOrId {
@@ -383,11 +859,127 @@ impl TryFrom<FfiAlias> for Option<common_pb::NameOrId> {
     }
 }
 
-fn destroy_ptr<M>(ptr: *const c_void) {
+/// Release the object of the handle, where a handle that is null, or is no longer valid, e.g.,
+/// destroyed twice, is ignored, while the error is still kept as the last error.
+pub(crate) fn destroy_handle<M: 'static>(ptr: *const impl Sized) {
     if !ptr.is_null() {
-        unsafe {
-            let _ = Box::from_raw(ptr as *mut M);
+        let _ = handle::release::<M>(ptr);
+    }
+}
+
+/// The value returned by an api that panics, as the panic must not unwind across the ffi boundary.
+/// The message of the panic is kept as the last error of the thread (see [`get_last_error_msg`]),
+/// of the code `InternalError`.
+pub(crate) trait FromPanic {
+    fn from_panic(msg: String) -> Self;
+}
+
+impl FromPanic for FfiResult {
+    fn from_panic(msg: String) -> Self {
+        FfiResult::new(ResultCode::InternalError, msg)
+    }
+}
+
+impl FromPanic for FfiData {
+    fn from_panic(msg: String) -> Self {
+        FfiResult::from_panic(msg).into()
+    }
+}
+
+impl FromPanic for FfiConst {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        FfiConst::default()
+    }
+}
+
+impl FromPanic for ResultCode {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        ResultCode::InternalError
+    }
+}
+
+impl<T> FromPanic for *const T {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        std::ptr::null()
+    }
+}
+
+impl<T> FromPanic for *mut T {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        std::ptr::null_mut()
+    }
+}
+
+impl FromPanic for () {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+    }
+}
+
+impl FromPanic for bool {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        false
+    }
+}
+
+impl FromPanic for i32 {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        0
+    }
+}
+
+impl FromPanic for u64 {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        0
+    }
+}
+
+/// Run the body of an api, in which a panic is caught and turned into the returned value of the
+/// api (see [`FromPanic`]), rather than unwinding across the ffi boundary, which is undefined.
+pub(crate) fn catch_panic<T: FromPanic, F: FnOnce() -> T>(f: F) -> T {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(t) => t,
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|msg| msg.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            T::from_panic(format!("the api panicked: {}", msg))
+        }
+    }
+}
+
+/// Check that the pointer given to an api, e.g., an output pointer, is not null, which is named as
+/// `name` in the error.
+pub(crate) fn check_not_null<T>(ptr: *const T, name: &str) -> Result<(), FfiResult> {
+    if ptr.is_null() {
+        Err(FfiResult::new(ResultCode::NullPointerError, format!("the {} is a null pointer", name)))
+    } else {
+        Ok(())
+    }
+}
+
+/// Write the result to the output pointer if succeed, otherwise return the error. A null output
+/// pointer is reported as `NullPointerError`, which the apis producing a new object check ahead,
+/// such that the object is not leaked.
+pub(crate) fn set_output<T>(out: *mut T, result: Result<T, FfiResult>) -> FfiResult {
+    match result {
+        Ok(t) => {
+            if let Err(e) = check_not_null(out, "output") {
+                return e;
+            }
+            unsafe { *out = t };
+            FfiResult::success()
         }
+        Err(e) => e,
     }
 }
 
@@ -409,13 +1001,13 @@ pub enum FfiDataType {
 #[derive(Clone)]
 #[repr(C)]
 pub struct FfiConst {
-    data_type: FfiDataType,
-    boolean: bool,
-    int32: i32,
-    int64: i64,
-    float64: f64,
-    cstr: *const c_char,
-    raw: *const c_void,
+    pub(crate) data_type: FfiDataType,
+    pub(crate) boolean: bool,
+    pub(crate) int32: i32,
+    pub(crate) int64: i64,
+    pub(crate) float64: f64,
+    pub(crate) cstr: *const c_char,
+    pub(crate) raw: *const c_void,
 }
 
 impl Default for FfiConst {
@@ -459,56 +1051,68 @@ impl TryFrom<FfiConst> for common_pb::Value {
 
 #[no_mangle]
 pub extern "C" fn boolean_as_const(boolean: bool) -> FfiConst {
-    let mut ffi = FfiConst::default();
-    ffi.data_type = FfiDataType::Boolean;
-    ffi.boolean = boolean;
-    ffi
+    catch_panic(|| {
+        let mut ffi = FfiConst::default();
+        ffi.data_type = FfiDataType::Boolean;
+        ffi.boolean = boolean;
+        ffi
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn int32_as_const(int32: i32) -> FfiConst {
-    let mut ffi = FfiConst::default();
-    ffi.data_type = FfiDataType::I32;
-    ffi.int32 = int32;
-    ffi
+    catch_panic(|| {
+        let mut ffi = FfiConst::default();
+        ffi.data_type = FfiDataType::I32;
+        ffi.int32 = int32;
+        ffi
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn int64_as_const(int64: i64) -> FfiConst {
-    let mut ffi = FfiConst::default();
-    ffi.data_type = FfiDataType::I64;
-    ffi.int64 = int64;
-    ffi
+    catch_panic(|| {
+        let mut ffi = FfiConst::default();
+        ffi.data_type = FfiDataType::I64;
+        ffi.int64 = int64;
+        ffi
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn f64_as_const(float64: f64) -> FfiConst {
-    let mut ffi = FfiConst::default();
-    ffi.data_type = FfiDataType::F64;
-    ffi.float64 = float64;
-    ffi
+    catch_panic(|| {
+        let mut ffi = FfiConst::default();
+        ffi.data_type = FfiDataType::F64;
+        ffi.float64 = float64;
+        ffi
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn cstr_as_const(cstr: *const c_char) -> FfiConst {
-    let mut ffi = FfiConst::default();
-    ffi.data_type = FfiDataType::Str;
-    ffi.cstr = cstr;
-    ffi
+    catch_panic(|| {
+        let mut ffi = FfiConst::default();
+        ffi.data_type = FfiDataType::Str;
+        ffi.cstr = cstr;
+        ffi
+    })
 }
 
 /// Set schema via a json-formatted cstring.
 #[no_mangle]
 pub extern "C" fn set_schema(cstr_json: *const c_char) -> FfiResult {
-    let result = cstr_to_string(cstr_json);
-    match result {
-        Ok(json) => {
-            set_schema_from_json(json.as_bytes());
+    catch_panic(|| {
+        let result = cstr_to_string(cstr_json);
+        match result {
+            Ok(json) => {
+                set_schema_from_json(json.as_bytes());
 
-            FfiResult::success()
+                FfiResult::success()
+            }
+            Err(e) => e,
         }
-        Err(e) => e,
-    }
+    })
 }
 
 #[repr(i32)]
@@ -532,46 +1136,96 @@ impl From<FfiKeyType> for KeyType {
 /// Query prop_name by given prop_id
 #[no_mangle]
 pub extern "C" fn get_key_name(key_id: i32, key_type: FfiKeyType) -> FfiResult {
-    use super::meta::STORE_META;
-    if let Ok(meta) = STORE_META.read() {
-        if let Some(schema) = &meta.schema {
-            let key_name = match key_type {
-                FfiKeyType::Entity => schema
-                    .get_entity_name(key_id)
-                    .ok_or(FfiResult::new(
-                        ResultCode::TableNotExistError,
-                        format!("entity label_id {:?} is not found", key_id),
-                    )),
-                FfiKeyType::Relation => schema
-                    .get_relation_name(key_id)
-                    .ok_or(FfiResult::new(
-                        ResultCode::TableNotExistError,
-                        format!("relation label_id {:?} is not found", key_id),
-                    )),
-                FfiKeyType::Column => schema
-                    .get_column_name(key_id)
-                    .ok_or(FfiResult::new(
-                        ResultCode::ColumnNotExistError,
-                        format!("prop_id {:?} is not found", key_id),
-                    )),
-            };
-
-            match key_name {
-                Ok(key_name) => {
-                    let key_name_cstr = string_to_cstr(key_name.clone());
-                    match key_name_cstr {
-                        Ok(msg) => FfiResult { code: ResultCode::Success, msg },
-                        Err(e) => e,
+    catch_panic(|| {
+        use super::meta::STORE_META;
+        if let Ok(meta) = STORE_META.read() {
+            if let Some(schema) = &meta.schema {
+                let key_name = match key_type {
+                    FfiKeyType::Entity => schema
+                        .get_entity_name(key_id)
+                        .ok_or(FfiResult::new(
+                            ResultCode::TableNotExistError,
+                            format!("entity label_id {:?} is not found", key_id),
+                        )),
+                    FfiKeyType::Relation => schema
+                        .get_relation_name(key_id)
+                        .ok_or(FfiResult::new(
+                            ResultCode::TableNotExistError,
+                            format!("relation label_id {:?} is not found", key_id),
+                        )),
+                    FfiKeyType::Column => schema
+                        .get_column_name(key_id)
+                        .ok_or(FfiResult::new(
+                            ResultCode::ColumnNotExistError,
+                            format!("prop_id {:?} is not found", key_id),
+                        )),
+                };
+
+                match key_name {
+                    Ok(key_name) => {
+                        let key_name_cstr = string_to_cstr(key_name.clone());
+                        match key_name_cstr {
+                            Ok(msg) => FfiResult { code: ResultCode::Success, msg },
+                            Err(e) => e,
+                        }
                     }
+                    Err(e) => e,
                 }
-                Err(e) => e,
+            } else {
+                FfiResult::new(ResultCode::Others, "error getting schema from store meta".to_string())
             }
         } else {
-            FfiResult::new(ResultCode::Others, "error getting schema from store meta".to_string())
+            FfiResult::new(ResultCode::Others, "error reading store meta".to_string())
         }
-    } else {
-        FfiResult::new(ResultCode::Others, "error reading store meta".to_string())
-    }
+    })
+}
+
+/// The encoding of the global ids given by the schema of the store, or the one of the experimental
+/// store (the label takes the highest 8 bits) if not given.
+fn store_id_encoding() -> IdEncoding {
+    use super::meta::STORE_META;
+    STORE_META
+        .read()
+        .ok()
+        .and_then(|meta| {
+            meta.schema
+                .as_ref()
+                .and_then(|schema| schema.get_id_encoding().cloned())
+        })
+        .unwrap_or_default()
+}
+
+/// Encode the global id of the vertex of the `label` and the `inner_id` (the offset of the vertex in
+/// its label), as the id encoding of the store, such that the callers need not hardcode the layout.
+#[no_mangle]
+pub extern "C" fn encode_global_id(label: i32, inner_id: i64, global_id: *mut i64) -> FfiResult {
+    catch_panic(|| {
+        let id_encoding = store_id_encoding();
+        let result = id_encoding
+            .encode(label, inner_id)
+            .ok_or_else(|| {
+                FfiResult::new(
+                    ResultCode::InvalidRangeError,
+                    format!(
+                        "the label {:?} or the inner id {:?} exceeds the {:?} bit(s) of the label",
+                        label,
+                        inner_id,
+                        id_encoding.label_bits()
+                    ),
+                )
+            });
+        set_output(global_id, result)
+    })
+}
+
+/// Decode the global id of a vertex into its `label` and `inner_id`, as the id encoding of the store.
+#[no_mangle]
+pub extern "C" fn decode_global_id(global_id: i64, label: *mut i32, inner_id: *mut i64) -> FfiResult {
+    catch_panic(|| {
+        let (label_id, offset) = store_id_encoding().decode(global_id);
+        set_output(label, Ok(label_id));
+        set_output(inner_id, Ok(offset))
+    })
 }
 
 /// an FfiPbPointer that point to a pb structure
@@ -581,122 +1235,1772 @@ pub struct FfiPbPointer {
     len: i64,
 }
 
-fn ptr_to_pb<T: Message + Default>(pb_ptr: FfiPbPointer) -> Result<T, FfiResult> {
-    let buf = unsafe { std::slice::from_raw_parts(pb_ptr.ptr, pb_ptr.len as usize) };
+impl FfiPbPointer {
+    /// The bytes pointed to, where a null pointer is taken as empty bytes only if `len` is 0.
+    pub(crate) fn as_bytes(&self) -> Result<&[u8], FfiResult> {
+        if self.len < 0 {
+            Err(FfiResult::new(
+                ResultCode::InvalidRangeError,
+                format!("invalid length {:?} of the pb pointer", self.len),
+            ))
+        } else if self.ptr.is_null() {
+            if self.len == 0 {
+                Ok(&[])
+            } else {
+                Err(FfiResult::new(
+                    ResultCode::NullPointerError,
+                    format!("the pb pointer of {:?} bytes is a null pointer", self.len),
+                ))
+            }
+        } else {
+            Ok(unsafe { std::slice::from_raw_parts(self.ptr, self.len as usize) })
+        }
+    }
+}
+
+pub(crate) fn ptr_to_pb<T: Message + Default>(pb_ptr: FfiPbPointer) -> Result<T, FfiResult> {
+    let buf = pb_ptr.as_bytes()?;
     Ok(T::decode(buf).map_err(|e| IrError::PbDecodeError(e))?)
 }
 
-/// Initialize a logical plan, which expose a pointer for c-like program to access the
-/// entry of the logical plan. This pointer, however, is owned by Rust, and the caller
-/// **must not** process any operation, which includes but not limited to deallocate it.
-/// We have provided  the [`destroy_logical_plan`] api for deallocating the pointer of the logical plan.
-#[no_mangle]
-pub extern "C" fn init_logical_plan() -> *const c_void {
-    let plan = Box::new(LogicalPlan::default());
-    Box::into_raw(plan) as *const c_void
+// The handles given to the callers are typed by the following opaque types, e.g., `FfiProjectOpr*`
+// for a project operator, rather than all being `void*`, such that the compiler of the caller catches
+// the misuses like appending a select operator via `append_project_operator()`. The handles remain
+// the ids in the registry of the handles rather than addresses, which must never be dereferenced.
+
+/// The opaque handle of a logical plan, which is owned by Rust, and must be released
+/// via [`v2::destroy_logical_plan_v2`].
+#[repr(C)]
+pub struct FfiLogicalPlan {
+    _private: [u8; 0],
 }
 
-/// To destroy a logical plan.
-#[no_mangle]
-pub extern "C" fn destroy_logical_plan(ptr_plan: *const c_void) {
-    destroy_ptr::<LogicalPlan>(ptr_plan)
+/// The opaque handle of the values of the parameters of a plan (see [`init_plan_params`]).
+#[repr(C)]
+pub struct FfiPlanParams {
+    _private: [u8; 0],
 }
 
-/// To release a FfiData
-#[no_mangle]
-pub extern "C" fn destroy_ffi_data(data: FfiData) {
-    if !data.ptr.is_null() {
-        let _ = unsafe { Vec::from_raw_parts(data.ptr, data.len, data.len) };
-    }
-    if !data.error.msg.is_null() {
-        let _ = unsafe { std::ffi::CString::from_raw(data.error.msg as *mut c_char) };
-    }
+/// The opaque handle of the warnings of linting a logical plan (see [`lint_logical_plan`]).
+#[repr(C)]
+pub struct FfiPlanWarnings {
+    _private: [u8; 0],
 }
 
-/// To build a physical plan from the logical plan.
-#[no_mangle]
-pub extern "C" fn build_physical_plan(
-    ptr_plan: *const c_void, num_workers: u32, num_servers: u32,
-) -> FfiData {
-    let mut plan = unsafe { Box::from_raw(ptr_plan as *mut LogicalPlan) };
-    if num_workers > 1 || num_servers > 1 {
-        plan.meta = plan.meta.with_partition();
-    }
-    let mut plan_meta = plan.meta.clone();
-    let mut builder = PlanBuilder::default();
-    let build_result = plan.add_job_builder(&mut builder, &mut plan_meta);
-    let result = match build_result {
-        Ok(_) => {
-            let physical_plan = builder.build();
-            let mut plan_bytes = physical_plan.encode_to_vec().into_boxed_slice();
-            let data = FfiData {
-                ptr: plan_bytes.as_mut_ptr() as *mut c_void,
-                len: plan_bytes.len(),
-                error: FfiResult::success(),
-            };
-            std::mem::forget(plan_bytes);
-
-            data
-        }
-        Err(e) => e.into(),
-    };
+/// The opaque handle of the parameters of querying the graph data (see `init_query_params()`).
+#[repr(C)]
+pub struct FfiQueryParams {
+    _private: [u8; 0],
+}
 
-    std::mem::forget(plan);
+/// The opaque handle of a project operator.
+#[repr(C)]
+pub struct FfiProjectOpr {
+    _private: [u8; 0],
+}
 
-    result
+/// The opaque handle of a select operator.
+#[repr(C)]
+pub struct FfiSelectOpr {
+    _private: [u8; 0],
 }
 
-fn append_operator(
-    ptr_plan: *const c_void, operator: pb::logical_plan::Operator, parent_ids: Vec<i32>, id: *mut i32,
-) -> FfiResult {
-    let mut plan = unsafe { Box::from_raw(ptr_plan as *mut LogicalPlan) };
-    let result = plan.append_operator_as_node(
-        operator,
-        parent_ids
-            .into_iter()
-            .filter_map(|x| if x >= 0 { Some(x as NodeId) } else { None })
-            .collect(),
-    );
-    std::mem::forget(plan);
-    match result {
-        Ok(i) => {
-            unsafe { *id = i as i32 };
-            FfiResult::success()
-        }
-        Err(e) => e.into(),
-    }
+/// The opaque handle of a join operator.
+#[repr(C)]
+pub struct FfiJoinOpr {
+    _private: [u8; 0],
 }
 
-#[no_mangle]
-pub extern "C" fn print_plan_as_json(ptr_plan: *const c_void) -> FfiResult {
-    let box_plan = unsafe { Box::from_raw(ptr_plan as *mut LogicalPlan) };
-    let pb_plan: pb::LogicalPlan = box_plan.as_ref().clone().into();
-    let mut result = FfiResult::success();
-    let json_result = serde_json::to_string_pretty(&pb_plan);
-    match json_result {
-        Ok(json) => {
-            let cstr_result = string_to_cstr(json);
-            match cstr_result {
-                Ok(cstr) => result.msg = cstr,
-                Err(e) => result = e,
-            }
-        }
-        Err(e) => result = FfiResult::new(ResultCode::Others, e.to_string()),
-    }
-    std::mem::forget(box_plan);
+/// The opaque handle of a union operator.
+#[repr(C)]
+pub struct FfiUnionOpr {
+    _private: [u8; 0],
+}
 
-    result
+/// The opaque handle of a group-by operator.
+#[repr(C)]
+pub struct FfiGroupByOpr {
+    _private: [u8; 0],
 }
 
-/// Internal options for some private functions
-#[allow(dead_code)]
-#[derive(PartialEq, Copy, Clone)]
-#[repr(i32)]
-enum InnerOpt {
-    Select = 0,
-    Scan = 1,
-    GetV = 2,
-    EdgeExpand = 3,
+/// The opaque handle of an order-by operator.
+#[repr(C)]
+pub struct FfiOrderByOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a dedup operator.
+#[repr(C)]
+pub struct FfiDedupOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a count operator.
+#[repr(C)]
+pub struct FfiCountOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of an unfold operator.
+#[repr(C)]
+pub struct FfiUnfoldOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a scan operator.
+#[repr(C)]
+pub struct FfiScanOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of the index predicate of a scan operator.
+#[repr(C)]
+pub struct FfiIndexPredicate {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a limit operator.
+#[repr(C)]
+pub struct FfiLimitOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of an as operator.
+#[repr(C)]
+pub struct FfiAsOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a sink operator.
+#[repr(C)]
+pub struct FfiSinkOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of an edge expand operator.
+#[repr(C)]
+pub struct FfiEdgeExpandOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a get-vertex operator.
+#[repr(C)]
+pub struct FfiGetVOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a path expand operator.
+#[repr(C)]
+pub struct FfiPathExpandOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a pattern operator.
+#[repr(C)]
+pub struct FfiPatternOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a sentence of a pattern operator.
+#[repr(C)]
+pub struct FfiPatternSentence {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of an apply operator.
+#[repr(C)]
+pub struct FfiApplyOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a segment-apply operator.
+#[repr(C)]
+pub struct FfiSegApplyOpr {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of a sample record for evaluating expressions (see `init_sample_record()`).
+#[repr(C)]
+pub struct FfiSampleRecord {
+    _private: [u8; 0],
+}
+
+/// The opaque handle of an expression built programmatically (see `expr_var()`).
+#[repr(C)]
+pub struct FfiExpr {
+    _private: [u8; 0],
+}
+
+/// Initialize a logical plan, which expose a pointer for c-like program to access the
+/// entry of the logical plan. This pointer, however, is owned by Rust, and the caller
+/// **must not** process any operation, which includes but not limited to deallocate it.
+/// We have provided  the [`destroy_logical_plan`] api for deallocating the pointer of the logical plan.
+#[no_mangle]
+#[deprecated(note = "use `init_logical_plan_v2` instead")]
+pub extern "C" fn init_logical_plan() -> *const FfiLogicalPlan {
+    catch_panic(|| v2::init_logical_plan_v2() as *const FfiLogicalPlan)
+}
+
+/// To destroy a logical plan.
+#[no_mangle]
+#[deprecated(note = "use `destroy_logical_plan_v2` instead")]
+pub extern "C" fn destroy_logical_plan(ptr_plan: *const FfiLogicalPlan) {
+    catch_panic(|| v2::destroy_logical_plan_v2(ptr_plan as *mut FfiLogicalPlan))
+}
+
+/// Whether the handle, e.g., of a logical plan or an operator, refers to a live object, namely,
+/// it has been neither destroyed nor consumed by an `append_xx_operator()` api.
+#[no_mangle]
+pub extern "C" fn is_valid_handle(ptr: *const c_void) -> bool {
+    catch_panic(|| handle::is_valid(ptr))
+}
+
+/// To release a FfiData
+#[no_mangle]
+pub extern "C" fn destroy_ffi_data(data: FfiData) {
+    catch_panic(|| {
+        if !data.ptr.is_null() {
+            let _ = unsafe { Vec::from_raw_parts(data.ptr, data.len, data.len) };
+        }
+        if !data.error.msg.is_null() {
+            let _ = unsafe { std::ffi::CString::from_raw(data.error.msg as *mut c_char) };
+        }
+    })
+}
+
+/// To build a physical plan from the logical plan.
+#[no_mangle]
+#[deprecated(note = "use `build_physical_plan_v2` instead")]
+pub extern "C" fn build_physical_plan(
+    ptr_plan: *const FfiLogicalPlan, num_workers: u32, num_servers: u32,
+) -> FfiData {
+    catch_panic(|| v2::build_physical_plan_v2(ptr_plan as *mut FfiLogicalPlan, num_workers, num_servers))
+}
+
+/// The parent id given to the `append_xx_operator()` apis to append the operator as a root of
+/// the logical plan, i.e., without any parent operator.
+pub const ROOT_PARENT_ID: i32 = -1;
+
+/// Check the parent ids given by the caller, where [`ROOT_PARENT_ID`] stands for no parent, while
+/// any other negative id, or the id of an operator that is absent in the plan, is rejected.
+fn check_parent_ids(plan: &LogicalPlan, parent_ids: Vec<i32>) -> Result<Vec<NodeId>, FfiResult> {
+    let mut node_ids = vec![];
+    for parent_id in parent_ids {
+        if parent_id == ROOT_PARENT_ID {
+            continue;
+        } else if parent_id < 0 {
+            return Err(FfiResult::new(
+                ResultCode::NegativeIndexError,
+                format!("invalid parent id {:?}, use {:?} to append as a root", parent_id, ROOT_PARENT_ID),
+            ));
+        } else if plan.get_node(parent_id as NodeId).is_none() {
+            return Err(IrError::ParentNodeNotExist(parent_id as NodeId).into());
+        }
+        node_ids.push(parent_id as NodeId);
+    }
+
+    Ok(node_ids)
+}
+
+/// Borrow the logical plan to be modified, which fails with `PlanFrozenError` if it has been frozen.
+fn borrow_unfrozen_plan(
+    ptr_plan: *const FfiLogicalPlan,
+) -> Result<handle::HandleRefMut<LogicalPlan>, FfiResult> {
+    let plan = handle::borrow_mut::<LogicalPlan>(ptr_plan)?;
+    if plan.is_frozen() {
+        Err(IrError::PlanFrozen.into())
+    } else {
+        Ok(plan)
+    }
+}
+
+/// Freeze the logical plan, e.g., once it is handed to the thread that submits it, after which the
+/// apis modifying the plan, such as appending an operator or changing its settings, fail with
+/// `PlanFrozenError`, while the apis inspecting or serializing the plan, as well as building the
+/// physical plan from it, still work. A plan cannot be unfrozen, while its copy via
+/// `clone_logical_plan()` is not frozen, e.g., to be extended into another query.
+#[no_mangle]
+pub extern "C" fn freeze_plan(ptr_plan: *const FfiLogicalPlan) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match handle::borrow_mut::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.freeze();
+
+        FfiResult::success()
+    })
+}
+
+/// Validate the structure of the logical plan (see [`LogicalPlan::validate`]), e.g., before submitting
+/// a plan of which the nodes are appended with the ids given by the callers. The id of the first offending
+/// node is written to `node_id`, or -1 if the plan is valid, and the error is returned, which is mostly
+/// `InvalidPlanError`, or `ParentNotFoundError` if a node refers to a missing parent.
+#[no_mangle]
+pub extern "C" fn validate_plan(ptr_plan: *const FfiLogicalPlan, node_id: *mut i32) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(node_id, "output") {
+            return e;
+        }
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let (id, result) = match plan.validate() {
+            Ok(()) => (-1, FfiResult::success()),
+            Err(e) => {
+                let id = match &e {
+                    IrError::InvalidNode(id, _)
+                    | IrError::ParentNodeNotExist(id)
+                    | IrError::NodeNotExist(id) => *id as i32,
+                    _ => -1,
+                };
+                (id, e.into())
+            }
+        };
+        unsafe { *node_id = id };
+
+        result
+    })
+}
+
+fn append_operator(
+    ptr_plan: *const FfiLogicalPlan, operator: pb::logical_plan::Operator, parent_ids: Vec<i32>,
+    id: *mut i32,
+) -> FfiResult {
+    // checked ahead, as the operator is otherwise appended without its id being known
+    if let Err(e) = check_not_null(id, "id of the operator") {
+        return e;
+    }
+    let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+        Ok(plan) => plan,
+        Err(e) => return e,
+    };
+    let context = format!("appending {}", default_display_name(&operator));
+    let next_id = plan.get_max_node_id();
+    let result = check_parent_ids(&plan, parent_ids)
+        .and_then(|parent_ids| {
+            plan.append_operator_as_node(operator, parent_ids)
+                .map_err(FfiResult::from)
+        })
+        .map_err(|e| e.with_context(&context).at_node(next_id));
+    set_output(id, result.map(|i| i as i32))
+}
+
+/// Append the operator of the given handle to the logical plan, which consumes (and thus invalidates)
+/// the handle if it succeeds, while the handle is kept valid otherwise, such that the caller can still
+/// fix it and append it again, or release it via the `destroy_xx_operator()` api.
+fn append_operator_handle<T>(
+    ptr_plan: *const FfiLogicalPlan, ptr_opr: *const impl Sized, parent_ids: Vec<i32>, id: *mut i32,
+) -> FfiResult
+where
+    T: Clone + Into<pb::logical_plan::Operator> + 'static,
+{
+    let opr = match handle::borrow::<T>(ptr_opr) {
+        Ok(opr) => opr.clone(),
+        Err(e) => return e,
+    };
+    let result = append_operator(ptr_plan, opr.into(), parent_ids, id);
+    if result.code == ResultCode::Success {
+        let _ = handle::release::<T>(ptr_opr);
+    }
+
+    result
+}
+
+/// The kind of an operator, which tells the type of the object of an operator handle.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FfiOprKind {
+    Project = 0,
+    Select = 1,
+    Join = 2,
+    Union = 3,
+    GroupBy = 4,
+    OrderBy = 5,
+    Dedup = 6,
+    Count = 7,
+    Unfold = 8,
+    Scan = 9,
+    Limit = 10,
+    As = 11,
+    Sink = 12,
+    EdgeExpand = 13,
+    GetV = 14,
+    PathExpand = 15,
+    Pattern = 16,
+    Apply = 17,
+    SegApply = 18,
+}
+
+/// Copy the operator of the given handle and kind, e.g., to be put into a plan, after which the handle
+/// is expected to be released via [`destroy_operator`].
+fn operator_of_handle(
+    ptr_opr: *const c_void, kind: FfiOprKind,
+) -> Result<pb::logical_plan::Operator, FfiResult> {
+    fn copy<M>(ptr_opr: *const c_void) -> Result<pb::logical_plan::Operator, FfiResult>
+    where
+        M: Clone + Into<pb::logical_plan::Operator> + 'static,
+    {
+        Ok(handle::borrow::<M>(ptr_opr)?.clone().into())
+    }
+
+    match kind {
+        FfiOprKind::Project => copy::<pb::Project>(ptr_opr),
+        FfiOprKind::Select => copy::<pb::Select>(ptr_opr),
+        FfiOprKind::Join => copy::<pb::Join>(ptr_opr),
+        FfiOprKind::Union => copy::<pb::Union>(ptr_opr),
+        FfiOprKind::GroupBy => copy::<pb::GroupBy>(ptr_opr),
+        FfiOprKind::OrderBy => copy::<pb::OrderBy>(ptr_opr),
+        FfiOprKind::Dedup => copy::<pb::Dedup>(ptr_opr),
+        FfiOprKind::Count => copy::<pb::Count>(ptr_opr),
+        FfiOprKind::Unfold => copy::<pb::Unfold>(ptr_opr),
+        FfiOprKind::Scan => copy::<pb::Scan>(ptr_opr),
+        FfiOprKind::Limit => copy::<pb::Limit>(ptr_opr),
+        FfiOprKind::As => copy::<pb::As>(ptr_opr),
+        FfiOprKind::Sink => copy::<pb::Sink>(ptr_opr),
+        FfiOprKind::EdgeExpand => copy::<pb::EdgeExpand>(ptr_opr),
+        FfiOprKind::GetV => copy::<pb::GetV>(ptr_opr),
+        FfiOprKind::PathExpand => copy::<pb::PathExpand>(ptr_opr),
+        FfiOprKind::Pattern => copy::<pb::Pattern>(ptr_opr),
+        FfiOprKind::Apply => copy::<pb::Apply>(ptr_opr),
+        FfiOprKind::SegApply => copy::<pb::SegmentApply>(ptr_opr),
+    }
+}
+
+/// Replace the operator of the node of the given id by the operator of the given handle and kind, e.g.,
+/// to swap a full `Scan` for one with an index predicate, where the id and the edges of the node are kept
+/// (see [`LogicalPlan::replace_operator`]). As [`append_operator_handle`], the handle is consumed if it
+/// succeeds, and is kept valid otherwise.
+#[no_mangle]
+pub extern "C" fn replace_operator(
+    ptr_plan: *const FfiLogicalPlan, id: i32, ptr_opr: *const c_void, kind: FfiOprKind,
+) -> FfiResult {
+    catch_panic(|| {
+        let opr = match operator_of_handle(ptr_opr, kind) {
+            Ok(opr) => opr,
+            Err(e) => return e,
+        };
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let id = id as NodeId;
+        let context = format!("replacing the node {} by {}", id, default_display_name(&opr));
+        match plan.replace_operator(id, opr) {
+            Ok(_) => {
+                let _ = destroy_operator(ptr_opr, kind);
+                FfiResult::success()
+            }
+            Err(e) => FfiResult::from(e)
+                .with_context(&context)
+                .at_node(id),
+        }
+    })
+}
+
+/// Insert the operator of the given handle and kind as a new node on the edge from the node of
+/// `parent_id` to the node of `child_id`, e.g., for the passes that inject operators once the plan is
+/// built (see [`LogicalPlan::insert_between`]), where the id of the new node is written to `id`. As
+/// [`append_operator_handle`], the handle is consumed if it succeeds, and is kept valid otherwise.
+#[no_mangle]
+pub extern "C" fn insert_between(
+    ptr_plan: *const FfiLogicalPlan, parent_id: i32, child_id: i32, ptr_opr: *const c_void,
+    kind: FfiOprKind, id: *mut i32,
+) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(id, "id of the operator") {
+            return e;
+        }
+        let opr = match operator_of_handle(ptr_opr, kind) {
+            Ok(opr) => opr,
+            Err(e) => return e,
+        };
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let context = format!(
+            "inserting {} between the node {} and the node {}",
+            default_display_name(&opr),
+            parent_id,
+            child_id
+        );
+        let next_id = plan.get_max_node_id();
+        let result = plan
+            .insert_between(parent_id as NodeId, child_id as NodeId, opr)
+            .map_err(|e| {
+                FfiResult::from(e)
+                    .with_context(&context)
+                    .at_node(next_id)
+            });
+        if result.is_ok() {
+            let _ = destroy_operator(ptr_opr, kind);
+        }
+        set_output(id, result.map(|i| i as i32))
+    })
+}
+
+/// Release an operator that has not been appended to a plan, given its handle and its kind, which
+/// is equivalent to the `destroy_xx_operator()` api of the kind, such that the bindings can release
+/// any operator via the same api. A null handle is ignored, while a handle that is no longer valid,
+/// or is not of the given kind, fails with `InvalidHandleError`, and is left untouched.
+#[no_mangle]
+pub extern "C" fn destroy_operator(ptr: *const c_void, kind: FfiOprKind) -> FfiResult {
+    fn release<M: 'static>(ptr: *const c_void) -> FfiResult {
+        match handle::release::<M>(ptr) {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    }
+
+    catch_panic(|| {
+        if ptr.is_null() {
+            return FfiResult::success();
+        }
+        match kind {
+            FfiOprKind::Project => release::<pb::Project>(ptr),
+            FfiOprKind::Select => release::<pb::Select>(ptr),
+            FfiOprKind::Join => release::<pb::Join>(ptr),
+            FfiOprKind::Union => release::<pb::Union>(ptr),
+            FfiOprKind::GroupBy => release::<pb::GroupBy>(ptr),
+            FfiOprKind::OrderBy => release::<pb::OrderBy>(ptr),
+            FfiOprKind::Dedup => release::<pb::Dedup>(ptr),
+            FfiOprKind::Count => release::<pb::Count>(ptr),
+            FfiOprKind::Unfold => release::<pb::Unfold>(ptr),
+            FfiOprKind::Scan => release::<pb::Scan>(ptr),
+            FfiOprKind::Limit => release::<pb::Limit>(ptr),
+            FfiOprKind::As => release::<pb::As>(ptr),
+            FfiOprKind::Sink => release::<pb::Sink>(ptr),
+            FfiOprKind::EdgeExpand => release::<pb::EdgeExpand>(ptr),
+            FfiOprKind::GetV => release::<pb::GetV>(ptr),
+            FfiOprKind::PathExpand => release::<pb::PathExpand>(ptr),
+            FfiOprKind::Pattern => release::<pb::Pattern>(ptr),
+            FfiOprKind::Apply => release::<pb::Apply>(ptr),
+            FfiOprKind::SegApply => release::<pb::SegmentApply>(ptr),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn print_plan_as_json(ptr_plan: *const FfiLogicalPlan) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let pb_plan: pb::LogicalPlan = plan.clone().into();
+        let mut result = FfiResult::success();
+        let json_result = serde_json::to_string_pretty(&pb_plan);
+        match json_result {
+            Ok(json) => {
+                let cstr_result = string_to_cstr(json);
+                match cstr_result {
+                    Ok(cstr) => result.msg = cstr,
+                    Err(e) => result = e,
+                }
+            }
+            Err(e) => result = FfiResult::new(ResultCode::Others, e.to_string()),
+        }
+
+        result
+    })
+}
+
+/// Render the logical plan as a graph of the DOT language, which is written to the `msg` of the
+/// result, such that the caller can visualize a plan of multiple branches, e.g., of joins, unions and
+/// the subtasks of applies, via Graphviz.
+#[no_mangle]
+pub extern "C" fn plan_to_dot(ptr_plan: *const FfiLogicalPlan) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        match string_to_cstr(plan.to_dot()) {
+            Ok(cstr) => {
+                let mut result = FfiResult::success();
+                result.msg = cstr;
+                result
+            }
+            Err(e) => e,
+        }
+    })
+}
+
+/// Explain the logical plan as an indented tree in the style of the relational algebra (see
+/// [`LogicalPlan::explain`]), which is written to the `msg` of the result, e.g., to be embedded in
+/// the error logs of the clients or shown by the query UIs.
+#[no_mangle]
+pub extern "C" fn explain_plan(ptr_plan: *const FfiLogicalPlan) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        match string_to_cstr(plan.explain()) {
+            Ok(cstr) => {
+                let mut result = FfiResult::success();
+                result.msg = cstr;
+                result
+            }
+            Err(e) => e,
+        }
+    })
+}
+
+/// Deliver the debug text of the logical plan, i.e., the plan explained as a tree (see [`explain_plan`])
+/// followed by the plan as json (see [`print_plan_as_json`]), to the `callback` as utf-8 bytes, along with
+/// the context given by the caller, rather than printing it to the stdout of the process, which the
+/// hosts, e.g., a JVM or a Python interpreter, cannot capture. The plan is not borrowed during the
+/// callback, and thus can be accessed by the callback.
+#[no_mangle]
+pub extern "C" fn debug_plan(
+    ptr_plan: *const FfiLogicalPlan, callback: Option<FfiWriteCallback>, ctx: *mut c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        let callback = match callback {
+            Some(callback) => callback,
+            None => {
+                return FfiResult::new(
+                    ResultCode::NullPointerError,
+                    "the callback is a null pointer".to_string(),
+                )
+            }
+        };
+        let text = {
+            let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+                Ok(plan) => plan,
+                Err(e) => return e,
+            };
+            let pb_plan: pb::LogicalPlan = plan.clone().into();
+            match serde_json::to_string_pretty(&pb_plan) {
+                Ok(json) => format!("{}\n\n{}", plan.explain(), json),
+                Err(e) => return FfiResult::new(ResultCode::Others, e.to_string()),
+            }
+        };
+        callback(ctx, text.as_ptr(), text.len());
+
+        FfiResult::success()
+    })
+}
+
+/// Serialize the logical plan into the bytes of a `LogicalPlan`, such that the caller can persist or
+/// transmit the plan by itself, e.g., to a remote service. The bytes are written to `out_buf`, and
+/// their length to `out_len`, which are null and 0 respectively if it fails. The bytes are owned by
+/// Rust, and must be released via [`free_serialized_buffer`].
+#[no_mangle]
+pub extern "C" fn serialize_logical_plan(
+    ptr_plan: *const FfiLogicalPlan, out_buf: *mut *mut u8, out_len: *mut usize,
+) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) =
+            check_not_null(out_buf, "output buffer").and_then(|_| check_not_null(out_len, "output length"))
+        {
+            return e;
+        }
+        unsafe {
+            *out_buf = std::ptr::null_mut();
+            *out_len = 0;
+        }
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        let bytes = plan_pb.encode_to_vec().into_boxed_slice();
+        unsafe {
+            *out_len = bytes.len();
+            *out_buf = Box::into_raw(bytes) as *mut u8;
+        }
+
+        FfiResult::success()
+    })
+}
+
+/// To release the bytes of a plan serialized via [`serialize_logical_plan`], given as the buffer
+/// and its length, where a null buffer is ignored.
+#[no_mangle]
+pub extern "C" fn free_serialized_buffer(buf: *mut u8, len: usize) {
+    catch_panic(|| {
+        if !buf.is_null() {
+            let _ = unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(buf, len)) };
+        }
+    })
+}
+
+/// Attach a free-form annotation, e.g., the originating step of the query, to the operator of
+/// the given id in the logical plan. The annotations are preserved while serializing the plan.
+#[no_mangle]
+pub extern "C" fn annotate_operator(
+    ptr_plan: *const FfiLogicalPlan, id: i32, cstr: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        if id < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid node id {:?}", id));
+        }
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result =
+            cstr_to_string(cstr).and_then(|annotation| Ok(plan.annotate_node(id as NodeId, annotation)?));
+
+        result.err().unwrap_or_else(FfiResult::success)
+    })
+}
+
+/// Attach a key/value annotation to the operator of the given id in the logical plan, e.g., the source
+/// line of the originating step of the query, the estimated cardinality, or the notes of the optimizer,
+/// which replaces the value annotated under the same key before, if any. The annotations are preserved
+/// while serializing the plan, e.g., for debugging on the server side.
+#[no_mangle]
+pub extern "C" fn annotate_node(
+    ptr_plan: *const FfiLogicalPlan, id: i32, cstr_key: *const c_char, cstr_value: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        if id < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid node id {:?}", id));
+        }
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = cstr_to_string(cstr_key).and_then(|key| {
+            let value = cstr_to_string(cstr_value)?;
+            Ok(plan.set_node_attribute(id as NodeId, key, value)?)
+        });
+
+        result.err().unwrap_or_else(FfiResult::success)
+    })
+}
+
+/// Get the value annotated under the given key to the operator of the given id in the logical plan via
+/// `annotate_node()`, which is written to the `msg` of the result, or fails with `MissingDataError` if
+/// there is no such annotation.
+#[no_mangle]
+pub extern "C" fn get_node_annotation(
+    ptr_plan: *const FfiLogicalPlan, id: i32, cstr_key: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        if id < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid node id {:?}", id));
+        }
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let key = match cstr_to_string(cstr_key) {
+            Ok(key) => key,
+            Err(e) => return e,
+        };
+        if plan.get_node(id as NodeId).is_none() {
+            return FfiResult::from(IrError::NodeNotExist(id as NodeId));
+        }
+        match plan.get_node_attribute(id as NodeId, &key) {
+            Some(value) => match string_to_cstr(value) {
+                Ok(msg) => FfiResult { code: ResultCode::Success, msg },
+                Err(e) => e,
+            },
+            None => FfiResult::new(
+                ResultCode::MissingDataError,
+                format!("the node {:?} is not annotated under the key {:?}", id, key),
+            ),
+        }
+    })
+}
+
+/// Give a human-readable name to the operator of the given id in the logical plan, which is used
+/// to describe the operator, e.g., in the error messages, instead of the default name made of the
+/// operator kind plus its key fields.
+#[no_mangle]
+pub extern "C" fn set_operator_display_name(
+    ptr_plan: *const FfiLogicalPlan, id: i32, cstr: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        if id < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid node id {:?}", id));
+        }
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = cstr_to_string(cstr)
+            .and_then(|display_name| Ok(plan.set_display_name(id as NodeId, display_name)?));
+
+        result.err().unwrap_or_else(FfiResult::success)
+    })
+}
+
+/// Attach a free-form annotation to the logical plan, which is preserved while serializing the plan.
+#[no_mangle]
+pub extern "C" fn annotate_plan(ptr_plan: *const FfiLogicalPlan, cstr: *const c_char) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = cstr_to_string(cstr).map(|annotation| plan.annotate_plan(annotation));
+
+        result.err().unwrap_or_else(FfiResult::success)
+    })
+}
+
+/// Allow the query of the logical plan to return partial results, i.e., the results returned before
+/// it fails on a worker failure or a timeout, rather than failing. Whether the fetched results are
+/// partial is reported via `is_job_partial()`, and in the metadata of the `results::CollectiveResults`.
+#[no_mangle]
+pub extern "C" fn set_plan_allow_partial(
+    ptr_plan: *const FfiLogicalPlan, allow_partial: bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.set_allow_partial(allow_partial);
+
+        FfiResult::success()
+    })
+}
+
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+#[repr(i32)]
+pub enum FfiSuperNodeAction {
+    Truncate = 0,
+    Sample = 1,
+    Fail = 2,
+}
+
+/// Set the policy applied by all the expansions of the query while expanding from a super node, i.e.,
+/// a vertex of more than `threshold` adjacent edges, which either expand the first `threshold` edges
+/// only, or a sample of `threshold` edges, or fail the query. The policy is recorded in the plan,
+/// where the `threshold` must be positive.
+#[no_mangle]
+pub extern "C" fn set_plan_super_node_policy(
+    ptr_plan: *const FfiLogicalPlan, action: FfiSuperNodeAction, threshold: i32,
+) -> FfiResult {
+    catch_panic(|| {
+        if threshold <= 0 {
+            return FfiResult::new(
+                ResultCode::InvalidRangeError,
+                format!("the threshold {:?} of the super node policy is not positive", threshold),
+            );
+        }
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.set_super_node_policy(pb::SuperNodePolicy { action: action as i32, threshold });
+
+        FfiResult::success()
+    })
+}
+
+/// Set where the plan originates from, namely, the original text of the query, the name of the frontend,
+/// and the version of its compiler, any of which may be null if unknown. The origin is carried through
+/// the serialization and to the physical plan, and is logged by the engine along with the job, such that
+/// a slow query can be correlated back to the query of the user.
+#[no_mangle]
+pub extern "C" fn set_plan_origin(
+    ptr_plan: *const FfiLogicalPlan, cstr_query: *const c_char, cstr_frontend: *const c_char,
+    cstr_compiler_version: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        let to_string =
+            |cstr: *const c_char| if cstr.is_null() { Ok(String::new()) } else { cstr_to_string(cstr) };
+        let result = to_string(cstr_query).and_then(|query| {
+            Ok(pb::PlanOrigin {
+                query,
+                frontend: to_string(cstr_frontend)?,
+                compiler_version: to_string(cstr_compiler_version)?,
+            })
+        });
+        let origin = match result {
+            Ok(origin) => origin,
+            Err(e) => return e,
+        };
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.set_origin(origin);
+
+        FfiResult::success()
+    })
+}
+
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+#[repr(i32)]
+pub enum FfiContinuousEmit {
+    Delta = 0,
+    DeltaWithRetractions = 1,
+    Snapshot = 2,
+}
+
+/// Mark the query of the logical plan as a continuous one, which is registered under the given name to
+/// run over the updates of the graph by the incremental runtime of the engine, rather than once over a
+/// snapshot. It emits either the results added by the updates (with or without the retracted ones), or
+/// all the results once changed, where the updates are batched for `emit_interval_ms` milliseconds, or
+/// emitted on each update if it is 0.
+#[no_mangle]
+pub extern "C" fn set_plan_continuous(
+    ptr_plan: *const FfiLogicalPlan, cstr_name: *const c_char, emit: FfiContinuousEmit,
+    emit_interval_ms: i64,
+) -> FfiResult {
+    catch_panic(|| {
+        let name = match cstr_to_string(cstr_name) {
+            Ok(name) => name,
+            Err(e) => return e,
+        };
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.set_continuous(pb::ContinuousMode { name, emit: emit as i32, emit_interval_ms });
+
+        FfiResult::success()
+    })
+}
+
+fn allow_plan_read(ptr_plan: *const FfiLogicalPlan, label: String, property: Option<String>) -> FfiResult {
+    let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+        Ok(plan) => plan,
+        Err(e) => return e,
+    };
+    let mut scope = plan
+        .get_read_scope()
+        .cloned()
+        .unwrap_or_default();
+    let index = match scope
+        .labels
+        .iter()
+        .position(|label_scope| label_scope.label == label)
+    {
+        Some(index) => index,
+        None => {
+            scope
+                .labels
+                .push(pb::read_scope::LabelScope { label, properties: vec![] });
+            scope.labels.len() - 1
+        }
+    };
+    scope.labels[index].properties.extend(property);
+    plan.set_read_scope(scope);
+
+    FfiResult::success()
+}
+
+/// Allow the logical plan to read the entities of the given label, e.g., as granted to the user that
+/// submits the query. Once any label is allowed, the plan is restricted to read only the allowed labels
+/// and properties, and fails to build otherwise with `ResultCode::AccessDeniedError`. All the properties
+/// of the label may be read, unless some of them are allowed by `allow_plan_read_property()`.
+#[no_mangle]
+pub extern "C" fn allow_plan_read_label(
+    ptr_plan: *const FfiLogicalPlan, cstr_label: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        let label = match cstr_to_string(cstr_label) {
+            Ok(label) => label,
+            Err(e) => return e,
+        };
+        allow_plan_read(ptr_plan, label, None)
+    })
+}
+
+/// Allow the logical plan to read the given property of the entities of the given label, which restricts
+/// the properties of the label that may be read to the allowed ones, see `allow_plan_read_label()`.
+#[no_mangle]
+pub extern "C" fn allow_plan_read_property(
+    ptr_plan: *const FfiLogicalPlan, cstr_label: *const c_char, cstr_property: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        let label = match cstr_to_string(cstr_label) {
+            Ok(label) => label,
+            Err(e) => return e,
+        };
+        let property = match cstr_to_string(cstr_property) {
+            Ok(property) => property,
+            Err(e) => return e,
+        };
+        allow_plan_read(ptr_plan, label, Some(property))
+    })
+}
+
+/// Check that the logical plan reads only the labels and the properties allowed by
+/// `allow_plan_read_label()` and `allow_plan_read_property()`, if any, which is otherwise checked
+/// while building the physical plan.
+#[no_mangle]
+pub extern "C" fn check_plan_read_scope(ptr_plan: *const FfiLogicalPlan) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        match plan.check_read_scope() {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e.into(),
+        }
+    })
+}
+
+/// Fetch the properties referred by the `Project`, `OrderBy` and `GroupBy` operators of the plan in bulk,
+/// namely, by a single `Auxilia` per tag that is injected before the operator, rather than lazily fetching
+/// the properties one by one, which saves the round-trips to the storage.
+#[no_mangle]
+pub extern "C" fn set_plan_bulk_fetch(ptr_plan: *const FfiLogicalPlan, is_bulk_fetch: bool) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta.set_bulk_fetch(is_bulk_fetch);
+
+        FfiResult::success()
+    })
+}
+
+/// Turn on (or off) the access tracing of the plan, by which the tags and properties referred by the
+/// expressions of each operator are recorded in the serialized plan, such that, e.g., a property that
+/// is not found by the runtime can be traced back to the operator, and thus the query fragment, that
+/// refers to it (see [`LogicalPlan::get_node_accesses`]).
+#[no_mangle]
+pub extern "C" fn set_plan_trace_access(
+    ptr_plan: *const FfiLogicalPlan, is_trace_access: bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta.set_trace_access(is_trace_access);
+
+        FfiResult::success()
+    })
+}
+
+/// The callback that the bytes are written to, e.g., the changes of a logical plan in the write-through
+/// mode, each as the bytes of a `PlanAppend`, along with the context given by the caller, e.g., the
+/// stream to write to. The bytes are owned by Rust, and are valid only during the callback.
+pub type FfiWriteCallback = extern "C" fn(ctx: *mut c_void, data: *const u8, len: usize);
+
+/// The context given along with a callback, which is called from whichever thread appends to the
+/// plan, e.g., of a concurrent plan, and thus it is up to the caller to make the context safe to be
+/// used from multiple threads.
+struct FfiCallbackContext(*mut c_void);
+
+unsafe impl Send for FfiCallbackContext {}
+
+/// Turn on the write-through mode of the plan, in which the nodes are written to the `callback` as
+/// the operators are appended, such that a very large plan can be streamed to, e.g., a file, rather
+/// than serialized as a whole at the end (see [`LogicalPlan::set_write_through`]). The nodes that
+/// have been appended are written at once. The mode is turned off if `callback` is null.
+#[no_mangle]
+pub extern "C" fn set_plan_write_through(
+    ptr_plan: *const FfiLogicalPlan, callback: Option<FfiWriteCallback>, ctx: *mut c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let ctx = FfiCallbackContext(ctx);
+        let sink = callback.map(|callback| -> Box<dyn FnMut(&pb::PlanAppend) + Send> {
+            Box::new(move |change: &pb::PlanAppend| {
+                let bytes = change.encode_to_vec();
+                callback(ctx.0, bytes.as_ptr(), bytes.len());
+            })
+        });
+        plan.set_write_through(sink);
+
+        FfiResult::success()
+    })
+}
+
+/// Initialize the values of the parameters of a plan, which are bound to a physical plan
+/// via [`bind_plan_params`], and must be released via [`destroy_plan_params`].
+#[no_mangle]
+pub extern "C" fn init_plan_params() -> *const FfiPlanParams {
+    catch_panic(|| {
+        let params = PlanParams::new();
+        handle::register(params)
+    })
+}
+
+/// Append a value to the list of values of the parameter of the given name.
+#[no_mangle]
+pub extern "C" fn add_plan_param_value(
+    ptr_params: *const FfiPlanParams, name: *const c_char, value: FfiConst,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(name).and_then(|name| Ok((name, common_pb::Value::try_from(value)?)));
+        match result {
+            Ok((name, value)) => {
+                let mut params = match handle::borrow_mut::<PlanParams>(ptr_params) {
+                    Ok(params) => params,
+                    Err(e) => return e,
+                };
+                params.entry(name).or_default().push(value);
+
+                FfiResult::success()
+            }
+            Err(e) => e,
+        }
+    })
+}
+
+/// Bind the parameters to the physical plan given as the bytes of a `PhysicalPlan`, e.g., as built by
+/// [`build_physical_plan`], returned as the bytes of the bound physical plan. The given physical plan
+/// is not changed, so it can be cached and rebound per submission.
+#[no_mangle]
+pub extern "C" fn bind_plan_params(plan: FfiPbPointer, ptr_params: *const FfiPlanParams) -> FfiData {
+    catch_panic(|| {
+        let mut plan = match ptr_to_pb::<physical_pb::PhysicalPlan>(plan) {
+            Ok(plan) => plan,
+            Err(e) => return e.into(),
+        };
+        let params = match handle::borrow::<PlanParams>(ptr_params) {
+            Ok(params) => params,
+            Err(e) => return e.into(),
+        };
+        let result = physical::bind_plan_params(&mut plan, &params);
+        match result {
+            Ok(_) => plan.encode_to_vec().into(),
+            Err(e) => e.into(),
+        }
+    })
+}
+
+/// To destroy the values of the parameters of a plan.
+#[no_mangle]
+pub extern "C" fn destroy_plan_params(ptr_params: *const FfiPlanParams) {
+    catch_panic(|| destroy_handle::<PlanParams>(ptr_params))
+}
+
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+#[repr(i32)]
+pub enum FfiAggOverflow {
+    Wrap = 0,
+    Saturate = 1,
+    Error = 2,
+    Promote = 3,
+}
+
+/// Set how the integer overflow of the `Sum` and `Avg` aggregate functions of the plan is dealt with,
+/// namely, to wrap around (by default), to saturate at the bounds, to fail the query, or to promote
+/// the aggregated value to a float. The setting must precede appending the aggregate functions, into
+/// which it is encoded.
+#[no_mangle]
+pub extern "C" fn set_plan_agg_overflow(
+    ptr_plan: *const FfiLogicalPlan, overflow: FfiAggOverflow,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let overflow = match overflow {
+            FfiAggOverflow::Wrap => pb::group_by::agg_func::Overflow::Wrap,
+            FfiAggOverflow::Saturate => pb::group_by::agg_func::Overflow::Saturate,
+            FfiAggOverflow::Error => pb::group_by::agg_func::Overflow::Error,
+            FfiAggOverflow::Promote => pb::group_by::agg_func::Overflow::Promote,
+        };
+        plan.meta.set_agg_overflow(overflow);
+
+        FfiResult::success()
+    })
+}
+
+/// Set the maximal number of hops of the path expansions of the plan, e.g., `both('*1..')` that
+/// expands unboundedly, beyond which appending a path expansion (including that of a pattern)
+/// fails with `HopRangeExceededError`. A non-positive maximum removes the limit.
+#[no_mangle]
+pub extern "C" fn set_plan_max_hops(ptr_plan: *const FfiLogicalPlan, max_hops: i32) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta
+            .set_max_hops(if max_hops > 0 { Some(max_hops) } else { None });
+
+        FfiResult::success()
+    })
+}
+
+/// Set whether to reorder the terms of the predicates of the operators appended to the plan afterwards
+/// by their estimated costs, e.g., a cheap check of the label goes before a `startsWith` over a property,
+/// such that the filters short-circuit as early as possible. The chosen order is annotated to the
+/// operators.
+#[no_mangle]
+pub extern "C" fn set_plan_reorder_predicates(
+    ptr_plan: *const FfiLogicalPlan, is_reorder_predicates: bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta
+            .set_reorder_predicates(is_reorder_predicates);
+
+        FfiResult::success()
+    })
+}
+
+/// Set whether to push the counting and summing aggregations appended to the plan afterwards down below
+/// the inner joins that they follow, if they are grouped by the join keys. Each side of the join is then
+/// partially aggregated before being joined, and the partial results are combined after the join.
+#[no_mangle]
+pub extern "C" fn set_plan_push_down_aggregation(
+    ptr_plan: *const FfiLogicalPlan, is_push_down_aggregation: bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta
+            .set_push_down_aggregation(is_push_down_aggregation);
+
+        FfiResult::success()
+    })
+}
+
+/// Set whether to project each input of the joins onto the join keys and the tags needed after the
+/// join while building the physical plan, such that the unused columns are dropped before the inputs
+/// are shuffled to be joined.
+#[no_mangle]
+pub extern "C" fn set_plan_project_join_keys(
+    ptr_plan: *const FfiLogicalPlan, is_project_join_keys: bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta
+            .set_project_join_keys(is_project_join_keys);
+
+        FfiResult::success()
+    })
+}
+
+/// Set whether to inline the union appended to the plan afterwards into a single scan, if its branches
+/// are the scans that differ only by their labels, or only by the constants of their index predicates.
+#[no_mangle]
+pub extern "C" fn set_plan_inline_union_scans(
+    ptr_plan: *const FfiLogicalPlan, is_inline_union_scans: bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta
+            .set_inline_union_scans(is_inline_union_scans);
+
+        FfiResult::success()
+    })
+}
+
+/// Set the time budget, in milliseconds, of the expensive optimizations of the operators appended to
+/// the plan afterwards, e.g., ordering the extensions of a pattern, which apply only until the budget is
+/// used up, while the cheap ones always apply. A negative budget removes the budget. The applied stage
+/// of the optimizations is reported by [`explain_plan`].
+#[no_mangle]
+pub extern "C" fn set_plan_optimization_budget(
+    ptr_plan: *const FfiLogicalPlan, budget_ms: i64,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta
+            .set_optimization_budget(if budget_ms >= 0 {
+                Some(Duration::from_millis(budget_ms as u64))
+            } else {
+                None
+            });
+
+        FfiResult::success()
+    })
+}
+
+/// Set the threshold of the estimated complexity of the plan, beyond which the plan is rejected by
+/// [`estimate_plan_complexity`]. A non-positive threshold removes the threshold.
+#[no_mangle]
+pub extern "C" fn set_plan_complexity_threshold(
+    ptr_plan: *const FfiLogicalPlan, threshold: f64,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        plan.meta
+            .set_complexity_threshold(if threshold > 0.0 { Some(threshold) } else { None });
+
+        FfiResult::success()
+    })
+}
+
+/// Estimate the complexity of the plan as a single score, which combines the number of the nodes,
+/// the hop ranges of the expansions, and the statistics of the store (see
+/// [`LogicalPlan::estimate_complexity`]). The score is written to `score` regardless, and the
+/// result is `ComplexityExceededError` if the score exceeds the threshold of the plan.
+#[no_mangle]
+pub extern "C" fn estimate_plan_complexity(ptr_plan: *const FfiLogicalPlan, score: *mut f64) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let estimated = plan.estimate_complexity();
+        if !score.is_null() {
+            unsafe { *score = estimated };
+        }
+        let result = match plan.check_complexity() {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e.into(),
+        };
+
+        result
+    })
+}
+
+/// Estimate the number of the rows output by the node of the given id by the statistics of the store
+/// (see [`LogicalPlan::estimate_cardinality`]), e.g., for the interactive clients to warn the users
+/// before running an expensive query, which is written to `cardinality`. It fails with
+/// `MissingDataError` if the number cannot be estimated, e.g., the statistics are absent.
+#[no_mangle]
+pub extern "C" fn estimate_output_cardinality(
+    ptr_plan: *const FfiLogicalPlan, id: i32, cardinality: *mut f64,
+) -> FfiResult {
+    catch_panic(|| {
+        if id < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid node id {:?}", id));
+        }
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = plan
+            .estimate_cardinality(id as NodeId)
+            .and_then(|estimate| {
+                estimate.ok_or_else(|| {
+                    IrError::MissingData(format!("the statistics to estimate the node {}", id))
+                })
+            })
+            .map_err(FfiResult::from);
+        set_output(cardinality, result)
+    })
+}
+
+/// Reassign the ids of the nodes of the plan by their canonical order (see
+/// [`LogicalPlan::canonical_order`]), rather than the order in which they were appended, such that the
+/// ids, and thus the serialized plan, remain stable when the independent operators are appended in a
+/// different order. If `ptr_ids` is not null, the reassigned id of the node of id `i` is written to
+/// `ptr_ids[i]` for each `i < len`, or `-1` if there is no such node.
+#[no_mangle]
+pub extern "C" fn canonicalize_node_ids(
+    ptr_plan: *const FfiLogicalPlan, ptr_ids: *mut i32, len: i32,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = plan.canonicalize_node_ids();
+
+        write_node_ids(result, ptr_ids, len)
+    })
+}
+
+/// Normalize the plan, such that the semantically identical plans produced by different frontends
+/// become identical, e.g., the operands of the commutative predicates and the branches of the unions
+/// are ordered (see [`LogicalPlan::normalize`]). The ids of the nodes are reassigned as well, which are
+/// written to `ptr_ids` as [`canonicalize_node_ids`] does.
+#[no_mangle]
+pub extern "C" fn normalize_logical_plan(
+    ptr_plan: *const FfiLogicalPlan, ptr_ids: *mut i32, len: i32,
+) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = plan.normalize();
+
+        write_node_ids(result, ptr_ids, len)
+    })
+}
+
+/// Compute the fingerprint of the normalized plan, which is the same for the semantically identical
+/// plans (see [`LogicalPlan::fingerprint`]), while the plan itself is left unchanged. The fingerprint
+/// is stable across the processes and the builds of the library, and thus can key a plan cache shared
+/// by the servers.
+#[no_mangle]
+pub extern "C" fn get_plan_fingerprint(
+    ptr_plan: *const FfiLogicalPlan, fingerprint: *mut u64,
+) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = plan.fingerprint().map_err(FfiResult::from);
+
+        set_output(fingerprint, result)
+    })
+}
+
+/// Check whether the two plans are identical once normalized (see [`LogicalPlan::is_equivalent`]).
+#[no_mangle]
+pub extern "C" fn is_equivalent_plan(
+    ptr_plan1: *const FfiLogicalPlan, ptr_plan2: *const FfiLogicalPlan, equivalent: *mut bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let plan1 = match handle::borrow::<LogicalPlan>(ptr_plan1) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let plan2 = match handle::borrow::<LogicalPlan>(ptr_plan2) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = plan1
+            .is_equivalent(&plan2)
+            .map_err(FfiResult::from);
+
+        set_output(equivalent, result)
+    })
+}
+
+/// Reassign the ids of the nodes of the plan as given by the caller, namely, the node of id
+/// `ptr_order[i]` is given the id `i`, for each `i < len_order`. The order must contain each node of
+/// the plan exactly once, and place the nodes after their parents and the subtasks of the `Apply`s
+/// after their nodes (see [`LogicalPlan::reorder_nodes`]). The reassigned ids are written to
+/// `ptr_ids` as [`canonicalize_node_ids`] does.
+#[no_mangle]
+pub extern "C" fn reorder_node_ids(
+    ptr_plan: *const FfiLogicalPlan, ptr_order: *const i32, len_order: i32, ptr_ids: *mut i32, len: i32,
+) -> FfiResult {
+    catch_panic(|| {
+        if ptr_order.is_null() {
+            return FfiResult::new(
+                ResultCode::NullPointerError,
+                "the order of the nodes is null".to_string(),
+            );
+        }
+        let order: Vec<NodeId> =
+            unsafe { std::slice::from_raw_parts(ptr_order, len_order.max(0) as usize) }
+                .iter()
+                .map(|id| *id as NodeId)
+                .collect();
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = plan.reorder_nodes(&order);
+
+        write_node_ids(result, ptr_ids, len)
+    })
+}
+
+fn write_node_ids(result: IrResult<HashMap<NodeId, NodeId>>, ptr_ids: *mut i32, len: i32) -> FfiResult {
+    match result {
+        Ok(id_map) => {
+            if !ptr_ids.is_null() {
+                let ids = unsafe { std::slice::from_raw_parts_mut(ptr_ids, len.max(0) as usize) };
+                for (old_id, new_id) in ids.iter_mut().enumerate() {
+                    *new_id = id_map
+                        .get(&(old_id as NodeId))
+                        .map(|id| *id as i32)
+                        .unwrap_or(-1);
+                }
+            }
+            FfiResult::success()
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Detach the node of the given id from the plan, e.g., a redundant `Dedup` or `Select`, where its
+/// parent is reconnected to its children in its place (see [`LogicalPlan::detach_node`]). It fails with
+/// `UnSupported` if the children would refer to something else without the node.
+#[no_mangle]
+pub extern "C" fn detach_plan_node(ptr_plan: *const FfiLogicalPlan, id: i32) -> FfiResult {
+    catch_panic(|| {
+        let mut plan = match borrow_unfrozen_plan(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        match plan.detach_node(id as NodeId) {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e.into(),
+        }
+    })
+}
+
+/// Lint the logical plan, which returns a pointer to the list of the warnings that likely make the
+/// query slow, or are mistakes, e.g., a cartesian product without a limit (see [`LogicalPlan::lint`]).
+/// The list can be iterated via [`get_plan_warnings_len`] and [`get_plan_warning`], and must be
+/// released via [`destroy_plan_warnings`].
+#[no_mangle]
+pub extern "C" fn lint_logical_plan(ptr_plan: *const FfiLogicalPlan) -> *const FfiPlanWarnings {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(_) => return std::ptr::null(),
+        };
+        let warnings = plan.lint();
+
+        handle::register(warnings)
+    })
+}
+
+/// Get the number of the warnings of linting a logical plan.
+#[no_mangle]
+pub extern "C" fn get_plan_warnings_len(ptr_warnings: *const FfiPlanWarnings, len: *mut i32) -> FfiResult {
+    catch_panic(|| {
+        let warnings = match handle::borrow::<Vec<PlanWarning>>(ptr_warnings) {
+            Ok(warnings) => warnings,
+            Err(e) => return e,
+        };
+        let result = set_output(len, Ok(warnings.len() as i32));
+
+        result
+    })
+}
+
+/// Get the `index`-th warning of linting a logical plan, where the rule (the discriminant of `LintRule`)
+/// and the id of the operator warned about are written to `rule` and `id`, while the message of the
+/// warning is given as the message of the result.
+#[no_mangle]
+pub extern "C" fn get_plan_warning(
+    ptr_warnings: *const FfiPlanWarnings, index: i32, rule: *mut i32, id: *mut i32,
+) -> FfiResult {
+    catch_panic(|| {
+        if index < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid index {:?}", index));
+        }
+        let warnings = match handle::borrow::<Vec<PlanWarning>>(ptr_warnings) {
+            Ok(warnings) => warnings,
+            Err(e) => return e,
+        };
+        let result = if let Some(warning) = warnings.get(index as usize) {
+            set_output(rule, Ok(warning.rule as i32));
+            set_output(id, Ok(warning.node as i32));
+            match string_to_cstr(warning.message.clone()) {
+                Ok(msg) => FfiResult { code: ResultCode::Success, msg },
+                Err(e) => e,
+            }
+        } else {
+            FfiResult::new(ResultCode::MissingDataError, format!("the {:?}-th warning is missing", index))
+        };
+
+        result
+    })
+}
+
+/// To destroy the list of the warnings of linting a logical plan.
+#[no_mangle]
+pub extern "C" fn destroy_plan_warnings(ptr_warnings: *const FfiPlanWarnings) {
+    catch_panic(|| destroy_handle::<Vec<PlanWarning>>(ptr_warnings))
+}
+
+/// Check whether the output of the operator of the given id is in order, i.e., an `OrderBy`
+/// precedes it with only order-preserving operators (e.g., `Select` and `Limit`) between,
+/// which can be used to validate that a query requires its results in order.
+#[no_mangle]
+pub extern "C" fn is_operator_ordered(
+    ptr_plan: *const FfiLogicalPlan, id: i32, ordered: *mut bool,
+) -> FfiResult {
+    catch_panic(|| {
+        if id < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid node id {:?}", id));
+        }
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = if plan.get_node(id as NodeId).is_some() {
+            Ok(plan.get_ordering(id as NodeId).is_some())
+        } else {
+            Err(IrError::NodeNotExist(id as NodeId).into())
+        };
+
+        set_output(ordered, result)
+    })
+}
+
+/// Validate that the output of the operator of the given id is in order, as a query requires its
+/// results in order, or fail with an `InvalidNodeError` naming the operator that breaks the order.
+#[no_mangle]
+pub extern "C" fn require_operator_ordered(ptr_plan: *const FfiLogicalPlan, id: i32) -> FfiResult {
+    catch_panic(|| {
+        if id < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid node id {:?}", id));
+        }
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = match plan.require_ordering(id as NodeId, &[]) {
+            Ok(()) => FfiResult::success(),
+            Err(e) => e.into(),
+        };
+
+        result
+    })
+}
+
+/// The estimated size, in bytes, of the logical plan held in memory, which can be checked to
+/// reject the pathological plans (e.g., of enormous number of operators) before building them.
+#[no_mangle]
+pub extern "C" fn plan_memory_footprint(ptr_plan: *const FfiLogicalPlan) -> u64 {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(_) => return 0,
+        };
+        let footprint = plan.memory_footprint() as u64;
+
+        footprint
+    })
+}
+
+/// The size, in bytes, of the logical plan serialized as a `LogicalPlan` pb, which is roughly
+/// the size of the plan shipped over the network.
+#[no_mangle]
+pub extern "C" fn plan_serialized_size(ptr_plan: *const FfiLogicalPlan) -> u64 {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(_) => return 0,
+        };
+        let size = plan.serialized_size() as u64;
+
+        size
+    })
+}
+
+/// Get the number of the operators of the logical plan, which is written to `size`.
+#[no_mangle]
+pub extern "C" fn get_plan_size(ptr_plan: *const FfiLogicalPlan, size: *mut i32) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let result = set_output(size, Ok(plan.len() as i32));
+
+        result
+    })
+}
+
+/// Get the ids of the operators of the logical plan in ascending order, of which the first `len` are
+/// written to `ptr_ids`, while the number of all the ids is written to `num_ids`, such that the
+/// caller can tell whether `ptr_ids` is large enough, e.g., sized via [`get_plan_size`].
+#[no_mangle]
+pub extern "C" fn get_node_ids(
+    ptr_plan: *const FfiLogicalPlan, ptr_ids: *mut i32, len: i32, num_ids: *mut i32,
+) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let node_ids = plan.get_node_ids();
+        write_ids(&node_ids, ptr_ids, len, num_ids)
+    })
+}
+
+/// Get the ids of the roots of the logical plan, i.e., the operators without parents (other than those
+/// of the subtasks), in ascending order, which are written as [`get_node_ids`] does.
+#[no_mangle]
+pub extern "C" fn get_plan_roots(
+    ptr_plan: *const FfiLogicalPlan, ptr_ids: *mut i32, len: i32, num_ids: *mut i32,
+) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        write_ids(&plan.get_roots(), ptr_ids, len, num_ids)
+    })
+}
+
+/// Get the ids of the sinks of the logical plan, i.e., the operators without children (other than those
+/// of the subtasks), in ascending order, which are written as [`get_node_ids`] does. The operators are
+/// appended to the sinks, e.g., while extending a plan deserialized from a pb, without tracking the ids
+/// of the operators that have been appended.
+#[no_mangle]
+pub extern "C" fn get_plan_sinks(
+    ptr_plan: *const FfiLogicalPlan, ptr_ids: *mut i32, len: i32, num_ids: *mut i32,
+) -> FfiResult {
+    catch_panic(|| {
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        write_ids(&plan.get_sinks(), ptr_ids, len, num_ids)
+    })
+}
+
+/// Write the first `len` of the ids to `ptr_ids`, if it is not null, and the number of all the ids to
+/// `num_ids`.
+fn write_ids(node_ids: &[NodeId], ptr_ids: *mut i32, len: i32, num_ids: *mut i32) -> FfiResult {
+    if !ptr_ids.is_null() {
+        let ids = unsafe { std::slice::from_raw_parts_mut(ptr_ids, len.max(0) as usize) };
+        for (id, node_id) in ids.iter_mut().zip(node_ids.iter()) {
+            *id = *node_id as i32;
+        }
+    }
+    set_output(num_ids, Ok(node_ids.len() as i32))
+}
+
+/// Get the operator of the given id as a json, which is written to the `msg` of the result, such that
+/// the caller can verify what has been appended, e.g., the kind, the predicates and the aliases of the
+/// operator, as they are after the operator is processed while appending.
+#[no_mangle]
+pub extern "C" fn get_operator_json(ptr_plan: *const FfiLogicalPlan, id: i32) -> FfiResult {
+    catch_panic(|| {
+        if id < 0 {
+            return FfiResult::new(ResultCode::NegativeIndexError, format!("invalid node id {:?}", id));
+        }
+        let plan = match handle::borrow::<LogicalPlan>(ptr_plan) {
+            Ok(plan) => plan,
+            Err(e) => return e,
+        };
+        let opr = match plan.get_opr(id as NodeId) {
+            Some(opr) => opr,
+            None => return FfiResult::from(IrError::NodeNotExist(id as NodeId)),
+        };
+        match serde_json::to_string_pretty(&opr) {
+            Ok(json) => match string_to_cstr(json) {
+                Ok(msg) => FfiResult { code: ResultCode::Success, msg },
+                Err(e) => e,
+            },
+            Err(e) => FfiResult::new(ResultCode::Others, e.to_string()),
+        }
+    })
+}
+
+/// Internal options for some private functions
+#[allow(dead_code)]
+#[derive(PartialEq, Copy, Clone)]
+#[repr(i32)]
+enum InnerOpt {
+    Select = 0,
+    Scan = 1,
+    GetV = 2,
+    EdgeExpand = 3,
     PathExpand = 4,
     Limit = 5,
     As = 6,
@@ -708,7 +3012,7 @@ enum InnerOpt {
 }
 
 /// Set the size range limitation for certain operators
-fn set_range(ptr: *const c_void, lower: i32, upper: i32, opt: InnerOpt) -> FfiResult {
+fn set_range(ptr: *const impl Sized, lower: i32, upper: i32, opt: InnerOpt) -> FfiResult {
     if lower < 0 || upper < 0 || upper < lower {
         FfiResult::new(
             ResultCode::InvalidRangeError,
@@ -717,24 +3021,32 @@ fn set_range(ptr: *const c_void, lower: i32, upper: i32, opt: InnerOpt) -> FfiRe
     } else {
         match opt {
             InnerOpt::Limit => {
-                let mut limit = unsafe { Box::from_raw(ptr as *mut pb::Limit) };
+                let mut limit = match handle::borrow_mut::<pb::Limit>(ptr) {
+                    Ok(limit) => limit,
+                    Err(e) => return e,
+                };
                 limit.range = Some(pb::Range { lower, upper });
-                std::mem::forget(limit);
             }
             InnerOpt::OrderBy => {
-                let mut orderby = unsafe { Box::from_raw(ptr as *mut pb::OrderBy) };
+                let mut orderby = match handle::borrow_mut::<pb::OrderBy>(ptr) {
+                    Ok(orderby) => orderby,
+                    Err(e) => return e,
+                };
                 orderby.limit = Some(pb::Range { lower, upper });
-                std::mem::forget(orderby);
             }
             InnerOpt::Params => {
-                let mut params = unsafe { Box::from_raw(ptr as *mut pb::QueryParams) };
+                let mut params = match handle::borrow_mut::<pb::QueryParams>(ptr) {
+                    Ok(params) => params,
+                    Err(e) => return e,
+                };
                 params.limit = Some(pb::Range { lower, upper });
-                std::mem::forget(params);
             }
             InnerOpt::PathExpand => {
-                let mut pathxpd = unsafe { Box::from_raw(ptr as *mut pb::PathExpand) };
+                let mut pathxpd = match handle::borrow_mut::<pb::PathExpand>(ptr) {
+                    Ok(pathxpd) => pathxpd,
+                    Err(e) => return e,
+                };
                 pathxpd.hop_range = Some(pb::Range { lower, upper });
-                std::mem::forget(pathxpd);
             }
             _ => unreachable!(),
         }
@@ -743,40 +3055,52 @@ fn set_range(ptr: *const c_void, lower: i32, upper: i32, opt: InnerOpt) -> FfiRe
     }
 }
 
-fn set_alias(ptr: *const c_void, alias: FfiAlias, opt: InnerOpt) -> FfiResult {
+fn set_alias(ptr: *const impl Sized, alias: FfiAlias, opt: InnerOpt) -> FfiResult {
     let alias_pb = alias.try_into();
     match alias_pb {
         Ok(pb) => {
             match opt {
                 InnerOpt::Scan => {
-                    let mut scan = unsafe { Box::from_raw(ptr as *mut pb::Scan) };
+                    let mut scan = match handle::borrow_mut::<pb::Scan>(ptr) {
+                        Ok(scan) => scan,
+                        Err(e) => return e,
+                    };
                     scan.alias = pb;
-                    std::mem::forget(scan);
                 }
                 InnerOpt::EdgeExpand => {
-                    let mut edgexpd = unsafe { Box::from_raw(ptr as *mut pb::EdgeExpand) };
+                    let mut edgexpd = match handle::borrow_mut::<pb::EdgeExpand>(ptr) {
+                        Ok(edgexpd) => edgexpd,
+                        Err(e) => return e,
+                    };
                     edgexpd.alias = pb;
-                    std::mem::forget(edgexpd);
                 }
                 InnerOpt::PathExpand => {
-                    let mut pathxpd = unsafe { Box::from_raw(ptr as *mut pb::PathExpand) };
+                    let mut pathxpd = match handle::borrow_mut::<pb::PathExpand>(ptr) {
+                        Ok(pathxpd) => pathxpd,
+                        Err(e) => return e,
+                    };
                     pathxpd.alias = pb;
-                    std::mem::forget(pathxpd);
                 }
                 InnerOpt::GetV => {
-                    let mut getv = unsafe { Box::from_raw(ptr as *mut pb::GetV) };
+                    let mut getv = match handle::borrow_mut::<pb::GetV>(ptr) {
+                        Ok(getv) => getv,
+                        Err(e) => return e,
+                    };
                     getv.alias = pb;
-                    std::mem::forget(getv);
                 }
                 InnerOpt::Apply => {
-                    let mut apply = unsafe { Box::from_raw(ptr as *mut pb::Apply) };
+                    let mut apply = match handle::borrow_mut::<pb::Apply>(ptr) {
+                        Ok(apply) => apply,
+                        Err(e) => return e,
+                    };
                     apply.alias = pb;
-                    std::mem::forget(apply);
                 }
                 InnerOpt::As => {
-                    let mut as_opr = unsafe { Box::from_raw(ptr as *mut pb::As) };
+                    let mut as_opr = match handle::borrow_mut::<pb::As>(ptr) {
+                        Ok(as_opr) => as_opr,
+                        Err(e) => return e,
+                    };
                     as_opr.alias = pb;
-                    std::mem::forget(as_opr);
                 }
                 _ => unreachable!(),
             }
@@ -786,112 +3110,151 @@ fn set_alias(ptr: *const c_void, alias: FfiAlias, opt: InnerOpt) -> FfiResult {
     }
 }
 
+/// Set the soft timeout, in milliseconds, of the operators that may run away, where 0 means
+/// no timeout
+fn set_timeout(ptr: *const impl Sized, timeout_ms: u64, opt: InnerOpt) -> FfiResult {
+    match opt {
+        InnerOpt::PathExpand => {
+            let mut pathxpd = match handle::borrow_mut::<pb::PathExpand>(ptr) {
+                Ok(pathxpd) => pathxpd,
+                Err(e) => return e,
+            };
+            pathxpd.timeout_ms = timeout_ms;
+        }
+        InnerOpt::Apply => {
+            let mut apply = match handle::borrow_mut::<pb::Apply>(ptr) {
+                Ok(apply) => apply,
+                Err(e) => return e,
+            };
+            apply.timeout_ms = timeout_ms;
+        }
+        _ => unreachable!(),
+    }
+
+    FfiResult::success()
+}
+
 /// To set an operator's meta_data.
-fn set_meta(ptr: *const c_void, meta_data: FfiPbPointer, opt: InnerOpt) -> FfiResult {
+fn set_meta(ptr: *const impl Sized, meta_data: FfiPbPointer, opt: InnerOpt) -> FfiResult {
     let meta_pb = ptr_to_pb::<pb::MetaData>(meta_data);
     match meta_pb {
         Ok(pb) => {
             match opt {
                 InnerOpt::Scan => {
-                    let mut scan = unsafe { Box::from_raw(ptr as *mut pb::Scan) };
+                    let mut scan = match handle::borrow_mut::<pb::Scan>(ptr) {
+                        Ok(scan) => scan,
+                        Err(e) => return e,
+                    };
                     scan.meta_data = Some(pb);
-                    std::mem::forget(scan);
                 }
                 InnerOpt::EdgeExpand => {
-                    let mut edgexpd = unsafe { Box::from_raw(ptr as *mut pb::EdgeExpand) };
+                    let mut edgexpd = match handle::borrow_mut::<pb::EdgeExpand>(ptr) {
+                        Ok(edgexpd) => edgexpd,
+                        Err(e) => return e,
+                    };
                     edgexpd.meta_data = Some(pb);
-                    std::mem::forget(edgexpd);
                 }
                 InnerOpt::GetV => {
-                    let mut getv = unsafe { Box::from_raw(ptr as *mut pb::GetV) };
+                    let mut getv = match handle::borrow_mut::<pb::GetV>(ptr) {
+                        Ok(getv) => getv,
+                        Err(e) => return e,
+                    };
                     getv.meta_data = Some(pb);
-                    std::mem::forget(getv);
                 }
                 InnerOpt::Unfold => {
-                    let mut unfold = unsafe { Box::from_raw(ptr as *mut pb::Unfold) };
+                    let mut unfold = match handle::borrow_mut::<pb::Unfold>(ptr) {
+                        Ok(unfold) => unfold,
+                        Err(e) => return e,
+                    };
                     unfold.meta_data = Some(pb);
-                    std::mem::forget(unfold);
                 }
                 _ => unreachable!(),
             }
-            FfiResult::success()
-        }
-        Err(e) => e,
-    }
-}
-
-/// To set an operator's predicate.
-fn set_predicate(ptr: *const c_void, cstr_predicate: *const c_char, opt: InnerOpt) -> FfiResult {
-    let predicate_pb = cstr_to_expr_pb(cstr_predicate);
-    if predicate_pb.is_ok() {
-        match opt {
-            InnerOpt::Select => {
-                let mut select = unsafe { Box::from_raw(ptr as *mut pb::Select) };
-                select.predicate = predicate_pb.ok();
-                std::mem::forget(select);
-            }
-            InnerOpt::Params => {
-                let mut params = unsafe { Box::from_raw(ptr as *mut pb::QueryParams) };
-                params.predicate = predicate_pb.ok();
-                std::mem::forget(params);
-            }
-            InnerOpt::PathExpand => {
-                let mut path = unsafe { Box::from_raw(ptr as *mut pb::PathExpand) };
-                path.condition = predicate_pb.ok();
-                std::mem::forget(path);
-            }
-            _ => unreachable!(),
+            FfiResult::success()
         }
-        FfiResult::success()
-    } else {
-        predicate_pb.err().unwrap()
+        Err(e) => e,
     }
 }
 
+/// To set an operator's predicate.
+fn set_predicate(ptr: *const impl Sized, cstr_predicate: *const c_char, opt: InnerOpt) -> FfiResult {
+    set_predicate_result(ptr, cstr_to_expr_pb(cstr_predicate), opt)
+}
+
 /// To set an operator's predicate from a pb predicate pointer
 /// In the following functions, we can set expression directly from a pb pointer,
 /// which is parsed by the compiler instead of ffi function.
-fn set_predicate_pb(ptr: *const c_void, ptr_predicate_pb: FfiPbPointer, opt: InnerOpt) -> FfiResult {
-    let predicate_pb = ptr_to_pb::<common_pb::Expression>(ptr_predicate_pb);
-    if predicate_pb.is_ok() {
-        match opt {
-            InnerOpt::Select => {
-                let mut select = unsafe { Box::from_raw(ptr as *mut pb::Select) };
-                select.predicate = predicate_pb.ok();
-                std::mem::forget(select);
-            }
-            InnerOpt::Params => {
-                let mut params = unsafe { Box::from_raw(ptr as *mut pb::QueryParams) };
-                params.predicate = predicate_pb.ok();
-                std::mem::forget(params);
-            }
-            _ => unreachable!(),
+fn set_predicate_pb(ptr: *const impl Sized, ptr_predicate_pb: FfiPbPointer, opt: InnerOpt) -> FfiResult {
+    set_predicate_result(ptr, ptr_to_pb::<common_pb::Expression>(ptr_predicate_pb), opt)
+}
+
+/// To set an operator's predicate from an expression built programmatically (see `expr_var()`),
+/// which is neither printed as a string nor encoded as pb by the caller.
+fn set_predicate_expr(ptr: *const impl Sized, ptr_expr: *const FfiExpr, opt: InnerOpt) -> FfiResult {
+    let predicate_pb =
+        handle::borrow::<Expr>(ptr_expr).map(|expr| common_pb::Expression::from((*expr).clone()));
+    set_predicate_result(ptr, predicate_pb, opt)
+}
+
+fn set_predicate_result(
+    ptr: *const impl Sized, predicate_pb: Result<common_pb::Expression, FfiResult>, opt: InnerOpt,
+) -> FfiResult {
+    let predicate_pb = match predicate_pb {
+        Ok(predicate_pb) => predicate_pb,
+        Err(e) => return e,
+    };
+    match opt {
+        InnerOpt::Select => {
+            let mut select = match handle::borrow_mut::<pb::Select>(ptr) {
+                Ok(select) => select,
+                Err(e) => return e,
+            };
+            select.predicate = Some(predicate_pb);
         }
-        FfiResult::success()
-    } else {
-        predicate_pb.err().unwrap()
+        InnerOpt::Params => {
+            let mut params = match handle::borrow_mut::<pb::QueryParams>(ptr) {
+                Ok(params) => params,
+                Err(e) => return e,
+            };
+            params.predicate = Some(predicate_pb);
+        }
+        InnerOpt::PathExpand => {
+            let mut path = match handle::borrow_mut::<pb::PathExpand>(ptr) {
+                Ok(path) => path,
+                Err(e) => return e,
+            };
+            path.condition = Some(predicate_pb);
+        }
+        _ => unreachable!(),
     }
+    FfiResult::success()
 }
 
-fn set_tag(ptr: *const c_void, tag: FfiNameOrId, opt: InnerOpt) -> FfiResult {
+fn set_tag(ptr: *const impl Sized, tag: FfiNameOrId, opt: InnerOpt) -> FfiResult {
     let pb_result = tag.try_into();
     match pb_result {
         Ok(pb) => {
             match opt {
                 InnerOpt::EdgeExpand => {
-                    let mut expand = unsafe { Box::from_raw(ptr as *mut pb::EdgeExpand) };
+                    let mut expand = match handle::borrow_mut::<pb::EdgeExpand>(ptr) {
+                        Ok(expand) => expand,
+                        Err(e) => return e,
+                    };
                     expand.v_tag = pb;
-                    std::mem::forget(expand);
                 }
                 InnerOpt::GetV => {
-                    let mut getv = unsafe { Box::from_raw(ptr as *mut pb::GetV) };
+                    let mut getv = match handle::borrow_mut::<pb::GetV>(ptr) {
+                        Ok(getv) => getv,
+                        Err(e) => return e,
+                    };
                     getv.tag = pb;
-                    std::mem::forget(getv);
                 }
                 InnerOpt::PathExpand => {
-                    let mut pathxpd = unsafe { Box::from_raw(ptr as *mut pb::PathExpand) };
+                    let mut pathxpd = match handle::borrow_mut::<pb::PathExpand>(ptr) {
+                        Ok(pathxpd) => pathxpd,
+                        Err(e) => return e,
+                    };
                     pathxpd.start_tag = pb;
-                    std::mem::forget(pathxpd);
                 }
                 _ => unreachable!(),
             }
@@ -905,116 +3268,183 @@ mod params {
     use std::collections::HashMap;
 
     use super::*;
+    use crate::plan::ffi::graph::FfiDirection;
 
     /// To initialize a query parameters
     #[no_mangle]
-    pub extern "C" fn init_query_params() -> *const c_void {
-        let query_params = Box::new(pb::QueryParams {
-            tables: vec![],
-            columns: vec![],
-            is_all_columns: false,
-            limit: None,
-            predicate: None,
-            sample_ratio: 1.0,
-            extra: HashMap::new(),
-        });
+    pub extern "C" fn init_query_params() -> *const FfiQueryParams {
+        catch_panic(|| {
+            let query_params = pb::QueryParams {
+                tables: vec![],
+                columns: vec![],
+                is_all_columns: false,
+                limit: None,
+                predicate: None,
+                sample_ratio: 1.0,
+                extra: HashMap::new(),
+                degree_predicates: vec![],
+            };
 
-        Box::into_raw(query_params) as *const c_void
+            handle::register(query_params)
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn add_params_table(ptr_params: *const c_void, table: FfiNameOrId) -> FfiResult {
-        let mut params = unsafe { Box::from_raw(ptr_params as *mut pb::QueryParams) };
-        let pb_result = table.try_into();
-        match pb_result {
-            Ok(pb) => {
-                if let Some(table) = pb {
-                    params.tables.push(table)
-                }
-                std::mem::forget(params);
+    pub extern "C" fn add_params_table(ptr_params: *const FfiQueryParams, table: FfiNameOrId) -> FfiResult {
+        catch_panic(|| {
+            let mut params = match handle::borrow_mut::<pb::QueryParams>(ptr_params) {
+                Ok(params) => params,
+                Err(e) => return e,
+            };
+            let pb_result = table.try_into();
+            match pb_result {
+                Ok(pb) => {
+                    if let Some(table) = pb {
+                        params.tables.push(table)
+                    }
 
-                FfiResult::success()
+                    FfiResult::success()
+                }
+                Err(e) => e,
             }
-            Err(e) => e,
-        }
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn add_params_column(ptr_params: *const c_void, col: FfiNameOrId) -> FfiResult {
-        let mut params = unsafe { Box::from_raw(ptr_params as *mut pb::QueryParams) };
-        let pb_result = col.try_into();
-        match pb_result {
-            Ok(pb) => {
-                if let Some(col) = pb {
-                    params.columns.push(col)
-                }
-                std::mem::forget(params);
+    pub extern "C" fn add_params_column(ptr_params: *const FfiQueryParams, col: FfiNameOrId) -> FfiResult {
+        catch_panic(|| {
+            let mut params = match handle::borrow_mut::<pb::QueryParams>(ptr_params) {
+                Ok(params) => params,
+                Err(e) => return e,
+            };
+            let pb_result = col.try_into();
+            match pb_result {
+                Ok(pb) => {
+                    if let Some(col) = pb {
+                        params.columns.push(col)
+                    }
 
-                FfiResult::success()
+                    FfiResult::success()
+                }
+                Err(e) => e,
             }
-            Err(e) => e,
-        }
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn set_params_range(ptr_params: *const c_void, lower: i32, upper: i32) -> FfiResult {
-        set_range(ptr_params, lower, upper, InnerOpt::Params)
+    pub extern "C" fn set_params_range(
+        ptr_params: *const FfiQueryParams, lower: i32, upper: i32,
+    ) -> FfiResult {
+        catch_panic(|| set_range(ptr_params, lower, upper, InnerOpt::Params))
     }
 
     #[no_mangle]
     pub extern "C" fn set_params_predicate(
-        ptr_params: *const c_void, cstr_pred: *const c_char,
+        ptr_params: *const FfiQueryParams, cstr_pred: *const c_char,
     ) -> FfiResult {
-        set_predicate(ptr_params, cstr_pred, InnerOpt::Params)
+        catch_panic(|| set_predicate(ptr_params, cstr_pred, InnerOpt::Params))
     }
 
     #[no_mangle]
     pub extern "C" fn set_params_predicate_pb(
-        ptr_params: *const c_void, ptr_str_pred_pb: FfiPbPointer,
+        ptr_params: *const FfiQueryParams, ptr_str_pred_pb: FfiPbPointer,
+    ) -> FfiResult {
+        catch_panic(|| set_predicate_pb(ptr_params, ptr_str_pred_pb, InnerOpt::Params))
+    }
+
+    /// Set the predicate of the parameters, e.g., of an `EdgeExpand`, as an expression built via the
+    /// apis like `expr_eq_var_const()`, which is copied, and thus must still be released by the caller.
+    #[no_mangle]
+    pub extern "C" fn set_params_predicate_expr(
+        ptr_params: *const FfiQueryParams, ptr_expr: *const FfiExpr,
     ) -> FfiResult {
-        set_predicate_pb(ptr_params, ptr_str_pred_pb, InnerOpt::Params)
+        catch_panic(|| set_predicate_expr(ptr_params, ptr_expr, InnerOpt::Params))
     }
 
     /// Set getting all columns
     #[no_mangle]
-    pub extern "C" fn set_params_is_all_columns(ptr_params: *const c_void) -> FfiResult {
-        let mut params = unsafe { Box::from_raw(ptr_params as *mut pb::QueryParams) };
-        params.is_all_columns = true;
-        std::mem::forget(params);
+    pub extern "C" fn set_params_is_all_columns(ptr_params: *const FfiQueryParams) -> FfiResult {
+        catch_panic(|| {
+            let mut params = match handle::borrow_mut::<pb::QueryParams>(ptr_params) {
+                Ok(params) => params,
+                Err(e) => return e,
+            };
+            params.is_all_columns = true;
 
-        FfiResult::success()
+            FfiResult::success()
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn set_params_sample_ratio(ptr_params: *const c_void, sample_ratio: f64) -> FfiResult {
-        let mut params = unsafe { Box::from_raw(ptr_params as *mut pb::QueryParams) };
-        params.sample_ratio = sample_ratio;
-        std::mem::forget(params);
+    pub extern "C" fn set_params_sample_ratio(
+        ptr_params: *const FfiQueryParams, sample_ratio: f64,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut params = match handle::borrow_mut::<pb::QueryParams>(ptr_params) {
+                Ok(params) => params,
+                Err(e) => return e,
+            };
+            params.sample_ratio = sample_ratio;
 
-        FfiResult::success()
+            FfiResult::success()
+        })
     }
 
     /// Add extra parameters
     #[no_mangle]
     pub extern "C" fn add_params_extra(
-        ptr_params: *const c_void, c_key: *const c_char, c_val: *const c_char,
+        ptr_params: *const FfiQueryParams, c_key: *const c_char, c_val: *const c_char,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut params = unsafe { Box::from_raw(ptr_params as *mut pb::QueryParams) };
-        let key = cstr_to_string(c_key);
-        if key.is_err() {
-            result = key.err().unwrap();
-        } else {
-            let val = cstr_to_string(c_val);
-            if val.is_err() {
-                result = val.err().unwrap();
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut params = match handle::borrow_mut::<pb::QueryParams>(ptr_params) {
+                Ok(params) => params,
+                Err(e) => return e,
+            };
+            let key = cstr_to_string(c_key);
+            if key.is_err() {
+                result = key.err().unwrap();
             } else {
-                params.extra.insert(key.unwrap(), val.unwrap());
+                let val = cstr_to_string(c_val);
+                if val.is_err() {
+                    result = val.err().unwrap();
+                } else {
+                    params.extra.insert(key.unwrap(), val.unwrap());
+                }
             }
-        }
-        std::mem::forget(params);
 
-        result
+            result
+        })
+    }
+
+    /// Add a predicate on the degree of the vertex, i.e., the number of its adjacent edges of the
+    /// table (of any table if not given) in the direction, which must fall into [lower, upper)
+    #[no_mangle]
+    pub extern "C" fn add_params_degree_predicate(
+        ptr_params: *const FfiQueryParams, dir: FfiDirection, table: FfiNameOrId, lower: i32, upper: i32,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut params = match handle::borrow_mut::<pb::QueryParams>(ptr_params) {
+                Ok(params) => params,
+                Err(e) => return e,
+            };
+            let pb_result: Result<Option<common_pb::NameOrId>, FfiResult> = table.try_into();
+            let result = match pb_result {
+                Ok(pb) => {
+                    params
+                        .degree_predicates
+                        .push(pb::DegreePredicate {
+                            direction: unsafe { std::mem::transmute::<FfiDirection, i32>(dir) },
+                            tables: pb.into_iter().collect(),
+                            range: Some(pb::Range { lower, upper }),
+                        });
+                    FfiResult::success()
+                }
+                Err(e) => e,
+            };
+
+            result
+        })
     }
 }
 
@@ -1022,91 +3452,109 @@ mod project {
     use super::*;
     /// To initialize a project operator.
     #[no_mangle]
-    pub extern "C" fn init_project_operator(is_append: i32) -> *const c_void {
-        let project = Box::new(pb::Project {
-            mappings: vec![],
-            is_append: if is_append == 0 { false } else { true },
-            meta_data: vec![],
-        });
-        Box::into_raw(project) as *const c_void
+    pub extern "C" fn init_project_operator(is_append: i32) -> *const FfiProjectOpr {
+        catch_panic(|| {
+            let project = pb::Project {
+                mappings: vec![],
+                is_append: if is_append == 0 { false } else { true },
+                meta_data: vec![],
+            };
+            handle::register(project)
+        })
     }
 
     /// To add a mapping for the project operator, which maps a c-like string to represent an
     /// expression, to a `NameOrId` parameter that represents an alias.
     #[no_mangle]
     pub extern "C" fn add_project_expr_alias(
-        ptr_project: *const c_void, cstr_expr: *const c_char, alias: FfiAlias,
+        ptr_project: *const FfiProjectOpr, cstr_expr: *const c_char, alias: FfiAlias,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut project = unsafe { Box::from_raw(ptr_project as *mut pb::Project) };
-        let expr_pb = cstr_to_expr_pb(cstr_expr);
-        let alias_pb = Option::<common_pb::NameOrId>::try_from(alias);
-
-        if !expr_pb.is_ok() {
-            result = expr_pb.err().unwrap();
-        } else if !alias_pb.is_ok() {
-            result = alias_pb.err().unwrap();
-        } else {
-            let attribute = pb::project::ExprAlias { expr: expr_pb.ok(), alias: alias_pb.unwrap() };
-            project.mappings.push(attribute);
-        }
-        std::mem::forget(project);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut project = match handle::borrow_mut::<pb::Project>(ptr_project) {
+                Ok(project) => project,
+                Err(e) => return e,
+            };
+            let expr_pb = cstr_to_expr_pb(cstr_expr);
+            let alias_pb = Option::<common_pb::NameOrId>::try_from(alias);
 
-        result
+            if !expr_pb.is_ok() {
+                result = expr_pb.err().unwrap();
+            } else if !alias_pb.is_ok() {
+                result = alias_pb.err().unwrap();
+            } else {
+                let attribute = pb::project::ExprAlias { expr: expr_pb.ok(), alias: alias_pb.unwrap() };
+                project.mappings.push(attribute);
+            }
+
+            result
+        })
     }
 
     /// To add a mapping for the project operator, which maps a pb pointer to represent an
     /// expression, and a `NameOrId` parameter that represents an alias.
     #[no_mangle]
     pub extern "C" fn add_project_expr_pb_alias(
-        ptr_project: *const c_void, pb_expr: FfiPbPointer, alias: FfiAlias,
+        ptr_project: *const FfiProjectOpr, pb_expr: FfiPbPointer, alias: FfiAlias,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut project = unsafe { Box::from_raw(ptr_project as *mut pb::Project) };
-        let expr_pb = ptr_to_pb::<common_pb::Expression>(pb_expr);
-        let alias_pb = Option::<common_pb::NameOrId>::try_from(alias);
-
-        if !expr_pb.is_ok() {
-            result = expr_pb.err().unwrap();
-        } else if !alias_pb.is_ok() {
-            result = alias_pb.err().unwrap();
-        } else {
-            let attribute = pb::project::ExprAlias { expr: expr_pb.ok(), alias: alias_pb.unwrap() };
-            project.mappings.push(attribute);
-        }
-        std::mem::forget(project);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut project = match handle::borrow_mut::<pb::Project>(ptr_project) {
+                Ok(project) => project,
+                Err(e) => return e,
+            };
+            let expr_pb = ptr_to_pb::<common_pb::Expression>(pb_expr);
+            let alias_pb = Option::<common_pb::NameOrId>::try_from(alias);
 
-        result
+            if !expr_pb.is_ok() {
+                result = expr_pb.err().unwrap();
+            } else if !alias_pb.is_ok() {
+                result = alias_pb.err().unwrap();
+            } else {
+                let attribute = pb::project::ExprAlias { expr: expr_pb.ok(), alias: alias_pb.unwrap() };
+                project.mappings.push(attribute);
+            }
+
+            result
+        })
     }
 
     /// To add the column's meta for the project operator
     #[no_mangle]
-    pub extern "C" fn add_project_meta(ptr_project: *const c_void, ptr_meta: FfiPbPointer) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut project = unsafe { Box::from_raw(ptr_project as *mut pb::Project) };
-        let type_pb = ptr_to_pb::<pb::MetaData>(ptr_meta);
-        if !type_pb.is_ok() {
-            result = type_pb.err().unwrap();
-        } else {
-            project.meta_data.push(type_pb.unwrap());
-        }
-        std::mem::forget(project);
+    pub extern "C" fn add_project_meta(
+        ptr_project: *const FfiProjectOpr, ptr_meta: FfiPbPointer,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut project = match handle::borrow_mut::<pb::Project>(ptr_project) {
+                Ok(project) => project,
+                Err(e) => return e,
+            };
+            let type_pb = ptr_to_pb::<pb::MetaData>(ptr_meta);
+            if !type_pb.is_ok() {
+                result = type_pb.err().unwrap();
+            } else {
+                project.meta_data.push(type_pb.unwrap());
+            }
 
-        result
+            result
+        })
     }
 
     /// Append a project operator to the logical plan. To do so, one specifies the following arguments:
     /// * `ptr_plan`: A rust-owned pointer created by `init_logical_plan()`.
     /// * `ptr_project`: A rust-owned pointer created by `init_project_operator()`.
     /// * `parent_id`: The unique parent operator's index in the logical plan, which must be present
-    /// except when a negative id is provided to bypass the setting of parent operator.
+    /// except when [`ROOT_PARENT_ID`] is provided to append the operator as a root of the plan.
+    /// Any other negative id is rejected as a `NegativeIndexError`.
     /// * `id`: An index pointer that gonna hold the index of this operator.
     ///
     /// If it is successful to be appended to the logical plan, the `ptr_project` will be
     /// automatically released by the rust program. Therefore, the caller needs not to deallocate
-    /// the pointer, and must **not** use it thereafter.
+    /// the pointer, and must **not** use it thereafter, which fails with an `InvalidHandleError`.
     ///
-    /// Otherwise, user can manually call [`destroy_project_operator()`] to release the pointer.
+    /// Otherwise, the pointer remains valid, and user can manually call [`destroy_project_operator()`]
+    /// to release it.
     ///
     /// # Return
     /// * Returning [`FfiResult`] to capture any error.
@@ -1115,15 +3563,14 @@ mod project {
     ///
     #[no_mangle]
     pub extern "C" fn append_project_operator(
-        ptr_plan: *const c_void, ptr_project: *const c_void, parent_id: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_project: *const FfiProjectOpr, parent_id: i32, id: *mut i32,
     ) -> FfiResult {
-        let project = unsafe { Box::from_raw(ptr_project as *mut pb::Project) };
-        append_operator(ptr_plan, project.as_ref().clone().into(), vec![parent_id], id)
+        catch_panic(|| append_operator_handle::<pb::Project>(ptr_plan, ptr_project, vec![parent_id], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_project_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Project>(ptr)
+    pub extern "C" fn destroy_project_operator(ptr: *const FfiProjectOpr) {
+        catch_panic(|| destroy_handle::<pb::Project>(ptr))
     }
 }
 
@@ -1132,39 +3579,214 @@ mod select {
 
     /// To initialize a select operator
     #[no_mangle]
-    pub extern "C" fn init_select_operator() -> *const c_void {
-        let select = Box::new(pb::Select { predicate: None });
-        Box::into_raw(select) as *const c_void
+    pub extern "C" fn init_select_operator() -> *const FfiSelectOpr {
+        catch_panic(|| {
+            let select = pb::Select { predicate: None };
+            handle::register(select)
+        })
     }
 
     /// To set a select operator's metadata, which is a predicate represented as a c-string.
     #[no_mangle]
     pub extern "C" fn set_select_predicate(
-        ptr_select: *const c_void, cstr_predicate: *const c_char,
+        ptr_select: *const FfiSelectOpr, cstr_predicate: *const c_char,
     ) -> FfiResult {
-        set_predicate(ptr_select, cstr_predicate, InnerOpt::Select)
+        catch_panic(|| set_predicate(ptr_select, cstr_predicate, InnerOpt::Select))
     }
 
     /// To set a select operator's metadata, which is a predicate represented as a pb pointer.
     #[no_mangle]
     pub extern "C" fn set_select_predicate_pb(
-        ptr_select: *const c_void, ptr_predicate_pb: FfiPbPointer,
+        ptr_select: *const FfiSelectOpr, ptr_predicate_pb: FfiPbPointer,
+    ) -> FfiResult {
+        catch_panic(|| set_predicate_pb(ptr_select, ptr_predicate_pb, InnerOpt::Select))
+    }
+
+    /// Set the predicate of a select operator as an expression built via the apis like
+    /// `expr_eq_var_const()`, which is copied, and thus must still be released by the caller.
+    #[no_mangle]
+    pub extern "C" fn set_select_predicate_expr(
+        ptr_select: *const FfiSelectOpr, ptr_expr: *const FfiExpr,
     ) -> FfiResult {
-        set_predicate_pb(ptr_select, ptr_predicate_pb, InnerOpt::Select)
+        catch_panic(|| set_predicate_expr(ptr_select, ptr_expr, InnerOpt::Select))
     }
 
     /// Append a select operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_select_operator(
-        ptr_plan: *const c_void, ptr_select: *const c_void, parent_id: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_select: *const FfiSelectOpr, parent_id: i32, id: *mut i32,
+    ) -> FfiResult {
+        catch_panic(|| append_operator_handle::<pb::Select>(ptr_plan, ptr_select, vec![parent_id], id))
+    }
+
+    #[no_mangle]
+    pub extern "C" fn destroy_select_operator(ptr: *const FfiSelectOpr) {
+        catch_panic(|| destroy_handle::<pb::Select>(ptr))
+    }
+}
+
+mod expr {
+    use ir_common::expr_parse::ast::BinaryOp;
+
+    use super::*;
+
+    #[allow(dead_code)]
+    #[repr(i32)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum FfiBinaryOp {
+        Add = 0,
+        Sub = 1,
+        Mul = 2,
+        Div = 3,
+        Mod = 4,
+        Exp = 5,
+        BitAnd = 6,
+        BitOr = 7,
+        BitXor = 8,
+        BitLShift = 9,
+        BitRShift = 10,
+        Eq = 11,
+        Ne = 12,
+        Lt = 13,
+        Le = 14,
+        Gt = 15,
+        Ge = 16,
+        And = 17,
+        Or = 18,
+        Within = 19,
+        Without = 20,
+        StartsWith = 21,
+        EndsWith = 22,
+    }
+
+    impl From<FfiBinaryOp> for BinaryOp {
+        fn from(op: FfiBinaryOp) -> Self {
+            match op {
+                FfiBinaryOp::Add => BinaryOp::Add,
+                FfiBinaryOp::Sub => BinaryOp::Sub,
+                FfiBinaryOp::Mul => BinaryOp::Mul,
+                FfiBinaryOp::Div => BinaryOp::Div,
+                FfiBinaryOp::Mod => BinaryOp::Mod,
+                FfiBinaryOp::Exp => BinaryOp::Exp,
+                FfiBinaryOp::BitAnd => BinaryOp::BitAnd,
+                FfiBinaryOp::BitOr => BinaryOp::BitOr,
+                FfiBinaryOp::BitXor => BinaryOp::BitXor,
+                FfiBinaryOp::BitLShift => BinaryOp::BitLShift,
+                FfiBinaryOp::BitRShift => BinaryOp::BitRShift,
+                FfiBinaryOp::Eq => BinaryOp::Eq,
+                FfiBinaryOp::Ne => BinaryOp::Ne,
+                FfiBinaryOp::Lt => BinaryOp::Lt,
+                FfiBinaryOp::Le => BinaryOp::Le,
+                FfiBinaryOp::Gt => BinaryOp::Gt,
+                FfiBinaryOp::Ge => BinaryOp::Ge,
+                FfiBinaryOp::And => BinaryOp::And,
+                FfiBinaryOp::Or => BinaryOp::Or,
+                FfiBinaryOp::Within => BinaryOp::Within,
+                FfiBinaryOp::Without => BinaryOp::Without,
+                FfiBinaryOp::StartsWith => BinaryOp::StartsWith,
+                FfiBinaryOp::EndsWith => BinaryOp::EndsWith,
+            }
+        }
+    }
+
+    fn register_expr(result: Result<Expr, FfiResult>, expr: *mut *const FfiExpr) -> FfiResult {
+        // checked ahead, as the expression is otherwise registered without being released
+        if let Err(e) = check_not_null(expr, "output expression") {
+            return e;
+        }
+        set_output(expr, result.map(|result| handle::register::<_, FfiExpr>(result)))
+    }
+
+    /// Build an expression of the variable, which is written to `expr`, and must be released via
+    /// [`destroy_expr`]. The expressions are built programmatically by such apis, rather than printed
+    /// as the strings to be parsed again, and are given to the plan as pb (see [`expr_to_pb`]).
+    #[no_mangle]
+    pub extern "C" fn expr_var(var: FfiVariable, expr: *mut *const FfiExpr) -> FfiResult {
+        catch_panic(|| register_expr(common_pb::Variable::try_from(var).map(Expr::Var), expr))
+    }
+
+    /// Build an expression of the constant, which is written to `expr`.
+    #[no_mangle]
+    pub extern "C" fn expr_const(value: FfiConst, expr: *mut *const FfiExpr) -> FfiResult {
+        catch_panic(|| register_expr(common_pb::Value::try_from(value).map(Expr::Const), expr))
+    }
+
+    /// Build an expression of the binary operation of the left and right expressions, which is
+    /// written to `expr`. The operands are copied, and thus must still be released by the caller.
+    #[no_mangle]
+    pub extern "C" fn expr_binary(
+        op: FfiBinaryOp, left: *const FfiExpr, right: *const FfiExpr, expr: *mut *const FfiExpr,
+    ) -> FfiResult {
+        catch_panic(|| build_binary(op.into(), left, right, expr))
+    }
+
+    fn build_binary(
+        op: BinaryOp, left: *const FfiExpr, right: *const FfiExpr, expr: *mut *const FfiExpr,
+    ) -> FfiResult {
+        let result = handle::borrow::<Expr>(left).and_then(|left| {
+            handle::borrow::<Expr>(right).map(|right| Expr::binary(op, (*left).clone(), (*right).clone()))
+        });
+        register_expr(result, expr)
+    }
+
+    /// Build an expression that the variable equals the constant, e.g., `@a.name == "John"`, which is
+    /// written to `expr`. As the constant is given as a value, a string constant needs no escaping.
+    #[no_mangle]
+    pub extern "C" fn expr_eq_var_const(
+        var: FfiVariable, value: FfiConst, expr: *mut *const FfiExpr,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let result = common_pb::Variable::try_from(var).and_then(|var| {
+                common_pb::Value::try_from(value)
+                    .map(|value| Expr::binary(BinaryOp::Eq, Expr::Var(var), Expr::Const(value)))
+            });
+            register_expr(result, expr)
+        })
+    }
+
+    /// Build the conjunction of the left and right expressions, which is written to `expr`.
+    /// The operands are copied, and thus must still be released by the caller.
+    #[no_mangle]
+    pub extern "C" fn expr_and(
+        left: *const FfiExpr, right: *const FfiExpr, expr: *mut *const FfiExpr,
+    ) -> FfiResult {
+        catch_panic(|| build_binary(BinaryOp::And, left, right, expr))
+    }
+
+    /// Build the disjunction of the left and right expressions, which is written to `expr`.
+    /// The operands are copied, and thus must still be released by the caller.
+    #[no_mangle]
+    pub extern "C" fn expr_or(
+        left: *const FfiExpr, right: *const FfiExpr, expr: *mut *const FfiExpr,
     ) -> FfiResult {
-        let select = unsafe { Box::from_raw(ptr_select as *mut pb::Select) };
-        append_operator(ptr_plan, select.as_ref().clone().into(), vec![parent_id], id)
+        catch_panic(|| build_binary(BinaryOp::Or, left, right, expr))
+    }
+
+    /// Build an expression of the logical negation of the given one, which is written to `expr`.
+    /// The operand is copied, and thus must still be released by the caller.
+    #[no_mangle]
+    pub extern "C" fn expr_not(operand: *const FfiExpr, expr: *mut *const FfiExpr) -> FfiResult {
+        catch_panic(|| {
+            let result = handle::borrow::<Expr>(operand).map(|operand| Expr::not((*operand).clone()));
+            register_expr(result, expr)
+        })
+    }
+
+    /// The expression as the bytes of a `common.Expression`, which is given to the apis taking the
+    /// expressions as pb, e.g., `set_select_predicate_pb()`.
+    #[no_mangle]
+    pub extern "C" fn expr_to_pb(ptr_expr: *const FfiExpr) -> FfiData {
+        catch_panic(|| match handle::borrow::<Expr>(ptr_expr) {
+            Ok(expr) => common_pb::Expression::from((*expr).clone())
+                .encode_to_vec()
+                .into(),
+            Err(e) => e.into(),
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_select_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Select>(ptr)
+    pub extern "C" fn destroy_expr(ptr: *const FfiExpr) {
+        catch_panic(|| destroy_handle::<Expr>(ptr))
     }
 }
 
@@ -1191,12 +3813,22 @@ mod join {
         Times = 6,
     }
 
+    #[allow(dead_code)]
+    #[repr(i32)]
+    #[derive(Copy, Clone, Debug)]
+    pub enum FfiJoinSide {
+        Left = 0,
+        Right = 1,
+    }
+
     /// To initialize a join operator
     #[no_mangle]
-    pub extern "C" fn init_join_operator(join_kind: FfiJoinKind) -> *const c_void {
-        let kind = unsafe { std::mem::transmute(join_kind) };
-        let join = Box::new(pb::Join { left_keys: vec![], right_keys: vec![], kind });
-        Box::into_raw(join) as *const c_void
+    pub extern "C" fn init_join_operator(join_kind: FfiJoinKind) -> *const FfiJoinOpr {
+        catch_panic(|| {
+            let kind = unsafe { std::mem::transmute(join_kind) };
+            let join = pb::Join { left_keys: vec![], right_keys: vec![], kind, renames: vec![] };
+            handle::register(join)
+        })
     }
 
     /// To add a join operator's metadata, which is a pair of left and right keys.
@@ -1204,103 +3836,182 @@ mod join {
     /// regarding left and right keys are **equivalent**.
     #[no_mangle]
     pub extern "C" fn add_join_key_pair(
-        ptr_join: *const c_void, left_key: FfiVariable, right_key: FfiVariable,
+        ptr_join: *const FfiJoinOpr, left_key: FfiVariable, right_key: FfiVariable,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut join = unsafe { Box::from_raw(ptr_join as *mut pb::Join) };
-        let left_key_pb = left_key.try_into();
-        let right_key_pb = right_key.try_into();
-        if left_key_pb.is_err() {
-            result = left_key_pb.err().unwrap();
-        } else if right_key_pb.is_err() {
-            result = right_key_pb.err().unwrap();
-        } else {
-            join.left_keys.push(left_key_pb.unwrap());
-            join.right_keys.push(right_key_pb.unwrap());
-        }
-        std::mem::forget(join);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut join = match handle::borrow_mut::<pb::Join>(ptr_join) {
+                Ok(join) => join,
+                Err(e) => return e,
+            };
+            let left_key_pb = left_key.try_into();
+            let right_key_pb = right_key.try_into();
+            if left_key_pb.is_err() {
+                result = left_key_pb.err().unwrap();
+            } else if right_key_pb.is_err() {
+                result = right_key_pb.err().unwrap();
+            } else {
+                join.left_keys.push(left_key_pb.unwrap());
+                join.right_keys.push(right_key_pb.unwrap());
+            }
 
-        result
+            result
+        })
     }
 
     /// To add a join operator's metadata, which is a pair of left and right keys.
     /// The left and right keys are represented as a pb pointer.
     #[no_mangle]
     pub extern "C" fn add_join_key_pair_pb(
-        ptr_join: *const c_void, left_key: FfiPbPointer, right_key: FfiPbPointer,
+        ptr_join: *const FfiJoinOpr, left_key: FfiPbPointer, right_key: FfiPbPointer,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut join = unsafe { Box::from_raw(ptr_join as *mut pb::Join) };
-        let left_key_pb = ptr_to_pb::<common_pb::Variable>(left_key);
-        let right_key_pb = ptr_to_pb::<common_pb::Variable>(right_key);
-        if left_key_pb.is_err() {
-            result = left_key_pb.err().unwrap();
-        } else if right_key_pb.is_err() {
-            result = right_key_pb.err().unwrap();
-        } else {
-            join.left_keys.push(left_key_pb.unwrap());
-            join.right_keys.push(right_key_pb.unwrap());
-        }
-        std::mem::forget(join);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut join = match handle::borrow_mut::<pb::Join>(ptr_join) {
+                Ok(join) => join,
+                Err(e) => return e,
+            };
+            let left_key_pb = ptr_to_pb::<common_pb::Variable>(left_key);
+            let right_key_pb = ptr_to_pb::<common_pb::Variable>(right_key);
+            if left_key_pb.is_err() {
+                result = left_key_pb.err().unwrap();
+            } else if right_key_pb.is_err() {
+                result = right_key_pb.err().unwrap();
+            } else {
+                join.left_keys.push(left_key_pb.unwrap());
+                join.right_keys.push(right_key_pb.unwrap());
+            }
 
-        result
+            result
+        })
+    }
+
+    /// To rename the tag `from` defined by the given side of the join as `to`. If both sides define
+    /// the same tag, the left one takes precedence unless either of them is renamed.
+    #[no_mangle]
+    pub extern "C" fn add_join_rename(
+        ptr_join: *const FfiJoinOpr, side: FfiJoinSide, from: FfiNameOrId, to: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut join = match handle::borrow_mut::<pb::Join>(ptr_join) {
+                Ok(join) => join,
+                Err(e) => return e,
+            };
+            let from_pb: Result<Option<common_pb::NameOrId>, FfiResult> = from.try_into();
+            let to_pb: Result<Option<common_pb::NameOrId>, FfiResult> = to.try_into();
+            match (from_pb, to_pb) {
+                (Ok(Some(from)), Ok(Some(to))) => join.renames.push(pb::join::Rename {
+                    side: unsafe { std::mem::transmute(side) },
+                    from: Some(from),
+                    to: Some(to),
+                }),
+                (Err(e), _) | (_, Err(e)) => result = e,
+                _ => {
+                    result = FfiResult::new(
+                        ResultCode::MissingDataError,
+                        "both tags of the rename of `Join` must be given".to_string(),
+                    )
+                }
+            }
+
+            result
+        })
     }
 
     /// Append a join operator to the logical plan. Note that both left and right parent ids
     /// for join must be non-negative, and they must refer some nodes in the logical plan
     #[no_mangle]
     pub extern "C" fn append_join_operator(
-        ptr_plan: *const c_void, ptr_join: *const c_void, parent_left: i32, parent_right: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_join: *const FfiJoinOpr, parent_left: i32, parent_right: i32,
+        id: *mut i32,
     ) -> FfiResult {
-        if parent_left < 0 || parent_right < 0 {
-            FfiResult::new(
-                ResultCode::NegativeIndexError,
-                format!("invalid left parent {:?}, or right {:?}", parent_left, parent_right),
-            )
-        } else {
-            let join = unsafe { Box::from_raw(ptr_join as *mut pb::Join) };
-            append_operator(ptr_plan, join.as_ref().clone().into(), vec![parent_left, parent_right], id)
-        }
+        catch_panic(|| {
+            if parent_left < 0 || parent_right < 0 {
+                FfiResult::new(
+                    ResultCode::NegativeIndexError,
+                    format!("invalid left parent {:?}, or right {:?}", parent_left, parent_right),
+                )
+            } else {
+                append_operator_handle::<pb::Join>(ptr_plan, ptr_join, vec![parent_left, parent_right], id)
+            }
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_join_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Join>(ptr)
+    pub extern "C" fn destroy_join_operator(ptr: *const FfiJoinOpr) {
+        catch_panic(|| destroy_handle::<pb::Join>(ptr))
     }
 }
 
 mod union {
     use super::*;
 
+    #[allow(dead_code)]
+    #[repr(i32)]
+    #[derive(Clone, Copy)]
+    pub enum FfiUnionAlignment {
+        None = 0,
+        Strict = 1,
+        Project = 2,
+    }
+
     /// To initialize a union operator
     #[no_mangle]
-    pub extern "C" fn init_union_operator() -> *const c_void {
-        let union = Box::new(pb::Union { parents: vec![] });
-        Box::into_raw(union) as *const c_void
+    pub extern "C" fn init_union_operator() -> *const FfiUnionOpr {
+        catch_panic(|| {
+            let union = pb::Union { parents: vec![], alignment: 0 };
+            handle::register(union)
+        })
     }
 
     /// Add the subtask parent id to Union
     #[no_mangle]
-    pub extern "C" fn add_union_parent(ptr_union: *const c_void, parent_id: i32) -> FfiResult {
-        let mut union = unsafe { Box::from_raw(ptr_union as *mut pb::Union) };
-        union.parents.push(parent_id);
-        std::mem::forget(union);
+    pub extern "C" fn add_union_parent(ptr_union: *const FfiUnionOpr, parent_id: i32) -> FfiResult {
+        catch_panic(|| {
+            let mut union = match handle::borrow_mut::<pb::Union>(ptr_union) {
+                Ok(union) => union,
+                Err(e) => return e,
+            };
+            union.parents.push(parent_id);
 
-        FfiResult::success()
+            FfiResult::success()
+        })
+    }
+
+    /// Set how to align the tags produced by the branches of the union
+    #[no_mangle]
+    pub extern "C" fn set_union_alignment(
+        ptr_union: *const FfiUnionOpr, alignment: FfiUnionAlignment,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut union = match handle::borrow_mut::<pb::Union>(ptr_union) {
+                Ok(union) => union,
+                Err(e) => return e,
+            };
+            union.alignment = alignment as i32;
+
+            FfiResult::success()
+        })
     }
 
     /// Append a Union operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_union_operator(
-        ptr_plan: *const c_void, ptr_union: *const c_void, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_union: *const FfiUnionOpr, id: *mut i32,
     ) -> FfiResult {
-        let union_opr = unsafe { Box::from_raw(ptr_union as *mut pb::Union) };
-        append_operator(ptr_plan, union_opr.as_ref().clone().into(), union_opr.parents, id)
+        catch_panic(|| {
+            let parents = match handle::borrow::<pb::Union>(ptr_union) {
+                Ok(union_opr) => union_opr.parents.clone(),
+                Err(e) => return e,
+            };
+            append_operator_handle::<pb::Union>(ptr_plan, ptr_union, parents, id)
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_union_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Union>(ptr)
+    pub extern "C" fn destroy_union_operator(ptr: *const FfiUnionOpr) {
+        catch_panic(|| destroy_handle::<pb::Union>(ptr))
     }
 }
 
@@ -1309,9 +4020,12 @@ mod groupby {
 
     /// To initialize a groupby operator
     #[no_mangle]
-    pub extern "C" fn init_groupby_operator() -> *const c_void {
-        let group = Box::new(pb::GroupBy { mappings: vec![], functions: vec![], meta_data: vec![] });
-        Box::into_raw(group) as *const c_void
+    pub extern "C" fn init_groupby_operator() -> *const FfiGroupByOpr {
+        catch_panic(|| {
+            let group =
+                pb::GroupBy { mappings: vec![], functions: vec![], meta_data: vec![], null_key_opt: 0 };
+            handle::register(group)
+        })
     }
 
     #[allow(dead_code)]
@@ -1328,6 +4042,23 @@ mod groupby {
         Avg = 7,
     }
 
+    #[allow(dead_code)]
+    #[repr(i32)]
+    #[derive(Clone, Copy)]
+    pub enum FfiAggEquality {
+        Default = 0,
+        ById = 1,
+        ByValue = 2,
+    }
+
+    #[allow(dead_code)]
+    #[repr(i32)]
+    #[derive(Clone, Copy)]
+    pub enum FfiNullKeyOpt {
+        GroupAsNull = 0,
+        Drop = 1,
+    }
+
     /*
     #[repr(C)]
     pub struct FfiAggFn {
@@ -1344,6 +4075,9 @@ mod groupby {
                 vars: vec![],
                 aggregate: unsafe { std::mem::transmute::<FfiAggOpt, i32>(value.aggregate) },
                 alias: None,
+                equality: 0,
+                overflow: 0,
+                approximation: None,
             };
             let (vars, alias) = (value.vars as *mut Vec<FfiVariable>, value.alias);
             let vars: Box<Vec<FfiVariable>> = unsafe { Box::from_raw(vars) };
@@ -1375,136 +4109,235 @@ mod groupby {
     /// Add the key (and its alias if any) according to which the grouping is conducted
     #[no_mangle]
     pub extern "C" fn add_groupby_key_alias(
-        ptr_groupby: *const c_void, key: FfiVariable, alias: FfiAlias,
+        ptr_groupby: *const FfiGroupByOpr, key: FfiVariable, alias: FfiAlias,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut group = unsafe { Box::from_raw(ptr_groupby as *mut pb::GroupBy) };
-        let key_pb = key.try_into();
-        let alias_pb = alias.try_into();
-
-        if key_pb.is_ok() && alias_pb.is_ok() {
-            group
-                .mappings
-                .push(pb::group_by::KeyAlias { key: key_pb.ok(), alias: alias_pb.unwrap() });
-        } else if key_pb.is_err() {
-            result = key_pb.err().unwrap();
-        } else {
-            result = alias_pb.err().unwrap();
-        }
-        std::mem::forget(group);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut group = match handle::borrow_mut::<pb::GroupBy>(ptr_groupby) {
+                Ok(group) => group,
+                Err(e) => return e,
+            };
+            let key_pb = key.try_into();
+            let alias_pb = alias.try_into();
+
+            if key_pb.is_ok() && alias_pb.is_ok() {
+                group
+                    .mappings
+                    .push(pb::group_by::KeyAlias { key: key_pb.ok(), alias: alias_pb.unwrap() });
+            } else if key_pb.is_err() {
+                result = key_pb.err().unwrap();
+            } else {
+                result = alias_pb.err().unwrap();
+            }
 
-        result
+            result
+        })
     }
 
     /// Add the key (and its alias if any) according to which the grouping is conducted.
     /// The key is represented as a pb pointer.
     #[no_mangle]
-    pub extern "C" fn add_groupby_key_pb_alias(
-        ptr_groupby: *const c_void, key: FfiPbPointer, alias: FfiAlias,
+    pub extern "C" fn add_groupby_key_pb_alias(
+        ptr_groupby: *const FfiGroupByOpr, key: FfiPbPointer, alias: FfiAlias,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut group = match handle::borrow_mut::<pb::GroupBy>(ptr_groupby) {
+                Ok(group) => group,
+                Err(e) => return e,
+            };
+            let key_pb = ptr_to_pb::<common_pb::Variable>(key);
+            let alias_pb = alias.try_into();
+
+            if key_pb.is_ok() && alias_pb.is_ok() {
+                group
+                    .mappings
+                    .push(pb::group_by::KeyAlias { key: key_pb.ok(), alias: alias_pb.unwrap() });
+            } else if key_pb.is_err() {
+                result = key_pb.err().unwrap();
+            } else {
+                result = alias_pb.err().unwrap();
+            }
+
+            result
+        })
+    }
+
+    /// Set how to deal with the records of which any grouping key is missing (null), which
+    /// are either grouped under the null key (by default), or dropped.
+    #[no_mangle]
+    pub extern "C" fn set_groupby_null_key_opt(
+        ptr_groupby: *const FfiGroupByOpr, opt: FfiNullKeyOpt,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut group = match handle::borrow_mut::<pb::GroupBy>(ptr_groupby) {
+                Ok(group) => group,
+                Err(e) => return e,
+            };
+            group.null_key_opt = opt as i32;
+
+            FfiResult::success()
+        })
+    }
+
+    /// Add the aggregate function for each group.
+    #[no_mangle]
+    pub extern "C" fn add_groupby_agg_fn(
+        ptr_groupby: *const FfiGroupByOpr, agg_val: FfiVariable, agg_opt: FfiAggOpt, alias: FfiAlias,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut group = match handle::borrow_mut::<pb::GroupBy>(ptr_groupby) {
+                Ok(group) => group,
+                Err(e) => return e,
+            };
+            let val_pb = agg_val.try_into();
+            let aggregate = unsafe { std::mem::transmute::<FfiAggOpt, i32>(agg_opt) };
+            let alias_pb = alias.try_into();
+            if val_pb.is_ok() && alias_pb.is_ok() {
+                group.functions.push(pb::group_by::AggFunc {
+                    vars: vec![val_pb.unwrap()],
+                    aggregate,
+                    alias: alias_pb.unwrap(),
+                    equality: 0,
+                    overflow: 0,
+                    approximation: None,
+                });
+            } else if val_pb.is_err() {
+                result = val_pb.err().unwrap();
+            } else {
+                result = alias_pb.err().unwrap();
+            }
+
+            result
+        })
+    }
+
+    /// Add the aggregate function for each group.
+    /// The aggregation function is represented as a pb pointer.
+    #[no_mangle]
+    pub extern "C" fn add_groupby_agg_fn_pb(
+        ptr_groupby: *const FfiGroupByOpr, agg_val: FfiPbPointer, agg_opt: FfiAggOpt, alias: FfiAlias,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut group = unsafe { Box::from_raw(ptr_groupby as *mut pb::GroupBy) };
-        let key_pb = ptr_to_pb::<common_pb::Variable>(key);
-        let alias_pb = alias.try_into();
-
-        if key_pb.is_ok() && alias_pb.is_ok() {
-            group
-                .mappings
-                .push(pb::group_by::KeyAlias { key: key_pb.ok(), alias: alias_pb.unwrap() });
-        } else if key_pb.is_err() {
-            result = key_pb.err().unwrap();
-        } else {
-            result = alias_pb.err().unwrap();
-        }
-        std::mem::forget(group);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut group = match handle::borrow_mut::<pb::GroupBy>(ptr_groupby) {
+                Ok(group) => group,
+                Err(e) => return e,
+            };
+            let val_pb = ptr_to_pb::<common_pb::Variable>(agg_val);
+            let aggregate = unsafe { std::mem::transmute::<FfiAggOpt, i32>(agg_opt) };
+            let alias_pb = alias.try_into();
+            if val_pb.is_ok() && alias_pb.is_ok() {
+                group.functions.push(pb::group_by::AggFunc {
+                    vars: vec![val_pb.unwrap()],
+                    aggregate,
+                    alias: alias_pb.unwrap(),
+                    equality: 0,
+                    overflow: 0,
+                    approximation: None,
+                });
+            } else if val_pb.is_err() {
+                result = val_pb.err().unwrap();
+            } else {
+                result = alias_pb.err().unwrap();
+            }
 
-        result
+            result
+        })
     }
 
-    /// Add the aggregate function for each group.
+    /// Set how the values of the `index`-th aggregate function are deduplicated, namely, by the
+    /// ids of the graph elements, or by their full values including the properties. It only
+    /// applies to `ToSet` and `CountDistinct`, and is validated while appending the operator.
     #[no_mangle]
-    pub extern "C" fn add_groupby_agg_fn(
-        ptr_groupby: *const c_void, agg_val: FfiVariable, agg_opt: FfiAggOpt, alias: FfiAlias,
+    pub extern "C" fn set_groupby_agg_fn_equality(
+        ptr_groupby: *const FfiGroupByOpr, index: i32, equality: FfiAggEquality,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut group = unsafe { Box::from_raw(ptr_groupby as *mut pb::GroupBy) };
-        let val_pb = agg_val.try_into();
-        let aggregate = unsafe { std::mem::transmute::<FfiAggOpt, i32>(agg_opt) };
-        let alias_pb = alias.try_into();
-        if val_pb.is_ok() && alias_pb.is_ok() {
-            group.functions.push(pb::group_by::AggFunc {
-                vars: vec![val_pb.unwrap()],
-                aggregate,
-                alias: alias_pb.unwrap(),
-            });
-        } else if val_pb.is_err() {
-            result = val_pb.err().unwrap();
-        } else {
-            result = alias_pb.err().unwrap();
-        }
-        std::mem::forget(group);
+        catch_panic(|| {
+            let mut group = match handle::borrow_mut::<pb::GroupBy>(ptr_groupby) {
+                Ok(group) => group,
+                Err(e) => return e,
+            };
+            let result = if index < 0 {
+                FfiResult::new(ResultCode::NegativeIndexError, format!("invalid index {:?}", index))
+            } else if let Some(agg_fn) = group.functions.get_mut(index as usize) {
+                agg_fn.equality = equality as i32;
+                FfiResult::success()
+            } else {
+                FfiResult::new(
+                    ResultCode::MissingDataError,
+                    format!("the {:?}-th aggregate function is missing", index),
+                )
+            };
 
-        result
+            result
+        })
     }
 
-    /// Add the aggregate function for each group.
-    /// The aggregation function is represented as a pb pointer.
+    /// Approximate the `index`-th aggregate function, which must be a `CountDistinct`, by a
+    /// HyperLogLog sketch of `2^precision` registers, where the precision ranges over [4, 18]. It
+    /// bounds the memory of counting a huge number of distinct values, at the cost of a standard
+    /// error of about `1.04 / sqrt(2^precision)`, and is validated while appending the operator.
     #[no_mangle]
-    pub extern "C" fn add_groupby_agg_fn_pb(
-        ptr_groupby: *const c_void, agg_val: FfiPbPointer, agg_opt: FfiAggOpt, alias: FfiAlias,
+    pub extern "C" fn set_groupby_agg_fn_approximation(
+        ptr_groupby: *const FfiGroupByOpr, index: i32, precision: i32,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut group = unsafe { Box::from_raw(ptr_groupby as *mut pb::GroupBy) };
-        let val_pb = ptr_to_pb::<common_pb::Variable>(agg_val);
-        let aggregate = unsafe { std::mem::transmute::<FfiAggOpt, i32>(agg_opt) };
-        let alias_pb = alias.try_into();
-        if val_pb.is_ok() && alias_pb.is_ok() {
-            group.functions.push(pb::group_by::AggFunc {
-                vars: vec![val_pb.unwrap()],
-                aggregate,
-                alias: alias_pb.unwrap(),
-            });
-        } else if val_pb.is_err() {
-            result = val_pb.err().unwrap();
-        } else {
-            result = alias_pb.err().unwrap();
-        }
-        std::mem::forget(group);
+        catch_panic(|| {
+            let mut group = match handle::borrow_mut::<pb::GroupBy>(ptr_groupby) {
+                Ok(group) => group,
+                Err(e) => return e,
+            };
+            let result = if index < 0 {
+                FfiResult::new(ResultCode::NegativeIndexError, format!("invalid index {:?}", index))
+            } else if let Some(agg_fn) = group.functions.get_mut(index as usize) {
+                agg_fn.approximation = Some(pb::group_by::agg_func::Approximation { precision });
+                FfiResult::success()
+            } else {
+                FfiResult::new(
+                    ResultCode::MissingDataError,
+                    format!("the {:?}-th aggregate function is missing", index),
+                )
+            };
 
-        result
+            result
+        })
     }
 
     /// Add the op_type for group keys or values.
     #[no_mangle]
     pub extern "C" fn add_groupby_key_value_meta(
-        ptr_groupby: *const c_void, ptr_meta: FfiPbPointer,
+        ptr_groupby: *const FfiGroupByOpr, ptr_meta: FfiPbPointer,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut group = unsafe { Box::from_raw(ptr_groupby as *mut pb::GroupBy) };
-        let meta_pb = ptr_to_pb::<pb::MetaData>(ptr_meta);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut group = match handle::borrow_mut::<pb::GroupBy>(ptr_groupby) {
+                Ok(group) => group,
+                Err(e) => return e,
+            };
+            let meta_pb = ptr_to_pb::<pb::MetaData>(ptr_meta);
 
-        if !meta_pb.is_ok() {
-            result = meta_pb.err().unwrap();
-        } else {
-            group.meta_data.push(meta_pb.unwrap());
-        }
-        std::mem::forget(group);
+            if !meta_pb.is_ok() {
+                result = meta_pb.err().unwrap();
+            } else {
+                group.meta_data.push(meta_pb.unwrap());
+            }
 
-        result
+            result
+        })
     }
 
     /// Append a groupby operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_groupby_operator(
-        ptr_plan: *const c_void, ptr_groupby: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_groupby: *const FfiGroupByOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let group = unsafe { Box::from_raw(ptr_groupby as *mut pb::GroupBy) };
-        append_operator(ptr_plan, group.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::GroupBy>(ptr_plan, ptr_groupby, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_groupby_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::GroupBy>(ptr)
+    pub extern "C" fn destroy_groupby_operator(ptr: *const FfiGroupByOpr) {
+        catch_panic(|| destroy_handle::<pb::GroupBy>(ptr))
     }
 }
 
@@ -1522,80 +4355,108 @@ mod orderby {
 
     /// To initialize an orderby operator
     #[no_mangle]
-    pub extern "C" fn init_orderby_operator() -> *const c_void {
-        let order = Box::new(pb::OrderBy { pairs: vec![], limit: None });
-        Box::into_raw(order) as *const c_void
+    pub extern "C" fn init_orderby_operator() -> *const FfiOrderByOpr {
+        catch_panic(|| {
+            let order = pb::OrderBy { pairs: vec![], limit: None, per_partition: false };
+            handle::register(order)
+        })
     }
 
     /// Add the pair for conducting ordering.
     #[no_mangle]
     pub extern "C" fn add_orderby_pair(
-        ptr_orderby: *const c_void, var: FfiVariable, order_opt: FfiOrderOpt,
+        ptr_orderby: *const FfiOrderByOpr, var: FfiVariable, order_opt: FfiOrderOpt,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut orderby = unsafe { Box::from_raw(ptr_orderby as *mut pb::OrderBy) };
-        let key_result = var.try_into();
-        if key_result.is_ok() {
-            let order = match order_opt {
-                FfiOrderOpt::Shuffle => 0,
-                FfiOrderOpt::Asc => 1,
-                FfiOrderOpt::Desc => 2,
-            };
-            orderby
-                .pairs
-                .push(pb::order_by::OrderingPair { key: key_result.ok(), order });
-        } else {
-            result = key_result.err().unwrap();
-        }
-        std::mem::forget(orderby);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut orderby = match handle::borrow_mut::<pb::OrderBy>(ptr_orderby) {
+                Ok(orderby) => orderby,
+                Err(e) => return e,
+            };
+            let key_result = var.try_into();
+            if key_result.is_ok() {
+                let order = match order_opt {
+                    FfiOrderOpt::Shuffle => 0,
+                    FfiOrderOpt::Asc => 1,
+                    FfiOrderOpt::Desc => 2,
+                };
+                orderby
+                    .pairs
+                    .push(pb::order_by::OrderingPair { key: key_result.ok(), order });
+            } else {
+                result = key_result.err().unwrap();
+            }
 
-        result
+            result
+        })
     }
 
     /// Add the pair for conducting ordering.
     /// The pair is represented as a pb pointer.
     #[no_mangle]
     pub extern "C" fn add_orderby_pair_pb(
-        ptr_orderby: *const c_void, ptr_var_pb: FfiPbPointer, order_opt: FfiOrderOpt,
+        ptr_orderby: *const FfiOrderByOpr, ptr_var_pb: FfiPbPointer, order_opt: FfiOrderOpt,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut orderby = unsafe { Box::from_raw(ptr_orderby as *mut pb::OrderBy) };
-        let key_result = ptr_to_pb::<common_pb::Variable>(ptr_var_pb);
-        if key_result.is_ok() {
-            let order = match order_opt {
-                FfiOrderOpt::Shuffle => 0,
-                FfiOrderOpt::Asc => 1,
-                FfiOrderOpt::Desc => 2,
-            };
-            orderby
-                .pairs
-                .push(pb::order_by::OrderingPair { key: key_result.ok(), order });
-        } else {
-            result = key_result.err().unwrap();
-        }
-        std::mem::forget(orderby);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut orderby = match handle::borrow_mut::<pb::OrderBy>(ptr_orderby) {
+                Ok(orderby) => orderby,
+                Err(e) => return e,
+            };
+            let key_result = ptr_to_pb::<common_pb::Variable>(ptr_var_pb);
+            if key_result.is_ok() {
+                let order = match order_opt {
+                    FfiOrderOpt::Shuffle => 0,
+                    FfiOrderOpt::Asc => 1,
+                    FfiOrderOpt::Desc => 2,
+                };
+                orderby
+                    .pairs
+                    .push(pb::order_by::OrderingPair { key: key_result.ok(), order });
+            } else {
+                result = key_result.err().unwrap();
+            }
 
-        result
+            result
+        })
     }
 
     /// Set the size limit of the orderby operator, which will turn it into topk
     #[no_mangle]
-    pub extern "C" fn set_orderby_limit(ptr_orderby: *const c_void, lower: i32, upper: i32) -> FfiResult {
-        set_range(ptr_orderby, lower, upper, InnerOpt::OrderBy)
+    pub extern "C" fn set_orderby_limit(
+        ptr_orderby: *const FfiOrderByOpr, lower: i32, upper: i32,
+    ) -> FfiResult {
+        catch_panic(|| set_range(ptr_orderby, lower, upper, InnerOpt::OrderBy))
+    }
+
+    /// Set whether the size limit of the orderby operator applies to each partition on its own,
+    /// rather than to all the partitions as a whole by default
+    #[no_mangle]
+    pub extern "C" fn set_orderby_per_partition(
+        ptr_orderby: *const FfiOrderByOpr, per_partition: bool,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut orderby = match handle::borrow_mut::<pb::OrderBy>(ptr_orderby) {
+                Ok(orderby) => orderby,
+                Err(e) => return e,
+            };
+            orderby.per_partition = per_partition;
+
+            FfiResult::success()
+        })
     }
 
     /// Append an orderby operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_orderby_operator(
-        ptr_plan: *const c_void, ptr_orderby: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_orderby: *const FfiOrderByOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let orderby = unsafe { Box::from_raw(ptr_orderby as *mut pb::OrderBy) };
-        append_operator(ptr_plan, orderby.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::OrderBy>(ptr_plan, ptr_orderby, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_orderby_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::OrderBy>(ptr)
+    pub extern "C" fn destroy_orderby_operator(ptr: *const FfiOrderByOpr) {
+        catch_panic(|| destroy_handle::<pb::OrderBy>(ptr))
     }
 }
 
@@ -1604,54 +4465,121 @@ mod dedup {
 
     /// To initialize a dedup operator
     #[no_mangle]
-    pub extern "C" fn init_dedup_operator() -> *const c_void {
-        let dedup = Box::new(pb::Dedup { keys: vec![] });
-        Box::into_raw(dedup) as *const c_void
+    pub extern "C" fn init_dedup_operator() -> *const FfiDedupOpr {
+        catch_panic(|| {
+            let dedup = pb::Dedup { keys: vec![] };
+            handle::register(dedup)
+        })
     }
 
     /// Add a key for de-duplicating.
     #[no_mangle]
-    pub extern "C" fn add_dedup_key(ptr_dedup: *const c_void, var: FfiVariable) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut dedup = unsafe { Box::from_raw(ptr_dedup as *mut pb::Dedup) };
-        let key_result = var.try_into();
-        match key_result {
-            Ok(key) => dedup.keys.push(key),
-            Err(e) => result = e,
-        }
-        std::mem::forget(dedup);
+    pub extern "C" fn add_dedup_key(ptr_dedup: *const FfiDedupOpr, var: FfiVariable) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut dedup = match handle::borrow_mut::<pb::Dedup>(ptr_dedup) {
+                Ok(dedup) => dedup,
+                Err(e) => return e,
+            };
+            let key_result = var.try_into();
+            match key_result {
+                Ok(key) => dedup.keys.push(key),
+                Err(e) => result = e,
+            }
 
-        result
+            result
+        })
     }
 
     /// Add a key for de-duplicating.
     /// The key is represented as a pb pointer.
     #[no_mangle]
-    pub extern "C" fn add_dedup_key_pb(ptr_dedup: *const c_void, ptr_var_pb: FfiPbPointer) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut dedup = unsafe { Box::from_raw(ptr_dedup as *mut pb::Dedup) };
-        let key_result = ptr_to_pb::<common_pb::Variable>(ptr_var_pb);
-        match key_result {
-            Ok(key) => dedup.keys.push(key),
-            Err(e) => result = e,
-        }
-        std::mem::forget(dedup);
+    pub extern "C" fn add_dedup_key_pb(
+        ptr_dedup: *const FfiDedupOpr, ptr_var_pb: FfiPbPointer,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut dedup = match handle::borrow_mut::<pb::Dedup>(ptr_dedup) {
+                Ok(dedup) => dedup,
+                Err(e) => return e,
+            };
+            let key_result = ptr_to_pb::<common_pb::Variable>(ptr_var_pb);
+            match key_result {
+                Ok(key) => dedup.keys.push(key),
+                Err(e) => result = e,
+            }
 
-        result
+            result
+        })
     }
 
     /// Append a dedup operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_dedup_operator(
-        ptr_plan: *const c_void, ptr_dedup: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_dedup: *const FfiDedupOpr, parent: i32, id: *mut i32,
+    ) -> FfiResult {
+        catch_panic(|| append_operator_handle::<pb::Dedup>(ptr_plan, ptr_dedup, vec![parent], id))
+    }
+
+    #[no_mangle]
+    pub extern "C" fn destroy_dedup_operator(ptr: *const FfiDedupOpr) {
+        catch_panic(|| destroy_handle::<pb::Dedup>(ptr))
+    }
+}
+
+mod count {
+    use super::*;
+
+    /// To initialize a count operator, which counts all the records by default
+    #[no_mangle]
+    pub extern "C" fn init_count_operator() -> *const FfiCountOpr {
+        catch_panic(|| {
+            let count = pb::Count { tag: None, alias: None };
+            handle::register(count)
+        })
+    }
+
+    /// Set the tag to count the presence of, such that only the records where the tag is present are counted
+    #[no_mangle]
+    pub extern "C" fn set_count_tag(ptr_count: *const FfiCountOpr, tag: FfiNameOrId) -> FfiResult {
+        catch_panic(|| {
+            let mut count = match handle::borrow_mut::<pb::Count>(ptr_count) {
+                Ok(count) => count,
+                Err(e) => return e,
+            };
+            let result = tag.try_into().map(|tag| count.tag = tag);
+
+            result.err().unwrap_or_else(FfiResult::success)
+        })
+    }
+
+    /// Set the alias of the count
+    #[no_mangle]
+    pub extern "C" fn set_count_alias(ptr_count: *const FfiCountOpr, alias: FfiAlias) -> FfiResult {
+        catch_panic(|| {
+            let mut count = match handle::borrow_mut::<pb::Count>(ptr_count) {
+                Ok(count) => count,
+                Err(e) => return e,
+            };
+            let result = alias
+                .try_into()
+                .map(|alias| count.alias = alias);
+
+            result.err().unwrap_or_else(FfiResult::success)
+        })
+    }
+
+    /// Append a count operator to the logical plan
+    #[no_mangle]
+    pub extern "C" fn append_count_operator(
+        ptr_plan: *const FfiLogicalPlan, ptr_count: *const FfiCountOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let dedup = unsafe { Box::from_raw(ptr_dedup as *mut pb::Dedup) };
-        append_operator(ptr_plan, dedup.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::Count>(ptr_plan, ptr_count, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_dedup_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Dedup>(ptr)
+    pub extern "C" fn destroy_count_operator(ptr: *const FfiCountOpr) {
+        catch_panic(|| destroy_handle::<pb::Count>(ptr))
     }
 }
 
@@ -1660,9 +4588,11 @@ mod unfold {
 
     /// To initialize an unfold operator
     #[no_mangle]
-    pub extern "C" fn init_unfold_operator() -> *const c_void {
-        let unfold = Box::new(pb::Unfold { tag: None, alias: None, meta_data: None });
-        Box::into_raw(unfold) as *const c_void
+    pub extern "C" fn init_unfold_operator() -> *const FfiUnfoldOpr {
+        catch_panic(|| {
+            let unfold = pb::Unfold { tag: None, alias: None, meta_data: None };
+            handle::register(unfold)
+        })
     }
 
     /// Set the argument pair for unfold, which are:
@@ -1670,43 +4600,51 @@ mod unfold {
     /// * an alias for referencing to each element of the collection.
     #[no_mangle]
     pub extern "C" fn set_unfold_pair(
-        ptr_unfold: *const c_void, tag: FfiNameOrId, alias: FfiNameOrId,
+        ptr_unfold: *const FfiUnfoldOpr, tag: FfiNameOrId, alias: FfiNameOrId,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut unfold = unsafe { Box::from_raw(ptr_unfold as *mut pb::Unfold) };
-        let tag_result = tag.try_into();
-        let alias_result = alias.try_into();
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut unfold = match handle::borrow_mut::<pb::Unfold>(ptr_unfold) {
+                Ok(unfold) => unfold,
+                Err(e) => return e,
+            };
+            let tag_result = tag.try_into();
+            let alias_result = alias.try_into();
 
-        if tag_result.is_ok() && alias_result.is_ok() {
-            unfold.tag = tag_result.unwrap();
-            unfold.alias = alias_result.unwrap();
-        } else {
-            result =
-                if tag_result.is_err() { tag_result.err().unwrap() } else { alias_result.err().unwrap() };
-        }
-        std::mem::forget(unfold);
+            if tag_result.is_ok() && alias_result.is_ok() {
+                unfold.tag = tag_result.unwrap();
+                unfold.alias = alias_result.unwrap();
+            } else {
+                result = if tag_result.is_err() {
+                    tag_result.err().unwrap()
+                } else {
+                    alias_result.err().unwrap()
+                };
+            }
 
-        result
+            result
+        })
     }
 
     /// To set the meta_data for the unfold operator
     #[no_mangle]
-    pub extern "C" fn set_unfold_meta(ptr_unfold: *const c_void, ptr_meta: FfiPbPointer) -> FfiResult {
-        set_meta(ptr_unfold, ptr_meta, InnerOpt::Unfold)
+    pub extern "C" fn set_unfold_meta(
+        ptr_unfold: *const FfiUnfoldOpr, ptr_meta: FfiPbPointer,
+    ) -> FfiResult {
+        catch_panic(|| set_meta(ptr_unfold, ptr_meta, InnerOpt::Unfold))
     }
 
     /// Append an unfold operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_unfold_operator(
-        ptr_plan: *const c_void, ptr_unfold: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_unfold: *const FfiUnfoldOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let unfold = unsafe { Box::from_raw(ptr_unfold as *mut pb::Unfold) };
-        append_operator(ptr_plan, unfold.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::Unfold>(ptr_plan, ptr_unfold, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_unfold_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Unfold>(ptr)
+    pub extern "C" fn destroy_unfold_operator(ptr: *const FfiUnfoldOpr) {
+        catch_panic(|| destroy_handle::<pb::Unfold>(ptr))
     }
 }
 
@@ -1721,139 +4659,351 @@ mod scan {
     pub enum FfiScanOpt {
         Entity = 0,
         Relation = 1,
+        Table = 2,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[repr(i32)]
+    pub enum FfiConnectorFormat {
+        Csv = 0,
+        Odps = 1,
+        Parquet = 2,
+        Orc = 3,
+    }
+
+    fn data_type_to_pb(data_type: FfiDataType) -> common_pb::DataType {
+        match data_type {
+            FfiDataType::Unknown => common_pb::DataType::None,
+            FfiDataType::Boolean => common_pb::DataType::Boolean,
+            FfiDataType::I32 => common_pb::DataType::Int32,
+            FfiDataType::I64 => common_pb::DataType::Int64,
+            FfiDataType::F64 => common_pb::DataType::Double,
+            FfiDataType::Str => common_pb::DataType::String,
+            FfiDataType::I32Array => common_pb::DataType::Int32Array,
+            FfiDataType::I64Array => common_pb::DataType::Int64Array,
+            FfiDataType::F64Array => common_pb::DataType::DoubleArray,
+            FfiDataType::StrArray => common_pb::DataType::StringArray,
+        }
     }
 
     /// To initialize a scan operator
     #[no_mangle]
-    pub extern "C" fn init_scan_operator(scan_opt: FfiScanOpt) -> *const c_void {
-        let scan = Box::new(pb::Scan {
-            scan_opt: unsafe { std::mem::transmute::<FfiScanOpt, i32>(scan_opt) },
-            alias: None,
-            params: Some(pb::QueryParams {
-                tables: vec![],
-                columns: vec![],
-                is_all_columns: false,
-                limit: None,
-                predicate: None,
-                sample_ratio: 1.0,
-                extra: HashMap::new(),
-            }),
-            idx_predicate: None,
-            meta_data: None,
-        });
-        Box::into_raw(scan) as *const c_void
+    pub extern "C" fn init_scan_operator(scan_opt: FfiScanOpt) -> *const FfiScanOpr {
+        catch_panic(|| {
+            let scan = pb::Scan {
+                scan_opt: unsafe { std::mem::transmute::<FfiScanOpt, i32>(scan_opt) },
+                alias: None,
+                params: Some(pb::QueryParams {
+                    tables: vec![],
+                    columns: vec![],
+                    is_all_columns: false,
+                    limit: None,
+                    predicate: None,
+                    sample_ratio: 1.0,
+                    extra: HashMap::new(),
+                    degree_predicates: vec![],
+                }),
+                idx_predicate: None,
+                meta_data: None,
+                connector: None,
+                named_result: String::new(),
+            };
+            handle::register(scan)
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn init_index_predicate() -> *const c_void {
-        let predicate: Box<pb::IndexPredicate> = Box::new(pb::IndexPredicate { or_predicates: vec![] });
-        Box::into_raw(predicate) as *const c_void
+    pub extern "C" fn init_index_predicate() -> *const FfiIndexPredicate {
+        catch_panic(|| {
+            let predicate = pb::IndexPredicate { or_predicates: vec![] };
+            handle::register(predicate)
+        })
     }
 
     fn parse_equiv_predicate(
         key: FfiProperty, value: FfiConst,
     ) -> Result<pb::index_predicate::Triplet, FfiResult> {
-        Ok(pb::index_predicate::Triplet { key: key.try_into()?, value: Some(value.try_into()?), cmp: None })
+        Ok(pb::index_predicate::Triplet {
+            key: key.try_into()?,
+            value: Some(value.try_into()?),
+            cmp: None,
+            param: None,
+        })
+    }
+
+    fn parse_equiv_param(
+        key: FfiProperty, name: *const c_char,
+    ) -> Result<pb::index_predicate::Triplet, FfiResult> {
+        Ok(pb::index_predicate::Triplet {
+            key: key.try_into()?,
+            value: None,
+            cmp: None,
+            param: Some(common_pb::DynamicParam { name: cstr_to_string(name)?, index: 0, data_type: None }),
+        })
     }
 
     #[no_mangle]
     pub extern "C" fn and_equiv_predicate(
-        ptr_predicate: *const c_void, key: FfiProperty, value: FfiConst,
+        ptr_predicate: *const FfiIndexPredicate, key: FfiProperty, value: FfiConst,
     ) -> FfiResult {
-        let equiv_pred_result = parse_equiv_predicate(key, value);
-        match equiv_pred_result {
-            Ok(equiv_pred) => {
-                let mut predicate = unsafe { Box::from_raw(ptr_predicate as *mut pb::IndexPredicate) };
-                if predicate.or_predicates.is_empty() {
+        catch_panic(|| {
+            let equiv_pred_result = parse_equiv_predicate(key, value);
+            match equiv_pred_result {
+                Ok(equiv_pred) => {
+                    let mut predicate = match handle::borrow_mut::<pb::IndexPredicate>(ptr_predicate) {
+                        Ok(predicate) => predicate,
+                        Err(e) => return e,
+                    };
+                    if predicate.or_predicates.is_empty() {
+                        predicate
+                            .or_predicates
+                            .push(pb::index_predicate::AndPredicate { predicates: vec![equiv_pred] });
+                    } else {
+                        predicate
+                            .or_predicates
+                            .last_mut()
+                            .unwrap()
+                            .predicates
+                            .push(equiv_pred)
+                    }
+
+                    FfiResult::success()
+                }
+                Err(e) => e,
+            }
+        })
+    }
+
+    #[no_mangle]
+    pub extern "C" fn or_equiv_predicate(
+        ptr_predicate: *const FfiIndexPredicate, key: FfiProperty, value: FfiConst,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let equiv_pred_result = parse_equiv_predicate(key, value);
+            match equiv_pred_result {
+                Ok(equiv_pred) => {
+                    let mut predicate = match handle::borrow_mut::<pb::IndexPredicate>(ptr_predicate) {
+                        Ok(predicate) => predicate,
+                        Err(e) => return e,
+                    };
                     predicate
                         .or_predicates
                         .push(pb::index_predicate::AndPredicate { predicates: vec![equiv_pred] });
+
+                    FfiResult::success()
+                }
+                Err(e) => e,
+            }
+        })
+    }
+
+    /// Add a predicate of `key == $name` to the last `AndPredicate` of the index predicate, where `name`
+    /// is a parameter of the plan that is bound to a list of values via [`super::bind_plan_params`].
+    #[no_mangle]
+    pub extern "C" fn and_equiv_param(
+        ptr_predicate: *const FfiIndexPredicate, key: FfiProperty, name: *const c_char,
+    ) -> FfiResult {
+        catch_panic(|| match parse_equiv_param(key, name) {
+            Ok(equiv_pred) => {
+                let mut predicate = match handle::borrow_mut::<pb::IndexPredicate>(ptr_predicate) {
+                    Ok(predicate) => predicate,
+                    Err(e) => return e,
+                };
+                if let Some(and_pred) = predicate.or_predicates.last_mut() {
+                    and_pred.predicates.push(equiv_pred);
                 } else {
                     predicate
                         .or_predicates
-                        .last_mut()
-                        .unwrap()
-                        .predicates
-                        .push(equiv_pred)
+                        .push(pb::index_predicate::AndPredicate { predicates: vec![equiv_pred] });
                 }
-                std::mem::forget(predicate);
 
                 FfiResult::success()
             }
             Err(e) => e,
-        }
+        })
     }
 
+    /// Add a predicate of `key == $name` as a new `AndPredicate` of the index predicate, where `name`
+    /// is a parameter of the plan that is bound to a list of values via [`super::bind_plan_params`].
     #[no_mangle]
-    pub extern "C" fn or_equiv_predicate(
-        ptr_predicate: *const c_void, key: FfiProperty, value: FfiConst,
+    pub extern "C" fn or_equiv_param(
+        ptr_predicate: *const FfiIndexPredicate, key: FfiProperty, name: *const c_char,
     ) -> FfiResult {
-        let equiv_pred_result = parse_equiv_predicate(key, value);
-        match equiv_pred_result {
+        catch_panic(|| match parse_equiv_param(key, name) {
             Ok(equiv_pred) => {
-                let mut predicate = unsafe { Box::from_raw(ptr_predicate as *mut pb::IndexPredicate) };
+                let mut predicate = match handle::borrow_mut::<pb::IndexPredicate>(ptr_predicate) {
+                    Ok(predicate) => predicate,
+                    Err(e) => return e,
+                };
                 predicate
                     .or_predicates
                     .push(pb::index_predicate::AndPredicate { predicates: vec![equiv_pred] });
-                std::mem::forget(predicate);
 
                 FfiResult::success()
             }
             Err(e) => e,
-        }
+        })
     }
 
     #[no_mangle]
     pub extern "C" fn add_scan_index_predicate(
-        ptr_scan: *const c_void, ptr_predicate: *const c_void,
+        ptr_scan: *const FfiScanOpr, ptr_predicate: *const FfiIndexPredicate,
     ) -> FfiResult {
-        let mut scan = unsafe { Box::from_raw(ptr_scan as *mut pb::Scan) };
-        let predicate = unsafe { Box::from_raw(ptr_predicate as *mut pb::IndexPredicate) };
-        scan.idx_predicate = Some(predicate.as_ref().clone());
-        std::mem::forget(scan);
+        catch_panic(|| {
+            let mut scan = match handle::borrow_mut::<pb::Scan>(ptr_scan) {
+                Ok(scan) => scan,
+                Err(e) => return e,
+            };
+            let predicate = match handle::release::<pb::IndexPredicate>(ptr_predicate) {
+                Ok(predicate) => predicate,
+                Err(e) => return e,
+            };
+            scan.idx_predicate = Some(predicate.as_ref().clone());
 
-        FfiResult::success()
+            FfiResult::success()
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn set_scan_params(ptr_scan: *const c_void, ptr_params: *const c_void) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut scan = unsafe { Box::from_raw(ptr_scan as *mut pb::Scan) };
-        let mut new_params = unsafe { Box::from_raw(ptr_params as *mut pb::QueryParams) };
-        if let Some(old_params) = scan.params.as_mut() {
-            std::mem::swap(old_params, new_params.as_mut());
-        } else {
-            result = FfiResult::new(ResultCode::MissingDataError, "pb::Scan::params".to_string());
-        }
-        std::mem::forget(scan);
+    pub extern "C" fn set_scan_params(
+        ptr_scan: *const FfiScanOpr, ptr_params: *const FfiQueryParams,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut scan = match handle::borrow_mut::<pb::Scan>(ptr_scan) {
+                Ok(scan) => scan,
+                Err(e) => return e,
+            };
+            let mut new_params = match handle::release::<pb::QueryParams>(ptr_params) {
+                Ok(new_params) => new_params,
+                Err(e) => return e,
+            };
+            if let Some(old_params) = scan.params.as_mut() {
+                std::mem::swap(old_params, new_params.as_mut());
+            } else {
+                result = FfiResult::new(ResultCode::MissingDataError, "pb::Scan::params".to_string());
+            }
 
-        result
+            result
+        })
     }
 
     /// Set the meta_data for the scan operator
     #[no_mangle]
-    pub extern "C" fn set_scan_meta(ptr_scan: *const c_void, ptr_meta: FfiPbPointer) -> FfiResult {
-        set_meta(ptr_scan, ptr_meta, InnerOpt::Scan)
+    pub extern "C" fn set_scan_meta(ptr_scan: *const FfiScanOpr, ptr_meta: FfiPbPointer) -> FfiResult {
+        catch_panic(|| set_meta(ptr_scan, ptr_meta, InnerOpt::Scan))
     }
 
     /// Set an alias for the data if it is a vertex/edge
     #[no_mangle]
-    pub extern "C" fn set_scan_alias(ptr_scan: *const c_void, alias: FfiAlias) -> FfiResult {
-        set_alias(ptr_scan, alias, InnerOpt::Scan)
+    pub extern "C" fn set_scan_alias(ptr_scan: *const FfiScanOpr, alias: FfiAlias) -> FfiResult {
+        catch_panic(|| set_alias(ptr_scan, alias, InnerOpt::Scan))
+    }
+
+    /// Set the connector of the external tabular source to scan from, which is located by the `uri`,
+    /// e.g., "hdfs://path/to/file.csv", and stored in the given `format`. The scan operator must be
+    /// initialized to scan a `Table`. Note that such a scan is kept in the logical plan for the engines
+    /// that can read the source, while building a job plan of it fails with `UnSupported`.
+    #[no_mangle]
+    pub extern "C" fn set_scan_connector(
+        ptr_scan: *const FfiScanOpr, uri: *const c_char, format: FfiConnectorFormat,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut scan = match handle::borrow_mut::<pb::Scan>(ptr_scan) {
+                Ok(scan) => scan,
+                Err(e) => return e,
+            };
+            let result = cstr_to_string(uri).map(|uri| {
+                scan.connector = Some(pb::scan::Connector {
+                    uri,
+                    format: format as i32,
+                    columns: vec![],
+                    options: HashMap::new(),
+                })
+            });
+
+            result.err().unwrap_or_else(FfiResult::success)
+        })
+    }
+
+    fn with_scan_connector<F>(ptr_scan: *const FfiScanOpr, f: F) -> FfiResult
+    where
+        F: FnOnce(&mut pb::scan::Connector) -> Result<(), FfiResult>,
+    {
+        let mut scan = match handle::borrow_mut::<pb::Scan>(ptr_scan) {
+            Ok(scan) => scan,
+            Err(e) => return e,
+        };
+        let result = if let Some(connector) = scan.connector.as_mut() {
+            f(connector)
+        } else {
+            Err(FfiResult::new(ResultCode::MissingDataError, "pb::Scan::connector".to_string()))
+        };
+
+        result.err().unwrap_or_else(FfiResult::success)
+    }
+
+    /// Add a column to the schema of the external source of the scan, in the order of being stored.
+    #[no_mangle]
+    pub extern "C" fn add_scan_connector_column(
+        ptr_scan: *const FfiScanOpr, name: *const c_char, data_type: FfiDataType,
+    ) -> FfiResult {
+        catch_panic(|| {
+            with_scan_connector(ptr_scan, |connector| {
+                let name = cstr_to_string(name)?;
+                connector
+                    .columns
+                    .push(pb::scan::connector::Column {
+                        name,
+                        data_type: data_type_to_pb(data_type) as i32,
+                    });
+                Ok(())
+            })
+        })
+    }
+
+    /// Add a format-specific option, e.g., ("delimiter", "|") for CSV, to the external source of the scan.
+    #[no_mangle]
+    pub extern "C" fn add_scan_connector_option(
+        ptr_scan: *const FfiScanOpr, key: *const c_char, value: *const c_char,
+    ) -> FfiResult {
+        catch_panic(|| {
+            with_scan_connector(ptr_scan, |connector| {
+                connector
+                    .options
+                    .insert(cstr_to_string(key)?, cstr_to_string(value)?);
+                Ok(())
+            })
+        })
+    }
+
+    /// Set the name of a result registered by a preceding query (via `register_result_as()`)
+    /// to scan from. The scan operator must be initialized to scan a `Table`.
+    #[no_mangle]
+    pub extern "C" fn set_scan_named_result(ptr_scan: *const FfiScanOpr, name: *const c_char) -> FfiResult {
+        catch_panic(|| {
+            let mut scan = match handle::borrow_mut::<pb::Scan>(ptr_scan) {
+                Ok(scan) => scan,
+                Err(e) => return e,
+            };
+            let result = cstr_to_string(name).map(|name| scan.named_result = name);
+
+            result.err().unwrap_or_else(FfiResult::success)
+        })
     }
 
     /// Append a scan operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_scan_operator(
-        ptr_plan: *const c_void, ptr_scan: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_scan: *const FfiScanOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let scan = unsafe { Box::from_raw(ptr_scan as *mut pb::Scan) };
-        append_operator(ptr_plan, scan.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::Scan>(ptr_plan, ptr_scan, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_scan_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Scan>(ptr)
+    pub extern "C" fn destroy_scan_operator(ptr: *const FfiScanOpr) {
+        catch_panic(|| destroy_handle::<pb::Scan>(ptr))
     }
 }
 
@@ -1861,28 +5011,46 @@ mod limit {
     use super::*;
 
     #[no_mangle]
-    pub extern "C" fn init_limit_operator() -> *const c_void {
-        let limit: Box<pb::Limit> = Box::new(pb::Limit { range: None });
-        Box::into_raw(limit) as *const c_void
+    pub extern "C" fn init_limit_operator() -> *const FfiLimitOpr {
+        catch_panic(|| {
+            let limit = pb::Limit { range: None, per_partition: false };
+            handle::register(limit)
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn set_limit_range(ptr_limit: *const c_void, lower: i32, upper: i32) -> FfiResult {
-        set_range(ptr_limit, lower, upper, InnerOpt::Limit)
+    pub extern "C" fn set_limit_range(ptr_limit: *const FfiLimitOpr, lower: i32, upper: i32) -> FfiResult {
+        catch_panic(|| set_range(ptr_limit, lower, upper, InnerOpt::Limit))
+    }
+
+    /// Set whether the range of the limit operator applies to each partition on its own, rather
+    /// than to all the partitions as a whole by default
+    #[no_mangle]
+    pub extern "C" fn set_limit_per_partition(
+        ptr_limit: *const FfiLimitOpr, per_partition: bool,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut limit = match handle::borrow_mut::<pb::Limit>(ptr_limit) {
+                Ok(limit) => limit,
+                Err(e) => return e,
+            };
+            limit.per_partition = per_partition;
+
+            FfiResult::success()
+        })
     }
 
     /// Append an indexed scan operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_limit_operator(
-        ptr_plan: *const c_void, ptr_limit: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_limit: *const FfiLimitOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let limit = unsafe { Box::from_raw(ptr_limit as *mut pb::Limit) };
-        append_operator(ptr_plan, limit.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::Limit>(ptr_plan, ptr_limit, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_limit_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Limit>(ptr)
+    pub extern "C" fn destroy_limit_operator(ptr: *const FfiLimitOpr) {
+        catch_panic(|| destroy_handle::<pb::Limit>(ptr))
     }
 }
 
@@ -1891,96 +5059,348 @@ mod as_opr {
 
     /// To initialize an As operator
     #[no_mangle]
-    pub extern "C" fn init_as_operator() -> *const c_void {
-        let as_opr = Box::new(pb::As { alias: None });
+    pub extern "C" fn init_as_operator() -> *const FfiAsOpr {
+        catch_panic(|| {
+            let as_opr = pb::As { alias: None };
 
-        Box::into_raw(as_opr) as *const c_void
+            handle::register(as_opr)
+        })
     }
 
     /// Set the alias of the entity to As
     #[no_mangle]
-    pub extern "C" fn set_as_alias(ptr_as: *const c_void, alias: FfiAlias) -> FfiResult {
-        set_alias(ptr_as, alias, InnerOpt::As)
+    pub extern "C" fn set_as_alias(ptr_as: *const FfiAsOpr, alias: FfiAlias) -> FfiResult {
+        catch_panic(|| set_alias(ptr_as, alias, InnerOpt::As))
     }
 
     /// Append an As operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_as_operator(
-        ptr_plan: *const c_void, ptr_as: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_as: *const FfiAsOpr, parent: i32, id: *mut i32,
+    ) -> FfiResult {
+        catch_panic(|| append_operator_handle::<pb::As>(ptr_plan, ptr_as, vec![parent], id))
+    }
+
+    #[no_mangle]
+    pub extern "C" fn destroy_as_operator(ptr: *const FfiAsOpr) {
+        catch_panic(|| destroy_handle::<pb::As>(ptr))
+    }
+}
+
+mod sink {
+    use super::*;
+
+    /// To initialize an Sink operator with target of SinkDefault (i.e., sink to client)
+    #[no_mangle]
+    pub extern "C" fn init_sink_operator() -> *const FfiSinkOpr {
+        catch_panic(|| {
+            let sink_opr = pb::Sink {
+                tags: vec![],
+                sink_target: Some(pb::sink::SinkTarget {
+                    inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
+                        id_name_mappings: vec![],
+                        layout: 0,
+                    })),
+                }),
+            };
+            handle::register(sink_opr)
+        })
+    }
+
+    /// To initialize an Sink operator with target of a Graph (now it is Vineyard as a default option),
+    /// which returns a null pointer if the `graph_name` is not a valid C string, with the error kept
+    /// as the last error (see `get_last_error_msg()`).
+    #[no_mangle]
+    pub extern "C" fn init_sink_graph_operator(graph_name: *const c_char) -> *const FfiSinkOpr {
+        catch_panic(|| {
+            let graph_name = match cstr_to_string(graph_name) {
+                Ok(graph_name) => graph_name,
+                Err(e) => {
+                    error!("invalid graph name of the sink: {}", e);
+                    e.discard();
+                    return std::ptr::null();
+                }
+            };
+            let sink_opr = pb::Sink {
+                // sink head by default
+                tags: vec![common_pb::NameOrIdKey { key: None }],
+                sink_target: Some(pb::sink::SinkTarget {
+                    inner: Some(pb::sink::sink_target::Inner::SinkVineyard(pb::SinkVineyard {
+                        graph_name,
+                        graph_schema: None,
+                    })),
+                }),
+            };
+            handle::register(sink_opr)
+        })
+    }
+
+    /// Register the results of the sink as a temporary result of the given `name`, instead of
+    /// returning them to the client, which can be scanned by the subsequent queries via
+    /// `set_scan_named_result()`.
+    #[no_mangle]
+    pub extern "C" fn register_result_as(ptr_sink: *const FfiSinkOpr, name: *const c_char) -> FfiResult {
+        catch_panic(|| {
+            let mut sink = match handle::borrow_mut::<pb::Sink>(ptr_sink) {
+                Ok(sink) => sink,
+                Err(e) => return e,
+            };
+            let result = cstr_to_string(name).map(|name| {
+                sink.sink_target = Some(pb::sink::SinkTarget {
+                    inner: Some(pb::sink::sink_target::Inner::SinkResult(pb::SinkResult { name })),
+                })
+            });
+
+            result.err().unwrap_or_else(FfiResult::success)
+        })
+    }
+
+    /// The layout of the results returned to the client, see `SinkDefault.Layout`.
+    #[allow(dead_code)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[repr(i32)]
+    pub enum FfiSinkLayout {
+        Row = 0,
+        Columnar = 1,
+    }
+
+    /// Set the layout of the results returned to the client by the sink, where the results of the
+    /// columnar layout are decoded via `decode_result_batch()`.
+    #[no_mangle]
+    pub extern "C" fn set_sink_layout(ptr_sink: *const FfiSinkOpr, layout: FfiSinkLayout) -> FfiResult {
+        catch_panic(|| {
+            let mut sink = match handle::borrow_mut::<pb::Sink>(ptr_sink) {
+                Ok(sink) => sink,
+                Err(e) => return e,
+            };
+            match sink
+                .sink_target
+                .as_mut()
+                .and_then(|target| target.inner.as_mut())
+            {
+                Some(pb::sink::sink_target::Inner::SinkDefault(sink_default)) => {
+                    sink_default.layout = layout as i32;
+                    FfiResult::success()
+                }
+                _ => FfiResult::new(
+                    ResultCode::UnSupported,
+                    "the sink does not return the results to the client, see `init_sink_operator()`"
+                        .to_string(),
+                ),
+            }
+        })
+    }
+
+    /// The entity of a bulk load that a column is loaded into, namely, the vertex or the edge being
+    /// loaded, or the source or the target vertex of the edge being loaded.
+    #[allow(dead_code)]
+    #[derive(Copy, Clone)]
+    #[repr(i32)]
+    pub enum FfiLoadEntity {
+        Element = 0,
+        SrcVertex = 1,
+        DstVertex = 2,
+    }
+
+    fn borrow_bulk_load(sink: &mut pb::Sink) -> Result<&mut pb::SinkBulkLoad, FfiResult> {
+        match sink
+            .sink_target
+            .as_mut()
+            .and_then(|target| target.inner.as_mut())
+        {
+            Some(pb::sink::sink_target::Inner::SinkBulkLoad(bulk_load)) => Ok(bulk_load),
+            _ => Err(FfiResult::new(
+                ResultCode::UnSupported,
+                "the sink is not a bulk load, see `load_sink_from()`".to_string(),
+            )),
+        }
+    }
+
+    /// Load the rows of the external source at `uri` into the graph of `graph_name` in bulk, instead
+    /// of returning them to the client. The rows are read by a scan with a connector to the same `uri`
+    /// (see `set_scan_connector()`), and loaded via `load_sink_as_vertex()` or `load_sink_as_edge()`.
+    #[no_mangle]
+    pub extern "C" fn load_sink_from(
+        ptr_sink: *const FfiSinkOpr, uri: *const c_char, graph_name: *const c_char,
     ) -> FfiResult {
-        let as_opr = unsafe { Box::from_raw(ptr_as as *mut pb::As) };
-        append_operator(ptr_plan, as_opr.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| {
+            let mut sink = match handle::borrow_mut::<pb::Sink>(ptr_sink) {
+                Ok(sink) => sink,
+                Err(e) => return e,
+            };
+            let result = cstr_to_string(uri).and_then(|uri| {
+                let graph_name = cstr_to_string(graph_name)?;
+                sink.sink_target = Some(pb::sink::SinkTarget {
+                    inner: Some(pb::sink::sink_target::Inner::SinkBulkLoad(pb::SinkBulkLoad {
+                        uri,
+                        graph_name,
+                        graph_schema: None,
+                        mapping: None,
+                    })),
+                });
+                Ok(())
+            });
+
+            result.err().unwrap_or_else(FfiResult::success)
+        })
+    }
+
+    /// Load the rows of a bulk load as the vertices of the given label.
+    #[no_mangle]
+    pub extern "C" fn load_sink_as_vertex(ptr_sink: *const FfiSinkOpr, label: FfiNameOrId) -> FfiResult {
+        catch_panic(|| {
+            let label: Option<common_pb::NameOrId> = match label.try_into() {
+                Ok(label) => label,
+                Err(e) => return e,
+            };
+            let mut sink = match handle::borrow_mut::<pb::Sink>(ptr_sink) {
+                Ok(sink) => sink,
+                Err(e) => return e,
+            };
+            match borrow_bulk_load(&mut sink) {
+                Ok(bulk_load) => {
+                    bulk_load.mapping =
+                        Some(pb::sink_bulk_load::Mapping::Vertex(pb::sink_bulk_load::VertexMapping {
+                            label,
+                            column_mappings: vec![],
+                        }));
+                    FfiResult::success()
+                }
+                Err(e) => e,
+            }
+        })
     }
 
+    /// Load the rows of a bulk load as the edges of the given label, from the vertices of `src_label`
+    /// to the vertices of `dst_label`.
     #[no_mangle]
-    pub extern "C" fn destroy_as_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::As>(ptr)
+    pub extern "C" fn load_sink_as_edge(
+        ptr_sink: *const FfiSinkOpr, label: FfiNameOrId, src_label: FfiNameOrId, dst_label: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut labels: Vec<Option<common_pb::NameOrId>> = vec![];
+            for label in [label, src_label, dst_label] {
+                match label.try_into() {
+                    Ok(label) => labels.push(label),
+                    Err(e) => return e,
+                }
+            }
+            let dst_label = labels.pop().unwrap();
+            let src_label = labels.pop().unwrap();
+            let label = labels.pop().unwrap();
+            let mut sink = match handle::borrow_mut::<pb::Sink>(ptr_sink) {
+                Ok(sink) => sink,
+                Err(e) => return e,
+            };
+            match borrow_bulk_load(&mut sink) {
+                Ok(bulk_load) => {
+                    bulk_load.mapping =
+                        Some(pb::sink_bulk_load::Mapping::Edge(pb::sink_bulk_load::EdgeMapping {
+                            label,
+                            src: Some(pb::sink_bulk_load::VertexMapping {
+                                label: src_label,
+                                column_mappings: vec![],
+                            }),
+                            dst: Some(pb::sink_bulk_load::VertexMapping {
+                                label: dst_label,
+                                column_mappings: vec![],
+                            }),
+                            column_mappings: vec![],
+                        }));
+                    FfiResult::success()
+                }
+                Err(e) => e,
+            }
+        })
     }
-}
 
-mod sink {
-    use super::*;
-
-    /// To initialize an Sink operator with target of SinkDefault (i.e., sink to client)
+    /// Map a column of the source of a bulk load to a property of the given `entity`, where the
+    /// columns mapped to the source and the target vertices of an edge are their primary keys.
     #[no_mangle]
-    pub extern "C" fn init_sink_operator() -> *const c_void {
-        let sink_opr = Box::new(pb::Sink {
-            tags: vec![],
-            sink_target: Some(pb::sink::SinkTarget {
-                inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
-                    id_name_mappings: vec![],
-                })),
-            }),
-        });
-        Box::into_raw(sink_opr) as *const c_void
-    }
+    pub extern "C" fn add_sink_load_column(
+        ptr_sink: *const FfiSinkOpr, entity: FfiLoadEntity, column: FfiNameOrId, property: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let column: Option<common_pb::NameOrId> = match column.try_into() {
+                Ok(column) => column,
+                Err(e) => return e,
+            };
+            let property: Option<common_pb::NameOrId> = match property.try_into() {
+                Ok(property) => property,
+                Err(e) => return e,
+            };
+            let mut sink = match handle::borrow_mut::<pb::Sink>(ptr_sink) {
+                Ok(sink) => sink,
+                Err(e) => return e,
+            };
+            let mapping = match borrow_bulk_load(&mut sink) {
+                Ok(bulk_load) => bulk_load.mapping.as_mut(),
+                Err(e) => return e,
+            };
+            let column_mappings = match (mapping, entity) {
+                (Some(pb::sink_bulk_load::Mapping::Vertex(vertex)), FfiLoadEntity::Element) => {
+                    &mut vertex.column_mappings
+                }
+                (Some(pb::sink_bulk_load::Mapping::Edge(edge)), FfiLoadEntity::Element) => {
+                    &mut edge.column_mappings
+                }
+                (Some(pb::sink_bulk_load::Mapping::Edge(edge)), FfiLoadEntity::SrcVertex) => {
+                    &mut edge
+                        .src
+                        .get_or_insert_with(Default::default)
+                        .column_mappings
+                }
+                (Some(pb::sink_bulk_load::Mapping::Edge(edge)), FfiLoadEntity::DstVertex) => {
+                    &mut edge
+                        .dst
+                        .get_or_insert_with(Default::default)
+                        .column_mappings
+                }
+                _ => {
+                    return FfiResult::new(
+                        ResultCode::UnSupported,
+                        "map a column to an entity that the bulk load does not load".to_string(),
+                    )
+                }
+            };
+            column_mappings.push(pb::sink_bulk_load::ColumnMapping { column, property });
 
-    /// To initialize an Sink operator with target of a Graph (now it is Vineyard as a default option)
-    #[no_mangle]
-    pub extern "C" fn init_sink_graph_operator(graph_name: *const c_char) -> *const c_void {
-        let graph_name = cstr_to_string(graph_name).expect("C String to Rust String error!");
-        let sink_opr = Box::new(pb::Sink {
-            // sink head by default
-            tags: vec![common_pb::NameOrIdKey { key: None }],
-            sink_target: Some(pb::sink::SinkTarget {
-                inner: Some(pb::sink::sink_target::Inner::SinkVineyard(pb::SinkVineyard {
-                    graph_name,
-                    graph_schema: None,
-                })),
-            }),
-        });
-        Box::into_raw(sink_opr) as *const c_void
+            FfiResult::success()
+        })
     }
 
     /// Add the tag of column to output to Sink
     #[no_mangle]
-    pub extern "C" fn add_sink_column(ptr_sink: *const c_void, ffi_tag: FfiNameOrId) -> FfiResult {
-        let mut result = FfiResult::success();
-        let tag_pb = ffi_tag.try_into();
-        match tag_pb {
-            Ok(tag) => {
-                let mut sink = unsafe { Box::from_raw(ptr_sink as *mut pb::Sink) };
-                sink.tags.push(tag);
-                std::mem::forget(sink);
+    pub extern "C" fn add_sink_column(ptr_sink: *const FfiSinkOpr, ffi_tag: FfiNameOrId) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let tag_pb = ffi_tag.try_into();
+            match tag_pb {
+                Ok(tag) => {
+                    let mut sink = match handle::borrow_mut::<pb::Sink>(ptr_sink) {
+                        Ok(sink) => sink,
+                        Err(e) => return e,
+                    };
+                    sink.tags.push(tag);
+                }
+                Err(e) => result = e,
             }
-            Err(e) => result = e,
-        }
 
-        result
+            result
+        })
     }
 
     /// Append an Sink operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_sink_operator(
-        ptr_plan: *const c_void, ptr_sink: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_sink: *const FfiSinkOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let sink_opr = unsafe { Box::from_raw(ptr_sink as *mut pb::Sink) };
-        append_operator(ptr_plan, sink_opr.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::Sink>(ptr_plan, ptr_sink, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_sink_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Sink>(ptr)
+    pub extern "C" fn destroy_sink_operator(ptr: *const FfiSinkOpr) {
+        catch_panic(|| destroy_handle::<pb::Sink>(ptr))
     }
 }
 
@@ -2011,74 +5431,188 @@ mod graph {
     /// To initialize an edge expand operator from an expand base
     // TODO: provide init with ExpandOption
     #[no_mangle]
-    pub extern "C" fn init_edgexpd_operator(expand_opt: FfiExpandOpt, dir: FfiDirection) -> *const c_void {
-        let edgexpd = Box::new(pb::EdgeExpand {
-            v_tag: None,
-            direction: unsafe { std::mem::transmute::<FfiDirection, i32>(dir) },
-            params: Some(pb::QueryParams {
-                tables: vec![],
-                columns: vec![],
-                is_all_columns: false,
-                limit: None,
-                predicate: None,
-                sample_ratio: 1.0,
-                extra: HashMap::new(),
-            }),
-            alias: None,
-            expand_opt: unsafe { std::mem::transmute::<FfiExpandOpt, i32>(expand_opt) },
-            meta_data: None,
-        });
+    pub extern "C" fn init_edgexpd_operator(
+        expand_opt: FfiExpandOpt, dir: FfiDirection,
+    ) -> *const FfiEdgeExpandOpr {
+        catch_panic(|| {
+            let edgexpd = pb::EdgeExpand {
+                v_tag: None,
+                direction: unsafe { std::mem::transmute::<FfiDirection, i32>(dir) },
+                params: Some(pb::QueryParams {
+                    tables: vec![],
+                    columns: vec![],
+                    is_all_columns: false,
+                    limit: None,
+                    predicate: None,
+                    sample_ratio: 1.0,
+                    extra: HashMap::new(),
+                    degree_predicates: vec![],
+                }),
+                alias: None,
+                expand_opt: unsafe { std::mem::transmute::<FfiExpandOpt, i32>(expand_opt) },
+                meta_data: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges: false,
+                vertex_tables: vec![],
+                is_optional: false,
+            };
 
-        Box::into_raw(edgexpd) as *const c_void
+            handle::register(edgexpd)
+        })
     }
 
     /// Set the start-vertex's tag to conduct this expansion
     #[no_mangle]
-    pub extern "C" fn set_edgexpd_vtag(ptr_edgexpd: *const c_void, v_tag: FfiNameOrId) -> FfiResult {
-        set_tag(ptr_edgexpd, v_tag, InnerOpt::EdgeExpand)
+    pub extern "C" fn set_edgexpd_vtag(
+        ptr_edgexpd: *const FfiEdgeExpandOpr, v_tag: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| set_tag(ptr_edgexpd, v_tag, InnerOpt::EdgeExpand))
     }
 
     #[no_mangle]
     pub extern "C" fn set_edgexpd_params(
-        ptr_edgexpd: *const c_void, ptr_params: *const c_void,
+        ptr_edgexpd: *const FfiEdgeExpandOpr, ptr_params: *const FfiQueryParams,
     ) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut edgexpd = unsafe { Box::from_raw(ptr_edgexpd as *mut pb::EdgeExpand) };
-        let mut new_params = unsafe { Box::from_raw(ptr_params as *mut pb::QueryParams) };
-        if let Some(old_params) = edgexpd.params.as_mut() {
-            std::mem::swap(old_params, new_params.as_mut());
-        } else {
-            result = FfiResult::new(ResultCode::MissingDataError, "pb::EdgeExpand::Params".to_string());
-        }
-        std::mem::forget(edgexpd);
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut edgexpd = match handle::borrow_mut::<pb::EdgeExpand>(ptr_edgexpd) {
+                Ok(edgexpd) => edgexpd,
+                Err(e) => return e,
+            };
+            let mut new_params = match handle::release::<pb::QueryParams>(ptr_params) {
+                Ok(new_params) => new_params,
+                Err(e) => return e,
+            };
+            if let Some(old_params) = edgexpd.params.as_mut() {
+                std::mem::swap(old_params, new_params.as_mut());
+            } else {
+                result = FfiResult::new(ResultCode::MissingDataError, "pb::EdgeExpand::Params".to_string());
+            }
 
-        result
+            result
+        })
     }
 
     /// Set edge alias of this edge expansion
     #[no_mangle]
-    pub extern "C" fn set_edgexpd_alias(ptr_edgexpd: *const c_void, alias: FfiAlias) -> FfiResult {
-        set_alias(ptr_edgexpd, alias, InnerOpt::EdgeExpand)
+    pub extern "C" fn set_edgexpd_alias(
+        ptr_edgexpd: *const FfiEdgeExpandOpr, alias: FfiAlias,
+    ) -> FfiResult {
+        catch_panic(|| set_alias(ptr_edgexpd, alias, InnerOpt::EdgeExpand))
     }
 
     /// Set the meta_data for the edge expansion operator
     #[no_mangle]
-    pub extern "C" fn set_edgexpd_meta(ptr_edgexpd: *const c_void, ptr_meta: FfiPbPointer) -> FfiResult {
-        set_meta(ptr_edgexpd, ptr_meta, InnerOpt::EdgeExpand)
+    pub extern "C" fn set_edgexpd_meta(
+        ptr_edgexpd: *const FfiEdgeExpandOpr, ptr_meta: FfiPbPointer,
+    ) -> FfiResult {
+        catch_panic(|| set_meta(ptr_edgexpd, ptr_meta, InnerOpt::EdgeExpand))
+    }
+
+    /// Set whether the `BOTH` expansion yields a self-loop of the start vertex once (as Cypher does),
+    /// rather than twice (as Gremlin does, by default)
+    #[no_mangle]
+    pub extern "C" fn set_edgexpd_dedup_both(
+        ptr_edgexpd: *const FfiEdgeExpandOpr, dedup_both: bool,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut edgexpd = match handle::borrow_mut::<pb::EdgeExpand>(ptr_edgexpd) {
+                Ok(edgexpd) => edgexpd,
+                Err(e) => return e,
+            };
+            edgexpd.dedup_both = dedup_both;
+
+            FfiResult::success()
+        })
+    }
+
+    /// Set whether the edge expansion skips the self-loops
+    #[no_mangle]
+    pub extern "C" fn set_edgexpd_exclude_self_loops(
+        ptr_edgexpd: *const FfiEdgeExpandOpr, exclude_self_loops: bool,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut edgexpd = match handle::borrow_mut::<pb::EdgeExpand>(ptr_edgexpd) {
+                Ok(edgexpd) => edgexpd,
+                Err(e) => return e,
+            };
+            edgexpd.exclude_self_loops = exclude_self_loops;
+
+            FfiResult::success()
+        })
+    }
+
+    /// Set whether the edge expansion collapses the parallel edges (of the same label and endpoints
+    /// in the same direction) into the first of them
+    #[no_mangle]
+    pub extern "C" fn set_edgexpd_collapse_parallel_edges(
+        ptr_edgexpd: *const FfiEdgeExpandOpr, collapse_parallel_edges: bool,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut edgexpd = match handle::borrow_mut::<pb::EdgeExpand>(ptr_edgexpd) {
+                Ok(edgexpd) => edgexpd,
+                Err(e) => return e,
+            };
+            edgexpd.collapse_parallel_edges = collapse_parallel_edges;
+
+            FfiResult::success()
+        })
+    }
+
+    /// Set whether the edge expansion is optional, which keeps a record of which the start vertex has
+    /// no (satisfied) adjacent edges, with the alias bound to null, as `OPTIONAL MATCH`
+    #[no_mangle]
+    pub extern "C" fn set_edgexpd_optional(
+        ptr_edgexpd: *const FfiEdgeExpandOpr, is_optional: bool,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut edgexpd = match handle::borrow_mut::<pb::EdgeExpand>(ptr_edgexpd) {
+                Ok(edgexpd) => edgexpd,
+                Err(e) => return e,
+            };
+            edgexpd.is_optional = is_optional;
+
+            FfiResult::success()
+        })
+    }
+
+    /// Add a table (label) that the adjacent vertices of the edge expansion must be of
+    #[no_mangle]
+    pub extern "C" fn add_edgexpd_vertex_table(
+        ptr_edgexpd: *const FfiEdgeExpandOpr, table: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut edgexpd = match handle::borrow_mut::<pb::EdgeExpand>(ptr_edgexpd) {
+                Ok(edgexpd) => edgexpd,
+                Err(e) => return e,
+            };
+            let pb_result = table.try_into();
+            let result = match pb_result {
+                Ok(pb) => {
+                    if let Some(table) = pb {
+                        edgexpd.vertex_tables.push(table)
+                    }
+                    FfiResult::success()
+                }
+                Err(e) => e,
+            };
+
+            result
+        })
     }
 
     /// Append an edge expand operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_edgexpd_operator(
-        ptr_plan: *const c_void, ptr_edgexpd: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_edgexpd: *const FfiEdgeExpandOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let edgexpd = unsafe { Box::from_raw(ptr_edgexpd as *mut pb::EdgeExpand) };
-        append_operator(ptr_plan, edgexpd.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::EdgeExpand>(ptr_plan, ptr_edgexpd, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_edgexpd_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::EdgeExpand>(ptr)
+    pub extern "C" fn destroy_edgexpd_operator(ptr: *const FfiEdgeExpandOpr) {
+        catch_panic(|| destroy_handle::<pb::EdgeExpand>(ptr))
     }
 
     #[allow(dead_code)]
@@ -2088,74 +5622,123 @@ mod graph {
         End = 1,
         Other = 2,
         Both = 3,
+        /// Get both the start and end vertices of an edge into two aliases in one record,
+        /// set via `set_getv_alias()` and `set_getv_end_alias()` respectively
+        Endpoints = 5,
     }
 
     /// To initialize an expansion base
     #[no_mangle]
-    pub extern "C" fn init_getv_operator(opt: FfiVOpt) -> *const c_void {
-        let getv = Box::new(pb::GetV {
-            tag: None,
-            opt: unsafe { std::mem::transmute::<FfiVOpt, i32>(opt) },
-            params: Some(pb::QueryParams {
-                tables: vec![],
-                columns: vec![],
-                is_all_columns: false,
-                limit: None,
-                predicate: None,
-                sample_ratio: 1.0,
-                extra: HashMap::new(),
-            }),
-            alias: None,
-            meta_data: None,
-        });
-        Box::into_raw(getv) as *const c_void
+    pub extern "C" fn init_getv_operator(opt: FfiVOpt) -> *const FfiGetVOpr {
+        catch_panic(|| {
+            let getv = pb::GetV {
+                tag: None,
+                opt: unsafe { std::mem::transmute::<FfiVOpt, i32>(opt) },
+                params: Some(pb::QueryParams {
+                    tables: vec![],
+                    columns: vec![],
+                    is_all_columns: false,
+                    limit: None,
+                    predicate: None,
+                    sample_ratio: 1.0,
+                    extra: HashMap::new(),
+                    degree_predicates: vec![],
+                }),
+                alias: None,
+                meta_data: None,
+                is_optional: false,
+                end_alias: None,
+            };
+            handle::register(getv)
+        })
     }
 
     /// Set the tag of edge/path to get the vertex
     #[no_mangle]
-    pub extern "C" fn set_getv_tag(ptr_getv: *const c_void, tag: FfiNameOrId) -> FfiResult {
-        set_tag(ptr_getv, tag, InnerOpt::GetV)
+    pub extern "C" fn set_getv_tag(ptr_getv: *const FfiGetVOpr, tag: FfiNameOrId) -> FfiResult {
+        catch_panic(|| set_tag(ptr_getv, tag, InnerOpt::GetV))
     }
 
     #[no_mangle]
-    pub extern "C" fn set_getv_params(ptr_getv: *const c_void, ptr_params: *const c_void) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut getv = unsafe { Box::from_raw(ptr_getv as *mut pb::GetV) };
-        let mut new_params = unsafe { Box::from_raw(ptr_params as *mut pb::QueryParams) };
-        if let Some(old_params) = getv.params.as_mut() {
-            std::mem::swap(old_params, new_params.as_mut());
-        } else {
-            result = FfiResult::new(ResultCode::MissingDataError, "pb::GetV::Params".to_string());
-        }
-        std::mem::forget(getv);
+    pub extern "C" fn set_getv_params(
+        ptr_getv: *const FfiGetVOpr, ptr_params: *const FfiQueryParams,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut getv = match handle::borrow_mut::<pb::GetV>(ptr_getv) {
+                Ok(getv) => getv,
+                Err(e) => return e,
+            };
+            let mut new_params = match handle::release::<pb::QueryParams>(ptr_params) {
+                Ok(new_params) => new_params,
+                Err(e) => return e,
+            };
+            if let Some(old_params) = getv.params.as_mut() {
+                std::mem::swap(old_params, new_params.as_mut());
+            } else {
+                result = FfiResult::new(ResultCode::MissingDataError, "pb::GetV::Params".to_string());
+            }
 
-        result
+            result
+        })
     }
 
     /// Set vertex alias of this getting vertex
     #[no_mangle]
-    pub extern "C" fn set_getv_alias(ptr_getv: *const c_void, alias: FfiAlias) -> FfiResult {
-        set_alias(ptr_getv, alias, InnerOpt::GetV)
+    pub extern "C" fn set_getv_alias(ptr_getv: *const FfiGetVOpr, alias: FfiAlias) -> FfiResult {
+        catch_panic(|| set_alias(ptr_getv, alias, InnerOpt::GetV))
+    }
+
+    /// Set the alias of the end vertex, which is required if getting the vertices of `FfiVOpt::Endpoints`
+    #[no_mangle]
+    pub extern "C" fn set_getv_end_alias(ptr_getv: *const FfiGetVOpr, alias: FfiAlias) -> FfiResult {
+        catch_panic(|| {
+            let end_alias: Option<common_pb::NameOrId> = match alias.try_into() {
+                Ok(alias) => alias,
+                Err(e) => return e,
+            };
+            let mut getv = match handle::borrow_mut::<pb::GetV>(ptr_getv) {
+                Ok(getv) => getv,
+                Err(e) => return e,
+            };
+            getv.end_alias = end_alias;
+
+            FfiResult::success()
+        })
+    }
+
+    /// Set whether getting the vertex is optional, which keeps a record of which the vertex is null or
+    /// does not satisfy the query parameters, with the alias bound to null, as `OPTIONAL MATCH`
+    #[no_mangle]
+    pub extern "C" fn set_getv_optional(ptr_getv: *const FfiGetVOpr, is_optional: bool) -> FfiResult {
+        catch_panic(|| {
+            let mut getv = match handle::borrow_mut::<pb::GetV>(ptr_getv) {
+                Ok(getv) => getv,
+                Err(e) => return e,
+            };
+            getv.is_optional = is_optional;
+
+            FfiResult::success()
+        })
     }
 
     /// Set the meta_data for the getv operator
     #[no_mangle]
-    pub extern "C" fn set_getv_meta(ptr_getv: *const c_void, ptr_meta: FfiPbPointer) -> FfiResult {
-        set_meta(ptr_getv, ptr_meta, InnerOpt::GetV)
+    pub extern "C" fn set_getv_meta(ptr_getv: *const FfiGetVOpr, ptr_meta: FfiPbPointer) -> FfiResult {
+        catch_panic(|| set_meta(ptr_getv, ptr_meta, InnerOpt::GetV))
     }
 
     /// Append the operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_getv_operator(
-        ptr_plan: *const c_void, ptr_getv: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_getv: *const FfiGetVOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let getv = unsafe { Box::from_raw(ptr_getv as *mut pb::GetV) };
-        append_operator(ptr_plan, getv.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::GetV>(ptr_plan, ptr_getv, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_getv_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::GetV>(ptr)
+    pub extern "C" fn destroy_getv_operator(ptr: *const FfiGetVOpr) {
+        catch_panic(|| destroy_handle::<pb::GetV>(ptr))
     }
 
     #[allow(dead_code)]
@@ -2177,123 +5760,167 @@ mod graph {
     // TODO: this function would be removed.
     #[no_mangle]
     pub extern "C" fn init_pathxpd_operator(
-        ptr_expand: *const c_void, path_opt: PathOpt, result_opt: PathResultOpt,
-    ) -> *const c_void {
-        let expand = unsafe { Box::from_raw(ptr_expand as *mut pb::EdgeExpand) };
-        let pathxpd = Box::new(pb::PathExpand {
-            base: Some(pb::path_expand::ExpandBase {
-                edge_expand: Some(expand.as_ref().clone()),
-                get_v: None,
-            }),
-            start_tag: None,
-            alias: None,
-            hop_range: None,
-            path_opt: unsafe { std::mem::transmute::<PathOpt, i32>(path_opt) },
-            result_opt: unsafe { std::mem::transmute::<PathResultOpt, i32>(result_opt) },
-            condition: None,
-        });
+        ptr_expand: *const FfiEdgeExpandOpr, path_opt: PathOpt, result_opt: PathResultOpt,
+    ) -> *const FfiPathExpandOpr {
+        catch_panic(|| {
+            let expand = match handle::release::<pb::EdgeExpand>(ptr_expand) {
+                Ok(expand) => expand,
+                Err(_) => return std::ptr::null(),
+            };
+            let pathxpd = pb::PathExpand {
+                base: Some(pb::path_expand::ExpandBase {
+                    edge_expand: Some(expand.as_ref().clone()),
+                    get_v: None,
+                }),
+                start_tag: None,
+                alias: None,
+                hop_range: None,
+                path_opt: unsafe { std::mem::transmute::<PathOpt, i32>(path_opt) },
+                result_opt: unsafe { std::mem::transmute::<PathResultOpt, i32>(result_opt) },
+                condition: None,
+                timeout_ms: 0,
+            };
 
-        Box::into_raw(pathxpd) as *const c_void
+            handle::register(pathxpd)
+        })
     }
 
     /// To initialize an path expand operator from an expand base
     #[no_mangle]
     pub extern "C" fn init_pathxpd_operator_with_expand_base(
-        ptr_expand: *const c_void, ptr_getv: *const c_void, path_opt: PathOpt, result_opt: PathResultOpt,
-    ) -> *const c_void {
-        let expand = unsafe { Box::from_raw(ptr_expand as *mut pb::EdgeExpand) };
-        let getv = unsafe { Box::from_raw(ptr_getv as *mut pb::GetV) };
-        let pathxpd = Box::new(pb::PathExpand {
-            base: Some(pb::path_expand::ExpandBase {
-                edge_expand: Some(expand.as_ref().clone()),
-                get_v: Some(getv.as_ref().clone()),
-            }),
-            start_tag: None,
-            alias: None,
-            hop_range: None,
-            path_opt: unsafe { std::mem::transmute::<PathOpt, i32>(path_opt) },
-            result_opt: unsafe { std::mem::transmute::<PathResultOpt, i32>(result_opt) },
-            condition: None,
-        });
+        ptr_expand: *const FfiEdgeExpandOpr, ptr_getv: *const FfiGetVOpr, path_opt: PathOpt,
+        result_opt: PathResultOpt,
+    ) -> *const FfiPathExpandOpr {
+        catch_panic(|| {
+            let expand = match handle::release::<pb::EdgeExpand>(ptr_expand) {
+                Ok(expand) => expand,
+                Err(_) => return std::ptr::null(),
+            };
+            let getv = match handle::release::<pb::GetV>(ptr_getv) {
+                Ok(getv) => getv,
+                Err(_) => return std::ptr::null(),
+            };
+            let pathxpd = pb::PathExpand {
+                base: Some(pb::path_expand::ExpandBase {
+                    edge_expand: Some(expand.as_ref().clone()),
+                    get_v: Some(getv.as_ref().clone()),
+                }),
+                start_tag: None,
+                alias: None,
+                hop_range: None,
+                path_opt: unsafe { std::mem::transmute::<PathOpt, i32>(path_opt) },
+                result_opt: unsafe { std::mem::transmute::<PathResultOpt, i32>(result_opt) },
+                condition: None,
+                timeout_ms: 0,
+            };
 
-        Box::into_raw(pathxpd) as *const c_void
+            handle::register(pathxpd)
+        })
     }
 
     /// Set path alias of this path expansion
     #[no_mangle]
-    pub extern "C" fn set_pathxpd_tag(ptr_pathxpd: *const c_void, tag: FfiNameOrId) -> FfiResult {
-        set_tag(ptr_pathxpd, tag, InnerOpt::PathExpand)
+    pub extern "C" fn set_pathxpd_tag(ptr_pathxpd: *const FfiPathExpandOpr, tag: FfiNameOrId) -> FfiResult {
+        catch_panic(|| set_tag(ptr_pathxpd, tag, InnerOpt::PathExpand))
     }
 
     /// Set path alias of this path expansion
     #[no_mangle]
-    pub extern "C" fn set_pathxpd_alias(ptr_pathxpd: *const c_void, alias: FfiAlias) -> FfiResult {
-        set_alias(ptr_pathxpd, alias, InnerOpt::PathExpand)
+    pub extern "C" fn set_pathxpd_alias(
+        ptr_pathxpd: *const FfiPathExpandOpr, alias: FfiAlias,
+    ) -> FfiResult {
+        catch_panic(|| set_alias(ptr_pathxpd, alias, InnerOpt::PathExpand))
     }
 
     /// Set the hop-range limitation of expanding path
     #[no_mangle]
-    pub extern "C" fn set_pathxpd_hops(ptr_pathxpd: *const c_void, lower: i32, upper: i32) -> FfiResult {
-        set_range(ptr_pathxpd, lower, upper, InnerOpt::PathExpand)
+    pub extern "C" fn set_pathxpd_hops(
+        ptr_pathxpd: *const FfiPathExpandOpr, lower: i32, upper: i32,
+    ) -> FfiResult {
+        catch_panic(|| set_range(ptr_pathxpd, lower, upper, InnerOpt::PathExpand))
     }
 
     /// To set a path expand operator's condition, which is a predicate represented as a c-string.
     #[no_mangle]
     pub extern "C" fn set_pathxpd_condition(
-        ptr_pathxpd: *const c_void, cstr_predicate: *const c_char,
+        ptr_pathxpd: *const FfiPathExpandOpr, cstr_predicate: *const c_char,
+    ) -> FfiResult {
+        catch_panic(|| set_predicate(ptr_pathxpd, cstr_predicate, InnerOpt::PathExpand))
+    }
+
+    /// Set the soft timeout (in milliseconds) of the path expansion, after which it stops
+    /// expanding, while the paths found so far are kept as partial results
+    #[no_mangle]
+    pub extern "C" fn set_pathxpd_timeout(
+        ptr_pathxpd: *const FfiPathExpandOpr, timeout_ms: u64,
     ) -> FfiResult {
-        set_predicate(ptr_pathxpd, cstr_predicate, InnerOpt::PathExpand)
+        catch_panic(|| set_timeout(ptr_pathxpd, timeout_ms, InnerOpt::PathExpand))
     }
 
     /// Append an path-expand operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_pathxpd_operator(
-        ptr_plan: *const c_void, ptr_pathxpd: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_pathxpd: *const FfiPathExpandOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let pathxpd = unsafe { Box::from_raw(ptr_pathxpd as *mut pb::PathExpand) };
-        append_operator(ptr_plan, pathxpd.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::PathExpand>(ptr_plan, ptr_pathxpd, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_pathxpd_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::PathExpand>(ptr)
+    pub extern "C" fn destroy_pathxpd_operator(ptr: *const FfiPathExpandOpr) {
+        catch_panic(|| destroy_handle::<pb::PathExpand>(ptr))
     }
 
     #[no_mangle]
-    pub extern "C" fn init_pattern_operator() -> *const c_void {
-        let pattern = Box::new(pb::Pattern { sentences: vec![], meta_data: vec![] });
+    pub extern "C" fn init_pattern_operator() -> *const FfiPatternOpr {
+        catch_panic(|| {
+            let pattern = pb::Pattern { sentences: vec![], meta_data: vec![] };
 
-        Box::into_raw(pattern) as *const c_void
+            handle::register(pattern)
+        })
     }
 
     #[no_mangle]
     pub extern "C" fn add_pattern_sentence(
-        ptr_pattern: *const c_void, ptr_sentence: *const c_void,
+        ptr_pattern: *const FfiPatternOpr, ptr_sentence: *const FfiPatternSentence,
     ) -> FfiResult {
-        let mut pattern = unsafe { Box::from_raw(ptr_pattern as *mut pb::Pattern) };
-        let sentence = unsafe { Box::from_raw(ptr_sentence as *mut pb::pattern::Sentence) };
-        pattern
-            .sentences
-            .push(sentence.as_ref().clone());
-        std::mem::forget(pattern);
+        catch_panic(|| {
+            let mut pattern = match handle::borrow_mut::<pb::Pattern>(ptr_pattern) {
+                Ok(pattern) => pattern,
+                Err(e) => return e,
+            };
+            let sentence = match handle::release::<pb::pattern::Sentence>(ptr_sentence) {
+                Ok(sentence) => sentence,
+                Err(e) => return e,
+            };
+            pattern
+                .sentences
+                .push(sentence.as_ref().clone());
 
-        FfiResult::success()
+            FfiResult::success()
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn init_pattern_sentence(join_kind: FfiJoinKind) -> *const c_void {
-        let sentence = Box::new(pb::pattern::Sentence {
-            start: None,
-            binders: vec![],
-            end: None,
-            join_kind: unsafe { std::mem::transmute(join_kind) },
-        });
+    pub extern "C" fn init_pattern_sentence(join_kind: FfiJoinKind) -> *const FfiPatternSentence {
+        catch_panic(|| {
+            let sentence = pb::pattern::Sentence {
+                start: None,
+                binders: vec![],
+                end: None,
+                join_kind: unsafe { std::mem::transmute(join_kind) },
+            };
 
-        Box::into_raw(sentence) as *const c_void
+            handle::register(sentence)
+        })
     }
 
-    fn set_sentence_tag(ptr_sentence: *const c_void, tag: FfiNameOrId, is_start: bool) -> FfiResult {
-        let mut sentence = unsafe { Box::from_raw(ptr_sentence as *mut pb::pattern::Sentence) };
+    fn set_sentence_tag(
+        ptr_sentence: *const FfiPatternSentence, tag: FfiNameOrId, is_start: bool,
+    ) -> FfiResult {
+        let mut sentence = match handle::borrow_mut::<pb::pattern::Sentence>(ptr_sentence) {
+            Ok(sentence) => sentence,
+            Err(e) => return e,
+        };
         let pb_result = tag.try_into();
         let result = match pb_result {
             Ok(pb) => {
@@ -2306,19 +5933,22 @@ mod graph {
             }
             Err(e) => e,
         };
-        std::mem::forget(sentence);
 
         result
     }
 
     #[no_mangle]
-    pub extern "C" fn set_sentence_start(ptr_sentence: *const c_void, tag: FfiNameOrId) -> FfiResult {
-        set_sentence_tag(ptr_sentence, tag, true)
+    pub extern "C" fn set_sentence_start(
+        ptr_sentence: *const FfiPatternSentence, tag: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| set_sentence_tag(ptr_sentence, tag, true))
     }
 
     #[no_mangle]
-    pub extern "C" fn set_sentence_end(ptr_sentence: *const c_void, tag: FfiNameOrId) -> FfiResult {
-        set_sentence_tag(ptr_sentence, tag, false)
+    pub extern "C" fn set_sentence_end(
+        ptr_sentence: *const FfiPatternSentence, tag: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| set_sentence_tag(ptr_sentence, tag, false))
     }
 
     #[derive(Copy, Clone, Debug)]
@@ -2333,68 +5963,89 @@ mod graph {
 
     #[no_mangle]
     pub extern "C" fn add_sentence_binder(
-        ptr_sentence: *const c_void, ptr: *const c_void, binder: FfiBinderOpt,
-    ) -> FfiResult {
-        let mut sentence = unsafe { Box::from_raw(ptr_sentence as *mut pb::pattern::Sentence) };
-        match binder {
-            FfiBinderOpt::Edge => {
-                let edgexpd = unsafe { Box::from_raw(ptr as *mut pb::EdgeExpand) };
-                sentence.binders.push(pb::pattern::Binder {
-                    item: Some(pb::pattern::binder::Item::Edge(edgexpd.as_ref().clone())),
-                });
-            }
-            FfiBinderOpt::Path => {
-                let pathxpd = unsafe { Box::from_raw(ptr as *mut pb::PathExpand) };
-                sentence.binders.push(pb::pattern::Binder {
-                    item: Some(pb::pattern::binder::Item::Path(pathxpd.as_ref().clone())),
-                });
-            }
-            FfiBinderOpt::Vertex => {
-                let getv = unsafe { Box::from_raw(ptr as *mut pb::GetV) };
-                sentence.binders.push(pb::pattern::Binder {
-                    item: Some(pb::pattern::binder::Item::Vertex(getv.as_ref().clone())),
-                });
-            }
-            FfiBinderOpt::Select => {
-                let select = unsafe { Box::from_raw(ptr as *mut pb::Select) };
-                sentence.binders.push(pb::pattern::Binder {
-                    item: Some(pb::pattern::binder::Item::Select(select.as_ref().clone())),
-                });
+        ptr_sentence: *const FfiPatternSentence, ptr: *const c_void, binder: FfiBinderOpt,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut sentence = match handle::borrow_mut::<pb::pattern::Sentence>(ptr_sentence) {
+                Ok(sentence) => sentence,
+                Err(e) => return e,
+            };
+            match binder {
+                FfiBinderOpt::Edge => {
+                    let edgexpd = match handle::release::<pb::EdgeExpand>(ptr) {
+                        Ok(edgexpd) => edgexpd,
+                        Err(e) => return e,
+                    };
+                    sentence.binders.push(pb::pattern::Binder {
+                        item: Some(pb::pattern::binder::Item::Edge(edgexpd.as_ref().clone())),
+                    });
+                }
+                FfiBinderOpt::Path => {
+                    let pathxpd = match handle::release::<pb::PathExpand>(ptr) {
+                        Ok(pathxpd) => pathxpd,
+                        Err(e) => return e,
+                    };
+                    sentence.binders.push(pb::pattern::Binder {
+                        item: Some(pb::pattern::binder::Item::Path(pathxpd.as_ref().clone())),
+                    });
+                }
+                FfiBinderOpt::Vertex => {
+                    let getv = match handle::release::<pb::GetV>(ptr) {
+                        Ok(getv) => getv,
+                        Err(e) => return e,
+                    };
+                    sentence.binders.push(pb::pattern::Binder {
+                        item: Some(pb::pattern::binder::Item::Vertex(getv.as_ref().clone())),
+                    });
+                }
+                FfiBinderOpt::Select => {
+                    let select = match handle::release::<pb::Select>(ptr) {
+                        Ok(select) => select,
+                        Err(e) => return e,
+                    };
+                    sentence.binders.push(pb::pattern::Binder {
+                        item: Some(pb::pattern::binder::Item::Select(select.as_ref().clone())),
+                    });
+                }
             }
-        }
-        std::mem::forget(sentence);
 
-        FfiResult::success()
+            FfiResult::success()
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn add_pattern_meta(ptr_pattern: *const c_void, ptr_meta: FfiPbPointer) -> FfiResult {
-        let mut result = FfiResult::success();
-        let mut pattern = unsafe { Box::from_raw(ptr_pattern as *mut pb::Pattern) };
-        let meta_data = ptr_to_pb::<pb::MetaData>(ptr_meta);
+    pub extern "C" fn add_pattern_meta(
+        ptr_pattern: *const FfiPatternOpr, ptr_meta: FfiPbPointer,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let mut pattern = match handle::borrow_mut::<pb::Pattern>(ptr_pattern) {
+                Ok(pattern) => pattern,
+                Err(e) => return e,
+            };
+            let meta_data = ptr_to_pb::<pb::MetaData>(ptr_meta);
 
-        if !meta_data.is_ok() {
-            result = meta_data.err().unwrap();
-        } else {
-            pattern.meta_data.push(meta_data.unwrap());
-        }
-        std::mem::forget(pattern);
+            if !meta_data.is_ok() {
+                result = meta_data.err().unwrap();
+            } else {
+                pattern.meta_data.push(meta_data.unwrap());
+            }
 
-        result
+            result
+        })
     }
 
     /// Append a pattern operator to the logical plan
     #[no_mangle]
     pub extern "C" fn append_pattern_operator(
-        ptr_plan: *const c_void, ptr_pattern: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_pattern: *const FfiPatternOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let pattern = unsafe { Box::from_raw(ptr_pattern as *mut pb::Pattern) };
-        append_operator(ptr_plan, pattern.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::Pattern>(ptr_plan, ptr_pattern, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_pattern_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Pattern>(ptr)
+    pub extern "C" fn destroy_pattern_operator(ptr: *const FfiPatternOpr) {
+        catch_panic(|| destroy_handle::<pb::Pattern>(ptr))
     }
 }
 
@@ -2406,37 +6057,77 @@ mod subtask {
     /// the subtask, one need to first prepare the subtask and append the operators within to the
     /// logical plan.
     #[no_mangle]
-    pub extern "C" fn init_apply_operator(subtask_root: i32, join_kind: FfiJoinKind) -> *const c_void {
-        let apply = Box::new(pb::Apply {
-            join_kind: unsafe { std::mem::transmute::<FfiJoinKind, i32>(join_kind) },
-            tags: vec![],
-            subtask: subtask_root,
-            alias: None,
-        });
+    pub extern "C" fn init_apply_operator(subtask_root: i32, join_kind: FfiJoinKind) -> *const FfiApplyOpr {
+        catch_panic(|| {
+            let apply = pb::Apply {
+                join_kind: unsafe { std::mem::transmute::<FfiJoinKind, i32>(join_kind) },
+                tags: vec![],
+                subtask: subtask_root,
+                alias: None,
+                timeout_ms: 0,
+                captures: vec![],
+            };
 
-        Box::into_raw(apply) as *const c_void
+            handle::register(apply)
+        })
     }
 
     #[no_mangle]
-    pub extern "C" fn add_apply_tag(ptr_apply: *const c_void, ffi_tag: FfiNameOrId) -> FfiResult {
-        let mut result = FfiResult::success();
-        let tag_pb: Result<Option<common_pb::NameOrId>, FfiResult> = ffi_tag.try_into();
-        match tag_pb {
-            Ok(Some(tag)) => {
-                let mut apply = unsafe { Box::from_raw(ptr_apply as *mut pb::Apply) };
-                apply.tags.push(tag);
-                std::mem::forget(apply);
+    pub extern "C" fn add_apply_tag(ptr_apply: *const FfiApplyOpr, ffi_tag: FfiNameOrId) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let tag_pb: Result<Option<common_pb::NameOrId>, FfiResult> = ffi_tag.try_into();
+            match tag_pb {
+                Ok(Some(tag)) => {
+                    let mut apply = match handle::borrow_mut::<pb::Apply>(ptr_apply) {
+                        Ok(apply) => apply,
+                        Err(e) => return e,
+                    };
+                    apply.tags.push(tag);
+                }
+                Ok(None) => { /* do nothing */ }
+                Err(e) => result = e,
             }
-            Ok(None) => { /* do nothing */ }
-            Err(e) => result = e,
-        }
 
-        result
+            result
+        })
+    }
+
+    /// Declare a tag of the input that the subtask refers to, i.e., a correlated parameter of the
+    /// subtask. Once any is declared, appending the apply fails with `TagNotExistError` if a declared
+    /// tag is absent from its input, or the subtask refers to an outer tag that is not declared.
+    #[no_mangle]
+    pub extern "C" fn add_apply_capture(ptr_apply: *const FfiApplyOpr, ffi_tag: FfiNameOrId) -> FfiResult {
+        catch_panic(|| {
+            let tag_pb: Result<Option<common_pb::NameOrId>, FfiResult> = ffi_tag.try_into();
+            match tag_pb {
+                Ok(Some(tag)) => {
+                    let mut apply = match handle::borrow_mut::<pb::Apply>(ptr_apply) {
+                        Ok(apply) => apply,
+                        Err(e) => return e,
+                    };
+                    apply.captures.push(tag);
+                    FfiResult::success()
+                }
+                Ok(None) => FfiResult::new(
+                    ResultCode::MissingDataError,
+                    "the captured tag of the apply is missing".to_string(),
+                ),
+                Err(e) => e,
+            }
+        })
+    }
+
+    #[no_mangle]
+    pub extern "C" fn set_apply_alias(ptr_apply: *const FfiApplyOpr, alias: FfiAlias) -> FfiResult {
+        catch_panic(|| set_alias(ptr_apply, alias, InnerOpt::Apply))
     }
 
+    /// Set the soft timeout (in milliseconds) of the subtask, after which the subtask stops
+    /// taking more input, while the rest of the query goes on with partial results
     #[no_mangle]
-    pub extern "C" fn set_apply_alias(ptr_apply: *const c_void, alias: FfiAlias) -> FfiResult {
-        set_alias(ptr_apply, alias, InnerOpt::Apply)
+    pub extern "C" fn set_apply_timeout(ptr_apply: *const FfiApplyOpr, timeout_ms: u64) -> FfiResult {
+        catch_panic(|| set_timeout(ptr_apply, timeout_ms, InnerOpt::Apply))
     }
 
     /// Append an apply operator to the logical plan.
@@ -2444,61 +6135,212 @@ mod subtask {
     /// in the logical plan.
     #[no_mangle]
     pub extern "C" fn append_apply_operator(
-        ptr_plan: *const c_void, ptr_apply: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_apply: *const FfiApplyOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        let apply = unsafe { Box::from_raw(ptr_apply as *mut pb::Apply) };
-        append_operator(ptr_plan, apply.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| append_operator_handle::<pb::Apply>(ptr_plan, ptr_apply, vec![parent], id))
     }
 
     #[no_mangle]
-    pub extern "C" fn destroy_apply_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::Apply>(ptr)
+    pub extern "C" fn destroy_apply_operator(ptr: *const FfiApplyOpr) {
+        catch_panic(|| destroy_handle::<pb::Apply>(ptr))
     }
 
     /// To initialize a segment apply operator from an apply operator.
     #[no_mangle]
-    pub extern "C" fn init_segapply_operator(ptr_apply: *const c_void) -> *const c_void {
-        let apply = unsafe { Box::from_raw(ptr_apply as *mut pb::Apply) };
-        let segapply =
-            Box::new(pb::SegmentApply { keys: vec![], apply_subtask: Some(apply.as_ref().clone()) });
+    pub extern "C" fn init_segapply_operator(ptr_apply: *const FfiApplyOpr) -> *const FfiSegApplyOpr {
+        catch_panic(|| {
+            let apply = match handle::release::<pb::Apply>(ptr_apply) {
+                Ok(apply) => apply,
+                Err(_) => return std::ptr::null(),
+            };
+            let segapply = pb::SegmentApply { keys: vec![], apply_subtask: Some(*apply) };
 
-        Box::into_raw(segapply) as *const c_void
+            handle::register(segapply)
+        })
     }
 
     /// To add the key for grouping on which the segment apply can be conducted.
     #[no_mangle]
-    pub extern "C" fn add_segapply_key(ptr_segapply: *const c_void, ffi_key: FfiNameOrId) -> FfiResult {
-        let mut result = FfiResult::success();
-        let key_pb: Result<Option<common_pb::NameOrId>, FfiResult> = ffi_key.try_into();
-        match key_pb {
-            Ok(Some(key)) => {
-                let mut segapply = unsafe { Box::from_raw(ptr_segapply as *mut pb::SegmentApply) };
-                segapply.keys.push(key);
-                std::mem::forget(segapply);
+    pub extern "C" fn add_segapply_key(
+        ptr_segapply: *const FfiSegApplyOpr, ffi_key: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut result = FfiResult::success();
+            let key_pb: Result<Option<common_pb::NameOrId>, FfiResult> = ffi_key.try_into();
+            match key_pb {
+                Ok(Some(key)) => {
+                    let mut segapply = match handle::borrow_mut::<pb::SegmentApply>(ptr_segapply) {
+                        Ok(segapply) => segapply,
+                        Err(e) => return e,
+                    };
+                    segapply.keys.push(key);
+                }
+                Ok(None) => {}
+                Err(e) => result = e,
             }
-            Ok(None) => {}
-            Err(e) => result = e,
-        }
 
-        result
+            result
+        })
     }
 
     /// Append an apply operator to the logical plan. The parent node id for appending a segment apply operator
     /// must not be negative and must present in the logical plan.
     #[no_mangle]
     pub extern "C" fn append_segapply_operator(
-        ptr_plan: *const c_void, ptr_segapply: *const c_void, parent: i32, id: *mut i32,
+        ptr_plan: *const FfiLogicalPlan, ptr_segapply: *const FfiSegApplyOpr, parent: i32, id: *mut i32,
     ) -> FfiResult {
-        if parent < 0 {
-            FfiResult::new(ResultCode::NegativeIndexError, format!("invalid parent id {:?}", parent))
-        } else {
-            let segapply = unsafe { Box::from_raw(ptr_segapply as *mut pb::SegmentApply) };
-            append_operator(ptr_plan, segapply.as_ref().clone().into(), vec![parent], id)
+        catch_panic(|| {
+            if parent < 0 {
+                FfiResult::new(ResultCode::NegativeIndexError, format!("invalid parent id {:?}", parent))
+            } else {
+                append_operator_handle::<pb::SegmentApply>(ptr_plan, ptr_segapply, vec![parent], id)
+            }
+        })
+    }
+
+    #[no_mangle]
+    pub extern "C" fn destroy_segapply_operator(ptr: *const FfiSegApplyOpr) {
+        catch_panic(|| destroy_handle::<pb::SegmentApply>(ptr))
+    }
+}
+
+mod sample {
+    use dyn_type::Object;
+    use ir_common::NameOrId;
+
+    use super::*;
+    use crate::plan::eval::{self, SampleEntry, SampleRecord};
+
+    fn to_tag(tag: FfiNameOrId) -> Result<Option<NameOrId>, FfiResult> {
+        let tag_pb: Option<common_pb::NameOrId> = tag.try_into()?;
+        Ok(tag_pb
+            .map(NameOrId::try_from)
+            .transpose()
+            .map_err(IrError::from)?)
+    }
+
+    fn to_object(value: FfiConst) -> Result<Object, FfiResult> {
+        let value_pb: common_pb::Value = value.try_into()?;
+        Ok(Object::try_from(value_pb).map_err(IrError::from)?)
+    }
+
+    fn with_entry<F>(ptr_record: *const FfiSampleRecord, tag: FfiNameOrId, f: F) -> FfiResult
+    where
+        F: FnOnce(&mut SampleEntry) -> Result<(), FfiResult>,
+    {
+        let mut record = match handle::borrow_mut::<SampleRecord>(ptr_record) {
+            Ok(record) => record,
+            Err(e) => return e,
+        };
+        let result = to_tag(tag).and_then(|tag| match record.get_entry_mut(tag.as_ref()) {
+            Some(entry) => f(entry),
+            None => Err(IrError::TagNotExist(tag.unwrap_or_default()).into()),
+        });
+
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
         }
     }
 
+    /// To initialize a sample record, for evaluating the expressions over it via [`eval_expression`].
+    #[no_mangle]
+    pub extern "C" fn init_sample_record() -> *const FfiSampleRecord {
+        catch_panic(|| handle::register(SampleRecord::default()))
+    }
+
+    /// Set a graph element of the given id and label as the entry of the tag, or the head if the
+    /// tag is none, in the sample record.
+    #[no_mangle]
+    pub extern "C" fn set_sample_element(
+        ptr_record: *const FfiSampleRecord, tag: FfiNameOrId, id: i64, label: FfiNameOrId,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut record = match handle::borrow_mut::<SampleRecord>(ptr_record) {
+                Ok(record) => record,
+                Err(e) => return e,
+            };
+            let result = to_tag(tag).and_then(|tag| {
+                let label = to_tag(label)?;
+                record.set_entry(tag, SampleEntry::element(id, label));
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => FfiResult::success(),
+                Err(e) => e,
+            }
+        })
+    }
+
+    /// Set a value as the entry of the tag, or the head if the tag is none, in the sample record.
+    #[no_mangle]
+    pub extern "C" fn set_sample_value(
+        ptr_record: *const FfiSampleRecord, tag: FfiNameOrId, value: FfiConst,
+    ) -> FfiResult {
+        catch_panic(|| {
+            let mut record = match handle::borrow_mut::<SampleRecord>(ptr_record) {
+                Ok(record) => record,
+                Err(e) => return e,
+            };
+            let result = to_tag(tag).and_then(|tag| {
+                record.set_entry(tag, SampleEntry::value(to_object(value)?));
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => FfiResult::success(),
+                Err(e) => e,
+            }
+        })
+    }
+
+    /// Add a property to the graph element of the tag, or the head if the tag is none, in the sample
+    /// record, which must have been set via [`set_sample_element`].
+    #[no_mangle]
+    pub extern "C" fn add_sample_property(
+        ptr_record: *const FfiSampleRecord, tag: FfiNameOrId, key: FfiNameOrId, value: FfiConst,
+    ) -> FfiResult {
+        catch_panic(|| {
+            with_entry(ptr_record, tag, |entry| {
+                let key = to_tag(key)?.ok_or_else(|| {
+                    FfiResult::new(
+                        ResultCode::MissingDataError,
+                        "the key of the property is none".to_string(),
+                    )
+                })?;
+                entry.properties.insert(key, to_object(value)?);
+                Ok(())
+            })
+        })
+    }
+
+    /// Evaluate the expression over the sample record, as the runtime evaluates it in a `Select`
+    /// or a `Project`, which is returned as the bytes of a `common.Value`. A property that is absent
+    /// from the sample record is evaluated as none.
+    #[no_mangle]
+    pub extern "C" fn eval_expression(
+        cstr_expr: *const c_char, ptr_record: *const FfiSampleRecord,
+    ) -> FfiData {
+        catch_panic(|| {
+            let record = match handle::borrow::<SampleRecord>(ptr_record) {
+                Ok(record) => record,
+                Err(e) => return e.into(),
+            };
+            let result = cstr_to_expr_pb(cstr_expr)
+                .and_then(|expr| eval::eval_expression(&expr, &record).map_err(FfiResult::from));
+
+            match result {
+                Ok(value) => common_pb::Value::from(value)
+                    .encode_to_vec()
+                    .into(),
+                Err(e) => e.into(),
+            }
+        })
+    }
+
     #[no_mangle]
-    pub extern "C" fn destroy_segapply_operator(ptr: *const c_void) {
-        destroy_ptr::<pb::SegmentApply>(ptr)
+    pub extern "C" fn destroy_sample_record(ptr: *const FfiSampleRecord) {
+        catch_panic(|| destroy_handle::<SampleRecord>(ptr))
     }
 }
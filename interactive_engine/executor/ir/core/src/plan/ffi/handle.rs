@@ -0,0 +1,376 @@
+//
+//! Copyright 2023 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The registry of the handles of all the Rust-owned objects that are given to the callers of the
+//! ffi apis, e.g., the logical plans, the operators, the jobs and their results. A handle is an id
+//! looked up in the registry, rather than the address of the object, such that the misuses of a
+//! handle, e.g., using it after it is destroyed or consumed by an `append_xx_operator()` api, or as
+//! an object of another type, are reported as `InvalidHandleError` (or `NullPointerError` for a null
+//! handle) instead of being undefined behaviors.
+//!
+//! The object of a handle is referred either by any number of shared references of a single thread
+//! (see [`borrow`]), or by a single exclusive one (see [`borrow_mut`]), such that an api referring to
+//! the same object more than once, e.g., to compare a plan with itself, never aliases a mutable
+//! reference, and an object is never referred by two threads at a time, as it is not required to be
+//! `Sync`, e.g., a plan whose nodes are `RefCell`s. A conflicting reference is reported as
+//! `InvalidHandleError` if it comes from the same thread, or from another thread while the object is
+//! not concurrent, which otherwise waits for the other threads referring to the object (see
+//! [`register_concurrent`]).
+
+use std::any::{type_name, TypeId};
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+use crate::plan::ffi::{FfiResult, ResultCode};
+
+/// The id of a handle, which starts from 1, such that a null pointer is never a valid handle,
+/// and is never reused, such that a stale handle never refers to a later object.
+pub type HandleId = u64;
+
+struct HandleEntry {
+    /// The address of the boxed object
+    addr: usize,
+    type_id: TypeId,
+    type_name: &'static str,
+    lock: Arc<HandleLock>,
+}
+
+/// The threads referring to an object, either by the shared references, or by an exclusive one.
+#[derive(Default)]
+struct Borrows {
+    /// The threads holding the shared references, each as many times as it holds
+    shared: Vec<ThreadId>,
+    exclusive: Option<ThreadId>,
+}
+
+/// The lock that tracks the references to an object, which serializes the accesses to a concurrent
+/// object from multiple threads.
+#[derive(Default)]
+struct HandleLock {
+    is_concurrent: bool,
+    borrows: Mutex<Borrows>,
+    released: Condvar,
+}
+
+impl HandleLock {
+    fn new(is_concurrent: bool) -> Self {
+        HandleLock { is_concurrent, ..Default::default() }
+    }
+
+    fn lock(self: &Arc<Self>, handle: *const c_void, is_exclusive: bool) -> Result<HandleGuard, FfiResult> {
+        let this = thread::current().id();
+        let mut borrows = self
+            .borrows
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        loop {
+            // a shared reference conflicts with the exclusive one, and with the shared ones of the
+            // other threads, while an exclusive one conflicts with any other
+            let holders: Vec<ThreadId> = if is_exclusive {
+                borrows
+                    .shared
+                    .iter()
+                    .chain(borrows.exclusive.iter())
+                    .cloned()
+                    .collect()
+            } else {
+                borrows
+                    .shared
+                    .iter()
+                    .filter(|id| **id != this)
+                    .chain(borrows.exclusive.iter())
+                    .cloned()
+                    .collect()
+            };
+            if holders.is_empty() {
+                break;
+            }
+            if holders.contains(&this) {
+                return Err(borrowed_handle(handle, "by the same thread"));
+            }
+            if !self.is_concurrent {
+                return Err(borrowed_handle(handle, "by another thread, while it is not concurrent"));
+            }
+            borrows = self
+                .released
+                .wait(borrows)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        if is_exclusive {
+            borrows.exclusive = Some(this);
+        } else {
+            borrows.shared.push(this);
+        }
+
+        Ok(HandleGuard { lock: self.clone(), is_exclusive })
+    }
+}
+
+struct HandleGuard {
+    lock: Arc<HandleLock>,
+    is_exclusive: bool,
+}
+
+impl Drop for HandleGuard {
+    fn drop(&mut self) {
+        let this = thread::current().id();
+        let mut borrows = self
+            .lock
+            .borrows
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if self.is_exclusive {
+            borrows.exclusive = None;
+        } else if let Some(pos) = borrows.shared.iter().position(|id| *id == this) {
+            borrows.shared.swap_remove(pos);
+        }
+        self.lock.released.notify_all();
+    }
+}
+
+struct HandleRegistry {
+    next_id: HandleId,
+    entries: HashMap<HandleId, HandleEntry>,
+}
+
+lazy_static! {
+    static ref HANDLE_REGISTRY: Mutex<HandleRegistry> =
+        Mutex::new(HandleRegistry { next_id: 1, entries: HashMap::new() });
+}
+
+fn registry() -> MutexGuard<'static, HandleRegistry> {
+    // The registry is never left inconsistent by a panic, so it is safe to recover from poisoning.
+    HANDLE_REGISTRY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// A shared reference to the object of a handle, which is valid while the handle is not released,
+/// and keeps the object from being referred exclusively until it is dropped.
+pub(crate) struct HandleRef<T> {
+    ptr: *const T,
+    _guard: HandleGuard,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Deref for HandleRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+/// An exclusive reference to the object of a handle, which is valid while the handle is not released,
+/// and keeps the object from being referred otherwise until it is dropped.
+pub(crate) struct HandleRefMut<T> {
+    ptr: *mut T,
+    _guard: HandleGuard,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Deref for HandleRefMut<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T> DerefMut for HandleRefMut<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+fn invalid_handle(handle: *const c_void) -> FfiResult {
+    FfiResult::new(
+        ResultCode::InvalidHandleError,
+        format!(
+            "the handle {:?} is invalid, which may have been destroyed or consumed",
+            handle as HandleId
+        ),
+    )
+}
+
+fn borrowed_handle(handle: *const c_void, by: &str) -> FfiResult {
+    FfiResult::new(
+        ResultCode::InvalidHandleError,
+        format!("the handle {:?} is being referred {}", handle as HandleId, by),
+    )
+}
+
+/// Look up the entry of the handle, which must refer to an object of type `T`. A null handle is told
+/// apart from the invalid ones, as it is more likely a handle that has never been initialized.
+fn check_entry<T: 'static>(entry: Option<&HandleEntry>, handle: *const c_void) -> Result<usize, FfiResult> {
+    if handle.is_null() {
+        return Err(FfiResult::new(
+            ResultCode::NullPointerError,
+            format!("the handle of a {:?} is a null pointer", type_name::<T>()),
+        ));
+    }
+    match entry {
+        Some(entry) if entry.type_id == TypeId::of::<T>() => Ok(entry.addr),
+        Some(entry) => Err(FfiResult::new(
+            ResultCode::InvalidHandleError,
+            format!(
+                "the handle {:?} refers to a {:?} rather than a {:?}",
+                handle as HandleId,
+                entry.type_name,
+                type_name::<T>()
+            ),
+        )),
+        None => Err(invalid_handle(handle)),
+    }
+}
+
+fn insert<T: 'static, P>(obj: T, is_concurrent: bool) -> *const P {
+    let addr = Box::into_raw(Box::new(obj)) as usize;
+    let mut registry = registry();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.entries.insert(
+        id,
+        HandleEntry {
+            addr,
+            type_id: TypeId::of::<T>(),
+            type_name: type_name::<T>(),
+            lock: Arc::new(HandleLock::new(is_concurrent)),
+        },
+    );
+
+    id as *const P
+}
+
+/// Move the object into the registry, returning its handle, which is typed by the opaque type `P`
+/// of the object given to the callers, e.g., `FfiLogicalPlan` for a logical plan.
+pub(crate) fn register<T: 'static, P>(obj: T) -> *const P {
+    insert(obj, false)
+}
+
+/// Move the object into the registry as a concurrent one, returning its handle, where the accesses
+/// to the object from multiple threads are serialized by the lock of the object, and thus it must
+/// be safe to send the object to another thread.
+pub(crate) fn register_concurrent<T: Send + 'static, P>(obj: T) -> *const P {
+    insert(obj, true)
+}
+
+/// Lock the object of the handle, which must be of type `T`, for a shared or an exclusive reference,
+/// which waits for the other threads referring to the object if it is concurrent.
+fn lock<T: 'static>(handle: *const c_void, is_exclusive: bool) -> Result<(usize, HandleGuard), FfiResult> {
+    let id = handle as HandleId;
+    let (addr, lock) = {
+        let registry = registry();
+        let entry = registry.entries.get(&id);
+        (check_entry::<T>(entry, handle)?, entry.unwrap().lock.clone())
+    };
+    let guard = lock.lock(handle, is_exclusive)?;
+    // the object may have been released by another thread while waiting for its lock
+    if !is_valid(handle) {
+        return Err(invalid_handle(handle));
+    }
+
+    Ok((addr, guard))
+}
+
+/// Refer to the object of the handle by a shared reference, without taking it out of the registry.
+pub(crate) fn borrow<T: 'static>(handle: *const impl Sized) -> Result<HandleRef<T>, FfiResult> {
+    let (addr, guard) = lock::<T>(handle as *const c_void, false)?;
+
+    Ok(HandleRef { ptr: addr as *const T, _guard: guard, _phantom: PhantomData })
+}
+
+/// Refer to the object of the handle by an exclusive reference, without taking it out of the registry.
+pub(crate) fn borrow_mut<T: 'static>(handle: *const impl Sized) -> Result<HandleRefMut<T>, FfiResult> {
+    let (addr, guard) = lock::<T>(handle as *const c_void, true)?;
+
+    Ok(HandleRefMut { ptr: addr as *mut T, _guard: guard, _phantom: PhantomData })
+}
+
+/// Take the object of the handle out of the registry, after which the handle is invalid. If the
+/// object is concurrent, it is taken after the other threads referring to it are done.
+pub(crate) fn release<T: 'static>(handle: *const impl Sized) -> Result<Box<T>, FfiResult> {
+    let handle = handle as *const c_void;
+    let (addr, _guard) = lock::<T>(handle, true)?;
+    if registry()
+        .entries
+        .remove(&(handle as HandleId))
+        .is_none()
+    {
+        return Err(invalid_handle(handle));
+    }
+
+    Ok(unsafe { Box::from_raw(addr as *mut T) })
+}
+
+/// Whether the handle refers to a live object, of any type.
+pub(crate) fn is_valid(handle: *const impl Sized) -> bool {
+    registry()
+        .entries
+        .contains_key(&(handle as *const c_void as HandleId))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrow_shared_or_exclusive() {
+        let handle = register::<_, c_void>(vec![1, 2, 3]);
+        {
+            // e.g., to compare an object with itself
+            let first = borrow::<Vec<i32>>(handle).unwrap();
+            let second = borrow::<Vec<i32>>(handle).unwrap();
+            assert_eq!(*first, *second);
+            assert!(borrow_mut::<Vec<i32>>(handle).is_err());
+            // but never shared with another thread at a time
+            let id = handle as usize;
+            assert!(std::thread::spawn(move || borrow::<Vec<i32>>(id as *const c_void).is_err())
+                .join()
+                .unwrap());
+        }
+        {
+            let mut exclusive = borrow_mut::<Vec<i32>>(handle).unwrap();
+            assert!(borrow::<Vec<i32>>(handle).is_err());
+            assert!(borrow_mut::<Vec<i32>>(handle).is_err());
+            assert!(release::<Vec<i32>>(handle).is_err());
+            exclusive.push(4);
+        }
+        assert_eq!(*release::<Vec<i32>>(handle).unwrap(), vec![1, 2, 3, 4]);
+        assert!(!is_valid(handle));
+    }
+
+    #[test]
+    fn borrow_concurrent() {
+        let handle = register_concurrent::<_, c_void>(0_usize) as usize;
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        *borrow_mut::<usize>(handle as *const c_void).unwrap() += 1;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*release::<usize>(handle as *const c_void).unwrap(), 400);
+    }
+}
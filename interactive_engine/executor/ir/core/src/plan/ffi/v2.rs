@@ -0,0 +1,330 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The v2 apis of building the plan, which refer to the logical plan via a typed handle
+//! instead of a `void*`, and append any operator given as the bytes of a
+//! `logical_plan::Operator` via a generic [`append_operator_v2`]. The v1 apis that these
+//! apis supersede are kept as deprecated shims, which delegate to the v2 ones, such that
+//! the existing callers keep linking while migrating, as:
+//!
+//! # Example
+//!
+//! # FfiLogicalPlan* plan = init_logical_plan_v2();
+//! # int32_t id = 0;
+//! # append_operator_v2(plan, { scan.ptr, scan.len }, NULL, 0, &id);
+//! # int32_t parents[1] = { id };
+//! # append_operator_v2(plan, { expand.ptr, expand.len }, parents, 1, &id);
+//! # FfiData physical = build_physical_plan_v2(plan, 1, 1);
+//! # destroy_ffi_data(physical);
+//! # destroy_logical_plan_v2(plan);
+
+use std::os::raw::c_char;
+
+use ir_common::generated::algebra as pb;
+use ir_physical_client::physical_builder::PlanBuilder;
+use prost::Message;
+
+use crate::plan::bundle::PlanBundle;
+use crate::plan::ffi::{
+    catch_panic, check_not_null, cstr_to_string, destroy_handle, handle, ptr_to_pb, set_output, FfiData,
+    FfiLogicalPlan, FfiPbPointer, FfiResult, ResultCode,
+};
+use crate::plan::logical::{default_display_name, LogicalPlan, NodeId};
+use crate::plan::physical::AsPhysical;
+use crate::JsonIO;
+
+/// Run `f` on the logical plan referred by the handle, without taking its ownership, which
+/// fails with `InvalidHandleError` if the handle does not refer to a live logical plan.
+fn with_plan<T, F: FnOnce(&mut LogicalPlan) -> T>(plan: *mut FfiLogicalPlan, f: F) -> Result<T, FfiResult> {
+    let mut plan = handle::borrow_mut::<LogicalPlan>(plan)?;
+    Ok(f(&mut *plan))
+}
+
+/// Initialize a logical plan.
+#[no_mangle]
+pub extern "C" fn init_logical_plan_v2() -> *mut FfiLogicalPlan {
+    catch_panic(|| handle::register::<_, FfiLogicalPlan>(LogicalPlan::default()) as *mut FfiLogicalPlan)
+}
+
+/// Initialize a logical plan that can be built from multiple threads, e.g., by appending the
+/// operators concurrently, where the apis referring to the plan are serialized by a lock of the
+/// plan, such that each appended operator is given a distinct id. As the ids then depend on the
+/// order in which the threads append the operators, the ids can be reassigned deterministically via
+/// `canonicalize_node_ids()` once the plan is built. The plan is released via [`destroy_logical_plan_v2`].
+///
+/// Note that the write-through callback of the plan, if any, is called from the appending threads.
+#[no_mangle]
+pub extern "C" fn init_concurrent_logical_plan() -> *mut FfiLogicalPlan {
+    catch_panic(|| {
+        handle::register_concurrent::<_, FfiLogicalPlan>(LogicalPlan::default()) as *mut FfiLogicalPlan
+    })
+}
+
+/// To destroy a logical plan.
+#[no_mangle]
+pub extern "C" fn destroy_logical_plan_v2(plan: *mut FfiLogicalPlan) {
+    catch_panic(|| destroy_handle::<LogicalPlan>(plan))
+}
+
+/// Append an operator, given as the bytes of a `logical_plan::Operator`, to the logical plan,
+/// as the child of the `num_parents` parents given by `parents`, or as a root if `num_parents`
+/// is 0. The id of the appended operator is written to `id`.
+#[no_mangle]
+pub extern "C" fn append_operator_v2(
+    plan: *mut FfiLogicalPlan, opr: FfiPbPointer, parents: *const i32, num_parents: usize, id: *mut i32,
+) -> FfiResult {
+    catch_panic(|| {
+        // checked ahead, as the operator is otherwise appended without its id being known
+        if let Err(e) = check_not_null(id, "id of the operator") {
+            return e;
+        }
+        let result = parents_from_raw(parents, num_parents).and_then(|parent_ids| {
+            let opr = ptr_to_pb::<pb::logical_plan::Operator>(opr)?;
+            let context = format!("appending {}", default_display_name(&opr));
+            let node_id = with_plan(plan, |plan| {
+                let next_id = plan.get_max_node_id();
+                plan.append_operator_as_node(opr, parent_ids)
+                    .map_err(|e| {
+                        FfiResult::from(e)
+                            .with_context(&context)
+                            .at_node(next_id)
+                    })
+            })??;
+            Ok(node_id as i32)
+        });
+        set_output(id, result)
+    })
+}
+
+fn parents_from_raw(parents: *const i32, num_parents: usize) -> Result<Vec<NodeId>, FfiResult> {
+    if num_parents == 0 {
+        return Ok(vec![]);
+    }
+    check_not_null(parents, "parents of the operator")?;
+    unsafe { std::slice::from_raw_parts(parents, num_parents) }
+        .iter()
+        .map(|&parent| {
+            if parent < 0 {
+                Err(FfiResult::new(
+                    ResultCode::NegativeIndexError,
+                    format!("invalid parent id {:?}", parent),
+                ))
+            } else {
+                Ok(parent as NodeId)
+            }
+        })
+        .collect()
+}
+
+/// Build the physical plan from the logical plan, returned as the bytes of a pegasus job.
+#[no_mangle]
+pub extern "C" fn build_physical_plan_v2(
+    plan: *mut FfiLogicalPlan, num_workers: u32, num_servers: u32,
+) -> FfiData {
+    catch_panic(|| {
+        with_plan(plan, |plan| {
+            if num_workers > 1 || num_servers > 1 {
+                plan.meta = plan.meta.clone().with_partition();
+            }
+            let mut plan_meta = plan.meta.clone();
+            let mut builder = PlanBuilder::default();
+            match plan.add_job_builder(&mut builder, &mut plan_meta) {
+                Ok(_) => builder.build().encode_to_vec().into(),
+                Err(e) => e.into(),
+            }
+        })
+        .unwrap_or_else(FfiData::from)
+    })
+}
+
+/// Print the logical plan as json, returned as the bytes of the json string.
+#[no_mangle]
+pub extern "C" fn plan_to_json_v2(plan: *mut FfiLogicalPlan) -> FfiData {
+    catch_panic(|| {
+        let plan_pb: pb::LogicalPlan = match with_plan(plan, |plan| plan.clone().into()) {
+            Ok(plan_pb) => plan_pb,
+            Err(e) => return e.into(),
+        };
+        match serde_json::to_vec_pretty(&plan_pb) {
+            Ok(json) => json.into(),
+            Err(e) => FfiResult::new(ResultCode::Others, e.to_string()).into(),
+        }
+    })
+}
+
+/// Build a logical plan from the json printed via [`plan_to_json_v2`], e.g., kept in a test fixture
+/// or edited by hand while debugging, which is written to `plan`, and must be released via
+/// [`destroy_logical_plan_v2`].
+#[no_mangle]
+pub extern "C" fn plan_from_json_v2(cstr_json: *const c_char, plan: *mut *mut FfiLogicalPlan) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(plan, "output plan") {
+            return e;
+        }
+        let result = cstr_to_string(cstr_json).and_then(|json| {
+            LogicalPlan::from_json(json.as_bytes())
+                .map(|plan| handle::register::<_, FfiLogicalPlan>(plan) as *mut FfiLogicalPlan)
+                .map_err(|e| FfiResult::new(ResultCode::ParsePbError, format!("invalid plan json: {}", e)))
+        });
+        set_output(plan, result)
+    })
+}
+
+/// Print the logical plan as json for debugging, returned as the bytes of the json string, in which
+/// the literal constants (e.g., in the predicates), the annotations and the display names are
+/// redacted while the structure is kept, such that the plan can be safely logged in production.
+#[no_mangle]
+pub extern "C" fn plan_to_debug_string_redacted(plan: *mut FfiLogicalPlan) -> FfiData {
+    catch_panic(|| {
+        with_plan(plan, |plan| match plan.to_debug_string_redacted() {
+            Ok(json) => json.into_bytes().into(),
+            Err(e) => e.into(),
+        })
+        .unwrap_or_else(FfiData::from)
+    })
+}
+
+/// Serialize the logical plan, returned as the bytes of a `LogicalPlan`, or of a
+/// `CompactLogicalPlan` if `with_string_table` is set, which keeps each distinct name of the
+/// tags, labels and properties once in a string table.
+#[no_mangle]
+pub extern "C" fn plan_to_pb_v2(plan: *mut FfiLogicalPlan, with_string_table: bool) -> FfiData {
+    catch_panic(|| {
+        with_plan(plan, |plan| {
+            if with_string_table {
+                match plan.to_compact_pb() {
+                    Ok(plan_pb) => plan_pb.encode_to_vec().into(),
+                    Err(e) => e.into(),
+                }
+            } else {
+                let plan_pb: pb::LogicalPlan = plan.clone().into();
+                plan_pb.encode_to_vec().into()
+            }
+        })
+        .unwrap_or_else(FfiData::from)
+    })
+}
+
+/// Copy the nodes lying between the nodes of `from_id` and `to_id` (both included) into a standalone
+/// logical plan (see [`LogicalPlan::extract_subplan_between`]), e.g., for caching or explaining a
+/// fragment of the plan. The handle of the new plan is written to `subplan`, which must be released
+/// via [`destroy_logical_plan_v2`].
+#[no_mangle]
+pub extern "C" fn extract_subplan(
+    plan: *mut FfiLogicalPlan, from_id: i32, to_id: i32, subplan: *mut *mut FfiLogicalPlan,
+) -> FfiResult {
+    catch_panic(|| {
+        if from_id < 0 || to_id < 0 {
+            return FfiResult::new(
+                ResultCode::NegativeIndexError,
+                format!("invalid node ids ({:?}, {:?})", from_id, to_id),
+            );
+        }
+        let result =
+            with_plan(plan, |plan| plan.extract_subplan_between(from_id as NodeId, to_id as NodeId))
+                .and_then(|fragment| fragment.map_err(FfiResult::from))
+                .map(|fragment| handle::register::<_, FfiLogicalPlan>(fragment) as *mut FfiLogicalPlan);
+        set_output(subplan, result)
+    })
+}
+
+/// Copy the logical plan into an independent one (see [`LogicalPlan::clone_plan`]), e.g., to keep a
+/// base plan as a template that is extended in different directions. The handle of the copy is written
+/// to `cloned`, which must be released via [`destroy_logical_plan_v2`].
+#[no_mangle]
+pub extern "C" fn clone_logical_plan(
+    plan: *mut FfiLogicalPlan, cloned: *mut *mut FfiLogicalPlan,
+) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(cloned, "output plan") {
+            return e;
+        }
+        let result = with_plan(plan, |plan| plan.clone_plan())
+            .map(|plan| handle::register::<_, FfiLogicalPlan>(plan) as *mut FfiLogicalPlan);
+        set_output(cloned, result)
+    })
+}
+
+/// The opaque handle of a bundle of logical plans (statements) that is submitted as a unit, which
+/// is owned by Rust, and must be released via [`destroy_plan_bundle`].
+#[repr(C)]
+pub struct FfiPlanBundle {
+    _private: [u8; 0],
+}
+
+fn with_bundle<T, F: FnOnce(&mut PlanBundle) -> T>(
+    bundle: *mut FfiPlanBundle, f: F,
+) -> Result<T, FfiResult> {
+    let mut bundle = handle::borrow_mut::<PlanBundle>(bundle)?;
+    Ok(f(&mut *bundle))
+}
+
+/// Initialize a bundle of plans.
+#[no_mangle]
+pub extern "C" fn init_plan_bundle() -> *mut FfiPlanBundle {
+    catch_panic(|| handle::register::<_, FfiPlanBundle>(PlanBundle::default()) as *mut FfiPlanBundle)
+}
+
+/// To destroy a bundle of plans.
+#[no_mangle]
+pub extern "C" fn destroy_plan_bundle(bundle: *mut FfiPlanBundle) {
+    catch_panic(|| destroy_handle::<PlanBundle>(bundle))
+}
+
+/// Add (a copy of) the logical plan to the bundle as a statement of the unique `name`, which depends
+/// on the `num_dependencies` preceding statements given by their indices in `dependencies`.
+/// The index of the added statement is written to `index`.
+#[no_mangle]
+pub extern "C" fn bundle_add_plan(
+    bundle: *mut FfiPlanBundle, plan: *mut FfiLogicalPlan, name: *const c_char, dependencies: *const i32,
+    num_dependencies: usize, index: *mut i32,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(name).and_then(|name| {
+            let dependencies = parents_from_raw(dependencies, num_dependencies)?
+                .into_iter()
+                .map(|dep| dep as usize)
+                .collect();
+            let plan = with_plan(plan, |plan| plan.clone())?;
+            let index = with_bundle(bundle, |bundle| bundle.add_statement(name, plan, dependencies))??;
+            Ok(index as i32)
+        });
+        set_output(index, result)
+    })
+}
+
+/// Serialize the bundle, returned as the bytes of a `PlanBundle`.
+#[no_mangle]
+pub extern "C" fn bundle_to_pb(bundle: *mut FfiPlanBundle) -> FfiData {
+    catch_panic(|| match with_bundle(bundle, |bundle| pb::PlanBundle::from(bundle.clone())) {
+        Ok(bundle_pb) => bundle_pb.encode_to_vec().into(),
+        Err(e) => e.into(),
+    })
+}
+
+/// Build the physical plans of the statements in the bundle, returned as the bytes of a
+/// `PhysicalPlanBundle`, which is submitted via `submit_job_bundle()`.
+#[no_mangle]
+pub extern "C" fn build_physical_bundle(
+    bundle: *mut FfiPlanBundle, num_workers: u32, num_servers: u32,
+) -> FfiData {
+    catch_panic(|| {
+        with_bundle(bundle, |bundle| match bundle.build_physical(num_workers > 1 || num_servers > 1) {
+            Ok(bundle_pb) => bundle_pb.encode_to_vec().into(),
+            Err(e) => e.into(),
+        })
+        .unwrap_or_else(FfiData::from)
+    })
+}
@@ -13,23 +13,32 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
-use std::cell::RefCell;
-use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
-use std::rc::Rc;
+use std::io;
+use std::sync::Arc;
+use std::time::Instant;
 
 use ir_common::error::ParsePbError;
+use ir_common::expr_parse::expr_pb_to_str;
 use ir_common::generated::algebra as pb;
 use ir_common::generated::algebra::pattern::binder::Item;
 use ir_common::generated::common as common_pb;
-use ir_common::{KeyId, NameOrId};
+use ir_common::{KeyId, LabelId, NameOrId};
+use prost::Message;
 use vec_map::VecMap;
 
 use crate::error::{IrError, IrResult};
+use crate::glogue::combine_exprs;
 use crate::glogue::error::IrPatternError;
-use crate::plan::meta::{ColumnsOpt, PlanMeta, Schema, StoreMeta, TagId, INVALID_META_ID, STORE_META};
+use crate::plan::meta::{
+    ColumnsOpt, IdEncoding, PlanMeta, Schema, StoreMeta, SyncCell, TagId, INVALID_META_ID, STORE_META,
+};
+use crate::plan::normalize::{normalize_operator, reorder_predicate};
 use crate::plan::patmat::{ExtendStrategy, MatchingStrategy, NaiveStrategy};
+use crate::plan::upgrade::{upgrade_plan, PLAN_FORMAT_VERSION};
+use crate::JsonIO;
 
 // Note that protobuf only support signed integer, while we actually requires the nodes'
 // id being non-negative
@@ -45,12 +54,360 @@ pub struct Node {
     pub(crate) opr: pb::logical_plan::Operator,
     pub(crate) parents: BTreeSet<NodeId>,
     pub(crate) children: BTreeSet<NodeId>,
+    /// The free-form annotations of the node
+    pub(crate) annotations: Vec<String>,
+    /// The key/value annotations of the node
+    pub(crate) attributes: BTreeMap<String, String>,
+    /// The human-readable name of the node, if it is given
+    pub(crate) display_name: Option<String>,
+}
+
+/// The rules of linting a logical plan, see [`LogicalPlan::lint`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LintRule {
+    /// A cartesian product that is not followed by any limit
+    CartesianWithoutLimit = 0,
+    /// A path expansion without an upper bound of its hops
+    UnboundedPathExpand = 1,
+    /// A dedup that destroys the ordering established by a preceding order
+    DedupAfterOrder = 2,
+    /// An alias that is never referred to
+    UnusedAlias = 3,
+}
+
+/// A warning of linting a logical plan, which does not fail the plan, but likely makes the query
+/// slow, or is a mistake.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanWarning {
+    pub rule: LintRule,
+    /// The id of the node that is warned about
+    pub node: NodeId,
+    pub message: String,
+}
+
+/// A difference of a logical plan from another, see [`LogicalPlan::diff`], where the nodes are
+/// referred by their indices in the serialized plans, i.e., the ids of the nodes after the removed
+/// ones are compacted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlanDiff {
+    /// A node that presents in the other plan only
+    NodeAdded(NodeId),
+    /// A node that presents in this plan only
+    NodeRemoved(NodeId),
+    /// A node of which the operators differ, given as the operators of this and the other plan
+    OperatorChanged { node: NodeId, this: String, other: String },
+    /// An edge from the parent to the child that presents in the other plan only
+    EdgeAdded(NodeId, NodeId),
+    /// An edge from the parent to the child that presents in this plan only
+    EdgeRemoved(NodeId, NodeId),
+}
+
+impl fmt::Display for PlanDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanDiff::NodeAdded(id) => write!(f, "+ node #{}", id),
+            PlanDiff::NodeRemoved(id) => write!(f, "- node #{}", id),
+            PlanDiff::OperatorChanged { node, this, other } => {
+                write!(f, "~ node #{}:\n  - {}\n  + {}", node, this, other)
+            }
+            PlanDiff::EdgeAdded(parent, child) => write!(f, "+ edge #{} -> #{}", parent, child),
+            PlanDiff::EdgeRemoved(parent, child) => write!(f, "- edge #{} -> #{}", parent, child),
+        }
+    }
+}
+
+fn name_or_id_to_string(name_or_id: &common_pb::NameOrId) -> String {
+    match &name_or_id.item {
+        Some(common_pb::name_or_id::Item::Name(name)) => name.clone(),
+        Some(common_pb::name_or_id::Item::Id(id)) => id.to_string(),
+        None => "".to_string(),
+    }
+}
+
+fn tables_to_string(params: &Option<pb::QueryParams>) -> Option<String> {
+    params
+        .as_ref()
+        .filter(|params| !params.tables.is_empty())
+        .map(|params| {
+            params
+                .tables
+                .iter()
+                .map(name_or_id_to_string)
+                .collect::<Vec<_>>()
+                .join("|")
+        })
+}
+
+fn range_to_string(range: &Option<pb::Range>) -> Option<String> {
+    range
+        .as_ref()
+        .map(|range| format!("{}..{}", range.lower, range.upper))
+}
+
+/// The default display name of an operator, which is the operator kind plus its key fields,
+/// e.g., `EdgeExpand[knows, OUT]`.
+pub(crate) fn default_display_name(opr: &pb::logical_plan::Operator) -> String {
+    use pb::logical_plan::operator::Opr;
+
+    let (kind, fields): (&str, Vec<Option<String>>) = match &opr.opr {
+        Some(Opr::Project(_)) => ("Project", vec![]),
+        Some(Opr::Select(_)) => ("Select", vec![]),
+        Some(Opr::Join(join)) => (
+            "Join",
+            vec![pb::join::JoinKind::from_i32(join.kind).map(|k| format!("{:?}", k).to_uppercase())],
+        ),
+        Some(Opr::Union(_)) => ("Union", vec![]),
+        Some(Opr::GroupBy(_)) => ("GroupBy", vec![]),
+        Some(Opr::Count(count)) => ("Count", vec![count.tag.as_ref().map(name_or_id_to_string)]),
+        Some(Opr::OrderBy(orderby)) => ("OrderBy", vec![range_to_string(&orderby.limit)]),
+        Some(Opr::Dedup(_)) => ("Dedup", vec![]),
+        Some(Opr::Unfold(_)) => ("Unfold", vec![]),
+        Some(Opr::Apply(_)) => ("Apply", vec![]),
+        Some(Opr::SegApply(_)) => ("SegmentApply", vec![]),
+        Some(Opr::Scan(scan)) => ("Scan", vec![tables_to_string(&scan.params)]),
+        Some(Opr::Limit(limit)) => ("Limit", vec![range_to_string(&limit.range)]),
+        Some(Opr::As(as_opr)) => ("As", vec![as_opr.alias.as_ref().map(name_or_id_to_string)]),
+        Some(Opr::Intersect(_)) => ("Intersect", vec![]),
+        Some(Opr::Sink(_)) => ("Sink", vec![]),
+        Some(Opr::Vertex(getv)) => (
+            "GetV",
+            vec![pb::get_v::VOpt::from_i32(getv.opt).map(|opt| format!("{:?}", opt).to_uppercase())],
+        ),
+        Some(Opr::Edge(edgexpd)) => (
+            "EdgeExpand",
+            vec![
+                tables_to_string(&edgexpd.params),
+                pb::edge_expand::Direction::from_i32(edgexpd.direction)
+                    .map(|dir| format!("{:?}", dir).to_uppercase()),
+            ],
+        ),
+        Some(Opr::Path(pathxpd)) => ("PathExpand", vec![range_to_string(&pathxpd.hop_range)]),
+        Some(Opr::Pattern(_)) => ("Pattern", vec![]),
+        None => ("Unknown", vec![]),
+    };
+    let fields = fields.into_iter().flatten().collect::<Vec<_>>();
+    if fields.is_empty() {
+        kind.to_string()
+    } else {
+        format!("{}[{}]", kind, fields.join(", "))
+    }
+}
+
+fn var_to_string(var: &common_pb::Variable) -> String {
+    expr_pb_to_str(&common_pb::Expression { operators: vec![var.clone().into()] })
+}
+
+fn vars_to_string(vars: &[common_pb::Variable]) -> String {
+    vars.iter()
+        .map(var_to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The details of an operator that are explained after its display name (see [`LogicalPlan::explain`]),
+/// e.g., the predicate of a `Select` as `WHERE @a.age > 10`, and the alias of an `EdgeExpand` as `AS b`.
+fn explain_details(opr: &pb::logical_plan::Operator) -> Vec<String> {
+    use pb::logical_plan::operator::Opr;
+
+    let predicate = |params: &Option<pb::QueryParams>| {
+        params
+            .as_ref()
+            .and_then(|params| params.predicate.as_ref())
+            .map(|predicate| format!("WHERE {}", expr_pb_to_str(predicate)))
+    };
+    let (details, alias): (Vec<Option<String>>, Option<&common_pb::NameOrId>) = match &opr.opr {
+        Some(Opr::Scan(scan)) => (vec![predicate(&scan.params)], scan.alias.as_ref()),
+        Some(Opr::Edge(edgexpd)) => (vec![predicate(&edgexpd.params)], edgexpd.alias.as_ref()),
+        Some(Opr::Vertex(getv)) => (vec![predicate(&getv.params)], getv.alias.as_ref()),
+        Some(Opr::Path(pathxpd)) => (
+            vec![pathxpd
+                .condition
+                .as_ref()
+                .map(|condition| format!("UNTIL {}", expr_pb_to_str(condition)))],
+            pathxpd.alias.as_ref(),
+        ),
+        Some(Opr::Select(select)) => (
+            vec![select
+                .predicate
+                .as_ref()
+                .map(|predicate| format!("WHERE {}", expr_pb_to_str(predicate)))],
+            None,
+        ),
+        Some(Opr::Project(project)) => {
+            let mappings = project
+                .mappings
+                .iter()
+                .map(|mapping| {
+                    let expr = mapping
+                        .expr
+                        .as_ref()
+                        .map(expr_pb_to_str)
+                        .unwrap_or_default();
+                    match &mapping.alias {
+                        Some(alias) => format!("{} AS {}", expr, name_or_id_to_string(alias)),
+                        None => expr,
+                    }
+                })
+                .collect::<Vec<_>>();
+            (
+                vec![
+                    Some(mappings.join(", ")),
+                    if project.is_append { Some("APPEND".to_string()) } else { None },
+                ],
+                None,
+            )
+        }
+        Some(Opr::GroupBy(group)) => {
+            let keys = group
+                .mappings
+                .iter()
+                .map(|mapping| {
+                    let key = mapping
+                        .key
+                        .as_ref()
+                        .map(var_to_string)
+                        .unwrap_or_default();
+                    match &mapping.alias {
+                        Some(alias) => format!("{} AS {}", key, name_or_id_to_string(alias)),
+                        None => key,
+                    }
+                })
+                .collect::<Vec<_>>();
+            let functions = group
+                .functions
+                .iter()
+                .map(|function| {
+                    let aggregate = pb::group_by::agg_func::Aggregate::from_i32(function.aggregate)
+                        .map(|aggregate| format!("{:?}", aggregate).to_uppercase())
+                        .unwrap_or_default();
+                    let function_str = format!("{}({})", aggregate, vars_to_string(&function.vars));
+                    match &function.alias {
+                        Some(alias) => format!("{} AS {}", function_str, name_or_id_to_string(alias)),
+                        None => function_str,
+                    }
+                })
+                .collect::<Vec<_>>();
+            (
+                vec![
+                    Some(format!("BY {}", keys.join(", "))).filter(|_| !keys.is_empty()),
+                    Some(functions.join(", ")).filter(|_| !functions.is_empty()),
+                ],
+                None,
+            )
+        }
+        Some(Opr::OrderBy(order)) => {
+            let pairs = order
+                .pairs
+                .iter()
+                .map(|pair| {
+                    let key = pair
+                        .key
+                        .as_ref()
+                        .map(var_to_string)
+                        .unwrap_or_default();
+                    let order = pb::order_by::ordering_pair::Order::from_i32(pair.order)
+                        .map(|order| format!("{:?}", order).to_uppercase())
+                        .unwrap_or_default();
+                    format!("{} {}", key, order)
+                })
+                .collect::<Vec<_>>();
+            (vec![Some(format!("BY {}", pairs.join(", "))).filter(|_| !pairs.is_empty())], None)
+        }
+        Some(Opr::Dedup(dedup)) => (
+            vec![Some(format!("BY {}", vars_to_string(&dedup.keys))).filter(|_| !dedup.keys.is_empty())],
+            None,
+        ),
+        Some(Opr::Join(join)) => {
+            let keys = join
+                .left_keys
+                .iter()
+                .zip(join.right_keys.iter())
+                .map(|(left, right)| format!("{} = {}", var_to_string(left), var_to_string(right)))
+                .collect::<Vec<_>>();
+            (vec![Some(format!("ON {}", keys.join(" AND "))).filter(|_| !keys.is_empty())], None)
+        }
+        Some(Opr::Unfold(unfold)) => (
+            vec![unfold
+                .tag
+                .as_ref()
+                .map(|tag| format!("@{}", name_or_id_to_string(tag)))],
+            unfold.alias.as_ref(),
+        ),
+        Some(Opr::Count(count)) => (vec![], count.alias.as_ref()),
+        Some(Opr::Apply(apply)) => (
+            vec![pb::join::JoinKind::from_i32(apply.join_kind)
+                .map(|kind| format!("{:?}", kind).to_uppercase())],
+            apply.alias.as_ref(),
+        ),
+        Some(Opr::Sink(sink)) => {
+            let tags = sink
+                .tags
+                .iter()
+                .filter_map(|tag| tag.key.as_ref())
+                .map(|tag| format!("@{}", name_or_id_to_string(tag)))
+                .collect::<Vec<_>>();
+            (vec![Some(tags.join(", ")).filter(|_| !tags.is_empty())], None)
+        }
+        _ => (vec![], None),
+    };
+
+    details
+        .into_iter()
+        .flatten()
+        .chain(alias.map(|alias| format!("AS {}", name_or_id_to_string(alias))))
+        .collect()
+}
+
+/// The root of the subtask of an `Apply` or a `SegmentApply`, if the operator is either of them
+fn get_subtask(opr: &pb::logical_plan::Operator) -> Option<NodeId> {
+    use pb::logical_plan::operator::Opr;
+
+    match &opr.opr {
+        Some(Opr::Apply(apply)) => Some(apply.subtask as NodeId),
+        Some(Opr::SegApply(seg_apply)) => seg_apply
+            .apply_subtask
+            .as_ref()
+            .map(|apply| apply.subtask as NodeId),
+        _ => None,
+    }
 }
 
 #[allow(dead_code)]
 impl Node {
     pub fn new(id: NodeId, opr: pb::logical_plan::Operator) -> Node {
-        Node { id, opr, parents: BTreeSet::new(), children: BTreeSet::new() }
+        Node {
+            id,
+            opr,
+            parents: BTreeSet::new(),
+            children: BTreeSet::new(),
+            annotations: vec![],
+            attributes: BTreeMap::new(),
+            display_name: None,
+        }
+    }
+
+    /// The human-readable name of the node, which is either given via [`LogicalPlan::set_display_name`],
+    /// or the operator kind plus its key fields by default, e.g., `EdgeExpand[knows, OUT]`.
+    pub fn display_name(&self) -> String {
+        self.display_name
+            .clone()
+            .unwrap_or_else(|| default_display_name(&self.opr))
+    }
+
+    pub fn get_id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn get_opr(&self) -> &pb::logical_plan::Operator {
+        &self.opr
+    }
+
+    pub fn get_parents(&self) -> &BTreeSet<NodeId> {
+        &self.parents
+    }
+
+    pub fn get_children(&self) -> &BTreeSet<NodeId> {
+        &self.children
     }
 
     pub fn add_child(&mut self, child_id: NodeId) {
@@ -64,9 +421,62 @@ impl Node {
     pub fn add_parent(&mut self, parent_id: NodeId) {
         self.parents.insert(parent_id);
     }
+
+    /// The estimated size, in bytes, of the node held in memory, where the operator is
+    /// estimated as its encoded size, as the heap allocations of a pb message are not tracked.
+    pub fn memory_footprint(&self) -> usize {
+        std::mem::size_of::<SyncCell<Node>>()
+            + self.opr.encoded_len()
+            + (self.parents.len() + self.children.len()) * std::mem::size_of::<NodeId>()
+            + self
+                .annotations
+                .iter()
+                .map(|annotation| std::mem::size_of::<String>() + annotation.capacity())
+                .sum::<usize>()
+            + self
+                .attributes
+                .iter()
+                .map(|(key, value)| 2 * std::mem::size_of::<String>() + key.capacity() + value.capacity())
+                .sum::<usize>()
+            + self
+                .display_name
+                .as_ref()
+                .map(|name| name.capacity())
+                .unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (#{})", self.display_name(), self.id)
+    }
+}
+
+pub(crate) type NodeType = Arc<SyncCell<Node>>;
+
+/// The 64-bit FNV-1a hasher, of which the hashes, unlike those of the `DefaultHasher` of the standard
+/// library, are guaranteed to be the same across the releases of Rust and the platforms, as long as the
+/// bytes are written in the same way, e.g., the integers as the little-endian bytes.
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
 }
 
-pub(crate) type NodeType = Rc<RefCell<Node>>;
+impl std::hash::Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
 
 /// An internal representation of the pb-[`LogicalPlan`].
 ///
@@ -79,11 +489,53 @@ pub struct LogicalPlan {
     pub(crate) max_node_id: NodeId,
     /// The metadata of the logical plan
     pub(crate) meta: PlanMeta,
+    /// The free-form annotations of the logical plan
+    pub(crate) annotations: Vec<String>,
+    /// Whether the query may return partial results on a worker failure or a timeout
+    pub(crate) allow_partial: bool,
+    /// The policy applied by the expansions while expanding from a super node
+    pub(crate) super_node_policy: Option<pb::SuperNodePolicy>,
+    /// Where the plan originates from, e.g., the text of the query
+    pub(crate) origin: Option<pb::PlanOrigin>,
+    /// The mode of the plan if it is a continuous query over the updates of the graph
+    pub(crate) continuous: Option<pb::ContinuousMode>,
+    /// The labels and the properties that the plan may read, if restricted
+    pub(crate) read_scope: Option<pb::ReadScope>,
+    /// The view that the plan is built within, if any
+    pub(crate) view: Option<pb::GraphView>,
+    /// The sink of the write-through mode, if it is on
+    pub(crate) write_through: WriteThrough,
+    /// Whether the plan is frozen, after which it can no longer be appended to
+    pub(crate) is_frozen: bool,
+}
+
+/// The sink that the changes of a logical plan are written through to as the operators are appended
+/// (see [`LogicalPlan::set_write_through`]), which is owned by the plan itself, and thus is not
+/// inherited by its clones.
+#[derive(Default)]
+pub(crate) struct WriteThrough {
+    sink: Option<Box<dyn FnMut(&pb::PlanAppend) + Send>>,
+    /// The nodes removed by the ongoing append
+    removed: Vec<NodeId>,
+}
+
+impl Clone for WriteThrough {
+    fn clone(&self) -> Self {
+        WriteThrough::default()
+    }
 }
 
 impl PartialEq for LogicalPlan {
     fn eq(&self, other: &Self) -> bool {
-        if self.nodes.len() != other.nodes.len() {
+        if self.nodes.len() != other.nodes.len()
+            || self.annotations != other.annotations
+            || self.allow_partial != other.allow_partial
+            || self.super_node_policy != other.super_node_policy
+            || self.origin != other.origin
+            || self.continuous != other.continuous
+            || self.read_scope != other.read_scope
+            || self.view != other.view
+        {
             return false;
         }
         for (this_node, other_node) in self
@@ -112,9 +564,24 @@ impl fmt::Debug for LogicalPlan {
 impl TryFrom<pb::LogicalPlan> for LogicalPlan {
     type Error = ParsePbError;
 
-    fn try_from(pb: pb::LogicalPlan) -> Result<Self, Self::Error> {
+    fn try_from(mut pb: pb::LogicalPlan) -> Result<Self, Self::Error> {
+        // the plan may be serialized by a former version of the library, e.g., cached by the caller
+        upgrade_plan(&mut pb)?;
+        LogicalPlan::from_pb_with_meta(pb, PlanMeta::default()).map(|(plan, _)| plan)
+    }
+}
+
+impl LogicalPlan {
+    /// Build the plan from the pb-[`LogicalPlan`] by appending its nodes in order to an empty plan with
+    /// the given meta, which also returns the ids of the nodes in the built plan by their indices in the pb.
+    ///
+    /// [`LogicalPlan`]: crate::generated::algebra::LogicalPlan
+    fn from_pb_with_meta(
+        pb: pb::LogicalPlan, meta: PlanMeta,
+    ) -> Result<(Self, HashMap<NodeId, NodeId>), ParsePbError> {
         let nodes_pb = pb.nodes;
         let mut plan = LogicalPlan::default();
+        plan.meta = meta;
         let mut id_map = HashMap::<NodeId, NodeId>::new();
         let mut parents = HashMap::<NodeId, BTreeSet<NodeId>>::new();
         for (id, node) in nodes_pb.iter().enumerate() {
@@ -147,16 +614,436 @@ impl TryFrom<pb::LogicalPlan> for LogicalPlan {
                     .into_iter()
                     .map(|old| id_map[&old])
                     .collect::<Vec<NodeId>>();
+                let max_node_id = plan.get_max_node_id();
                 let new_id = plan
                     .append_operator_as_node(opr, parent_ids)
                     .map_err(|err| ParsePbError::ParseError(format!("{:?}", err)))?;
+                // Skip the elided node, of which the id refers to an existing node
+                if let Some(new_node) = plan
+                    .get_node(new_id)
+                    .filter(|_| new_id >= max_node_id)
+                {
+                    let mut new_node = new_node.borrow_mut();
+                    new_node.annotations = node.annotations;
+                    new_node.attributes = node.attributes.into_iter().collect();
+                    if !node.display_name.is_empty() {
+                        new_node.display_name = Some(node.display_name);
+                    }
+                }
                 id_map.insert(id as NodeId, new_id);
             } else {
                 return Err(ParsePbError::EmptyFieldError("Node::opr".to_string()));
             }
         }
+        plan.annotations = pb.annotations;
+        plan.allow_partial = pb.allow_partial;
+        plan.super_node_policy = pb.super_node_policy;
+        plan.origin = pb.origin;
+        plan.continuous = pb.continuous;
+        plan.read_scope = pb.read_scope;
+        plan.view = pb.view;
+
+        Ok((plan, id_map))
+    }
+}
 
-        Ok(plan)
+impl TryFrom<pb::CompactLogicalPlan> for LogicalPlan {
+    type Error = ParsePbError;
+
+    fn try_from(pb: pb::CompactLogicalPlan) -> Result<Self, Self::Error> {
+        let plan_pb = pb
+            .plan
+            .ok_or_else(|| ParsePbError::EmptyFieldError("CompactLogicalPlan::plan".to_string()))?;
+        let strings = pb.strings;
+        let plan_pb = map_plan_names(&plan_pb, |name| {
+            name.parse::<usize>()
+                .ok()
+                .and_then(|idx| strings.get(idx).cloned())
+                .ok_or_else(|| ParsePbError::ParseError(format!("invalid string index {:?}", name)))
+        })?;
+
+        plan_pb.try_into()
+    }
+}
+
+/// Rewrite each name of the tags, labels and properties, namely the `Name` of any
+/// `common_pb::NameOrId` in the plan, by `f`.
+fn map_plan_names<F>(plan_pb: &pb::LogicalPlan, mut f: F) -> Result<pb::LogicalPlan, ParsePbError>
+where
+    F: FnMut(&str) -> Result<String, ParsePbError>,
+{
+    let mut plan_pb = plan_pb.clone();
+    plan_pb.map_names(&mut f)?;
+
+    Ok(plan_pb)
+}
+
+type NameMapper<'a> = dyn FnMut(&str) -> Result<String, ParsePbError> + 'a;
+
+/// The pb messages that refer to the tags, labels and properties by their names, which are
+/// rewritten in place by [`map_plan_names`].
+trait MapNames {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError>;
+}
+
+impl<T: MapNames> MapNames for Option<T> {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        match self {
+            Some(value) => value.map_names(f),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: MapNames> MapNames for Vec<T> {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        for value in self.iter_mut() {
+            value.map_names(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for common_pb::NameOrId {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        if let Some(common_pb::name_or_id::Item::Name(name)) = self.item.as_mut() {
+            *name = f(name)?;
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for common_pb::NameOrIdKey {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.key.map_names(f)
+    }
+}
+
+impl MapNames for common_pb::IrDataType {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        if let Some(common_pb::ir_data_type::Type::GraphType(graph_type)) = self.r#type.as_mut() {
+            for element_type in graph_type.graph_data_type.iter_mut() {
+                for prop in element_type.props.iter_mut() {
+                    prop.prop_id.map_names(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for pb::MetaData {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.r#type.map_names(f)
+    }
+}
+
+impl MapNames for common_pb::Property {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        if let Some(common_pb::property::Item::Key(key)) = self.item.as_mut() {
+            key.map_names(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for common_pb::Variable {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.tag.map_names(f)?;
+        self.property.map_names(f)?;
+        self.node_type.map_names(f)
+    }
+}
+
+impl MapNames for common_pb::DynamicParam {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.data_type.map_names(f)
+    }
+}
+
+impl MapNames for common_pb::Expression {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        use common_pb::expr_opr::Item;
+
+        for opr in self.operators.iter_mut() {
+            match opr.item.as_mut() {
+                Some(Item::Var(var)) => var.map_names(f)?,
+                Some(Item::Vars(keys)) | Some(Item::VarMap(keys)) => keys.keys.map_names(f)?,
+                Some(Item::Param(param)) => param.map_names(f)?,
+                Some(Item::Case(case)) => {
+                    for when_then in case.when_then_expressions.iter_mut() {
+                        when_then.when_expression.map_names(f)?;
+                        when_then.then_result_expression.map_names(f)?;
+                    }
+                    case.else_result_expression.map_names(f)?;
+                }
+                _ => {}
+            }
+            opr.node_type.map_names(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for pb::QueryParams {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.tables.map_names(f)?;
+        self.columns.map_names(f)?;
+        self.predicate.map_names(f)?;
+        for degree_predicate in self.degree_predicates.iter_mut() {
+            degree_predicate.tables.map_names(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for pb::IndexPredicate {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        for and_predicate in self.or_predicates.iter_mut() {
+            for triplet in and_predicate.predicates.iter_mut() {
+                triplet.key.map_names(f)?;
+                triplet.param.map_names(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for pb::EdgeExpand {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.v_tag.map_names(f)?;
+        self.params.map_names(f)?;
+        self.alias.map_names(f)?;
+        self.meta_data.map_names(f)?;
+        self.vertex_tables.map_names(f)
+    }
+}
+
+impl MapNames for pb::GetV {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.tag.map_names(f)?;
+        self.params.map_names(f)?;
+        self.alias.map_names(f)?;
+        self.meta_data.map_names(f)?;
+        self.end_alias.map_names(f)
+    }
+}
+
+impl MapNames for pb::PathExpand {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        if let Some(base) = self.base.as_mut() {
+            base.edge_expand.map_names(f)?;
+            base.get_v.map_names(f)?;
+        }
+        self.start_tag.map_names(f)?;
+        self.alias.map_names(f)?;
+        self.condition.map_names(f)
+    }
+}
+
+impl MapNames for pb::Apply {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.tags.map_names(f)?;
+        self.alias.map_names(f)?;
+        self.captures.map_names(f)
+    }
+}
+
+impl MapNames for pb::sink_bulk_load::VertexMapping {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.label.map_names(f)?;
+        for column_mapping in self.column_mappings.iter_mut() {
+            column_mapping.column.map_names(f)?;
+            column_mapping.property.map_names(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for pb::Sink {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        self.tags.map_names(f)?;
+        let bulk_load = match self
+            .sink_target
+            .as_mut()
+            .and_then(|target| target.inner.as_mut())
+        {
+            Some(pb::sink::sink_target::Inner::SinkBulkLoad(bulk_load)) => bulk_load,
+            _ => return Ok(()),
+        };
+        match bulk_load.mapping.as_mut() {
+            Some(pb::sink_bulk_load::Mapping::Vertex(vertex)) => vertex.map_names(f)?,
+            Some(pb::sink_bulk_load::Mapping::Edge(edge)) => {
+                edge.label.map_names(f)?;
+                edge.src.map_names(f)?;
+                edge.dst.map_names(f)?;
+                for column_mapping in edge.column_mappings.iter_mut() {
+                    column_mapping.column.map_names(f)?;
+                    column_mapping.property.map_names(f)?;
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for pb::logical_plan::Operator {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        use pb::logical_plan::operator::Opr;
+
+        match self.opr.as_mut() {
+            Some(Opr::Project(project)) => {
+                for mapping in project.mappings.iter_mut() {
+                    mapping.expr.map_names(f)?;
+                    mapping.alias.map_names(f)?;
+                }
+                project.meta_data.map_names(f)?;
+            }
+            Some(Opr::Select(select)) => select.predicate.map_names(f)?,
+            Some(Opr::Join(join)) => {
+                join.left_keys.map_names(f)?;
+                join.right_keys.map_names(f)?;
+                for rename in join.renames.iter_mut() {
+                    rename.from.map_names(f)?;
+                    rename.to.map_names(f)?;
+                }
+            }
+            Some(Opr::GroupBy(group)) => {
+                for mapping in group.mappings.iter_mut() {
+                    mapping.key.map_names(f)?;
+                    mapping.alias.map_names(f)?;
+                }
+                for function in group.functions.iter_mut() {
+                    function.vars.map_names(f)?;
+                    function.alias.map_names(f)?;
+                }
+                group.meta_data.map_names(f)?;
+            }
+            Some(Opr::OrderBy(order)) => {
+                for pair in order.pairs.iter_mut() {
+                    pair.key.map_names(f)?;
+                }
+            }
+            Some(Opr::Dedup(dedup)) => dedup.keys.map_names(f)?,
+            Some(Opr::Unfold(unfold)) => {
+                unfold.tag.map_names(f)?;
+                unfold.alias.map_names(f)?;
+                unfold.meta_data.map_names(f)?;
+            }
+            Some(Opr::Apply(apply)) => apply.map_names(f)?,
+            Some(Opr::SegApply(seg_apply)) => {
+                seg_apply.keys.map_names(f)?;
+                seg_apply.apply_subtask.map_names(f)?;
+            }
+            Some(Opr::Scan(scan)) => {
+                scan.alias.map_names(f)?;
+                scan.params.map_names(f)?;
+                scan.idx_predicate.map_names(f)?;
+                scan.meta_data.map_names(f)?;
+            }
+            Some(Opr::As(as_opr)) => as_opr.alias.map_names(f)?,
+            Some(Opr::Intersect(intersect)) => intersect.key.map_names(f)?,
+            Some(Opr::Sink(sink)) => sink.map_names(f)?,
+            Some(Opr::Count(count)) => {
+                count.tag.map_names(f)?;
+                count.alias.map_names(f)?;
+            }
+            Some(Opr::Vertex(getv)) => getv.map_names(f)?,
+            Some(Opr::Edge(edge)) => edge.map_names(f)?,
+            Some(Opr::Path(path)) => path.map_names(f)?,
+            Some(Opr::Pattern(pattern)) => {
+                for sentence in pattern.sentences.iter_mut() {
+                    sentence.start.map_names(f)?;
+                    for binder in sentence.binders.iter_mut() {
+                        match binder.item.as_mut() {
+                            Some(Item::Edge(edge)) => edge.map_names(f)?,
+                            Some(Item::Path(path)) => path.map_names(f)?,
+                            Some(Item::Vertex(getv)) => getv.map_names(f)?,
+                            Some(Item::Select(select)) => select.predicate.map_names(f)?,
+                            None => {}
+                        }
+                    }
+                    sentence.end.map_names(f)?;
+                }
+                pattern.meta_data.map_names(f)?;
+            }
+            Some(Opr::Union(_)) | Some(Opr::Limit(_)) | None => {}
+        }
+        Ok(())
+    }
+}
+
+impl MapNames for pb::LogicalPlan {
+    fn map_names(&mut self, f: &mut NameMapper) -> Result<(), ParsePbError> {
+        for node in self.nodes.iter_mut() {
+            node.opr.map_names(f)?;
+            node.accesses.map_names(f)?;
+        }
+        if let Some(view) = self.view.as_mut() {
+            view.vertex_labels.map_names(f)?;
+            view.vertex_predicate.map_names(f)?;
+            view.edge_labels.map_names(f)?;
+            view.edge_predicate.map_names(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The placeholder of the redacted texts in [`LogicalPlan::to_debug_string_redacted`].
+pub const REDACTED: &str = "<redacted>";
+
+/// Mask the literal constants, namely any `common_pb::Value` except `None`, as well as the free-form
+/// annotations, the values of the key/value annotations and display names, in the plan serialized as
+/// json. The kind of a constant is kept.
+fn redact_literals(value: &mut serde_json::Value) {
+    const LITERAL_KINDS: [&str; 11] = [
+        "Boolean",
+        "I32",
+        "I64",
+        "F64",
+        "Str",
+        "Blob",
+        "I32Array",
+        "I64Array",
+        "F64Array",
+        "StrArray",
+        "PairArray",
+    ];
+    let redacted = || serde_json::Value::String(REDACTED.to_string());
+    match value {
+        serde_json::Value::Object(map) => {
+            if let Some(serde_json::Value::Object(item)) = map.get_mut("item") {
+                if item.len() == 1 {
+                    if let Some((kind, literal)) = item.iter_mut().next() {
+                        if LITERAL_KINDS.contains(&kind.as_str()) {
+                            *literal = redacted();
+                            return;
+                        }
+                    }
+                }
+            }
+            for (key, value) in map.iter_mut() {
+                match (key.as_str(), value) {
+                    ("annotations", serde_json::Value::Array(annotations)) => {
+                        annotations
+                            .iter_mut()
+                            .for_each(|annotation| *annotation = redacted());
+                    }
+                    ("attributes", serde_json::Value::Object(attributes)) => {
+                        attributes
+                            .values_mut()
+                            .for_each(|value| *value = redacted());
+                    }
+                    ("display_name", serde_json::Value::String(name)) if !name.is_empty() => {
+                        *name = REDACTED.to_string();
+                    }
+                    (_, value) => redact_literals(value),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_literals),
+        _ => {}
     }
 }
 
@@ -171,9 +1058,40 @@ impl From<LogicalPlan> for pb::LogicalPlan {
                 roots.push(new_id as PbNodeId);
             }
         }
-        let mut plan_pb = pb::LogicalPlan { nodes: vec![], roots };
-        for (_, node) in &plan.nodes {
-            let mut node_pb = pb::logical_plan::Node { opr: None, children: vec![] };
+        let mut plan_pb = pb::LogicalPlan {
+            nodes: vec![],
+            roots,
+            annotations: plan.annotations.clone(),
+            allow_partial: plan.allow_partial,
+            super_node_policy: plan.super_node_policy.clone(),
+            format_version: PLAN_FORMAT_VERSION,
+            origin: plan.origin.clone(),
+            continuous: plan.continuous.clone(),
+            read_scope: plan.read_scope.clone(),
+            view: plan.view.clone(),
+        };
+        let mut node_accesses =
+            if plan.meta.is_trace_access() { plan.get_node_accesses() } else { BTreeMap::new() };
+        for (id, node) in &plan.nodes {
+            let mut node_pb = pb::logical_plan::Node {
+                opr: None,
+                children: vec![],
+                annotations: node.borrow().annotations.clone(),
+                attributes: node
+                    .borrow()
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+                display_name: node
+                    .borrow()
+                    .display_name
+                    .clone()
+                    .unwrap_or_default(),
+                accesses: node_accesses
+                    .remove(&(id as NodeId))
+                    .unwrap_or_default(),
+            };
             let mut operator = node.borrow().opr.clone();
             match operator.opr.as_mut() {
                 Some(pb::logical_plan::operator::Opr::Apply(apply)) => {
@@ -195,16 +1113,132 @@ impl From<LogicalPlan> for pb::LogicalPlan {
     }
 }
 
-fn clone_node(node: NodeType) -> Node {
-    let mut clone_node = (*node.borrow()).clone();
-    clone_node.children.clear();
-    clone_node.parents.clear();
+impl JsonIO for LogicalPlan {
+    fn into_json<W: io::Write>(self, writer: W) -> io::Result<()> {
+        let plan_pb = pb::LogicalPlan::from(self);
+        serde_json::to_writer_pretty(writer, &plan_pb)?;
 
-    clone_node
+        Ok(())
+    }
+
+    fn from_json<R: io::Read>(reader: R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let plan_pb = serde_json::from_reader::<_, pb::LogicalPlan>(reader)?;
+        LogicalPlan::try_from(plan_pb)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
 }
 
-// Implement some private functions
-#[allow(dead_code)]
+/// Remap the ids of the nodes that the operator refers to, namely, the subtask of an `Apply`, and the
+/// parents of a `Union` or an `Intersect`, where the ids that cannot be remapped are set to `0` and
+/// left out respectively.
+fn remap_node_refs<F>(opr: &pb::logical_plan::Operator, remap: F) -> pb::logical_plan::Operator
+where
+    F: Fn(NodeId) -> Option<NodeId>,
+{
+    use pb::logical_plan::operator::Opr;
+
+    let mut opr = opr.clone();
+    match opr.opr.as_mut() {
+        Some(Opr::Apply(apply)) => {
+            apply.subtask = remap(apply.subtask as NodeId).unwrap_or(0) as PbNodeId;
+        }
+        Some(Opr::Union(union)) => {
+            union.parents = union
+                .parents
+                .iter()
+                .filter_map(|parent| remap(*parent as NodeId).map(|id| id as PbNodeId))
+                .collect();
+        }
+        Some(Opr::Intersect(intersect)) => {
+            intersect.parents = intersect
+                .parents
+                .iter()
+                .filter_map(|parent| remap(*parent as NodeId).map(|id| id as PbNodeId))
+                .collect();
+        }
+        _ => {}
+    }
+
+    opr
+}
+
+/// Refer to the tags of the operator by their names, rather than their ids that are assigned in the
+/// order of appending the operators, where a tag is found as the `tag`, `alias`, `tags`, etc., of the
+/// operator. The tags without names are left as they are.
+fn name_tags(
+    opr: &pb::logical_plan::Operator, tag_names: &BTreeMap<TagId, String>,
+) -> pb::logical_plan::Operator {
+    const TAG_FIELDS: [&str; 8] =
+        ["tag", "alias", "end_alias", "tags", "start_tag", "v_tag", "start", "end"];
+
+    fn name_tag(value: &mut serde_json::Value, tag_names: &BTreeMap<TagId, String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                if let Some(serde_json::Value::Object(item)) = map.get_mut("item") {
+                    if let Some(name) = item
+                        .get("Id")
+                        .and_then(|id| id.as_i64())
+                        .and_then(|id| tag_names.get(&(id as TagId)))
+                    {
+                        item.remove("Id");
+                        item.insert("Name".to_string(), serde_json::Value::String(name.clone()));
+                    }
+                } else if let Some(key) = map.get_mut("key") {
+                    // the tags of a `Sink`
+                    name_tag(key, tag_names);
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for value in values.iter_mut() {
+                    name_tag(value, tag_names);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn name_all_tags(value: &mut serde_json::Value, tag_names: &BTreeMap<TagId, String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (field, value) in map.iter_mut() {
+                    if TAG_FIELDS.contains(&field.as_str()) {
+                        name_tag(value, tag_names);
+                    } else {
+                        name_all_tags(value, tag_names);
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for value in values.iter_mut() {
+                    name_all_tags(value, tag_names);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    serde_json::to_value(opr)
+        .ok()
+        .and_then(|mut value| {
+            name_all_tags(&mut value, tag_names);
+            serde_json::from_value(value).ok()
+        })
+        .unwrap_or_else(|| opr.clone())
+}
+
+fn clone_node(node: NodeType) -> Node {
+    let mut clone_node = (*node.borrow()).clone();
+    clone_node.children.clear();
+    clone_node.parents.clear();
+
+    clone_node
+}
+
+// Implement some private functions
+#[allow(dead_code)]
 impl LogicalPlan {
     /// Get the corresponding merge node of the given branch node.
     fn get_merge_node(&self, branch_node: NodeType) -> Option<NodeType> {
@@ -262,9 +1296,22 @@ impl LogicalPlan {
         meta.refer_to_nodes(node_id, vec![node_id]);
         let _ = meta.curr_node_meta_mut();
         let mut nodes = VecMap::new();
-        nodes.insert(node_id as usize, Rc::new(RefCell::new(node)));
-
-        Self { nodes, max_node_id: node_id + 1, meta }
+        nodes.insert(node_id as usize, Arc::new(SyncCell::new(node)));
+
+        Self {
+            nodes,
+            max_node_id: node_id + 1,
+            meta,
+            annotations: vec![],
+            allow_partial: false,
+            super_node_policy: None,
+            origin: None,
+            continuous: None,
+            read_scope: None,
+            view: None,
+            write_through: WriteThrough::default(),
+            is_frozen: false,
+        }
     }
 
     /// Get a node reference from the logical plan
@@ -272,1845 +1319,9044 @@ impl LogicalPlan {
         self.nodes.get(id as usize).cloned()
     }
 
-    /// Get a operator reference from the logical plan
-    pub fn get_opr(&self, id: NodeId) -> Option<pb::logical_plan::Operator> {
-        self.nodes
-            .get(id as usize)
-            .map(|node| node.borrow().opr.clone())
-    }
+    /// Attach a free-form annotation to the node of the given id, which is preserved
+    /// while serializing the plan.
+    pub fn annotate_node(&mut self, id: NodeId, annotation: String) -> IrResult<()> {
+        let node = self
+            .get_node(id)
+            .ok_or(IrError::NodeNotExist(id))?;
+        node.borrow_mut().annotations.push(annotation);
 
-    /// Get first node in the logical plan
-    pub fn get_first_node(&self) -> Option<NodeType> {
-        self.nodes
-            .iter()
-            .next()
-            .map(|tuple| tuple.1.clone())
+        Ok(())
     }
 
-    /// Get last node in the logical plan
-    pub fn get_last_node(&self) -> Option<NodeType> {
-        self.nodes
-            .iter()
-            .rev()
-            .next()
-            .map(|tuple| tuple.1.clone())
+    /// Attach a key/value annotation to the node of the given id, e.g., the source line of the step in
+    /// the query that the node originates from, the estimated cardinality, or the notes of the optimizer,
+    /// which is preserved while serializing the plan. It returns the value annotated before under the
+    /// same key, if any, which is replaced.
+    pub fn set_node_attribute(
+        &mut self, id: NodeId, key: String, value: String,
+    ) -> IrResult<Option<String>> {
+        let node = self
+            .get_node(id)
+            .ok_or(IrError::NodeNotExist(id))?;
+        let old_value = node.borrow_mut().attributes.insert(key, value);
+
+        Ok(old_value)
     }
 
-    pub fn len(&self) -> usize {
-        self.nodes.len()
+    pub fn get_node_attribute(&self, id: NodeId, key: &str) -> Option<String> {
+        self.get_node(id)
+            .and_then(|node| node.borrow().attributes.get(key).cloned())
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.nodes.is_empty()
-    }
+    /// Give a human-readable name to the node of the given id, which is used while describing
+    /// the node, e.g., in the error messages, instead of the default one.
+    pub fn set_display_name(&mut self, id: NodeId, display_name: String) -> IrResult<()> {
+        let node = self
+            .get_node(id)
+            .ok_or(IrError::NodeNotExist(id))?;
+        node.borrow_mut().display_name = Some(display_name);
 
-    pub fn get_meta(&self) -> &PlanMeta {
-        &self.meta
+        Ok(())
     }
 
-    pub fn get_max_node_id(&self) -> NodeId {
-        self.max_node_id
+    /// Describe the node of the given id as its display name and id, e.g., `EdgeExpand[knows, OUT] (#17)`.
+    pub fn describe_node(&self, id: NodeId) -> Option<String> {
+        self.get_node(id)
+            .map(|node| node.borrow().to_string())
     }
 
-    /// Append a new node into the logical plan, with specified `parent_ids`
-    /// as its parent nodes. In order to do so, all specified parents must present in the
-    /// logical plan.
-    ///
-    /// # Return
-    ///   * If succeed, the id of the newly added node
-    ///   * Otherwise, `IrError::ParentNodeNotExist`
-    pub fn append_node(&mut self, mut node: Node, parent_ids: Vec<NodeId>) -> IrResult<NodeId> {
-        let id = node.id;
-        if !self.is_empty() && !parent_ids.is_empty() {
-            let mut parent_nodes = vec![];
-            for parent_id in parent_ids {
-                if let Some(parent_node) = self.get_node(parent_id) {
-                    parent_nodes.push(parent_node);
-                } else {
-                    return Err(IrError::ParentNodeNotExist(parent_id));
-                }
+    /// Render the plan as a graph of the DOT language, which can be visualized via Graphviz, e.g.,
+    /// `dot -Tsvg`. Each node is labeled as it is described (see [`LogicalPlan::describe_node`]),
+    /// followed by its key/value annotations as `key=value` and its free-form annotations line by line,
+    /// and points to its children, while an `Apply` further
+    /// points to the root of its subtask by a dashed edge, as the subtask is not a child of the apply.
+    /// The annotations of the plan label the graph.
+    pub fn to_dot(&self) -> String {
+        use pb::logical_plan::operator::Opr;
+        use std::fmt::Write;
+
+        let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = String::from("digraph plan {\n");
+        if !self.annotations.is_empty() {
+            let label = self
+                .annotations
+                .iter()
+                .map(|annotation| escape(annotation))
+                .collect::<Vec<_>>();
+            let _ = writeln!(dot, "  label=\"{}\";", label.join("\\n"));
+        }
+        for (id, node) in self.nodes.iter() {
+            let node = node.borrow();
+            let mut label = vec![escape(&node.to_string())];
+            label.extend(
+                node.attributes
+                    .iter()
+                    .map(|(key, value)| escape(&format!("{}={}", key, value))),
+            );
+            label.extend(
+                node.annotations
+                    .iter()
+                    .map(|annotation| escape(annotation)),
+            );
+            let _ = writeln!(dot, "  n{} [label=\"{}\"];", id, label.join("\\n"));
+            for child in &node.children {
+                let _ = writeln!(dot, "  n{} -> n{};", id, child);
             }
-            for parent_node in parent_nodes {
-                node.add_parent(parent_node.borrow().id);
-                parent_node.borrow_mut().add_child(id);
+            let subtask = match &node.opr.opr {
+                Some(Opr::Apply(apply)) => Some(apply.subtask),
+                Some(Opr::SegApply(seg_apply)) => seg_apply
+                    .apply_subtask
+                    .as_ref()
+                    .map(|apply| apply.subtask),
+                _ => None,
+            };
+            if let Some(subtask) = subtask {
+                let _ = writeln!(dot, "  n{} -> n{} [style=dashed, label=\"subtask\"];", id, subtask);
             }
         }
-        let node_rc = Rc::new(RefCell::new(node));
-        self.nodes.insert(id as usize, node_rc.clone());
-        self.max_node_id = std::cmp::max(self.max_node_id, id) + 1;
+        dot.push('}');
 
-        Ok(id)
+        dot
     }
 
-    /// Append an existing logical plan to the logical plan, with the specified
-    /// parent node's id. Note that we currently only allow appending a logical
-    /// plan to one single parent node.
-    pub fn append_plan(&mut self, plan: pb::LogicalPlan, parent_ids: Vec<NodeId>) -> IrResult<NodeId> {
-        if parent_ids.len() != 1 {
-            return Err(IrError::Unsupported(
-                "only support appending plan for one single parent!".to_string(),
+    /// Explain the plan as an indented tree in the style of the relational algebra, e.g., for the error
+    /// logs of the clients and the query UIs. Each line is an operator, given as its display name, its
+    /// details, e.g., the predicates, the mappings and the aliases, and its id, under which its inputs
+    /// (parents) follow as indented, while the subtask of an `Apply` follows the apply. A node that has
+    /// been explained as the input of another one is referred to by its id only, e.g., `-> #3`. An
+    /// operator whose output is kept in the order of a preceding `OrderBy` is followed by the ordering,
+    /// e.g., `ORDERED BY @a.name ASC`. The key/value annotations of an operator follow its details,
+    /// e.g., `{line=3}`, and the free-form annotations follow its id as a comment, e.g., `// g.V()`.
+    /// If the plan has an optimization budget, the first line reports the stage of the optimizations
+    /// that is applied, see [`crate::plan::meta::OptimizationStage`], followed by the super node policy,
+    /// e.g., `super node: TRUNCATE beyond 1000 edge(s)`, and the annotations of the plan.
+    pub fn explain(&self) -> String {
+        let mut subtask_nodes = BTreeSet::new();
+        for (_, node) in self.nodes.iter() {
+            if let Some(subtask) = get_subtask(&node.borrow().opr) {
+                subtask_nodes
+                    .extend(self.collect_nodes(subtask, |node| node.children.iter().cloned().collect()));
+            }
+        }
+        let tag_names = self.get_tag_names();
+        let mut lines = vec![];
+        if let Some(budget) = self.meta.get_optimization_budget() {
+            lines.push(format!(
+                "optimization: {} (budget: {:?})",
+                self.meta.get_optimization_stage(),
+                budget
             ));
         }
-        let mut id_map: HashMap<NodeId, NodeId> = HashMap::new();
-        let mut parents: HashMap<NodeId, BTreeSet<NodeId>> = HashMap::new();
-        let mut result_id = 0;
-        for (id, node) in plan.nodes.iter().enumerate() {
-            for child in &node.children {
-                parents
-                    .entry(*child as NodeId)
-                    .or_insert_with(BTreeSet::new)
-                    .insert(id as NodeId);
-            }
+        if let Some(policy) = self.super_node_policy.as_ref() {
+            let action = pb::super_node_policy::Action::from_i32(policy.action)
+                .map(|action| format!("{:?}", action).to_uppercase())
+                .unwrap_or_default();
+            lines.push(format!("super node: {} beyond {} edge(s)", action, policy.threshold));
         }
-        for (id, node) in plan.nodes.into_iter().enumerate() {
-            if let Some(opr) = node.opr {
-                let new_parents = if !parents.contains_key(&(id as NodeId)) {
-                    parent_ids.clone()
-                } else {
-                    parents
-                        .get(&(id as NodeId))
-                        .cloned()
-                        .unwrap_or_default()
-                        .into_iter()
-                        .map(|old| {
-                            id_map
-                                .get(&old)
-                                .cloned()
-                                .ok_or(IrError::ParentNodeNotExist(old))
-                        })
-                        .collect::<IrResult<Vec<NodeId>>>()?
-                };
-                let new_id = self.append_operator_as_node(opr, new_parents)?;
-                id_map.insert(id as NodeId, new_id);
-                result_id = new_id;
-            } else {
-                return Err(IrError::MissingData("Node::opr".to_string()));
+        for annotation in &self.annotations {
+            lines.push(format!("annotation: {}", annotation));
+        }
+        let mut visited = BTreeSet::new();
+        for (id, node) in self.nodes.iter() {
+            let id = id as NodeId;
+            if node.borrow().children.is_empty() && !subtask_nodes.contains(&id) {
+                self.explain_node(id, 0, &tag_names, &mut visited, &mut lines);
             }
         }
 
-        Ok(result_id)
+        lines.join("\n")
     }
 
-    /// Append an operator into the logical plan, as a new node with `self.max_node_id` as its id.
-    pub fn append_operator_as_node(
-        &mut self, mut opr: pb::logical_plan::Operator, parent_ids: Vec<NodeId>,
-    ) -> IrResult<NodeId> {
-        use pb::logical_plan::operator::Opr;
-
-        let old_curr_node = self.meta.get_curr_node();
-        if opr.opr.is_none() {
-            return Err(IrError::MissingData("Operator::opr".to_string()));
+    fn explain_node(
+        &self, id: NodeId, depth: usize, tag_names: &BTreeMap<TagId, String>,
+        visited: &mut BTreeSet<NodeId>, lines: &mut Vec<String>,
+    ) {
+        let indent = "  ".repeat(depth);
+        if !visited.insert(id) {
+            lines.push(format!("{}-> #{}", indent, id));
+            return;
         }
-        // Set new current node as `self.max_node_id`
-        let new_curr_node = self.max_node_id;
-        self.meta.set_curr_node(new_curr_node);
-        // Configure `NodeMeta` for current node
-        let _ = self.meta.curr_node_meta_mut();
-        // By default, refer to the nodes that the the parent nodes refer to
-        // Certain operators will modify the referred nodes during preprocessing, including
-        // Scan, EdgeExpand, PathExpand, GetV, Apply and Project
-        let ref_parent_nodes = self.meta.get_referred_nodes(&parent_ids);
-        self.meta
-            .refer_to_nodes(new_curr_node, ref_parent_nodes);
-
-        if let Ok(store_meta) = STORE_META.read() {
-            opr.preprocess(&store_meta, &mut self.meta)?;
+        let node = match self.get_node(id) {
+            Some(node) => node,
+            None => return,
+        };
+        let node = node.borrow();
+        let mut line = format!("{}{}", indent, node.display_name());
+        // the tags are explained by their names rather than their ids
+        for detail in explain_details(&name_tags(&node.opr, tag_names)) {
+            line.push(' ');
+            line.push_str(&detail);
         }
-        let new_curr_node_rst = match opr.opr.as_ref().unwrap() {
-            Opr::Pattern(pattern) => {
-                if parent_ids.len() == 1 {
-                    // We try to match via ExtendStrategy. If not supported, match via NaiveStrategy.
-
-                    // Notice that there are some limitations of ExtendStrategy, including:
-                    // 1. the input of match should be a whole graph, i.e., `g.V().match(...)`
-                    // 2. the match sentences are connected by join_kind of `InnerJoin`
-                    // 3. the match binders should expand vertices if it is EdgeExpand opr
-                    // 4. if match binders of PathExpand exists, it should at least range from 1
-                    let is_pattern_source_whole_graph = self
-                        .get_opr(parent_ids[0])
-                        .map(|pattern_source| pattern_source.is_whole_graph())
-                        .ok_or(IrError::ParentNodeNotExist(parent_ids[0]))?;
-                    let extend_strategy = if is_pattern_source_whole_graph {
-                        ExtendStrategy::init(&pattern, &self.meta)
-                    } else {
-                        Err(IrPatternError::Unsupported("pattern source is not whole graph".to_string()))
-                    };
-                    match extend_strategy {
-                        Ok(extend_strategy) => {
-                            debug!("pattern matching by ExtendStrategy");
-                            let plan = extend_strategy.build_logical_plan()?;
-                            let new_node_id = self.append_plan(plan, parent_ids.clone())?;
-                            // As we have added a new source op to scan with label efficiently in extend_strategy,
-                            // we remove the old source op.
-                            self.nodes.remove(0);
-                            Ok(new_node_id)
-                        }
-                        Err(err) => match err {
-                            IrPatternError::Unsupported(_) => {
-                                // if is not supported in ExtendStrategy, try NaiveStrategy
-                                debug!("pattern matching by NaiveStrategy");
-                                let naive_strategy = NaiveStrategy::try_from(pattern.clone())?;
-                                let plan = naive_strategy.build_logical_plan()?;
-                                self.append_plan(plan, parent_ids.clone())
-                            }
-                            _ => Err(err.into()),
-                        },
-                    }
-                } else {
-                    Err(IrError::Unsupported(
-                        "only one single parent is supported for the `Pattern` operator".to_string(),
-                    ))
+        // the ordering kept from a preceding `OrderBy`, if any
+        if !matches!(node.opr.opr, Some(pb::logical_plan::operator::Opr::OrderBy(_))) {
+            if let Some(ordering) = self.get_ordering(id) {
+                line = format!("{} ORDERED {}", line, explain_ordering(ordering, tag_names));
+            }
+        }
+        if !node.attributes.is_empty() {
+            let attributes = node
+                .attributes
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>();
+            line = format!("{} {{{}}}", line, attributes.join(", "));
+        }
+        line = format!("{} (#{})", line, id);
+        if !node.annotations.is_empty() {
+            line = format!("{} // {}", line, node.annotations.join("; "));
+        }
+        lines.push(line);
+        if let Some(subtask) = get_subtask(&node.opr) {
+            lines.push(format!("{}  subtask:", indent));
+            for sub_id in self.collect_nodes(subtask, |node| node.children.iter().cloned().collect()) {
+                let is_leaf = self
+                    .get_node(sub_id)
+                    .map(|sub_node| sub_node.borrow().children.is_empty())
+                    .unwrap_or(false);
+                if is_leaf {
+                    self.explain_node(sub_id, depth + 2, tag_names, visited, lines);
                 }
             }
-            _ => self.append_node(Node::new(new_curr_node, opr), parent_ids.clone()),
-        };
-
-        // As in this case, the current id will not refer to any actual nodes, it is fine to
-        // keep its referred nodes.
-        if new_curr_node_rst.is_err() {
-            self.meta.set_curr_node(old_curr_node);
         }
+        for parent in node.parents.iter() {
+            self.explain_node(*parent, depth + 1, tag_names, visited, lines);
+        }
+    }
 
-        new_curr_node_rst
+    /// Attach a free-form annotation to the plan.
+    pub fn annotate_plan(&mut self, annotation: String) {
+        self.annotations.push(annotation);
     }
 
-    /// Remove a node from the logical plan, and do the following:
-    /// * For each of its parent, if present, remove this node's id reference from its `children`.
-    /// * For each of its children, remove this node's id reference from its `parent`, and if
-    /// the child's parent becomes empty, the child must be removed recursively.
-    ///
-    ///  Note that this does not decrease `self.node_max_id`, which serves as the indication
-    /// of new id of the plan.
-    pub fn remove_node(&mut self, id: NodeId) -> Option<NodeType> {
-        let node = self.nodes.remove(id as usize);
-        if let Some(n) = &node {
-            for p in &n.borrow().parents {
-                if let Some(parent_node) = self.get_node(*p) {
-                    parent_node.borrow_mut().children.remove(&id);
-                }
-            }
+    pub fn get_annotations(&self) -> &[String] {
+        &self.annotations
+    }
 
-            for c in &n.borrow().children {
-                if let Some(child) = self.get_node(*c) {
-                    child.borrow_mut().parents.remove(&id);
-                    if child.borrow().parents.is_empty() {
-                        // Recursively remove the child
-                        let _ = self.remove_node(*c);
-                    }
-                }
-            }
-        }
-        node
+    /// Allow the query to return partial results, i.e., the results returned before it fails on a
+    /// worker failure or a timeout, rather than failing.
+    pub fn set_allow_partial(&mut self, allow_partial: bool) {
+        self.allow_partial = allow_partial;
     }
 
-    /// Append branch plans to a certain node which has **no** children in this logical plan.
-    pub fn append_branch_plans(&mut self, node: NodeType, subplans: Vec<LogicalPlan>) {
-        if !node.borrow().children.is_empty() {
-            return;
-        } else {
-            for subplan in subplans {
-                if let Some((_, root)) = subplan.nodes.iter().next() {
-                    node.borrow_mut()
-                        .children
-                        .insert(root.borrow().id);
-                    root.borrow_mut()
-                        .parents
-                        .insert(node.borrow().id);
-                }
-                self.nodes.extend(subplan.nodes.into_iter());
-            }
-        }
+    pub fn is_partial_allowed(&self) -> bool {
+        self.allow_partial
     }
 
-    /// From a branch node `root`, obtain the sub-plans (branch node excluded), each representing
-    /// a branch of operators, till the merge node (merge node excluded).
-    ///
-    /// # Return
-    ///   * the merge node and sub-plans if the `brach_node` is indeed a branch node (has more
-    /// than one child), and its corresponding merge_node present.
-    ///   * `None` and empty sub-plans if otherwise.
-    pub fn get_branch_plans(&self, branch_node: NodeType) -> (Option<NodeType>, Vec<LogicalPlan>) {
-        let mut plans = vec![];
-        let mut merge_node_opt = None;
-        if branch_node.borrow().children.len() > 1 {
-            merge_node_opt = self.get_merge_node(branch_node.clone());
-            if let Some(merge_node) = &merge_node_opt {
-                for &child_node_id in &branch_node.borrow().children {
-                    if let Some(child_node) = self.get_node(child_node_id) {
-                        if let Some(subplan) = self.subplan(child_node, merge_node.clone()) {
-                            plans.push(subplan)
-                        }
+    /// Set the policy applied by all the expansions of the query while expanding from a super node,
+    /// i.e., a vertex of more than `policy.threshold` adjacent edges.
+    pub fn set_super_node_policy(&mut self, policy: pb::SuperNodePolicy) {
+        self.super_node_policy = Some(policy);
+    }
+
+    pub fn get_super_node_policy(&self) -> Option<&pb::SuperNodePolicy> {
+        self.super_node_policy.as_ref()
+    }
+
+    /// Set where the plan originates from, e.g., the text of the query, which is carried to the
+    /// physical plan, such that the engine logs it along with the job.
+    pub fn set_origin(&mut self, origin: pb::PlanOrigin) {
+        self.origin = Some(origin);
+    }
+
+    pub fn get_origin(&self) -> Option<&pb::PlanOrigin> {
+        self.origin.as_ref()
+    }
+
+    /// Mark the plan as a continuous query, which is registered to run over the updates of the graph
+    /// by the incremental runtime of the engine, and emits the changes of its results as `mode.emit`.
+    pub fn set_continuous(&mut self, mode: pb::ContinuousMode) {
+        self.continuous = Some(mode);
+    }
+
+    pub fn get_continuous(&self) -> Option<&pb::ContinuousMode> {
+        self.continuous.as_ref()
+    }
+
+    pub fn is_continuous(&self) -> bool {
+        self.continuous.is_some()
+    }
+
+    /// Restrict the labels and the properties that the plan may read to the given scope, e.g., those
+    /// granted to the user submitting the query, which is enforced by [`LogicalPlan::check_read_scope`]
+    /// while building the physical plan.
+    pub fn set_read_scope(&mut self, scope: pb::ReadScope) {
+        self.read_scope = Some(scope);
+    }
+
+    pub fn get_read_scope(&self) -> Option<&pb::ReadScope> {
+        self.read_scope.as_ref()
+    }
+
+    /// Check that the plan reads only the labels and the properties in its read scope, if any, such that
+    /// the label-level and property-level security is enforced at compile time. It fails with
+    /// `IrError::AccessDenied` on the first node (by the order of the ids) that:
+    /// * Scans, expands or gets the entities of a label out of the scope, or of any label, i.e., without
+    ///   the labels given, as the entities of any label may be read then
+    /// * Refers to a property that is out of the scope of any label that the entity may be of, where the
+    ///   labels are given by the operator that the tag (or the head) refers to, or are taken as all the
+    ///   labels of the scope if they are unknown, e.g., of a tag referring to the result of a join
+    /// * Refers to all the properties, e.g., as `valueMap()`, of a label restricted to some properties
+    pub fn check_read_scope(&self) -> IrResult<()> {
+        use pb::logical_plan::operator::Opr;
+
+        let scope = match self.read_scope.as_ref() {
+            Some(scope) => ReadScopeIndex::from(scope),
+            None => return Ok(()),
+        };
+        let schema = self
+            .meta
+            .with_store_meta(|store_meta| store_meta.schema.clone())
+            .flatten();
+        let schema = schema.as_ref();
+        for node in self.nodes.values() {
+            let node = node.borrow();
+            let parents: Vec<NodeId> = node.parents.iter().cloned().collect();
+            // the labels of the entity that a variable refers to, namely, the outputs of the nodes that
+            // its tag refers to, or the head of the node if it has no tag
+            let get_var_labels = |var: &common_pb::Variable, head: Option<&[String]>| match var.tag.as_ref()
+            {
+                Some(tag) => get_tag_id(tag, &self.meta)
+                    .and_then(|tag_id| self.get_output_labels(self.meta.get_tag_nodes(tag_id), schema)),
+                None => head
+                    .map(|labels| labels.to_vec())
+                    .or_else(|| self.get_output_labels(&self.meta.get_referred_nodes(&parents), schema)),
+            };
+            let mut result = Ok(());
+            let all_params = get_opr_params(&node.opr);
+            if all_params.is_empty() {
+                for var in get_referred_vars(&node.opr).unwrap_or_default() {
+                    if let Some(property) = var.property.as_ref() {
+                        let labels = get_var_labels(&var, None);
+                        result =
+                            result.and_then(|_| scope.check_property(labels.as_deref(), property, schema));
+                    }
+                }
+            }
+            for (params, is_entity) in all_params {
+                let params = params.cloned().unwrap_or_default();
+                let labels = get_table_names(&params.tables, is_entity, schema);
+                result = result
+                    .and_then(|_| scope.check_labels(&labels))
+                    .and_then(|_| scope.check_columns(&labels, &params, schema));
+                let mut vars = vec![];
+                params
+                    .predicate
+                    .iter()
+                    .for_each(|pred| collect_expr_vars(pred, &mut vars));
+                for var in vars {
+                    if let Some(property) = var.property.as_ref() {
+                        let labels = get_var_labels(var, Some(labels.as_slice()));
+                        result =
+                            result.and_then(|_| scope.check_property(labels.as_deref(), property, schema));
                     }
                 }
             }
+            if let Some(Opr::Edge(edgexpd)) = node.opr.opr.as_ref() {
+                if !edgexpd.vertex_tables.is_empty() {
+                    let labels = get_table_names(&edgexpd.vertex_tables, true, schema);
+                    result = result.and_then(|_| scope.check_labels(&labels));
+                }
+            }
+            if let Err(err) = result {
+                return Err(IrError::NodeError(node.to_string(), Box::new(err)));
+            }
         }
 
-        (merge_node_opt, plans)
+        Ok(())
     }
 
-    /// To construct a subplan from every node lying between `from_node` (included) and `to_node` (excluded)
-    /// in the logical plan. Thus, for the subplan to be valid, `to_node` must refer to a
-    /// downstream node against `from_node` in the plan.
-    ///
-    /// If there are some branches between `from_node` and `to_node`, there are two cases:
-    /// * 1. `to_node` lies within a sub-branch. In this case, **NO** subplan can be produced;
-    /// * 2. `to_node` is a downstream node of the merge node of the branch, then all branches
-    /// of nodes must be included in the subplan. For example, F is a `from_node` which has two
-    /// branches, namely F -> A1 -> B1 -> M, and F -> A2 -> B2 -> M. we have `to_node` T connected
-    /// to M in the logical plan, as M -> T0 -> T, the subplan from F to T, must include the operators
-    /// of F, A1, B1, A2, B2, M and T0.
-    ///
-    /// # Return
-    ///   * The subplan in case of success,
-    ///   * `None` if `from_node` is `to_node`, or could not arrive at `to_node` following the
-    ///  plan, or there is a branch node in between, but fail to locate the corresponding merge node.
-    pub fn subplan(&self, from_node: NodeType, to_node: NodeType) -> Option<LogicalPlan> {
-        if from_node == to_node {
-            return None;
-        }
-        let mut plan = LogicalPlan::with_root(clone_node(from_node.clone()));
-        plan.meta = self.meta.clone();
-        let mut curr_node = from_node;
-        while curr_node != to_node {
-            if curr_node.borrow().children.is_empty() {
-                // While still not locating to_node
-                return None;
-            } else if curr_node.borrow().children.len() == 1 {
-                let next_node_id = curr_node.borrow().get_first_child().unwrap();
-                if let Some(next_node) = self.get_node(next_node_id) {
-                    if next_node.borrow().id != to_node.borrow().id {
-                        plan.append_node(clone_node(next_node.clone()), vec![curr_node.borrow().id])
-                            .expect("append node to subplan error");
-                    }
-                    curr_node = next_node;
-                } else {
-                    return None;
+    /// The names of the labels of the entities output by the nodes, or `None` if the labels of some node
+    /// are unknown, e.g., the node outputs no entities, or they are of any label.
+    fn get_output_labels(&self, nodes: &[NodeId], schema: Option<&Schema>) -> Option<Vec<String>> {
+        use pb::logical_plan::operator::Opr;
+
+        let mut labels = vec![];
+        for id in nodes {
+            let node = self.get_node(*id)?;
+            let node = node.borrow();
+            let (tables, is_entity) = match node.opr.opr.as_ref()? {
+                Opr::Scan(scan) => {
+                    (&scan.params.as_ref()?.tables, scan.scan_opt != pb::scan::ScanOpt::Edge as i32)
                 }
-            } else {
-                let (merge_node_opt, subplans) = self.get_branch_plans(curr_node.clone());
-                if let Some(merge_node) = merge_node_opt {
-                    plan.append_branch_plans(plan.get_node(curr_node.borrow().id).unwrap(), subplans);
-                    if merge_node.borrow().id != to_node.borrow().id {
-                        let merge_node_parent = merge_node
-                            .borrow()
-                            .parents
-                            .iter()
-                            .map(|x| *x)
-                            .collect();
-                        let merge_node_clone = clone_node(merge_node.clone());
-                        plan.append_node(merge_node_clone, merge_node_parent)
-                            .expect("append node to subplan error");
-                    }
-                    curr_node = merge_node;
-                } else {
-                    return None;
+                // expanding to the vertices, of which the labels are given by the vertex tables
+                Opr::Edge(edgexpd) if edgexpd.expand_opt == pb::edge_expand::ExpandOpt::Vertex as i32 => {
+                    (&edgexpd.vertex_tables, true)
+                }
+                Opr::Edge(edgexpd) if edgexpd.expand_opt == pb::edge_expand::ExpandOpt::Edge as i32 => {
+                    (&edgexpd.params.as_ref()?.tables, false)
                 }
+                Opr::Vertex(getv) => (&getv.params.as_ref()?.tables, true),
+                _ => return None,
+            };
+            if tables.is_empty() {
+                return None;
             }
+            labels.extend(get_table_names(tables, is_entity, schema));
+        }
+
+        if labels.is_empty() {
+            None
+        } else {
+            Some(labels)
         }
-        Some(plan)
     }
 
-    /// Given a node that contains a subtask, which is typically an  `Apply` operator,
-    /// try to extract the subtask as a logical plan.
+    /// Build the plan within the given view, which constrains the scans and the expansions that are
+    /// appended to the plan afterwards, including those of the patterns, to the labels and the
+    /// predicates of the view, e.g., to isolate the queries of a tenant to its part of a shared graph.
+    /// Specifically, the labels of a scan or an expansion are narrowed down to those in the view, or
+    /// taken as those of the view if not given, and the predicate of the view is conjoined to its
+    /// predicate, where it reads nothing if none of its labels is in the view.
     ///
-    /// TODO(longbin): There may be an issue when the last node of a subtask is a merge node.
-    pub fn extract_subplan(&self, node: NodeType) -> Option<LogicalPlan> {
-        let node_borrow = node.borrow();
-        match &node_borrow.opr.opr {
-            Some(pb::logical_plan::operator::Opr::Apply(apply_opr)) => {
-                if let Some(from_node) = self.get_node(apply_opr.subtask as NodeId) {
-                    let mut curr_node = from_node.clone();
-                    while let Some(to_node) = curr_node
-                        .clone()
-                        .borrow()
-                        .get_first_child()
-                        .and_then(|node_id| self.get_node(node_id))
-                    {
-                        curr_node = to_node.clone();
-                    }
-                    let parent_ids = curr_node
-                        .borrow()
-                        .parents
-                        .iter()
-                        .cloned()
-                        .collect();
-                    let mut subplan = if from_node == curr_node {
-                        let mut p = LogicalPlan::default();
-                        p.meta = self.meta.clone();
-                        Some(p)
-                    } else {
-                        self.subplan(from_node, curr_node.clone())
+    /// The view is set before any operator is appended, as the appended ones are not constrained. Note
+    /// that expanding to the vertices is not supported within a view of a vertex predicate, as the
+    /// predicate can not apply to the vertices of an expansion, which expands the edges and gets the
+    /// vertices instead.
+    pub fn set_view(&mut self, view: pb::GraphView) -> IrResult<()> {
+        if self.is_frozen {
+            return Err(IrError::PlanFrozen);
+        }
+        if !self.is_empty() {
+            return Err(IrError::Unsupported(format!(
+                "setting the view {:?} of a non-empty plan",
+                view.name
+            )));
+        }
+        self.view = Some(view);
+
+        Ok(())
+    }
+
+    pub fn get_view(&self) -> Option<&pb::GraphView> {
+        self.view.as_ref()
+    }
+
+    /// The estimated size, in bytes, of the plan held in memory, which is meant to reject
+    /// the pathological plans before building them, rather than an exact accounting.
+    pub fn memory_footprint(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.nodes.capacity() * std::mem::size_of::<Option<NodeType>>()
+            + self
+                .nodes
+                .iter()
+                .map(|(_, node)| node.borrow().memory_footprint())
+                .sum::<usize>()
+            + self
+                .annotations
+                .iter()
+                .map(|annotation| std::mem::size_of::<String>() + annotation.capacity())
+                .sum::<usize>()
+    }
+
+    /// The size, in bytes, of the plan serialized as a `pb::LogicalPlan`, which sums up the encoded
+    /// lengths of the nodes and the fields of the plan one by one, rather than serializing a copy of
+    /// the plan, as the serialization does (see `From<LogicalPlan> for pb::LogicalPlan`).
+    pub fn serialized_size(&self) -> usize {
+        use prost::encoding::{self, btree_map, int32, message, string};
+
+        // As in the serialization, the nodes' ids are remapped to their indices
+        let id_map: HashMap<NodeId, PbNodeId> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(new_id, (old_id, _))| (old_id as NodeId, new_id as PbNodeId))
+            .collect();
+        let mut node_accesses =
+            if self.meta.is_trace_access() { self.get_node_accesses() } else { BTreeMap::new() };
+        let mut roots = vec![];
+        let mut size = 0;
+        for (id, node) in &self.nodes {
+            let node = node.borrow();
+            if node.parents.is_empty() {
+                roots.push(id_map[&(id as NodeId)]);
+            }
+            let opr_len = match &node.opr.opr {
+                Some(pb::logical_plan::operator::Opr::Apply(apply)) => {
+                    let apply = pb::Apply {
+                        subtask: id_map[&(apply.subtask as NodeId)] as PbNodeId,
+                        ..apply.clone()
                     };
-                    if let Some(plan) = subplan.as_mut() {
-                        plan.append_node(curr_node.borrow().clone(), parent_ids)
-                            .expect("append node to subplan error!");
-                    }
-                    subplan
-                } else {
-                    None
+                    message::encoded_len(1, &pb::logical_plan::Operator::from(apply))
                 }
-            }
-            _ => None,
+                _ => message::encoded_len(1, &node.opr),
+            };
+            let children = node
+                .children
+                .iter()
+                .map(|old_id| id_map[old_id])
+                .collect::<Vec<_>>();
+            let node_len = opr_len
+                + int32::encoded_len_packed(2, &children)
+                + string::encoded_len_repeated(3, &node.annotations)
+                + node
+                    .display_name
+                    .as_ref()
+                    .filter(|name| !name.is_empty())
+                    .map_or(0, |name| string::encoded_len(4, name))
+                + node_accesses
+                    .remove(&(id as NodeId))
+                    .map_or(0, |accesses| message::encoded_len_repeated(5, &accesses))
+                + btree_map::encoded_len(string::encoded_len, string::encoded_len, 6, &node.attributes);
+            size += encoding::key_len(1) + encoding::encoded_len_varint(node_len as u64) + node_len;
+        }
+        size += int32::encoded_len_packed(2, &roots) + string::encoded_len_repeated(3, &self.annotations);
+        if self.allow_partial {
+            size += encoding::bool::encoded_len(4, &self.allow_partial);
+        }
+        if PLAN_FORMAT_VERSION != 0 {
+            size += encoding::uint32::encoded_len(6, &PLAN_FORMAT_VERSION);
         }
+        size += self
+            .super_node_policy
+            .as_ref()
+            .map_or(0, |policy| message::encoded_len(5, policy))
+            + self
+                .origin
+                .as_ref()
+                .map_or(0, |origin| message::encoded_len(7, origin))
+            + self
+                .continuous
+                .as_ref()
+                .map_or(0, |continuous| message::encoded_len(8, continuous))
+            + self
+                .read_scope
+                .as_ref()
+                .map_or(0, |read_scope| message::encoded_len(9, read_scope))
+            + self
+                .view
+                .as_ref()
+                .map_or(0, |view| message::encoded_len(10, view));
+
+        size
     }
-}
 
-pub trait AsLogical {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()>;
-}
+    /// Serialize the plan as a `pb::CompactLogicalPlan`, in which the names of the tags, labels
+    /// and properties are kept once in a string table, and referred by their indices.
+    pub fn to_compact_pb(&self) -> IrResult<pb::CompactLogicalPlan> {
+        let plan_pb: pb::LogicalPlan = self.clone().into();
+        let mut strings = vec![];
+        let mut indices = HashMap::<String, usize>::new();
+        let plan_pb = map_plan_names(&plan_pb, |name| {
+            let idx = *indices
+                .entry(name.to_string())
+                .or_insert_with(|| {
+                    strings.push(name.to_string());
+                    strings.len() - 1
+                });
+            Ok(idx.to_string())
+        })?;
+
+        Ok(pb::CompactLogicalPlan { plan: Some(plan_pb), strings })
+    }
 
-fn check_primary_key_from_pb(
-    schema: &Schema, table: &common_pb::NameOrId, col: &common_pb::NameOrId, is_entity: bool,
-) -> (bool, usize) {
-    use ir_common::generated::common::name_or_id::Item;
+    /// Print the plan as json for debugging, in which the literal constants (e.g., the names or
+    /// emails compared in the predicates), the annotations, the display names and the text of the
+    /// query that the plan originates from are masked as [`REDACTED`], while the structure of the
+    /// plan is kept, such that it can be logged safely.
+    pub fn to_debug_string_redacted(&self) -> IrResult<String> {
+        let mut plan_pb: pb::LogicalPlan = self.clone().into();
+        if let Some(origin) = plan_pb
+            .origin
+            .as_mut()
+            .filter(|origin| !origin.query.is_empty())
+        {
+            origin.query = REDACTED.to_string();
+        }
+        let mut value =
+            serde_json::to_value(plan_pb).map_err(|e| ParsePbError::ParseError(format!("{:?}", e)))?;
+        redact_literals(&mut value);
+        Ok(serde_json::to_string_pretty(&value)
+            .map_err(|e| ParsePbError::ParseError(format!("{:?}", e)))?)
+    }
 
-    let mut table_name = "";
-    let mut col_name = "";
-    if let Some(item) = table.item.as_ref() {
-        match item {
-            Item::Name(name) => table_name = name.as_str(),
-            Item::Id(id) => {
-                if let Some(name) =
-                    if is_entity { schema.get_entity_name(*id) } else { schema.get_relation_name(*id) }
+    /// The names of the results that the plan registers via the sinks of `SinkResult`.
+    pub fn get_registered_results(&self) -> Vec<String> {
+        self.nodes
+            .values()
+            .filter_map(|node| match &node.borrow().opr.opr {
+                Some(pb::logical_plan::operator::Opr::Sink(pb::Sink {
+                    sink_target:
+                        Some(pb::sink::SinkTarget {
+                            inner: Some(pb::sink::sink_target::Inner::SinkResult(sink_result)),
+                        }),
+                    ..
+                })) => Some(sink_result.name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The names of the registered results that the plan scans.
+    pub fn get_scanned_results(&self) -> Vec<String> {
+        self.nodes
+            .values()
+            .filter_map(|node| match &node.borrow().opr.opr {
+                Some(pb::logical_plan::operator::Opr::Scan(scan)) if !scan.named_result.is_empty() => {
+                    Some(scan.named_result.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Lint the plan, which, beyond the validation while appending the operators, warns about the
+    /// patterns that likely make the query slow, or are mistakes, ordered by the ids of the nodes:
+    /// * A cartesian product that is not followed by any limit
+    /// * A path expansion without an upper bound of its hops
+    /// * A dedup that destroys the ordering established by a preceding order
+    /// * An alias that is never referred to, unless all the tags are sinked
+    pub fn lint(&self) -> Vec<PlanWarning> {
+        use pb::logical_plan::operator::Opr;
+
+        let mut referred_tags = Some(BTreeSet::new());
+        for node in self.nodes.values() {
+            referred_tags = referred_tags
+                .zip(get_referred_tags(&node.borrow().opr, &self.meta))
+                .map(|(mut tags, node_tags)| {
+                    tags.extend(node_tags);
+                    tags
+                });
+        }
+        let tag_names: BTreeMap<TagId, &String> = self
+            .meta
+            .get_tag_id_mappings()
+            .iter()
+            .map(|(name, tag_id)| (*tag_id, name))
+            .collect();
+
+        let mut warnings = vec![];
+        for (_, node) in self.nodes.iter() {
+            let node = node.borrow();
+            let mut warn = |rule: LintRule, message: String| {
+                warnings.push(PlanWarning {
+                    rule,
+                    node: node.id,
+                    message: format!("{}: {}", node, message),
+                })
+            };
+            match &node.opr.opr {
+                Some(Opr::Join(join)) if is_cartesian_product(join) && !self.is_limited(node.id) => warn(
+                    LintRule::CartesianWithoutLimit,
+                    "the cartesian product is not followed by any limit".to_string(),
+                ),
+                Some(Opr::Path(pathxpd))
+                    if pathxpd
+                        .hop_range
+                        .as_ref()
+                        .map(|range| range.upper == i32::MAX)
+                        .unwrap_or(true) =>
                 {
-                    table_name = name.as_str();
+                    warn(
+                        LintRule::UnboundedPathExpand,
+                        "the path expansion has no upper bound of its hops".to_string(),
+                    )
+                }
+                Some(Opr::Dedup(_))
+                    if node
+                        .parents
+                        .iter()
+                        .any(|parent| self.meta.get_node_ordering(*parent).is_some()) =>
+                {
+                    warn(
+                        LintRule::DedupAfterOrder,
+                        "the dedup destroys the ordering of its input, consider to dedup before ordering"
+                            .to_string(),
+                    )
+                }
+                _ => {}
+            }
+            if let Some(referred_tags) = referred_tags.as_ref() {
+                for alias in get_aliases(&node.opr) {
+                    if let Some(tag_id) = get_tag_id(alias, &self.meta) {
+                        let tag_name = tag_names.get(&tag_id);
+                        // the aliases given by the system are not warned about
+                        if !referred_tags.contains(&tag_id)
+                            && !tag_name
+                                .map(|name| name.starts_with('~'))
+                                .unwrap_or(false)
+                        {
+                            let tag_name = tag_name
+                                .map(|name| name.to_string())
+                                .unwrap_or_else(|| tag_id.to_string());
+                            warn(
+                                LintRule::UnusedAlias,
+                                format!("the alias {:?} is never referred to", tag_name),
+                            );
+                        }
+                    }
                 }
             }
         }
+
+        warnings
     }
-    if let Some(item) = col.item.as_ref() {
-        match item {
-            Item::Name(name) => col_name = name.as_str(),
-            Item::Id(id) => {
-                if let Some(name) = schema.get_column_name(*id) {
-                    col_name = name.as_str();
+
+    /// Whether the output of the node of the given id is limited by some node that follows it,
+    /// i.e., a `Limit`, or an `OrderBy` with a limit.
+    fn is_limited(&self, id: NodeId) -> bool {
+        use pb::logical_plan::operator::Opr;
+
+        let mut visited = BTreeSet::new();
+        let mut queue: VecDeque<NodeId> = VecDeque::new();
+        queue.push_back(id);
+        while let Some(id) = queue.pop_front() {
+            if let Some(node) = self.get_node(id) {
+                for child in node.borrow().children.iter() {
+                    if !visited.insert(*child) {
+                        continue;
+                    }
+                    let is_limit = self
+                        .get_node(*child)
+                        .map(|child| match &child.borrow().opr.opr {
+                            Some(Opr::Limit(_)) => true,
+                            Some(Opr::OrderBy(order)) => order.limit.is_some(),
+                            _ => false,
+                        })
+                        .unwrap_or(false);
+                    if is_limit {
+                        return true;
+                    }
+                    queue.push_back(*child);
                 }
             }
         }
+
+        false
     }
 
-    schema.check_primary_key(table_name, col_name)
-}
+    /// The tags and properties referred by each node, namely, by the expressions and the keys of its
+    /// operator, where the tags are given by their names. They are recorded in the serialized plan if
+    /// the access tracing is on (see [`PlanMeta::set_trace_access`]), such that, e.g., a property that
+    /// is not found by the runtime can be traced back to the node referring to it. A node that refers
+    /// to all the tags, i.e., a sink of no tags, is absent.
+    pub fn get_node_accesses(&self) -> BTreeMap<NodeId, Vec<common_pb::Variable>> {
+        let tag_names = self.get_tag_names();
+        self.nodes
+            .iter()
+            .filter_map(|(id, node)| {
+                get_opr_accesses(&node.borrow().opr, &tag_names).map(|accesses| (id as NodeId, accesses))
+            })
+            .collect()
+    }
 
-/// To optimize a triplet predicate of <pk, cmp, val> into an `IndexPredicate`.
-/// Notice that multiple tables allowed. If the tables have the same pk, it can be optimized into one `IndexPredicate`.
-fn triplet_to_index_predicate(
-    operators: &[common_pb::ExprOpr], tables: &Vec<common_pb::NameOrId>, is_vertex: bool, meta: &StoreMeta,
-) -> IrResult<Option<pb::IndexPredicate>> {
-    if operators.len() != 3 {
-        return Ok(None);
-    }
-    if meta.schema.is_none() {
-        return Ok(None);
+    /// Estimate the complexity of the plan as a single score, which sums up the costs of its nodes:
+    /// * A `Scan` costs by the logarithm of the number of the rows it scans (or looks up via the index),
+    ///   given by the statistics of the store if any
+    /// * An expansion costs by the number of the hops, and a path expansion exponentially by its
+    ///   maximal hops, which are capped at `MAX_SCORED_HOPS` if the expansion is unbounded
+    /// * A join costs more than the other operators, and a cartesian product even more
+    pub fn estimate_complexity(&self) -> f64 {
+        let score = |schema: Option<&Schema>| -> f64 {
+            self.nodes
+                .values()
+                .map(|node| get_opr_complexity(&node.borrow().opr, schema))
+                .sum()
+        };
+        self.meta
+            .with_store_meta(|store_meta| score(store_meta.schema.as_ref()))
+            .unwrap_or_else(|| score(None))
     }
-    if tables.is_empty() {
-        return Ok(None);
+
+    /// Estimate the complexity of the plan (see [`LogicalPlan::estimate_complexity`]), which fails
+    /// with `IrError::ComplexityExceeded` if it exceeds the threshold configured in the plan.
+    pub fn check_complexity(&self) -> IrResult<f64> {
+        let score = self.estimate_complexity();
+        match self.meta.get_complexity_threshold() {
+            Some(threshold) if score > threshold => Err(IrError::ComplexityExceeded(score, threshold)),
+            _ => Ok(score),
+        }
     }
-    let schema = meta.schema.as_ref().unwrap();
-    let mut key = None;
-    let mut is_eq = false;
-    let mut value = None;
-    if let Some(item) = &operators.get(0).unwrap().item {
-        match item {
-            common_pb::expr_opr::Item::Var(var) => {
-                if let Some(property) = &var.property {
-                    if let Some(item) = &property.item {
-                        match item {
-                            common_pb::property::Item::Key(col) => {
-                                let mut is_pk = true;
-                                for table in tables {
-                                    let (is_pk_, num_pks) =
-                                        check_primary_key_from_pb(schema, table, col, is_vertex);
-                                    if !is_pk_ || num_pks != 1 {
-                                        is_pk = false;
-                                        break;
-                                    }
-                                }
-                                if is_pk {
-                                    key = Some(property.clone());
-                                }
-                            }
-                            _ => { /*do nothing*/ }
-                        }
+
+    /// Validate the structure of the plan, which is kept by appending the operators, while may be broken
+    /// by appending the nodes of the ids given by the callers (see [`LogicalPlan::append_node`]), and return
+    /// the error of the first offending node, by checking that:
+    /// * The parents and the children of the nodes refer to each other, and a missing parent is reported
+    ///   as `IrError::ParentNodeNotExist`
+    /// * A `Join` has exactly two parents, and an `Intersect` has at least two
+    /// * The subtask of an `Apply` is a node without parents
+    /// * There is no cycle
+    /// * There is a single sink, i.e., a node without children, other than the subtasks, as the branches
+    ///   of a plan must be merged, e.g., by a `Union` or a `Join`
+    pub fn validate(&self) -> IrResult<()> {
+        use pb::logical_plan::operator::Opr;
+
+        let mut subtask_nodes = BTreeSet::new();
+        for (id, node) in self.nodes.iter() {
+            let id = id as NodeId;
+            let node = node.borrow();
+            for parent in node.parents.iter() {
+                let parent_node = self
+                    .get_node(*parent)
+                    .ok_or(IrError::ParentNodeNotExist(*parent))?;
+                if !parent_node.borrow().children.contains(&id) {
+                    return Err(IrError::InvalidNode(
+                        id,
+                        format!("its parent {} does not have it as a child", parent),
+                    ));
+                }
+            }
+            for child in node.children.iter() {
+                let is_linked = self
+                    .get_node(*child)
+                    .map(|child| child.borrow().parents.contains(&id))
+                    .unwrap_or(false);
+                if !is_linked {
+                    return Err(IrError::InvalidNode(
+                        id,
+                        format!("its child {} does not exist, or does not have it as a parent", child),
+                    ));
+                }
+            }
+            match &node.opr.opr {
+                Some(Opr::Join(_)) if node.parents.len() != 2 => {
+                    return Err(IrError::InvalidNode(
+                        id,
+                        format!("a `Join` has {} parents rather than two", node.parents.len()),
+                    ));
+                }
+                Some(Opr::Intersect(_)) if node.parents.len() < 2 => {
+                    return Err(IrError::InvalidNode(
+                        id,
+                        format!(
+                            "an `Intersect` has {} parents rather than at least two",
+                            node.parents.len()
+                        ),
+                    ));
+                }
+                Some(Opr::Apply(apply)) => {
+                    let subtask = apply.subtask as NodeId;
+                    let is_root = self
+                        .get_node(subtask)
+                        .map(|node| node.borrow().parents.is_empty())
+                        .unwrap_or(false);
+                    if !is_root {
+                        return Err(IrError::InvalidNode(
+                            id,
+                            format!("its subtask {} does not exist, or has parents", subtask),
+                        ));
                     }
+                    subtask_nodes.extend(
+                        self.collect_nodes(subtask, |node| node.children.iter().cloned().collect()),
+                    );
                 }
+                _ => {}
             }
-            _ => { /*do nothing*/ }
         }
-    };
+        let visited: BTreeSet<NodeId> = self
+            .iter_topo()
+            .map(|node| node.borrow().id)
+            .collect();
+        if let Some(id) = self
+            .nodes
+            .keys()
+            .map(|id| id as NodeId)
+            .find(|id| !visited.contains(id))
+        {
+            return Err(IrError::InvalidNode(id, "it is in a cycle, or depends on one".to_string()));
+        }
+        if let Some(id) = self
+            .nodes
+            .iter()
+            .filter(|(id, node)| {
+                node.borrow().children.is_empty() && !subtask_nodes.contains(&(*id as NodeId))
+            })
+            .map(|(id, _)| id as NodeId)
+            .nth(1)
+        {
+            return Err(IrError::InvalidNode(
+                id,
+                "it is a sink besides another one, while the branches must be merged".to_string(),
+            ));
+        }
 
-    if key.is_none() {
-        return Ok(None);
+        Ok(())
     }
 
-    if let Some(item) = &operators.get(1).unwrap().item {
-        match item {
-            common_pb::expr_opr::Item::Logical(l) => {
-                if *l == 0 {
-                    // Eq
-                    is_eq = true;
+    /// Estimate the number of the rows output by the node of the given id, e.g., for the clients to warn
+    /// the users of an expensive query before running it, by the statistics of the store, i.e., the
+    /// numbers of the entities and the relations of each label:
+    /// * A `Scan` outputs the rows of its tables, or a row per key it looks up via the index
+    /// * An expansion outputs the average degree of its relations per input row, for each hop of a path
+    ///   expansion, where an unbounded path expansion is capped at `MAX_SCORED_HOPS`
+    /// * A predicate keeps `PREDICATE_SELECTIVITY` of its input, and a limit keeps up to its range
+    /// * A join outputs the product of its inputs if it is a cartesian product, or the larger input
+    ///   otherwise
+    ///
+    /// # Return
+    ///   * the estimated number of the rows, or `None` if it cannot be estimated, e.g., the statistics
+    /// are absent, or the node is (downstream of) a `Pattern`
+    pub fn estimate_cardinality(&self, id: NodeId) -> IrResult<Option<f64>> {
+        if self.get_node(id).is_none() {
+            return Err(IrError::NodeNotExist(id));
+        }
+        let estimate = self
+            .meta
+            .with_store_meta(|store_meta| {
+                store_meta
+                    .schema
+                    .as_ref()
+                    .and_then(|schema| self.estimate_node_cardinality(id, schema, &mut HashMap::new()))
+            })
+            .flatten();
+
+        Ok(estimate)
+    }
+
+    fn estimate_node_cardinality(
+        &self, id: NodeId, schema: &Schema, estimates: &mut HashMap<NodeId, Option<f64>>,
+    ) -> Option<f64> {
+        use pb::logical_plan::operator::Opr;
+
+        if let Some(estimate) = estimates.get(&id) {
+            return *estimate;
+        }
+        let (opr, parents) = {
+            let node = self.get_node(id)?;
+            let node = node.borrow();
+            (node.opr.clone(), node.parents.iter().cloned().collect::<Vec<_>>())
+        };
+        let inputs: Vec<Option<f64>> = parents
+            .into_iter()
+            .map(|parent| self.estimate_node_cardinality(parent, schema, estimates))
+            .collect();
+        let input = inputs.first().cloned().flatten();
+        let estimate = match &opr.opr {
+            Some(Opr::Scan(scan)) => get_scan_cardinality(scan, schema),
+            Some(Opr::Edge(edge)) if edge.expand_opt == pb::edge_expand::ExpandOpt::Degree as i32 => input,
+            Some(Opr::Edge(edge)) => input
+                .zip(get_expand_degree(edge, schema))
+                .map(|(input, degree)| input * degree * get_selectivity(edge.params.as_ref())),
+            Some(Opr::Path(pathxpd)) => {
+                let degree = pathxpd
+                    .base
+                    .as_ref()
+                    .and_then(|base| base.edge_expand.as_ref())
+                    .and_then(|edge| get_expand_degree(edge, schema));
+                let (lower, upper) = pathxpd
+                    .hop_range
+                    .as_ref()
+                    .map(|range| (range.lower.max(0), range.upper.min(MAX_SCORED_HOPS + 1)))
+                    .unwrap_or((1, MAX_SCORED_HOPS + 1));
+                input.zip(degree).map(|(input, degree)| {
+                    input
+                        * (lower..upper)
+                            .map(|hops| degree.powi(hops))
+                            .sum::<f64>()
+                })
+            }
+            Some(Opr::Vertex(getv)) => input.map(|input| input * get_selectivity(getv.params.as_ref())),
+            Some(Opr::Select(_)) => input.map(|input| input * PREDICATE_SELECTIVITY),
+            Some(Opr::Limit(limit)) => {
+                input.map(|input| get_limited_cardinality(input, limit.range.as_ref()))
+            }
+            Some(Opr::OrderBy(order)) => {
+                input.map(|input| get_limited_cardinality(input, order.limit.as_ref()))
+            }
+            Some(Opr::Count(_)) => Some(1.0),
+            Some(Opr::GroupBy(group)) if group.mappings.is_empty() => Some(1.0),
+            Some(Opr::Union(_)) => inputs.iter().cloned().sum(),
+            Some(Opr::Intersect(_)) => inputs
+                .iter()
+                .cloned()
+                .collect::<Option<Vec<f64>>>()
+                .and_then(|inputs| inputs.into_iter().reduce(f64::min)),
+            Some(Opr::Join(join)) => {
+                let right = inputs.get(1).cloned().flatten();
+                match pb::join::JoinKind::from_i32(join.kind) {
+                    Some(pb::join::JoinKind::Semi) | Some(pb::join::JoinKind::Anti) => input,
+                    _ if is_cartesian_product(join) => input
+                        .zip(right)
+                        .map(|(left, right)| left * right),
+                    _ => input
+                        .zip(right)
+                        .map(|(left, right)| left.max(right)),
                 }
             }
-            _ => { /*do nothing*/ }
-        }
-    };
+            Some(Opr::Pattern(_)) => None,
+            _ => input,
+        };
+        estimates.insert(id, estimate);
 
-    if !is_eq {
-        return Ok(None);
+        estimate
     }
 
-    if let Some(item) = &operators.get(2).unwrap().item {
-        match item {
-            common_pb::expr_opr::Item::Const(c) => {
-                value = Some(c.clone());
+    /// The nodes of the plan in a canonical order, i.e., regardless of the order in which the
+    /// independent operators were appended, which is meant to assign the ids of the nodes
+    /// deterministically (see [`LogicalPlan::canonicalize_node_ids`]).
+    ///
+    /// The order is topological, in which a node follows its parents, an `Apply` follows the nodes
+    /// of its subtask, and the parents of a node keep their relative order (e.g., the left and right
+    /// of a join). Among the nodes that are ready at once, the one with the earliest parents goes
+    /// first, and then the one with the smallest operator, and then the one with the smallest
+    /// operators of its descendants, where the operators refer to the tags by their names, as the ids
+    /// of the tags are also assigned in the order of appending.
+    pub fn canonical_order(&self) -> Vec<NodeId> {
+        self.get_canonical_order(true)
+    }
+
+    /// Iterate over the nodes of the plan in a topological order, in which a node follows all the
+    /// nodes that it depends on as described in [`LogicalPlan::canonical_order`], and among the nodes
+    /// that are ready at once, the one with the smallest id goes first.
+    pub fn iter_topo(&self) -> TopoIter<'_> {
+        let dependencies = self.get_node_dependencies(true);
+        let mut dependents: BTreeMap<NodeId, Vec<NodeId>> = BTreeMap::new();
+        for (id, deps) in dependencies.iter() {
+            for dep in deps {
+                dependents.entry(*dep).or_default().push(*id);
             }
-            _ => { /*do nothing*/ }
         }
-    };
-    if value.is_none() {
-        return Ok(None);
+        let pending: BTreeMap<NodeId, usize> = dependencies
+            .iter()
+            .map(|(id, deps)| (*id, deps.len()))
+            .collect();
+        let ready = pending
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(id, _)| *id)
+            .collect();
+
+        TopoIter { plan: self, pending, dependents, ready }
     }
 
-    let idx_pred = pb::IndexPredicate {
-        or_predicates: vec![pb::index_predicate::AndPredicate {
-            predicates: vec![pb::index_predicate::Triplet { key, value, cmp: None }],
-        }],
-    };
+    /// Walk the plan with the visitor in the order of [`LogicalPlan::iter_topo`], which stops at
+    /// the first node that the visitor fails on.
+    pub fn accept<V: PlanVisitor + ?Sized>(&self, visitor: &mut V) -> IrResult<()> {
+        for node in self.iter_topo() {
+            visitor.visit_node(&node.borrow())?;
+        }
 
-    Ok(Some(idx_pred))
-}
+        Ok(())
+    }
 
-fn get_table_id_from_pb(schema: &Schema, name: &common_pb::NameOrId) -> Option<KeyId> {
-    name.item.as_ref().and_then(|item| match item {
-        common_pb::name_or_id::Item::Name(name) => schema.get_table_id(name),
-        common_pb::name_or_id::Item::Id(id) => Some(*id),
-    })
-}
+    /// The canonical order of the nodes, in which the parents of a `Union` or an `Intersect` are free
+    /// to go in any order if `is_branch_ordered` is `false`, and thus are ordered by what they are.
+    fn get_canonical_order(&self, is_branch_ordered: bool) -> Vec<NodeId> {
+        let dependencies = self.get_node_dependencies(is_branch_ordered);
+        let tag_names = self.get_tag_names();
+        let named_oprs: HashMap<NodeId, pb::logical_plan::Operator> = self
+            .nodes
+            .iter()
+            .map(|(id, node)| (id as NodeId, name_tags(&node.borrow().opr, &tag_names)))
+            .collect();
+        let mut signatures = HashMap::new();
+        let mut positions: HashMap<NodeId, NodeId> = HashMap::with_capacity(self.nodes.len());
+        let mut pending: BTreeSet<NodeId> = self
+            .nodes
+            .keys()
+            .map(|id| id as NodeId)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while !pending.is_empty() {
+            let next = pending
+                .iter()
+                .filter(|id| {
+                    dependencies[*id]
+                        .iter()
+                        .all(|dep| positions.contains_key(dep))
+                })
+                .map(|id| {
+                    let node = self.nodes[*id as usize].borrow();
+                    let parents: Vec<NodeId> = node
+                        .parents
+                        .iter()
+                        .map(|parent| positions[parent])
+                        .collect();
+                    let opr =
+                        remap_node_refs(&named_oprs[id], |id| positions.get(&id).cloned()).encode_to_vec();
+                    let signature = self.get_node_signature(*id, &named_oprs, &mut signatures);
+                    (parents, opr, signature, *id)
+                })
+                .min()
+                .map(|(_, _, _, id)| id)
+                // the dependencies always refer to the nodes of smaller ids, and thus some node is ready
+                .expect("no node is ready in the canonical order");
+            pending.remove(&next);
+            positions.insert(next, order.len() as NodeId);
+            order.push(next);
+        }
 
-fn get_column_id_from_pb(schema: &Schema, name: &common_pb::NameOrId) -> Option<KeyId> {
-    name.item.as_ref().and_then(|item| match item {
-        common_pb::name_or_id::Item::Name(name) => schema.get_column_id(name),
-        common_pb::name_or_id::Item::Id(id) => Some(*id),
-    })
-}
+        order
+    }
 
-fn preprocess_var(
-    var: &mut common_pb::Variable, meta: &StoreMeta, plan_meta: &mut PlanMeta, is_predicate: bool,
-) -> IrResult<()> {
-    let tag =
-        if let Some(tag) = var.tag.as_mut() { Some(get_or_set_tag_id(tag, plan_meta)?) } else { None };
-    let mut node_meta = plan_meta.curr_node_meta_mut();
-    if let Some(property) = var.property.as_mut() {
-        if let Some(key) = property.item.as_mut() {
-            match key {
-                common_pb::property::Item::Key(key) => {
-                    if let Some(schema) = &meta.schema {
-                        if schema.is_column_id() {
-                            let new_key = get_column_id_from_pb(schema, key)
-                                .unwrap_or(INVALID_META_ID)
-                                .into();
-                            debug!("column: {:?} -> {:?}", key, new_key);
-                            *key = new_key;
-                        }
-                    }
-                    // A column that only presents for a predicate should not be materialized
-                    if !is_predicate {
-                        debug!("add column ({:?}) to {:?}", key, var.tag);
-                        node_meta.insert_tag_column(tag, key.clone().try_into()?);
-                    }
-                }
-                common_pb::property::Item::All(_) => {
-                    node_meta.set_tag_columns_opt(tag, ColumnsOpt::All(256))
+    /// Reassign the ids of the nodes by the given order, in which the node at index `i` is given
+    /// the id `i`, and return the reassigned ids of the nodes by their previous ids. The order must
+    /// contain each node of the plan exactly once, and respect the dependencies among the nodes as
+    /// described in [`LogicalPlan::canonical_order`], otherwise, the plan is left unchanged.
+    ///
+    /// As the plan is rebuilt in the order, the ids of the named tags are also reassigned in the order.
+    pub fn reorder_nodes(&mut self, order: &[NodeId]) -> IrResult<HashMap<NodeId, NodeId>> {
+        self.reorder_nodes_by(order, true)
+    }
+
+    fn reorder_nodes_by(
+        &mut self, order: &[NodeId], is_branch_ordered: bool,
+    ) -> IrResult<HashMap<NodeId, NodeId>> {
+        let mut positions: HashMap<NodeId, NodeId> = HashMap::with_capacity(order.len());
+        for id in order {
+            if !self.nodes.contains_key(*id as usize) {
+                return Err(IrError::NodeNotExist(*id));
+            }
+            positions.insert(*id, positions.len() as NodeId);
+        }
+        if let Some(missing) = self
+            .nodes
+            .keys()
+            .find(|id| !positions.contains_key(&(*id as NodeId)))
+        {
+            return Err(IrError::MissingData(format!("node {} in the order", missing)));
+        }
+        for (id, dependencies) in self.get_node_dependencies(is_branch_ordered) {
+            if let Some(dep) = dependencies
+                .into_iter()
+                .find(|dep| positions[dep] > positions[&id])
+            {
+                // the dependency does not exist yet while appending the node
+                return Err(IrError::ParentNodeNotExist(dep));
+            }
+        }
+
+        let plan_pb = self.to_pb_in_order(order);
+        let (plan, id_map) =
+            LogicalPlan::from_pb_with_meta(plan_pb, PlanMeta::with_settings_of(&self.meta))?;
+        *self = plan;
+
+        Ok(positions
+            .into_iter()
+            .map(|(old, pos)| (old, id_map[&pos]))
+            .collect())
+    }
+
+    /// Serialize the given nodes of the plan in the given order, in which the tags are referred by their
+    /// names, such that the plan rebuilt from it assigns the ids of the nodes, as well as the tags, in the
+    /// order. The nodes out of the order are left out, and thus a node whose parents are all left out
+    /// becomes a root.
+    fn to_pb_in_order(&self, order: &[NodeId]) -> pb::LogicalPlan {
+        let positions: HashMap<NodeId, NodeId> = order
+            .iter()
+            .enumerate()
+            .map(|(pos, id)| (*id, pos as NodeId))
+            .collect();
+        let tag_names = self.get_tag_names();
+        let mut plan_pb = pb::LogicalPlan {
+            nodes: vec![],
+            roots: vec![],
+            annotations: self.annotations.clone(),
+            allow_partial: self.allow_partial,
+            super_node_policy: self.super_node_policy.clone(),
+            format_version: PLAN_FORMAT_VERSION,
+            origin: self.origin.clone(),
+            continuous: self.continuous.clone(),
+            read_scope: self.read_scope.clone(),
+            view: self.view.clone(),
+        };
+        for id in order {
+            let node = self.nodes[*id as usize].borrow();
+            if node
+                .parents
+                .iter()
+                .all(|parent| !positions.contains_key(parent))
+            {
+                plan_pb.roots.push(positions[id] as PbNodeId);
+            }
+            plan_pb.nodes.push(pb::logical_plan::Node {
+                opr: Some(name_tags(
+                    &remap_node_refs(&node.opr, |id| positions.get(&id).cloned()),
+                    &tag_names,
+                )),
+                children: node
+                    .children
+                    .iter()
+                    .filter_map(|child| positions.get(child).map(|pos| *pos as PbNodeId))
+                    .collect(),
+                annotations: node.annotations.clone(),
+                display_name: node.display_name.clone().unwrap_or_default(),
+                accesses: vec![],
+                attributes: node
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            });
+        }
+
+        plan_pb
+    }
+
+    /// Reassign the ids of the nodes by the canonical order of the nodes, such that the ids, and
+    /// thus the serialized plan, are deterministic regardless of the order in which the independent
+    /// operators were appended. It returns the reassigned ids of the nodes by their previous ids.
+    pub fn canonicalize_node_ids(&mut self) -> IrResult<HashMap<NodeId, NodeId>> {
+        let order = self.canonical_order();
+        self.reorder_nodes(&order)
+    }
+
+    /// Normalize the plan, such that the semantically identical plans, e.g., those produced by
+    /// different frontends, become identical. The predicates and the ranges of the operators are
+    /// normalized (see [`normalize_operator`]), and then the ids of the nodes are reassigned by their
+    /// canonical order as [`LogicalPlan::canonicalize_node_ids`] does, except that the branches of
+    /// a `Union` or an `Intersect` are ordered by what they are, rather than the order in which they
+    /// were appended.
+    /// It returns the reassigned ids of the nodes by their previous ids.
+    pub fn normalize(&mut self) -> IrResult<HashMap<NodeId, NodeId>> {
+        let tag_names = self.get_tag_names();
+        for (_, node) in self.nodes.iter() {
+            let mut node = node.borrow_mut();
+            if let Err(err) = normalize_operator(&mut node.opr, &tag_names) {
+                return Err(IrError::NodeError(node.to_string(), Box::new(err)));
+            }
+        }
+        let order = self.get_canonical_order(false);
+        let id_map = self.reorder_nodes_by(&order, false)?;
+        for (_, node) in self.nodes.iter() {
+            match node.borrow_mut().opr.opr.as_mut() {
+                Some(pb::logical_plan::operator::Opr::Union(union)) => union.parents.sort_unstable(),
+                Some(pb::logical_plan::operator::Opr::Intersect(intersect)) => {
+                    intersect.parents.sort_unstable()
                 }
                 _ => {}
             }
         }
+
+        Ok(id_map)
     }
 
-    Ok(())
-}
+    /// The normalized plan serialized as a `LogicalPlan` pb, as [`LogicalPlan::to_structural_pb`] does.
+    fn to_normalized_pb(&self) -> IrResult<pb::LogicalPlan> {
+        let mut plan = self.clone_plan();
+        plan.normalize()?;
 
-fn preprocess_label(
-    label: &mut common_pb::Value, meta: &StoreMeta, _plan_meta: &mut PlanMeta,
-) -> IrResult<()> {
-    if let Some(schema) = &meta.schema {
-        // A Const needs to be preprocessed only if it is while comparing a label (table)
-        if schema.is_table_id() {
-            if let Some(item) = label.item.as_mut() {
-                match item {
-                    common_pb::value::Item::Str(name) => {
-                        let new_item = common_pb::value::Item::I32(
-                            schema
-                                .get_table_id(name)
-                                .ok_or(IrError::TableNotExist(NameOrId::Str(name.to_string())))?,
-                        );
-                        debug!("table: {:?} -> {:?}", item, new_item);
-                        *item = new_item;
-                    }
-                    common_pb::value::Item::StrArray(names) => {
-                        let new_item = common_pb::value::Item::I32Array(common_pb::I32Array {
-                            item: names
-                                .item
-                                .iter()
-                                .map(|name| {
-                                    schema
-                                        .get_table_id(name)
-                                        .ok_or(IrError::TableNotExist(NameOrId::Str(name.to_string())))
-                                })
-                                .collect::<IrResult<Vec<_>>>()?,
-                        });
-                        debug!("table: {:?} -> {:?}", item, new_item);
-                        *item = new_item;
-                    }
-                    _ => {}
+        Ok(plan.to_structural_pb())
+    }
+
+    /// The plan serialized as a `LogicalPlan` pb, in which the tags are referred by their names, and
+    /// the annotations and the display names, which do not affect the results, are left out.
+    fn to_structural_pb(&self) -> pb::LogicalPlan {
+        let order: Vec<NodeId> = self
+            .nodes
+            .keys()
+            .map(|id| id as NodeId)
+            .collect();
+        let mut plan_pb = self.to_pb_in_order(&order);
+        plan_pb.annotations.clear();
+        plan_pb.origin = None;
+        for node in plan_pb.nodes.iter_mut() {
+            node.annotations.clear();
+            node.attributes.clear();
+            node.display_name.clear();
+        }
+
+        plan_pb
+    }
+
+    /// The fingerprint of the plan, which is computed over the normalized plan (see
+    /// [`LogicalPlan::normalize`]), such that the semantically identical plans have the same
+    /// fingerprint, regardless of the frontends producing them, unless they are built against the
+    /// different versions of the schema of a session. The plan itself is left unchanged.
+    ///
+    /// The fingerprint is computed by the FNV-1a hash, and thus is stable across the processes and the
+    /// builds of the library, such that it can key the plans cached by, e.g., the servers.
+    pub fn fingerprint(&self) -> IrResult<u64> {
+        use std::hash::Hasher;
+
+        let mut hasher = StableHasher::default();
+        hasher.write(&self.to_normalized_pb()?.encode_to_vec());
+        // the same plan built against a reloaded schema is given another fingerprint
+        if let Some(version) = self
+            .meta
+            .get_store_meta()
+            .map(|store_meta| store_meta.version)
+            .filter(|version| *version > 0)
+        {
+            hasher.write(&version.to_le_bytes());
+        }
+
+        Ok(hasher.finish())
+    }
+
+    /// Whether the plan is equivalent to the other, namely, they are identical once normalized
+    /// (see [`LogicalPlan::normalize`]), which, unlike comparing the fingerprints, is free of collisions.
+    pub fn is_equivalent(&self, other: &LogicalPlan) -> IrResult<bool> {
+        Ok(self.to_normalized_pb()? == other.to_normalized_pb()?)
+    }
+
+    /// Whether the plan is structurally equal to the other, namely, they have the same operators connected
+    /// in the same way, see [`LogicalPlan::diff`]. Unlike [`LogicalPlan::is_equivalent`], the plans are
+    /// not normalized, which tells whether a plan is built exactly as expected, e.g., in a golden test.
+    pub fn structurally_equals(&self, other: &LogicalPlan) -> bool {
+        self.diff(other).is_empty()
+    }
+
+    /// The differences of the other plan from this one, namely, the nodes, the operators of the nodes,
+    /// and the edges between the nodes that differ, ordered by the nodes. The operators are compared
+    /// with the tags referred by their names, while the annotations and the display names are left out.
+    pub fn diff(&self, other: &LogicalPlan) -> Vec<PlanDiff> {
+        let this_pb = self.to_structural_pb();
+        let other_pb = other.to_structural_pb();
+        let mut diffs = vec![];
+        for idx in 0..std::cmp::max(this_pb.nodes.len(), other_pb.nodes.len()) {
+            let id = idx as NodeId;
+            let (this_node, other_node) = match (this_pb.nodes.get(idx), other_pb.nodes.get(idx)) {
+                (Some(this_node), Some(other_node)) => (this_node, other_node),
+                (Some(_), None) => {
+                    diffs.push(PlanDiff::NodeRemoved(id));
+                    continue;
+                }
+                (None, _) => {
+                    diffs.push(PlanDiff::NodeAdded(id));
+                    continue;
                 }
+            };
+            if this_node.opr != other_node.opr {
+                let opr_to_string = |opr: &Option<pb::logical_plan::Operator>| {
+                    format!("{:?}", opr.as_ref().and_then(|opr| opr.opr.as_ref()))
+                };
+                diffs.push(PlanDiff::OperatorChanged {
+                    node: id,
+                    this: opr_to_string(&this_node.opr),
+                    other: opr_to_string(&other_node.opr),
+                });
             }
+            diffs.extend(
+                other_node
+                    .children
+                    .iter()
+                    .filter(|child| !this_node.children.contains(child))
+                    .map(|child| PlanDiff::EdgeAdded(id, *child as NodeId)),
+            );
+            diffs.extend(
+                this_node
+                    .children
+                    .iter()
+                    .filter(|child| !other_node.children.contains(child))
+                    .map(|child| PlanDiff::EdgeRemoved(id, *child as NodeId)),
+            );
         }
+
+        diffs
     }
-    Ok(())
-}
 
-fn preprocess_expression(
-    expr: &mut common_pb::Expression, meta: &StoreMeta, plan_meta: &mut PlanMeta, is_predicate: bool,
-) -> IrResult<()> {
-    let mut count = 0;
-    for opr in expr.operators.iter_mut() {
-        if let Some(item) = opr.item.as_mut() {
-            match item {
-                common_pb::expr_opr::Item::Var(var) => {
-                    if let Some(property) = var.property.as_mut() {
-                        if let Some(key) = property.item.as_mut() {
-                            match key {
-                                common_pb::property::Item::Label(_) => count = 1,
-                                _ => count = 0,
-                            }
-                        }
-                    }
-                    preprocess_var(var, meta, plan_meta, is_predicate)?;
-                }
-                common_pb::expr_opr::Item::Logical(l) => {
-                    if count == 1 {
-                        // means previous one is LabelKey
-                        // The logical operator of Eq, Ne, Lt, Le, Gt, Ge, Within, Without
-                        if *l >= 0 && *l <= 7 {
-                            count = 2; // indicates LabelKey <cmp>
+    /// The nodes that each node depends on, namely, that must precede the node, including
+    /// its parents, the nodes of its subtask if it is an `Apply`, and the preceding parents of
+    /// each of its parents that has siblings, for keeping the relative order of the parents,
+    /// except for the parents of a `Union` or an `Intersect` if `is_branch_ordered` is `false`.
+    fn get_node_dependencies(&self, is_branch_ordered: bool) -> BTreeMap<NodeId, BTreeSet<NodeId>> {
+        let mut dependencies: BTreeMap<NodeId, BTreeSet<NodeId>> = self
+            .nodes
+            .iter()
+            .map(|(id, node)| (id as NodeId, node.borrow().parents.clone()))
+            .collect();
+        for (id, node) in self.nodes.iter() {
+            let node = node.borrow();
+            if let Some(pb::logical_plan::operator::Opr::Apply(apply)) = &node.opr.opr {
+                let mut queue = VecDeque::new();
+                queue.push_back(apply.subtask as NodeId);
+                while let Some(sub_id) = queue.pop_front() {
+                    if let Some(sub_node) = self.get_node(sub_id) {
+                        if dependencies
+                            .get_mut(&(id as NodeId))
+                            .unwrap()
+                            .insert(sub_id)
+                        {
+                            queue.extend(sub_node.borrow().children.iter().cloned());
                         }
-                    } else {
-                        count = 0;
                     }
                 }
-                common_pb::expr_opr::Item::Const(c) => {
-                    if count == 2 {
-                        // indicates LabelKey <cmp> labelValue
-                        preprocess_label(c, meta, plan_meta)?;
-                    }
-                    count = 0;
+            }
+            if !is_branch_ordered {
+                if let Some(pb::logical_plan::operator::Opr::Union(_))
+                | Some(pb::logical_plan::operator::Opr::Intersect(_)) = &node.opr.opr
+                {
+                    continue;
                 }
-                common_pb::expr_opr::Item::Vars(vars) | common_pb::expr_opr::Item::VarMap(vars) => {
-                    for var in &mut vars.keys {
-                        preprocess_var(var, meta, plan_meta, false)?;
-                    }
-                    count = 0;
+            }
+            let parents: Vec<NodeId> = node.parents.iter().cloned().collect();
+            for pair in parents.windows(2) {
+                if let Some(deps) = dependencies.get_mut(&pair[1]) {
+                    deps.insert(pair[0]);
                 }
-                _ => count = 0,
             }
         }
+
+        dependencies
     }
 
-    Ok(())
-}
+    /// The signature of the operators of the node and its descendants, which distinguishes the
+    /// nodes of the same parents and operators by what follows them.
+    fn get_node_signature(
+        &self, id: NodeId, named_oprs: &HashMap<NodeId, pb::logical_plan::Operator>,
+        signatures: &mut HashMap<NodeId, u64>,
+    ) -> u64 {
+        use std::hash::Hasher;
 
-fn preprocess_params(
-    params: &mut pb::QueryParams, meta: &StoreMeta, plan_meta: &mut PlanMeta,
-) -> IrResult<()> {
-    if let Some(pred) = &mut params.predicate {
-        preprocess_expression(pred, meta, plan_meta, true)?;
-    }
-    if let Some(schema) = &meta.schema {
-        if schema.is_table_id() {
-            for table in params.tables.iter_mut() {
-                let new_table = get_table_id_from_pb(schema, table)
-                    .ok_or(IrError::TableNotExist(table.clone().try_into()?))?
-                    .into();
-                debug!("table: {:?} -> {:?}", table, new_table);
-                *table = new_table;
-            }
+        if let Some(signature) = signatures.get(&id) {
+            return *signature;
         }
-    }
-    let mut node_meta = plan_meta.curr_node_meta_mut();
-    if params.is_all_columns {
-        node_meta.set_columns_opt(ColumnsOpt::All(256));
-    } else {
-        for column in params.columns.iter_mut() {
-            if let Some(schema) = &meta.schema {
-                if schema.is_column_id() {
-                    let column_id = get_column_id_from_pb(schema, column)
-                        .unwrap_or(INVALID_META_ID)
-                        .into();
-                    debug!("column: {:?} -> {:?}", column, column_id);
-                    *column = column_id;
-                }
+        // the signatures order the nodes while normalizing the plan, and thus are stable as well
+        let mut hasher = StableHasher::default();
+        if let Some(node) = self.get_node(id) {
+            let node = node.borrow();
+            // the references to the other nodes are left out, as they are yet to be ordered
+            hasher.write(&remap_node_refs(&named_oprs[&id], |_| None).encode_to_vec());
+            if let Some(pb::logical_plan::operator::Opr::Apply(apply)) = &node.opr.opr {
+                let subtask = self.get_node_signature(apply.subtask as NodeId, named_oprs, signatures);
+                hasher.write(&subtask.to_le_bytes());
+            }
+            let mut children: Vec<u64> = node
+                .children
+                .iter()
+                .map(|child| self.get_node_signature(*child, named_oprs, signatures))
+                .collect();
+            children.sort_unstable();
+            for child in children {
+                hasher.write(&child.to_le_bytes());
             }
-            debug!("add column ({:?}) to HEAD", column);
-            node_meta.insert_column(column.clone().try_into()?);
         }
+        let signature = hasher.finish();
+        signatures.insert(id, signature);
+
+        signature
     }
 
-    Ok(())
-}
+    /// The names of the tags by their ids.
+    fn get_tag_names(&self) -> BTreeMap<TagId, String> {
+        self.meta
+            .get_tag_id_mappings()
+            .iter()
+            .map(|(name, tag_id)| (*tag_id, name.clone()))
+            .collect()
+    }
 
-fn get_or_set_tag_id(tag_pb: &mut common_pb::NameOrId, plan_meta: &mut PlanMeta) -> IrResult<TagId> {
-    use common_pb::name_or_id::Item;
-    if let Some(tag_item) = tag_pb.item.as_mut() {
-        let (_, tag_id) = match tag_item {
-            Item::Name(tag) => plan_meta.get_or_set_tag_id(tag),
-            Item::Id(id) => {
-                plan_meta.set_max_tag_id(*id as TagId + 1);
-                (true, *id as TagId)
-            }
-        };
-        *tag_pb = (tag_id as i32).into();
+    /// Get a operator reference from the logical plan
+    pub fn get_opr(&self, id: NodeId) -> Option<pb::logical_plan::Operator> {
+        self.nodes
+            .get(id as usize)
+            .map(|node| node.borrow().opr.clone())
+    }
 
-        Ok(tag_id)
-    } else {
-        Err(IrError::MissingData("NameOrId::Item".to_string()))
+    /// Get first node in the logical plan
+    pub fn get_first_node(&self) -> Option<NodeType> {
+        self.nodes
+            .iter()
+            .next()
+            .map(|tuple| tuple.1.clone())
     }
-}
 
-/// Process the columns' meta in `plan_meta` such that the columns can be added to
-/// corresponding nodes.
-fn process_columns_meta(plan_meta: &mut PlanMeta, is_late_project: bool) -> IrResult<()> {
-    let tag_columns = plan_meta
-        .get_curr_node_meta()
-        .unwrap()
-        .get_tag_columns();
-    // late project currently only handles the case of one single tag
-    if !is_late_project || tag_columns.len() > 1 {
-        for (tag, columns) in tag_columns.into_iter() {
-            let mut meta = plan_meta.tag_nodes_meta_mut(tag)?;
-            if columns.is_all() {
-                meta.set_columns_opt(ColumnsOpt::All(256));
-            } else if columns.len() > 0 {
-                for col in columns.get() {
-                    meta.insert_column(col);
-                }
-            }
-        }
-    } else {
-        // apply late project that does not record the columns in the corresponding nodes
+    /// Get last node in the logical plan
+    pub fn get_last_node(&self) -> Option<NodeType> {
+        self.nodes
+            .iter()
+            .rev()
+            .next()
+            .map(|tuple| tuple.1.clone())
     }
 
-    Ok(())
-}
+    /// The ids of the roots of the plan, i.e., the nodes without parents, other than those of the
+    /// subtasks, in ascending order.
+    pub fn get_roots(&self) -> Vec<NodeId> {
+        self.get_nodes_without(|node| &node.parents)
+    }
 
-impl AsLogical for pb::Project {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        use common_pb::expr_opr::Item;
-        // Notice some special cases of Project when project tag(s) only:
-        // 1. when project("@a"), it indicates to move the `Head` to the nodes referred by the tag `a`
-        // 2. when project("@a").as("b") (or, project multiple columns with new aliases).
-        //    In this case, when we want to access the property of "b" (assuming "a"/"b" refers to a vertex), we actually need to refer to the entries of "a".
-        //    e.g., we may want to Project the 0-th and 1-th columns on r1{v0, v1, v2}, and we get r2{v0, v1};
-        //    When we want to project properties on v0, we may need to refer to the source that generates v0, rather than the Project operator
+    /// The ids of the sinks of the plan, i.e., the nodes without children, other than those of the
+    /// subtasks, in ascending order, which the operators are appended to, e.g., while extending a plan
+    /// deserialized from a pb.
+    pub fn get_sinks(&self) -> Vec<NodeId> {
+        self.get_nodes_without(|node| &node.children)
+    }
 
-        let len = self.mappings.len();
-        for mapping in self.mappings.iter_mut() {
-            // the flag to indicate if the project is to project tags only, e.g., project("@a")
-            let mut is_project_tag_only = false;
-            let mut tag_nodes = vec![];
-            if let Some(expr) = &mut mapping.expr {
-                let mut is_project_as_head = false;
-                let curr_node = plan_meta.get_curr_node();
-                preprocess_expression(expr, meta, plan_meta, false)?;
-                if expr.operators.len() == 1 {
-                    if let common_pb::ExprOpr { item: Some(Item::Var(var)), .. } =
-                        expr.operators.get_mut(0).unwrap()
-                    {
-                        if let Some(tag) = var.tag.as_mut() {
-                            let tag_id = get_or_set_tag_id(tag, plan_meta)?;
-                            if var.property.is_none() {
-                                tag_nodes = plan_meta.get_tag_nodes(tag_id).to_vec();
-                                if !tag_nodes.is_empty() {
-                                    // the case of project to some tagged columns.
-                                    // if alias exists, e.g., `project("@a").as("b")`,
-                                    // when visiting "b", should actually visit the nodes referred by the tag `a`
-                                    // i.e., `rename` for the nodes from `a` to `b`
-                                    is_project_tag_only = true;
-                                    if len == 1 {
-                                        // furthermore, the case of `project("@a")`, must refer to the nodes referred by the tag,
-                                        plan_meta.refer_to_nodes(curr_node, tag_nodes.clone());
-                                        is_project_as_head = true;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                if !is_project_as_head {
-                    process_columns_meta(plan_meta, false)?;
-                    // projection alters the head of the record unless it is the case of project_as_head
-                    plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
-                }
-            }
-            if let Some(alias) = mapping.alias.as_mut() {
-                let alias_id = get_or_set_tag_id(alias, plan_meta)?;
-                if is_project_tag_only {
-                    // "rename" for the ori_tag_nodes
-                    plan_meta.set_tag_nodes(alias_id, tag_nodes);
-                } else {
-                    plan_meta.set_tag_nodes(alias_id, vec![plan_meta.get_curr_node()]);
-                }
+    /// The ids of the nodes, other than those of the subtasks, of which the given neighbors are empty.
+    fn get_nodes_without<F: Fn(&Node) -> &BTreeSet<NodeId>>(&self, neighbors: F) -> Vec<NodeId> {
+        let mut subtask_nodes = BTreeSet::new();
+        for (_, node) in self.nodes.iter() {
+            if let Some(subtask) = get_subtask(&node.borrow().opr) {
+                subtask_nodes
+                    .extend(self.collect_nodes(subtask, |node| node.children.iter().cloned().collect()));
             }
         }
-        Ok(())
+        self.nodes
+            .iter()
+            .map(|(id, node)| (id as NodeId, node))
+            .filter(|(id, node)| neighbors(&node.borrow()).is_empty() && !subtask_nodes.contains(id))
+            .map(|(id, _)| id)
+            .collect()
     }
-}
 
-impl AsLogical for pb::Select {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        if let Some(pred) = self.predicate.as_mut() {
-            // the columns will be added to the current node rather than tagged nodes
-            // thus, can lazy fetched the columns upon filtering
-            preprocess_expression(pred, meta, plan_meta, false)?;
-            process_columns_meta(plan_meta, true)?;
-            Ok(())
-        } else {
-            Err(IrError::MissingData("`pb::Select::predicate`".to_string()))
-        }
+    pub fn len(&self) -> usize {
+        self.nodes.len()
     }
-}
 
-impl AsLogical for pb::Scan {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        let curr_node = plan_meta.get_curr_node();
-        plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
-        if let Some(alias) = self.alias.as_mut() {
-            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
-        }
-        if let Some(params) = self.params.as_mut() {
-            if self.idx_predicate.is_none() {
-                if let Some(expr) = &params.predicate {
-                    let idx_pred = triplet_to_index_predicate(
-                        expr.operators.as_slice(),
-                        &params.tables,
-                        self.scan_opt != 1,
-                        meta,
-                    )?;
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
 
-                    if idx_pred.is_some() {
-                        params.predicate = None;
-                        self.idx_predicate = idx_pred;
-                    }
-                }
-            }
-            preprocess_params(params, meta, plan_meta)?;
-        }
-        if let Some(idx_pred) = self.idx_predicate.as_mut() {
-            idx_pred.preprocess(meta, plan_meta)?;
-        }
+    /// The ids of the nodes of the plan, in ascending order.
+    pub fn get_node_ids(&self) -> Vec<NodeId> {
+        self.nodes
+            .iter()
+            .map(|(id, _)| id as NodeId)
+            .collect()
+    }
 
-        process_columns_meta(plan_meta, false)?;
+    pub fn get_meta(&self) -> &PlanMeta {
+        &self.meta
+    }
 
-        Ok(())
+    pub fn get_max_node_id(&self) -> NodeId {
+        self.max_node_id
     }
-}
 
-impl AsLogical for pb::EdgeExpand {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        let curr_node = plan_meta.get_curr_node();
-        plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
-        if let Some(params) = self.params.as_mut() {
-            preprocess_params(params, meta, plan_meta)?;
-        }
-        if let Some(alias) = self.alias.as_mut() {
-            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
-        }
-        let expand_opt: pb::edge_expand::ExpandOpt = unsafe { ::std::mem::transmute(self.expand_opt) };
-        if expand_opt == pb::edge_expand::ExpandOpt::Vertex {
-            process_columns_meta(plan_meta, false)?;
-        }
+    /// Whether the output of the single parent is already ordered by the `order` without a limit,
+    /// namely the order is a prefix of the ordering of the parent's output.
+    fn is_ordered_by(&self, parent_ids: &[NodeId], order: &pb::OrderBy) -> bool {
+        order.limit.is_none()
+            && parent_ids.len() == 1
+            && self
+                .meta
+                .get_node_ordering(parent_ids[0])
+                .map(|ordering| ordering.starts_with(&order.pairs))
+                .unwrap_or(false)
+    }
 
-        Ok(())
+    /// Get the ordering of the output of the node of the given id, or `None` if its output is
+    /// in no order, i.e., no `OrderBy` precedes it with only order-preserving operators between.
+    pub fn get_ordering(&self, id: NodeId) -> Option<&[pb::order_by::OrderingPair]> {
+        self.meta.get_node_ordering(id)
     }
-}
 
-impl AsLogical for pb::PathExpand {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        let curr_node = plan_meta.get_curr_node();
-        plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
-        if let Some(base) = self.base.as_mut() {
-            if let Some(edge_expand) = base.edge_expand.as_mut() {
-                edge_expand.preprocess(meta, plan_meta)?;
-            }
-            if let Some(get_v) = base.edge_expand.as_mut() {
-                get_v.preprocess(meta, plan_meta)?;
-            }
+    /// Validate that the output of the node of the given id is ordered by the `order`, i.e., the
+    /// `order` is a prefix of its ordering (see [`LogicalPlan::get_ordering`]), or that it is in any
+    /// order if the `order` is empty. The keys of the `order` must refer to the tags by their ids as
+    /// the plan is preprocessed. Otherwise, the error names the operator that breaks the order, i.e.,
+    /// the first one upstream that does not preserve the order, or the `OrderBy` of another order.
+    pub fn require_ordering(&self, id: NodeId, order: &[pb::order_by::OrderingPair]) -> IrResult<()> {
+        if self.get_node(id).is_none() {
+            return Err(IrError::NodeNotExist(id));
         }
-        if let Some(pred) = self.condition.as_mut() {
-            preprocess_expression(pred, meta, plan_meta, false)?;
-            process_columns_meta(plan_meta, true)?;
+        if self
+            .get_ordering(id)
+            .map(|ordering| ordering.starts_with(order))
+            .unwrap_or(false)
+        {
+            return Ok(());
         }
-        if let Some(alias) = self.alias.as_mut() {
-            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        let tag_names = self.get_tag_names();
+        let required = if order.is_empty() {
+            "in order".to_string()
+        } else {
+            format!("ordered {}", explain_ordering(order, &tag_names))
+        };
+        let mut curr_id = id;
+        while let Some(node) = self.get_node(curr_id) {
+            let node = node.borrow();
+            if let Some(ordering) = self
+                .get_ordering(curr_id)
+                .filter(|_| matches!(node.opr.opr, Some(pb::logical_plan::operator::Opr::OrderBy(_))))
+            {
+                return Err(IrError::InvalidNode(
+                    id,
+                    format!(
+                        "the output is required to be {}, but is ordered {} by {} (#{})",
+                        required,
+                        explain_ordering(ordering, &tag_names),
+                        node.display_name(),
+                        curr_id
+                    ),
+                ));
+            } else if is_order_preserving(&node.opr) && node.parents.len() == 1 {
+                curr_id = *node.parents.iter().next().unwrap();
+            } else {
+                return Err(IrError::InvalidNode(
+                    id,
+                    format!(
+                        "the output is required to be {}, but the order is not kept by {} (#{})",
+                        required,
+                        node.display_name(),
+                        curr_id
+                    ),
+                ));
+            }
         }
-        // PathExpand would never require adding columns
-        plan_meta
-            .curr_node_meta_mut()
-            .set_columns_opt(ColumnsOpt::None);
 
-        Ok(())
+        Err(IrError::NodeNotExist(curr_id))
     }
-}
 
-impl AsLogical for pb::GetV {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        let curr_node = plan_meta.get_curr_node();
-        plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
-        if let Some(params) = self.params.as_mut() {
-            preprocess_params(params, meta, plan_meta)?;
+    /// Append a new node into the logical plan, with specified `parent_ids`
+    /// as its parent nodes. In order to do so, all specified parents must present in the
+    /// logical plan.
+    ///
+    /// # Return
+    ///   * If succeed, the id of the newly added node
+    ///   * `IrError::InvalidNode` if a node of the id exists, or the node is a parent of itself
+    ///   * Otherwise, `IrError::ParentNodeNotExist`
+    pub fn append_node(&mut self, mut node: Node, parent_ids: Vec<NodeId>) -> IrResult<NodeId> {
+        let id = node.id;
+        // a node replacing an existing one, or being a parent of itself, may make a cycle
+        if self.get_node(id).is_some() {
+            return Err(IrError::InvalidNode(id, "a node of the id already exists".to_string()));
         }
-        if let Some(alias) = self.alias.as_mut() {
-            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        if parent_ids.contains(&id) {
+            return Err(IrError::InvalidNode(id, "it is a parent of itself".to_string()));
         }
-
-        process_columns_meta(plan_meta, false)?;
-
-        Ok(())
-    }
-}
-
-impl AsLogical for pb::Dedup {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        for var in self.keys.iter_mut() {
-            preprocess_var(var, meta, plan_meta, false)?;
+        if !self.is_empty() && !parent_ids.is_empty() {
+            let mut parent_nodes = vec![];
+            for parent_id in parent_ids {
+                if let Some(parent_node) = self.get_node(parent_id) {
+                    parent_nodes.push(parent_node);
+                } else {
+                    return Err(IrError::ParentNodeNotExist(parent_id));
+                }
+            }
+            for parent_node in parent_nodes {
+                node.add_parent(parent_node.borrow().id);
+                parent_node.borrow_mut().add_child(id);
+            }
         }
-        process_columns_meta(plan_meta, false)?;
+        let node_rc = Arc::new(SyncCell::new(node));
+        self.nodes.insert(id as usize, node_rc.clone());
+        self.max_node_id = std::cmp::max(self.max_node_id, id) + 1;
 
-        Ok(())
+        Ok(id)
     }
-}
 
-impl AsLogical for pb::GroupBy {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        for mapping in self.mappings.iter_mut() {
-            if let Some(key) = &mut mapping.key {
-                preprocess_var(key, meta, plan_meta, false)?;
-                if let Some(alias) = mapping.alias.as_mut() {
-                    let key_alias_id = get_or_set_tag_id(alias, plan_meta)?;
-                    // the key must refer to some previous node that will be accessed later, e.g.
-                    // g.V().groupCount().select(keys).by('name')
-                    // In this case, if `key.property` is `None`, the `alias` must be able to refer
-                    // to a previous node to get the property; otherwise, the `alias` must have
-                    // referred to the current node
-                    if key.property.is_none() {
-                        let node_ids = if let Some(tag_pb) = key.tag.as_mut() {
-                            let tag_id = get_or_set_tag_id(tag_pb, plan_meta)?;
-                            plan_meta.get_tag_nodes(tag_id).to_vec()
-                        } else {
-                            plan_meta.get_curr_referred_nodes().to_vec()
-                        };
-                        if !node_ids.is_empty() {
-                            plan_meta.set_tag_nodes(key_alias_id, node_ids);
-                        }
-                    } else {
-                        plan_meta.set_tag_nodes(key_alias_id, vec![plan_meta.get_curr_node()]);
-                    }
-                }
-            }
+    /// Append an existing logical plan to the logical plan, with the specified
+    /// parent node's id. Note that we currently only allow appending a logical
+    /// plan to one single parent node.
+    pub fn append_plan(&mut self, plan: pb::LogicalPlan, parent_ids: Vec<NodeId>) -> IrResult<NodeId> {
+        if parent_ids.len() != 1 {
+            return Err(IrError::Unsupported(
+                "only support appending plan for one single parent!".to_string(),
+            ));
         }
-        for agg_fn in self.functions.iter_mut() {
-            for var in agg_fn.vars.iter_mut() {
-                preprocess_var(var, meta, plan_meta, false)?;
+        let mut id_map: HashMap<NodeId, NodeId> = HashMap::new();
+        let mut parents: HashMap<NodeId, BTreeSet<NodeId>> = HashMap::new();
+        let mut result_id = 0;
+        for (id, node) in plan.nodes.iter().enumerate() {
+            for child in &node.children {
+                parents
+                    .entry(*child as NodeId)
+                    .or_insert_with(BTreeSet::new)
+                    .insert(id as NodeId);
             }
-            if let Some(alias) = agg_fn.alias.as_mut() {
-                let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-                plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        }
+        for (id, node) in plan.nodes.into_iter().enumerate() {
+            if let Some(opr) = node.opr {
+                let new_parents = if !parents.contains_key(&(id as NodeId)) {
+                    parent_ids.clone()
+                } else {
+                    parents
+                        .get(&(id as NodeId))
+                        .cloned()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|old| {
+                            id_map
+                                .get(&old)
+                                .cloned()
+                                .ok_or(IrError::ParentNodeNotExist(old))
+                        })
+                        .collect::<IrResult<Vec<NodeId>>>()?
+                };
+                let new_id = self.append_operator(opr, new_parents)?;
+                id_map.insert(id as NodeId, new_id);
+                result_id = new_id;
+            } else {
+                return Err(IrError::MissingData("Node::opr".to_string()));
             }
         }
 
-        process_columns_meta(plan_meta, false)?;
-
-        Ok(())
+        Ok(result_id)
     }
-}
 
-impl AsLogical for pb::IndexPredicate {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        for and_pred in self.or_predicates.iter_mut() {
-            for pred in and_pred.predicates.iter_mut() {
-                if let Some(pred_key) = &mut pred.key {
-                    if let Some(key_item) = pred_key.item.as_mut() {
-                        match key_item {
-                            common_pb::property::Item::Key(key) => {
-                                if let Some(schema) = &meta.schema {
-                                    if schema.is_column_id() {
-                                        let new_key = get_column_id_from_pb(schema, key)
-                                            .unwrap_or(INVALID_META_ID)
-                                            .into();
-                                        debug!("column: {:?} -> {:?}", key, new_key);
-                                        *key = new_key;
-                                    }
-                                }
-                            }
-                            common_pb::property::Item::Label(_) => {
-                                if let Some(val) = pred.value.as_mut() {
-                                    preprocess_label(val, meta, plan_meta)?;
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
+    /// Graft all the nodes of another logical plan onto the plan, under the nodes of `parent_ids`, e.g.,
+    /// to compose the fragments of a query that are built by different passes of the frontends, rather
+    /// than appending their operators once again. Unlike [`LogicalPlan::append_plan`], the operators are
+    /// appended in a topological order of `other` (see [`LogicalPlan::canonical_order`]), where their
+    /// references to the other nodes, e.g., the parents of a `Union` and the subtask of an `Apply`, are
+    /// remapped to the new ids, and the tags are carried by their names, such that a tag of `other`
+    /// refers to the tag of the same name in the plan. The roots of `other`, except the subtasks, take
+    /// `parent_ids` as their parents, or become roots if `parent_ids` is empty.
+    ///
+    /// # Return
+    ///   * the new ids of the roots and of the leaves of `other` if succeed
+    ///   * an error if failing to append any operator of `other`, which leaves the plan unchanged
+    pub fn append_subplan(
+        &mut self, other: &LogicalPlan, parent_ids: Vec<NodeId>,
+    ) -> IrResult<(Vec<NodeId>, Vec<NodeId>)> {
+        if self.is_frozen {
+            return Err(IrError::PlanFrozen);
+        }
+        if let Some(parent_id) = parent_ids
+            .iter()
+            .find(|id| self.get_node(**id).is_none())
+        {
+            return Err(IrError::ParentNodeNotExist(*parent_id));
+        }
+        let mut subtask_nodes = BTreeSet::new();
+        for (_, node) in other.nodes.iter() {
+            if let Some(subtask) = get_subtask(&node.borrow().opr) {
+                subtask_nodes
+                    .extend(other.collect_nodes(subtask, |node| node.children.iter().cloned().collect()));
+            }
+        }
+        let tag_names = other.get_tag_names();
+        let max_node_id = self.max_node_id;
+        // the operators are appended to a copy of the plan, which replaces the plan only if all of
+        // them are appended
+        let mut plan = self.clone_plan();
+        let mut id_map: HashMap<NodeId, NodeId> = HashMap::new();
+        let mut roots = vec![];
+        let mut leaves = vec![];
+        for id in other.canonical_order() {
+            let node = other.nodes[id as usize].borrow();
+            let opr = name_tags(&remap_node_refs(&node.opr, |id| id_map.get(&id).cloned()), &tag_names);
+            let new_parents = if !node.parents.is_empty() {
+                node.parents
+                    .iter()
+                    .map(|parent| id_map[parent])
+                    .collect()
+            } else if subtask_nodes.contains(&id) {
+                vec![]
+            } else {
+                parent_ids.clone()
+            };
+            let next_id = plan.max_node_id;
+            let new_id = plan.append_operator(opr, new_parents)?;
+            if new_id >= next_id {
+                // the operator is appended as a new node, rather than merged into an existing one
+                for annotation in &node.annotations {
+                    plan.annotate_node(new_id, annotation.clone())?;
+                }
+                for (key, value) in &node.attributes {
+                    plan.set_node_attribute(new_id, key.clone(), value.clone())?;
+                }
+                if let Some(display_name) = &node.display_name {
+                    plan.set_display_name(new_id, display_name.clone())?;
+                }
+            }
+            id_map.insert(id, new_id);
+            if !subtask_nodes.contains(&id) {
+                if node.parents.is_empty() && !roots.contains(&new_id) {
+                    roots.push(new_id);
+                }
+                if node.children.is_empty() && !leaves.contains(&new_id) {
+                    leaves.push(new_id);
                 }
             }
         }
+        self.replace_with_copy(plan);
+        self.write_through_nodes(max_node_id);
 
-        Ok(())
+        Ok((roots, leaves))
     }
-}
 
-impl AsLogical for pb::OrderBy {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        for pair in self.pairs.iter_mut() {
-            if let Some(key) = &mut pair.key {
-                preprocess_var(key, meta, plan_meta, false)?;
-            }
+    /// Append an operator into the logical plan, as a new node with `self.max_node_id` as its id.
+    pub fn append_operator_as_node(
+        &mut self, opr: pb::logical_plan::Operator, parent_ids: Vec<NodeId>,
+    ) -> IrResult<NodeId> {
+        if self.is_frozen {
+            return Err(IrError::PlanFrozen);
         }
-        process_columns_meta(plan_meta, false)?;
+        let max_node_id = self.max_node_id;
+        let result = self.append_operator(opr, parent_ids);
+        // the nodes injected before a failure, if any, remain in the plan, and thus are written as well
+        self.write_through_nodes(max_node_id);
 
-        Ok(())
+        result
     }
-}
 
-impl AsLogical for pb::Limit {
-    fn preprocess(&mut self, _meta: &StoreMeta, _plan_meta: &mut PlanMeta) -> IrResult<()> {
-        Ok(())
+    /// Write the changes of the plan through to the `sink` as the operators are appended via
+    /// [`LogicalPlan::append_operator_as_node`], each as a [`pb::PlanAppend`] of the nodes appended
+    /// and removed by an operator, such that the caller can rebuild the plan by applying the changes in
+    /// order, without materializing the whole plan as a pb. The existing nodes, if any, are written as a
+    /// change at once, and the write-through mode is off if `sink` is `None`.
+    ///
+    /// Note that the ids of the nodes are those in the plan, namely, the removed nodes are not
+    /// compacted, and the changes of rewriting the plan, e.g., [`LogicalPlan::canonicalize_node_ids`],
+    /// are not written through.
+    pub fn set_write_through(&mut self, sink: Option<Box<dyn FnMut(&pb::PlanAppend) + Send>>) {
+        self.write_through = WriteThrough { sink, removed: vec![] };
+        self.write_through_nodes(0);
     }
-}
 
-impl AsLogical for pb::As {
-    fn preprocess(&mut self, _meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        if let Some(alias) = self.alias.as_mut() {
-            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-            plan_meta.set_tag_nodes(tag_id, plan_meta.get_curr_referred_nodes().to_vec());
+    /// Write the nodes from the given id on, and the nodes removed before them, to the sink, if any.
+    fn write_through_nodes(&mut self, from_id: NodeId) {
+        let removed = std::mem::take(&mut self.write_through.removed);
+        if self.write_through.sink.is_none() {
+            return;
+        }
+        let tag_names = if self.meta.is_trace_access() { Some(self.get_tag_names()) } else { None };
+        let nodes: Vec<pb::plan_append::AppendedNode> = (from_id..self.max_node_id)
+            .filter_map(|id| self.get_node(id))
+            .map(|node| {
+                let node = node.borrow();
+                pb::plan_append::AppendedNode {
+                    id: node.id as PbNodeId,
+                    parents: node
+                        .parents
+                        .iter()
+                        .map(|id| *id as PbNodeId)
+                        .collect(),
+                    node: Some(pb::logical_plan::Node {
+                        opr: Some(node.opr.clone()),
+                        children: vec![],
+                        annotations: node.annotations.clone(),
+                        display_name: node.display_name.clone().unwrap_or_default(),
+                        accesses: tag_names
+                            .as_ref()
+                            .and_then(|tag_names| get_opr_accesses(&node.opr, tag_names))
+                            .unwrap_or_default(),
+                        attributes: node
+                            .attributes
+                            .iter()
+                            .map(|(key, value)| (key.clone(), value.clone()))
+                            .collect(),
+                    }),
+                }
+            })
+            .collect();
+        let removed: Vec<PbNodeId> = removed
+            .into_iter()
+            .filter(|id| *id < from_id)
+            .map(|id| id as PbNodeId)
+            .collect();
+        if !nodes.is_empty() || !removed.is_empty() {
+            if let Some(sink) = self.write_through.sink.as_mut() {
+                sink(&pb::PlanAppend { nodes, removed });
+            }
         }
-        Ok(())
     }
-}
 
-impl AsLogical for pb::Join {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        for left_key in self.left_keys.iter_mut() {
-            preprocess_var(left_key, meta, plan_meta, false)?
+    fn append_operator(
+        &mut self, mut opr: pb::logical_plan::Operator, parent_ids: Vec<NodeId>,
+    ) -> IrResult<NodeId> {
+        use pb::logical_plan::operator::Opr;
+
+        if opr.opr.is_none() {
+            return Err(IrError::MissingData("Operator::opr".to_string()));
         }
-        for right_key in self.right_keys.iter_mut() {
-            preprocess_var(right_key, meta, plan_meta, false)?
+        if let Some(view) = self.view.as_ref() {
+            self.meta
+                .with_store_meta(|store_meta| constrain_by_view(&mut opr, view, store_meta.schema.as_ref()))
+                .unwrap_or(Ok(()))?;
         }
+        if self.meta.is_inline_union_scans() && matches!(opr.opr, Some(Opr::Union(_))) {
+            if let Some(node_id) = self.inline_union_scans(&parent_ids)? {
+                return Ok(node_id);
+            }
+        }
+        let parent_ids = if let Some(Opr::Count(count)) = &opr.opr {
+            self.strip_fetches_before_count(count, parent_ids)
+        } else {
+            parent_ids
+        };
+        if self.meta.is_push_down_aggregation()
+            && matches!(opr.opr, Some(Opr::GroupBy(_)) | Some(Opr::Count(_)))
+            && self.meta.admit_expensive_optimization()
+        {
+            let start = Instant::now();
+            let node_id = self.push_down_aggregation(&opr, &parent_ids);
+            self.meta
+                .charge_optimization_time(start.elapsed());
+            if let Some(node_id) = node_id? {
+                return Ok(node_id);
+            }
+        }
+        let parent_ids = if let Some(Opr::Union(union)) = opr.opr.as_mut() {
+            self.align_union_branches(union, parent_ids)?
+        } else {
+            parent_ids
+        };
+        let old_curr_node = self.meta.get_curr_node();
+        // Set new current node as `self.max_node_id`
+        let new_curr_node = self.max_node_id;
+        self.meta.set_curr_node(new_curr_node);
+        // Configure `NodeMeta` for current node
+        let _ = self.meta.curr_node_meta_mut();
+        // By default, refer to the nodes that the the parent nodes refer to
+        // Certain operators will modify the referred nodes during preprocessing, including
+        // Scan, EdgeExpand, PathExpand, GetV, Apply and Project
+        let ref_parent_nodes = self.meta.get_referred_nodes(&parent_ids);
+        self.meta
+            .refer_to_nodes(new_curr_node, ref_parent_nodes);
 
-        process_columns_meta(plan_meta, false)?;
-
-        Ok(())
-    }
-}
-
-impl AsLogical for pb::Sink {
-    fn preprocess(&mut self, _meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        for tag_key in self.tags.iter_mut() {
-            if let Some(tag) = tag_key.key.as_mut() {
-                get_or_set_tag_id(tag, plan_meta)?;
+        // the store meta of the session, if any, is shared rather than borrowed from the plan meta
+        if let Some(store_meta) = self.meta.get_store_meta() {
+            opr.preprocess(&store_meta, &mut self.meta)?;
+        } else if let Ok(store_meta) = STORE_META.read() {
+            opr.preprocess(&store_meta, &mut self.meta)?;
+        }
+        let ordering = match opr.opr.as_ref().unwrap() {
+            Opr::OrderBy(order) => Some(order.pairs.clone()),
+            _ if is_order_preserving(&opr) && parent_ids.len() == 1 => self
+                .meta
+                .get_node_ordering(parent_ids[0])
+                .map(|ordering| ordering.to_vec()),
+            _ => None,
+        };
+        let tags = get_output_tags(&opr, &parent_ids, &self.meta);
+        let new_curr_node_rst = match opr.opr.as_ref().unwrap() {
+            Opr::OrderBy(order) if self.is_ordered_by(&parent_ids, order) => {
+                // The input is already in the order, and thus the `OrderBy` is elided
+                debug!("elide the redundant order {:?}", order);
+                self.meta.set_curr_node(parent_ids[0]);
+                Ok(parent_ids[0])
+            }
+            Opr::Pattern(pattern) => {
+                if parent_ids.len() == 1 {
+                    // We try to match via ExtendStrategy. If not supported, match via NaiveStrategy.
+
+                    // Notice that there are some limitations of ExtendStrategy, including:
+                    // 1. the input of match should be a whole graph, i.e., `g.V().match(...)`
+                    // 2. the match sentences are connected by join_kind of `InnerJoin`
+                    // 3. the match binders should expand vertices if it is EdgeExpand opr
+                    // 4. if match binders of PathExpand exists, it should at least range from 1
+                    let is_pattern_source_whole_graph = self
+                        .get_opr(parent_ids[0])
+                        .map(|pattern_source| pattern_source.is_whole_graph())
+                        .ok_or(IrError::ParentNodeNotExist(parent_ids[0]))?;
+                    // 5. the optimization budget of the plan, if any, is not used up, as ordering the
+                    // extensions is expensive for large patterns
+                    let start = Instant::now();
+                    let extend_strategy = if !is_pattern_source_whole_graph {
+                        Err(IrPatternError::Unsupported("pattern source is not whole graph".to_string()))
+                    } else if !self.meta.admit_expensive_optimization() {
+                        Err(IrPatternError::Unsupported("optimization budget is used up".to_string()))
+                    } else {
+                        ExtendStrategy::init(&pattern, &self.meta)
+                    };
+                    match extend_strategy {
+                        Ok(extend_strategy) => {
+                            debug!("pattern matching by ExtendStrategy");
+                            let plan = extend_strategy.build_logical_plan();
+                            self.meta
+                                .charge_optimization_time(start.elapsed());
+                            let plan = plan?;
+                            let new_node_id = self.append_plan(plan, parent_ids.clone())?;
+                            // As we have added a new source op to scan with label efficiently in extend_strategy,
+                            // we remove the old source op.
+                            self.nodes.remove(0);
+                            self.write_through.removed.push(0);
+                            Ok(new_node_id)
+                        }
+                        Err(err) => match err {
+                            IrPatternError::Unsupported(_) => {
+                                // if is not supported in ExtendStrategy, try NaiveStrategy
+                                debug!("pattern matching by NaiveStrategy");
+                                let naive_strategy = NaiveStrategy::try_from(pattern.clone())?;
+                                let plan = naive_strategy.build_logical_plan()?;
+                                self.append_plan(plan, parent_ids.clone())
+                            }
+                            _ => Err(err.into()),
+                        },
+                    }
+                } else {
+                    Err(IrError::Unsupported(
+                        "only one single parent is supported for the `Pattern` operator".to_string(),
+                    ))
+                }
+            }
+            Opr::Join(join) => match check_join_renames(join, &parent_ids, &self.meta) {
+                Ok(()) => self.append_node(Node::new(new_curr_node, opr), parent_ids.clone()),
+                Err(err) => Err(err),
+            },
+            Opr::Apply(apply) => match self.check_apply_captures(apply, &parent_ids) {
+                Ok(()) => self.append_node(Node::new(new_curr_node, opr), parent_ids.clone()),
+                Err(err) => Err(err),
+            },
+            Opr::Sink(pb::Sink {
+                sink_target:
+                    Some(pb::sink::SinkTarget {
+                        inner: Some(pb::sink::sink_target::Inner::SinkBulkLoad(bulk_load)),
+                    }),
+                ..
+            }) => match self.check_bulk_load_source(bulk_load, &parent_ids) {
+                Ok(()) => self.append_node(Node::new(new_curr_node, opr), parent_ids.clone()),
+                Err(err) => Err(err),
+            },
+            _ => self.append_node(Node::new(new_curr_node, opr), parent_ids.clone()),
+        };
+
+        // As in this case, the current id will not refer to any actual nodes, it is fine to
+        // keep its referred nodes.
+        let annotations = self.meta.take_node_annotations(new_curr_node);
+        if new_curr_node_rst.is_err() {
+            self.meta.set_curr_node(old_curr_node);
+        } else if matches!(new_curr_node_rst, Ok(id) if id == new_curr_node) {
+            if let Some(ordering) = ordering {
+                self.meta
+                    .set_node_ordering(new_curr_node, ordering);
+            }
+            self.meta.set_node_tags(new_curr_node, tags);
+            for annotation in annotations {
+                self.annotate_node(new_curr_node, annotation)?;
             }
         }
-        Ok(())
+
+        new_curr_node_rst
     }
-}
 
-impl AsLogical for pb::Apply {
-    fn preprocess(&mut self, _meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        let curr_node = plan_meta.get_curr_node();
-        if let Some(alias) = self.alias.as_mut() {
-            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
-        } else {
-            if self.join_kind != 4 && self.join_kind != 5 {
-                // if not semi_join, not anti_join or the alias has not been set
-                plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
+    /// Check that the input of the bulk load, i.e., the `parent_ids`, reads the rows from a scan with
+    /// a connector to the source of the bulk load, and the mapped columns are present in the source.
+    fn check_bulk_load_source(&self, bulk_load: &pb::SinkBulkLoad, parent_ids: &[NodeId]) -> IrResult<()> {
+        let connector = parent_ids
+            .iter()
+            .flat_map(|id| self.collect_nodes(*id, |node| node.parents.iter().cloned().collect()))
+            .filter_map(|id| match &self.get_node(id)?.borrow().opr.opr {
+                Some(pb::logical_plan::operator::Opr::Scan(pb::Scan {
+                    connector: Some(connector),
+                    ..
+                })) if connector.uri == bulk_load.uri => Some(connector.clone()),
+                _ => None,
+            })
+            .next()
+            .ok_or_else(|| {
+                IrError::Unsupported(format!("bulk load from {:?} without scanning it", bulk_load.uri))
+            })?;
+        let column_mappings: Vec<&pb::sink_bulk_load::ColumnMapping> = match &bulk_load.mapping {
+            Some(pb::sink_bulk_load::Mapping::Vertex(vertex)) => vertex.column_mappings.iter().collect(),
+            Some(pb::sink_bulk_load::Mapping::Edge(edge)) => edge
+                .src
+                .iter()
+                .chain(edge.dst.iter())
+                .flat_map(|vertex| vertex.column_mappings.iter())
+                .chain(edge.column_mappings.iter())
+                .collect(),
+            None => vec![],
+        };
+        for mapping in column_mappings {
+            let column: NameOrId = mapping
+                .column
+                .clone()
+                .ok_or(IrError::MissingData("SinkBulkLoad::ColumnMapping::column".to_string()))?
+                .try_into()?;
+            let is_present = match &column {
+                NameOrId::Str(name) => connector
+                    .columns
+                    .iter()
+                    .any(|col| &col.name == name),
+                NameOrId::Id(id) => (*id as usize) < connector.columns.len(),
+            };
+            if !is_present {
+                return Err(IrError::ColumnNotExist(column));
             }
         }
+
         Ok(())
     }
-}
 
-impl AsLogical for pb::Pattern {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        for sentence in self.sentences.iter_mut() {
-            if let Some(alias) = sentence.start.as_mut() {
-                let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-                if plan_meta.has_tag(tag_id) {
-                    warn!("`pb::Pattern` reference existing tag: {:?}", alias);
-                }
-            } else {
-                return Err(IrError::InvalidPattern(
-                    "the start tag in `pb::Pattern` does not exist".to_string(),
-                ));
-            }
-            if let Some(alias) = sentence.end.as_mut() {
-                let tag_id = get_or_set_tag_id(alias, plan_meta)?;
-                if plan_meta.has_tag(tag_id) {
-                    warn!("`pb::Pattern` reference existing tag: {:?}", alias);
+    /// Check the captures of the apply, if any, namely, that each captured tag presents in the input
+    /// of the apply, i.e., the `parent_ids`, and the subtask refers to no tag other than the captured
+    /// ones and those defined within the subtask.
+    fn check_apply_captures(&self, apply: &pb::Apply, parent_ids: &[NodeId]) -> IrResult<()> {
+        if apply.captures.is_empty() {
+            return Ok(());
+        }
+        let input_tags: BTreeSet<TagId> = parent_ids
+            .iter()
+            .flat_map(|id| self.meta.get_node_tags(*id))
+            .collect();
+        let mut visible_tags = BTreeSet::new();
+        for capture in &apply.captures {
+            if let Some(tag) = get_tag(Some(capture)) {
+                // The tags of the input may not be tracked, e.g., if it is built from a pattern
+                if !input_tags.is_empty() && !input_tags.contains(&tag) {
+                    return Err(IrError::TagNotExist((tag as KeyId).into()));
                 }
+                visible_tags.insert(tag);
             }
-            for binder_opt in &mut sentence.binders {
-                if let Some(binder) = binder_opt.item.as_mut() {
-                    match binder {
-                        Item::Edge(edge) => edge.preprocess(meta, plan_meta)?,
-                        Item::Path(path) => path.preprocess(meta, plan_meta)?,
-                        Item::Vertex(vertex) => vertex.preprocess(meta, plan_meta)?,
-                        Item::Select(pred) => pred.preprocess(meta, plan_meta)?,
-                    }
-                }
+        }
+        let subtask_nodes =
+            self.collect_nodes(apply.subtask as NodeId, |node| node.children.iter().cloned().collect());
+        for id in &subtask_nodes {
+            let node = self.get_node(*id).unwrap();
+            visible_tags.extend(
+                get_aliases(&node.borrow().opr)
+                    .into_iter()
+                    .filter_map(|alias| get_tag_id(alias, &self.meta)),
+            );
+        }
+        for id in subtask_nodes {
+            let node = self.get_node(id).unwrap();
+            let referred_tags = get_referred_tags(&node.borrow().opr, &self.meta).unwrap_or_default();
+            if let Some(tag) = referred_tags.difference(&visible_tags).next() {
+                return Err(IrError::NodeError(
+                    format!("node {} of the subtask refers to a tag not captured by the apply", id),
+                    Box::new(IrError::TagNotExist((*tag as KeyId).into())),
+                ));
             }
         }
 
         Ok(())
     }
-}
 
-impl AsLogical for pb::logical_plan::Operator {
-    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+    /// Inline the branches of a union, i.e., the `parent_ids`, into a single `Scan`, if each branch is a
+    /// `Scan` alone from the same parents, and the scans differ only by their tables (labels), or only by
+    /// the constants of their index predicates, e.g., scanning the label `A` union scanning the label `B`
+    /// is inlined as scanning the labels `[A, B]`. As the union keeps the duplicates, the tables, or the
+    /// constants, of the scans must be disjoint, and none of the scans is limited, as the limit applies
+    /// to each branch rather than to the union. The scans are removed, and the inlined `Scan` is appended
+    /// in place of the union.
+    ///
+    /// # Return
+    ///   * the id of the inlined `Scan` if the branches are inlined
+    ///   * `None` if not applicable, in which case the plan is left unchanged
+    fn inline_union_scans(&mut self, parent_ids: &[NodeId]) -> IrResult<Option<NodeId>> {
         use pb::logical_plan::operator::Opr;
-        if let Some(opr) = self.opr.as_mut() {
-            match opr {
-                Opr::Project(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Select(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Scan(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Edge(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Path(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Vertex(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Dedup(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::GroupBy(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::OrderBy(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Limit(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::As(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Join(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Sink(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Apply(opr) => opr.preprocess(meta, plan_meta)?,
-                Opr::Pattern(opr) => opr.preprocess(meta, plan_meta)?,
-                _ => {}
+
+        if parent_ids.len() < 2 {
+            return Ok(None);
+        }
+        let mut scans = Vec::with_capacity(parent_ids.len());
+        let mut scan_parents = None;
+        for id in parent_ids {
+            let node = match self.get_node(*id) {
+                Some(node) if node.borrow().children.is_empty() => node,
+                _ => return Ok(None),
+            };
+            let node = node.borrow();
+            match &node.opr.opr {
+                Some(Opr::Scan(pb::Scan { params: Some(params), .. })) if params.limit.is_some() => {
+                    return Ok(None)
+                }
+                Some(Opr::Scan(scan)) if scan.params.is_some() => scans.push(scan.clone()),
+                _ => return Ok(None),
+            }
+            match &scan_parents {
+                Some(parents) if parents != &node.parents => return Ok(None),
+                Some(_) => {}
+                None => scan_parents = Some(node.parents.clone()),
             }
         }
-        Ok(())
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use ir_common::expr_parse::str_to_expr_pb;
-    use ir_common::generated::algebra::logical_plan::operator::Opr;
-    use ir_common::generated::common::property::Item;
+        let (mut tables, mut and_predicates) = (vec![], vec![]);
+        for scan in &scans {
+            let params = scan.params.as_ref().unwrap();
+            tables.push(params.tables.clone());
+            and_predicates.extend(
+                scan.idx_predicate
+                    .iter()
+                    .flat_map(|pred| pred.or_predicates.iter().cloned()),
+            );
+        }
+        let strip = |scan: &pb::Scan| {
+            let mut scan = scan.clone();
+            scan.params.as_mut().unwrap().tables.clear();
+            scan.idx_predicate = None;
+            scan
+        };
+        let first = strip(&scans[0]);
+        if scans
+            .iter()
+            .skip(1)
+            .any(|scan| strip(scan) != first)
+        {
+            return Ok(None);
+        }
 
-    use super::*;
-    use crate::plan::meta::Schema;
-    use crate::JsonIO;
+        let mut inlined = scans[0].clone();
+        if scans
+            .iter()
+            .all(|scan| scan.idx_predicate == scans[0].idx_predicate)
+        {
+            // differ by the tables, which must be given, and disjoint
+            if tables.iter().any(|tables| tables.is_empty()) {
+                return Ok(None);
+            }
+            let mut all_tables = vec![];
+            for table in tables.into_iter().flatten() {
+                if all_tables.contains(&table) {
+                    return Ok(None);
+                }
+                all_tables.push(table);
+            }
+            inlined.params.as_mut().unwrap().tables = all_tables;
+        } else if tables.iter().all(|table| table == &tables[0]) {
+            // differ by the constants, where each conjunction must compare the same keys to the
+            // constants, and the constants of any two conjunctions differ
+            if scans
+                .iter()
+                .any(|scan| scan.idx_predicate.is_none())
+            {
+                return Ok(None);
+            }
+            let keys_values = |and_pred: &pb::index_predicate::AndPredicate| {
+                let mut keys = Vec::with_capacity(and_pred.predicates.len());
+                let mut values = Vec::with_capacity(and_pred.predicates.len());
+                for triplet in &and_pred.predicates {
+                    if triplet.param.is_some() || triplet.value.is_none() {
+                        return None;
+                    }
+                    keys.push(triplet.key.clone());
+                    values.push(triplet.value.clone());
+                }
+                Some((keys, values))
+            };
+            let mut all_keys = None;
+            let mut all_values = vec![];
+            for and_pred in &and_predicates {
+                let (keys, values) = match keys_values(and_pred) {
+                    Some(keys_values) => keys_values,
+                    None => return Ok(None),
+                };
+                match &all_keys {
+                    Some(all_keys) if all_keys != &keys => return Ok(None),
+                    Some(_) => {}
+                    None => all_keys = Some(keys),
+                }
+                if all_values.contains(&values) {
+                    return Ok(None);
+                }
+                all_values.push(values);
+            }
+            inlined.idx_predicate = Some(pb::IndexPredicate { or_predicates: and_predicates });
+        } else {
+            return Ok(None);
+        }
 
-    #[allow(dead_code)]
-    fn query_params(
-        tables: Vec<common_pb::NameOrId>, columns: Vec<common_pb::NameOrId>,
-    ) -> pb::QueryParams {
-        pb::QueryParams {
-            tables,
-            columns,
-            is_all_columns: false,
-            limit: None,
-            predicate: None,
-            sample_ratio: 1.0,
-            extra: HashMap::new(),
+        for id in parent_ids {
+            self.remove_node(*id);
+            self.write_through.removed.push(*id);
         }
+        self.meta.annotate_node(
+            self.max_node_id,
+            format!("union: inline the scans {:?} as one scan", parent_ids),
+        );
+        let parents = scan_parents
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        self.append_operator(inlined.into(), parents)
+            .map(Some)
     }
 
-    #[test]
-    fn logical_plan_construct() {
-        let opr = pb::logical_plan::Operator {
-            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
+    /// Align the tags produced by the branches of the union, i.e., the `parent_ids`, according to
+    /// its alignment. If a `Project` is injected at the end of each branch, the union's parents are
+    /// replaced by the injected nodes.
+    ///
+    /// # Return
+    ///   * If succeed, the parents of the union after the alignment
+    ///   * Otherwise, `IrError::UnalignedUnion` if the tags are strictly aligned but differ
+    fn align_union_branches(
+        &mut self, union: &mut pb::Union, parent_ids: Vec<NodeId>,
+    ) -> IrResult<Vec<NodeId>> {
+        use pb::union::TagAlignment;
+
+        let alignment = TagAlignment::from_i32(union.alignment)
+            .ok_or(ParsePbError::from(format!("invalid alignment of `Union`: {:?}", union.alignment)))?;
+        if alignment == TagAlignment::None {
+            return Ok(parent_ids);
+        }
+        let branch_tags: Vec<BTreeSet<TagId>> = parent_ids
+            .iter()
+            .map(|id| self.meta.get_node_tags(*id))
+            .collect();
+        if branch_tags
+            .windows(2)
+            .all(|pair| pair[0] == pair[1])
+        {
+            return Ok(parent_ids);
+        }
+        if alignment == TagAlignment::Strict {
+            return Err(IrError::UnalignedUnion(format!(
+                "the branches ending at {:?} produce the tags {:?}",
+                parent_ids, branch_tags
+            )));
+        }
+        // Project each branch onto all the tags, plus the head as a hidden tag, which also guarantees
+        // more than one column to project, such that a tag absent from the branch is kept as `None`
+        let (_, head_tag) = self
+            .meta
+            .get_or_set_tag_id(&format!("~union_head_{:?}", self.max_node_id));
+        let as_expr = |tag: Option<TagId>| common_pb::Expression {
+            operators: vec![common_pb::Variable {
+                tag: tag.map(|tag| (tag as i32).into()),
+                property: None,
+                node_type: None,
+            }
+            .into()],
         };
-        let mut plan = LogicalPlan::default();
-
-        let id = plan
-            .append_operator_as_node(opr.clone(), vec![])
-            .unwrap();
-        assert_eq!(id, 0);
-        assert_eq!(plan.len(), 1);
-        assert_eq!(plan.max_node_id, 1);
-        let node0 = plan.get_node(0).unwrap().clone();
+        let mappings = branch_tags
+            .into_iter()
+            .flatten()
+            .collect::<BTreeSet<TagId>>()
+            .into_iter()
+            .map(|tag| pb::project::ExprAlias {
+                expr: Some(as_expr(Some(tag))),
+                alias: Some((tag as i32).into()),
+            })
+            .chain(std::iter::once(pb::project::ExprAlias {
+                expr: Some(as_expr(None)),
+                alias: Some((head_tag as i32).into()),
+            }))
+            .collect();
+        let project = pb::Project { mappings, is_append: false, meta_data: vec![] };
+        let mut aligned_ids = Vec::with_capacity(parent_ids.len());
+        for parent_id in parent_ids {
+            aligned_ids.push(self.append_operator(project.clone().into(), vec![parent_id])?);
+        }
+        union.parents = aligned_ids
+            .iter()
+            .map(|id| *id as PbNodeId)
+            .collect();
 
-        let id = plan
-            .append_operator_as_node(opr.clone(), vec![0])
-            .unwrap();
-        assert_eq!(id, 1);
-        assert_eq!(plan.len(), 2);
-        assert_eq!(plan.max_node_id, 2);
-        let node1 = plan.get_node(1).unwrap().clone();
+        Ok(aligned_ids)
+    }
 
-        let parents = node1
-            .borrow()
-            .parents
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(parents, vec![0]);
+    /// Push an aggregation of counting and summing, i.e., a `Count` or a `GroupBy` of which the functions
+    /// are all `Count` and `Sum`, down below the inner join that it follows, if it is grouped by the join
+    /// keys, and each of its variables is from either side of the join. It is rewritten as:
+    ///   * a partial `GroupBy` at the end of each side, which groups by the join keys of the side, counts
+    ///     the records of the side, and aggregates the variables from the side;
+    ///   * a `Join` of the partial aggregations on the join keys;
+    ///   * a `Project` that multiplies the partial aggregations of a side by the count of the other side;
+    ///   * a `GroupBy` that sums the products, grouped by the (join) keys of the aggregation,
+    /// such that the records are aggregated before rather than after being joined.
+    ///
+    /// # Return
+    ///   * the id of the final `GroupBy` if the aggregation is pushed down
+    ///   * `None` if not applicable, in which case the plan is left unchanged
+    ///   * an error if failing to append the rewritten operators, which leaves the plan unchanged as well
+    fn push_down_aggregation(
+        &mut self, opr: &pb::logical_plan::Operator, parent_ids: &[NodeId],
+    ) -> IrResult<Option<NodeId>> {
+        use pb::group_by::agg_func::Aggregate;
+        use pb::logical_plan::operator::Opr;
 
-        let children = node0
-            .borrow()
-            .children
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(children, vec![1]);
+        if !matches!(opr.opr, Some(Opr::GroupBy(_)) | Some(Opr::Count(_))) || parent_ids.len() != 1 {
+            return Ok(None);
+        }
+        let join_id = parent_ids[0];
+        let join_node = match self.get_node(join_id) {
+            Some(node) if node.borrow().children.is_empty() => node,
+            _ => return Ok(None),
+        };
+        let join = match &join_node.borrow().opr.opr {
+            Some(Opr::Join(join)) => join.clone(),
+            _ => return Ok(None),
+        };
+        let join_parents = join_node.borrow().parents.clone();
+        if join.kind != pb::join::JoinKind::Inner as i32
+            || !join.renames.is_empty()
+            || join.left_keys.is_empty()
+            || join.left_keys.len() != join.right_keys.len()
+            || join_parents.len() != 2
+        {
+            return Ok(None);
+        }
+        // Each side is a chain of nodes from a common branch node, of which the heads are ordered
+        // as the sides of the join
+        let mut sides = Vec::with_capacity(2);
+        for end in join_parents {
+            let mut curr = end;
+            let (head, branch) = loop {
+                let node = match self.get_node(curr) {
+                    Some(node) => node,
+                    None => return Ok(None),
+                };
+                if node.borrow().children.len() != 1 || node.borrow().parents.len() != 1 {
+                    return Ok(None);
+                }
+                let parent = *node.borrow().parents.iter().next().unwrap();
+                match self.get_node(parent) {
+                    Some(parent_node) if parent_node.borrow().children.len() > 1 => break (curr, parent),
+                    Some(_) => curr = parent,
+                    None => return Ok(None),
+                }
+            };
+            sides.push((head, branch, end));
+        }
+        sides.sort();
+        if sides[0].1 != sides[1].1 {
+            return Ok(None);
+        }
+        let (left_end, right_end) = (sides[0].2, sides[1].2);
+
+        // The variables of the aggregation are resolved against a copy of the meta, while the
+        // original aliases are kept for the final aggregation
+        let mut resolved = opr.clone();
+        let mut plan_meta = self.meta.deep_clone();
+        plan_meta.set_curr_node(self.max_node_id);
+        let _ = plan_meta.curr_node_meta_mut();
+        let ref_parent_nodes = plan_meta.get_referred_nodes(parent_ids);
+        plan_meta.refer_to_nodes(self.max_node_id, ref_parent_nodes);
+        let preprocessed = if let Some(store_meta) = plan_meta.get_store_meta() {
+            resolved.preprocess(&store_meta, &mut plan_meta)
+        } else if let Ok(store_meta) = STORE_META.read() {
+            resolved.preprocess(&store_meta, &mut plan_meta)
+        } else {
+            Ok(())
+        };
+        if preprocessed.is_err() {
+            // leave the error to be raised while appending the aggregation as it is
+            return Ok(None);
+        }
+        let (keys, functions, null_key_opt, meta_data) = match (opr.opr.as_ref(), resolved.opr) {
+            (Some(Opr::GroupBy(group)), Some(Opr::GroupBy(resolved))) => (
+                resolved
+                    .mappings
+                    .into_iter()
+                    .zip(group.mappings.iter())
+                    .map(|(resolved, mapping)| (resolved.key, mapping.alias.clone()))
+                    .collect::<Vec<_>>(),
+                resolved
+                    .functions
+                    .into_iter()
+                    .zip(group.functions.iter())
+                    .map(|(resolved, func)| pb::group_by::AggFunc { alias: func.alias.clone(), ..resolved })
+                    .collect::<Vec<_>>(),
+                group.null_key_opt,
+                group.meta_data.clone(),
+            ),
+            (Some(Opr::Count(count)), Some(Opr::Count(resolved))) => (
+                vec![],
+                vec![pb::group_by::AggFunc {
+                    vars: resolved
+                        .tag
+                        .map(|tag| common_pb::Variable { tag: Some(tag), property: None, node_type: None })
+                        .into_iter()
+                        .collect(),
+                    aggregate: Aggregate::Count as i32,
+                    alias: count.alias.clone(),
+                    equality: 0,
+                    overflow: 0,
+                    approximation: None,
+                }],
+                0,
+                vec![],
+            ),
+            _ => return Ok(None),
+        };
+        let is_same_var = |var1: &common_pb::Variable, var2: &common_pb::Variable| {
+            var1.tag == var2.tag && var1.property == var2.property
+        };
+        // The index of the join keys that each grouping key refers to
+        let mut key_indices = Vec::with_capacity(keys.len());
+        for (key, _) in &keys {
+            let key = match key {
+                Some(key) => key,
+                None => return Ok(None),
+            };
+            match join
+                .left_keys
+                .iter()
+                .zip(join.right_keys.iter())
+                .position(|(left, right)| is_same_var(key, left) || is_same_var(key, right))
+            {
+                Some(idx) => key_indices.push(idx),
+                None => return Ok(None),
+            }
+        }
+        // The side that each function aggregates from, or `None` if it counts all the records,
+        // where a tag of both sides is taken from the left, as the join does
+        let left_tags = self.meta.get_node_tags(left_end);
+        let right_tags = self.meta.get_node_tags(right_end);
+        let mut func_sides = Vec::with_capacity(functions.len());
+        for func in &functions {
+            let aggregate = Aggregate::from_i32(func.aggregate);
+            if aggregate == Some(Aggregate::Count) && func.vars.is_empty() {
+                func_sides.push(None);
+                continue;
+            }
+            if !matches!(aggregate, Some(Aggregate::Count) | Some(Aggregate::Sum)) || func.vars.len() != 1 {
+                return Ok(None);
+            }
+            let tag_id = match func.vars[0].tag.clone().map(NameOrId::try_from) {
+                Some(Ok(NameOrId::Id(tag_id))) => tag_id as TagId,
+                _ => return Ok(None),
+            };
+            if left_tags.contains(&tag_id) {
+                func_sides.push(Some(true));
+            } else if right_tags.contains(&tag_id) {
+                func_sides.push(Some(false));
+            } else {
+                return Ok(None);
+            }
+        }
 
-        let id = plan
-            .append_operator_as_node(opr.clone(), vec![0, 1])
-            .unwrap();
-        assert_eq!(id, 2);
-        assert_eq!(plan.len(), 3);
-        assert_eq!(plan.max_node_id, 3);
-        let node2 = plan.get_node(2).unwrap().clone();
+        // the rewrite is done on a copy of the plan, which replaces the plan only if all the operators
+        // are appended
+        let mut plan = self.clone_plan();
+        let base = plan.max_node_id;
+        let mut hidden_tag = |name: String| -> common_pb::NameOrId {
+            (plan
+                .meta
+                .get_or_set_tag_id(&format!("~agg_{}_{:?}", name, base))
+                .1 as i32)
+                .into()
+        };
+        let left_key_tags: Vec<_> = (0..join.left_keys.len())
+            .map(|idx| hidden_tag(format!("left_key_{}", idx)))
+            .collect();
+        let right_key_tags: Vec<_> = (0..join.right_keys.len())
+            .map(|idx| hidden_tag(format!("right_key_{}", idx)))
+            .collect();
+        let (left_count_tag, right_count_tag) =
+            (hidden_tag("left_count".to_string()), hidden_tag("right_count".to_string()));
+        let partial_tags: Vec<_> = (0..functions.len())
+            .map(|idx| hidden_tag(format!("partial_{}", idx)))
+            .collect();
+        let product_tags: Vec<_> = (0..functions.len())
+            .map(|idx| hidden_tag(format!("product_{}", idx)))
+            .collect();
+        let as_var = |tag: &common_pb::NameOrId| common_pb::Variable {
+            tag: Some(tag.clone()),
+            property: None,
+            node_type: None,
+        };
+        let partial_group = |is_left: bool| {
+            let (join_keys, key_tags, count_tag) = if is_left {
+                (&join.left_keys, &left_key_tags, &left_count_tag)
+            } else {
+                (&join.right_keys, &right_key_tags, &right_count_tag)
+            };
+            let counting = pb::group_by::AggFunc {
+                vars: vec![],
+                aggregate: Aggregate::Count as i32,
+                alias: Some(count_tag.clone()),
+                equality: 0,
+                overflow: 0,
+                approximation: None,
+            };
+            pb::GroupBy {
+                mappings: join_keys
+                    .iter()
+                    .zip(key_tags.iter())
+                    .map(|(key, tag)| pb::group_by::KeyAlias {
+                        key: Some(key.clone()),
+                        alias: Some(tag.clone()),
+                    })
+                    .collect(),
+                functions: std::iter::once(counting)
+                    .chain(
+                        functions
+                            .iter()
+                            .zip(func_sides.iter())
+                            .zip(partial_tags.iter())
+                            .filter(|((_, side), _)| **side == Some(is_left))
+                            .map(|((func, _), tag)| pb::group_by::AggFunc {
+                                alias: Some(tag.clone()),
+                                ..func.clone()
+                            }),
+                    )
+                    .collect(),
+                meta_data: vec![],
+                null_key_opt: 0,
+            }
+        };
+        let (left_group, right_group) = (partial_group(true), partial_group(false));
+        let partial_join = pb::Join {
+            left_keys: left_key_tags.iter().map(as_var).collect(),
+            right_keys: right_key_tags.iter().map(as_var).collect(),
+            kind: pb::join::JoinKind::Inner as i32,
+            renames: vec![],
+        };
+        let multiply = common_pb::ExprOpr {
+            node_type: None,
+            item: Some(common_pb::expr_opr::Item::Arith(common_pb::Arithmetic::Mul as i32)),
+        };
+        let project = pb::Project {
+            mappings: func_sides
+                .iter()
+                .zip(partial_tags.iter())
+                .zip(product_tags.iter())
+                .map(|((side, partial_tag), product_tag)| {
+                    let (multiplicand, multiplier) = match side {
+                        None => (&left_count_tag, &right_count_tag),
+                        Some(true) => (partial_tag, &right_count_tag),
+                        Some(false) => (partial_tag, &left_count_tag),
+                    };
+                    pb::project::ExprAlias {
+                        expr: Some(common_pb::Expression {
+                            operators: vec![
+                                as_var(multiplicand).into(),
+                                multiply.clone(),
+                                as_var(multiplier).into(),
+                            ],
+                        }),
+                        alias: Some(product_tag.clone()),
+                    }
+                })
+                .collect(),
+            is_append: true,
+            meta_data: vec![],
+        };
+        let final_group = pb::GroupBy {
+            mappings: keys
+                .into_iter()
+                .zip(key_indices.into_iter())
+                .map(|((_, alias), idx)| pb::group_by::KeyAlias {
+                    key: Some(as_var(&left_key_tags[idx])),
+                    alias,
+                })
+                .collect(),
+            functions: functions
+                .iter()
+                .zip(product_tags.iter())
+                .map(|(func, tag)| pb::group_by::AggFunc {
+                    vars: vec![as_var(tag)],
+                    aggregate: Aggregate::Sum as i32,
+                    alias: func.alias.clone(),
+                    equality: 0,
+                    overflow: if func.aggregate == Aggregate::Sum as i32 { func.overflow } else { 0 },
+                    approximation: None,
+                })
+                .collect(),
+            meta_data,
+            null_key_opt,
+        };
 
-        let parents = node2
-            .borrow()
-            .parents
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(parents, vec![0, 1]);
+        plan.remove_node(join_id);
+        plan.write_through.removed.push(join_id);
+        let left_id = plan.append_operator(left_group.into(), vec![left_end])?;
+        let right_id = plan.append_operator(right_group.into(), vec![right_end])?;
+        let join_id = plan.append_operator(partial_join.into(), vec![left_id, right_id])?;
+        let project_id = plan.append_operator(project.into(), vec![join_id])?;
+        let group_id = plan.append_operator(final_group.into(), vec![project_id])?;
+        plan.annotate_node(
+            group_id,
+            format!("aggregation: push down below the join as the nodes [{}, {}]", left_id, right_id),
+        )?;
+
+        self.replace_with_copy(plan);
+
+        Ok(Some(group_id))
+    }
 
-        let children = node0
-            .borrow()
-            .children
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(children, vec![1, 2]);
+    /// The projections of the left and right inputs of the join of the given id, each onto the tags
+    /// referred by the keys of the side, and the tags of the side needed after the join, such that the
+    /// unused columns are dropped before the inputs are shuffled to be joined. A side is not projected,
+    /// i.e., `None`, if it keeps all its tags, or the tags that it outputs are unknown. Neither side
+    /// is projected if some key refers to the head, or the tags needed after the join cannot be told,
+    /// e.g., as all the tags are sunk.
+    ///
+    /// As the head is not kept by a projection of multiple columns, the left input of a semi or anti
+    /// join, which is output as it is, is never projected.
+    pub(crate) fn get_join_key_projections(
+        &self, join_id: NodeId,
+    ) -> (Option<pb::Project>, Option<pb::Project>) {
+        use pb::join::{rename::Side, JoinKind};
+        use pb::logical_plan::operator::Opr;
 
-        let children = node1
-            .borrow()
-            .children
+        let node = match self.get_node(join_id) {
+            Some(node) => node,
+            None => return (None, None),
+        };
+        let node = node.borrow();
+        let join = match &node.opr.opr {
+            Some(Opr::Join(join)) => join,
+            _ => return (None, None),
+        };
+        if node.parents.len() != 2
+            || join.left_keys.is_empty()
+            || join.left_keys.len() != join.right_keys.len()
+        {
+            return (None, None);
+        }
+        let needed_tags = match self.get_referred_tags_after(&node.children) {
+            Some(tags) => tags,
+            None => return (None, None),
+        };
+        let is_left_only = join.kind == JoinKind::Semi as i32 || join.kind == JoinKind::Anti as i32;
+        let mut projections = Vec::with_capacity(2);
+        for (idx, (parent_id, keys)) in node
+            .parents
             .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(children, vec![2]);
+            .zip(vec![&join.left_keys, &join.right_keys])
+            .enumerate()
+        {
+            let side = if idx == 0 { Side::Left } else { Side::Right };
+            let mut side_needed = BTreeSet::new();
+            for key in keys {
+                match key
+                    .tag
+                    .as_ref()
+                    .and_then(|tag| get_tag_id(tag, &self.meta))
+                {
+                    Some(tag) => {
+                        side_needed.insert(tag);
+                    }
+                    None => return (None, None),
+                }
+            }
+            let side_tags = self.meta.get_node_tags(*parent_id);
+            if (side == Side::Left && is_left_only) || !side_needed.is_subset(&side_tags) {
+                projections.push(None);
+                continue;
+            }
+            if !is_left_only {
+                let renamed: BTreeSet<TagId> = join
+                    .renames
+                    .iter()
+                    .filter(|rename| rename.side == side as i32)
+                    .filter_map(|rename| get_tag(rename.from.as_ref()))
+                    .collect();
+                // the renamed tags are kept, as they are renamed while being joined
+                side_needed.extend(
+                    side_tags
+                        .iter()
+                        .filter(|tag| renamed.contains(tag) || needed_tags.contains(tag)),
+                );
+            }
+            if side_needed.len() == side_tags.len() {
+                projections.push(None);
+            } else {
+                let mappings = side_needed
+                    .into_iter()
+                    .map(|tag| pb::project::ExprAlias {
+                        expr: Some(common_pb::Expression {
+                            operators: vec![common_pb::Variable {
+                                tag: Some((tag as i32).into()),
+                                property: None,
+                                node_type: None,
+                            }
+                            .into()],
+                        }),
+                        alias: Some((tag as i32).into()),
+                    })
+                    .collect();
+                projections.push(Some(pb::Project { mappings, is_append: false, meta_data: vec![] }));
+            }
+        }
+        let right = projections.pop().flatten();
+        let left = projections.pop().flatten();
 
-        let node2 = plan.remove_node(2);
-        assert_eq!(node2.unwrap().borrow().id, 2);
-        assert_eq!(plan.len(), 2);
-        assert_eq!(plan.max_node_id, 3);
-        let children = node0
-            .borrow()
-            .children
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(children, vec![1]);
+        (left, right)
+    }
 
-        let children = node1
-            .borrow()
-            .children
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert!(children.is_empty());
+    /// Strip the property fetches, i.e., the `Project`s of the variables only, e.g., `values('name')`,
+    /// right before a `Count` of all the records, as a `Project` maps each record to exactly one, and
+    /// thus never changes the count. A `Project` is stripped only if it is the input of no other node
+    /// and can be detached (see [`LogicalPlan::detach_node`]), e.g., it is aliased by no tag.
+    ///
+    /// # Return
+    ///   * the parents to append the `Count` to, i.e., the input of the stripped `Project`s if any
+    fn strip_fetches_before_count(
+        &mut self, count: &pb::Count, mut parent_ids: Vec<NodeId>,
+    ) -> Vec<NodeId> {
+        use common_pb::expr_opr::Item;
+        use pb::logical_plan::operator::Opr;
 
-        let _id = plan.append_operator_as_node(opr.clone(), vec![0, 2]);
-        match _id.err().unwrap() {
-            IrError::ParentNodeNotExist(node) => assert_eq!(node, 2),
-            _ => panic!("wrong error type"),
+        if count.tag.is_some() {
+            return parent_ids;
+        }
+        while parent_ids.len() == 1 {
+            let parent_id = parent_ids[0];
+            let grandparent_id = match self.get_node(parent_id) {
+                Some(node) => {
+                    let node = node.borrow();
+                    let is_fetch = match &node.opr.opr {
+                        Some(Opr::Project(project)) => project.mappings.iter().all(|mapping| {
+                            mapping
+                                .expr
+                                .as_ref()
+                                .map(|expr| {
+                                    expr.operators.len() == 1
+                                        && matches!(
+                                            expr.operators[0].item,
+                                            Some(Item::Var(_))
+                                                | Some(Item::Vars(_))
+                                                | Some(Item::VarMap(_))
+                                        )
+                                })
+                                .unwrap_or(false)
+                        }),
+                        _ => false,
+                    };
+                    if !is_fetch || !node.children.is_empty() || node.parents.len() != 1 {
+                        break;
+                    }
+                    *node.parents.iter().next().unwrap()
+                }
+                None => break,
+            };
+            if self.detach_node(parent_id).is_err() {
+                break;
+            }
+            parent_ids = vec![grandparent_id];
         }
-        assert_eq!(plan.len(), 2);
-        assert_eq!(plan.max_node_id, 3);
-        let children = node0
-            .borrow()
-            .children
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(children, vec![1]);
-
-        // add node2 back again for further testing recursive removal
-        let _ = plan
-            .append_operator_as_node(opr.clone(), vec![0, 1])
-            .unwrap();
-        let node3 = plan.get_node(3).unwrap();
-        let _ = plan.remove_node(1);
-        assert_eq!(plan.len(), 2);
-        assert_eq!(plan.max_node_id, 4);
-        let children = node0
-            .borrow()
-            .children
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(children, vec![3]);
 
-        let parents = node3
-            .borrow()
-            .parents
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(parents, vec![0]);
+        parent_ids
     }
 
-    #[test]
-    fn logical_plan_from_pb() {
-        let opr = pb::logical_plan::Operator {
-            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
-        };
-        let root_pb = pb::logical_plan::Node { opr: Some(opr.clone()), children: vec![1, 2] };
-        let node1_pb = pb::logical_plan::Node { opr: Some(opr.clone()), children: vec![2] };
-        let node2_pb = pb::logical_plan::Node { opr: Some(opr.clone()), children: vec![] };
-        let plan_pb = pb::LogicalPlan { nodes: vec![root_pb, node1_pb, node2_pb], roots: vec![0] };
-
-        let plan = LogicalPlan::try_from(plan_pb).unwrap();
-        assert_eq!(plan.len(), 3);
-        let node0 = plan.get_node(0).unwrap();
-        let node1 = plan.get_node(1).unwrap();
-        let node2 = plan.get_node(2).unwrap();
+    /// The tags referred by the given nodes and those that follow them, including the subtasks of the
+    /// `Apply`s among them, up to the nodes that replace the records, e.g., a `GroupBy`. It returns
+    /// `None` if some node refers to all the tags, i.e., a sink of no tags.
+    fn get_referred_tags_after(&self, ids: &BTreeSet<NodeId>) -> Option<BTreeSet<TagId>> {
+        use pb::logical_plan::operator::Opr;
 
-        let children = node0
-            .borrow()
-            .children
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(children, vec![1, 2]);
+        let mut tags = BTreeSet::new();
+        let mut visited = BTreeSet::new();
+        let mut queue: VecDeque<NodeId> = ids.iter().cloned().collect();
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(node) = self.get_node(id) {
+                let node = node.borrow();
+                tags.extend(get_referred_tags(&node.opr, &self.meta)?);
+                match &node.opr.opr {
+                    Some(Opr::Project(project)) if !project.is_append => continue,
+                    Some(Opr::GroupBy(_)) | Some(Opr::Count(_)) => continue,
+                    Some(Opr::Apply(apply)) => queue.push_back(apply.subtask as NodeId),
+                    _ => {}
+                }
+                queue.extend(node.children.iter().cloned());
+            }
+        }
 
-        let children = node1
-            .borrow()
-            .children
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(children, vec![2]);
+        Some(tags)
+    }
 
-        let parents = node1
-            .borrow()
-            .parents
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(parents, vec![0]);
+    /// Remove a node from the logical plan, and do the following:
+    /// * For each of its parent, if present, remove this node's id reference from its `children`.
+    /// * For each of its children, remove this node's id reference from its `parent`, and if
+    /// the child's parent becomes empty, the child must be removed recursively.
+    ///
+    ///  Note that this does not decrease `self.node_max_id`, which serves as the indication
+    /// of new id of the plan.
+    pub fn remove_node(&mut self, id: NodeId) -> Option<NodeType> {
+        let node = self.nodes.remove(id as usize);
+        if let Some(n) = &node {
+            for p in &n.borrow().parents {
+                if let Some(parent_node) = self.get_node(*p) {
+                    parent_node.borrow_mut().children.remove(&id);
+                }
+            }
 
-        let parents = node2
-            .borrow()
-            .parents
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<NodeId>>();
-        assert_eq!(parents, vec![0, 1]);
+            for c in &n.borrow().children {
+                if let Some(child) = self.get_node(*c) {
+                    child.borrow_mut().parents.remove(&id);
+                    if child.borrow().parents.is_empty() {
+                        // Recursively remove the child
+                        let _ = self.remove_node(*c);
+                    }
+                }
+            }
+        }
+        node
     }
 
-    #[test]
-    fn logical_plan_into_pb() {
-        let opr = pb::logical_plan::Operator {
-            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
+    /// Detach a node from the logical plan, and reconnect its parent to its children in its place, e.g.,
+    /// to drop a redundant `Dedup` or `Select`. Unlike [`LogicalPlan::remove_node`], the children are
+    /// kept, and thus the node must not change what its children refer to, namely:
+    /// * it has exactly one parent, which is not a parent of its children yet;
+    /// * it is neither aliased by a tag, nor the head referred by the other nodes, nor a subtask.
+    ///
+    /// The references of its children to it, e.g., the parents of a `Union`, are replaced by the parent.
+    pub fn detach_node(&mut self, id: NodeId) -> IrResult<NodeType> {
+        let node = self
+            .get_node(id)
+            .ok_or(IrError::NodeNotExist(id))?;
+        let (parents, children) = {
+            let node = node.borrow();
+            (node.parents.clone(), node.children.clone())
         };
-        let mut plan = LogicalPlan::default();
-
-        let _ = plan
-            .append_operator_as_node(opr.clone(), vec![])
-            .unwrap();
-        let _ = plan
-            .append_operator_as_node(opr.clone(), vec![0])
-            .unwrap();
-        let _ = plan
-            .append_operator_as_node(opr.clone(), vec![0])
-            .unwrap();
-
-        let _ = plan.remove_node(1);
+        if parents.len() != 1 {
+            return Err(IrError::Unsupported(format!(
+                "detaching the node {} of {} parents",
+                id,
+                parents.len()
+            )));
+        }
+        let parent = *parents.iter().next().unwrap();
+        for child in &children {
+            if let Some(child_node) = self.get_node(*child) {
+                if child_node.borrow().parents.contains(&parent) {
+                    return Err(IrError::Unsupported(format!(
+                        "detaching the node {}, as its parent {} is already a parent of its child {}",
+                        id, parent, child
+                    )));
+                }
+            }
+        }
+        for (tag, name) in self.get_tag_names() {
+            if self.meta.get_tag_nodes(tag).contains(&id) {
+                return Err(IrError::Unsupported(format!(
+                    "detaching the node {} aliased by {:?}",
+                    id, name
+                )));
+            }
+        }
+        for (other, other_node) in self.nodes.iter() {
+            let other = other as NodeId;
+            if other != id
+                && self
+                    .meta
+                    .get_referred_nodes(&[other])
+                    .contains(&id)
+            {
+                return Err(IrError::Unsupported(format!(
+                    "detaching the node {} referred by the node {}",
+                    id, other
+                )));
+            }
+            if get_subtask(&other_node.borrow().opr) == Some(id) {
+                return Err(IrError::Unsupported(format!(
+                    "detaching the node {} as the subtask of the node {}",
+                    id, other
+                )));
+            }
+        }
 
-        let plan_pb = pb::LogicalPlan::from(plan);
-        assert_eq!(plan_pb.nodes.len(), 2);
+        self.nodes.remove(id as usize);
+        self.write_through.removed.push(id);
+        if let Some(parent_node) = self.get_node(parent) {
+            let mut parent_node = parent_node.borrow_mut();
+            parent_node.children.remove(&id);
+            parent_node
+                .children
+                .extend(children.iter().cloned());
+        }
+        for child in &children {
+            if let Some(child_node) = self.get_node(*child) {
+                let mut child_node = child_node.borrow_mut();
+                child_node.parents.remove(&id);
+                child_node.parents.insert(parent);
+                child_node.opr =
+                    remap_node_refs(&child_node.opr, |node| Some(if node == id { parent } else { node }));
+            }
+        }
+        if self.meta.get_curr_node() == id {
+            self.meta.set_curr_node(parent);
+        }
+        {
+            let mut node = node.borrow_mut();
+            node.parents.clear();
+            node.children.clear();
+        }
 
-        let node0 = &plan_pb.nodes[0];
-        let node1 = &plan_pb.nodes[1];
-        assert_eq!(node0.opr, Some(opr.clone()));
-        assert_eq!(node0.children, vec![1]);
-        assert_eq!(node1.opr, Some(opr.clone()));
-        assert!(node1.children.is_empty());
+        Ok(node)
     }
 
-    #[test]
-    fn preprocess_expr() {
-        let mut plan_meta = PlanMeta::default();
-        let a_id = plan_meta.get_or_set_tag_id("a").1;
-        let b_id = plan_meta.get_or_set_tag_id("b").1;
-        plan_meta.set_tag_nodes(a_id, vec![1]);
-        plan_meta.set_tag_nodes(b_id, vec![2]);
-        plan_meta.curr_node_meta_mut();
-        plan_meta.refer_to_nodes(0, vec![0]);
+    /// Replace the operator of a node in place, e.g., to swap a full `Scan` for one with an index
+    /// predicate, where the id and the edges of the node are kept. The operator is preprocessed as if it
+    /// were appended as the node, and thus must be of the same kind, and produce the same tags as the old
+    /// one, which its children rely on. The operators that refer to the other nodes, namely, `Union`,
+    /// `Intersect`, `Apply` and `SegApply`, are not replaced.
+    ///
+    /// # Return
+    ///   * the old operator if succeed
+    pub fn replace_operator(
+        &mut self, id: NodeId, mut opr: pb::logical_plan::Operator,
+    ) -> IrResult<pb::logical_plan::Operator> {
+        use pb::logical_plan::operator::Opr;
 
-        let meta = StoreMeta {
-            schema: Some(Schema::new(
-                vec![("person".to_string(), 0), ("software".to_string(), 1)],
-                vec![("knows".to_string(), 0), ("creates".to_string(), 1)],
-                vec![("id".to_string(), 0), ("name".to_string(), 1), ("age".to_string(), 2)],
-            )),
-        };
+        let node = self
+            .get_node(id)
+            .ok_or(IrError::NodeNotExist(id))?;
+        let new_kind = opr
+            .opr
+            .as_ref()
+            .ok_or(IrError::MissingData("Operator::opr".to_string()))?;
+        let old_opr = node.borrow().opr.clone();
+        if matches!(new_kind, Opr::Union(_) | Opr::Intersect(_) | Opr::Apply(_) | Opr::SegApply(_))
+            || old_opr.opr.as_ref().map(std::mem::discriminant) != Some(std::mem::discriminant(new_kind))
+        {
+            return Err(IrError::Unsupported(format!(
+                "replacing the {} of the node {} by the {}",
+                default_display_name(&old_opr),
+                id,
+                default_display_name(&opr)
+            )));
+        }
 
-        let mut expression = str_to_expr_pb("@.~label == \"person\"".to_string()).unwrap();
-        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
-        let opr = expression.operators.get(2).unwrap().clone();
-        match opr.item.unwrap() {
-            common_pb::expr_opr::Item::Const(val) => match val.item.unwrap() {
-                common_pb::value::Item::I32(i) => assert_eq!(i, 0),
-                _ => panic!(),
-            },
-            _ => panic!(),
+        let old_curr_node = self.meta.get_curr_node();
+        self.meta.set_curr_node(id);
+        let result = if let Some(store_meta) = self.meta.get_store_meta() {
+            opr.preprocess(&store_meta, &mut self.meta)
+        } else if let Ok(store_meta) = STORE_META.read() {
+            opr.preprocess(&store_meta, &mut self.meta)
+        } else {
+            Ok(())
+        };
+        self.meta.set_curr_node(old_curr_node);
+        let annotations = self.meta.take_node_annotations(id);
+        result?;
+        let parent_ids: Vec<NodeId> = node.borrow().parents.iter().cloned().collect();
+        if get_output_tags(&opr, &parent_ids, &self.meta) != self.meta.get_node_tags(id) {
+            return Err(IrError::Unsupported(format!(
+                "replacing the operator of the node {} by one that produces other tags",
+                id
+            )));
+        }
+        node.borrow_mut().opr = opr;
+        for annotation in annotations {
+            self.annotate_node(id, annotation)?;
         }
 
-        let mut expression =
-            str_to_expr_pb("@.~label within [\"person\", \"software\"]".to_string()).unwrap();
-        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
-        let opr = expression.operators.get(2).unwrap().clone();
-        match opr.item.unwrap() {
-            common_pb::expr_opr::Item::Const(val) => match val.item.unwrap() {
-                common_pb::value::Item::I32Array(arr) => {
-                    assert_eq!(arr.item, vec![0, 1]);
-                }
-                _ => panic!(),
-            },
-            _ => panic!(),
+        Ok(old_opr)
+    }
+
+    /// Insert an operator as a new node on the edge from `parent_id` to `child_id`, e.g., for the passes
+    /// that inject a `GetV` to fetch the properties of the vertices, or a `Dedup`, once the plan is
+    /// built. The operator is preprocessed as if it were appended to the parent, and must take the
+    /// parent as its only input, and produce the same tags as the parent, which the child relies on.
+    /// The references of the child to the parent, e.g., the parents of a `Union`, are replaced by the
+    /// new node.
+    ///
+    /// # Return
+    ///   * the id of the new node if succeed
+    pub fn insert_between(
+        &mut self, parent_id: NodeId, child_id: NodeId, mut opr: pb::logical_plan::Operator,
+    ) -> IrResult<NodeId> {
+        use pb::logical_plan::operator::Opr;
+
+        if self.is_frozen {
+            return Err(IrError::PlanFrozen);
+        }
+        let parent = self
+            .get_node(parent_id)
+            .ok_or(IrError::NodeNotExist(parent_id))?;
+        let child = self
+            .get_node(child_id)
+            .ok_or(IrError::NodeNotExist(child_id))?;
+        if !parent.borrow().children.contains(&child_id) {
+            return Err(IrError::Unsupported(format!(
+                "inserting between the node {} and the node {}, which is not its child",
+                parent_id, child_id
+            )));
+        }
+        match opr.opr.as_ref() {
+            None => return Err(IrError::MissingData("Operator::opr".to_string())),
+            Some(
+                Opr::Scan(_)
+                | Opr::Join(_)
+                | Opr::Union(_)
+                | Opr::Intersect(_)
+                | Opr::Apply(_)
+                | Opr::SegApply(_)
+                | Opr::Pattern(_),
+            ) => {
+                return Err(IrError::Unsupported(format!(
+                    "inserting the {} between two nodes",
+                    default_display_name(&opr)
+                )))
+            }
+            _ => {}
         }
 
-        let mut expression =
-            str_to_expr_pb("(@.name == \"person\") && @a.~label == \"knows\"".to_string()).unwrap();
-        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
+        let id = self.max_node_id;
+        let old_curr_node = self.meta.get_curr_node();
+        self.meta.set_curr_node(id);
+        let _ = self.meta.curr_node_meta_mut();
+        let ref_parent_nodes = self.meta.get_referred_nodes(&[parent_id]);
+        self.meta.refer_to_nodes(id, ref_parent_nodes);
+        let result = if let Some(store_meta) = self.meta.get_store_meta() {
+            opr.preprocess(&store_meta, &mut self.meta)
+        } else if let Ok(store_meta) = STORE_META.read() {
+            opr.preprocess(&store_meta, &mut self.meta)
+        } else {
+            Ok(())
+        };
+        self.meta.set_curr_node(old_curr_node);
+        let annotations = self.meta.take_node_annotations(id);
+        result?;
+        let tags = get_output_tags(&opr, &[parent_id], &self.meta);
+        if tags != self.meta.get_node_tags(parent_id) {
+            return Err(IrError::Unsupported(format!(
+                "inserting the {} that produces other tags than the node {}",
+                default_display_name(&opr),
+                parent_id
+            )));
+        }
+        let ordering = match opr.opr.as_ref() {
+            Some(Opr::OrderBy(order)) => Some(order.pairs.clone()),
+            _ if is_order_preserving(&opr) => self
+                .meta
+                .get_node_ordering(parent_id)
+                .map(|ordering| ordering.to_vec()),
+            _ => None,
+        };
 
-        // person should not be mapped, as name is not a label key
-        let opr = expression.operators.get(3).unwrap().clone();
-        match opr.item.unwrap() {
-            common_pb::expr_opr::Item::Const(val) => match val.item.unwrap() {
-                common_pb::value::Item::Str(str) => assert_eq!(str, "person".to_string()),
-                _ => panic!(),
-            },
-            _ => panic!(),
+        let mut node = Node::new(id, opr);
+        node.add_parent(parent_id);
+        node.add_child(child_id);
+        {
+            let mut parent = parent.borrow_mut();
+            parent.children.remove(&child_id);
+            parent.add_child(id);
         }
-        // "knows maps to 0"
-        let opr = expression.operators.get(8).unwrap().clone();
-        match opr.item.unwrap() {
-            common_pb::expr_opr::Item::Const(val) => match val.item.unwrap() {
-                common_pb::value::Item::I32(i) => assert_eq!(i, 0),
-                _ => panic!(),
-            },
-            _ => panic!(),
+        {
+            let mut child = child.borrow_mut();
+            child.parents.remove(&parent_id);
+            child.add_parent(id);
+            child.opr = remap_node_refs(&child.opr, |node| Some(if node == parent_id { id } else { node }));
+        }
+        self.nodes
+            .insert(id as usize, Arc::new(SyncCell::new(node)));
+        self.max_node_id = id + 1;
+        if let Some(ordering) = ordering {
+            self.meta.set_node_ordering(id, ordering);
+        }
+        self.meta.set_node_tags(id, tags);
+        for annotation in annotations {
+            self.annotate_node(id, annotation)?;
         }
 
-        // Assert whether the columns have been updated in PlanMeta
-        assert_eq!(
-            plan_meta
-                .get_curr_node_meta()
-                .unwrap()
-                .get_tag_columns()
-                .get(&None)
-                .unwrap()
-                .get(),
-            // has a new column "name", which is mapped to 1
-            vec![1.into()]
-        );
+        Ok(id)
+    }
 
-        // name maps to 1
-        let mut expression = str_to_expr_pb("@a.name == \"John\"".to_string()).unwrap();
-        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
-        let opr = expression.operators.get(0).unwrap().clone();
-        match opr.item.unwrap() {
-            common_pb::expr_opr::Item::Var(var) => {
-                match var.clone().property.unwrap().item.unwrap() {
-                    Item::Key(key) => assert_eq!(key, 1.into()),
-                    _ => panic!(),
+    /// Append branch plans to a certain node which has **no** children in this logical plan.
+    pub fn append_branch_plans(&mut self, node: NodeType, subplans: Vec<LogicalPlan>) {
+        if !node.borrow().children.is_empty() {
+            return;
+        } else {
+            for subplan in subplans {
+                if let Some((_, root)) = subplan.nodes.iter().next() {
+                    node.borrow_mut()
+                        .children
+                        .insert(root.borrow().id);
+                    root.borrow_mut()
+                        .parents
+                        .insert(node.borrow().id);
                 }
-                assert_eq!(var.tag.unwrap(), (a_id as i32).into());
+                self.nodes.extend(subplan.nodes.into_iter());
             }
-            _ => panic!(),
         }
+    }
 
-        // Assert whether the columns have been updated in PlanMeta
-        assert_eq!(
-            plan_meta
-                .get_curr_node_meta()
-                .unwrap()
-                .get_tag_columns()
-                .get(&Some(a_id))
-                .unwrap()
-                .get(),
-            // node1 with tag a has a new column "name", which is mapped to 1
-            vec![1.into()]
-        );
-
-        let mut expression = str_to_expr_pb("{@a.name, @b.id}".to_string()).unwrap();
-        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
-        let opr = expression.operators.get(0).unwrap().clone();
-        match opr.item.unwrap() {
-            common_pb::expr_opr::Item::VarMap(vars) => {
-                let var1 = vars.keys[0].clone();
-                match var1.property.unwrap().item.unwrap() {
-                    Item::Key(key) => assert_eq!(key, 1.into()),
-                    _ => panic!(),
-                }
-                assert_eq!(var1.tag.unwrap(), (a_id as i32).into());
-                let var2 = vars.keys[1].clone();
-                match var2.property.unwrap().item.unwrap() {
-                    Item::Key(key) => assert_eq!(key, 0.into()),
-                    _ => panic!(),
+    /// From a branch node `root`, obtain the sub-plans (branch node excluded), each representing
+    /// a branch of operators, till the merge node (merge node excluded).
+    ///
+    /// # Return
+    ///   * the merge node and sub-plans if the `brach_node` is indeed a branch node (has more
+    /// than one child), and its corresponding merge_node present.
+    ///   * `None` and empty sub-plans if otherwise.
+    pub fn get_branch_plans(&self, branch_node: NodeType) -> (Option<NodeType>, Vec<LogicalPlan>) {
+        let mut plans = vec![];
+        let mut merge_node_opt = None;
+        if branch_node.borrow().children.len() > 1 {
+            merge_node_opt = self.get_merge_node(branch_node.clone());
+            if let Some(merge_node) = &merge_node_opt {
+                for &child_node_id in &branch_node.borrow().children {
+                    if let Some(child_node) = self.get_node(child_node_id) {
+                        if let Some(subplan) = self.subplan(child_node, merge_node.clone()) {
+                            plans.push(subplan)
+                        }
+                    }
                 }
-                assert_eq!(var2.tag.unwrap(), (b_id as i32).into());
             }
-            _ => panic!(),
         }
 
-        // Assert whether the columns have been updated in PlanMeta
-        assert_eq!(
-            plan_meta
-                .get_curr_node_meta()
-                .unwrap()
-                .get_tag_columns()
-                .get(&Some(a_id))
-                .unwrap()
-                .get(),
-            // node1 with tag a has a new column "name", which is mapped to 1
-            vec![1.into()]
-        );
-        assert_eq!(
-            plan_meta
-                .get_curr_node_meta()
-                .unwrap()
-                .get_tag_columns()
-                .get(&Some(b_id))
-                .unwrap()
-                .get(),
-            // node2 with tag b has a new column "id", which is mapped to 0
-            vec![0.into()]
-        );
+        (merge_node_opt, plans)
     }
 
-    #[test]
-    fn preprocess_scan() {
-        let mut plan_meta = PlanMeta::default();
-        let a_id = plan_meta.get_or_set_tag_id("a").1;
-        plan_meta.set_tag_nodes(a_id, vec![1]);
+    /// To construct a subplan from every node lying between `from_node` (included) and `to_node` (excluded)
+    /// in the logical plan. Thus, for the subplan to be valid, `to_node` must refer to a
+    /// downstream node against `from_node` in the plan.
+    ///
+    /// If there are some branches between `from_node` and `to_node`, there are two cases:
+    /// * 1. `to_node` lies within a sub-branch. In this case, **NO** subplan can be produced;
+    /// * 2. `to_node` is a downstream node of the merge node of the branch, then all branches
+    /// of nodes must be included in the subplan. For example, F is a `from_node` which has two
+    /// branches, namely F -> A1 -> B1 -> M, and F -> A2 -> B2 -> M. we have `to_node` T connected
+    /// to M in the logical plan, as M -> T0 -> T, the subplan from F to T, must include the operators
+    /// of F, A1, B1, A2, B2, M and T0.
+    ///
+    /// # Return
+    ///   * The subplan in case of success,
+    ///   * `None` if `from_node` is `to_node`, or could not arrive at `to_node` following the
+    ///  plan, or there is a branch node in between, but fail to locate the corresponding merge node.
+    pub fn subplan(&self, from_node: NodeType, to_node: NodeType) -> Option<LogicalPlan> {
+        if from_node == to_node {
+            return None;
+        }
+        let mut plan = LogicalPlan::with_root(clone_node(from_node.clone()));
+        plan.meta = self.meta.clone();
+        let mut curr_node = from_node;
+        while curr_node != to_node {
+            if curr_node.borrow().children.is_empty() {
+                // While still not locating to_node
+                return None;
+            } else if curr_node.borrow().children.len() == 1 {
+                let next_node_id = curr_node.borrow().get_first_child().unwrap();
+                if let Some(next_node) = self.get_node(next_node_id) {
+                    if next_node.borrow().id != to_node.borrow().id {
+                        plan.append_node(clone_node(next_node.clone()), vec![curr_node.borrow().id])
+                            .expect("append node to subplan error");
+                    }
+                    curr_node = next_node;
+                } else {
+                    return None;
+                }
+            } else {
+                let (merge_node_opt, subplans) = self.get_branch_plans(curr_node.clone());
+                if let Some(merge_node) = merge_node_opt {
+                    plan.append_branch_plans(plan.get_node(curr_node.borrow().id).unwrap(), subplans);
+                    if merge_node.borrow().id != to_node.borrow().id {
+                        let merge_node_parent = merge_node
+                            .borrow()
+                            .parents
+                            .iter()
+                            .map(|x| *x)
+                            .collect();
+                        let merge_node_clone = clone_node(merge_node.clone());
+                        plan.append_node(merge_node_clone, merge_node_parent)
+                            .expect("append node to subplan error");
+                    }
+                    curr_node = merge_node;
+                } else {
+                    return None;
+                }
+            }
+        }
+        Some(plan)
+    }
+
+    /// Given a node that contains a subtask, which is typically an  `Apply` operator,
+    /// try to extract the subtask as a logical plan.
+    ///
+    /// TODO(longbin): There may be an issue when the last node of a subtask is a merge node.
+    pub fn extract_subplan(&self, node: NodeType) -> Option<LogicalPlan> {
+        let node_borrow = node.borrow();
+        match &node_borrow.opr.opr {
+            Some(pb::logical_plan::operator::Opr::Apply(apply_opr)) => {
+                if let Some(from_node) = self.get_node(apply_opr.subtask as NodeId) {
+                    let mut curr_node = from_node.clone();
+                    while let Some(to_node) = curr_node
+                        .clone()
+                        .borrow()
+                        .get_first_child()
+                        .and_then(|node_id| self.get_node(node_id))
+                    {
+                        curr_node = to_node.clone();
+                    }
+                    let parent_ids = curr_node
+                        .borrow()
+                        .parents
+                        .iter()
+                        .cloned()
+                        .collect();
+                    let mut subplan = if from_node == curr_node {
+                        let mut p = LogicalPlan::default();
+                        p.meta = self.meta.clone();
+                        Some(p)
+                    } else {
+                        self.subplan(from_node, curr_node.clone())
+                    };
+                    if let Some(plan) = subplan.as_mut() {
+                        plan.append_node(curr_node.borrow().clone(), parent_ids)
+                            .expect("append node to subplan error!");
+                    }
+                    subplan
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Copy the plan into an independent one, e.g., to keep a base plan as a template that is extended
+    /// in different directions, such as one copy with a `Limit` and another with a `Count`. Unlike
+    /// `clone()`, which shares the nodes and their metadata with the plan, they are copied, such that
+    /// appending to either plan leaves the other intact. The copy keeps the ids of the nodes and the
+    /// settings of the plan, while the write-through sink, if any, is not inherited.
+    pub fn clone_plan(&self) -> LogicalPlan {
+        let mut plan = self.clone();
+        plan.nodes = self
+            .nodes
+            .iter()
+            .map(|(id, node)| (id, Arc::new(SyncCell::new(node.borrow().clone()))))
+            .collect();
+        plan.meta = self.meta.deep_clone();
+        plan.is_frozen = false;
+
+        plan
+    }
+
+    /// Replace the plan with a copy of it (see [`LogicalPlan::clone_plan`]) that is rewritten, e.g., by
+    /// appending several operators, such that a rewrite failing halfway leaves the plan unchanged as
+    /// it is done on the copy. The write-through sink of the plan, if any, is kept.
+    fn replace_with_copy(&mut self, mut copy: LogicalPlan) {
+        let removed = std::mem::take(&mut copy.write_through.removed);
+        copy.write_through = std::mem::take(&mut self.write_through);
+        copy.write_through.removed.extend(removed);
+        copy.is_frozen = self.is_frozen;
+        *self = copy;
+    }
+
+    /// Freeze the plan, e.g., once it is handed over to be submitted, after which appending to it via
+    /// [`LogicalPlan::append_operator_as_node`] fails with [`IrError::PlanFrozen`], while it can still
+    /// be inspected and serialized. A frozen plan cannot be unfrozen, while its copy via
+    /// [`LogicalPlan::clone_plan`] is not frozen.
+    pub fn freeze(&mut self) {
+        self.is_frozen = true;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.is_frozen
+    }
+
+    /// Copy the nodes lying between `from_id` and `to_id` (both included), namely, the nodes that are
+    /// downstream of `from_id` and upstream of `to_id`, as well as the subtasks of the `Apply`s among
+    /// them, into a standalone plan, e.g., for caching or explaining a fragment of the plan. The nodes
+    /// are given new ids in their order in the plan, with `from_id` being the root.
+    ///
+    /// It fails with `IrError::InvalidRange` if `to_id` is not downstream of `from_id`, or with
+    /// `IrError::ParentNodeNotExist` if a node other than `from_id` has a parent out of the fragment,
+    /// e.g., a join with a branch from elsewhere, which leaves the fragment incomplete.
+    pub fn extract_subplan_between(&self, from_id: NodeId, to_id: NodeId) -> IrResult<LogicalPlan> {
+        for id in [from_id, to_id] {
+            if !self.nodes.contains_key(id as usize) {
+                return Err(IrError::NodeNotExist(id));
+            }
+        }
+        let downstream = self.collect_nodes(from_id, |node| node.children.iter().cloned().collect());
+        let upstream = self.collect_nodes(to_id, |node| node.parents.iter().cloned().collect());
+        let mut ids: BTreeSet<NodeId> = downstream
+            .intersection(&upstream)
+            .cloned()
+            .collect();
+        if ids.is_empty() {
+            return Err(IrError::InvalidRange(from_id as i32, to_id as i32));
+        }
+        let mut subtask_roots = BTreeSet::new();
+        let mut queue: VecDeque<NodeId> = ids.iter().cloned().collect();
+        while let Some(id) = queue.pop_front() {
+            if let Some(pb::logical_plan::operator::Opr::Apply(apply)) =
+                &self.nodes[id as usize].borrow().opr.opr
+            {
+                let subtask = apply.subtask as NodeId;
+                subtask_roots.insert(subtask);
+                for sub_id in self.collect_nodes(subtask, |node| node.children.iter().cloned().collect()) {
+                    if ids.insert(sub_id) {
+                        queue.push_back(sub_id);
+                    }
+                }
+            }
+        }
+        for id in ids.iter() {
+            if *id == from_id || subtask_roots.contains(id) {
+                continue;
+            }
+            if let Some(parent) = self.nodes[*id as usize]
+                .borrow()
+                .parents
+                .iter()
+                .find(|parent| !ids.contains(parent))
+            {
+                return Err(IrError::ParentNodeNotExist(*parent));
+            }
+        }
+
+        let order: Vec<NodeId> = ids.into_iter().collect();
+        let (plan, _) = LogicalPlan::from_pb_with_meta(
+            self.to_pb_in_order(&order),
+            PlanMeta::with_settings_of(&self.meta),
+        )?;
+
+        Ok(plan)
+    }
+
+    /// Collect the nodes reachable from the given node (included) via the nodes given by `next`.
+    fn collect_nodes<F>(&self, id: NodeId, next: F) -> BTreeSet<NodeId>
+    where
+        F: Fn(&Node) -> Vec<NodeId>,
+    {
+        let mut visited = BTreeSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(id);
+        while let Some(id) = queue.pop_front() {
+            if let Some(node) = self.get_node(id) {
+                if visited.insert(id) {
+                    queue.extend(next(&node.borrow()));
+                }
+            }
+        }
+
+        visited
+    }
+}
+
+/// An iterator over the nodes of a logical plan in a topological order, see [`LogicalPlan::iter_topo`].
+pub struct TopoIter<'a> {
+    plan: &'a LogicalPlan,
+    /// The number of the unvisited dependencies of each unvisited node
+    pending: BTreeMap<NodeId, usize>,
+    /// The nodes that depend on each node
+    dependents: BTreeMap<NodeId, Vec<NodeId>>,
+    /// The unvisited nodes of which the dependencies have all been visited
+    ready: BTreeSet<NodeId>,
+}
+
+impl<'a> Iterator for TopoIter<'a> {
+    type Item = NodeType;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = *self.ready.iter().next()?;
+        self.ready.remove(&id);
+        self.pending.remove(&id);
+        for dependent in self.dependents.get(&id).into_iter().flatten() {
+            if let Some(count) = self.pending.get_mut(dependent) {
+                *count -= 1;
+                if *count == 0 {
+                    self.ready.insert(*dependent);
+                }
+            }
+        }
+
+        self.plan.get_node(id)
+    }
+}
+
+/// A visitor of the nodes of a logical plan, e.g., an optimizer, a lowering or a validator, which
+/// visits a node only after all the nodes that it depends on, see [`LogicalPlan::accept`].
+pub trait PlanVisitor {
+    fn visit_node(&mut self, node: &Node) -> IrResult<()>;
+}
+
+pub trait AsLogical {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()>;
+}
+
+fn check_primary_key_from_pb(
+    schema: &Schema, table: &common_pb::NameOrId, col: &common_pb::NameOrId, is_entity: bool,
+) -> (bool, usize) {
+    use ir_common::generated::common::name_or_id::Item;
+
+    let mut table_name = "";
+    let mut col_name = "";
+    if let Some(item) = table.item.as_ref() {
+        match item {
+            Item::Name(name) => table_name = name.as_str(),
+            Item::Id(id) => {
+                if let Some(name) =
+                    if is_entity { schema.get_entity_name(*id) } else { schema.get_relation_name(*id) }
+                {
+                    table_name = name.as_str();
+                }
+            }
+        }
+    }
+    if let Some(item) = col.item.as_ref() {
+        match item {
+            Item::Name(name) => col_name = name.as_str(),
+            Item::Id(id) => {
+                if let Some(name) = schema.get_column_name(*id) {
+                    col_name = name.as_str();
+                }
+            }
+        }
+    }
+
+    schema.check_primary_key(table_name, col_name)
+}
+
+/// Check that the global ids looked up by an `IndexPredicate` are of the scanned labels, as decoded
+/// via the `id_encoding` of the store. Nothing is checked if the labels are not given as ids.
+fn check_indexed_global_ids(
+    idx_pred: &pb::IndexPredicate, tables: &[common_pb::NameOrId], id_encoding: &IdEncoding,
+) -> IrResult<()> {
+    let labels = tables
+        .iter()
+        .map(|table| match table.item {
+            Some(common_pb::name_or_id::Item::Id(id)) => Some(id),
+            _ => None,
+        })
+        .collect::<Option<Vec<LabelId>>>()
+        .unwrap_or_default();
+    if labels.is_empty() {
+        return Ok(());
+    }
+    // the predicates other than looking up the global ids are left to the runtime
+    let global_ids = Vec::<i64>::try_from(idx_pred.clone()).unwrap_or_default();
+    for global_id in global_ids {
+        let (label, _) = id_encoding.decode(global_id);
+        if !labels.contains(&label) {
+            return Err(IrError::InvalidGlobalId(global_id));
+        }
+    }
+
+    Ok(())
+}
+
+/// To optimize a triplet predicate of <pk, cmp, val> into an `IndexPredicate`.
+/// Notice that multiple tables allowed. If the tables have the same pk, it can be optimized into one `IndexPredicate`.
+fn triplet_to_index_predicate(
+    operators: &[common_pb::ExprOpr], tables: &Vec<common_pb::NameOrId>, is_vertex: bool, meta: &StoreMeta,
+) -> IrResult<Option<pb::IndexPredicate>> {
+    if operators.len() != 3 {
+        return Ok(None);
+    }
+    if meta.schema.is_none() {
+        return Ok(None);
+    }
+    if tables.is_empty() {
+        return Ok(None);
+    }
+    let schema = meta.schema.as_ref().unwrap();
+    let mut key = None;
+    let mut is_eq = false;
+    let mut value = None;
+    if let Some(item) = &operators.get(0).unwrap().item {
+        match item {
+            common_pb::expr_opr::Item::Var(var) => {
+                if let Some(property) = &var.property {
+                    if let Some(item) = &property.item {
+                        match item {
+                            common_pb::property::Item::Key(col) => {
+                                let mut is_pk = true;
+                                for table in tables {
+                                    let (is_pk_, num_pks) =
+                                        check_primary_key_from_pb(schema, table, col, is_vertex);
+                                    if !is_pk_ || num_pks != 1 {
+                                        is_pk = false;
+                                        break;
+                                    }
+                                }
+                                if is_pk {
+                                    key = Some(property.clone());
+                                }
+                            }
+                            _ => { /*do nothing*/ }
+                        }
+                    }
+                }
+            }
+            _ => { /*do nothing*/ }
+        }
+    };
+
+    if key.is_none() {
+        return Ok(None);
+    }
+
+    if let Some(item) = &operators.get(1).unwrap().item {
+        match item {
+            common_pb::expr_opr::Item::Logical(l) => {
+                if *l == 0 {
+                    // Eq
+                    is_eq = true;
+                }
+            }
+            _ => { /*do nothing*/ }
+        }
+    };
+
+    if !is_eq {
+        return Ok(None);
+    }
+
+    if let Some(item) = &operators.get(2).unwrap().item {
+        match item {
+            common_pb::expr_opr::Item::Const(c) => {
+                value = Some(c.clone());
+            }
+            _ => { /*do nothing*/ }
+        }
+    };
+    if value.is_none() {
+        return Ok(None);
+    }
+
+    let idx_pred = pb::IndexPredicate {
+        or_predicates: vec![pb::index_predicate::AndPredicate {
+            predicates: vec![pb::index_predicate::Triplet { key, value, cmp: None, param: None }],
+        }],
+    };
+
+    Ok(Some(idx_pred))
+}
+
+/// The cost of looking up a row via the index relative to reading a row in a full scan, as a lookup
+/// is a random access to the store while a full scan reads the rows sequentially.
+const INDEX_LOOKUP_COST: u64 = 10;
+
+/// The complexity of an operator that neither scans, expands nor joins.
+const BASE_COMPLEXITY: f64 = 1.0;
+/// The complexity of a single hop of an expansion.
+const EXPAND_COMPLEXITY: f64 = 2.0;
+/// The complexity of a join with keys.
+const JOIN_COMPLEXITY: f64 = 4.0;
+/// The complexity of a cartesian product.
+const CARTESIAN_COMPLEXITY: f64 = 16.0;
+/// The complexity of a scan whose number of rows is unknown.
+const UNKNOWN_SCAN_COMPLEXITY: f64 = 16.0;
+/// The maximal hops of a path expansion that are scored, which stand for an unbounded expansion.
+const MAX_SCORED_HOPS: i32 = 16;
+/// The fraction of the rows that are assumed to pass a predicate, as the values are not profiled.
+const PREDICATE_SELECTIVITY: f64 = 0.1;
+
+/// The complexity of an operator, see [`LogicalPlan::estimate_complexity`].
+fn get_opr_complexity(opr: &pb::logical_plan::Operator, schema: Option<&Schema>) -> f64 {
+    use pb::logical_plan::operator::Opr;
+
+    match &opr.opr {
+        Some(Opr::Scan(scan)) => get_scan_complexity(scan, schema),
+        Some(Opr::Edge(_)) => EXPAND_COMPLEXITY,
+        Some(Opr::Path(pathxpd)) => get_path_complexity(pathxpd),
+        Some(Opr::Join(join)) if is_cartesian_product(join) => CARTESIAN_COMPLEXITY,
+        Some(Opr::Join(_)) => JOIN_COMPLEXITY,
+        Some(Opr::Pattern(pattern)) => pattern
+            .sentences
+            .iter()
+            .flat_map(|sentence| sentence.binders.iter())
+            .map(|binder| match &binder.item {
+                Some(Item::Edge(_)) => EXPAND_COMPLEXITY,
+                Some(Item::Path(pathxpd)) => get_path_complexity(pathxpd),
+                _ => BASE_COMPLEXITY,
+            })
+            .sum(),
+        _ => BASE_COMPLEXITY,
+    }
+}
+
+/// The complexity of a scan, which is logarithmic to the number of the rows it reads, namely,
+/// the number of the lookups if it looks up via the index, or the number of the rows of its tables.
+fn get_scan_complexity(scan: &pb::Scan, schema: Option<&Schema>) -> f64 {
+    let rows = if let Some(idx_pred) = scan.idx_predicate.as_ref() {
+        Some(idx_pred.or_predicates.len() as u64)
+    } else {
+        schema.and_then(|schema| {
+            let tables = scan
+                .params
+                .as_ref()
+                .map(|params| params.tables.as_slice())
+                .unwrap_or(&[]);
+            if tables.is_empty() {
+                schema.get_total_entity_count()
+            } else {
+                tables
+                    .iter()
+                    .map(|table| {
+                        get_table_id_from_pb(schema, table).and_then(|label| schema.get_entity_count(label))
+                    })
+                    .sum::<Option<u64>>()
+            }
+        })
+    };
+    rows.map(|rows| BASE_COMPLEXITY + (1.0 + rows as f64).log2())
+        .unwrap_or(UNKNOWN_SCAN_COMPLEXITY)
+}
+
+/// The complexity of a path expansion, which is exponential to its maximal hops.
+fn get_path_complexity(pathxpd: &pb::PathExpand) -> f64 {
+    // the upper bound of the hops is exclusive
+    let max_hops = pathxpd
+        .hop_range
+        .as_ref()
+        .map(|range| range.upper.saturating_sub(1))
+        .unwrap_or(MAX_SCORED_HOPS)
+        .max(1)
+        .min(MAX_SCORED_HOPS);
+    EXPAND_COMPLEXITY * 2f64.powi(max_hops - 1)
+}
+
+/// The number of the rows of a scan, namely, the number of the keys it looks up via the index, or the
+/// number of the rows of its tables that pass its predicate, see [`LogicalPlan::estimate_cardinality`].
+fn get_scan_cardinality(scan: &pb::Scan, schema: &Schema) -> Option<f64> {
+    if let Some(idx_pred) = scan.idx_predicate.as_ref() {
+        return Some(idx_pred.or_predicates.len() as f64);
+    }
+    let tables = scan
+        .params
+        .as_ref()
+        .map(|params| params.tables.as_slice())
+        .unwrap_or(&[]);
+    let is_edge = scan.scan_opt == pb::scan::ScanOpt::Edge as i32;
+    let rows = if tables.is_empty() {
+        if is_edge {
+            schema.get_total_relation_count()
+        } else {
+            schema.get_total_entity_count()
+        }
+    } else {
+        tables
+            .iter()
+            .map(|table| {
+                get_table_id_from_pb(schema, table).and_then(|label| {
+                    if is_edge {
+                        schema.get_relation_count(label)
+                    } else {
+                        schema.get_entity_count(label)
+                    }
+                })
+            })
+            .sum::<Option<u64>>()
+    };
+
+    rows.map(|rows| rows as f64 * get_selectivity(scan.params.as_ref()))
+}
+
+/// The average number of the relations that an expansion follows from a vertex, namely, the number of
+/// the relations of its tables over the number of all the entities, which doubles for both directions.
+fn get_expand_degree(edge: &pb::EdgeExpand, schema: &Schema) -> Option<f64> {
+    let tables = edge
+        .params
+        .as_ref()
+        .map(|params| params.tables.as_slice())
+        .unwrap_or(&[]);
+    let relations = if tables.is_empty() {
+        schema.get_total_relation_count()
+    } else {
+        tables
+            .iter()
+            .map(|table| {
+                get_table_id_from_pb(schema, table).and_then(|label| schema.get_relation_count(label))
+            })
+            .sum::<Option<u64>>()
+    }?;
+    let entities = schema.get_total_entity_count()?;
+    if entities == 0 {
+        return Some(0.0);
+    }
+    let degree = relations as f64 / entities as f64;
+    if edge.direction == pb::edge_expand::Direction::Both as i32 {
+        Some(degree * 2.0)
+    } else {
+        Some(degree)
+    }
+}
+
+/// The fraction of the rows that pass the predicate of the query parameters, if any.
+fn get_selectivity(params: Option<&pb::QueryParams>) -> f64 {
+    if params
+        .map(|params| params.predicate.is_some())
+        .unwrap_or(false)
+    {
+        PREDICATE_SELECTIVITY
+    } else {
+        1.0
+    }
+}
+
+fn get_limited_cardinality(input: f64, range: Option<&pb::Range>) -> f64 {
+    range
+        .map(|range| input.min(range.upper.saturating_sub(range.lower).max(0) as f64))
+        .unwrap_or(input)
+}
+
+/// The choice between looking up the vertices via the index, and a full scan of the vertices that
+/// filters them by the equivalent `within` predicate, of a `Scan` with an `IndexPredicate`.
+#[derive(Debug)]
+enum ScanChoice {
+    IndexLookup { lookups: u64, rows: u64 },
+    FullScan { lookups: u64, rows: u64, within: common_pb::Expression },
+}
+
+impl fmt::Display for ScanChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanChoice::IndexLookup { lookups, rows } => {
+                write!(
+                    f,
+                    "scan: index lookup of {} key(s) rather than a full scan of {} row(s)",
+                    lookups, rows
+                )
+            }
+            ScanChoice::FullScan { lookups, rows, .. } => {
+                write!(
+                    f,
+                    "scan: full scan of {} row(s) rather than an index lookup of {} key(s)",
+                    rows, lookups
+                )
+            }
+        }
+    }
+}
+
+/// Choose between many point lookups via the index predicate and a filtered full scan of the `tables`
+/// (all the vertices if empty), whichever is estimated to be cheaper by the statistics of the store.
+/// The choice is not made, i.e., the index is always used, if the numbers of the vertices are unknown,
+/// or the index predicate cannot be turned into a `within` predicate, or the number of lookups is not
+/// known until the parameters of the plan are bound.
+fn choose_vertex_scan(
+    idx_pred: &pb::IndexPredicate, tables: &[common_pb::NameOrId], schema: &Schema,
+) -> Option<ScanChoice> {
+    if idx_pred
+        .or_predicates
+        .iter()
+        .flat_map(|and_pred| and_pred.predicates.iter())
+        .any(|triplet| triplet.param.is_some())
+    {
+        return None;
+    }
+    let rows = if tables.is_empty() {
+        schema.get_total_entity_count()?
+    } else {
+        tables
+            .iter()
+            .map(|table| {
+                get_table_id_from_pb(schema, table).and_then(|label| schema.get_entity_count(label))
+            })
+            .sum::<Option<u64>>()?
+    };
+    let lookups = idx_pred.or_predicates.len() as u64;
+    if lookups.saturating_mul(INDEX_LOOKUP_COST) < rows {
+        Some(ScanChoice::IndexLookup { lookups, rows })
+    } else {
+        let within = index_predicate_to_within(idx_pred)?;
+        Some(ScanChoice::FullScan { lookups, rows, within })
+    }
+}
+
+/// Turn the index predicate of `key == v1 || key == v2 || ...` on the same key into the equivalent
+/// predicate of `key within [v1, v2, ...]`, if the values are of the same type.
+fn index_predicate_to_within(idx_pred: &pb::IndexPredicate) -> Option<common_pb::Expression> {
+    use common_pb::value::Item;
+
+    let mut key = None;
+    let mut values = vec![];
+    for and_pred in &idx_pred.or_predicates {
+        if and_pred.predicates.len() != 1 {
+            return None;
+        }
+        let triplet = &and_pred.predicates[0];
+        if key.is_none() {
+            key = triplet.key.clone();
+        } else if key != triplet.key {
+            return None;
+        }
+        values.push(triplet.value.as_ref()?.item.as_ref()?);
+    }
+    let array = match values.first()? {
+        Item::I32(_) => Item::I32Array(common_pb::I32Array {
+            item: values
+                .iter()
+                .map(|value| if let Item::I32(v) = value { Some(*v) } else { None })
+                .collect::<Option<_>>()?,
+        }),
+        Item::I64(_) => Item::I64Array(common_pb::I64Array {
+            item: values
+                .iter()
+                .map(|value| if let Item::I64(v) = value { Some(*v) } else { None })
+                .collect::<Option<_>>()?,
+        }),
+        Item::F64(_) => Item::F64Array(common_pb::DoubleArray {
+            item: values
+                .iter()
+                .map(|value| if let Item::F64(v) = value { Some(*v) } else { None })
+                .collect::<Option<_>>()?,
+        }),
+        Item::Str(_) => Item::StrArray(common_pb::StringArray {
+            item: values
+                .iter()
+                .map(|value| if let Item::Str(v) = value { Some(v.clone()) } else { None })
+                .collect::<Option<_>>()?,
+        }),
+        _ => return None,
+    };
+
+    Some(common_pb::Expression {
+        operators: vec![
+            common_pb::Variable { tag: None, property: key, node_type: None }.into(),
+            common_pb::Logical::Within.into(),
+            common_pb::Value { item: Some(array) }.into(),
+        ],
+    })
+}
+
+fn get_table_id_from_pb(schema: &Schema, name: &common_pb::NameOrId) -> Option<KeyId> {
+    name.item.as_ref().and_then(|item| match item {
+        common_pb::name_or_id::Item::Name(name) => schema.get_table_id(name),
+        common_pb::name_or_id::Item::Id(id) => Some(*id),
+    })
+}
+
+/// The id of the column in the schema, or `INVALID_META_ID` if the column is absent from the schema,
+/// in which case the column is not matched by any data, and is thus warned.
+fn get_column_id_from_pb(schema: &Schema, name: &common_pb::NameOrId) -> KeyId {
+    name.item
+        .as_ref()
+        .and_then(|item| match item {
+            common_pb::name_or_id::Item::Name(name) => schema.get_column_id(name),
+            common_pb::name_or_id::Item::Id(id) => Some(*id),
+        })
+        .unwrap_or_else(|| {
+            warn!("column {:?} does not exist in the schema, which is taken as an invalid id", name);
+            INVALID_META_ID
+        })
+}
+
+fn preprocess_var(
+    var: &mut common_pb::Variable, meta: &StoreMeta, plan_meta: &mut PlanMeta, is_predicate: bool,
+) -> IrResult<()> {
+    let tag =
+        if let Some(tag) = var.tag.as_mut() { Some(get_or_set_tag_id(tag, plan_meta)?) } else { None };
+    // The tag refers to a value computed by the plan, e.g., the result of an aggregate function,
+    // which has no properties other than its length
+    if let (Some(tag_id), Some(property)) = (tag, var.property.as_ref()) {
+        if let Some(data_type) = plan_meta.get_tag_type(tag_id) {
+            if !matches!(property.item, Some(common_pb::property::Item::Len(_))) {
+                return Err(IrError::Unsupported(format!(
+                    "the property {:?} of the tag {:?} that refers to {:?} values",
+                    property, tag_id, data_type
+                )));
+            }
+        }
+    }
+    let mut node_meta = plan_meta.curr_node_meta_mut();
+    if let Some(property) = var.property.as_mut() {
+        if let Some(key) = property.item.as_mut() {
+            match key {
+                common_pb::property::Item::Key(key) => {
+                    if let Some(schema) = &meta.schema {
+                        if schema.is_column_id() {
+                            let new_key = get_column_id_from_pb(schema, key).into();
+                            debug!("column: {:?} -> {:?}", key, new_key);
+                            *key = new_key;
+                        }
+                    }
+                    // A column that only presents for a predicate should not be materialized
+                    if !is_predicate {
+                        debug!("add column ({:?}) to {:?}", key, var.tag);
+                        node_meta.insert_tag_column(tag, key.clone().try_into()?);
+                    }
+                }
+                common_pb::property::Item::All(_) => {
+                    node_meta.set_tag_columns_opt(tag, ColumnsOpt::All(256))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn preprocess_label(
+    label: &mut common_pb::Value, meta: &StoreMeta, _plan_meta: &mut PlanMeta,
+) -> IrResult<()> {
+    if let Some(schema) = &meta.schema {
+        // A Const needs to be preprocessed only if it is while comparing a label (table)
+        if schema.is_table_id() {
+            if let Some(item) = label.item.as_mut() {
+                match item {
+                    common_pb::value::Item::Str(name) => {
+                        let new_item = common_pb::value::Item::I32(
+                            schema
+                                .get_table_id(name)
+                                .ok_or(IrError::TableNotExist(NameOrId::Str(name.to_string())))?,
+                        );
+                        debug!("table: {:?} -> {:?}", item, new_item);
+                        *item = new_item;
+                    }
+                    common_pb::value::Item::StrArray(names) => {
+                        let new_item = common_pb::value::Item::I32Array(common_pb::I32Array {
+                            item: names
+                                .item
+                                .iter()
+                                .map(|name| {
+                                    schema
+                                        .get_table_id(name)
+                                        .ok_or(IrError::TableNotExist(NameOrId::Str(name.to_string())))
+                                })
+                                .collect::<IrResult<Vec<_>>>()?,
+                        });
+                        debug!("table: {:?} -> {:?}", item, new_item);
+                        *item = new_item;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn preprocess_expression(
+    expr: &mut common_pb::Expression, meta: &StoreMeta, plan_meta: &mut PlanMeta, is_predicate: bool,
+) -> IrResult<()> {
+    let mut count = 0;
+    for opr in expr.operators.iter_mut() {
+        if let Some(item) = opr.item.as_mut() {
+            match item {
+                common_pb::expr_opr::Item::Var(var) => {
+                    if let Some(property) = var.property.as_mut() {
+                        if let Some(key) = property.item.as_mut() {
+                            match key {
+                                common_pb::property::Item::Label(_) => count = 1,
+                                _ => count = 0,
+                            }
+                        }
+                    }
+                    preprocess_var(var, meta, plan_meta, is_predicate)?;
+                }
+                common_pb::expr_opr::Item::Logical(l) => {
+                    if count == 1 {
+                        // means previous one is LabelKey
+                        // The logical operator of Eq, Ne, Lt, Le, Gt, Ge, Within, Without
+                        if *l >= 0 && *l <= 7 {
+                            count = 2; // indicates LabelKey <cmp>
+                        }
+                    } else {
+                        count = 0;
+                    }
+                }
+                common_pb::expr_opr::Item::Const(c) => {
+                    if count == 2 {
+                        // indicates LabelKey <cmp> labelValue
+                        preprocess_label(c, meta, plan_meta)?;
+                    }
+                    count = 0;
+                }
+                common_pb::expr_opr::Item::Vars(vars) | common_pb::expr_opr::Item::VarMap(vars) => {
+                    for var in &mut vars.keys {
+                        preprocess_var(var, meta, plan_meta, false)?;
+                    }
+                    count = 0;
+                }
+                _ => count = 0,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn preprocess_tables(tables: &mut [common_pb::NameOrId], meta: &StoreMeta) -> IrResult<()> {
+    if let Some(schema) = &meta.schema {
+        if schema.is_table_id() {
+            for table in tables.iter_mut() {
+                let new_table = get_table_id_from_pb(schema, table)
+                    .ok_or(IrError::TableNotExist(table.clone().try_into()?))?
+                    .into();
+                debug!("table: {:?} -> {:?}", table, new_table);
+                *table = new_table;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reorder the terms of the predicate by their estimated costs if it is enabled for the plan (see
+/// [`reorder_predicate`]), where the current node is annotated with the chosen order.
+fn reorder_predicate_terms(pred: &mut common_pb::Expression, plan_meta: &mut PlanMeta) -> IrResult<()> {
+    if plan_meta.is_reorder_predicates() {
+        if let Some(order) = reorder_predicate(pred)? {
+            let annotation = if order.is_empty() {
+                "predicate: reorder the terms by their estimated costs".to_string()
+            } else {
+                format!("predicate: reorder the terms of `&&` as {:?} by their estimated costs", order)
+            };
+            plan_meta.annotate_node(plan_meta.get_curr_node(), annotation);
+        }
+    }
+    Ok(())
+}
+
+fn preprocess_params(
+    params: &mut pb::QueryParams, meta: &StoreMeta, plan_meta: &mut PlanMeta,
+) -> IrResult<()> {
+    if let Some(pred) = &mut params.predicate {
+        preprocess_expression(pred, meta, plan_meta, true)?;
+        reorder_predicate_terms(pred, plan_meta)?;
+    }
+    preprocess_tables(&mut params.tables, meta)?;
+    for degree_pred in params.degree_predicates.iter_mut() {
+        let range = degree_pred
+            .range
+            .as_ref()
+            .ok_or(IrError::MissingData("DegreePredicate::range".to_string()))?;
+        if range.lower < 0 || range.lower >= range.upper {
+            return Err(IrError::InvalidRange(range.lower, range.upper));
+        }
+        preprocess_tables(&mut degree_pred.tables, meta)?;
+    }
+    let mut node_meta = plan_meta.curr_node_meta_mut();
+    if params.is_all_columns {
+        node_meta.set_columns_opt(ColumnsOpt::All(256));
+    } else {
+        for column in params.columns.iter_mut() {
+            if let Some(schema) = &meta.schema {
+                if schema.is_column_id() {
+                    let column_id = get_column_id_from_pb(schema, column).into();
+                    debug!("column: {:?} -> {:?}", column, column_id);
+                    *column = column_id;
+                }
+            }
+            debug!("add column ({:?}) to HEAD", column);
+            node_meta.insert_column(column.clone().try_into()?);
+        }
+    }
+
+    Ok(())
+}
+
+fn get_or_set_tag_id(tag_pb: &mut common_pb::NameOrId, plan_meta: &mut PlanMeta) -> IrResult<TagId> {
+    use common_pb::name_or_id::Item;
+    if let Some(tag_item) = tag_pb.item.as_mut() {
+        let (_, tag_id) = match tag_item {
+            Item::Name(tag) => plan_meta.get_or_set_tag_id(tag),
+            Item::Id(id) => {
+                plan_meta.set_max_tag_id(*id as TagId + 1);
+                (true, *id as TagId)
+            }
+        };
+        *tag_pb = (tag_id as i32).into();
+
+        Ok(tag_id)
+    } else {
+        Err(IrError::MissingData("NameOrId::Item".to_string()))
+    }
+}
+
+/// Process the columns' meta in `plan_meta` such that the columns can be added to
+/// corresponding nodes.
+fn process_columns_meta(plan_meta: &mut PlanMeta, is_late_project: bool) -> IrResult<()> {
+    let tag_columns = plan_meta
+        .get_curr_node_meta()
+        .unwrap()
+        .get_tag_columns();
+    // late project currently only handles the case of one single tag
+    if !is_late_project || tag_columns.len() > 1 {
+        for (tag, columns) in tag_columns.into_iter() {
+            let mut meta = plan_meta.tag_nodes_meta_mut(tag)?;
+            if columns.is_all() {
+                meta.set_columns_opt(ColumnsOpt::All(256));
+            } else if columns.len() > 0 {
+                for col in columns.get() {
+                    meta.insert_column(col);
+                }
+            }
+        }
+    } else {
+        // apply late project that does not record the columns in the corresponding nodes
+    }
+
+    Ok(())
+}
+
+impl AsLogical for pb::Project {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        use common_pb::expr_opr::Item;
+        // Notice some special cases of Project when project tag(s) only:
+        // 1. when project("@a"), it indicates to move the `Head` to the nodes referred by the tag `a`
+        // 2. when project("@a").as("b") (or, project multiple columns with new aliases).
+        //    In this case, when we want to access the property of "b" (assuming "a"/"b" refers to a vertex), we actually need to refer to the entries of "a".
+        //    e.g., we may want to Project the 0-th and 1-th columns on r1{v0, v1, v2}, and we get r2{v0, v1};
+        //    When we want to project properties on v0, we may need to refer to the source that generates v0, rather than the Project operator
+
+        let len = self.mappings.len();
+        for mapping in self.mappings.iter_mut() {
+            // the flag to indicate if the project is to project tags only, e.g., project("@a")
+            let mut is_project_tag_only = false;
+            let mut tag_nodes = vec![];
+            let mut data_type = None;
+            if let Some(expr) = &mut mapping.expr {
+                let mut is_project_as_head = false;
+                let curr_node = plan_meta.get_curr_node();
+                preprocess_expression(expr, meta, plan_meta, false)?;
+                if expr.operators.len() == 1 {
+                    if let common_pb::ExprOpr { item: Some(Item::Var(var)), .. } =
+                        expr.operators.get_mut(0).unwrap()
+                    {
+                        if let Some(tag) = var.tag.as_mut() {
+                            let tag_id = get_or_set_tag_id(tag, plan_meta)?;
+                            if var.property.is_none() {
+                                tag_nodes = plan_meta.get_tag_nodes(tag_id).to_vec();
+                                if !tag_nodes.is_empty() {
+                                    // the case of project to some tagged columns.
+                                    // if alias exists, e.g., `project("@a").as("b")`,
+                                    // when visiting "b", should actually visit the nodes referred by the tag `a`
+                                    // i.e., `rename` for the nodes from `a` to `b`
+                                    is_project_tag_only = true;
+                                    if len == 1 {
+                                        // furthermore, the case of `project("@a")`, must refer to the nodes referred by the tag,
+                                        plan_meta.refer_to_nodes(curr_node, tag_nodes.clone());
+                                        is_project_as_head = true;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                data_type = get_expr_data_type(expr, plan_meta);
+                if !is_project_as_head {
+                    process_columns_meta(plan_meta, false)?;
+                    // projection alters the head of the record unless it is the case of project_as_head
+                    plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
+                }
+            }
+            if let Some(alias) = mapping.alias.as_mut() {
+                let alias_id = get_or_set_tag_id(alias, plan_meta)?;
+                if is_project_tag_only {
+                    // "rename" for the ori_tag_nodes
+                    plan_meta.set_tag_nodes(alias_id, tag_nodes);
+                } else {
+                    plan_meta.set_tag_nodes(alias_id, vec![plan_meta.get_curr_node()]);
+                }
+                if let Some(data_type) = data_type {
+                    plan_meta.set_tag_type(alias_id, data_type);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::Select {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        if let Some(pred) = self.predicate.as_mut() {
+            // the columns will be added to the current node rather than tagged nodes
+            // thus, can lazy fetched the columns upon filtering
+            preprocess_expression(pred, meta, plan_meta, false)?;
+            reorder_predicate_terms(pred, plan_meta)?;
+            process_columns_meta(plan_meta, true)?;
+            Ok(())
+        } else {
+            Err(IrError::MissingData("`pb::Select::predicate`".to_string()))
+        }
+    }
+}
+
+/// Explain the ordering pairs as `BY key1 ORDER1, key2 ORDER2, ...`, where the tags are named.
+fn explain_ordering(pairs: &[pb::order_by::OrderingPair], tag_names: &BTreeMap<TagId, String>) -> String {
+    let order: pb::logical_plan::Operator =
+        pb::OrderBy { pairs: pairs.to_vec(), limit: None, per_partition: false }.into();
+    explain_details(&name_tags(&order, tag_names))
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
+/// Whether the operator outputs the records in the order of its input, such that the ordering
+/// established by a preceding `OrderBy` is kept. Note that the operators that change the head,
+/// or shuffle the records, e.g., `Project` and `EdgeExpand`, do not preserve the order.
+fn is_order_preserving(opr: &pb::logical_plan::Operator) -> bool {
+    use pb::logical_plan::operator::Opr;
+
+    matches!(opr.opr, Some(Opr::Select(_)) | Some(Opr::Limit(_)) | Some(Opr::As(_)) | Some(Opr::Sink(_)))
+}
+
+/// The tags that present in the output of the operator, given the parent nodes as its input,
+/// where the aliases of the operator must have been preprocessed.
+fn get_output_tags(
+    opr: &pb::logical_plan::Operator, parent_ids: &[NodeId], plan_meta: &PlanMeta,
+) -> BTreeSet<TagId> {
+    use pb::logical_plan::operator::Opr;
+
+    if let Some(Opr::Join(join)) = &opr.opr {
+        return get_join_output_tags(join, parent_ids, plan_meta);
+    }
+    let mut tags: BTreeSet<TagId> = parent_ids
+        .iter()
+        .flat_map(|id| plan_meta.get_node_tags(*id))
+        .collect();
+    match &opr.opr {
+        Some(Opr::Project(project)) if !project.is_append => tags.clear(),
+        Some(Opr::GroupBy(_)) | Some(Opr::Count(_)) => tags.clear(),
+        _ => {}
+    }
+    tags.extend(
+        get_aliases(opr)
+            .into_iter()
+            .filter_map(|alias| get_tag_id(alias, plan_meta)),
+    );
+
+    tags
+}
+
+/// Whether the join is a cartesian product, i.e., a `Times` join, or a join without keys.
+fn is_cartesian_product(join: &pb::Join) -> bool {
+    use pb::join::JoinKind;
+
+    join.kind == JoinKind::Times as i32
+        || (join.left_keys.is_empty()
+            && join.kind != JoinKind::Semi as i32
+            && join.kind != JoinKind::Anti as i32)
+}
+
+/// The aliases given by the operator, which introduce the tags to its output.
+fn get_aliases(opr: &pb::logical_plan::Operator) -> Vec<&common_pb::NameOrId> {
+    use pb::logical_plan::operator::Opr;
+
+    let aliases: Vec<Option<&common_pb::NameOrId>> = match &opr.opr {
+        Some(Opr::Project(project)) => project
+            .mappings
+            .iter()
+            .map(|mapping| mapping.alias.as_ref())
+            .collect(),
+        Some(Opr::GroupBy(group)) => group
+            .mappings
+            .iter()
+            .map(|mapping| mapping.alias.as_ref())
+            .chain(
+                group
+                    .functions
+                    .iter()
+                    .map(|agg_fn| agg_fn.alias.as_ref()),
+            )
+            .collect(),
+        Some(Opr::Count(count)) => vec![count.alias.as_ref()],
+        Some(Opr::Scan(scan)) => vec![scan.alias.as_ref()],
+        Some(Opr::Edge(edgexpd)) => vec![edgexpd.alias.as_ref()],
+        Some(Opr::Path(pathxpd)) => vec![pathxpd.alias.as_ref()],
+        Some(Opr::Vertex(getv)) => vec![getv.alias.as_ref(), getv.end_alias.as_ref()],
+        Some(Opr::As(as_opr)) => vec![as_opr.alias.as_ref()],
+        Some(Opr::Apply(apply)) => vec![apply.alias.as_ref()],
+        Some(Opr::Unfold(unfold)) => vec![unfold.alias.as_ref()],
+        _ => vec![],
+    };
+
+    aliases.into_iter().flatten().collect()
+}
+
+/// The query parameters of the operator that scans, expands or gets the entities, each with whether the
+/// labels (tables) of the parameters are of the entities, rather than the relations, where the absent
+/// parameters are given as `None`.
+fn get_opr_params(opr: &pb::logical_plan::Operator) -> Vec<(Option<&pb::QueryParams>, bool)> {
+    use pb::logical_plan::operator::Opr;
+
+    fn get_path_params(pathxpd: &pb::PathExpand) -> Vec<(Option<&pb::QueryParams>, bool)> {
+        let mut all_params = vec![];
+        if let Some(base) = pathxpd.base.as_ref() {
+            if let Some(edgexpd) = base.edge_expand.as_ref() {
+                all_params.push((edgexpd.params.as_ref(), false));
+            }
+            if let Some(getv) = base.get_v.as_ref() {
+                all_params.push((getv.params.as_ref(), true));
+            }
+        }
+        all_params
+    }
+
+    match opr.opr.as_ref() {
+        Some(Opr::Scan(scan)) => {
+            vec![(scan.params.as_ref(), scan.scan_opt != pb::scan::ScanOpt::Edge as i32)]
+        }
+        Some(Opr::Edge(edgexpd)) => vec![(edgexpd.params.as_ref(), false)],
+        Some(Opr::Vertex(getv)) => vec![(getv.params.as_ref(), true)],
+        Some(Opr::Path(pathxpd)) => get_path_params(pathxpd),
+        Some(Opr::Pattern(pattern)) => pattern
+            .sentences
+            .iter()
+            .flat_map(|sentence| sentence.binders.iter())
+            .flat_map(|binder| match binder.item.as_ref() {
+                Some(Item::Edge(edgexpd)) => vec![(edgexpd.params.as_ref(), false)],
+                Some(Item::Vertex(getv)) => vec![(getv.params.as_ref(), true)],
+                Some(Item::Path(pathxpd)) => get_path_params(pathxpd),
+                _ => vec![],
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Constrain the scans and the expansions of the operator, including those of the binders of a pattern,
+/// to the labels and the predicates of the view, see [`LogicalPlan::set_view`].
+fn constrain_by_view(
+    opr: &mut pb::logical_plan::Operator, view: &pb::GraphView, schema: Option<&Schema>,
+) -> IrResult<()> {
+    use pb::logical_plan::operator::Opr;
+
+    match opr.opr.as_mut() {
+        // the relational tables are out of any view of the graph
+        Some(Opr::Scan(scan)) if scan.scan_opt != pb::scan::ScanOpt::Table as i32 => {
+            let is_entity = scan.scan_opt == pb::scan::ScanOpt::Vertex as i32;
+            constrain_params(&mut scan.params, view, is_entity, schema);
+        }
+        Some(Opr::Edge(edgexpd)) => constrain_edge_expand(edgexpd, view, schema)?,
+        Some(Opr::Vertex(getv)) => constrain_params(&mut getv.params, view, true, schema),
+        Some(Opr::Path(pathxpd)) => constrain_path_expand(pathxpd, view, schema)?,
+        Some(Opr::Pattern(pattern)) => {
+            for binder in pattern
+                .sentences
+                .iter_mut()
+                .flat_map(|sentence| sentence.binders.iter_mut())
+            {
+                match binder.item.as_mut() {
+                    Some(Item::Edge(edgexpd)) => constrain_edge_expand(edgexpd, view, schema)?,
+                    Some(Item::Vertex(getv)) => constrain_params(&mut getv.params, view, true, schema),
+                    Some(Item::Path(pathxpd)) => constrain_path_expand(pathxpd, view, schema)?,
+                    None => {}
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn constrain_edge_expand(
+    edgexpd: &mut pb::EdgeExpand, view: &pb::GraphView, schema: Option<&Schema>,
+) -> IrResult<()> {
+    constrain_params(&mut edgexpd.params, view, false, schema);
+    if edgexpd.expand_opt != pb::edge_expand::ExpandOpt::Edge as i32 {
+        if view.vertex_predicate.is_some() {
+            return Err(IrError::Unsupported(format!(
+                "expanding to the vertices within the view {:?} of a vertex predicate",
+                view.name
+            )));
+        }
+        if !constrain_tables(&mut edgexpd.vertex_tables, &view.vertex_labels, true, schema) {
+            // none of the adjacent vertices is in the view
+            let params = edgexpd
+                .params
+                .get_or_insert_with(empty_query_params);
+            conjoin_predicate(params, false_expr());
+        }
+    }
+
+    Ok(())
+}
+
+fn constrain_path_expand(
+    pathxpd: &mut pb::PathExpand, view: &pb::GraphView, schema: Option<&Schema>,
+) -> IrResult<()> {
+    if let Some(base) = pathxpd.base.as_mut() {
+        if let Some(edgexpd) = base.edge_expand.as_mut() {
+            constrain_edge_expand(edgexpd, view, schema)?;
+        }
+        if let Some(getv) = base.get_v.as_mut() {
+            constrain_params(&mut getv.params, view, true, schema);
+        }
+    }
+
+    Ok(())
+}
+
+/// Constrain the query parameters of the vertices (if `is_entity`) or the edges to the view.
+fn constrain_params(
+    params: &mut Option<pb::QueryParams>, view: &pb::GraphView, is_entity: bool, schema: Option<&Schema>,
+) {
+    let (labels, predicate) = if is_entity {
+        (&view.vertex_labels, view.vertex_predicate.as_ref())
+    } else {
+        (&view.edge_labels, view.edge_predicate.as_ref())
+    };
+    if labels.is_empty() && predicate.is_none() {
+        return;
+    }
+    let params = params.get_or_insert_with(empty_query_params);
+    if !constrain_tables(&mut params.tables, labels, is_entity, schema) {
+        conjoin_predicate(params, false_expr());
+    } else if let Some(predicate) = predicate {
+        conjoin_predicate(params, predicate.clone());
+    }
+}
+
+/// Narrow the tables down to the labels of a view, or take the labels if the tables are empty, i.e., of
+/// any label. It returns false if none of the tables is of the labels, where the tables are kept as is.
+fn constrain_tables(
+    tables: &mut Vec<common_pb::NameOrId>, labels: &[common_pb::NameOrId], is_entity: bool,
+    schema: Option<&Schema>,
+) -> bool {
+    if labels.is_empty() {
+        return true;
+    }
+    if tables.is_empty() {
+        *tables = labels.to_vec();
+        return true;
+    }
+    let label_names: BTreeSet<String> = get_table_names(labels, is_entity, schema)
+        .into_iter()
+        .collect();
+    let constrained: Vec<common_pb::NameOrId> = tables
+        .iter()
+        .zip(get_table_names(tables, is_entity, schema))
+        .filter(|(_, name)| label_names.contains(name))
+        .map(|(table, _)| table.clone())
+        .collect();
+    if constrained.is_empty() {
+        false
+    } else {
+        *tables = constrained;
+        true
+    }
+}
+
+fn conjoin_predicate(params: &mut pb::QueryParams, predicate: common_pb::Expression) {
+    params.predicate = Some(match params.predicate.take() {
+        Some(old_predicate) => combine_exprs(old_predicate, predicate),
+        None => predicate,
+    });
+}
+
+fn empty_query_params() -> pb::QueryParams {
+    pb::QueryParams {
+        tables: vec![],
+        columns: vec![],
+        is_all_columns: false,
+        limit: None,
+        predicate: None,
+        sample_ratio: 1.0,
+        extra: HashMap::new(),
+        degree_predicates: vec![],
+    }
+}
+
+fn false_expr() -> common_pb::Expression {
+    common_pb::Expression { operators: vec![common_pb::Value::from(false).into()] }
+}
+
+/// The names of the labels (tables), which may have been given by their ids as per the schema, where the
+/// ids are of the entities or the relations as `is_entity`. An id absent from the schema is named as `#id`,
+/// which is thus out of any read scope.
+fn get_table_names(
+    tables: &[common_pb::NameOrId], is_entity: bool, schema: Option<&Schema>,
+) -> Vec<String> {
+    tables
+        .iter()
+        .map(|table| match table.item.as_ref() {
+            Some(common_pb::name_or_id::Item::Name(name)) => name.clone(),
+            Some(common_pb::name_or_id::Item::Id(id)) => schema
+                .and_then(|schema| {
+                    if is_entity {
+                        schema.get_entity_name(*id)
+                    } else {
+                        schema.get_relation_name(*id)
+                    }
+                })
+                .cloned()
+                .unwrap_or_else(|| format!("#{}", id)),
+            None => String::new(),
+        })
+        .collect()
+}
+
+/// The name of the property (column), which may have been given by its id as per the schema.
+fn get_column_name(column: &common_pb::NameOrId, schema: Option<&Schema>) -> String {
+    match column.item.as_ref() {
+        Some(common_pb::name_or_id::Item::Name(name)) => name.clone(),
+        Some(common_pb::name_or_id::Item::Id(id)) => schema
+            .and_then(|schema| schema.get_column_name(*id))
+            .cloned()
+            .unwrap_or_else(|| format!("#{}", id)),
+        None => String::new(),
+    }
+}
+
+/// The read scope of a plan, as the properties that may be read of each label, where an empty set of
+/// the properties allows all of them.
+struct ReadScopeIndex<'a> {
+    labels: BTreeMap<&'a str, BTreeSet<&'a str>>,
+}
+
+impl<'a> From<&'a pb::ReadScope> for ReadScopeIndex<'a> {
+    fn from(scope: &'a pb::ReadScope) -> Self {
+        let mut labels: BTreeMap<&'a str, BTreeSet<&'a str>> = BTreeMap::new();
+        for label in scope.labels.iter() {
+            labels
+                .entry(label.label.as_str())
+                .or_default()
+                .extend(label.properties.iter().map(|p| p.as_str()));
+        }
+        ReadScopeIndex { labels }
+    }
+}
+
+impl<'a> ReadScopeIndex<'a> {
+    fn check_labels(&self, labels: &[String]) -> IrResult<()> {
+        if labels.is_empty() {
+            return Err(IrError::AccessDenied("the entities of any label are read".to_string()));
+        }
+        match labels
+            .iter()
+            .find(|label| !self.labels.contains_key(label.as_str()))
+        {
+            Some(label) => Err(IrError::AccessDenied(format!("the label {:?} is read", label))),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the property can be read of each of the labels, or of each label of the scope if the
+    /// labels are unknown.
+    fn check_property(
+        &self, labels: Option<&[String]>, property: &common_pb::Property, schema: Option<&Schema>,
+    ) -> IrResult<()> {
+        use common_pb::property::Item;
+
+        let scope_labels: Vec<&str> = match labels {
+            Some(labels) => labels
+                .iter()
+                .map(|label| label.as_str())
+                .collect(),
+            None => self.labels.keys().cloned().collect(),
+        };
+        for label in scope_labels {
+            let properties = self
+                .labels
+                .get(label)
+                .ok_or_else(|| IrError::AccessDenied(format!("the label {:?} is read", label)))?;
+            if properties.is_empty() {
+                continue;
+            }
+            match property.item.as_ref() {
+                Some(Item::Key(key)) => {
+                    let key = get_column_name(key, schema);
+                    if !properties.contains(key.as_str()) {
+                        return Err(IrError::AccessDenied(format!(
+                            "the property {:?} of the label {:?} is read",
+                            key, label
+                        )));
+                    }
+                }
+                Some(Item::All(_)) => {
+                    return Err(IrError::AccessDenied(format!(
+                        "all the properties of the label {:?} are read",
+                        label
+                    )))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the columns of the query parameters can be read of each of the labels
+    fn check_columns(
+        &self, labels: &[String], params: &pb::QueryParams, schema: Option<&Schema>,
+    ) -> IrResult<()> {
+        if params.is_all_columns {
+            let all =
+                common_pb::Property { item: Some(common_pb::property::Item::All(common_pb::AllKey {})) };
+            self.check_property(Some(labels), &all, schema)?;
+        }
+        for column in params.columns.iter() {
+            let key = common_pb::Property { item: Some(common_pb::property::Item::Key(column.clone())) };
+            self.check_property(Some(labels), &key, schema)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The variables referred by the operator, namely, by its expressions and keys, where a tag that is
+/// referred otherwise, e.g., as the starting vertices of an expansion, is given as a variable of the
+/// tag only. It returns `None` if the operator refers to all the tags, i.e., a sink of no tags.
+fn get_referred_vars(opr: &pb::logical_plan::Operator) -> Option<Vec<common_pb::Variable>> {
+    use pb::logical_plan::operator::Opr;
+
+    let mut tags: Vec<&common_pb::NameOrId> = vec![];
+    let mut exprs: Vec<&common_pb::Expression> = vec![];
+    let mut vars: Vec<&common_pb::Variable> = vec![];
+    match &opr.opr {
+        Some(Opr::Project(project)) => exprs.extend(
+            project
+                .mappings
+                .iter()
+                .filter_map(|mapping| mapping.expr.as_ref()),
+        ),
+        Some(Opr::Select(select)) => exprs.extend(select.predicate.as_ref()),
+        Some(Opr::OrderBy(order)) => vars.extend(
+            order
+                .pairs
+                .iter()
+                .filter_map(|pair| pair.key.as_ref()),
+        ),
+        Some(Opr::Dedup(dedup)) => vars.extend(dedup.keys.iter()),
+        Some(Opr::GroupBy(group)) => {
+            vars.extend(
+                group
+                    .mappings
+                    .iter()
+                    .filter_map(|mapping| mapping.key.as_ref()),
+            );
+            vars.extend(
+                group
+                    .functions
+                    .iter()
+                    .flat_map(|agg_fn| agg_fn.vars.iter()),
+            );
+        }
+        Some(Opr::Join(join)) => {
+            vars.extend(
+                join.left_keys
+                    .iter()
+                    .chain(join.right_keys.iter()),
+            );
+            tags.extend(
+                join.renames
+                    .iter()
+                    .filter_map(|rename| rename.from.as_ref()),
+            );
+        }
+        Some(Opr::Scan(scan)) => exprs.extend(
+            scan.params
+                .as_ref()
+                .and_then(|params| params.predicate.as_ref()),
+        ),
+        Some(Opr::Edge(edgexpd)) => {
+            tags.extend(edgexpd.v_tag.as_ref());
+            exprs.extend(
+                edgexpd
+                    .params
+                    .as_ref()
+                    .and_then(|params| params.predicate.as_ref()),
+            );
+        }
+        Some(Opr::Vertex(getv)) => {
+            tags.extend(getv.tag.as_ref());
+            exprs.extend(
+                getv.params
+                    .as_ref()
+                    .and_then(|params| params.predicate.as_ref()),
+            );
+        }
+        Some(Opr::Path(pathxpd)) => {
+            tags.extend(pathxpd.start_tag.as_ref());
+            exprs.extend(pathxpd.condition.as_ref());
+        }
+        Some(Opr::Unfold(unfold)) => tags.extend(unfold.tag.as_ref()),
+        Some(Opr::Apply(apply)) => tags.extend(apply.tags.iter()),
+        Some(Opr::Count(count)) => tags.extend(count.tag.as_ref()),
+        Some(Opr::Sink(sink)) => {
+            if sink.tags.is_empty() {
+                return None;
+            }
+            tags.extend(
+                sink.tags
+                    .iter()
+                    .filter_map(|tag| tag.key.as_ref()),
+            );
+        }
+        _ => {}
+    }
+    for expr in exprs {
+        collect_expr_vars(expr, &mut vars);
+    }
+    let tag_vars = tags.into_iter().map(|tag| common_pb::Variable {
+        tag: Some(tag.clone()),
+        property: None,
+        node_type: None,
+    });
+    let mut referred_vars: Vec<common_pb::Variable> = vec![];
+    for var in vars.into_iter().cloned().chain(tag_vars) {
+        if !referred_vars.contains(&var) {
+            referred_vars.push(var);
+        }
+    }
+
+    Some(referred_vars)
+}
+
+/// The variables referred by the operator as recorded in its accesses, in which the tags are
+/// referred by their names, and the data types are left out.
+fn get_opr_accesses(
+    opr: &pb::logical_plan::Operator, tag_names: &BTreeMap<TagId, String>,
+) -> Option<Vec<common_pb::Variable>> {
+    get_referred_vars(opr).map(|vars| {
+        let mut accesses: Vec<common_pb::Variable> = vec![];
+        for mut var in vars {
+            var.node_type = None;
+            if let Some(common_pb::name_or_id::Item::Id(tag_id)) = var
+                .tag
+                .as_ref()
+                .and_then(|tag| tag.item.as_ref())
+            {
+                if let Some(name) = tag_names.get(&(*tag_id as TagId)) {
+                    var.tag = Some(name.as_str().into());
+                }
+            }
+            if !accesses.contains(&var) {
+                accesses.push(var);
+            }
+        }
+        accesses
+    })
+}
+
+/// The tags referred by the operator, e.g., by its expressions, or as the starting vertices of an
+/// expansion. It returns `None` if the operator refers to all the tags, i.e., a sink of no tags.
+fn get_referred_tags(opr: &pb::logical_plan::Operator, plan_meta: &PlanMeta) -> Option<BTreeSet<TagId>> {
+    Some(
+        get_referred_vars(opr)?
+            .iter()
+            .filter_map(|var| var.tag.as_ref())
+            .filter_map(|tag| get_tag_id(tag, plan_meta))
+            .collect(),
+    )
+}
+
+fn collect_expr_vars<'a>(expr: &'a common_pb::Expression, vars: &mut Vec<&'a common_pb::Variable>) {
+    use common_pb::expr_opr::Item;
+
+    for opr in &expr.operators {
+        match &opr.item {
+            Some(Item::Var(var)) => vars.push(var),
+            Some(Item::Vars(keys)) | Some(Item::VarMap(keys)) => vars.extend(keys.keys.iter()),
+            Some(Item::Case(case)) => {
+                for when_then in &case.when_then_expressions {
+                    for expr in when_then
+                        .when_expression
+                        .iter()
+                        .chain(when_then.then_result_expression.iter())
+                    {
+                        collect_expr_vars(expr, vars);
+                    }
+                }
+                for expr in case.else_result_expression.iter() {
+                    collect_expr_vars(expr, vars);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn get_tag_id(tag_pb: &common_pb::NameOrId, plan_meta: &PlanMeta) -> Option<TagId> {
+    match tag_pb.item.as_ref()? {
+        common_pb::name_or_id::Item::Id(id) => Some(*id as TagId),
+        common_pb::name_or_id::Item::Name(name) => plan_meta.get_tag_id(name),
+    }
+}
+
+/// Get the tags output by the join, i.e., the tags of both sides after the renames, where the
+/// `parent_ids` are the left and right sides in order. A semi or anti join only outputs the left side.
+fn get_join_output_tags(join: &pb::Join, parent_ids: &[NodeId], plan_meta: &PlanMeta) -> BTreeSet<TagId> {
+    use pb::join::{rename::Side, JoinKind};
+
+    let is_left_only = join.kind == JoinKind::Semi as i32 || join.kind == JoinKind::Anti as i32;
+    let mut tags = BTreeSet::new();
+    for (idx, id) in parent_ids.iter().enumerate() {
+        let side = if idx == 0 { Side::Left } else { Side::Right };
+        if side == Side::Right && is_left_only {
+            break;
+        }
+        let mut side_tags = plan_meta.get_node_tags(*id);
+        for rename in join
+            .renames
+            .iter()
+            .filter(|rename| rename.side == side as i32)
+        {
+            if let (Some(from), Some(to)) = (get_tag(rename.from.as_ref()), get_tag(rename.to.as_ref())) {
+                if side_tags.remove(&from) {
+                    side_tags.insert(to);
+                }
+            }
+        }
+        tags.extend(side_tags);
+    }
+
+    tags
+}
+
+/// Check that each tag renamed by the join is defined by the side that it is renamed on.
+fn check_join_renames(join: &pb::Join, parent_ids: &[NodeId], plan_meta: &PlanMeta) -> IrResult<()> {
+    for rename in &join.renames {
+        if let Some(from) = get_tag(rename.from.as_ref()) {
+            let side_tags = parent_ids
+                .get(rename.side as usize)
+                .map(|id| plan_meta.get_node_tags(*id))
+                .unwrap_or_default();
+            // The tags of a side may not be tracked, e.g., if it is built from a pattern
+            if !side_tags.is_empty() && !side_tags.contains(&from) {
+                return Err(IrError::TagNotExist((from as KeyId).into()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_tag(tag_pb: Option<&common_pb::NameOrId>) -> Option<TagId> {
+    match tag_pb.and_then(|tag| tag.item.as_ref()) {
+        Some(common_pb::name_or_id::Item::Id(id)) => Some(*id as TagId),
+        _ => None,
+    }
+}
+
+/// Check that the connector refers to an external source, and the columns of the scan
+/// are present in the source.
+fn check_connector(
+    scan_opt: i32, connector: &pb::scan::Connector, params: Option<&pb::QueryParams>,
+) -> IrResult<()> {
+    if scan_opt != pb::scan::ScanOpt::Table as i32 {
+        return Err(IrError::Unsupported(format!("scan_opt {:?} with a connector", scan_opt)));
+    }
+    if connector.uri.is_empty() {
+        return Err(IrError::MissingData("Scan::connector::uri".to_string()));
+    }
+    if let Some(params) = params {
+        for column in &params.columns {
+            let column: NameOrId = column.clone().try_into()?;
+            let is_present = match &column {
+                NameOrId::Str(name) => connector
+                    .columns
+                    .iter()
+                    .any(|col| &col.name == name),
+                NameOrId::Id(id) => (*id as usize) < connector.columns.len(),
+            };
+            if !is_present {
+                return Err(IrError::ColumnNotExist(column));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the bulk load maps the columns of its source into a vertex or an edge of a given label,
+/// where the source and the target vertices of an edge are given as well.
+fn check_bulk_load_mapping(bulk_load: &pb::SinkBulkLoad) -> IrResult<()> {
+    if bulk_load.uri.is_empty() {
+        return Err(IrError::MissingData("SinkBulkLoad::uri".to_string()));
+    }
+    let check_vertex = |vertex: &pb::sink_bulk_load::VertexMapping, field: &str| -> IrResult<()> {
+        if vertex.label.is_none() {
+            return Err(IrError::MissingData(format!("SinkBulkLoad::{}::label", field)));
+        }
+        Ok(())
+    };
+    match bulk_load.mapping.as_ref() {
+        Some(pb::sink_bulk_load::Mapping::Vertex(vertex)) => check_vertex(vertex, "vertex"),
+        Some(pb::sink_bulk_load::Mapping::Edge(edge)) => {
+            if edge.label.is_none() {
+                return Err(IrError::MissingData("SinkBulkLoad::edge::label".to_string()));
+            }
+            let src = edge
+                .src
+                .as_ref()
+                .ok_or(IrError::MissingData("SinkBulkLoad::edge::src".to_string()))?;
+            check_vertex(src, "edge::src")?;
+            let dst = edge
+                .dst
+                .as_ref()
+                .ok_or(IrError::MissingData("SinkBulkLoad::edge::dst".to_string()))?;
+            check_vertex(dst, "edge::dst")
+        }
+        None => Err(IrError::MissingData("SinkBulkLoad::mapping".to_string())),
+    }
+}
+
+/// Check that the registered result is scanned as a relational table, which has no connector.
+fn check_named_result(scan_opt: i32, connector: Option<&pb::scan::Connector>) -> IrResult<()> {
+    if scan_opt != pb::scan::ScanOpt::Table as i32 {
+        return Err(IrError::Unsupported(format!("scan_opt {:?} of a named result", scan_opt)));
+    }
+    if connector.is_some() {
+        return Err(IrError::Unsupported("scan a named result with a connector".to_string()));
+    }
+
+    Ok(())
+}
+
+impl AsLogical for pb::Scan {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        let curr_node = plan_meta.get_curr_node();
+        plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
+        if let Some(alias) = self.alias.as_mut() {
+            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        }
+        if let Some(params) = &self.params {
+            if !params.degree_predicates.is_empty() {
+                return Err(IrError::Unsupported("degree predicates in `Scan`".to_string()));
+            }
+        }
+        if let Some(connector) = &self.connector {
+            check_connector(self.scan_opt, connector, self.params.as_ref())?;
+        }
+        if !self.named_result.is_empty() {
+            check_named_result(self.scan_opt, self.connector.as_ref())?;
+        }
+        if self.connector.is_some() || !self.named_result.is_empty() {
+            // The columns of an external source (or a registered result) are given by its connector
+            // (or the query registering it), rather than by the schema of the graph, and thus are
+            // not resolved against the schema
+            if let Some(params) = &self.params {
+                let mut node_meta = plan_meta.curr_node_meta_mut();
+                for column in &params.columns {
+                    node_meta.insert_column(column.clone().try_into()?);
+                }
+            }
+            return Ok(());
+        }
+        if let Some(params) = self.params.as_mut() {
+            if self.idx_predicate.is_none() {
+                if let Some(expr) = &params.predicate {
+                    let idx_pred = triplet_to_index_predicate(
+                        expr.operators.as_slice(),
+                        &params.tables,
+                        self.scan_opt != 1,
+                        meta,
+                    )?;
+
+                    if idx_pred.is_some() {
+                        params.predicate = None;
+                        self.idx_predicate = idx_pred;
+                    }
+                }
+            }
+            if let (Some(idx_pred), Some(schema)) = (&self.idx_predicate, &meta.schema) {
+                if self.scan_opt == pb::scan::ScanOpt::Vertex as i32 {
+                    if let Some(choice) = choose_vertex_scan(idx_pred, &params.tables, schema) {
+                        plan_meta.annotate_node(plan_meta.get_curr_node(), choice.to_string());
+                        if let ScanChoice::FullScan { within, .. } = choice {
+                            // Look up the vertices by the predicate while scanning instead
+                            params.predicate = Some(match params.predicate.take() {
+                                Some(predicate) => combine_exprs(predicate, within),
+                                None => within,
+                            });
+                            self.idx_predicate = None;
+                        }
+                    }
+                }
+            }
+            preprocess_params(params, meta, plan_meta)?;
+        }
+        if let Some(idx_pred) = self.idx_predicate.as_mut() {
+            idx_pred.preprocess(meta, plan_meta)?;
+        }
+        if self.scan_opt == pb::scan::ScanOpt::Vertex as i32 {
+            if let (Some(idx_pred), Some(params), Some(schema)) =
+                (&self.idx_predicate, &self.params, &meta.schema)
+            {
+                if let Some(id_encoding) = schema.get_id_encoding() {
+                    check_indexed_global_ids(idx_pred, &params.tables, id_encoding)?;
+                }
+            }
+        }
+
+        process_columns_meta(plan_meta, false)?;
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::EdgeExpand {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        if self.dedup_both && self.direction != pb::edge_expand::Direction::Both as i32 {
+            return Err(IrError::Unsupported(format!(
+                "dedup_both of the expansion in direction {:?}",
+                self.direction
+            )));
+        }
+        // the degree of a vertex is always yielded, which is zero if no edges are expanded
+        if self.is_optional && self.expand_opt == pb::edge_expand::ExpandOpt::Degree as i32 {
+            return Err(IrError::Unsupported("an optional expansion of the degree".to_string()));
+        }
+        let curr_node = plan_meta.get_curr_node();
+        plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
+        if let Some(params) = self.params.as_mut() {
+            preprocess_params(params, meta, plan_meta)?;
+        }
+        preprocess_tables(&mut self.vertex_tables, meta)?;
+        if let Some(alias) = self.alias.as_mut() {
+            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        }
+        let expand_opt: pb::edge_expand::ExpandOpt = unsafe { ::std::mem::transmute(self.expand_opt) };
+        if expand_opt == pb::edge_expand::ExpandOpt::Vertex {
+            process_columns_meta(plan_meta, false)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::PathExpand {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        if let (Some(max_hops), Some(range)) = (plan_meta.get_max_hops(), self.hop_range.as_ref()) {
+            // the upper bound of the range is excluded
+            if range.upper.saturating_sub(1) > max_hops {
+                return Err(IrError::HopRangeExceeded(range.lower, range.upper, max_hops));
+            }
+        }
+        if let Some(base) = self.base.as_ref() {
+            // a hop of the path cannot be null, which would otherwise end the path in a null vertex
+            let is_optional = base
+                .edge_expand
+                .as_ref()
+                .map(|edge_expand| edge_expand.is_optional)
+                .unwrap_or(false)
+                || base
+                    .get_v
+                    .as_ref()
+                    .map(|get_v| get_v.is_optional)
+                    .unwrap_or(false);
+            if is_optional {
+                return Err(IrError::Unsupported(
+                    "an optional expansion as the base of `PathExpand`".to_string(),
+                ));
+            }
+        }
+        let curr_node = plan_meta.get_curr_node();
+        plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
+        if let Some(base) = self.base.as_mut() {
+            if let Some(edge_expand) = base.edge_expand.as_mut() {
+                edge_expand.preprocess(meta, plan_meta)?;
+            }
+            if let Some(get_v) = base.edge_expand.as_mut() {
+                get_v.preprocess(meta, plan_meta)?;
+            }
+        }
+        if let Some(pred) = self.condition.as_mut() {
+            preprocess_expression(pred, meta, plan_meta, false)?;
+            process_columns_meta(plan_meta, true)?;
+        }
+        if let Some(alias) = self.alias.as_mut() {
+            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        }
+        // PathExpand would never require adding columns
+        plan_meta
+            .curr_node_meta_mut()
+            .set_columns_opt(ColumnsOpt::None);
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::GetV {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        let curr_node = plan_meta.get_curr_node();
+        plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
+        if let Some(params) = self.params.as_mut() {
+            preprocess_params(params, meta, plan_meta)?;
+        }
+        if self.opt == pb::get_v::VOpt::Endpoints as i32 {
+            // both endpoints are appended to the record, and hence must be aliased to be accessed
+            if self.alias.is_none() {
+                return Err(IrError::MissingData("GetV::alias".to_string()));
+            }
+            if self.end_alias.is_none() {
+                return Err(IrError::MissingData("GetV::end_alias".to_string()));
+            }
+        }
+        for alias in self
+            .alias
+            .iter_mut()
+            .chain(self.end_alias.iter_mut())
+        {
+            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        }
+
+        process_columns_meta(plan_meta, false)?;
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::Dedup {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        for var in self.keys.iter_mut() {
+            preprocess_var(var, meta, plan_meta, false)?;
+        }
+        process_columns_meta(plan_meta, false)?;
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::GroupBy {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        for mapping in self.mappings.iter_mut() {
+            if let Some(key) = &mut mapping.key {
+                preprocess_var(key, meta, plan_meta, false)?;
+                if let Some(alias) = mapping.alias.as_mut() {
+                    let key_alias_id = get_or_set_tag_id(alias, plan_meta)?;
+                    // the key must refer to some previous node that will be accessed later, e.g.
+                    // g.V().groupCount().select(keys).by('name')
+                    // In this case, if `key.property` is `None`, the `alias` must be able to refer
+                    // to a previous node to get the property; otherwise, the `alias` must have
+                    // referred to the current node
+                    if key.property.is_none() {
+                        let node_ids = if let Some(tag_pb) = key.tag.as_mut() {
+                            let tag_id = get_or_set_tag_id(tag_pb, plan_meta)?;
+                            plan_meta.get_tag_nodes(tag_id).to_vec()
+                        } else {
+                            plan_meta.get_curr_referred_nodes().to_vec()
+                        };
+                        if !node_ids.is_empty() {
+                            plan_meta.set_tag_nodes(key_alias_id, node_ids);
+                        }
+                    } else {
+                        plan_meta.set_tag_nodes(key_alias_id, vec![plan_meta.get_curr_node()]);
+                    }
+                    if let Some(data_type) = get_var_data_type(key, plan_meta) {
+                        plan_meta.set_tag_type(key_alias_id, data_type);
+                    }
+                }
+            }
+        }
+        for agg_fn in self.functions.iter_mut() {
+            check_agg_equality(agg_fn)?;
+            check_agg_approximation(agg_fn)?;
+            set_agg_overflow(agg_fn, plan_meta);
+            let is_by_value = agg_fn.equality == pb::group_by::agg_func::Equality::ByValue as i32;
+            for var in agg_fn.vars.iter_mut() {
+                preprocess_var(var, meta, plan_meta, false)?;
+                // Comparing the graph elements by value requires all of their properties
+                if is_by_value && var.property.is_none() {
+                    let tag = if let Some(tag_pb) = var.tag.as_mut() {
+                        Some(get_or_set_tag_id(tag_pb, plan_meta)?)
+                    } else {
+                        None
+                    };
+                    plan_meta
+                        .curr_node_meta_mut()
+                        .set_tag_columns_opt(tag, ColumnsOpt::All(256));
+                }
+            }
+            let data_type = get_agg_data_type(agg_fn, plan_meta);
+            if let Some(alias) = agg_fn.alias.as_mut() {
+                let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+                plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+                if let Some(data_type) = data_type {
+                    plan_meta.set_tag_type(tag_id, data_type);
+                }
+            }
+        }
+
+        process_columns_meta(plan_meta, false)?;
+
+        Ok(())
+    }
+}
+
+/// Check that the equality of the aggregate function is specified only for `ToSet` and
+/// `CountDistinct`, and that the values deduplicated by id must be graph elements rather
+/// than properties.
+fn check_agg_equality(agg_fn: &pb::group_by::AggFunc) -> IrResult<()> {
+    use pb::group_by::agg_func::{Aggregate, Equality};
+
+    if agg_fn.equality == Equality::Default as i32 {
+        return Ok(());
+    }
+    if agg_fn.aggregate != Aggregate::ToSet as i32 && agg_fn.aggregate != Aggregate::CountDistinct as i32 {
+        return Err(IrError::Unsupported(format!(
+            "equality {:?} for the aggregate function {:?}",
+            agg_fn.equality, agg_fn.aggregate
+        )));
+    }
+    if agg_fn.equality == Equality::ById as i32 {
+        if let Some(var) = agg_fn
+            .vars
+            .iter()
+            .find(|var| var.property.is_some())
+        {
+            return Err(IrError::Unsupported(format!("deduplicate the property of {:?} by id", var)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the approximation of the aggregate function is specified only for `CountDistinct`,
+/// with a precision of the HyperLogLog sketch within [4, 18]. As the sketch hashes the values by
+/// their ids, the values deduplicated by value cannot be approximated.
+fn check_agg_approximation(agg_fn: &pb::group_by::AggFunc) -> IrResult<()> {
+    use pb::group_by::agg_func::{Aggregate, Equality};
+
+    if let Some(approx) = agg_fn.approximation.as_ref() {
+        if agg_fn.aggregate != Aggregate::CountDistinct as i32 {
+            return Err(IrError::Unsupported(format!(
+                "approximating the aggregate function {:?}",
+                agg_fn.aggregate
+            )));
+        }
+        if agg_fn.equality == Equality::ByValue as i32 {
+            return Err(IrError::Unsupported("approximating the distinct count by value".to_string()));
+        }
+        if approx.precision < 4 || approx.precision > 18 {
+            return Err(IrError::Unsupported(format!(
+                "the precision {} of the approximation, which must be within [4, 18]",
+                approx.precision
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Encode the overflow policy of the plan into the `Sum` and `Avg` aggregate functions, unless they
+/// specify their own.
+fn set_agg_overflow(agg_fn: &mut pb::group_by::AggFunc, plan_meta: &PlanMeta) {
+    use pb::group_by::agg_func::{Aggregate, Overflow};
+
+    let is_sum_or_avg =
+        agg_fn.aggregate == Aggregate::Sum as i32 || agg_fn.aggregate == Aggregate::Avg as i32;
+    if is_sum_or_avg && agg_fn.overflow == Overflow::Wrap as i32 {
+        agg_fn.overflow = plan_meta.get_agg_overflow() as i32;
+    }
+}
+
+/// The data type of the result of the aggregate function, if known. A count is always an `Int64`, and
+/// an average a `Double`, while the others follow the data type of the aggregated values, which is
+/// known only if they are referred by a typed tag, e.g., the sum of the counts of a previous `GroupBy`.
+fn get_agg_data_type(agg_fn: &pb::group_by::AggFunc, plan_meta: &PlanMeta) -> Option<common_pb::DataType> {
+    use common_pb::DataType;
+    use pb::group_by::agg_func::Aggregate;
+
+    let var_type = || match agg_fn.vars.as_slice() {
+        [var] => get_var_data_type(var, plan_meta),
+        _ => None,
+    };
+    match Aggregate::from_i32(agg_fn.aggregate)? {
+        Aggregate::Count | Aggregate::CountDistinct => Some(DataType::Int64),
+        Aggregate::Avg => Some(DataType::Double),
+        Aggregate::Sum => var_type().filter(|data_type| is_numeric_type(*data_type)),
+        Aggregate::Min | Aggregate::Max => var_type(),
+        Aggregate::ToList | Aggregate::ToSet => match var_type()? {
+            DataType::Int32 => Some(DataType::Int32Array),
+            DataType::Int64 => Some(DataType::Int64Array),
+            DataType::Double => Some(DataType::DoubleArray),
+            DataType::String => Some(DataType::StringArray),
+            _ => None,
+        },
+    }
+}
+
+/// The data type of the variable, which is known if it refers to the value of a typed tag as a whole.
+fn get_var_data_type(var: &common_pb::Variable, plan_meta: &PlanMeta) -> Option<common_pb::DataType> {
+    use common_pb::name_or_id::Item;
+
+    if var.property.is_some() {
+        return None;
+    }
+    let tag_id = match var.tag.as_ref()?.item.as_ref()? {
+        Item::Name(name) => plan_meta.get_tag_id(name)?,
+        Item::Id(id) => *id as TagId,
+    };
+    plan_meta.get_tag_type(tag_id)
+}
+
+/// The data type of the result of the expression, which is known if it is a predicate, or an arithmetic
+/// of the constants and the typed variables, where the integers are promoted to the widest of them,
+/// and to a `Double` if any operand is. E.g., `@total / @cnt` is a `Double` if `@total` is an average.
+fn get_expr_data_type(expr: &common_pb::Expression, plan_meta: &PlanMeta) -> Option<common_pb::DataType> {
+    use common_pb::expr_opr::Item;
+    use common_pb::DataType;
+
+    if expr
+        .operators
+        .iter()
+        .any(|opr| matches!(opr.item, Some(Item::Logical(_))))
+    {
+        return Some(DataType::Boolean);
+    }
+    let mut data_type = None;
+    let mut is_arith = false;
+    for opr in &expr.operators {
+        let operand_type = match opr.item.as_ref()? {
+            Item::Arith(_) => {
+                is_arith = true;
+                continue;
+            }
+            Item::Brace(_) => continue,
+            Item::Const(value) => get_value_data_type(value)?,
+            Item::Var(var) => get_var_data_type(var, plan_meta)?,
+            _ => return None,
+        };
+        data_type = match data_type {
+            None => Some(operand_type),
+            Some(data_type) => Some(promote_numeric_type(data_type, operand_type)?),
+        };
+    }
+    if is_arith {
+        data_type.filter(|data_type| is_numeric_type(*data_type))
+    } else {
+        data_type
+    }
+}
+
+fn get_value_data_type(value: &common_pb::Value) -> Option<common_pb::DataType> {
+    use common_pb::value::Item;
+    use common_pb::DataType;
+
+    match value.item.as_ref()? {
+        Item::Boolean(_) => Some(DataType::Boolean),
+        Item::I32(_) => Some(DataType::Int32),
+        Item::I64(_) => Some(DataType::Int64),
+        Item::F64(_) => Some(DataType::Double),
+        Item::Str(_) => Some(DataType::String),
+        _ => None,
+    }
+}
+
+fn is_numeric_type(data_type: common_pb::DataType) -> bool {
+    use common_pb::DataType;
+
+    matches!(data_type, DataType::Int32 | DataType::Int64 | DataType::Double)
+}
+
+fn promote_numeric_type(
+    left: common_pb::DataType, right: common_pb::DataType,
+) -> Option<common_pb::DataType> {
+    use common_pb::DataType;
+
+    match (left, right) {
+        (DataType::Double, r) if is_numeric_type(r) => Some(DataType::Double),
+        (l, DataType::Double) if is_numeric_type(l) => Some(DataType::Double),
+        (DataType::Int64, r) if is_numeric_type(r) => Some(DataType::Int64),
+        (l, DataType::Int64) if is_numeric_type(l) => Some(DataType::Int64),
+        (DataType::Int32, DataType::Int32) => Some(DataType::Int32),
+        _ => None,
+    }
+}
+
+impl AsLogical for pb::Count {
+    fn preprocess(&mut self, _meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        if let Some(tag) = self.tag.as_mut() {
+            let tag_name: NameOrId = tag.clone().try_into()?;
+            let tag_id = get_or_set_tag_id(tag, plan_meta)?;
+            if !plan_meta.has_tag(tag_id) {
+                return Err(IrError::TagNotExist(tag_name));
+            }
+        }
+        if let Some(alias) = self.alias.as_mut() {
+            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        }
+        // Unlike the `GroupBy`, no columns are required to count the records, and thus
+        // the columns meta is not processed
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::IndexPredicate {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        for and_pred in self.or_predicates.iter_mut() {
+            for pred in and_pred.predicates.iter_mut() {
+                if let Some(pred_key) = &mut pred.key {
+                    if let Some(key_item) = pred_key.item.as_mut() {
+                        match key_item {
+                            common_pb::property::Item::Key(key) => {
+                                if let Some(schema) = &meta.schema {
+                                    if schema.is_column_id() {
+                                        let new_key = get_column_id_from_pb(schema, key).into();
+                                        debug!("column: {:?} -> {:?}", key, new_key);
+                                        *key = new_key;
+                                    }
+                                }
+                            }
+                            common_pb::property::Item::Label(_) => {
+                                if let Some(val) = pred.value.as_mut() {
+                                    preprocess_label(val, meta, plan_meta)?;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::OrderBy {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        for pair in self.pairs.iter_mut() {
+            if let Some(key) = &mut pair.key {
+                preprocess_var(key, meta, plan_meta, false)?;
+            }
+        }
+        process_columns_meta(plan_meta, false)?;
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::Limit {
+    fn preprocess(&mut self, _meta: &StoreMeta, _plan_meta: &mut PlanMeta) -> IrResult<()> {
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::As {
+    fn preprocess(&mut self, _meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        if let Some(alias) = self.alias.as_mut() {
+            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+            plan_meta.set_tag_nodes(tag_id, plan_meta.get_curr_referred_nodes().to_vec());
+        }
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::Join {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        for left_key in self.left_keys.iter_mut() {
+            preprocess_var(left_key, meta, plan_meta, false)?
+        }
+        for right_key in self.right_keys.iter_mut() {
+            preprocess_var(right_key, meta, plan_meta, false)?
+        }
+        for rename in self.renames.iter_mut() {
+            let from = rename
+                .from
+                .as_mut()
+                .ok_or(IrError::MissingData("Join::Rename::from".to_string()))?;
+            let from_tag = get_or_set_tag_id(from, plan_meta)?;
+            let to = rename
+                .to
+                .as_mut()
+                .ok_or(IrError::MissingData("Join::Rename::to".to_string()))?;
+            let to_tag = get_or_set_tag_id(to, plan_meta)?;
+            // The renamed tag refers to the nodes that the original tag refers to
+            plan_meta.set_tag_nodes(to_tag, plan_meta.get_tag_nodes(from_tag).to_vec());
+        }
+
+        process_columns_meta(plan_meta, false)?;
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::Sink {
+    fn preprocess(&mut self, _meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        match self
+            .sink_target
+            .as_ref()
+            .and_then(|target| target.inner.as_ref())
+        {
+            Some(pb::sink::sink_target::Inner::SinkResult(sink_result)) => {
+                if sink_result.name.is_empty() {
+                    return Err(IrError::MissingData("Sink::sink_target::SinkResult::name".to_string()));
+                }
+                if self.tags.len() > 1 {
+                    return Err(IrError::Unsupported(
+                        "register more than one column as a named result".to_string(),
+                    ));
+                }
+            }
+            Some(pb::sink::sink_target::Inner::SinkBulkLoad(bulk_load)) => {
+                check_bulk_load_mapping(bulk_load)?
+            }
+            _ => {}
+        }
+        for tag_key in self.tags.iter_mut() {
+            if let Some(tag) = tag_key.key.as_mut() {
+                get_or_set_tag_id(tag, plan_meta)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::Apply {
+    fn preprocess(&mut self, _meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        let curr_node = plan_meta.get_curr_node();
+        for capture in self.captures.iter_mut() {
+            // a captured tag must have been defined, rather than being defined by the capture
+            let tag_name: NameOrId = capture.clone().try_into()?;
+            let tag_id = get_tag_id(capture, plan_meta).ok_or(IrError::TagNotExist(tag_name))?;
+            *capture = (tag_id as KeyId).into();
+        }
+        if let Some(alias) = self.alias.as_mut() {
+            let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+            plan_meta.set_tag_nodes(tag_id, vec![plan_meta.get_curr_node()]);
+        } else {
+            if self.join_kind != 4 && self.join_kind != 5 {
+                // if not semi_join, not anti_join or the alias has not been set
+                plan_meta.refer_to_nodes(curr_node, vec![curr_node]);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::Pattern {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        for sentence in self.sentences.iter_mut() {
+            if let Some(alias) = sentence.start.as_mut() {
+                let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+                if plan_meta.has_tag(tag_id) {
+                    warn!("`pb::Pattern` reference existing tag: {:?}", alias);
+                }
+            } else {
+                return Err(IrError::InvalidPattern(
+                    "the start tag in `pb::Pattern` does not exist".to_string(),
+                ));
+            }
+            if let Some(alias) = sentence.end.as_mut() {
+                let tag_id = get_or_set_tag_id(alias, plan_meta)?;
+                if plan_meta.has_tag(tag_id) {
+                    warn!("`pb::Pattern` reference existing tag: {:?}", alias);
+                }
+            }
+            for binder_opt in &mut sentence.binders {
+                if let Some(binder) = binder_opt.item.as_mut() {
+                    let is_optional = match binder {
+                        Item::Edge(edge) => edge.is_optional,
+                        Item::Vertex(vertex) => vertex.is_optional,
+                        _ => false,
+                    };
+                    if is_optional {
+                        return Err(IrError::Unsupported(format!(
+                            "the optional binder {:?} in `pb::Pattern`",
+                            binder
+                        )));
+                    }
+                    match binder {
+                        Item::Edge(edge) => edge.preprocess(meta, plan_meta)?,
+                        Item::Path(path) => path.preprocess(meta, plan_meta)?,
+                        Item::Vertex(vertex) => vertex.preprocess(meta, plan_meta)?,
+                        Item::Select(pred) => pred.preprocess(meta, plan_meta)?,
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl AsLogical for pb::logical_plan::Operator {
+    fn preprocess(&mut self, meta: &StoreMeta, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        use pb::logical_plan::operator::Opr;
+        if let Some(opr) = self.opr.as_mut() {
+            match opr {
+                Opr::Project(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Select(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Scan(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Edge(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Path(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Vertex(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Dedup(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::GroupBy(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Count(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::OrderBy(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Limit(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::As(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Join(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Sink(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Apply(opr) => opr.preprocess(meta, plan_meta)?,
+                Opr::Pattern(opr) => opr.preprocess(meta, plan_meta)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use ir_common::expr_parse::str_to_expr_pb;
+    use ir_common::generated::algebra::logical_plan::operator::Opr;
+    use ir_common::generated::common::property::Item;
+
+    use super::*;
+    use crate::plan::meta::{OptimizationStage, Schema};
+
+    #[allow(dead_code)]
+    fn query_params(
+        tables: Vec<common_pb::NameOrId>, columns: Vec<common_pb::NameOrId>,
+    ) -> pb::QueryParams {
+        pb::QueryParams {
+            tables,
+            columns,
+            is_all_columns: false,
+            limit: None,
+            predicate: None,
+            sample_ratio: 1.0,
+            extra: HashMap::new(),
+            degree_predicates: vec![],
+        }
+    }
+
+    #[test]
+    fn logical_plan_construct() {
+        let opr = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
+        };
+        let mut plan = LogicalPlan::default();
+
+        let id = plan
+            .append_operator_as_node(opr.clone(), vec![])
+            .unwrap();
+        assert_eq!(id, 0);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan.max_node_id, 1);
+        let node0 = plan.get_node(0).unwrap().clone();
+
+        let id = plan
+            .append_operator_as_node(opr.clone(), vec![0])
+            .unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan.max_node_id, 2);
+        let node1 = plan.get_node(1).unwrap().clone();
+
+        let parents = node1
+            .borrow()
+            .parents
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(parents, vec![0]);
+
+        let children = node0
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(children, vec![1]);
+
+        let id = plan
+            .append_operator_as_node(opr.clone(), vec![0, 1])
+            .unwrap();
+        assert_eq!(id, 2);
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan.max_node_id, 3);
+        let node2 = plan.get_node(2).unwrap().clone();
+
+        let parents = node2
+            .borrow()
+            .parents
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(parents, vec![0, 1]);
+
+        let children = node0
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(children, vec![1, 2]);
+
+        let children = node1
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(children, vec![2]);
+
+        let node2 = plan.remove_node(2);
+        assert_eq!(node2.unwrap().borrow().id, 2);
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan.max_node_id, 3);
+        let children = node0
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(children, vec![1]);
+
+        let children = node1
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert!(children.is_empty());
+
+        let _id = plan.append_operator_as_node(opr.clone(), vec![0, 2]);
+        match _id.err().unwrap() {
+            IrError::ParentNodeNotExist(node) => assert_eq!(node, 2),
+            _ => panic!("wrong error type"),
+        }
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan.max_node_id, 3);
+        let children = node0
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(children, vec![1]);
+
+        // add node2 back again for further testing recursive removal
+        let _ = plan
+            .append_operator_as_node(opr.clone(), vec![0, 1])
+            .unwrap();
+        let node3 = plan.get_node(3).unwrap();
+        let _ = plan.remove_node(1);
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan.max_node_id, 4);
+        let children = node0
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(children, vec![3]);
+
+        let parents = node3
+            .borrow()
+            .parents
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(parents, vec![0]);
+    }
+
+    #[test]
+    fn detach_node() {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec!["knows".into()], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let select = pb::Select { predicate: str_to_expr_pb("@.age > 10".to_string()).ok() };
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(expand.clone().into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(expand.into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(select.into(), vec![2])
+            .unwrap();
+        plan.append_operator_as_node(pb::Dedup { keys: vec![] }.into(), vec![3])
+            .unwrap();
+        let union = pb::Union { parents: vec![1, 4], alignment: 0 };
+        plan.append_operator_as_node(union.into(), vec![1, 4])
+            .unwrap();
+
+        // the dedup and then the select are dropped
+        assert_eq!(plan.detach_node(4).unwrap().borrow().id, 4);
+        assert_eq!(plan.detach_node(3).unwrap().borrow().id, 3);
+        assert_eq!(plan.len(), 4);
+        let union_node = plan.get_node(5).unwrap();
+        assert_eq!(union_node.borrow().parents, vec![1, 2].into_iter().collect());
+        match &union_node.borrow().opr.opr {
+            Some(Opr::Union(union)) => assert_eq!(union.parents, vec![1, 2]),
+            _ => panic!("the node 5 is not a `Union`"),
+        }
+        assert_eq!(plan.get_node(2).unwrap().borrow().children, vec![5].into_iter().collect());
+
+        // the source, the heads of the union, and the union of multiple parents cannot be detached
+        assert!(matches!(plan.detach_node(0), Err(IrError::Unsupported(_))));
+        assert!(matches!(plan.detach_node(1), Err(IrError::Unsupported(_))));
+        assert!(matches!(plan.detach_node(5), Err(IrError::Unsupported(_))));
+        assert!(matches!(plan.detach_node(3), Err(IrError::NodeNotExist(3))));
+        assert_eq!(plan.len(), 4);
+    }
+
+    #[test]
+    fn replace_operator() {
+        let scan = |alias: &str, idx_predicate: Option<pb::IndexPredicate>| pb::Scan {
+            scan_opt: 0,
+            alias: Some(alias.into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let select = |predicate: &str| pb::Select { predicate: str_to_expr_pb(predicate.to_string()).ok() };
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan("a", None).into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(select("@a.age > 10").into(), vec![0])
+            .unwrap();
+
+        // swap the full scan for an indexed one
+        let idx_predicate = pb::IndexPredicate {
+            or_predicates: vec![pb::index_predicate::AndPredicate {
+                predicates: vec![pb::index_predicate::Triplet {
+                    key: Some(common_pb::Property { item: Some(Item::Id(common_pb::IdKey {})) }),
+                    value: Some(1_i64.into()),
+                    cmp: None,
+                    param: None,
+                }],
+            }],
+        };
+        let old_opr = plan
+            .replace_operator(0, scan("a", Some(idx_predicate.clone())).into())
+            .unwrap();
+        assert!(matches!(old_opr.opr, Some(Opr::Scan(scan)) if scan.idx_predicate.is_none()));
+        match plan.get_opr(0).unwrap().opr {
+            Some(Opr::Scan(scan)) => assert_eq!(scan.idx_predicate, Some(idx_predicate)),
+            _ => panic!("the node 0 is not a `Scan`"),
+        }
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan.get_node(0).unwrap().borrow().children, vec![1].into_iter().collect());
+        assert_eq!(plan.get_node(1).unwrap().borrow().parents, vec![0].into_iter().collect());
+
+        let old_opr = plan
+            .replace_operator(1, select("@a.age > 20").into())
+            .unwrap();
+        assert!(matches!(old_opr.opr, Some(Opr::Select(_))));
+        assert_ne!(plan.get_opr(1).unwrap(), old_opr);
+
+        // neither of another kind, nor producing other tags
+        assert!(matches!(
+            plan.replace_operator(0, select("@a.age > 20").into()),
+            Err(IrError::Unsupported(_))
+        ));
+        assert!(matches!(plan.replace_operator(0, scan("b", None).into()), Err(IrError::Unsupported(_))));
+        assert!(matches!(
+            plan.replace_operator(2, select("@a.age > 20").into()),
+            Err(IrError::NodeNotExist(2))
+        ));
+    }
+
+    #[test]
+    fn insert_between() {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let select = pb::Select { predicate: str_to_expr_pb("@a.age > 10".to_string()).ok() };
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(select.into(), vec![0])
+            .unwrap();
+
+        // 0 -> 2 (dedup) -> 1
+        let id = plan
+            .insert_between(0, 1, pb::Dedup { keys: vec![] }.into())
+            .unwrap();
+        assert_eq!(id, 2);
+        assert!(matches!(plan.get_opr(2).unwrap().opr, Some(Opr::Dedup(_))));
+        assert_eq!(plan.get_node(0).unwrap().borrow().children, vec![2].into_iter().collect());
+        assert_eq!(plan.get_node(2).unwrap().borrow().parents, vec![0].into_iter().collect());
+        assert_eq!(plan.get_node(2).unwrap().borrow().children, vec![1].into_iter().collect());
+        assert_eq!(plan.get_node(1).unwrap().borrow().parents, vec![2].into_iter().collect());
+        assert_eq!(plan.meta.get_node_tags(2), plan.meta.get_node_tags(0));
+
+        // neither on a missing edge, nor dropping the tags that the child relies on
+        assert!(matches!(
+            plan.insert_between(0, 1, pb::Dedup { keys: vec![] }.into()),
+            Err(IrError::Unsupported(_))
+        ));
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@a.name".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: false,
+            meta_data: vec![],
+        };
+        assert!(matches!(plan.insert_between(2, 1, project.into()), Err(IrError::Unsupported(_))));
+        assert_eq!(plan.len(), 3);
+
+        // the child refers to the new node in place of the parent
+        let as_opr: pb::logical_plan::Operator = pb::As { alias: None }.into();
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(as_opr.clone(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(as_opr.clone(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(as_opr.clone(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(pb::Union { parents: vec![1, 2], alignment: 0 }.into(), vec![1, 2])
+            .unwrap();
+        let limit = pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false };
+        let id = plan.insert_between(1, 3, limit.into()).unwrap();
+        assert_eq!(id, 4);
+        match plan.get_opr(3).unwrap().opr {
+            Some(Opr::Union(union)) => assert_eq!(union.parents, vec![4, 2]),
+            _ => panic!("the node 3 is not a `Union`"),
+        }
+        assert_eq!(plan.get_node(3).unwrap().borrow().parents, vec![2, 4].into_iter().collect());
+    }
+
+    #[test]
+    fn append_subplan() {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(
+            pb::Select { predicate: str_to_expr_pb("@a.age > 10".to_string()).ok() }.into(),
+            vec![0],
+        )
+        .unwrap();
+
+        // 0 (as b) -> 1, 2 -> 3 (union) -> 4 (select)
+        let as_opr: pb::logical_plan::Operator = pb::As { alias: None }.into();
+        let mut fragment = LogicalPlan::default();
+        fragment
+            .append_operator_as_node(pb::As { alias: Some("b".into()) }.into(), vec![])
+            .unwrap();
+        fragment
+            .append_operator_as_node(as_opr.clone(), vec![0])
+            .unwrap();
+        fragment
+            .append_operator_as_node(as_opr, vec![0])
+            .unwrap();
+        fragment
+            .append_operator_as_node(pb::Union { parents: vec![1, 2], alignment: 0 }.into(), vec![1, 2])
+            .unwrap();
+        fragment
+            .append_operator_as_node(
+                pb::Select { predicate: str_to_expr_pb("@b.name == \"x\"".to_string()).ok() }.into(),
+                vec![3],
+            )
+            .unwrap();
+        fragment
+            .annotate_node(4, "filter by name".to_string())
+            .unwrap();
+
+        let (roots, leaves) = plan.append_subplan(&fragment, vec![1]).unwrap();
+        assert_eq!(roots, vec![2]);
+        assert_eq!(leaves, vec![6]);
+        assert_eq!(plan.len(), 7);
+        assert_eq!(plan.get_node(2).unwrap().borrow().parents, vec![1].into_iter().collect());
+        match plan.get_opr(5).unwrap().opr {
+            Some(Opr::Union(union)) => assert_eq!(union.parents, vec![3, 4]),
+            _ => panic!("the node 5 is not a `Union`"),
+        }
+        assert_eq!(plan.get_node(6).unwrap().borrow().annotations, vec!["filter by name".to_string()]);
+        // the tag `b` is carried by its name
+        let b_id = plan.meta.get_tag_id("b").unwrap();
+        assert_ne!(b_id, plan.meta.get_tag_id("a").unwrap());
+        assert!(plan.meta.get_node_tags(6).contains(&b_id));
+        assert_eq!(fragment.len(), 5);
+
+        assert!(matches!(plan.append_subplan(&fragment, vec![10]), Err(IrError::ParentNodeNotExist(10))));
+
+        // the path of the fragment is too long for the plan, which is left unchanged
+        let path = pb::PathExpand {
+            base: Some(pb::path_expand::ExpandBase {
+                edge_expand: Some(pb::EdgeExpand {
+                    v_tag: None,
+                    direction: 0,
+                    params: Some(query_params(vec![], vec![])),
+                    expand_opt: 0,
+                    alias: None,
+                    meta_data: None,
+                    dedup_both: false,
+                    exclude_self_loops: false,
+                    collapse_parallel_edges: false,
+                    vertex_tables: vec![],
+                    is_optional: false,
+                }),
+                get_v: None,
+            }),
+            start_tag: None,
+            alias: None,
+            hop_range: Some(pb::Range { lower: 1, upper: 10 }),
+            path_opt: 0,
+            result_opt: 0,
+            condition: None,
+            timeout_ms: 0,
+        };
+        fragment
+            .append_operator_as_node(path.into(), vec![4])
+            .unwrap();
+        plan.meta.set_max_hops(Some(3));
+        let expected = plan.clone_plan();
+        assert!(matches!(
+            plan.append_subplan(&fragment, vec![6]),
+            Err(IrError::HopRangeExceeded(1, 10, 3))
+        ));
+        assert_eq!(plan, expected);
+        assert_eq!(plan.max_node_id, 7);
+    }
+
+    #[test]
+    fn logical_plan_from_pb() {
+        let opr = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
+        };
+        let root_pb = pb::logical_plan::Node {
+            opr: Some(opr.clone()),
+            children: vec![1, 2],
+            annotations: vec![],
+            display_name: "".to_string(),
+            accesses: vec![],
+            attributes: HashMap::new(),
+        };
+        let node1_pb = pb::logical_plan::Node {
+            opr: Some(opr.clone()),
+            children: vec![2],
+            annotations: vec![],
+            display_name: "".to_string(),
+            accesses: vec![],
+            attributes: HashMap::new(),
+        };
+        let node2_pb = pb::logical_plan::Node {
+            opr: Some(opr.clone()),
+            children: vec![],
+            annotations: vec![],
+            display_name: "".to_string(),
+            accesses: vec![],
+            attributes: HashMap::new(),
+        };
+        let plan_pb = pb::LogicalPlan {
+            nodes: vec![root_pb, node1_pb, node2_pb],
+            roots: vec![0],
+            annotations: vec![],
+            allow_partial: false,
+            super_node_policy: None,
+            format_version: PLAN_FORMAT_VERSION,
+            origin: None,
+            continuous: None,
+            read_scope: None,
+            view: None,
+        };
+
+        let plan = LogicalPlan::try_from(plan_pb).unwrap();
+        assert_eq!(plan.len(), 3);
+        let node0 = plan.get_node(0).unwrap();
+        let node1 = plan.get_node(1).unwrap();
+        let node2 = plan.get_node(2).unwrap();
+
+        let children = node0
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(children, vec![1, 2]);
+
+        let children = node1
+            .borrow()
+            .children
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(children, vec![2]);
+
+        let parents = node1
+            .borrow()
+            .parents
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(parents, vec![0]);
+
+        let parents = node2
+            .borrow()
+            .parents
+            .iter()
+            .map(|x| *x)
+            .collect::<Vec<NodeId>>();
+        assert_eq!(parents, vec![0, 1]);
+    }
+
+    #[test]
+    fn node_display_names() {
+        let mut plan = LogicalPlan::default();
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec!["knows".into()], vec![])),
+            alias: None,
+            expand_opt: 0,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let id0 = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let id1 = plan
+            .append_operator_as_node(expand.into(), vec![id0])
+            .unwrap();
+        let id2 = plan
+            .append_operator_as_node(
+                pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false }.into(),
+                vec![id1],
+            )
+            .unwrap();
+        assert_eq!(plan.describe_node(id0).unwrap(), "Scan[person] (#0)");
+        assert_eq!(plan.describe_node(id1).unwrap(), "EdgeExpand[knows, OUT] (#1)");
+        assert_eq!(plan.describe_node(id2).unwrap(), "Limit[0..10] (#2)");
+        assert!(plan.describe_node(3).is_none());
+
+        plan.set_display_name(id1, "out('knows')".to_string())
+            .unwrap();
+        assert_eq!(plan.describe_node(id1).unwrap(), "out('knows') (#1)");
+        let plan_from_pb = LogicalPlan::try_from(pb::LogicalPlan::from(plan)).unwrap();
+        assert_eq!(plan_from_pb.describe_node(id1).unwrap(), "out('knows') (#1)");
+        assert_eq!(plan_from_pb.describe_node(id0).unwrap(), "Scan[person] (#0)");
+    }
+
+    #[test]
+    fn logical_plan_to_dot() {
+        let mut plan = LogicalPlan::default();
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec!["knows".into()], vec![])),
+            alias: None,
+            expand_opt: 0,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let id0 = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let id1 = plan
+            .append_operator_as_node(expand.into(), vec![id0])
+            .unwrap();
+        let id2 = plan
+            .append_operator_as_node(
+                pb::Limit { range: Some(pb::Range { lower: 0, upper: 1 }), per_partition: false }.into(),
+                vec![],
+            )
+            .unwrap();
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: id2 as PbNodeId,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
+        plan.append_operator_as_node(apply.into(), vec![id1])
+            .unwrap();
+        plan.set_display_name(id1, "out(\"knows\")".to_string())
+            .unwrap();
+        plan.annotate_node(id0, "g.V().hasLabel('person')".to_string())
+            .unwrap();
+        plan.annotate_node(id0, "line 1".to_string())
+            .unwrap();
+        plan.set_node_attribute(id0, "rows".to_string(), "100".to_string())
+            .unwrap();
+        plan.set_node_attribute(id0, "cost".to_string(), "1.5".to_string())
+            .unwrap();
+        plan.annotate_plan("query \"q1\"".to_string());
+
+        let expected = vec![
+            "digraph plan {",
+            "  label=\"query \\\"q1\\\"\";",
+            // the annotations of the node follow its description line by line
+            "  n0 [label=\"Scan[person] (#0)\\ncost=1.5\\nrows=100\\ng.V().hasLabel('person')\\nline 1\"];",
+            "  n0 -> n1;",
+            // the quotes within the label are escaped
+            "  n1 [label=\"out(\\\"knows\\\") (#1)\"];",
+            "  n1 -> n3;",
+            "  n2 [label=\"Limit[0..1] (#2)\"];",
+            "  n3 [label=\"Apply (#3)\"];",
+            "  n3 -> n2 [style=dashed, label=\"subtask\"];",
+            "}",
+        ];
+        assert_eq!(plan.to_dot(), expected.join("\n"));
+    }
+
+    #[test]
+    fn plan_roots_and_sinks() {
+        let mut plan = LogicalPlan::default();
+        assert!(plan.get_roots().is_empty());
+        assert!(plan.get_sinks().is_empty());
+
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let limit = |upper: i32| -> pb::logical_plan::Operator {
+            pb::Limit { range: Some(pb::Range { lower: 0, upper }), per_partition: false }.into()
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(limit(10), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(limit(20), vec![0])
+            .unwrap();
+        assert_eq!(plan.get_roots(), vec![0]);
+        assert_eq!(plan.get_sinks(), vec![1, 2]);
+
+        // the nodes of the subtask are neither roots nor sinks
+        plan.append_operator_as_node(limit(1), vec![])
+            .unwrap();
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: 3,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
+        plan.append_operator_as_node(apply.into(), vec![1])
+            .unwrap();
+        assert_eq!(plan.get_roots(), vec![0]);
+        assert_eq!(plan.get_sinks(), vec![2, 4]);
+
+        // the roots and the sinks are kept by a plan deserialized from a pb
+        let plan = LogicalPlan::try_from(pb::LogicalPlan::from(plan)).unwrap();
+        assert_eq!(plan.get_roots(), vec![0]);
+        assert_eq!(plan.get_sinks(), vec![2, 4]);
+    }
+
+    #[test]
+    fn explain_plan() {
+        let mut plan = LogicalPlan::default();
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec!["knows".into()], vec![])),
+            alias: Some("b".into()),
+            expand_opt: 0,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let select = pb::Select { predicate: str_to_expr_pb("@b.age > 10".to_string()).ok() };
+        let id0 = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let id1 = plan
+            .append_operator_as_node(expand.into(), vec![id0])
+            .unwrap();
+        let id2 = plan
+            .append_operator_as_node(select.into(), vec![id1])
+            .unwrap();
+        let id3 = plan
+            .append_operator_as_node(
+                pb::Limit { range: Some(pb::Range { lower: 0, upper: 1 }), per_partition: false }.into(),
+                vec![],
+            )
+            .unwrap();
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: id3 as PbNodeId,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
+        let id4 = plan
+            .append_operator_as_node(apply.into(), vec![id2])
+            .unwrap();
+        let union = pb::Union { parents: vec![id1 as PbNodeId, id4 as PbNodeId], alignment: 0 };
+        plan.append_operator_as_node(union.into(), vec![id1, id4])
+            .unwrap();
+        plan.annotate_node(id1, "g.V().as('a').out('knows')".to_string())
+            .unwrap();
+        plan.annotate_node(id1, "line 2".to_string())
+            .unwrap();
+        plan.set_node_attribute(id1, "step".to_string(), "out".to_string())
+            .unwrap();
+        plan.set_node_attribute(id0, "rows".to_string(), "100".to_string())
+            .unwrap();
+        plan.annotate_plan("query q1".to_string());
+        plan.set_super_node_policy(pb::SuperNodePolicy { action: 1, threshold: 1000 });
+
+        let expected = vec![
+            "super node: SAMPLE beyond 1000 edge(s)",
+            "annotation: query q1",
+            "Union (#5)",
+            "  EdgeExpand[knows, OUT] AS b {step=out} (#1) // g.V().as('a').out('knows'); line 2",
+            "    Scan[person] AS a {rows=100} (#0)",
+            "  Apply SEMI (#4)",
+            "    subtask:",
+            "      Limit[0..1] (#3)",
+            "    Select WHERE @b.age > 10 (#2)",
+            // explained as the input of the union already
+            "      -> #1",
+        ];
+        assert_eq!(plan.explain(), expected.join("\n"));
+    }
+
+    #[test]
+    fn logical_plan_annotations() {
+        let mut plan = LogicalPlan::default();
+        let opr = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
+        };
+        let id0 = plan
+            .append_operator_as_node(opr.clone(), vec![])
+            .unwrap();
+        let id1 = plan
+            .append_operator_as_node(opr, vec![id0])
+            .unwrap();
+        plan.annotate_plan("g.V().as('a')".to_string());
+        plan.annotate_node(id0, "V()".to_string())
+            .unwrap();
+        plan.annotate_node(id1, "as('a')".to_string())
+            .unwrap();
+        assert!(plan.annotate_node(10, "".to_string()).is_err());
+
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        assert_eq!(plan_pb.annotations, vec!["g.V().as('a')".to_string()]);
+        assert_eq!(plan_pb.nodes[1].annotations, vec!["as('a')".to_string()]);
+        let plan_from_pb = LogicalPlan::try_from(plan_pb).unwrap();
+        assert_eq!(plan_from_pb, plan);
+        assert_eq!(
+            plan_from_pb
+                .get_node(id0)
+                .unwrap()
+                .borrow()
+                .annotations,
+            vec!["V()".to_string()]
+        );
+    }
+
+    #[test]
+    fn logical_plan_node_attributes() {
+        let mut plan = LogicalPlan::default();
+        let opr = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
+        };
+        let id0 = plan
+            .append_operator_as_node(opr.clone(), vec![])
+            .unwrap();
+        let id1 = plan
+            .append_operator_as_node(opr, vec![id0])
+            .unwrap();
+        let fingerprint = plan.fingerprint().unwrap();
+        assert_eq!(
+            plan.set_node_attribute(id0, "line".to_string(), "1".to_string())
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            plan.set_node_attribute(id0, "line".to_string(), "2".to_string())
+                .unwrap(),
+            Some("1".to_string())
+        );
+        plan.set_node_attribute(id1, "cardinality".to_string(), "100".to_string())
+            .unwrap();
+        assert!(matches!(
+            plan.set_node_attribute(10, "line".to_string(), "1".to_string()),
+            Err(IrError::NodeNotExist(10))
+        ));
+        assert_eq!(plan.get_node_attribute(id0, "line"), Some("2".to_string()));
+        assert_eq!(plan.get_node_attribute(id0, "cardinality"), None);
+        // the attributes do not affect the results
+        assert_eq!(plan.fingerprint().unwrap(), fingerprint);
+
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        assert_eq!(plan_pb.nodes[1].attributes.get("cardinality"), Some(&"100".to_string()));
+        let plan_from_pb = LogicalPlan::try_from(plan_pb).unwrap();
+        assert_eq!(plan_from_pb, plan);
+        assert_eq!(plan_from_pb.get_node_attribute(id0, "line"), Some("2".to_string()));
+    }
+
+    #[test]
+    fn logical_plan_json() {
+        let mut plan = LogicalPlan::default();
+        let select = pb::Select { predicate: str_to_expr_pb("@.age > 10".to_string()).ok() };
+        let id0 = plan
+            .append_operator_as_node(select.into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(
+            pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false }.into(),
+            vec![id0],
+        )
+        .unwrap();
+        plan.annotate_node(id0, "has('age', gt(10))".to_string())
+            .unwrap();
+
+        let mut json = vec![];
+        plan.clone().into_json(&mut json).unwrap();
+        let plan_from_json = LogicalPlan::from_json(json.as_slice()).unwrap();
+        assert_eq!(plan_from_json, plan);
+
+        assert!(LogicalPlan::from_json("{\"nodes\": 1}".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn logical_plan_ordering() {
+        let order_by = |keys: Vec<&str>, limit: Option<pb::Range>| pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::OrderBy(pb::OrderBy {
+                pairs: keys
+                    .into_iter()
+                    .map(|key| pb::order_by::OrderingPair {
+                        key: Some(
+                            str_to_expr_pb(key.to_string())
+                                .unwrap()
+                                .operators[0]
+                                .clone(),
+                        )
+                        .and_then(|opr| match opr.item {
+                            Some(common_pb::expr_opr::Item::Var(var)) => Some(var),
+                            _ => None,
+                        }),
+                        order: 1,
+                    })
+                    .collect(),
+                limit,
+                per_partition: false,
+            })),
+        };
+        let select = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::Select(pb::Select {
+                predicate: Some(str_to_expr_pb("@.age > 10".to_string()).unwrap()),
+            })),
+        };
+        let scan = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::Scan(pb::Scan {
+                scan_opt: 0,
+                alias: None,
+                params: None,
+                idx_predicate: None,
+                meta_data: None,
+                connector: None,
+                named_result: String::new(),
+            })),
+        };
+        let mut plan = LogicalPlan::default();
+        let id0 = plan
+            .append_operator_as_node(scan, vec![])
+            .unwrap();
+        assert!(plan.get_ordering(id0).is_none());
+        let id1 = plan
+            .append_operator_as_node(order_by(vec!["@.name", "@.age"], None), vec![id0])
+            .unwrap();
+        assert_eq!(plan.get_ordering(id1).unwrap().len(), 2);
+        // the order is preserved by select
+        let id2 = plan
+            .append_operator_as_node(select, vec![id1])
+            .unwrap();
+        assert_eq!(plan.get_ordering(id2), plan.get_ordering(id1));
+        // a redundant order is elided
+        let id3 = plan
+            .append_operator_as_node(order_by(vec!["@.name"], None), vec![id2])
+            .unwrap();
+        assert_eq!(id3, id2);
+        assert_eq!(plan.len(), 3);
+        // an order with a limit, or of different keys, is kept
+        let id4 = plan
+            .append_operator_as_node(
+                order_by(vec!["@.name"], Some(pb::Range { lower: 0, upper: 10 })),
+                vec![id3],
+            )
+            .unwrap();
+        assert_ne!(id4, id3);
+        let id5 = plan
+            .append_operator_as_node(order_by(vec!["@.age"], None), vec![id4])
+            .unwrap();
+        assert_ne!(id5, id4);
+        assert_eq!(plan.len(), 5);
+
+        // the ordering kept by the select is explained, while the one of the order itself is not
+        let explain = plan.explain();
+        let line_of = |name: &str| {
+            explain
+                .lines()
+                .find(|line| line.trim_start().starts_with(name))
+                .unwrap()
+                .to_string()
+        };
+        assert!(line_of("Select").contains(" ORDERED BY "));
+        assert!(!line_of("Scan").contains(" ORDERED BY "));
+        assert!(explain
+            .lines()
+            .filter(|line| line.trim_start().starts_with("OrderBy"))
+            .all(|line| !line.contains(" ORDERED BY ")));
+    }
+
+    // e.g., g.V().values('name').count()
+    #[test]
+    fn strip_fetches_before_count() {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let project = |alias: Option<common_pb::NameOrId>| pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@.name".to_string()).ok(),
+                alias,
+            }],
+            is_append: false,
+            meta_data: vec![],
+        };
+        let count = |tag: Option<common_pb::NameOrId>| pb::Count { tag, alias: None };
+
+        // the fetch of the names is stripped
+        let mut plan = LogicalPlan::default();
+        let id0 = plan
+            .append_operator_as_node(scan.clone().into(), vec![])
+            .unwrap();
+        let id1 = plan
+            .append_operator_as_node(project(None).into(), vec![id0])
+            .unwrap();
+        let id2 = plan
+            .append_operator_as_node(count(None).into(), vec![id1])
+            .unwrap();
+        assert_eq!(plan.len(), 2);
+        assert!(plan.get_node(id1).is_none());
+        assert_eq!(
+            plan.get_node(id2)
+                .unwrap()
+                .borrow()
+                .parents
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![id0]
+        );
+
+        // the fetch of the names aliased by a tag, which may be counted, is kept
+        let mut plan = LogicalPlan::default();
+        let id0 = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let id1 = plan
+            .append_operator_as_node(project(Some("a".into())).into(), vec![id0])
+            .unwrap();
+        plan.append_operator_as_node(count(Some("a".into())).into(), vec![id1])
+            .unwrap();
+        assert_eq!(plan.len(), 3);
+    }
+
+    #[test]
+    fn logical_plan_require_ordering() {
+        let order_pair = |key: &str| pb::order_by::OrderingPair {
+            key: match str_to_expr_pb(key.to_string())
+                .unwrap()
+                .operators[0]
+                .item
+                .clone()
+            {
+                Some(common_pb::expr_opr::Item::Var(var)) => Some(var),
+                _ => None,
+            },
+            order: 1,
+        };
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: None,
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let order_by = pb::OrderBy { pairs: vec![order_pair("@.name")], limit: None, per_partition: false };
+        let select = pb::Select { predicate: Some(str_to_expr_pb("@.age > 10".to_string()).unwrap()) };
+        let dedup = pb::Dedup { keys: vec![] };
+
+        let mut plan = LogicalPlan::default();
+        let id0 = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let id1 = plan
+            .append_operator_as_node(order_by.into(), vec![id0])
+            .unwrap();
+        let id2 = plan
+            .append_operator_as_node(select.into(), vec![id1])
+            .unwrap();
+        let id3 = plan
+            .append_operator_as_node(dedup.into(), vec![id2])
+            .unwrap();
+
+        // the order is kept by the select
+        assert!(plan.require_ordering(id2, &[]).is_ok());
+        assert!(plan
+            .require_ordering(id2, &plan.get_ordering(id1).unwrap().to_vec())
+            .is_ok());
+        // the order is broken by the dedup
+        match plan.require_ordering(id3, &[]) {
+            Err(IrError::InvalidNode(id, msg)) => {
+                assert_eq!(id, id3);
+                assert!(msg.ends_with(&format!("the order is not kept by Dedup (#{})", id3)));
+            }
+            _ => panic!("should fail on the dedup that breaks the order"),
+        }
+        // the output is ordered by another key
+        match plan.require_ordering(id2, &[order_pair("@.age")]) {
+            Err(IrError::InvalidNode(id, msg)) => {
+                assert_eq!(id, id2);
+                assert!(msg.contains(&format!("by OrderBy (#{})", id1)));
+            }
+            _ => panic!("should fail on the order of another key"),
+        }
+        // the scan is in no order
+        assert!(plan.require_ordering(id0, &[]).is_err());
+        assert!(plan.require_ordering(10, &[]).is_err());
+    }
+
+    #[test]
+    fn logical_plan_sizes() {
+        let opr = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
+        };
+        let mut plan = LogicalPlan::default();
+        let empty_footprint = plan.memory_footprint();
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        assert_eq!(plan.serialized_size(), plan_pb.encode_to_vec().len());
+
+        let id0 = plan
+            .append_operator_as_node(opr.clone(), vec![])
+            .unwrap();
+        let footprint = plan.memory_footprint();
+        let size = plan.serialized_size();
+        assert!(footprint > empty_footprint);
+        assert!(size > 0);
+
+        let id1 = plan
+            .append_operator_as_node(opr, vec![id0])
+            .unwrap();
+        plan.annotate_plan("g.V().as('a')".to_string());
+        assert!(plan.memory_footprint() > footprint);
+        assert!(plan.serialized_size() > size);
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        assert_eq!(plan.serialized_size(), plan_pb.encode_to_vec().len());
+
+        // the size of the nodes of the subtasks, the annotations and the display names are summed up
+        let id2 = plan
+            .append_operator_as_node(
+                pb::Limit { range: Some(pb::Range { lower: 0, upper: 1 }), per_partition: false }.into(),
+                vec![],
+            )
+            .unwrap();
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: id2 as PbNodeId,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
+        plan.append_operator_as_node(apply.into(), vec![id1])
+            .unwrap();
+        plan.set_display_name(id1, "as('a')".to_string())
+            .unwrap();
+        plan.annotate_node(id0, "g.V()".to_string())
+            .unwrap();
+        plan.set_node_attribute(id0, "line".to_string(), "1".to_string())
+            .unwrap();
+        plan.set_allow_partial(true);
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        assert_eq!(plan.serialized_size(), plan_pb.encode_to_vec().len());
+    }
+
+    #[test]
+    fn logical_plan_with_string_table() {
+        let mut plan = LogicalPlan::default();
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        // the names of the properties repeat in every operator
+        for age in 20..30 {
+            let predicate = format!("@.firstName != \"marko\" && @.creationDate > {}", age);
+            let select = pb::Select { predicate: str_to_expr_pb(predicate).ok() };
+            id = plan
+                .append_operator_as_node(select.into(), vec![id])
+                .unwrap();
+        }
+
+        let compact_pb = plan.to_compact_pb().unwrap();
+        assert_eq!(
+            compact_pb.strings,
+            vec!["person".to_string(), "firstName".to_string(), "creationDate".to_string()]
+        );
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        assert!(compact_pb.encoded_len() < plan_pb.encoded_len());
+
+        let plan_from_compact = LogicalPlan::try_from(compact_pb).unwrap();
+        assert_eq!(pb::LogicalPlan::from(plan_from_compact), plan_pb);
+
+        let invalid_pb = pb::CompactLogicalPlan { plan: Some(plan_pb), strings: vec![] };
+        assert!(LogicalPlan::try_from(invalid_pb).is_err());
+    }
+
+    #[test]
+    fn logical_plan_redacted() {
+        let mut plan = LogicalPlan::default();
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let predicate = "@.email == \"marko@example.com\" && @.age within [29, 31]".to_string();
+        let select = pb::Select { predicate: str_to_expr_pb(predicate).ok() };
+        let id = plan
+            .append_operator_as_node(select.into(), vec![id])
+            .unwrap();
+        plan.annotate_node(id, "has('email', 'marko@example.com')".to_string())
+            .unwrap();
+        plan.annotate_plan("g.V().has('age', within(29, 31))".to_string());
+
+        let redacted = plan.to_debug_string_redacted().unwrap();
+        // the structure is kept
+        assert!(redacted.contains("person"));
+        assert!(redacted.contains("email"));
+        assert!(redacted.contains("\"Str\": \"<redacted>\""));
+        assert!(redacted.contains("\"I64Array\": \"<redacted>\""));
+        // while the constants and the annotations are masked
+        assert!(!redacted.contains("marko"));
+        assert!(!redacted.contains("29"));
+        // while the plan itself is not changed
+        let plan_pb: pb::LogicalPlan = plan.into();
+        assert!(serde_json::to_string(&plan_pb)
+            .unwrap()
+            .contains("marko@example.com"));
+    }
+
+    #[test]
+    fn logical_plan_into_pb() {
+        let opr = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
+        };
+        let mut plan = LogicalPlan::default();
+
+        let _ = plan
+            .append_operator_as_node(opr.clone(), vec![])
+            .unwrap();
+        let _ = plan
+            .append_operator_as_node(opr.clone(), vec![0])
+            .unwrap();
+        let _ = plan
+            .append_operator_as_node(opr.clone(), vec![0])
+            .unwrap();
+
+        let _ = plan.remove_node(1);
+
+        let plan_pb = pb::LogicalPlan::from(plan);
+        assert_eq!(plan_pb.nodes.len(), 2);
+
+        let node0 = &plan_pb.nodes[0];
+        let node1 = &plan_pb.nodes[1];
+        assert_eq!(node0.opr, Some(opr.clone()));
+        assert_eq!(node0.children, vec![1]);
+        assert_eq!(node1.opr, Some(opr.clone()));
+        assert!(node1.children.is_empty());
+    }
+
+    #[test]
+    fn preprocess_expr() {
+        let mut plan_meta = PlanMeta::default();
+        let a_id = plan_meta.get_or_set_tag_id("a").1;
+        let b_id = plan_meta.get_or_set_tag_id("b").1;
+        plan_meta.set_tag_nodes(a_id, vec![1]);
+        plan_meta.set_tag_nodes(b_id, vec![2]);
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+
+        let meta = StoreMeta {
+            schema: Some(Schema::new(
+                vec![("person".to_string(), 0), ("software".to_string(), 1)],
+                vec![("knows".to_string(), 0), ("creates".to_string(), 1)],
+                vec![("id".to_string(), 0), ("name".to_string(), 1), ("age".to_string(), 2)],
+            )),
+            version: 0,
+        };
+
+        let mut expression = str_to_expr_pb("@.~label == \"person\"".to_string()).unwrap();
+        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
+        let opr = expression.operators.get(2).unwrap().clone();
+        match opr.item.unwrap() {
+            common_pb::expr_opr::Item::Const(val) => match val.item.unwrap() {
+                common_pb::value::Item::I32(i) => assert_eq!(i, 0),
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+
+        let mut expression =
+            str_to_expr_pb("@.~label within [\"person\", \"software\"]".to_string()).unwrap();
+        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
+        let opr = expression.operators.get(2).unwrap().clone();
+        match opr.item.unwrap() {
+            common_pb::expr_opr::Item::Const(val) => match val.item.unwrap() {
+                common_pb::value::Item::I32Array(arr) => {
+                    assert_eq!(arr.item, vec![0, 1]);
+                }
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+
+        let mut expression =
+            str_to_expr_pb("(@.name == \"person\") && @a.~label == \"knows\"".to_string()).unwrap();
+        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
+
+        // person should not be mapped, as name is not a label key
+        let opr = expression.operators.get(3).unwrap().clone();
+        match opr.item.unwrap() {
+            common_pb::expr_opr::Item::Const(val) => match val.item.unwrap() {
+                common_pb::value::Item::Str(str) => assert_eq!(str, "person".to_string()),
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+        // "knows maps to 0"
+        let opr = expression.operators.get(8).unwrap().clone();
+        match opr.item.unwrap() {
+            common_pb::expr_opr::Item::Const(val) => match val.item.unwrap() {
+                common_pb::value::Item::I32(i) => assert_eq!(i, 0),
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+
+        // Assert whether the columns have been updated in PlanMeta
+        assert_eq!(
+            plan_meta
+                .get_curr_node_meta()
+                .unwrap()
+                .get_tag_columns()
+                .get(&None)
+                .unwrap()
+                .get(),
+            // has a new column "name", which is mapped to 1
+            vec![1.into()]
+        );
+
+        // name maps to 1
+        let mut expression = str_to_expr_pb("@a.name == \"John\"".to_string()).unwrap();
+        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
+        let opr = expression.operators.get(0).unwrap().clone();
+        match opr.item.unwrap() {
+            common_pb::expr_opr::Item::Var(var) => {
+                match var.clone().property.unwrap().item.unwrap() {
+                    Item::Key(key) => assert_eq!(key, 1.into()),
+                    _ => panic!(),
+                }
+                assert_eq!(var.tag.unwrap(), (a_id as i32).into());
+            }
+            _ => panic!(),
+        }
+
+        // Assert whether the columns have been updated in PlanMeta
+        assert_eq!(
+            plan_meta
+                .get_curr_node_meta()
+                .unwrap()
+                .get_tag_columns()
+                .get(&Some(a_id))
+                .unwrap()
+                .get(),
+            // node1 with tag a has a new column "name", which is mapped to 1
+            vec![1.into()]
+        );
+
+        let mut expression = str_to_expr_pb("{@a.name, @b.id}".to_string()).unwrap();
+        preprocess_expression(&mut expression, &meta, &mut plan_meta, false).unwrap();
+        let opr = expression.operators.get(0).unwrap().clone();
+        match opr.item.unwrap() {
+            common_pb::expr_opr::Item::VarMap(vars) => {
+                let var1 = vars.keys[0].clone();
+                match var1.property.unwrap().item.unwrap() {
+                    Item::Key(key) => assert_eq!(key, 1.into()),
+                    _ => panic!(),
+                }
+                assert_eq!(var1.tag.unwrap(), (a_id as i32).into());
+                let var2 = vars.keys[1].clone();
+                match var2.property.unwrap().item.unwrap() {
+                    Item::Key(key) => assert_eq!(key, 0.into()),
+                    _ => panic!(),
+                }
+                assert_eq!(var2.tag.unwrap(), (b_id as i32).into());
+            }
+            _ => panic!(),
+        }
+
+        // Assert whether the columns have been updated in PlanMeta
+        assert_eq!(
+            plan_meta
+                .get_curr_node_meta()
+                .unwrap()
+                .get_tag_columns()
+                .get(&Some(a_id))
+                .unwrap()
+                .get(),
+            // node1 with tag a has a new column "name", which is mapped to 1
+            vec![1.into()]
+        );
+        assert_eq!(
+            plan_meta
+                .get_curr_node_meta()
+                .unwrap()
+                .get_tag_columns()
+                .get(&Some(b_id))
+                .unwrap()
+                .get(),
+            // node2 with tag b has a new column "id", which is mapped to 0
+            vec![0.into()]
+        );
+    }
+
+    #[test]
+    fn preprocess_scan() {
+        let mut plan_meta = PlanMeta::default();
+        let a_id = plan_meta.get_or_set_tag_id("a").1;
+        plan_meta.set_tag_nodes(a_id, vec![1]);
+
+        plan_meta.curr_node_meta_mut();
+        plan_meta
+            .tag_nodes_meta_mut(Some(a_id))
+            .unwrap();
+        plan_meta.refer_to_nodes(0, vec![0]);
+
+        let meta = StoreMeta {
+            schema: Some(Schema::new(
+                vec![("person".to_string(), 0), ("software".to_string(), 1)],
+                vec![("knows".to_string(), 0), ("creates".to_string(), 1)],
+                vec![("id".to_string(), 0), ("name".to_string(), 1), ("age".to_string(), 2)],
+            )),
+            version: 0,
+        };
+
+        let mut scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec!["person".into()],
+                columns: vec!["age".into(), "name".into()],
+                is_all_columns: false,
+                limit: None,
+                predicate: Some(
+                    str_to_expr_pb("@a.~label > \"person\" && @a.age == 10".to_string()).unwrap(),
+                ),
+                sample_ratio: 1.0,
+                extra: HashMap::new(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: Some(vec!["software".to_string()].into()),
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        scan.preprocess(&meta, &mut plan_meta).unwrap();
+        assert_eq!(scan.clone().params.unwrap().tables[0], 0.into());
+        assert_eq!(
+            scan.idx_predicate.unwrap().or_predicates[0].predicates[0]
+                .value
+                .clone()
+                .unwrap(),
+            1.into()
+        );
+        let operators = scan
+            .params
+            .clone()
+            .unwrap()
+            .predicate
+            .unwrap()
+            .operators;
+        match operators.get(2).unwrap().item.as_ref().unwrap() {
+            common_pb::expr_opr::Item::Const(val) => assert_eq!(val.clone(), 0.into()),
+            _ => panic!(),
+        }
+        match operators.get(4).unwrap().item.as_ref().unwrap() {
+            common_pb::expr_opr::Item::Var(var) => {
+                match var
+                    .property
+                    .as_ref()
+                    .unwrap()
+                    .item
+                    .clone()
+                    .unwrap()
+                {
+                    Item::Key(key) => assert_eq!(key, 2.into()),
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+        // Assert whether the columns have been updated in PlanMeta
+        assert_eq!(
+            plan_meta
+                .get_node_meta(0)
+                .unwrap()
+                .get_columns(),
+            vec![1.into(), 2.into()]
+        );
+
+        // The column "age" of a predicate "a.age == 10" should not be added
+        assert!(plan_meta
+            .get_node_meta(1)
+            .unwrap()
+            .get_columns()
+            .is_empty());
+    }
+
+    #[test]
+    fn preprocess_scan_connector() {
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+        let meta = StoreMeta {
+            schema: Some(
+                Schema::from_json(std::fs::File::open("resource/modern_schema.json").unwrap()).unwrap(),
+            ),
+            version: 0,
+        };
+        let connector = pb::scan::Connector {
+            uri: "hdfs://path/to/orders.csv".to_string(),
+            format: pb::scan::connector::Format::Csv as i32,
+            columns: vec![
+                pb::scan::connector::Column {
+                    name: "order_id".to_string(),
+                    data_type: common_pb::DataType::Int64 as i32,
+                },
+                pb::scan::connector::Column {
+                    name: "person_name".to_string(),
+                    data_type: common_pb::DataType::String as i32,
+                },
+            ],
+            options: HashMap::new(),
+        };
+        let mut scan = pb::Scan {
+            scan_opt: 2,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec!["orders".into()],
+                columns: vec!["person_name".into()],
+                is_all_columns: false,
+                limit: None,
+                predicate: None,
+                sample_ratio: 1.0,
+                extra: HashMap::new(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: None,
+            meta_data: None,
+            connector: Some(connector),
+            named_result: String::new(),
+        };
+        // the table of an external source is not resolved against the schema
+        scan.clone()
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+        assert_eq!(
+            plan_meta
+                .get_node_meta(0)
+                .unwrap()
+                .get_columns(),
+            vec!["person_name".into()]
+        );
+
+        let mut absent_column = scan.clone();
+        absent_column.params.as_mut().unwrap().columns = vec!["age".into()];
+        assert!(absent_column
+            .preprocess(&meta, &mut plan_meta)
+            .is_err());
+
+        scan.scan_opt = 0;
+        assert!(scan.preprocess(&meta, &mut plan_meta).is_err());
+    }
+
+    #[test]
+    fn bulk_load_from_connector() {
+        let connector = pb::scan::Connector {
+            uri: "hdfs://path/to/knows.csv".to_string(),
+            format: pb::scan::connector::Format::Csv as i32,
+            columns: vec![
+                pb::scan::connector::Column {
+                    name: "src_id".to_string(),
+                    data_type: common_pb::DataType::Int64 as i32,
+                },
+                pb::scan::connector::Column {
+                    name: "dst_id".to_string(),
+                    data_type: common_pb::DataType::Int64 as i32,
+                },
+                pb::scan::connector::Column {
+                    name: "since".to_string(),
+                    data_type: common_pb::DataType::Int32 as i32,
+                },
+            ],
+            options: HashMap::new(),
+        };
+        let scan = pb::Scan {
+            scan_opt: 2,
+            alias: None,
+            params: Some(query_params(vec!["knows".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: Some(connector),
+            named_result: String::new(),
+        };
+        let column_mapping = |column: &str, property: &str| pb::sink_bulk_load::ColumnMapping {
+            column: Some(column.into()),
+            property: Some(property.into()),
+        };
+        let bulk_load = pb::SinkBulkLoad {
+            uri: "hdfs://path/to/knows.csv".to_string(),
+            graph_name: "modern".to_string(),
+            graph_schema: None,
+            mapping: Some(pb::sink_bulk_load::Mapping::Edge(pb::sink_bulk_load::EdgeMapping {
+                label: Some("knows".into()),
+                src: Some(pb::sink_bulk_load::VertexMapping {
+                    label: Some("person".into()),
+                    column_mappings: vec![column_mapping("src_id", "id")],
+                }),
+                dst: Some(pb::sink_bulk_load::VertexMapping {
+                    label: Some("person".into()),
+                    column_mappings: vec![column_mapping("dst_id", "id")],
+                }),
+                column_mappings: vec![column_mapping("since", "since")],
+            })),
+        };
+        let sink_bulk_load = |bulk_load: pb::SinkBulkLoad| pb::Sink {
+            tags: vec![common_pb::NameOrIdKey { key: None }],
+            sink_target: Some(pb::sink::SinkTarget {
+                inner: Some(pb::sink::sink_target::Inner::SinkBulkLoad(bulk_load)),
+            }),
+        };
+        let mut plan = LogicalPlan::default();
+        let id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        // the rows can be transformed by the plan before being loaded
+        let id = plan
+            .append_operator_as_node(
+                pb::Limit { range: Some(pb::Range { lower: 0, upper: 1000 }), per_partition: false }.into(),
+                vec![id],
+            )
+            .unwrap();
+
+        // a column absent from the source
+        let mut absent_column = bulk_load.clone();
+        if let Some(pb::sink_bulk_load::Mapping::Edge(edge)) = absent_column.mapping.as_mut() {
+            edge.column_mappings
+                .push(column_mapping("weight", "weight"));
+        }
+        assert!(matches!(
+            plan.append_operator_as_node(sink_bulk_load(absent_column).into(), vec![id]),
+            Err(IrError::ColumnNotExist(_))
+        ));
+        // a source that is not scanned by the plan
+        let mut unscanned_source = bulk_load.clone();
+        unscanned_source.uri = "hdfs://path/to/created.csv".to_string();
+        assert!(matches!(
+            plan.append_operator_as_node(sink_bulk_load(unscanned_source).into(), vec![id]),
+            Err(IrError::Unsupported(_))
+        ));
+        // an edge without its target vertex
+        let mut absent_dst = bulk_load.clone();
+        if let Some(pb::sink_bulk_load::Mapping::Edge(edge)) = absent_dst.mapping.as_mut() {
+            edge.dst = None;
+        }
+        assert!(matches!(
+            plan.append_operator_as_node(sink_bulk_load(absent_dst).into(), vec![id]),
+            Err(IrError::MissingData(_))
+        ));
+
+        let id = plan
+            .append_operator_as_node(sink_bulk_load(bulk_load).into(), vec![id])
+            .unwrap();
+        assert_eq!(plan.get_sinks(), vec![id]);
+    }
+
+    #[test]
+    fn preprocess_scan_named_result() {
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+        let meta = StoreMeta {
+            schema: Some(
+                Schema::from_json(std::fs::File::open("resource/modern_schema.json").unwrap()).unwrap(),
+            ),
+            version: 0,
+        };
+        let mut scan = pb::Scan {
+            scan_opt: 2,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec![],
+                columns: vec!["nick_name".into()],
+                is_all_columns: false,
+                limit: None,
+                predicate: None,
+                sample_ratio: 1.0,
+                extra: HashMap::new(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: "friends".to_string(),
+        };
+        // the columns of a registered result are not resolved against the schema
+        scan.clone()
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+        assert_eq!(
+            plan_meta
+                .get_node_meta(0)
+                .unwrap()
+                .get_columns(),
+            vec!["nick_name".into()]
+        );
+
+        let mut with_connector = scan.clone();
+        with_connector.connector = Some(pb::scan::Connector {
+            uri: "hdfs://path/to/friends.csv".to_string(),
+            format: pb::scan::connector::Format::Csv as i32,
+            columns: vec![],
+            options: HashMap::new(),
+        });
+        assert!(with_connector
+            .preprocess(&meta, &mut plan_meta)
+            .is_err());
+
+        scan.scan_opt = 0;
+        assert!(scan.preprocess(&meta, &mut plan_meta).is_err());
+    }
+
+    #[test]
+    fn preprocess_edgexpd_dedup_both() {
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+        let meta = StoreMeta::default();
+        let mut expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: pb::edge_expand::Direction::Both as i32,
+            params: None,
+            alias: None,
+            expand_opt: pb::edge_expand::ExpandOpt::Vertex as i32,
+            meta_data: None,
+            dedup_both: true,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        expand
+            .clone()
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+
+        // only a `BOTH` expansion yields a self-loop twice
+        expand.direction = pb::edge_expand::Direction::Out as i32;
+        assert!(expand
+            .clone()
+            .preprocess(&meta, &mut plan_meta)
+            .is_err());
+        expand.dedup_both = false;
+        expand
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+    }
+
+    #[test]
+    fn preprocess_edgexpd_vertex_tables() {
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+        let meta = StoreMeta {
+            schema: Some(Schema::new(
+                vec![("person".to_string(), 0), ("software".to_string(), 1)],
+                vec![("knows".to_string(), 0), ("creates".to_string(), 1)],
+                vec![("id".to_string(), 0), ("name".to_string(), 1)],
+            )),
+            version: 0,
+        };
+        // g.V().outE("creates").inV().hasLabel("software")
+        let mut expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: pb::edge_expand::Direction::Out as i32,
+            params: Some(query_params(vec!["creates".into()], vec![])),
+            alias: None,
+            expand_opt: pb::edge_expand::ExpandOpt::Vertex as i32,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec!["software".into()],
+            is_optional: false,
+        };
+        expand
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+        assert_eq!(expand.params.unwrap().tables, vec![1.into()]);
+        assert_eq!(expand.vertex_tables, vec![1.into()]);
+
+        let mut expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: pb::edge_expand::Direction::Out as i32,
+            params: None,
+            alias: None,
+            expand_opt: pb::edge_expand::ExpandOpt::Vertex as i32,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec!["city".into()],
+            is_optional: false,
+        };
+        assert!(expand
+            .preprocess(&meta, &mut plan_meta)
+            .is_err());
+    }
+
+    #[test]
+    fn preprocess_degree_predicates() {
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+        let meta = StoreMeta {
+            schema: Some(Schema::new(
+                vec![("person".to_string(), 0), ("software".to_string(), 1)],
+                vec![("knows".to_string(), 0), ("creates".to_string(), 1)],
+                vec![("id".to_string(), 0), ("name".to_string(), 1)],
+            )),
+            version: 0,
+        };
+        let degree_pred = pb::DegreePredicate {
+            direction: pb::edge_expand::Direction::Out as i32,
+            tables: vec!["knows".into()],
+            range: Some(pb::Range { lower: 0, upper: 1000 }),
+        };
+        // g.V().out(), only from the vertices of less than 1000 outgoing `knows` edges
+        let mut params = query_params(vec![], vec![]);
+        params
+            .degree_predicates
+            .push(degree_pred.clone());
+        let mut expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: pb::edge_expand::Direction::Out as i32,
+            params: Some(params.clone()),
+            alias: None,
+            expand_opt: pb::edge_expand::ExpandOpt::Vertex as i32,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        expand
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+        assert_eq!(expand.params.unwrap().degree_predicates[0].tables, vec![0.into()]);
+
+        // an empty range
+        let mut params = query_params(vec![], vec![]);
+        params
+            .degree_predicates
+            .push(pb::DegreePredicate {
+                range: Some(pb::Range { lower: 10, upper: 10 }),
+                ..degree_pred.clone()
+            });
+        let mut getv = pb::GetV {
+            tag: None,
+            opt: 1,
+            params: Some(params),
+            alias: None,
+            meta_data: None,
+            is_optional: false,
+            end_alias: None,
+        };
+        assert!(getv.preprocess(&meta, &mut plan_meta).is_err());
+
+        // the degree predicates are not supported in scan
+        let mut params = query_params(vec![], vec![]);
+        params.degree_predicates.push(degree_pred);
+        let mut scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(params),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        assert!(scan.preprocess(&meta, &mut plan_meta).is_err());
+    }
+
+    #[test]
+    fn logical_plan_named_results() {
+        let scan = pb::Scan {
+            scan_opt: 2,
+            alias: None,
+            params: None,
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: "friends".to_string(),
+        };
+        let sink_result = |name: &str| pb::Sink {
+            tags: vec![],
+            sink_target: Some(pb::sink::SinkTarget {
+                inner: Some(pb::sink::sink_target::Inner::SinkResult(pb::SinkResult {
+                    name: name.to_string(),
+                })),
+            }),
+        };
+        let mut plan = LogicalPlan::default();
+        let id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        // the name of the result to register is missing
+        assert!(plan
+            .append_operator_as_node(sink_result("").into(), vec![id])
+            .is_err());
+        plan.append_operator_as_node(sink_result("friends_of_friends").into(), vec![id])
+            .unwrap();
+
+        assert_eq!(plan.get_scanned_results(), vec!["friends".to_string()]);
+        assert_eq!(plan.get_registered_results(), vec!["friends_of_friends".to_string()]);
+    }
+
+    #[test]
+    fn scan_pred_to_idx_pred() {
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.set_curr_node(0);
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+        let meta = StoreMeta {
+            schema: Some(
+                Schema::from_json(std::fs::File::open("resource/modern_schema_pk.json").unwrap()).unwrap(),
+            ),
+            version: 0,
+        };
+        let mut scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec!["person".into()],
+                columns: vec![],
+                is_all_columns: false,
+                limit: None,
+                predicate: Some(str_to_expr_pb("@.name == \"John\"".to_string()).unwrap()),
+                sample_ratio: 1.0,
+                extra: HashMap::new(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+
+        scan.preprocess(&meta, &mut plan_meta).unwrap();
+        assert!(scan.params.unwrap().predicate.is_none());
+        assert_eq!(
+            scan.idx_predicate.unwrap(),
+            pb::IndexPredicate {
+                or_predicates: vec![pb::index_predicate::AndPredicate {
+                    predicates: vec![pb::index_predicate::Triplet {
+                        key: Some(common_pb::Property {
+                            item: Some(common_pb::property::Item::Key("name".into())),
+                        }),
+                        value: Some("John".to_string().into()),
+                        cmp: None,
+                        param: None,
+                    }]
+                }]
+            }
+        );
+    }
+
+    // e.g., g.V(id1, id2).hasLabel("person")
+    #[test]
+    fn scan_idx_pred_global_ids() {
+        let id_encoding = IdEncoding::default();
+        let person_id = id_encoding.encode(0, 1).unwrap();
+        let software_id = id_encoding.encode(1, 1).unwrap();
+        assert_eq!(person_id, 1);
+        assert_eq!(software_id, 1 << 56 | 1);
+        assert_eq!(id_encoding.decode(software_id), (1, 1));
+        assert!(id_encoding.encode(256, 1).is_none());
+        assert!(id_encoding.encode(0, 1 << 56).is_none());
+        assert_eq!(id_encoding.decode(id_encoding.encode(255, 7).unwrap()), (255, 7));
+
+        let meta = StoreMeta {
+            schema: Some(
+                Schema::new(vec![("person".to_string(), 0), ("software".to_string(), 1)], vec![], vec![])
+                    .with_id_encoding(id_encoding),
+            ),
+            version: 0,
+        };
+        let scan = |global_ids: Vec<i64>| pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: Some(global_ids.into()),
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.set_curr_node(0);
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+        assert!(scan(vec![person_id])
+            .preprocess(&meta, &mut plan_meta)
+            .is_ok());
+        match scan(vec![person_id, software_id]).preprocess(&meta, &mut plan_meta) {
+            Err(IrError::InvalidGlobalId(id)) => assert_eq!(id, software_id),
+            _ => panic!("should fail on the global id of another label"),
+        }
+    }
+
+    // e.g., g.V(id1, id2, ...).hasLabel("person")
+    #[test]
+    fn scan_idx_pred_cost_choice() {
+        let meta = StoreMeta {
+            schema: Some(
+                Schema::new(vec![("person".to_string(), 0), ("software".to_string(), 1)], vec![], vec![])
+                    .with_entity_counts(vec![(0, 100)]),
+            ),
+            version: 0,
+        };
+        let scan = |tables: Vec<common_pb::NameOrId>, global_ids: Vec<i64>| pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(tables, vec![])),
+            idx_predicate: Some(global_ids.into()),
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.set_curr_node(0);
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+
+        // a few lookups are cheaper than scanning the 100 persons
+        let mut few_lookups = scan(vec!["person".into()], vec![1, 2]);
+        few_lookups
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+        assert!(few_lookups.idx_predicate.is_some());
+        assert!(few_lookups.params.unwrap().predicate.is_none());
+        assert_eq!(
+            plan_meta.take_node_annotations(0),
+            vec!["scan: index lookup of 2 key(s) rather than a full scan of 100 row(s)".to_string()]
+        );
+
+        // many lookups turn into a full scan with the equivalent `within` predicate
+        let mut many_lookups = scan(vec!["person".into()], (1..=20).collect());
+        many_lookups
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+        assert!(many_lookups.idx_predicate.is_none());
+        assert_eq!(
+            many_lookups.params.unwrap().predicate.unwrap(),
+            str_to_expr_pb(format!("@.~id within {:?}", (1..=20).collect::<Vec<i64>>())).unwrap()
+        );
+        assert_eq!(
+            plan_meta.take_node_annotations(0),
+            vec!["scan: full scan of 100 row(s) rather than an index lookup of 20 key(s)".to_string()]
+        );
+
+        // the number of the softwares is unknown, and thus the index is used without a choice
+        let mut unknown_rows = scan(vec![], (1..=20).collect());
+        unknown_rows
+            .preprocess(&meta, &mut plan_meta)
+            .unwrap();
+        assert!(unknown_rows.idx_predicate.is_some());
+        assert!(plan_meta.take_node_annotations(0).is_empty());
+    }
+
+    #[test]
+    fn explain_scan_choice() {
+        let schema =
+            Schema::new(vec![("person".to_string(), 0), ("software".to_string(), 1)], vec![], vec![])
+                .with_entity_counts(vec![(0, 100)]);
+        let explain = |global_ids: Vec<i64>| {
+            let mut plan = LogicalPlan::default();
+            plan.meta
+                .set_store_meta(Some(std::sync::Arc::new(StoreMeta {
+                    schema: Some(schema.clone()),
+                    version: 0,
+                })));
+            let scan = pb::Scan {
+                scan_opt: 0,
+                alias: None,
+                params: Some(query_params(vec!["person".into()], vec![])),
+                idx_predicate: Some(global_ids.into()),
+                meta_data: None,
+                connector: None,
+                named_result: String::new(),
+            };
+            plan.append_operator_as_node(scan.into(), vec![])
+                .unwrap();
+            plan.explain()
+        };
+
+        // the choice of the scan is explained as an annotation of the scan
+        assert!(explain(vec![1, 2]).lines().any(|line| line
+            .ends_with("(#0) // scan: index lookup of 2 key(s) rather than a full scan of 100 row(s)")));
+        assert!(explain((1..=20).collect())
+            .lines()
+            .any(|line| line.ends_with(
+                "(#0) // scan: full scan of 100 row(s) rather than an index lookup of 20 key(s)"
+            )));
+    }
+
+    // e.g., g.V().hasLabel("person", "software").has("name", "John")
+    #[test]
+    fn scan_multi_labels_pred_to_idx_pred() {
+        let mut plan_meta = PlanMeta::default();
+        plan_meta.set_curr_node(0);
+        plan_meta.curr_node_meta_mut();
+        plan_meta.refer_to_nodes(0, vec![0]);
+        let meta = StoreMeta {
+            schema: Some(
+                Schema::from_json(std::fs::File::open("resource/modern_schema_pk.json").unwrap()).unwrap(),
+            ),
+            version: 0,
+        };
+        let mut scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec!["person".into(), "software".into()],
+                columns: vec![],
+                is_all_columns: false,
+                limit: None,
+                predicate: Some(str_to_expr_pb("@.name == \"John\"".to_string()).unwrap()),
+                sample_ratio: 1.0,
+                extra: HashMap::new(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+
+        scan.preprocess(&meta, &mut plan_meta).unwrap();
+        assert!(scan.params.unwrap().predicate.is_none());
+        assert_eq!(
+            scan.idx_predicate.unwrap(),
+            pb::IndexPredicate {
+                or_predicates: vec![pb::index_predicate::AndPredicate {
+                    predicates: vec![pb::index_predicate::Triplet {
+                        key: Some(common_pb::Property {
+                            item: Some(common_pb::property::Item::Key("name".into())),
+                        }),
+                        value: Some("John".to_string().into()),
+                        cmp: None,
+                        param: None,
+                    }]
+                }]
+            }
+        );
+    }
+
+    #[test]
+    fn column_maintain_case1() {
+        let mut plan = LogicalPlan::default();
+        // g.V().hasLabel("person").has("age", 27).valueMap("age", "name", "id")
+
+        // g.V()
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        // .hasLabel("person")
+        let select = pb::Select { predicate: str_to_expr_pb("@.~label == \"person\"".to_string()).ok() };
+        plan.append_operator_as_node(select.into(), vec![0])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        // .has("age", 27)
+        let select = pb::Select { predicate: str_to_expr_pb("@.age == 27".to_string()).ok() };
+        plan.append_operator_as_node(select.into(), vec![1])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+        // The column "age" in a predicate ".age == 27" should not be added
+        assert!(plan
+            .meta
+            .get_node_meta(0)
+            .unwrap()
+            .get_columns()
+            .is_empty());
+
+        // .valueMap("age", "name", "id")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("{@.name, @.age, @.id}".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: false,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![2])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![3]);
+        assert_eq!(
+            plan.meta
+                .get_node_meta(0)
+                .unwrap()
+                .get_columns(),
+            vec!["age".into(), "id".into(), "name".into()]
+        );
+    }
+
+    #[test]
+    fn column_maintain_case2() {
+        let mut plan = LogicalPlan::default();
+        // g.V().out().as("here").has("lang", "java").select("here").values("name")
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        // .out().as("here")
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 0,
+            alias: Some("here".into()),
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        plan.append_operator_as_node(expand.into(), vec![0])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+
+        // .has("lang", "Java")
+        let select = pb::Select { predicate: str_to_expr_pb("@.lang == \"Java\"".to_string()).ok() };
+        plan.append_operator_as_node(select.into(), vec![1])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+        // The column "lang" in a predicate should not be added
+        assert!(plan
+            .meta
+            .get_node_meta(1)
+            .unwrap()
+            .get_columns()
+            .is_empty());
+
+        // .select("here")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@here".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![2])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+
+        // .values("name")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@.name".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![3])
+            .unwrap();
+        assert_eq!(
+            plan.meta
+                .get_node_meta(1)
+                .unwrap()
+                .get_columns(),
+            vec!["name".into()]
+        );
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![4]);
+    }
+
+    #[test]
+    fn column_maintain_case3() {
+        let mut plan = LogicalPlan::default();
+        // g.V().outE().as("e").inV().as("v").select("e").order().by("weight").select("v").values("name").dedup()
+
+        // g.V()
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        // .outE().as(0)
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 1,
+            alias: Some("e".into()),
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        plan.append_operator_as_node(expand.into(), vec![0])
+            .unwrap();
+        let e_tag_id = plan.meta.get_tag_id("e").unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+        assert_eq!(plan.meta.get_tag_nodes(e_tag_id), &vec![1]);
+
+        // .inV().as("v")
+        let getv = pb::GetV {
+            tag: None,
+            opt: 1,
+            params: Some(query_params(vec![], vec![])),
+            alias: Some("v".into()),
+            meta_data: None,
+            is_optional: false,
+            end_alias: None,
+        };
+        plan.append_operator_as_node(getv.into(), vec![1])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![2]);
+        let v_tag_id = plan.meta.get_tag_id("v").unwrap();
+        assert_eq!(plan.meta.get_tag_nodes(v_tag_id), &vec![2]);
+
+        // .select("e")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@e".to_string()).ok(),
+                alias: Some("project_e".into()),
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![2])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+
+        // .order().by("weight")
+        let orderby = pb::OrderBy {
+            pairs: vec![pb::order_by::OrderingPair {
+                key: Some(common_pb::Variable {
+                    tag: None,
+                    property: Some(common_pb::Property {
+                        item: Some(common_pb::property::Item::Key("weight".into())),
+                    }),
+                    node_type: None,
+                }),
+                order: 1,
+            }],
+            limit: None,
+            per_partition: false,
+        };
+        plan.append_operator_as_node(orderby.into(), vec![3])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+        assert_eq!(
+            plan.meta
+                .get_node_meta(1)
+                .unwrap()
+                .get_columns(),
+            vec!["weight".into()]
+        );
+
+        // select("v")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@v".to_string()).ok(),
+                alias: Some("project_v".into()),
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![4])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![2]);
+
+        // .values("name")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@.name".to_string()).ok(),
+                alias: Some("name".into()),
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![5])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![6]);
+        assert_eq!(
+            plan.meta
+                .get_node_meta(2)
+                .unwrap()
+                .get_columns(),
+            vec!["name".into()]
+        );
+    }
+
+    #[test]
+    fn column_maintain_case4() {
+        let mut plan = LogicalPlan::default();
+        // g.V("person").has("name", "John").as('a').outE("knows").as('b')
+        //  .has("date", 20200101).inV().as('c').has('id', 10)
+        //  .select('a').by(valueMap('age', "name"))
+        //  .select('c').by(valueMap('id', "name"))
+
+        // g.V("person")
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut opr_id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        // .has("name", "John")
+        let select = pb::Select { predicate: str_to_expr_pb("@.name == \"John\"".to_string()).ok() };
+        opr_id = plan
+            .append_operator_as_node(select.into(), vec![opr_id as NodeId])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+        // The column "name" in a predicate should not be added
+        assert!(plan
+            .meta
+            .get_node_meta(0)
+            .unwrap()
+            .get_columns()
+            .is_empty());
+
+        // .as('a')
+        let as_opr = pb::As { alias: Some("a".into()) };
+        opr_id = plan
+            .append_operator_as_node(as_opr.into(), vec![opr_id as NodeId])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+        let a_id = plan.meta.get_tag_id("a").unwrap();
+        assert_eq!(plan.meta.get_tag_nodes(a_id), &vec![0]);
+
+        // outE("knows").as('b').has("date", 20200101)
+        let expand = pb::EdgeExpand {
+            v_tag: Some("a".into()),
+            direction: 0,
+            params: Some(query_params(vec!["knows".into()], vec![])),
+            expand_opt: 1,
+            alias: Some("b".into()),
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        opr_id = plan
+            .append_operator_as_node(expand.into(), vec![opr_id as NodeId])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId]);
+        let b_id = plan.meta.get_tag_id("b").unwrap();
+        assert_eq!(plan.meta.get_tag_nodes(b_id), &vec![opr_id as NodeId]);
+
+        //.inV().as('c')
+        let getv = pb::GetV {
+            tag: None,
+            opt: 2,
+            params: Some(query_params(vec![], vec![])),
+            alias: Some("c".into()),
+            meta_data: None,
+            is_optional: false,
+            end_alias: None,
+        };
+        opr_id = plan
+            .append_operator_as_node(getv.into(), vec![opr_id as NodeId])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId]);
+        let c_id = plan.meta.get_tag_id("c").unwrap();
+        assert_eq!(plan.meta.get_tag_nodes(c_id), &vec![opr_id as NodeId]);
+
+        // .has("id", 10)
+        let select = pb::Select { predicate: str_to_expr_pb("@.id == 10".to_string()).ok() };
+        opr_id = plan
+            .append_operator_as_node(select.into(), vec![opr_id as NodeId])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId - 1]);
+        // The column "id" in a predicate should not be added
+        assert!(plan
+            .meta
+            .get_node_meta(opr_id as NodeId - 1)
+            .unwrap()
+            .get_columns()
+            .is_empty());
+
+        // .select('a').by(valueMap('age', "name"))
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("{@a.age, @a.name}".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        opr_id = plan
+            .append_operator_as_node(project.into(), vec![opr_id as NodeId])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId]);
+        assert_eq!(
+            plan.meta
+                .get_node_meta(plan.meta.get_tag_nodes(a_id)[0])
+                .unwrap()
+                .get_columns(),
+            vec!["age".into(), "name".into()]
+        );
+
+        // .select('c').by(valueMap('age', "name"))
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("{@c.age, @c.name}".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        opr_id = plan
+            .append_operator_as_node(project.into(), vec![opr_id as NodeId])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId]);
+        assert_eq!(
+            plan.meta
+                .get_node_meta(plan.meta.get_tag_nodes(c_id)[0])
+                .unwrap()
+                .get_columns(),
+            vec!["age".into(), "name".into()]
+        );
+    }
+
+    #[test]
+    fn column_maintain_case5() {
+        // Test the maintenance of all columns
+        let mut plan = LogicalPlan::default();
+        // g.V("person").valueMap(ALL)
+
+        // g.V("person")
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec!["person".into()],
+                columns: vec!["a".into()],
+                is_all_columns: true,
+                limit: None,
+                predicate: None,
+                sample_ratio: 1.0,
+                extra: Default::default(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert!(plan
+            .meta
+            .get_curr_node_meta()
+            .unwrap()
+            .is_all_columns());
+
+        let mut plan = LogicalPlan::default();
+        // g.V("person").valueMap(ALL)
+
+        // g.V("person")
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec!["person".into()],
+                columns: vec!["a".into()],
+                is_all_columns: false,
+                limit: None,
+                predicate: None,
+                sample_ratio: 1.0,
+                extra: Default::default(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+
+        let opr_id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@.~all".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+
+        plan.append_operator_as_node(project.into(), vec![opr_id])
+            .unwrap();
+        assert!(plan
+            .meta
+            .get_node_meta(0)
+            .unwrap()
+            .is_all_columns());
+    }
+
+    #[test]
+    fn column_maintain_case6() {
+        // Test the maintenance of columns after **rename tags**
+        let mut plan = LogicalPlan::default();
+        // g.V().out().as("a").in().select("a").as("b").select("b").by("name")
+
+        // g.V()
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        // .out().as("a")
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 0,
+            alias: Some("a".into()),
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        plan.append_operator_as_node(expand.into(), vec![0])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+
+        // .in()
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 1,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        plan.append_operator_as_node(expand.into(), vec![1])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![2]);
+
+        // .select("a").as("b")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@a".to_string()).ok(),
+                alias: Some("b".into()),
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![2])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+
+        // select("b").by("name")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@b.name".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![3])
+            .unwrap();
+        assert_eq!(
+            plan.meta
+                .get_node_meta(1)
+                .unwrap()
+                .get_columns(),
+            vec!["name".into()]
+        );
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![4]);
+    }
+
+    #[test]
+    fn column_maintain_semi_apply() {
+        let mut plan = LogicalPlan::default();
+        // g.V().where(out()).valueMap("age")
+
+        // g.V()
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let oprid = plan
+            .append_operator_as_node(expand.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+
+        // .where(out())
+        let apply = pb::Apply {
+            join_kind: 4, // semi join
+            tags: vec![],
+            subtask: oprid as PbNodeId,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
+        let oprid = plan
+            .append_operator_as_node(apply.into(), vec![0])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        // .valueMap("age")
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("{@.age}".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: false,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![oprid])
+            .unwrap();
+        assert_eq!(
+            plan.meta
+                .get_node_meta(0)
+                .unwrap()
+                .get_columns(),
+            vec!["age".into()]
+        );
+    }
+
+    #[test]
+    fn column_maintain_groupby_case1() {
+        // groupBy contains tagging a keys that is further a vertex
+        let mut plan = LogicalPlan::default();
+        // g.V().groupCount().order().by(select(keys).by('name'))
+
+        // g.V()
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        let group = pb::GroupBy {
+            mappings: vec![pb::group_by::KeyAlias {
+                key: Some(common_pb::Variable { tag: None, property: None, node_type: None }),
+                alias: Some("~keys_2_0".into()),
+            }],
+            functions: vec![pb::group_by::AggFunc {
+                vars: vec![],
+                aggregate: 3,
+                alias: Some("~values_2_0".into()),
+                equality: 0,
+                overflow: 0,
+                approximation: None,
+            }],
+            meta_data: vec![],
+            null_key_opt: 0,
+        };
+        plan.append_operator_as_node(group.into(), vec![0])
+            .unwrap();
+        let keys_tag_id = plan.meta.get_tag_id("~keys_2_0").unwrap();
+        assert_eq!(plan.meta.get_tag_nodes(keys_tag_id), &vec![0]);
+
+        let order = pb::OrderBy {
+            pairs: vec![pb::order_by::OrderingPair {
+                key: Some(common_pb::Variable {
+                    tag: Some("~keys_2_0".into()),
+                    property: Some(common_pb::Property {
+                        item: Some(common_pb::property::Item::Key("name".into())),
+                    }),
+                    node_type: None,
+                }),
+                order: 0,
+            }],
+            limit: None,
+            per_partition: false,
+        };
+        plan.append_operator_as_node(order.into(), vec![1])
+            .unwrap();
+        assert!(plan
+            .meta
+            .get_node_meta(0)
+            .unwrap()
+            .get_columns()
+            .contains(&"name".into()));
+    }
+
+    #[test]
+    fn column_maintain_groupby_case2() {
+        // groupBy contains tagging a key that is further a vertex
+        let mut plan = LogicalPlan::default();
+        // g.V().groupCount().select(values)
+
+        // g.V()
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        let group = pb::GroupBy {
+            mappings: vec![pb::group_by::KeyAlias {
+                key: Some(common_pb::Variable { tag: None, property: None, node_type: None }),
+                alias: Some("~keys_2_0".into()),
+            }],
+            functions: vec![pb::group_by::AggFunc {
+                vars: vec![],
+                aggregate: 3,
+                alias: Some("~values_2_0".into()),
+                equality: 0,
+                overflow: 0,
+                approximation: None,
+            }],
+            meta_data: vec![],
+            null_key_opt: 0,
+        };
+        plan.append_operator_as_node(group.into(), vec![0])
+            .unwrap();
+        let keys_tag_id = plan.meta.get_tag_id("~keys_2_0").unwrap();
+
+        assert_eq!(plan.meta.get_tag_nodes(keys_tag_id), &vec![0]);
+
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@~values_2_0".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![1])
+            .unwrap();
+    }
+
+    #[test]
+    fn column_maintain_groupby_case3() {
+        // groupBy contains tagging a keys
+        let mut plan = LogicalPlan::default();
+        // g.V().group().by(values('name').as('a')).select('a')
+        // g.V()
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        let group = pb::GroupBy {
+            mappings: vec![pb::group_by::KeyAlias {
+                key: Some(common_pb::Variable {
+                    tag: None,
+                    property: Some(common_pb::Property {
+                        item: Some(common_pb::property::Item::Key("name".into())),
+                    }),
+                    node_type: None,
+                }),
+                alias: Some("a".into()),
+            }],
+            functions: vec![pb::group_by::AggFunc {
+                vars: vec![],
+                aggregate: 5,
+                alias: Some("~values_0_1".into()),
+                equality: 0,
+                overflow: 0,
+                approximation: None,
+            }],
+            meta_data: vec![],
+            null_key_opt: 0,
+        };
+        plan.append_operator_as_node(group.into(), vec![0])
+            .unwrap();
+        let keys_tag_id = plan.meta.get_tag_id("a").unwrap();
+        assert_eq!(plan.meta.get_tag_nodes(keys_tag_id), &vec![1]);
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+        assert_eq!(
+            plan.meta
+                .get_nodes_meta(&[0])
+                .unwrap()
+                .get_columns(),
+            vec!["name".into()]
+        );
+
+        let project = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@a".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![1])
+            .unwrap();
+    }
+
+    #[test]
+    fn column_maintain_groupby_case4() {
+        let mut plan = LogicalPlan::default();
+        // g.V().group().by(outE().count()).by('name')
+        // g.V()
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+
+        // by(outE().count())
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 1,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let subtask = plan
+            .append_operator_as_node(expand.into(), vec![])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![subtask]);
+
+        let group = pb::GroupBy {
+            mappings: vec![],
+            functions: vec![pb::group_by::AggFunc {
+                vars: vec![],
+                aggregate: 3,
+                alias: Some("~values_0_1".into()),
+                equality: 0,
+                overflow: 0,
+                approximation: None,
+            }],
+            meta_data: vec![],
+            null_key_opt: 0,
+        };
+        plan.append_operator_as_node(group.into(), vec![subtask])
+            .unwrap();
+        // the tag "~values_0_1" maps to id 0
+        assert_eq!(plan.meta.get_tag_nodes(0), &vec![2]); // tag self
 
-        plan_meta.curr_node_meta_mut();
-        plan_meta
-            .tag_nodes_meta_mut(Some(a_id))
+        let apply = pb::Apply {
+            join_kind: 0, // join
+            tags: vec![],
+            subtask: subtask as PbNodeId,
+            alias: Some("~apply".into()),
+            timeout_ms: 0,
+            captures: vec![],
+        };
+        plan.append_operator_as_node(apply.into(), vec![0])
             .unwrap();
-        plan_meta.refer_to_nodes(0, vec![0]);
+        // do not change the head of current node, given that alias is given
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+        // the tag "~apply" maps to id 1
+        assert_eq!(plan.meta.get_tag_nodes(1), &vec![3]);
 
-        let meta = StoreMeta {
-            schema: Some(Schema::new(
-                vec![("person".to_string(), 0), ("software".to_string(), 1)],
-                vec![("knows".to_string(), 0), ("creates".to_string(), 1)],
-                vec![("id".to_string(), 0), ("name".to_string(), 1), ("age".to_string(), 2)],
-            )),
+        let group = pb::GroupBy {
+            mappings: vec![pb::group_by::KeyAlias {
+                key: Some(common_pb::Variable {
+                    tag: Some("~apply".into()),
+                    property: None,
+                    node_type: None,
+                }),
+                alias: Some("~apply".into()),
+            }],
+            functions: vec![pb::group_by::AggFunc {
+                vars: vec![common_pb::Variable {
+                    tag: None,
+                    property: Some(common_pb::Property {
+                        item: Some(common_pb::property::Item::Key("name".into())),
+                    }),
+                    node_type: None,
+                }],
+                aggregate: 5,
+                alias: Some("~values_0_1".into()),
+                equality: 0,
+                overflow: 0,
+                approximation: None,
+            }],
+            meta_data: vec![],
+            null_key_opt: 0,
         };
+        plan.append_operator_as_node(group.into(), vec![3])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+        assert_eq!(
+            plan.meta
+                .get_node_meta(0)
+                .unwrap()
+                .get_columns(),
+            vec!["name".into()]
+        )
+    }
 
-        let mut scan = pb::Scan {
+    #[test]
+    fn groupby_agg_equality() {
+        let scan = pb::Scan {
             scan_opt: 0,
             alias: None,
-            params: Some(pb::QueryParams {
-                tables: vec!["person".into()],
-                columns: vec!["age".into(), "name".into()],
-                is_all_columns: false,
-                limit: None,
-                predicate: Some(
-                    str_to_expr_pb("@a.~label > \"person\" && @a.age == 10".to_string()).unwrap(),
-                ),
-                sample_ratio: 1.0,
-                extra: HashMap::new(),
-            }),
-            idx_predicate: Some(vec!["software".to_string()].into()),
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        scan.preprocess(&meta, &mut plan_meta).unwrap();
-        assert_eq!(scan.clone().params.unwrap().tables[0], 0.into());
-        assert_eq!(
-            scan.idx_predicate.unwrap().or_predicates[0].predicates[0]
-                .value
-                .clone()
-                .unwrap(),
-            1.into()
-        );
-        let operators = scan
-            .params
-            .clone()
-            .unwrap()
-            .predicate
+        let group = |aggregate: i32, property: Option<common_pb::Property>, equality: i32| pb::GroupBy {
+            mappings: vec![],
+            functions: vec![pb::group_by::AggFunc {
+                vars: vec![common_pb::Variable { tag: None, property, node_type: None }],
+                aggregate,
+                alias: Some("a".into()),
+                equality,
+                overflow: 0,
+                approximation: None,
+            }],
+            meta_data: vec![],
+            null_key_opt: 0,
+        };
+        let name =
+            || Some(common_pb::Property { item: Some(common_pb::property::Item::Key("name".into())) });
+
+        // g.V().aggregate(toSet) by value requires all the properties of the vertices
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.clone().into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(group(6, None, 2).into(), vec![0])
+            .unwrap();
+        assert!(plan
+            .meta
+            .get_node_meta(0)
             .unwrap()
-            .operators;
-        match operators.get(2).unwrap().item.as_ref().unwrap() {
-            common_pb::expr_opr::Item::Const(val) => assert_eq!(val.clone(), 0.into()),
-            _ => panic!(),
-        }
-        match operators.get(4).unwrap().item.as_ref().unwrap() {
-            common_pb::expr_opr::Item::Var(var) => {
-                match var
-                    .property
-                    .as_ref()
-                    .unwrap()
-                    .item
-                    .clone()
-                    .unwrap()
-                {
-                    Item::Key(key) => assert_eq!(key, 2.into()),
-                    _ => panic!(),
-                }
-            }
-            _ => panic!(),
-        }
-        // Assert whether the columns have been updated in PlanMeta
-        assert_eq!(
-            plan_meta
-                .get_node_meta(0)
-                .unwrap()
-                .get_columns(),
-            vec![1.into(), 2.into()]
-        );
+            .is_all_columns());
 
-        // The column "age" of a predicate "a.age == 10" should not be added
-        assert!(plan_meta
-            .get_node_meta(1)
+        // count distinct by id is fine with the vertices
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.clone().into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(group(4, None, 1).into(), vec![0])
+            .unwrap();
+        assert!(!plan
+            .meta
+            .get_node_meta(0)
             .unwrap()
-            .get_columns()
-            .is_empty());
+            .is_all_columns());
+
+        // the property values cannot be deduplicated by id
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.clone().into(), vec![])
+            .unwrap();
+        let result = plan.append_operator_as_node(group(6, name(), 1).into(), vec![0]);
+        assert!(matches!(result, Err(IrError::Unsupported(_))));
+
+        // the equality does not apply to other aggregate functions
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let result = plan.append_operator_as_node(group(5, name(), 2).into(), vec![0]);
+        assert!(matches!(result, Err(IrError::Unsupported(_))));
     }
 
     #[test]
-    fn scan_pred_to_idx_pred() {
-        let mut plan_meta = PlanMeta::default();
-        plan_meta.set_curr_node(0);
-        plan_meta.curr_node_meta_mut();
-        plan_meta.refer_to_nodes(0, vec![0]);
-        let meta = StoreMeta {
-            schema: Some(
-                Schema::from_json(std::fs::File::open("resource/modern_schema_pk.json").unwrap()).unwrap(),
-            ),
-        };
-        let mut scan = pb::Scan {
+    fn groupby_agg_alias_types() {
+        // g.V().group().by(count().as('cnt'), avg('age').as('avg'), sum('age').as('total'))
+        let scan = pb::Scan {
             scan_opt: 0,
             alias: None,
-            params: Some(pb::QueryParams {
-                tables: vec!["person".into()],
-                columns: vec![],
-                is_all_columns: false,
-                limit: None,
-                predicate: Some(str_to_expr_pb("@.name == \"John\"".to_string()).unwrap()),
-                sample_ratio: 1.0,
-                extra: HashMap::new(),
-            }),
+            params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
+        let agg_fn =
+            |aggregate: i32, var: Option<common_pb::Variable>, alias: &str| pb::group_by::AggFunc {
+                vars: var.into_iter().collect(),
+                aggregate,
+                alias: Some(alias.into()),
+                equality: 0,
+                overflow: 0,
+                approximation: None,
+            };
+        let age = || {
+            Some(common_pb::Variable {
+                tag: None,
+                property: Some(common_pb::Property {
+                    item: Some(common_pb::property::Item::Key("age".into())),
+                }),
+                node_type: None,
+            })
+        };
+        let group = pb::GroupBy {
+            mappings: vec![],
+            functions: vec![agg_fn(3, None, "cnt"), agg_fn(7, age(), "avg"), agg_fn(0, age(), "total")],
+            meta_data: vec![],
+            null_key_opt: 0,
+        };
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(group.into(), vec![0])
+            .unwrap();
+        let tag_type = |plan: &LogicalPlan, tag: &str| {
+            plan.meta
+                .get_tag_id(tag)
+                .and_then(|tag_id| plan.meta.get_tag_type(tag_id))
+        };
+        assert_eq!(tag_type(&plan, "cnt"), Some(common_pb::DataType::Int64));
+        assert_eq!(tag_type(&plan, "avg"), Some(common_pb::DataType::Double));
+        // the type of the property is unknown
+        assert_eq!(tag_type(&plan, "total"), None);
 
-        scan.preprocess(&meta, &mut plan_meta).unwrap();
-        assert!(scan.params.unwrap().predicate.is_none());
-        assert_eq!(
-            scan.idx_predicate.unwrap(),
-            pb::IndexPredicate {
-                or_predicates: vec![pb::index_predicate::AndPredicate {
-                    predicates: vec![pb::index_predicate::Triplet {
-                        key: Some(common_pb::Property {
-                            item: Some(common_pb::property::Item::Key("name".into())),
-                        }),
-                        value: Some("John".to_string().into()),
-                        cmp: None,
-                    }]
-                }]
-            }
-        );
+        // where(@cnt > 10)
+        let select = pb::Select { predicate: str_to_expr_pb("@cnt > 10".to_string()).ok() };
+        plan.append_operator_as_node(select.into(), vec![1])
+            .unwrap();
+
+        // project(@avg * @cnt as 'x', @cnt + 1 as 'y', @cnt > 10 as 'z', @total / @cnt as 'w')
+        let project = pb::Project {
+            mappings: vec![
+                pb::project::ExprAlias {
+                    expr: str_to_expr_pb("@avg * @cnt".to_string()).ok(),
+                    alias: Some("x".into()),
+                },
+                pb::project::ExprAlias {
+                    expr: str_to_expr_pb("@cnt + 1".to_string()).ok(),
+                    alias: Some("y".into()),
+                },
+                pb::project::ExprAlias {
+                    expr: str_to_expr_pb("@cnt > 10".to_string()).ok(),
+                    alias: Some("z".into()),
+                },
+                pb::project::ExprAlias {
+                    expr: str_to_expr_pb("@total / @cnt".to_string()).ok(),
+                    alias: Some("w".into()),
+                },
+            ],
+            is_append: true,
+            meta_data: vec![],
+        };
+        plan.append_operator_as_node(project.into(), vec![2])
+            .unwrap();
+        assert_eq!(tag_type(&plan, "x"), Some(common_pb::DataType::Double));
+        assert_eq!(tag_type(&plan, "y"), Some(common_pb::DataType::Int64));
+        assert_eq!(tag_type(&plan, "z"), Some(common_pb::DataType::Boolean));
+        assert_eq!(tag_type(&plan, "w"), None);
+
+        // the aggregate values have no properties
+        let select = pb::Select { predicate: str_to_expr_pb("@cnt.name == \"John\"".to_string()).ok() };
+        let result = plan.append_operator_as_node(select.into(), vec![3]);
+        assert!(matches!(result, Err(IrError::Unsupported(_))));
     }
 
-    // e.g., g.V().hasLabel("person", "software").has("name", "John")
     #[test]
-    fn scan_multi_labels_pred_to_idx_pred() {
-        let mut plan_meta = PlanMeta::default();
-        plan_meta.set_curr_node(0);
-        plan_meta.curr_node_meta_mut();
-        plan_meta.refer_to_nodes(0, vec![0]);
-        let meta = StoreMeta {
-            schema: Some(
-                Schema::from_json(std::fs::File::open("resource/modern_schema_pk.json").unwrap()).unwrap(),
-            ),
-        };
-        let mut scan = pb::Scan {
+    fn groupby_agg_overflow() {
+        use pb::group_by::agg_func::Overflow;
+
+        let scan = pb::Scan {
             scan_opt: 0,
             alias: None,
-            params: Some(pb::QueryParams {
-                tables: vec!["person".into(), "software".into()],
-                columns: vec![],
-                is_all_columns: false,
-                limit: None,
-                predicate: Some(str_to_expr_pb("@.name == \"John\"".to_string()).unwrap()),
-                sample_ratio: 1.0,
-                extra: HashMap::new(),
-            }),
+            params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-
-        scan.preprocess(&meta, &mut plan_meta).unwrap();
-        assert!(scan.params.unwrap().predicate.is_none());
-        assert_eq!(
-            scan.idx_predicate.unwrap(),
-            pb::IndexPredicate {
-                or_predicates: vec![pb::index_predicate::AndPredicate {
-                    predicates: vec![pb::index_predicate::Triplet {
-                        key: Some(common_pb::Property {
-                            item: Some(common_pb::property::Item::Key("name".into())),
-                        }),
-                        value: Some("John".to_string().into()),
-                        cmp: None,
-                    }]
-                }]
-            }
+        let agg_fn = |aggregate: i32, overflow: Overflow, alias: &str| pb::group_by::AggFunc {
+            vars: vec![],
+            aggregate,
+            alias: Some(alias.into()),
+            equality: 0,
+            overflow: overflow as i32,
+            approximation: None,
+        };
+        // sum, avg, count, and a sum with its own overflow
+        let group = pb::GroupBy {
+            mappings: vec![],
+            functions: vec![
+                agg_fn(0, Overflow::Wrap, "a"),
+                agg_fn(7, Overflow::Wrap, "b"),
+                agg_fn(3, Overflow::Wrap, "c"),
+                agg_fn(0, Overflow::Error, "d"),
+            ],
+            meta_data: vec![],
+            null_key_opt: 0,
+        };
+        let mut plan = LogicalPlan::default();
+        plan.meta.set_agg_overflow(Overflow::Saturate);
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(group.into(), vec![0])
+            .unwrap();
+        let overflows: Vec<i32> = match &plan.get_node(1).unwrap().borrow().opr.opr {
+            Some(Opr::GroupBy(group)) => group
+                .functions
+                .iter()
+                .map(|agg_fn| agg_fn.overflow)
+                .collect(),
+            _ => panic!("should be a group"),
+        };
+        assert_eq!(
+            overflows,
+            vec![
+                Overflow::Saturate as i32,
+                Overflow::Saturate as i32,
+                Overflow::Wrap as i32,
+                Overflow::Error as i32
+            ]
         );
     }
 
     #[test]
-    fn column_maintain_case1() {
-        let mut plan = LogicalPlan::default();
-        // g.V().hasLabel("person").has("age", 27).valueMap("age", "name", "id")
+    fn groupby_agg_approximation() {
+        use pb::group_by::agg_func::{Aggregate, Approximation, Equality};
 
-        // g.V()
         let scan = pb::Scan {
             scan_opt: 0,
             alias: None,
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
+        let mut plan = LogicalPlan::default();
         plan.append_operator_as_node(scan.into(), vec![])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-
-        // .hasLabel("person")
-        let select = pb::Select { predicate: str_to_expr_pb("@.~label == \"person\"".to_string()).ok() };
-        plan.append_operator_as_node(select.into(), vec![0])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-
-        // .has("age", 27)
-        let select = pb::Select { predicate: str_to_expr_pb("@.age == 27".to_string()).ok() };
-        plan.append_operator_as_node(select.into(), vec![1])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-        // The column "age" in a predicate ".age == 27" should not be added
-        assert!(plan
-            .meta
-            .get_node_meta(0)
-            .unwrap()
-            .get_columns()
-            .is_empty());
-
-        // .valueMap("age", "name", "id")
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("{@.name, @.age, @.id}".to_string()).ok(),
-                alias: None,
+        let group = |aggregate: Aggregate, equality: Equality, precision: i32| pb::GroupBy {
+            mappings: vec![],
+            functions: vec![pb::group_by::AggFunc {
+                vars: vec![],
+                aggregate: aggregate as i32,
+                alias: Some("a".into()),
+                equality: equality as i32,
+                overflow: 0,
+                approximation: Some(Approximation { precision }),
             }],
-            is_append: false,
             meta_data: vec![],
+            null_key_opt: 0,
         };
-        plan.append_operator_as_node(project.into(), vec![2])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![3]);
-        assert_eq!(
-            plan.meta
-                .get_node_meta(0)
-                .unwrap()
-                .get_columns(),
-            vec!["age".into(), "id".into(), "name".into()]
+
+        let mut plan1 = plan.clone();
+        assert!(plan1
+            .append_operator_as_node(group(Aggregate::CountDistinct, Equality::ById, 14).into(), vec![0])
+            .is_ok());
+        // only the distinct count can be approximated
+        let mut plan1 = plan.clone();
+        let result =
+            plan1.append_operator_as_node(group(Aggregate::Sum, Equality::Default, 14).into(), vec![0]);
+        assert!(matches!(result, Err(IrError::Unsupported(_))));
+        // the sketch hashes the values by ids
+        let mut plan1 = plan.clone();
+        let result = plan1.append_operator_as_node(
+            group(Aggregate::CountDistinct, Equality::ByValue, 14).into(),
+            vec![0],
+        );
+        assert!(matches!(result, Err(IrError::Unsupported(_))));
+        // the precision is out of [4, 18]
+        let mut plan1 = plan.clone();
+        let result = plan1.append_operator_as_node(
+            group(Aggregate::CountDistinct, Equality::Default, 20).into(),
+            vec![0],
         );
+        assert!(matches!(result, Err(IrError::Unsupported(_))));
     }
 
     #[test]
-    fn column_maintain_case2() {
+    fn column_maintain_orderby() {
         let mut plan = LogicalPlan::default();
-        // g.V().out().as("here").has("lang", "java").select("here").values("name")
+        // g.E(xx).values("workFrom").as("a").order().by(select("a"))
+
         let scan = pb::Scan {
-            scan_opt: 0,
+            scan_opt: 1,
             alias: None,
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
         plan.append_operator_as_node(scan.into(), vec![])
             .unwrap();
         assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
 
-        // .out().as("here")
-        let expand = pb::EdgeExpand {
-            v_tag: None,
-            direction: 0,
-            params: Some(query_params(vec![], vec![])),
-            expand_opt: 0,
-            alias: Some("here".into()),
-            meta_data: None,
-        };
-        plan.append_operator_as_node(expand.into(), vec![0])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
-
-        // .has("lang", "Java")
-        let select = pb::Select { predicate: str_to_expr_pb("@.lang == \"Java\"".to_string()).ok() };
-        plan.append_operator_as_node(select.into(), vec![1])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
-        // The column "lang" in a predicate should not be added
-        assert!(plan
-            .meta
-            .get_node_meta(1)
-            .unwrap()
-            .get_columns()
-            .is_empty());
-
-        // .select("here")
         let project = pb::Project {
             mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@here".to_string()).ok(),
-                alias: None,
+                expr: str_to_expr_pb("@.workFrom".to_string()).ok(),
+                alias: Some("a".into()),
             }],
             is_append: true,
             meta_data: vec![],
         };
-        plan.append_operator_as_node(project.into(), vec![2])
+        plan.append_operator_as_node(project.into(), vec![0])
             .unwrap();
         assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+        assert!(plan
+            .meta
+            .get_node_meta(0)
+            .unwrap()
+            .get_columns()
+            .contains(&"workFrom".into()));
 
-        // .values("name")
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@.name".to_string()).ok(),
-                alias: None,
+        let order = pb::OrderBy {
+            pairs: vec![pb::order_by::OrderingPair {
+                key: Some(common_pb::Variable { tag: Some("a".into()), property: None, node_type: None }),
+                order: 0,
             }],
-            is_append: true,
-            meta_data: vec![],
+            limit: None,
+            per_partition: false,
         };
-        plan.append_operator_as_node(project.into(), vec![3])
+        plan.append_operator_as_node(order.into(), vec![1])
             .unwrap();
-        assert_eq!(
-            plan.meta
-                .get_node_meta(1)
-                .unwrap()
-                .get_columns(),
-            vec!["name".into()]
-        );
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![4]);
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
     }
 
     #[test]
-    fn column_maintain_case3() {
+    fn column_maintain_union() {
         let mut plan = LogicalPlan::default();
-        // g.V().outE().as("e").inV().as("v").select("e").order().by("weight").select("v").values("name").dedup()
-
+        // g.V().union(out().has("age", Gt(10)), out().out()).as('a').select('a').by(valueMap('name', 'age'))
         // g.V()
         let scan = pb::Scan {
             scan_opt: 0,
@@ -2118,836 +10364,1961 @@ mod test {
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
         plan.append_operator_as_node(scan.into(), vec![])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
 
-        // .outE().as(0)
-        let expand = pb::EdgeExpand {
+        let expand1 = pb::EdgeExpand {
             v_tag: None,
             direction: 0,
             params: Some(query_params(vec![], vec![])),
-            expand_opt: 1,
-            alias: Some("e".into()),
+            expand_opt: 0,
+            alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
-        plan.append_operator_as_node(expand.into(), vec![0])
+        let filter = pb::Select { predicate: Some(str_to_expr_pb("@.age > 10".to_string()).unwrap()) };
+
+        let expand2 = expand1.clone();
+        let expand3 = expand1.clone();
+        let id1 = plan
+            .append_operator_as_node(expand1.into(), vec![0])
+            .unwrap();
+        let id1_f = plan
+            .append_operator_as_node(filter.into(), vec![id1])
             .unwrap();
-        let e_tag_id = plan.meta.get_tag_id("e").unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
-        assert_eq!(plan.meta.get_tag_nodes(e_tag_id), &vec![1]);
 
-        // .inV().as("v")
-        let getv = pb::GetV {
-            tag: None,
-            opt: 1,
-            params: Some(query_params(vec![], vec![])),
-            alias: Some("v".into()),
-            meta_data: None,
-        };
-        plan.append_operator_as_node(getv.into(), vec![1])
+        let opr_id = plan
+            .append_operator_as_node(expand2.into(), vec![0])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![2]);
-        let v_tag_id = plan.meta.get_tag_id("v").unwrap();
-        assert_eq!(plan.meta.get_tag_nodes(v_tag_id), &vec![2]);
+        let id2 = plan
+            .append_operator_as_node(expand3.into(), vec![opr_id])
+            .unwrap();
+        let union = pb::Union { parents: vec![id1_f as PbNodeId, id2 as PbNodeId], alignment: 0 };
+        plan.append_operator_as_node(union.into(), vec![id1_f, id2])
+            .unwrap();
+        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![id1, id2]);
+
+        let as_opr = pb::As { alias: Some("a".into()) };
+        let opr_id = plan
+            .append_operator_as_node(as_opr.into(), vec![id1, id2])
+            .unwrap();
+        let a_id = plan.meta.get_tag_id("a").unwrap();
+        assert_eq!(plan.meta.get_tag_nodes(a_id), &vec![id1, id2]);
 
-        // .select("e")
         let project = pb::Project {
             mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@e".to_string()).ok(),
-                alias: Some("project_e".into()),
+                expr: str_to_expr_pb("{@a.name, @a.age}".to_string()).ok(),
+                alias: None,
             }],
-            is_append: true,
+            is_append: false,
             meta_data: vec![],
         };
-        plan.append_operator_as_node(project.into(), vec![2])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
-
-        // .order().by("weight")
-        let orderby = pb::OrderBy {
-            pairs: vec![pb::order_by::OrderingPair {
-                key: Some(common_pb::Variable {
-                    tag: None,
-                    property: Some(common_pb::Property {
-                        item: Some(common_pb::property::Item::Key("weight".into())),
-                    }),
-                    node_type: None,
-                }),
-                order: 1,
-            }],
-            limit: None,
-        };
-        plan.append_operator_as_node(orderby.into(), vec![3])
+        plan.append_operator_as_node(project.into(), vec![opr_id])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
         assert_eq!(
             plan.meta
-                .get_node_meta(1)
+                .get_node_meta(id1)
                 .unwrap()
                 .get_columns(),
-            vec!["weight".into()]
+            vec!["age".into(), "name".into()]
+        );
+        assert_eq!(
+            plan.meta
+                .get_node_meta(id2)
+                .unwrap()
+                .get_columns(),
+            vec!["age".into(), "name".into()]
         );
+    }
 
-        // select("v")
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@v".to_string()).ok(),
-                alias: Some("project_v".into()),
-            }],
-            is_append: true,
-            meta_data: vec![],
+    #[test]
+    fn union_tags_alignment() {
+        // g.V().as('a').union(out().as('b'), out()), where only the first branch produces the tag 'b'
+        let build_branches = || {
+            let mut plan = LogicalPlan::default();
+            let scan = pb::Scan {
+                scan_opt: 0,
+                alias: Some("a".into()),
+                params: Some(query_params(vec![], vec![])),
+                idx_predicate: None,
+                meta_data: None,
+                connector: None,
+                named_result: String::new(),
+            };
+            plan.append_operator_as_node(scan.into(), vec![])
+                .unwrap();
+            let expand = pb::EdgeExpand {
+                v_tag: None,
+                direction: 0,
+                params: Some(query_params(vec![], vec![])),
+                expand_opt: 0,
+                alias: None,
+                meta_data: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges: false,
+                vertex_tables: vec![],
+                is_optional: false,
+            };
+            let mut expand_b = expand.clone();
+            expand_b.alias = Some("b".into());
+            let id1 = plan
+                .append_operator_as_node(expand_b.into(), vec![0])
+                .unwrap();
+            let id2 = plan
+                .append_operator_as_node(expand.into(), vec![0])
+                .unwrap();
+            (plan, id1, id2)
+        };
+
+        let (mut plan, id1, id2) = build_branches();
+        let a_id = plan.meta.get_tag_id("a").unwrap();
+        let b_id = plan.meta.get_tag_id("b").unwrap();
+        assert_eq!(plan.meta.get_node_tags(id1), vec![a_id, b_id].into_iter().collect());
+        assert_eq!(plan.meta.get_node_tags(id2), vec![a_id].into_iter().collect());
+        let union = pb::Union { parents: vec![id1 as PbNodeId, id2 as PbNodeId], alignment: 1 };
+        assert!(matches!(
+            plan.append_operator_as_node(union.into(), vec![id1, id2]),
+            Err(IrError::UnalignedUnion(_))
+        ));
+
+        let (mut plan, id1, id2) = build_branches();
+        let union = pb::Union { parents: vec![id1 as PbNodeId, id2 as PbNodeId], alignment: 2 };
+        let union_id = plan
+            .append_operator_as_node(union.into(), vec![id1, id2])
+            .unwrap();
+        let union_node = plan.get_node(union_id).unwrap();
+        let parents: Vec<NodeId> = union_node
+            .borrow()
+            .parents
+            .iter()
+            .cloned()
+            .collect();
+        assert_eq!(parents.len(), 2);
+        for parent in &parents {
+            let parent_node = plan.get_node(*parent).unwrap();
+            match &parent_node.borrow().opr.opr {
+                Some(pb::logical_plan::operator::Opr::Project(project)) => {
+                    // the tags 'a', 'b', and the hidden tag of the head
+                    assert_eq!(project.mappings.len(), 3);
+                    assert!(!project.is_append);
+                }
+                _ => panic!("the branch is not aligned by a `Project`"),
+            }
+        }
+        assert_eq!(plan.meta.get_node_tags(parents[0]), plan.meta.get_node_tags(parents[1]));
+        assert!(plan
+            .meta
+            .get_node_tags(union_id)
+            .contains(&b_id));
+    }
+
+    #[test]
+    fn inline_union_scans() {
+        let scan = |label: &str, id: Option<i64>| pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec![label.into()], vec![])),
+            idx_predicate: id.map(|id| pb::IndexPredicate {
+                or_predicates: vec![pb::index_predicate::AndPredicate {
+                    predicates: vec![pb::index_predicate::Triplet {
+                        key: Some(common_pb::Property { item: Some(Item::Id(common_pb::IdKey {})) }),
+                        value: Some(id.into()),
+                        cmp: None,
+                        param: None,
+                    }],
+                }],
+            }),
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let union = |plan: &mut LogicalPlan, scans: Vec<pb::Scan>| {
+            let ids: Vec<NodeId> = scans
+                .into_iter()
+                .map(|scan| {
+                    plan.append_operator_as_node(scan.into(), vec![])
+                        .unwrap()
+                })
+                .collect();
+            let union = pb::Union { parents: ids.iter().map(|id| *id as PbNodeId).collect(), alignment: 0 };
+            plan.append_operator_as_node(union.into(), ids)
+                .unwrap()
+        };
+        let get_scan = |plan: &LogicalPlan, id: NodeId| match plan.get_opr(id).unwrap().opr {
+            Some(Opr::Scan(scan)) => scan,
+            _ => panic!("the node {} is not a `Scan`", id),
         };
-        plan.append_operator_as_node(project.into(), vec![4])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![2]);
 
-        // .values("name")
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@.name".to_string()).ok(),
-                alias: Some("name".into()),
-            }],
-            is_append: true,
-            meta_data: vec![],
+        let new_plan = || {
+            let mut plan = LogicalPlan::default();
+            plan.meta.set_inline_union_scans(true);
+            plan
         };
-        plan.append_operator_as_node(project.into(), vec![5])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![6]);
+
+        // not inlined by default
+        let mut plan = LogicalPlan::default();
+        let id = union(&mut plan, vec![scan("person", None), scan("software", None)]);
+        assert_eq!(plan.len(), 3);
+        assert!(matches!(plan.get_opr(id).unwrap().opr, Some(Opr::Union(_))));
+
+        // differ by the labels
+        let mut plan = new_plan();
+        let id = union(&mut plan, vec![scan("person", None), scan("software", None)]);
+        assert_eq!(id, 2);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(get_scan(&plan, id).params.unwrap().tables, vec!["person".into(), "software".into()]);
         assert_eq!(
-            plan.meta
-                .get_node_meta(2)
+            plan.get_node(id).unwrap().borrow().annotations,
+            vec!["union: inline the scans [0, 1] as one scan".to_string()]
+        );
+
+        // differ by the constants
+        let mut plan = new_plan();
+        let id = union(&mut plan, vec![scan("person", Some(1)), scan("person", Some(2))]);
+        assert_eq!(plan.len(), 1);
+        let scan_pb = get_scan(&plan, id);
+        assert_eq!(scan_pb.params.unwrap().tables, vec!["person".into()]);
+        assert_eq!(
+            scan_pb
+                .idx_predicate
                 .unwrap()
-                .get_columns(),
-            vec!["name".into()]
+                .or_predicates
+                .len(),
+            2
         );
-    }
 
-    #[test]
-    fn column_maintain_case4() {
-        let mut plan = LogicalPlan::default();
-        // g.V("person").has("name", "John").as('a').outE("knows").as('b')
-        //  .has("date", 20200101).inV().as('c').has('id', 10)
-        //  .select('a').by(valueMap('age', "name"))
-        //  .select('c').by(valueMap('id', "name"))
+        // the same label, of which the duplicates are kept by the union
+        let mut plan = new_plan();
+        let id = union(&mut plan, vec![scan("person", None), scan("person", None)]);
+        assert_eq!(plan.len(), 3);
+        assert!(matches!(plan.get_opr(id).unwrap().opr, Some(Opr::Union(_))));
 
-        // g.V("person")
-        let scan = pb::Scan {
-            scan_opt: 0,
-            alias: None,
-            params: Some(query_params(vec!["person".into()], vec![])),
-            idx_predicate: None,
-            meta_data: None,
+        // differ by both the labels and the constants
+        let mut plan = new_plan();
+        let id = union(&mut plan, vec![scan("person", Some(1)), scan("software", Some(2))]);
+        assert_eq!(plan.len(), 3);
+        assert!(matches!(plan.get_opr(id).unwrap().opr, Some(Opr::Union(_))));
+
+        // each of the scans is limited, which is not the same as limiting the inlined scan
+        let limited = |label: &str| {
+            let mut scan = scan(label, None);
+            scan.params.as_mut().unwrap().limit = Some(pb::Range { lower: 0, upper: 10 });
+            scan
         };
-        let mut opr_id = plan
-            .append_operator_as_node(scan.into(), vec![])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+        let mut plan = new_plan();
+        let id = union(&mut plan, vec![limited("person"), limited("software")]);
+        assert_eq!(plan.len(), 3);
+        assert!(matches!(plan.get_opr(id).unwrap().opr, Some(Opr::Union(_))));
+    }
 
-        // .has("name", "John")
-        let select = pb::Select { predicate: str_to_expr_pb("@.name == \"John\"".to_string()).ok() };
-        opr_id = plan
-            .append_operator_as_node(select.into(), vec![opr_id as NodeId])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-        // The column "name" in a predicate should not be added
-        assert!(plan
-            .meta
-            .get_node_meta(0)
-            .unwrap()
-            .get_columns()
-            .is_empty());
+    #[test]
+    fn join_tags_rename() {
+        // g.V().as('a').join(out().as('b'), out().as('b')) on 'a', where both sides define the tag 'b'
+        let build_sides = || {
+            let mut plan = LogicalPlan::default();
+            let scan = pb::Scan {
+                scan_opt: 0,
+                alias: Some("a".into()),
+                params: Some(query_params(vec![], vec![])),
+                idx_predicate: None,
+                meta_data: None,
+                connector: None,
+                named_result: String::new(),
+            };
+            plan.append_operator_as_node(scan.into(), vec![])
+                .unwrap();
+            let expand = pb::EdgeExpand {
+                v_tag: None,
+                direction: 0,
+                params: Some(query_params(vec![], vec![])),
+                expand_opt: 0,
+                alias: Some("b".into()),
+                meta_data: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges: false,
+                vertex_tables: vec![],
+                is_optional: false,
+            };
+            let id1 = plan
+                .append_operator_as_node(expand.clone().into(), vec![0])
+                .unwrap();
+            let id2 = plan
+                .append_operator_as_node(expand.into(), vec![0])
+                .unwrap();
+            (plan, id1, id2)
+        };
+        let join_with_renames = |renames: Vec<pb::join::Rename>| pb::Join {
+            left_keys: vec![common_pb::Variable::from("@a".to_string())],
+            right_keys: vec![common_pb::Variable::from("@a".to_string())],
+            kind: 0,
+            renames,
+        };
 
-        // .as('a')
-        let as_opr = pb::As { alias: Some("a".into()) };
-        opr_id = plan
-            .append_operator_as_node(as_opr.into(), vec![opr_id as NodeId])
+        // without renames, the left 'b' takes precedence
+        let (mut plan, id1, id2) = build_sides();
+        let join_id = plan
+            .append_operator_as_node(join_with_renames(vec![]).into(), vec![id1, id2])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
         let a_id = plan.meta.get_tag_id("a").unwrap();
-        assert_eq!(plan.meta.get_tag_nodes(a_id), &vec![0]);
+        let b_id = plan.meta.get_tag_id("b").unwrap();
+        assert_eq!(plan.meta.get_node_tags(join_id), vec![a_id, b_id].into_iter().collect());
 
-        // outE("knows").as('b').has("date", 20200101)
-        let expand = pb::EdgeExpand {
-            v_tag: Some("a".into()),
-            direction: 0,
-            params: Some(query_params(vec!["knows".into()], vec![])),
-            expand_opt: 1,
-            alias: Some("b".into()),
-            meta_data: None,
-        };
-        opr_id = plan
-            .append_operator_as_node(expand.into(), vec![opr_id as NodeId])
+        // rename the right 'b' as 'c'
+        let (mut plan, id1, id2) = build_sides();
+        let rename = pb::join::Rename { side: 1, from: Some("b".into()), to: Some("c".into()) };
+        let join_id = plan
+            .append_operator_as_node(join_with_renames(vec![rename]).into(), vec![id1, id2])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId]);
         let b_id = plan.meta.get_tag_id("b").unwrap();
-        assert_eq!(plan.meta.get_tag_nodes(b_id), &vec![opr_id as NodeId]);
+        let c_id = plan.meta.get_tag_id("c").unwrap();
+        assert_eq!(plan.meta.get_node_tags(join_id), vec![a_id, b_id, c_id].into_iter().collect());
+        assert_eq!(plan.meta.get_tag_nodes(c_id), plan.meta.get_tag_nodes(b_id));
+        match &plan.get_node(join_id).unwrap().borrow().opr.opr {
+            Some(pb::logical_plan::operator::Opr::Join(join)) => {
+                assert_eq!(join.renames[0].from, Some((b_id as i32).into()));
+                assert_eq!(join.renames[0].to, Some((c_id as i32).into()));
+            }
+            _ => panic!("the node is not a `Join`"),
+        }
 
-        //.inV().as('c')
-        let getv = pb::GetV {
-            tag: None,
-            opt: 2,
+        // the renamed tag 'd' is not defined by the left side
+        let (mut plan, id1, id2) = build_sides();
+        plan.meta.get_or_set_tag_id("d");
+        let rename = pb::join::Rename { side: 0, from: Some("d".into()), to: Some("e".into()) };
+        assert!(matches!(
+            plan.append_operator_as_node(join_with_renames(vec![rename]).into(), vec![id1, id2]),
+            Err(IrError::TagNotExist(_))
+        ));
+    }
+
+    #[test]
+    fn logical_plan_lint() {
+        let scan = |alias: &str| pb::Scan {
+            scan_opt: 0,
+            alias: Some(alias.into()),
             params: Some(query_params(vec![], vec![])),
-            alias: Some("c".into()),
+            idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        opr_id = plan
-            .append_operator_as_node(getv.into(), vec![opr_id as NodeId])
+        let sink = |tags: Vec<&str>| pb::Sink {
+            tags: tags
+                .into_iter()
+                .map(|tag| common_pb::NameOrIdKey { key: Some(tag.into()) })
+                .collect(),
+            sink_target: Some(pb::sink::SinkTarget {
+                inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
+                    id_name_mappings: vec![],
+                    layout: 0,
+                })),
+            }),
+        };
+        let times = pb::Join { left_keys: vec![], right_keys: vec![], kind: 6, renames: vec![] };
+        let rules = |plan: &LogicalPlan| -> Vec<(LintRule, NodeId)> {
+            plan.lint()
+                .into_iter()
+                .map(|warning| (warning.rule, warning.node))
+                .collect()
+        };
+
+        // a cartesian product of which the 'b' is never referred to
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan("a").into(), vec![])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId]);
-        let c_id = plan.meta.get_tag_id("c").unwrap();
-        assert_eq!(plan.meta.get_tag_nodes(c_id), &vec![opr_id as NodeId]);
+        plan.append_operator_as_node(scan("b").into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(times.clone().into(), vec![0, 1])
+            .unwrap();
+        plan.append_operator_as_node(sink(vec!["a"]).into(), vec![2])
+            .unwrap();
+        assert_eq!(rules(&plan), vec![(LintRule::UnusedAlias, 1), (LintRule::CartesianWithoutLimit, 2)]);
+        assert!(plan.lint()[0].message.contains("\"b\""));
 
-        // .has("id", 10)
-        let select = pb::Select { predicate: str_to_expr_pb("@.id == 10".to_string()).ok() };
-        opr_id = plan
-            .append_operator_as_node(select.into(), vec![opr_id as NodeId])
+        // the cartesian product is limited, and all the tags are sinked
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan("a").into(), vec![])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId - 1]);
-        // The column "id" in a predicate should not be added
-        assert!(plan
-            .meta
-            .get_node_meta(opr_id as NodeId - 1)
-            .unwrap()
-            .get_columns()
-            .is_empty());
+        plan.append_operator_as_node(scan("b").into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(times.into(), vec![0, 1])
+            .unwrap();
+        plan.append_operator_as_node(
+            pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false }.into(),
+            vec![2],
+        )
+        .unwrap();
+        plan.append_operator_as_node(sink(vec![]).into(), vec![3])
+            .unwrap();
+        assert!(plan.lint().is_empty());
 
-        // .select('a').by(valueMap('age', "name"))
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("{@a.age, @a.name}".to_string()).ok(),
-                alias: None,
+        // a dedup after ordering, and an unbounded path expansion
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan("a").into(), vec![])
+            .unwrap();
+        let order = pb::OrderBy {
+            pairs: vec![pb::order_by::OrderingPair {
+                key: Some(common_pb::Variable::from("@a.name".to_string())),
+                order: 1,
             }],
-            is_append: true,
-            meta_data: vec![],
+            limit: None,
+            per_partition: false,
         };
-        opr_id = plan
-            .append_operator_as_node(project.into(), vec![opr_id as NodeId])
+        plan.append_operator_as_node(order.into(), vec![0])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId]);
+        let dedup = pb::Dedup { keys: vec![common_pb::Variable::from("@a".to_string())] };
+        plan.append_operator_as_node(dedup.into(), vec![1])
+            .unwrap();
+        let path = pb::PathExpand {
+            base: Some(pb::path_expand::ExpandBase {
+                edge_expand: Some(pb::EdgeExpand {
+                    v_tag: None,
+                    direction: 0,
+                    params: Some(query_params(vec![], vec![])),
+                    expand_opt: 0,
+                    alias: None,
+                    meta_data: None,
+                    dedup_both: false,
+                    exclude_self_loops: false,
+                    collapse_parallel_edges: false,
+                    vertex_tables: vec![],
+                    is_optional: false,
+                }),
+                get_v: None,
+            }),
+            start_tag: None,
+            alias: None,
+            hop_range: Some(pb::Range { lower: 1, upper: i32::MAX }),
+            path_opt: 0,
+            result_opt: 0,
+            condition: None,
+            timeout_ms: 0,
+        };
+        plan.append_operator_as_node(path.into(), vec![2])
+            .unwrap();
+        plan.append_operator_as_node(sink(vec![]).into(), vec![3])
+            .unwrap();
+        assert_eq!(rules(&plan), vec![(LintRule::DedupAfterOrder, 2), (LintRule::UnboundedPathExpand, 3)]);
+    }
+
+    #[test]
+    fn logical_plan_complexity() {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let path = |hop_range: Option<pb::Range>| pb::PathExpand {
+            base: Some(pb::path_expand::ExpandBase {
+                edge_expand: Some(pb::EdgeExpand {
+                    v_tag: None,
+                    direction: 0,
+                    params: Some(query_params(vec![], vec![])),
+                    expand_opt: 0,
+                    alias: None,
+                    meta_data: None,
+                    dedup_both: false,
+                    exclude_self_loops: false,
+                    collapse_parallel_edges: false,
+                    vertex_tables: vec![],
+                    is_optional: false,
+                }),
+                get_v: None,
+            }),
+            start_tag: None,
+            alias: None,
+            hop_range,
+            path_opt: 0,
+            result_opt: 0,
+            condition: None,
+            timeout_ms: 0,
+        };
+        assert_eq!(get_scan_complexity(&scan, None), UNKNOWN_SCAN_COMPLEXITY);
+        // at most 3 hops
+        assert_eq!(get_path_complexity(&path(Some(pb::Range { lower: 1, upper: 4 }))), 8.0);
         assert_eq!(
-            plan.meta
-                .get_node_meta(plan.meta.get_tag_nodes(a_id)[0])
-                .unwrap()
-                .get_columns(),
-            vec!["age".into(), "name".into()]
+            get_path_complexity(&path(None)),
+            get_path_complexity(&path(Some(pb::Range { lower: 1, upper: i32::MAX })))
         );
 
-        // .select('c').by(valueMap('age', "name"))
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("{@c.age, @c.name}".to_string()).ok(),
-                alias: None,
-            }],
-            is_append: true,
-            meta_data: vec![],
+        let new_plan = |hop_range: Option<pb::Range>| {
+            let mut plan = LogicalPlan::default();
+            plan.append_operator_as_node(scan.clone().into(), vec![])
+                .unwrap();
+            plan.append_operator_as_node(path(hop_range).into(), vec![0])
+                .unwrap();
+            plan
         };
-        opr_id = plan
-            .append_operator_as_node(project.into(), vec![opr_id as NodeId])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![opr_id as NodeId]);
+        let mut bounded = new_plan(Some(pb::Range { lower: 1, upper: 4 }));
+        let mut unbounded = new_plan(None);
+        let bounded_score = bounded.estimate_complexity();
         assert_eq!(
-            plan.meta
-                .get_node_meta(plan.meta.get_tag_nodes(c_id)[0])
-                .unwrap()
-                .get_columns(),
-            vec!["age".into(), "name".into()]
+            unbounded.estimate_complexity() - bounded_score,
+            2.0 * 2f64.powi(MAX_SCORED_HOPS - 1) - 8.0
         );
+
+        // no threshold by default
+        assert_eq!(unbounded.check_complexity().unwrap(), unbounded.estimate_complexity());
+        bounded
+            .meta
+            .set_complexity_threshold(Some(bounded_score));
+        unbounded
+            .meta
+            .set_complexity_threshold(Some(bounded_score));
+        assert_eq!(bounded.check_complexity().unwrap(), bounded_score);
+        match unbounded.check_complexity() {
+            Err(IrError::ComplexityExceeded(_, threshold)) => assert_eq!(threshold, bounded_score),
+            _ => panic!("should exceed the threshold"),
+        }
     }
 
     #[test]
-    fn column_maintain_case5() {
-        // Test the maintenance of all columns
-        let mut plan = LogicalPlan::default();
-        // g.V("person").valueMap(ALL)
-
-        // g.V("person")
+    fn estimate_cardinality() {
         let scan = pb::Scan {
             scan_opt: 0,
-            alias: None,
-            params: Some(pb::QueryParams {
-                tables: vec!["person".into()],
-                columns: vec!["a".into()],
-                is_all_columns: true,
-                limit: None,
-                predicate: None,
-                sample_ratio: 1.0,
-                extra: Default::default(),
-            }),
+            alias: Some("a".into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec!["knows".into()], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let path = pb::PathExpand {
+            base: Some(pb::path_expand::ExpandBase { edge_expand: Some(expand.clone()), get_v: None }),
+            start_tag: None,
+            alias: None,
+            hop_range: Some(pb::Range { lower: 1, upper: 3 }),
+            path_opt: 0,
+            result_opt: 0,
+            condition: None,
+            timeout_ms: 0,
+        };
+        let select = pb::Select { predicate: str_to_expr_pb("@a.age > 10".to_string()).ok() };
+        let limit = pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false };
+        let new_plan = |schema: Option<Schema>| {
+            let mut plan = LogicalPlan::default();
+            plan.meta
+                .set_store_meta(Some(std::sync::Arc::new(StoreMeta { schema, version: 0 })));
+            plan.append_operator_as_node(scan.clone().into(), vec![])
+                .unwrap();
+            plan.append_operator_as_node(expand.clone().into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(select.clone().into(), vec![1])
+                .unwrap();
+            plan.append_operator_as_node(limit.clone().into(), vec![2])
+                .unwrap();
+            plan.append_operator_as_node(path.clone().into(), vec![0])
+                .unwrap();
+            plan
         };
 
-        plan.append_operator_as_node(scan.into(), vec![])
-            .unwrap();
-        assert!(plan
-            .meta
-            .get_curr_node_meta()
-            .unwrap()
-            .is_all_columns());
-
-        let mut plan = LogicalPlan::default();
-        // g.V("person").valueMap(ALL)
+        // 100 persons and 100 softwares, with 2 knows per vertex on average
+        let schema = Schema::new(
+            vec![("person".to_string(), 0), ("software".to_string(), 1)],
+            vec![("knows".to_string(), 0)],
+            vec![],
+        )
+        .with_entity_counts(vec![(0, 100), (1, 100)])
+        .with_relation_counts(vec![(0, 400)]);
+        let plan = new_plan(Some(schema));
+        assert_eq!(plan.estimate_cardinality(0).unwrap(), Some(100.0));
+        assert_eq!(plan.estimate_cardinality(1).unwrap(), Some(200.0));
+        assert_eq!(plan.estimate_cardinality(2).unwrap(), Some(200.0 * PREDICATE_SELECTIVITY));
+        assert_eq!(plan.estimate_cardinality(3).unwrap(), Some(10.0));
+        // 1 or 2 hops
+        assert_eq!(plan.estimate_cardinality(4).unwrap(), Some(100.0 * (2.0 + 4.0)));
+        assert!(matches!(plan.estimate_cardinality(5), Err(IrError::NodeNotExist(5))));
+
+        // unknown without the statistics
+        let plan = new_plan(None);
+        assert_eq!(plan.estimate_cardinality(1).unwrap(), None);
+    }
 
-        // g.V("person")
+    #[test]
+    fn max_hops_of_path_expand() {
         let scan = pb::Scan {
             scan_opt: 0,
             alias: None,
-            params: Some(pb::QueryParams {
-                tables: vec!["person".into()],
-                columns: vec!["a".into()],
-                is_all_columns: false,
-                limit: None,
-                predicate: None,
-                sample_ratio: 1.0,
-                extra: Default::default(),
-            }),
+            params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let path = |lower: i32, upper: i32| pb::PathExpand {
+            base: Some(pb::path_expand::ExpandBase {
+                edge_expand: Some(pb::EdgeExpand {
+                    v_tag: None,
+                    direction: 2,
+                    params: Some(query_params(vec![], vec![])),
+                    expand_opt: 0,
+                    alias: None,
+                    meta_data: None,
+                    dedup_both: false,
+                    exclude_self_loops: false,
+                    collapse_parallel_edges: false,
+                    vertex_tables: vec![],
+                    is_optional: false,
+                }),
+                get_v: None,
+            }),
+            start_tag: None,
+            alias: None,
+            hop_range: Some(pb::Range { lower, upper }),
+            path_opt: 0,
+            result_opt: 0,
+            condition: None,
+            timeout_ms: 0,
         };
 
-        let opr_id = plan
-            .append_operator_as_node(scan.into(), vec![])
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        // no maximum by default
+        plan.append_operator_as_node(path(1, i32::MAX).into(), vec![0])
             .unwrap();
 
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@.~all".to_string()).ok(),
-                alias: None,
-            }],
-            is_append: true,
-            meta_data: vec![],
-        };
-
-        plan.append_operator_as_node(project.into(), vec![opr_id])
+        plan.meta.set_max_hops(Some(3));
+        // the upper bound is excluded
+        plan.append_operator_as_node(path(1, 4).into(), vec![0])
             .unwrap();
-        assert!(plan
-            .meta
-            .get_node_meta(0)
-            .unwrap()
-            .is_all_columns());
+        match plan.append_operator_as_node(path(1, 5).into(), vec![0]) {
+            Err(IrError::HopRangeExceeded(1, 5, 3)) => {}
+            _ => panic!("should exceed the maximal hops"),
+        }
+        // e.g., `both('*1..')`
+        match plan.append_operator_as_node(path(1, i32::MAX).into(), vec![0]) {
+            Err(IrError::HopRangeExceeded(1, i32::MAX, 3)) => {}
+            _ => panic!("should exceed the maximal hops"),
+        }
+        assert_eq!(plan.len(), 3);
     }
 
     #[test]
-    fn column_maintain_case6() {
-        // Test the maintenance of columns after **rename tags**
-        let mut plan = LogicalPlan::default();
-        // g.V().out().as("a").in().select("a").as("b").select("b").by("name")
-
-        // g.V()
+    fn optional_expansions() {
         let scan = pb::Scan {
             scan_opt: 0,
             alias: None,
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(scan.into(), vec![])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-
-        // .out().as("a")
-        let expand = pb::EdgeExpand {
+        let expand = |expand_opt: i32| pb::EdgeExpand {
             v_tag: None,
             direction: 0,
             params: Some(query_params(vec![], vec![])),
-            expand_opt: 0,
-            alias: Some("a".into()),
+            expand_opt,
+            alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: true,
         };
-        plan.append_operator_as_node(expand.into(), vec![0])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
 
-        // .in()
-        let expand = pb::EdgeExpand {
-            v_tag: None,
-            direction: 1,
-            params: Some(query_params(vec![], vec![])),
-            expand_opt: 0,
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        // e.g., `OPTIONAL MATCH (a)-[]->(b)`
+        plan.append_operator_as_node(expand(0).into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(
+            pb::GetV {
+                tag: None,
+                opt: 4,
+                params: Some(query_params(vec![], vec![])),
+                alias: None,
+                meta_data: None,
+                is_optional: true,
+                end_alias: None,
+            }
+            .into(),
+            vec![1],
+        )
+        .unwrap();
+        // the degree is always yielded
+        match plan.append_operator_as_node(expand(2).into(), vec![0]) {
+            Err(IrError::Unsupported(_)) => {}
+            _ => panic!("should not expand the degree optionally"),
+        }
+        // a hop of the path cannot be null
+        let path = pb::PathExpand {
+            base: Some(pb::path_expand::ExpandBase { edge_expand: Some(expand(0)), get_v: None }),
+            start_tag: None,
             alias: None,
-            meta_data: None,
+            hop_range: Some(pb::Range { lower: 1, upper: 3 }),
+            path_opt: 0,
+            result_opt: 0,
+            condition: None,
+            timeout_ms: 0,
         };
-        plan.append_operator_as_node(expand.into(), vec![1])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![2]);
+        match plan.append_operator_as_node(path.into(), vec![0]) {
+            Err(IrError::Unsupported(_)) => {}
+            _ => panic!("should not expand the path optionally"),
+        }
+        assert_eq!(plan.len(), 3);
+    }
 
-        // .select("a").as("b")
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@a".to_string()).ok(),
-                alias: Some("b".into()),
-            }],
-            is_append: true,
-            meta_data: vec![],
+    #[test]
+    fn reorder_predicates_by_costs() {
+        let select = |predicate: &str| pb::Select {
+            predicate: Some(str_to_expr_pb(predicate.to_string()).unwrap()),
         };
-        plan.append_operator_as_node(project.into(), vec![2])
+        let predicate = "@.name StartsWith \"J\" && @.~label == 1";
+
+        // not reordered by default
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(select(predicate).into(), vec![])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+        assert_eq!(plan.get_node(0).unwrap().borrow().opr, select(predicate).into());
 
-        // select("b").by("name")
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@b.name".to_string()).ok(),
-                alias: None,
+        let mut plan = LogicalPlan::default();
+        plan.meta.set_reorder_predicates(true);
+        plan.append_operator_as_node(select(predicate).into(), vec![])
+            .unwrap();
+        let node = plan.get_node(0).unwrap();
+        assert_eq!(node.borrow().opr, select("(@.~label == 1) && (@.name StartsWith \"J\")").into());
+        assert_eq!(
+            node.borrow().annotations,
+            vec!["predicate: reorder the terms of `&&` as [1, 0] by their estimated costs".to_string()]
+        );
+    }
+
+    #[test]
+    fn push_down_aggregation_below_join() {
+        let build_join = || {
+            let mut plan = LogicalPlan::default();
+            plan.meta.set_push_down_aggregation(true);
+            let scan = pb::Scan {
+                scan_opt: 0,
+                alias: Some("a".into()),
+                params: Some(query_params(vec!["person".into()], vec![])),
+                idx_predicate: None,
+                meta_data: None,
+                connector: None,
+                named_result: String::new(),
+            };
+            let expand = |label: &str, alias: &str| pb::EdgeExpand {
+                v_tag: None,
+                direction: 0,
+                params: Some(query_params(vec![label.into()], vec![])),
+                expand_opt: 1,
+                alias: Some(alias.into()),
+                meta_data: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges: false,
+                vertex_tables: vec![],
+                is_optional: false,
+            };
+            let join = pb::Join {
+                left_keys: vec![common_pb::Variable::from("@a".to_string())],
+                right_keys: vec![common_pb::Variable::from("@a".to_string())],
+                kind: 0,
+                renames: vec![],
+            };
+            plan.append_operator_as_node(scan.into(), vec![])
+                .unwrap();
+            plan.append_operator_as_node(expand("knows", "b").into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(expand("created", "c").into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(join.into(), vec![1, 2])
+                .unwrap();
+            plan
+        };
+        let agg_func = |var: Option<&str>, aggregate: i32, alias: &str| pb::group_by::AggFunc {
+            vars: var
+                .map(|var| common_pb::Variable::from(var.to_string()))
+                .into_iter()
+                .collect(),
+            aggregate,
+            alias: Some(alias.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
+        };
+        let group = |functions: Vec<pb::group_by::AggFunc>| pb::GroupBy {
+            mappings: vec![pb::group_by::KeyAlias {
+                key: Some(common_pb::Variable::from("@a".to_string())),
+                alias: Some("k".into()),
             }],
-            is_append: true,
+            functions,
             meta_data: vec![],
+            null_key_opt: 0,
         };
-        plan.append_operator_as_node(project.into(), vec![3])
+        let get_group = |plan: &LogicalPlan, id: NodeId| match plan.get_opr(id).unwrap().opr {
+            Some(pb::logical_plan::operator::Opr::GroupBy(group)) => group,
+            _ => panic!("the node {} is not a `GroupBy`", id),
+        };
+
+        // count() and sum(@c.weight) grouped by the join key
+        let mut plan = build_join();
+        let id = plan
+            .append_operator_as_node(
+                group(vec![agg_func(None, 3, "cnt"), agg_func(Some("@c.weight"), 0, "w")]).into(),
+                vec![3],
+            )
             .unwrap();
+        assert_eq!(id, 8);
+        assert!(plan.get_node(3).is_none());
+        let hidden_tag = |name: &str| -> common_pb::NameOrId {
+            (plan
+                .meta
+                .get_tag_id(&format!("~agg_{}_4", name))
+                .unwrap() as i32)
+                .into()
+        };
+        let tag_var = |name: &str| common_pb::Variable {
+            tag: Some(hidden_tag(name)),
+            property: None,
+            node_type: None,
+        };
+        assert_eq!(plan.get_node(4).unwrap().borrow().parents, vec![1].into_iter().collect());
+        assert_eq!(plan.get_node(5).unwrap().borrow().parents, vec![2].into_iter().collect());
+        assert_eq!(plan.get_node(6).unwrap().borrow().parents, vec![4, 5].into_iter().collect());
+        // the left side only counts, and the right side also sums the weights
+        let left_group = get_group(&plan, 4);
+        assert_eq!(left_group.mappings[0].alias, Some(hidden_tag("left_key_0")));
+        assert_eq!(left_group.functions.len(), 1);
+        assert_eq!(left_group.functions[0].alias, Some(hidden_tag("left_count")));
+        let right_group = get_group(&plan, 5);
+        assert_eq!(right_group.functions.len(), 2);
+        assert_eq!(right_group.functions[1].aggregate, 0);
+        assert_eq!(right_group.functions[1].alias, Some(hidden_tag("partial_1")));
+        let mul = common_pb::ExprOpr {
+            node_type: None,
+            item: Some(common_pb::expr_opr::Item::Arith(common_pb::Arithmetic::Mul as i32)),
+        };
+        match plan.get_opr(7).unwrap().opr {
+            Some(pb::logical_plan::operator::Opr::Project(project)) => {
+                assert!(project.is_append);
+                assert_eq!(
+                    project.mappings[0]
+                        .expr
+                        .as_ref()
+                        .unwrap()
+                        .operators,
+                    vec![tag_var("left_count").into(), mul.clone(), tag_var("right_count").into()]
+                );
+                assert_eq!(
+                    project.mappings[1]
+                        .expr
+                        .as_ref()
+                        .unwrap()
+                        .operators,
+                    vec![tag_var("partial_1").into(), mul, tag_var("left_count").into()]
+                );
+            }
+            _ => panic!("the node 7 is not a `Project`"),
+        }
+        let final_group = get_group(&plan, 8);
+        assert_eq!(final_group.mappings[0].key, Some(tag_var("left_key_0")));
+        assert_eq!(final_group.mappings[0].alias, Some((plan.meta.get_tag_id("k").unwrap() as i32).into()));
+        assert_eq!(final_group.functions.len(), 2);
+        assert!(final_group
+            .functions
+            .iter()
+            .all(|func| func.aggregate == 0));
+        assert_eq!(final_group.functions[1].vars, vec![tag_var("product_1")]);
         assert_eq!(
-            plan.meta
-                .get_node_meta(1)
-                .unwrap()
-                .get_columns(),
-            vec!["name".into()]
+            plan.get_node(8).unwrap().borrow().annotations,
+            vec!["aggregation: push down below the join as the nodes [4, 5]".to_string()]
         );
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![4]);
+
+        // max() is not pushed down
+        let mut plan = build_join();
+        let id = plan
+            .append_operator_as_node(group(vec![agg_func(Some("@c.weight"), 2, "w")]).into(), vec![3])
+            .unwrap();
+        assert_eq!(id, 4);
+        assert_eq!(plan.get_node(4).unwrap().borrow().parents, vec![3].into_iter().collect());
+
+        // not pushed down once the optimization budget is used up
+        let mut plan = build_join();
+        plan.meta
+            .set_optimization_budget(Some(Duration::ZERO));
+        let id = plan
+            .append_operator_as_node(group(vec![agg_func(None, 3, "cnt")]).into(), vec![3])
+            .unwrap();
+        assert_eq!(id, 4);
+        assert_eq!(plan.meta.get_optimization_stage(), OptimizationStage::Cheap);
+        assert!(plan
+            .explain()
+            .starts_with("optimization: cheap (budget: 0ns)\n"));
+
+        let mut plan = build_join();
+        plan.meta
+            .set_optimization_budget(Some(Duration::from_secs(10)));
+        let id = plan
+            .append_operator_as_node(group(vec![agg_func(None, 3, "cnt")]).into(), vec![3])
+            .unwrap();
+        assert_eq!(id, 8);
+        assert_eq!(plan.meta.get_optimization_stage(), OptimizationStage::Full);
+        assert!(plan
+            .explain()
+            .starts_with("optimization: full (budget: 10s)\n"));
     }
 
     #[test]
-    fn column_maintain_semi_apply() {
-        let mut plan = LogicalPlan::default();
-        // g.V().where(out()).valueMap("age")
+    fn join_key_projections() {
+        let build_join = |kind: i32, left_key: &str, sink_tags: Vec<&str>| {
+            let mut plan = LogicalPlan::default();
+            let scan = pb::Scan {
+                scan_opt: 0,
+                alias: Some("a".into()),
+                params: Some(query_params(vec!["person".into()], vec![])),
+                idx_predicate: None,
+                meta_data: None,
+                connector: None,
+                named_result: String::new(),
+            };
+            let expand = |label: &str, alias: &str| pb::EdgeExpand {
+                v_tag: None,
+                direction: 0,
+                params: Some(query_params(vec![label.into()], vec![])),
+                expand_opt: 1,
+                alias: Some(alias.into()),
+                meta_data: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges: false,
+                vertex_tables: vec![],
+                is_optional: false,
+            };
+            let join = pb::Join {
+                left_keys: vec![common_pb::Variable::from(left_key.to_string())],
+                right_keys: vec![common_pb::Variable::from("@a".to_string())],
+                kind,
+                renames: vec![],
+            };
+            let sink = pb::Sink {
+                tags: sink_tags
+                    .into_iter()
+                    .map(|tag| common_pb::NameOrIdKey { key: Some(tag.into()) })
+                    .collect(),
+                sink_target: Some(pb::sink::SinkTarget {
+                    inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
+                        id_name_mappings: vec![],
+                        layout: 0,
+                    })),
+                }),
+            };
+            plan.append_operator_as_node(scan.into(), vec![])
+                .unwrap();
+            plan.append_operator_as_node(expand("knows", "b").into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(expand("created", "c").into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(join.into(), vec![1, 2])
+                .unwrap();
+            plan.append_operator_as_node(sink.into(), vec![3])
+                .unwrap();
+            plan
+        };
+        let project = |tags: Vec<i32>| pb::Project {
+            mappings: tags
+                .into_iter()
+                .map(|tag| pb::project::ExprAlias {
+                    expr: Some(common_pb::Expression {
+                        operators: vec![common_pb::Variable {
+                            tag: Some(tag.into()),
+                            property: None,
+                            node_type: None,
+                        }
+                        .into()],
+                    }),
+                    alias: Some(tag.into()),
+                })
+                .collect(),
+            is_append: false,
+            meta_data: vec![],
+        };
 
-        // g.V()
-        let scan = pb::Scan {
+        // the left side keeps all its tags, while "c" of the right side is unused after the join
+        let plan = build_join(0, "@a", vec!["b"]);
+        assert_eq!(plan.get_join_key_projections(3), (None, Some(project(vec![0]))));
+        let plan = build_join(0, "@a", vec!["b", "c"]);
+        assert_eq!(plan.get_join_key_projections(3), (None, None));
+        // the right side of a semi join is kept for the keys only
+        let plan = build_join(4, "@a", vec!["a"]);
+        assert_eq!(plan.get_join_key_projections(3), (None, Some(project(vec![0]))));
+        // all the tags are sunk
+        let plan = build_join(0, "@a", vec![]);
+        assert_eq!(plan.get_join_key_projections(3), (None, None));
+        // the key of the left side refers to the head
+        let plan = build_join(0, "@.name", vec!["b"]);
+        assert_eq!(plan.get_join_key_projections(3), (None, None));
+        // not a join
+        assert_eq!(plan.get_join_key_projections(1), (None, None));
+    }
+
+    #[test]
+    fn canonicalize_node_ids() {
+        let scan = |alias: &str| pb::Scan {
             scan_opt: 0,
-            alias: None,
-            params: Some(query_params(vec![], vec![])),
+            alias: Some(alias.into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(scan.into(), vec![])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-
-        let expand = pb::EdgeExpand {
+        let expand = |label: &str| pb::EdgeExpand {
             v_tag: None,
             direction: 0,
-            params: Some(query_params(vec![], vec![])),
+            params: Some(query_params(vec![label.into()], vec![])),
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
-        let oprid = plan
-            .append_operator_as_node(expand.into(), vec![])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+        let times = pb::Join { left_keys: vec![], right_keys: vec![], kind: 6, renames: vec![] };
 
-        // .where(out())
-        let apply = pb::Apply {
-            join_kind: 4, // semi join
-            tags: vec![],
-            subtask: oprid as PbNodeId,
-            alias: None,
-        };
-        let oprid = plan
-            .append_operator_as_node(apply.into(), vec![0])
+        // the branches of the join are appended one after another
+        let mut plan1 = LogicalPlan::default();
+        plan1
+            .append_operator_as_node(scan("a").into(), vec![])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-
-        // .valueMap("age")
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("{@.age}".to_string()).ok(),
-                alias: None,
-            }],
-            is_append: false,
-            meta_data: vec![],
-        };
-        plan.append_operator_as_node(project.into(), vec![oprid])
+        plan1
+            .append_operator_as_node(expand("knows").into(), vec![0])
             .unwrap();
-        assert_eq!(
-            plan.meta
-                .get_node_meta(0)
-                .unwrap()
-                .get_columns(),
-            vec!["age".into()]
-        );
+        plan1
+            .append_operator_as_node(scan("b").into(), vec![])
+            .unwrap();
+        plan1
+            .append_operator_as_node(expand("likes").into(), vec![2])
+            .unwrap();
+        plan1
+            .append_operator_as_node(times.clone().into(), vec![1, 3])
+            .unwrap();
+        // the scans of the branches are appended first
+        let mut plan2 = LogicalPlan::default();
+        plan2
+            .append_operator_as_node(scan("b").into(), vec![])
+            .unwrap();
+        plan2
+            .append_operator_as_node(scan("a").into(), vec![])
+            .unwrap();
+        plan2
+            .append_operator_as_node(expand("knows").into(), vec![1])
+            .unwrap();
+        plan2
+            .append_operator_as_node(expand("likes").into(), vec![0])
+            .unwrap();
+        plan2
+            .append_operator_as_node(times.into(), vec![2, 3])
+            .unwrap();
+        assert_ne!(pb::LogicalPlan::from(plan1.clone()), pb::LogicalPlan::from(plan2.clone()));
+
+        let id_map1 = plan1.canonicalize_node_ids().unwrap();
+        let id_map2 = plan2.canonicalize_node_ids().unwrap();
+        assert_eq!(pb::LogicalPlan::from(plan1.clone()), pb::LogicalPlan::from(plan2));
+        assert_eq!(plan1.len(), 5);
+        // the left branch of the join precedes the right one
+        assert_eq!(id_map1[&4], id_map2[&4]);
+        assert!(id_map1[&1] < id_map1[&3]);
+        assert_eq!(id_map1[&1], id_map2[&2]);
+
+        // a join before its parents
+        let order: Vec<NodeId> = vec![4, 0, 1, 2, 3];
+        assert!(plan1.reorder_nodes(&order).is_err());
+        // a missing node
+        assert!(plan1.reorder_nodes(&[0, 1, 2, 3]).is_err());
+        let plan_pb = pb::LogicalPlan::from(plan1.clone());
+        let id_map = plan1.reorder_nodes(&[1, 0, 2, 3, 4]).unwrap();
+        assert_eq!(id_map[&1], 0);
+        assert_ne!(pb::LogicalPlan::from(plan1), plan_pb);
     }
 
     #[test]
-    fn column_maintain_groupby_case1() {
-        // groupBy contains tagging a keys that is further a vertex
-        let mut plan = LogicalPlan::default();
-        // g.V().groupCount().order().by(select(keys).by('name'))
-
-        // g.V()
-        let scan = pb::Scan {
+    fn iter_plan_topo() {
+        let scan = |alias: &str| pb::Scan {
             scan_opt: 0,
-            alias: None,
-            params: Some(query_params(vec![], vec![])),
+            alias: Some(alias.into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(scan.into(), vec![])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-
-        let group = pb::GroupBy {
-            mappings: vec![pb::group_by::KeyAlias {
-                key: Some(common_pb::Variable { tag: None, property: None, node_type: None }),
-                alias: Some("~keys_2_0".into()),
-            }],
-            functions: vec![pb::group_by::AggFunc {
-                vars: vec![],
-                aggregate: 3,
-                alias: Some("~values_2_0".into()),
-            }],
-            meta_data: vec![],
+        let expand = |label: &str| pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![label.into()], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
-        plan.append_operator_as_node(group.into(), vec![0])
+        let times = pb::Join { left_keys: vec![], right_keys: vec![], kind: 6, renames: vec![] };
+
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan("b").into(), vec![])
             .unwrap();
-        let keys_tag_id = plan.meta.get_tag_id("~keys_2_0").unwrap();
-        assert_eq!(plan.meta.get_tag_nodes(keys_tag_id), &vec![0]);
+        plan.append_operator_as_node(scan("a").into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(expand("likes").into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(expand("knows").into(), vec![1])
+            .unwrap();
+        plan.append_operator_as_node(times.into(), vec![2, 3])
+            .unwrap();
+        let limit = pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false };
+        let id = plan.insert_between(0, 2, limit.into()).unwrap();
+        assert_eq!(id, 5);
+        // the inserted node precedes its child, and the left branch of the join precedes the right one
+        let order: Vec<NodeId> = plan
+            .iter_topo()
+            .map(|node| node.borrow().get_id())
+            .collect();
+        assert_eq!(order, vec![0, 1, 5, 2, 3, 4]);
+
+        struct ExpandCounter {
+            count: usize,
+        }
+        impl PlanVisitor for ExpandCounter {
+            fn visit_node(&mut self, node: &Node) -> IrResult<()> {
+                match &node.get_opr().opr {
+                    Some(pb::logical_plan::operator::Opr::Edge(_)) => self.count += 1,
+                    Some(pb::logical_plan::operator::Opr::Join(_)) => {
+                        return Err(IrError::Unsupported("join".to_string()))
+                    }
+                    _ => {}
+                }
+                Ok(())
+            }
+        }
+        let mut counter = ExpandCounter { count: 0 };
+        assert!(matches!(plan.accept(&mut counter), Err(IrError::Unsupported(_))));
+        // all the expansions precede the join
+        assert_eq!(counter.count, 2);
+    }
 
-        let order = pb::OrderBy {
-            pairs: vec![pb::order_by::OrderingPair {
-                key: Some(common_pb::Variable {
-                    tag: Some("~keys_2_0".into()),
-                    property: Some(common_pb::Property {
-                        item: Some(common_pb::property::Item::Key("name".into())),
-                    }),
-                    node_type: None,
-                }),
-                order: 0,
-            }],
-            limit: None,
+    #[test]
+    fn validate_plan() {
+        let opr = pb::logical_plan::Operator {
+            opr: Some(pb::logical_plan::operator::Opr::As(pb::As { alias: None })),
         };
-        plan.append_operator_as_node(order.into(), vec![1])
+        let join: pb::logical_plan::Operator =
+            pb::Join { left_keys: vec![], right_keys: vec![], kind: 6, renames: vec![] }.into();
+
+        let mut plan = LogicalPlan::with_root(Node::new(0, opr.clone()));
+        plan.append_node(Node::new(1, opr.clone()), vec![0])
             .unwrap();
-        assert!(plan
-            .meta
-            .get_node_meta(0)
+        plan.append_node(Node::new(2, opr.clone()), vec![0])
+            .unwrap();
+        assert!(matches!(
+            plan.append_node(Node::new(1, opr.clone()), vec![2]),
+            Err(IrError::InvalidNode(1, _))
+        ));
+        assert!(matches!(
+            plan.append_node(Node::new(3, opr.clone()), vec![3]),
+            Err(IrError::InvalidNode(3, _))
+        ));
+        // the branches are not merged
+        assert!(matches!(plan.validate(), Err(IrError::InvalidNode(2, _))));
+
+        let mut join_plan = plan.clone();
+        join_plan
+            .append_node(Node::new(3, join.clone()), vec![1])
+            .unwrap();
+        assert!(matches!(join_plan.validate(), Err(IrError::InvalidNode(3, _))));
+
+        plan.append_node(Node::new(3, join), vec![1, 2])
+            .unwrap();
+        plan.validate().unwrap();
+
+        // a cycle of the nodes 1 and 3
+        plan.get_node(1)
             .unwrap()
-            .get_columns()
-            .contains(&"name".into()));
+            .borrow_mut()
+            .parents
+            .insert(3);
+        // the node 3 does not have the node 1 as a child
+        assert!(matches!(plan.validate(), Err(IrError::InvalidNode(1, _))));
+        plan.get_node(3)
+            .unwrap()
+            .borrow_mut()
+            .children
+            .insert(1);
+        assert!(matches!(plan.validate(), Err(IrError::InvalidNode(1, _))));
     }
 
     #[test]
-    fn column_maintain_groupby_case2() {
-        // groupBy contains tagging a key that is further a vertex
-        let mut plan = LogicalPlan::default();
-        // g.V().groupCount().select(values)
-
-        // g.V()
-        let scan = pb::Scan {
+    fn check_plan_read_scope() {
+        let scan = |tables: Vec<common_pb::NameOrId>| pb::Scan {
             scan_opt: 0,
-            alias: None,
-            params: Some(query_params(vec![], vec![])),
+            alias: Some("a".into()),
+            params: Some(query_params(tables, vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(scan.into(), vec![])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-
-        let group = pb::GroupBy {
-            mappings: vec![pb::group_by::KeyAlias {
-                key: Some(common_pb::Variable { tag: None, property: None, node_type: None }),
-                alias: Some("~keys_2_0".into()),
-            }],
-            functions: vec![pb::group_by::AggFunc {
-                vars: vec![],
-                aggregate: 3,
-                alias: Some("~values_2_0".into()),
-            }],
-            meta_data: vec![],
+        let select = |predicate: &str| pb::Select { predicate: str_to_expr_pb(predicate.to_string()).ok() };
+        let is_denied = |result: IrResult<()>| match result {
+            Err(IrError::NodeError(_, err)) => matches!(*err, IrError::AccessDenied(_)),
+            _ => false,
         };
-        plan.append_operator_as_node(group.into(), vec![0])
+        let scope = pb::ReadScope {
+            labels: vec![
+                pb::read_scope::LabelScope {
+                    label: "person".to_string(),
+                    properties: vec!["name".to_string()],
+                },
+                pb::read_scope::LabelScope { label: "software".to_string(), properties: vec![] },
+            ],
+        };
+
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan(vec!["person".into()]).into(), vec![])
             .unwrap();
-        let keys_tag_id = plan.meta.get_tag_id("~keys_2_0").unwrap();
+        plan.append_operator_as_node(select("@.name == \"marko\"").into(), vec![0])
+            .unwrap();
+        // no read scope is given
+        plan.check_read_scope().unwrap();
+        plan.set_read_scope(scope.clone());
+        plan.check_read_scope().unwrap();
+
+        let mut age_plan = plan.clone();
+        age_plan
+            .append_operator_as_node(select("@a.age > 10").into(), vec![1])
+            .unwrap();
+        assert!(is_denied(age_plan.check_read_scope()));
 
-        assert_eq!(plan.meta.get_tag_nodes(keys_tag_id), &vec![0]);
+        // all the properties of `software` may be read
+        let mut software_plan = LogicalPlan::default();
+        software_plan.set_read_scope(scope.clone());
+        software_plan
+            .append_operator_as_node(scan(vec!["software".into()]).into(), vec![])
+            .unwrap();
+        software_plan
+            .append_operator_as_node(select("@a.lang == \"java\"").into(), vec![0])
+            .unwrap();
+        software_plan.check_read_scope().unwrap();
+
+        for tables in vec![vec!["person".into(), "city".into()], vec![]] {
+            let mut plan = LogicalPlan::default();
+            plan.set_read_scope(scope.clone());
+            plan.append_operator_as_node(scan(tables).into(), vec![])
+                .unwrap();
+            assert!(is_denied(plan.check_read_scope()));
+        }
+    }
 
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@~values_2_0".to_string()).ok(),
+    #[test]
+    fn build_plan_in_view() {
+        let scan = |tables: Vec<common_pb::NameOrId>, predicate: Option<&str>| {
+            let mut params = query_params(tables, vec![]);
+            params.predicate = predicate.map(|pred| str_to_expr_pb(pred.to_string()).unwrap());
+            pb::Scan {
+                scan_opt: 0,
                 alias: None,
-            }],
-            is_append: true,
-            meta_data: vec![],
+                params: Some(params),
+                idx_predicate: None,
+                meta_data: None,
+                connector: None,
+                named_result: String::new(),
+            }
         };
-        plan.append_operator_as_node(project.into(), vec![1])
+        let expand = |expand_opt: pb::edge_expand::ExpandOpt, vertex_tables: Vec<common_pb::NameOrId>| {
+            pb::EdgeExpand {
+                v_tag: None,
+                direction: 0,
+                params: Some(query_params(vec![], vec![])),
+                expand_opt: expand_opt as i32,
+                alias: None,
+                meta_data: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges: false,
+                vertex_tables,
+                is_optional: false,
+            }
+        };
+        let view = pb::GraphView {
+            name: "a".to_string(),
+            vertex_labels: vec!["person".into(), "software".into()],
+            vertex_predicate: str_to_expr_pb("@.tenant == \"a\"".to_string()).ok(),
+            edge_labels: vec!["knows".into()],
+            edge_predicate: None,
+        };
+        // append the operator to a plan within the view, after a scan if it is an expansion
+        let build_in_view = |view: &pb::GraphView, opr: pb::logical_plan::Operator| {
+            let mut plan = LogicalPlan::default();
+            plan.set_view(view.clone()).unwrap();
+            let mut parents = vec![];
+            if !matches!(opr.opr, Some(Opr::Scan(_))) {
+                plan.append_operator_as_node(scan(vec!["person".into()], None).into(), vec![])
+                    .unwrap();
+                parents.push(0);
+            }
+            plan.append_operator_as_node(opr, parents)
+                .map(|id| plan.get_opr(id).unwrap())
+        };
+        let pred_str = |pred: &Option<common_pb::Expression>| pred.as_ref().map(expr_pb_to_str);
+
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan(vec![], None).into(), vec![])
             .unwrap();
+        // the operators appended before are not constrained
+        assert!(matches!(plan.set_view(view.clone()), Err(IrError::Unsupported(_))));
+
+        match build_in_view(&view, scan(vec![], None).into())
+            .unwrap()
+            .opr
+        {
+            Some(Opr::Scan(scan)) => {
+                let params = scan.params.unwrap();
+                assert_eq!(params.tables, vec!["person".into(), "software".into()]);
+                assert_eq!(pred_str(&params.predicate), pred_str(&view.vertex_predicate));
+            }
+            _ => panic!("should be a `Scan`"),
+        }
+        match build_in_view(&view, scan(vec!["person".into(), "city".into()], Some("@.age > 10")).into())
+            .unwrap()
+            .opr
+        {
+            Some(Opr::Scan(scan)) => {
+                let params = scan.params.unwrap();
+                assert_eq!(params.tables, vec!["person".into()]);
+                let expected = combine_exprs(
+                    str_to_expr_pb("@.age > 10".to_string()).unwrap(),
+                    view.vertex_predicate.clone().unwrap(),
+                );
+                assert_eq!(pred_str(&params.predicate), Some(expr_pb_to_str(&expected)));
+            }
+            _ => panic!("should be a `Scan`"),
+        }
+        // none of the labels is in the view, and thus nothing is read
+        match build_in_view(&view, scan(vec!["city".into()], None).into())
+            .unwrap()
+            .opr
+        {
+            Some(Opr::Scan(scan)) => {
+                let params = scan.params.unwrap();
+                assert_eq!(params.tables, vec!["city".into()]);
+                assert_eq!(pred_str(&params.predicate), Some(expr_pb_to_str(&false_expr())));
+            }
+            _ => panic!("should be a `Scan`"),
+        }
+
+        match build_in_view(&view, expand(pb::edge_expand::ExpandOpt::Edge, vec![]).into())
+            .unwrap()
+            .opr
+        {
+            Some(Opr::Edge(edgexpd)) => {
+                assert_eq!(edgexpd.params.unwrap().tables, vec!["knows".into()]);
+                assert!(edgexpd.vertex_tables.is_empty());
+            }
+            _ => panic!("should be an `EdgeExpand`"),
+        }
+        // the vertex predicate can not apply to the vertices of an expansion
+        assert!(matches!(
+            build_in_view(&view, expand(pb::edge_expand::ExpandOpt::Vertex, vec![]).into()),
+            Err(IrError::Unsupported(_))
+        ));
+        let label_view = pb::GraphView { vertex_predicate: None, ..view.clone() };
+        match build_in_view(
+            &label_view,
+            expand(pb::edge_expand::ExpandOpt::Vertex, vec!["software".into(), "city".into()]).into(),
+        )
+        .unwrap()
+        .opr
+        {
+            Some(Opr::Edge(edgexpd)) => {
+                let params = edgexpd.params.unwrap();
+                assert_eq!(params.tables, vec!["knows".into()]);
+                assert!(params.predicate.is_none());
+                assert_eq!(edgexpd.vertex_tables, vec!["software".into()]);
+            }
+            _ => panic!("should be an `EdgeExpand`"),
+        }
     }
 
     #[test]
-    fn column_maintain_groupby_case3() {
-        // groupBy contains tagging a keys
-        let mut plan = LogicalPlan::default();
-        // g.V().group().by(values('name').as('a')).select('a')
-        // g.V()
+    fn normalize_plan() {
         let scan = pb::Scan {
             scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let expand = |label: &str| pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![label.into()], vec![])),
+            expand_opt: 0,
             alias: None,
-            params: Some(query_params(vec![], vec![])),
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let union = pb::Union { parents: vec![1, 2], alignment: 0 };
+        let select = |predicate: &str| pb::Select { predicate: str_to_expr_pb(predicate.to_string()).ok() };
+        let plan = |labels: [&str; 2], predicate: &str| {
+            let mut plan = LogicalPlan::default();
+            plan.append_operator_as_node(scan.clone().into(), vec![])
+                .unwrap();
+            plan.append_operator_as_node(expand(labels[0]).into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(expand(labels[1]).into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(union.clone().into(), vec![1, 2])
+                .unwrap();
+            plan.append_operator_as_node(select(predicate).into(), vec![3])
+                .unwrap();
+            plan
+        };
+
+        let mut plan1 = plan(["knows", "likes"], "@a.age > 10 && @a.name == \"x\"");
+        let mut plan2 = plan(["likes", "knows"], "\"x\" == @a.name && 10 < @a.age");
+        let plan3 = plan(["likes", "knows"], "@a.age > 10 || @a.name == \"x\"");
+        assert!(plan1.is_equivalent(&plan2).unwrap());
+        assert_eq!(plan1.fingerprint().unwrap(), plan2.fingerprint().unwrap());
+        assert!(!plan1.is_equivalent(&plan3).unwrap());
+        assert_ne!(plan1.fingerprint().unwrap(), plan3.fingerprint().unwrap());
+
+        // the branches of the union keep their order while canonicalizing the ids only
+        let mut canonical1 = plan1.clone();
+        let mut canonical2 = plan2.clone();
+        canonical1.canonicalize_node_ids().unwrap();
+        canonical2.canonicalize_node_ids().unwrap();
+        assert_ne!(pb::LogicalPlan::from(canonical1), pb::LogicalPlan::from(canonical2));
+
+        let id_map1 = plan1.normalize().unwrap();
+        let id_map2 = plan2.normalize().unwrap();
+        assert_eq!(pb::LogicalPlan::from(plan1.clone()), pb::LogicalPlan::from(plan2));
+        assert_eq!(id_map1[&1], id_map2[&2]);
+        // the normalization is idempotent
+        let plan_pb = pb::LogicalPlan::from(plan1.clone());
+        plan1.normalize().unwrap();
+        assert_eq!(pb::LogicalPlan::from(plan1), plan_pb);
+    }
+
+    #[test]
+    fn plan_diff() {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(scan.into(), vec![])
+        let expand = |label: &str| pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![label.into()], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let plan = |labels: Vec<&str>| {
+            let mut plan = LogicalPlan::default();
+            plan.append_operator_as_node(scan.clone().into(), vec![])
+                .unwrap();
+            for (idx, label) in labels.into_iter().enumerate() {
+                plan.append_operator_as_node(expand(label).into(), vec![idx as NodeId])
+                    .unwrap();
+            }
+            plan
+        };
+
+        let plan1 = plan(vec!["knows", "likes"]);
+        let mut plan2 = plan(vec!["knows", "likes"]);
+        assert!(plan1.structurally_equals(&plan2));
+        // the annotations are left out
+        plan2
+            .annotate_node(1, "note".to_string())
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
+        assert!(plan1.structurally_equals(&plan2));
+
+        let plan3 = plan(vec!["knows", "created", "likes"]);
+        assert!(!plan1.structurally_equals(&plan3));
+        let diffs = plan1.diff(&plan3);
+        assert_eq!(diffs.len(), 3);
+        assert!(matches!(&diffs[0], PlanDiff::OperatorChanged { node: 2, .. }));
+        assert_eq!(diffs[1], PlanDiff::EdgeAdded(2, 3));
+        assert_eq!(diffs[2], PlanDiff::NodeAdded(3));
+        assert_eq!(diffs[2].to_string(), "+ node #3");
+        assert_eq!(plan3.diff(&plan1)[1..], [PlanDiff::EdgeRemoved(2, 3), PlanDiff::NodeRemoved(3)]);
+    }
 
-        let group = pb::GroupBy {
-            mappings: vec![pb::group_by::KeyAlias {
-                key: Some(common_pb::Variable {
-                    tag: None,
-                    property: Some(common_pb::Property {
-                        item: Some(common_pb::property::Item::Key("name".into())),
-                    }),
-                    node_type: None,
-                }),
-                alias: Some("a".into()),
-            }],
-            functions: vec![pb::group_by::AggFunc {
-                vars: vec![],
-                aggregate: 5,
-                alias: Some("~values_0_1".into()),
-            }],
-            meta_data: vec![],
+    #[test]
+    fn stable_fingerprint() {
+        use std::hash::Hasher;
+
+        let fnv = |bytes: &[u8]| {
+            let mut hasher = StableHasher::default();
+            hasher.write(bytes);
+            hasher.finish()
         };
-        plan.append_operator_as_node(group.into(), vec![0])
-            .unwrap();
-        let keys_tag_id = plan.meta.get_tag_id("a").unwrap();
-        assert_eq!(plan.meta.get_tag_nodes(keys_tag_id), &vec![1]);
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-        assert_eq!(
-            plan.meta
-                .get_nodes_meta(&[0])
-                .unwrap()
-                .get_columns(),
-            vec!["name".into()]
-        );
+        assert_eq!(fnv(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv(b"foobar"), 0x85944171f73967e8);
 
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@a".to_string()).ok(),
-                alias: None,
-            }],
-            is_append: true,
-            meta_data: vec![],
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(project.into(), vec![1])
-            .unwrap();
+        let expand = |label: &str| pb::EdgeExpand {
+            v_tag: Some("a".into()),
+            direction: 0,
+            params: Some(query_params(vec![label.into()], vec![])),
+            expand_opt: 0,
+            alias: Some("c".into()),
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let intersect = pb::Intersect { parents: vec![1, 2], key: Some("c".into()) };
+        let plan = |labels: [&str; 2]| {
+            let mut plan = LogicalPlan::default();
+            plan.append_operator_as_node(scan.clone().into(), vec![])
+                .unwrap();
+            plan.append_operator_as_node(expand(labels[0]).into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(expand(labels[1]).into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(intersect.clone().into(), vec![1, 2])
+                .unwrap();
+            plan
+        };
+
+        // the branches of an intersection are free to go in any order
+        let plan1 = plan(["knows", "likes"]);
+        let plan2 = plan(["likes", "knows"]);
+        assert!(!plan1.structurally_equals(&plan2));
+        assert!(plan1.is_equivalent(&plan2).unwrap());
+        assert_eq!(plan1.fingerprint().unwrap(), plan2.fingerprint().unwrap());
+        // the plans are normalized into copies, and thus left unchanged
+        assert!(plan1.structurally_equals(&plan(["knows", "likes"])));
+        assert!(plan2.structurally_equals(&plan(["likes", "knows"])));
     }
 
     #[test]
-    fn column_maintain_groupby_case4() {
-        let mut plan = LogicalPlan::default();
-        // g.V().group().by(outE().count()).by('name')
-        // g.V()
+    fn write_through_appends() {
         let scan = pb::Scan {
             scan_opt: 0,
-            alias: None,
+            alias: Some("a".into()),
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(scan.into(), vec![])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-
-        // by(outE().count())
         let expand = pb::EdgeExpand {
             v_tag: None,
             direction: 0,
             params: Some(query_params(vec![], vec![])),
-            expand_opt: 1,
+            expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
-        let subtask = plan
-            .append_operator_as_node(expand.into(), vec![])
-            .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![subtask]);
-
-        let group = pb::GroupBy {
-            mappings: vec![],
-            functions: vec![pb::group_by::AggFunc {
-                vars: vec![],
-                aggregate: 3,
-                alias: Some("~values_0_1".into()),
-            }],
-            meta_data: vec![],
-        };
-        plan.append_operator_as_node(group.into(), vec![subtask])
-            .unwrap();
-        // the tag "~values_0_1" maps to id 0
-        assert_eq!(plan.meta.get_tag_nodes(0), &vec![2]); // tag self
-
-        let apply = pb::Apply {
-            join_kind: 0, // join
-            tags: vec![],
-            subtask: subtask as PbNodeId,
-            alias: Some("~apply".into()),
-        };
-        plan.append_operator_as_node(apply.into(), vec![0])
-            .unwrap();
-        // do not change the head of current node, given that alias is given
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-        // the tag "~apply" maps to id 1
-        assert_eq!(plan.meta.get_tag_nodes(1), &vec![3]);
+        let changes: Arc<Mutex<Vec<pb::PlanAppend>>> = Arc::new(Mutex::new(vec![]));
+        let changes_clone = changes.clone();
 
-        let group = pb::GroupBy {
-            mappings: vec![pb::group_by::KeyAlias {
-                key: Some(common_pb::Variable {
-                    tag: Some("~apply".into()),
-                    property: None,
-                    node_type: None,
-                }),
-                alias: Some("~apply".into()),
-            }],
-            functions: vec![pb::group_by::AggFunc {
-                vars: vec![common_pb::Variable {
-                    tag: None,
-                    property: Some(common_pb::Property {
-                        item: Some(common_pb::property::Item::Key("name".into())),
-                    }),
-                    node_type: None,
-                }],
-                aggregate: 5,
-                alias: Some("~values_0_1".into()),
-            }],
-            meta_data: vec![],
-        };
-        plan.append_operator_as_node(group.into(), vec![3])
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
-        assert_eq!(
-            plan.meta
-                .get_node_meta(0)
+        // the existing node is written at once
+        plan.set_write_through(Some(Box::new(move |change: &pb::PlanAppend| {
+            changes_clone
+                .lock()
                 .unwrap()
-                .get_columns(),
-            vec!["name".into()]
-        )
+                .push(change.clone())
+        })));
+        assert_eq!(changes.lock().unwrap().len(), 1);
+        plan.append_operator_as_node(expand.clone().into(), vec![0])
+            .unwrap();
+        // a failed append without any node appended writes nothing
+        assert!(plan
+            .append_operator_as_node(expand.clone().into(), vec![5])
+            .is_err());
+        // the clones do not write through
+        plan.clone()
+            .append_operator_as_node(expand.clone().into(), vec![1])
+            .unwrap();
+        {
+            let changes = changes.lock().unwrap();
+            assert_eq!(changes.len(), 2);
+            let ids: Vec<Vec<i32>> = changes
+                .iter()
+                .map(|change| {
+                    change
+                        .nodes
+                        .iter()
+                        .map(|node| node.id)
+                        .collect()
+                })
+                .collect();
+            assert_eq!(ids, vec![vec![0], vec![1]]);
+            assert_eq!(changes[1].nodes[0].parents, vec![0]);
+            assert_eq!(
+                changes[1].nodes[0]
+                    .node
+                    .as_ref()
+                    .and_then(|node| node.opr.clone()),
+                plan.get_opr(1)
+            );
+        }
+
+        plan.set_write_through(None);
+        plan.append_operator_as_node(expand.into(), vec![1])
+            .unwrap();
+        assert_eq!(changes.lock().unwrap().len(), 2);
     }
 
     #[test]
-    fn column_maintain_orderby() {
-        let mut plan = LogicalPlan::default();
-        // g.E(xx).values("workFrom").as("a").order().by(select("a"))
+    fn clone_plan() {
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 0,
+            alias: Some("b".into()),
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let limit = pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false };
 
         let scan = pb::Scan {
-            scan_opt: 1,
-            alias: None,
+            scan_opt: 0,
+            alias: Some("a".into()),
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(scan.into(), vec![])
+
+        let mut base = LogicalPlan::default();
+        base.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        base.append_operator_as_node(expand.clone().into(), vec![0])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![0]);
 
-        let project = pb::Project {
-            mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("@.workFrom".to_string()).ok(),
-                alias: Some("a".into()),
-            }],
-            is_append: true,
-            meta_data: vec![],
-        };
-        plan.append_operator_as_node(project.into(), vec![0])
+        let mut plan = base.clone_plan();
+        assert!(plan.structurally_equals(&base));
+        plan.append_operator_as_node(limit.into(), vec![1])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
-        assert!(plan
-            .meta
-            .get_node_meta(0)
+        let mut other = base.clone_plan();
+        other
+            .append_operator_as_node(expand.into(), vec![1])
+            .unwrap();
+
+        // the copies are extended independently, and leave the base intact
+        assert_eq!(base.len(), 2);
+        assert!(base
+            .get_node(1)
             .unwrap()
-            .get_columns()
-            .contains(&"workFrom".into()));
+            .borrow()
+            .children
+            .is_empty());
+        assert_eq!(plan.len(), 3);
+        assert!(matches!(plan.get_opr(2).unwrap().opr, Some(Opr::Limit(_))));
+        assert_eq!(other.len(), 3);
+        assert!(matches!(other.get_opr(2).unwrap().opr, Some(Opr::Edge(_))));
+        assert_eq!(
+            other
+                .get_node(1)
+                .unwrap()
+                .borrow()
+                .children
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+    }
 
-        let order = pb::OrderBy {
-            pairs: vec![pb::order_by::OrderingPair {
-                key: Some(common_pb::Variable { tag: Some("a".into()), property: None, node_type: None }),
-                order: 0,
-            }],
-            limit: None,
+    #[test]
+    fn freeze_plan() {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
-        plan.append_operator_as_node(order.into(), vec![1])
+        let limit = pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false };
+
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![1]);
+        plan.freeze();
+        assert!(plan.is_frozen());
+        assert!(matches!(
+            plan.append_operator_as_node(limit.clone().into(), vec![0]),
+            Err(IrError::PlanFrozen)
+        ));
+        // the frozen plan can still be serialized
+        assert_eq!(plan.len(), 1);
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        assert_eq!(plan_pb.nodes.len(), 1);
+
+        // while its copy is not frozen
+        let mut copy = plan.clone_plan();
+        assert!(!copy.is_frozen());
+        copy.append_operator_as_node(limit.into(), vec![0])
+            .unwrap();
+        assert_eq!(copy.len(), 2);
+        assert_eq!(plan.len(), 1);
     }
 
     #[test]
-    fn column_maintain_union() {
+    fn getv_endpoints() {
         let mut plan = LogicalPlan::default();
-        // g.V().union(out().has("age", Gt(10)), out().out()).as('a').select('a').by(valueMap('name', 'age'))
-        // g.V()
+        // g.V().outE(), with both vertices of the edge as "a" and "b"
         let scan = pb::Scan {
             scan_opt: 0,
             alias: None,
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 1,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let mut getv = pb::GetV {
+            tag: None,
+            opt: pb::get_v::VOpt::Endpoints as i32,
+            params: Some(query_params(vec![], vec![])),
+            alias: Some("a".into()),
+            meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         plan.append_operator_as_node(scan.into(), vec![])
             .unwrap();
+        plan.append_operator_as_node(expand.into(), vec![0])
+            .unwrap();
+        // the end vertex must be aliased
+        assert!(matches!(
+            plan.append_operator_as_node(getv.clone().into(), vec![1]),
+            Err(IrError::MissingData(_))
+        ));
 
-        let expand1 = pb::EdgeExpand {
+        getv.end_alias = Some("b".into());
+        let id = plan
+            .append_operator_as_node(getv.into(), vec![1])
+            .unwrap();
+        let a_id = plan.meta.get_tag_id("a").unwrap();
+        let b_id = plan.meta.get_tag_id("b").unwrap();
+        assert_ne!(a_id, b_id);
+        assert_eq!(plan.meta.get_tag_nodes(a_id), &vec![id]);
+        assert_eq!(plan.meta.get_tag_nodes(b_id), &vec![id]);
+    }
+
+    #[test]
+    fn extract_subplan_between() {
+        let scan = |alias: &str| pb::Scan {
+            scan_opt: 0,
+            alias: Some(alias.into()),
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let expand = pb::EdgeExpand {
             v_tag: None,
             direction: 0,
             params: Some(query_params(vec![], vec![])),
             expand_opt: 0,
-            alias: None,
+            alias: Some("b".into()),
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
-        let filter = pb::Select { predicate: Some(str_to_expr_pb("@.age > 10".to_string()).unwrap()) };
+        let limit = pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false };
+        let times = pb::Join { left_keys: vec![], right_keys: vec![], kind: 6, renames: vec![] };
 
-        let expand2 = expand1.clone();
-        let expand3 = expand1.clone();
-        let id1 = plan
-            .append_operator_as_node(expand1.into(), vec![0])
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan("a").into(), vec![])
             .unwrap();
-        let id1_f = plan
-            .append_operator_as_node(filter.into(), vec![id1])
+        plan.append_operator_as_node(expand.clone().into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(expand.clone().into(), vec![1])
+            .unwrap();
+        plan.append_operator_as_node(limit.clone().into(), vec![2])
             .unwrap();
 
-        let opr_id = plan
-            .append_operator_as_node(expand2.into(), vec![0])
+        let subplan = plan.extract_subplan_between(1, 2).unwrap();
+        assert_eq!(subplan.len(), 2);
+        assert!(matches!(subplan.get_opr(0).unwrap().opr, Some(Opr::Edge(_))));
+        assert!(subplan
+            .get_node(0)
+            .unwrap()
+            .borrow()
+            .parents
+            .is_empty());
+        assert_eq!(subplan.get_node(1).unwrap().borrow().parents, vec![0].into_iter().collect());
+        // the tag "b" is the first tag of the subplan
+        assert_eq!(subplan.meta.get_tag_id_mappings().get("b"), Some(&0));
+        assert_eq!(
+            plan.extract_subplan_between(3, 3)
+                .unwrap()
+                .len(),
+            1
+        );
+        match plan.extract_subplan_between(2, 1) {
+            Err(IrError::InvalidRange(2, 1)) => {}
+            _ => panic!("node 1 is not downstream of node 2"),
+        }
+
+        // the join has a branch out of the fragment
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan("a").into(), vec![])
             .unwrap();
-        let id2 = plan
-            .append_operator_as_node(expand3.into(), vec![opr_id])
+        plan.append_operator_as_node(scan("c").into(), vec![])
             .unwrap();
-        let union = pb::Union { parents: vec![id1_f as PbNodeId, id2 as PbNodeId] };
-        plan.append_operator_as_node(union.into(), vec![id1_f, id2])
+        plan.append_operator_as_node(times.into(), vec![0, 1])
             .unwrap();
-        assert_eq!(plan.meta.get_curr_referred_nodes(), &vec![id1, id2]);
-
-        let as_opr = pb::As { alias: Some("a".into()) };
-        let opr_id = plan
-            .append_operator_as_node(as_opr.into(), vec![id1, id2])
+        plan.append_operator_as_node(limit.into(), vec![2])
             .unwrap();
-        let a_id = plan.meta.get_tag_id("a").unwrap();
-        assert_eq!(plan.meta.get_tag_nodes(a_id), &vec![id1, id2]);
+        match plan.extract_subplan_between(0, 3) {
+            Err(IrError::ParentNodeNotExist(1)) => {}
+            _ => panic!("the join has a parent out of the fragment"),
+        }
+    }
 
+    #[test]
+    fn trace_node_accesses() {
+        let var = |tag: Option<&str>, key: &str| common_pb::Variable {
+            tag: tag.map(|tag| tag.into()),
+            property: Some(common_pb::Property { item: Some(common_pb::property::Item::Key(key.into())) }),
+            node_type: None,
+        };
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let select = pb::Select {
+            predicate: str_to_expr_pb(
+                "@a.name == \"John\" && @.age > 10 && @a.name != \"Josh\"".to_string(),
+            )
+            .ok(),
+        };
         let project = pb::Project {
             mappings: vec![pb::project::ExprAlias {
-                expr: str_to_expr_pb("{@a.name, @a.age}".to_string()).ok(),
+                expr: str_to_expr_pb("@a.name".to_string()).ok(),
                 alias: None,
             }],
             is_append: false,
             meta_data: vec![],
         };
-        plan.append_operator_as_node(project.into(), vec![opr_id])
+
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(scan.into(), vec![])
             .unwrap();
-        assert_eq!(
-            plan.meta
-                .get_node_meta(id1)
-                .unwrap()
-                .get_columns(),
-            vec!["age".into(), "name".into()]
-        );
-        assert_eq!(
-            plan.meta
-                .get_node_meta(id2)
-                .unwrap()
-                .get_columns(),
-            vec!["age".into(), "name".into()]
-        );
+        plan.append_operator_as_node(select.into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(project.into(), vec![1])
+            .unwrap();
+
+        let plan_pb = pb::LogicalPlan::from(plan.clone());
+        assert!(plan_pb
+            .nodes
+            .iter()
+            .all(|node| node.accesses.is_empty()));
+
+        plan.meta.set_trace_access(true);
+        let plan_pb = pb::LogicalPlan::from(plan);
+        assert!(plan_pb.nodes[0].accesses.is_empty());
+        // the tag is given by its name rather than its internal id, and referred once only
+        assert_eq!(plan_pb.nodes[1].accesses, vec![var(Some("a"), "name"), var(None, "age")]);
+        assert_eq!(plan_pb.nodes[2].accesses, vec![var(Some("a"), "name")]);
     }
 
     #[test]
@@ -2979,6 +12350,8 @@ mod test {
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let opr_id = plan
@@ -2993,6 +12366,11 @@ mod test {
             expand_opt: 0,
             alias: Some("o".into()),
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let root_id = plan
@@ -3004,7 +12382,14 @@ mod test {
         plan.append_operator_as_node(select.into(), vec![root_id])
             .unwrap();
 
-        let apply = pb::Apply { join_kind: 4, tags: vec![], subtask: root_id as PbNodeId, alias: None };
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: root_id as PbNodeId,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
         let opr_id = plan
             .append_operator_as_node(apply.into(), vec![opr_id])
             .unwrap();
@@ -3058,6 +12443,8 @@ mod test {
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         plan.append_operator_as_node(scan.into(), vec![])
@@ -3070,12 +12457,24 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let root_id = plan
             .append_operator_as_node(expand.into(), vec![])
             .unwrap();
 
-        let apply = pb::Apply { join_kind: 5, tags: vec![], subtask: root_id as PbNodeId, alias: None };
+        let apply = pb::Apply {
+            join_kind: 5,
+            tags: vec![],
+            subtask: root_id as PbNodeId,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
         plan.append_operator_as_node(apply.into(), vec![0])
             .unwrap();
 
@@ -3095,6 +12494,7 @@ mod test {
             sink_target: Some(pb::sink::SinkTarget {
                 inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
                     id_name_mappings: vec![],
+                    layout: 0,
                 })),
             }),
         };
@@ -3119,6 +12519,108 @@ mod test {
         }
     }
 
+    #[test]
+    fn apply_captures_check() {
+        let mut plan = LogicalPlan::default();
+        // g.V().as("a").out().as("b").where(out().as("c").where("c", eq("a")).by("name"))
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: Some("a".into()),
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        plan.append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let mut expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 0,
+            alias: Some("b".into()),
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        plan.append_operator_as_node(expand.clone().into(), vec![0])
+            .unwrap();
+
+        // the subtask refers to the outer tag "a"
+        expand.alias = Some("c".into());
+        let root_id = plan
+            .append_operator_as_node(expand.into(), vec![])
+            .unwrap();
+        let select = pb::Select { predicate: str_to_expr_pb("@c.name == @a.name".to_string()).ok() };
+        plan.append_operator_as_node(select.into(), vec![root_id])
+            .unwrap();
+
+        let apply = |captures: Vec<&str>| pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: root_id as PbNodeId,
+            alias: None,
+            timeout_ms: 0,
+            captures: captures
+                .into_iter()
+                .map(|tag| tag.into())
+                .collect(),
+        };
+
+        // "a" presents in the input, and is captured
+        let mut plan1 = plan.clone();
+        let apply_id = plan1
+            .append_operator_as_node(apply(vec!["a"]).into(), vec![1])
+            .unwrap();
+        let a_id = plan1.meta.get_tag_id("a").unwrap();
+        match plan1
+            .get_node(apply_id)
+            .unwrap()
+            .borrow()
+            .opr
+            .opr
+            .as_ref()
+        {
+            Some(pb::logical_plan::operator::Opr::Apply(apply)) => {
+                assert_eq!(apply.captures, vec![(a_id as KeyId).into()])
+            }
+            _ => panic!("should be apply"),
+        }
+
+        // "a" presents in the input, but is not captured
+        let mut plan2 = plan.clone();
+        match plan2.append_operator_as_node(apply(vec!["b"]).into(), vec![1]) {
+            Err(IrError::NodeError(_, err)) => match *err {
+                IrError::TagNotExist(tag) => assert_eq!(tag, (a_id as KeyId).into()),
+                err => panic!("unexpected error {:?}", err),
+            },
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // "c" is defined by the subtask rather than the input
+        let mut plan3 = plan.clone();
+        let c_id = plan3.meta.get_tag_id("c").unwrap();
+        match plan3.append_operator_as_node(apply(vec!["a", "c"]).into(), vec![1]) {
+            Err(IrError::TagNotExist(tag)) => assert_eq!(tag, (c_id as KeyId).into()),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // "d" has never been defined
+        let mut plan4 = plan.clone();
+        match plan4.append_operator_as_node(apply(vec!["d"]).into(), vec![1]) {
+            Err(IrError::TagNotExist(tag)) => assert_eq!(tag, "d".into()),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // nothing is checked without the captures
+        plan.append_operator_as_node(apply(vec![]).into(), vec![1])
+            .unwrap();
+    }
+
     // The plan looks like:
     //       root
     //       / \
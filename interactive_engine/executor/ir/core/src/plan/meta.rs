@@ -13,15 +13,17 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell, RefMut};
 use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BTreeSet};
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::io;
 use std::ops::Deref;
-use std::rc::Rc;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+use ir_common::generated::algebra as pb;
+use ir_common::generated::common as common_pb;
 use ir_common::generated::schema as schema_pb;
 use ir_common::{KeyId, OneOrMany};
 use ir_common::{LabelId, NameOrId};
@@ -37,6 +39,48 @@ lazy_static! {
     pub static ref STORE_META: RwLock<StoreMeta> = RwLock::new(StoreMeta::default());
 }
 
+/// A `RefCell` of the shared parts of a plan, e.g., its nodes, which can be sent to another thread
+/// along with the plan, e.g., a plan that is built concurrently (see `init_concurrent_logical_plan()`).
+/// A conflicting borrow thus still panics, rather than waiting for itself, and a node is accessed
+/// without any lock, as the plan as a whole is locked by its handle instead.
+#[derive(Default)]
+pub struct SyncCell<T> {
+    inner: RefCell<T>,
+}
+
+// SAFETY: the cells of a plan are shared only by the plan itself and its shallow clones, which never
+// outlive the api that makes them, while the copies kept by another plan or a session are deep ones
+// (see `LogicalPlan::clone_plan()`). The plan, in turn, is referred by a single thread at a time via
+// the lock of its handle (see `handle::borrow()`), such that no two threads ever access a cell at once.
+unsafe impl<T: Send> Send for SyncCell<T> {}
+unsafe impl<T: Send> Sync for SyncCell<T> {}
+
+impl<T> SyncCell<T> {
+    pub fn new(value: T) -> Self {
+        Self { inner: RefCell::new(value) }
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.inner.borrow()
+    }
+
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.inner.borrow_mut()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SyncCell<T> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.borrow() == *other.borrow()
+    }
+}
+
+impl<T: Debug> Debug for SyncCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.borrow().fmt(f)
+    }
+}
+
 pub fn set_schema_from_json<R: io::Read>(read: R) {
     if let Ok(mut meta) = STORE_META.write() {
         if let Ok(schema) = Schema::from_json(read) {
@@ -60,6 +104,9 @@ pub fn reset_schema() {
 #[derive(Clone, Debug, Default)]
 pub struct StoreMeta {
     pub schema: Option<Schema>,
+    /// The version of the metadata, which is bumped once the schema is reloaded (see
+    /// `Session::reload_schema`), and tells apart the plans built against the different schemas
+    pub version: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -115,6 +162,63 @@ impl Default for KeyType {
     }
 }
 
+/// The number of bits of a global id
+const ID_BITS: u32 = 64;
+
+/// The encoding of the global ids of the vertices in the store, as `label << offset_bits | inner_id`,
+/// where the label takes the highest `label_bits` bits of the id, and the inner id (the offset of
+/// the vertex in its label) takes the remaining `offset_bits` bits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IdEncoding {
+    label_bits: u32,
+}
+
+impl Default for IdEncoding {
+    /// The encoding of the experimental store, where the label takes the highest 8 bits.
+    fn default() -> Self {
+        IdEncoding { label_bits: 8 }
+    }
+}
+
+impl IdEncoding {
+    /// The encoding where the label takes the highest `label_bits` bits, which must be in `[1, 63]`.
+    pub fn new(label_bits: u32) -> Option<Self> {
+        if (1..ID_BITS).contains(&label_bits) {
+            Some(IdEncoding { label_bits })
+        } else {
+            None
+        }
+    }
+
+    pub fn label_bits(&self) -> u32 {
+        self.label_bits
+    }
+
+    pub fn offset_bits(&self) -> u32 {
+        ID_BITS - self.label_bits
+    }
+
+    /// Encode the global id of the vertex of the `label` and the `inner_id`, or none if either of
+    /// them exceeds its bits.
+    pub fn encode(&self, label: LabelId, inner_id: i64) -> Option<i64> {
+        if label < 0 || (label as u64) >> self.label_bits != 0 {
+            return None;
+        }
+        if inner_id < 0 || (inner_id as u64) >> self.offset_bits() != 0 {
+            return None;
+        }
+        Some((((label as u64) << self.offset_bits()) | inner_id as u64) as i64)
+    }
+
+    /// Decode the global id into the label and the inner id of the vertex.
+    pub fn decode(&self, global_id: i64) -> (LabelId, i64) {
+        let global_id = global_id as u64;
+        let label = global_id >> self.offset_bits();
+        let inner_id = global_id & ((1_u64 << self.offset_bits()) - 1);
+        (label as LabelId, inner_id as i64)
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Schema {
     /// A map from table (Entity or Relation) name to its internally encoded id
@@ -137,6 +241,14 @@ pub struct Schema {
     entities: Vec<schema_pb::EntityMeta>,
     /// Relations
     relations: Vec<schema_pb::RelationMeta>,
+    /// The encoding of the global ids, if the store exposes it
+    id_encoding: Option<IdEncoding>,
+    /// The number of the entities of each label, as the statistics exposed by the store
+    entity_counts: BTreeMap<LabelId, u64>,
+    /// The number of the relations of each label, as the statistics exposed by the store
+    relation_counts: BTreeMap<LabelId, u64>,
+    /// The partitions that hold the entities of each label, as declared by the store
+    label_partitions: BTreeMap<LabelId, Vec<u32>>,
 }
 
 impl Schema {
@@ -214,6 +326,71 @@ impl Schema {
         self.is_table_id
     }
 
+    pub fn with_id_encoding(mut self, id_encoding: IdEncoding) -> Self {
+        self.id_encoding = Some(id_encoding);
+        self
+    }
+
+    pub fn get_id_encoding(&self) -> Option<&IdEncoding> {
+        self.id_encoding.as_ref()
+    }
+
+    pub fn with_entity_counts(mut self, entity_counts: Vec<(LabelId, u64)>) -> Self {
+        self.entity_counts.extend(entity_counts);
+        self
+    }
+
+    /// Get the number of the entities of the given label, if it is given by the statistics
+    pub fn get_entity_count(&self, label: LabelId) -> Option<u64> {
+        self.entity_counts.get(&label).cloned()
+    }
+
+    pub fn with_relation_counts(mut self, relation_counts: Vec<(LabelId, u64)>) -> Self {
+        self.relation_counts.extend(relation_counts);
+        self
+    }
+
+    /// Get the number of the relations of the given label, if it is given by the statistics
+    pub fn get_relation_count(&self, label: LabelId) -> Option<u64> {
+        self.relation_counts.get(&label).cloned()
+    }
+
+    pub fn with_label_partitions(mut self, label_partitions: Vec<(LabelId, Vec<u32>)>) -> Self {
+        self.label_partitions.extend(label_partitions);
+        self
+    }
+
+    /// Get the partitions that hold the entities of the given label, if the store declares them
+    pub fn get_label_partitions(&self, label: LabelId) -> Option<&Vec<u32>> {
+        self.label_partitions.get(&label)
+    }
+
+    /// Get the number of all the entities, if the statistics are given for each label of the entities
+    pub fn get_total_entity_count(&self) -> Option<u64> {
+        let mut labels = self
+            .table_name_to_id
+            .values()
+            .filter(|(key_type, _)| *key_type == KeyType::Entity)
+            .peekable();
+        labels.peek()?;
+        labels
+            .map(|(_, label)| self.get_entity_count(*label))
+            .sum()
+    }
+
+    /// Get the number of all the relations, if the statistics are given for each label of the relations
+    pub fn get_total_relation_count(&self) -> Option<u64> {
+        let mut labels = self
+            .table_name_to_id
+            .values()
+            .filter(|(key_type, _)| *key_type == KeyType::Relation)
+            .peekable();
+        labels.peek()?;
+        labels
+            .map(|(_, label)| self.get_relation_count(*label))
+            .sum()
+    }
+
     /// Check whether a given table contains a given column as a primary key.
     /// Also return the number of primary keys of the given table.
     pub fn check_primary_key(&self, table: &str, col: &str) -> (bool, usize) {
@@ -274,6 +451,63 @@ impl From<Schema> for schema_pb::Schema {
             relations: relations_pb,
             is_table_id: schema.is_table_id,
             is_column_id: schema.is_column_id,
+            id_encoding: schema
+                .id_encoding
+                .map(|encoding| schema_pb::IdEncoding { label_bits: encoding.label_bits }),
+            statistics: if schema.entity_counts.is_empty() && schema.relation_counts.is_empty() {
+                None
+            } else {
+                Some(schema_pb::Statistics {
+                    entity_counts: schema
+                        .entity_counts
+                        .iter()
+                        .map(|(label, count)| schema_pb::statistics::EntityCount {
+                            label: Some(schema_pb::LabelMeta {
+                                id: *label,
+                                name: schema
+                                    .get_entity_name(*label)
+                                    .cloned()
+                                    .unwrap_or_default(),
+                            }),
+                            count: *count,
+                        })
+                        .collect(),
+                    relation_counts: schema
+                        .relation_counts
+                        .iter()
+                        .map(|(label, count)| schema_pb::statistics::EntityCount {
+                            label: Some(schema_pb::LabelMeta {
+                                id: *label,
+                                name: schema
+                                    .get_relation_name(*label)
+                                    .cloned()
+                                    .unwrap_or_default(),
+                            }),
+                            count: *count,
+                        })
+                        .collect(),
+                })
+            },
+            partitioning: if schema.label_partitions.is_empty() {
+                None
+            } else {
+                Some(schema_pb::Partitioning {
+                    label_partitions: schema
+                        .label_partitions
+                        .iter()
+                        .map(|(label, partitions)| schema_pb::partitioning::LabelPartitions {
+                            label: Some(schema_pb::LabelMeta {
+                                id: *label,
+                                name: schema
+                                    .get_entity_name(*label)
+                                    .cloned()
+                                    .unwrap_or_default(),
+                            }),
+                            partitions: partitions.clone(),
+                        })
+                        .collect(),
+                })
+            },
         }
     }
 }
@@ -285,6 +519,34 @@ impl From<schema_pb::Schema> for Schema {
         schema.relations = schema_pb.relations.clone();
         schema.is_table_id = schema_pb.is_table_id;
         schema.is_column_id = schema_pb.is_column_id;
+        schema.id_encoding = schema_pb
+            .id_encoding
+            .and_then(|encoding| IdEncoding::new(encoding.label_bits));
+        if let Some(statistics) = &schema_pb.statistics {
+            for entity_count in &statistics.entity_counts {
+                if let Some(label) = &entity_count.label {
+                    schema
+                        .entity_counts
+                        .insert(label.id, entity_count.count);
+                }
+            }
+            for relation_count in &statistics.relation_counts {
+                if let Some(label) = &relation_count.label {
+                    schema
+                        .relation_counts
+                        .insert(label.id, relation_count.count);
+                }
+            }
+        }
+        if let Some(partitioning) = &schema_pb.partitioning {
+            for label_partitions in &partitioning.label_partitions {
+                if let Some(label) = &label_partitions.label {
+                    schema
+                        .label_partitions
+                        .insert(label.id, label_partitions.partitions.clone());
+                }
+            }
+        }
         for entity in schema_pb.entities {
             if schema_pb.is_table_id {
                 if let Some(label) = &entity.label {
@@ -510,25 +772,25 @@ impl NodeMeta {
 }
 
 pub struct NodeMetaOpt {
-    inner: OneOrMany<Rc<RefCell<NodeMeta>>>,
+    inner: OneOrMany<Arc<SyncCell<NodeMeta>>>,
 }
 
-impl AsRef<OneOrMany<Rc<RefCell<NodeMeta>>>> for NodeMetaOpt {
-    fn as_ref(&self) -> &OneOrMany<Rc<RefCell<NodeMeta>>> {
+impl AsRef<OneOrMany<Arc<SyncCell<NodeMeta>>>> for NodeMetaOpt {
+    fn as_ref(&self) -> &OneOrMany<Arc<SyncCell<NodeMeta>>> {
         &self.inner
     }
 }
 
 impl Deref for NodeMetaOpt {
-    type Target = OneOrMany<Rc<RefCell<NodeMeta>>>;
+    type Target = OneOrMany<Arc<SyncCell<NodeMeta>>>;
 
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
 }
 
-impl From<OneOrMany<Rc<RefCell<NodeMeta>>>> for NodeMetaOpt {
-    fn from(inner: OneOrMany<Rc<RefCell<NodeMeta>>>) -> Self {
+impl From<OneOrMany<Arc<SyncCell<NodeMeta>>>> for NodeMetaOpt {
+    fn from(inner: OneOrMany<Arc<SyncCell<NodeMeta>>>) -> Self {
         Self { inner }
     }
 }
@@ -625,10 +887,34 @@ impl NodeMetaOpt {
     }
 }
 
+/// The stage of the optimizations that have been applied to a plan. The cheap optimizations, e.g.,
+/// eliding the redundant `OrderBy` and reordering the terms of the predicates, always apply, while
+/// the expensive ones, namely, ordering the extensions of a pattern and pushing the aggregations down
+/// below the joins, apply only within the time budget of the plan, if any, as they may take seconds for
+/// large machine-generated plans.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OptimizationStage {
+    /// Only the cheap optimizations apply to some operators, as the budget was used up
+    Cheap,
+    /// All the enabled optimizations apply
+    Full,
+}
+
+impl fmt::Display for OptimizationStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptimizationStage::Cheap => write!(f, "cheap"),
+            OptimizationStage::Full => write!(f, "full"),
+        }
+    }
+}
+
 /// To record any metadata while processing the logical plan, including:
 /// * The tables/columns required by a given node
 /// * The tag-node mutual mappings
 /// * The tag-id mappings, if preprocessing tag to id
+/// * The ordering of the output of the nodes
+/// * The tags present in the output of the nodes
 /// * TODO etc.
 #[derive(Default, Clone, Debug)]
 pub struct PlanMeta {
@@ -639,7 +925,7 @@ pub struct PlanMeta {
     /// information is critical in distributed processing, as the computation may not align
     /// with the storage to access the required column. Thus, such information can help
     /// the computation route and fetch columns.
-    node_metas: BTreeMap<NodeId, Rc<RefCell<NodeMeta>>>,
+    node_metas: BTreeMap<NodeId, Arc<SyncCell<NodeMeta>>>,
     /// Refer a node to the node that points to the head of the plan.
     /// A head of the plan is often determined by an operator that changes the head of the
     /// `Record`. Such operators include Scan, EdgeExpand, PathExpand, GetV, Project, etc.
@@ -655,6 +941,56 @@ pub struct PlanMeta {
     max_tag_id: TagId,
     /// Whether to partition the task
     is_partition: bool,
+    /// Whether to fetch the properties referred by `Project`, `OrderBy` and `GroupBy` in bulk, namely,
+    /// by a single `Auxilia` per tag, rather than lazily fetching them one by one
+    is_bulk_fetch: bool,
+    /// How the integer overflow of `Sum` and `Avg` is dealt with, which applies to all the aggregate
+    /// functions of the plan that do not specify their own
+    agg_overflow: pb::group_by::agg_func::Overflow,
+    /// The maximal estimated complexity of the plan that is accepted, if any,
+    /// see [`crate::plan::logical::LogicalPlan::check_complexity`]
+    complexity_threshold: Option<f64>,
+    /// Whether to record the tags and properties referred by each node in the serialized plan,
+    /// see [`crate::plan::logical::LogicalPlan::get_node_accesses`]
+    is_trace_access: bool,
+    /// The maximal number of hops of a `PathExpand` that is accepted, if any
+    max_hops: Option<i32>,
+    /// Whether to reorder the terms of the predicates by their estimated costs,
+    /// see [`crate::plan::normalize::reorder_predicate`]
+    is_reorder_predicates: bool,
+    /// Whether to push the counting and summing aggregations down below the inner joins,
+    /// see [`crate::plan::logical::LogicalPlan::push_down_aggregation`]
+    is_push_down_aggregation: bool,
+    /// Whether to project the inputs of the joins onto the keys and the tags needed after the joins,
+    /// see [`crate::plan::logical::LogicalPlan::get_join_key_projections`]
+    is_project_join_keys: bool,
+    /// Whether to inline the union of scans that differ only by their labels or constants into a single
+    /// scan, see [`crate::plan::logical::LogicalPlan::inline_union_scans`]
+    is_inline_union_scans: bool,
+    /// The time budget of the expensive optimizations, if any, see [`OptimizationStage`]
+    optimization_budget: Option<Duration>,
+    /// The time that has been spent on the expensive optimizations
+    optimization_elapsed: Duration,
+    /// Whether some expensive optimization has been skipped as the budget is used up
+    is_optimization_skipped: bool,
+    /// The metadata of the store, e.g., the schema, that the plan is built against, as given by the
+    /// session of the plan, or the global [`STORE_META`] if not given
+    store_meta: Option<Arc<StoreMeta>>,
+    /// The ordering of the output of the nodes, which is established by an `OrderBy`, and kept
+    /// by the order-preserving operators that follow it. An absent node outputs in no order.
+    node_orderings: BTreeMap<NodeId, Vec<pb::order_by::OrderingPair>>,
+    /// The tags that present in the output of the nodes, which are introduced by the aliases of the
+    /// operators, and carried over by the operators that follow unless the record is replaced.
+    node_tags: BTreeMap<NodeId, BTreeSet<TagId>>,
+    /// The annotations of the nodes made while preprocessing them, e.g., the decisions of the optimizations,
+    /// which are attached to the nodes once they are appended to the plan
+    node_annotations: BTreeMap<NodeId, Vec<String>>,
+    /// The data types of the values referred by the tags, which are known for the values computed by
+    /// the plan, e.g., the results of the aggregate functions, rather than the graph elements.
+    tag_types: BTreeMap<TagId, common_pb::DataType>,
+    /// Whether the operators being built are in the subtask of an `Apply`, which runs in a sub-scope
+    /// per input record, such that an operator is shared among the sub-scopes.
+    is_in_subtask: bool,
 }
 
 // Some constructors
@@ -666,15 +1002,54 @@ impl PlanMeta {
         plan_meta
     }
 
+    /// Clone the metadata, where the metadata of the nodes are copied rather than shared as by `clone()`
+    pub fn deep_clone(&self) -> Self {
+        let mut plan_meta = self.clone();
+        plan_meta.node_metas = self
+            .node_metas
+            .iter()
+            .map(|(id, meta)| (*id, Arc::new(SyncCell::new(meta.borrow().clone()))))
+            .collect();
+        plan_meta
+    }
+
     pub fn with_partition(mut self) -> Self {
         self.is_partition = true;
         self
     }
+
+    pub fn with_bulk_fetch(mut self) -> Self {
+        self.is_bulk_fetch = true;
+        self
+    }
+
+    /// A new plan meta that keeps the settings of the given one, e.g., whether to partition the task,
+    /// but none of its nodes and tags, which is meant to rebuild the plan from scratch.
+    pub fn with_settings_of(other: &PlanMeta) -> Self {
+        PlanMeta {
+            is_partition: other.is_partition,
+            is_bulk_fetch: other.is_bulk_fetch,
+            agg_overflow: other.agg_overflow,
+            complexity_threshold: other.complexity_threshold,
+            is_trace_access: other.is_trace_access,
+            max_hops: other.max_hops,
+            is_reorder_predicates: other.is_reorder_predicates,
+            is_push_down_aggregation: other.is_push_down_aggregation,
+            is_project_join_keys: other.is_project_join_keys,
+            is_inline_union_scans: other.is_inline_union_scans,
+            optimization_budget: other.optimization_budget,
+            store_meta: other.store_meta.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 impl PlanMeta {
+    /// Refer the tag to the nodes, which also clears the data type of the tag, if any, as the tag
+    /// may now refer to some other values, e.g., the graph elements.
     pub fn set_tag_nodes(&mut self, tag: TagId, nodes: Vec<NodeId>) {
         *self.tag_nodes.entry(tag).or_default() = nodes;
+        self.tag_types.remove(&tag);
     }
 
     pub fn get_tag_nodes(&self, tag: TagId) -> &[NodeId] {
@@ -844,4 +1219,188 @@ impl PlanMeta {
     pub fn is_partition(&self) -> bool {
         self.is_partition
     }
+
+    pub fn annotate_node(&mut self, node: NodeId, annotation: String) {
+        debug!("node {:?}: {}", node, annotation);
+        self.node_annotations
+            .entry(node)
+            .or_default()
+            .push(annotation);
+    }
+
+    /// Take the annotations made for the given node while preprocessing it
+    pub fn take_node_annotations(&mut self, node: NodeId) -> Vec<String> {
+        self.node_annotations
+            .remove(&node)
+            .unwrap_or_default()
+    }
+
+    pub fn set_bulk_fetch(&mut self, is_bulk_fetch: bool) {
+        self.is_bulk_fetch = is_bulk_fetch;
+    }
+
+    pub fn is_bulk_fetch(&self) -> bool {
+        self.is_bulk_fetch
+    }
+
+    pub fn set_agg_overflow(&mut self, overflow: pb::group_by::agg_func::Overflow) {
+        self.agg_overflow = overflow;
+    }
+
+    pub fn get_agg_overflow(&self) -> pb::group_by::agg_func::Overflow {
+        self.agg_overflow
+    }
+
+    pub fn set_complexity_threshold(&mut self, threshold: Option<f64>) {
+        self.complexity_threshold = threshold;
+    }
+
+    pub fn get_complexity_threshold(&self) -> Option<f64> {
+        self.complexity_threshold
+    }
+
+    pub fn set_trace_access(&mut self, is_trace_access: bool) {
+        self.is_trace_access = is_trace_access;
+    }
+
+    pub fn is_trace_access(&self) -> bool {
+        self.is_trace_access
+    }
+
+    pub fn set_max_hops(&mut self, max_hops: Option<i32>) {
+        self.max_hops = max_hops;
+    }
+
+    pub fn get_max_hops(&self) -> Option<i32> {
+        self.max_hops
+    }
+
+    pub fn set_in_subtask(&mut self, is_in_subtask: bool) {
+        self.is_in_subtask = is_in_subtask;
+    }
+
+    pub fn is_in_subtask(&self) -> bool {
+        self.is_in_subtask
+    }
+
+    pub fn set_reorder_predicates(&mut self, is_reorder_predicates: bool) {
+        self.is_reorder_predicates = is_reorder_predicates;
+    }
+
+    pub fn is_reorder_predicates(&self) -> bool {
+        self.is_reorder_predicates
+    }
+
+    pub fn set_push_down_aggregation(&mut self, is_push_down_aggregation: bool) {
+        self.is_push_down_aggregation = is_push_down_aggregation;
+    }
+
+    pub fn is_push_down_aggregation(&self) -> bool {
+        self.is_push_down_aggregation
+    }
+
+    pub fn set_project_join_keys(&mut self, is_project_join_keys: bool) {
+        self.is_project_join_keys = is_project_join_keys;
+    }
+
+    pub fn is_project_join_keys(&self) -> bool {
+        self.is_project_join_keys
+    }
+
+    pub fn set_inline_union_scans(&mut self, is_inline_union_scans: bool) {
+        self.is_inline_union_scans = is_inline_union_scans;
+    }
+
+    pub fn is_inline_union_scans(&self) -> bool {
+        self.is_inline_union_scans
+    }
+
+    pub fn set_optimization_budget(&mut self, budget: Option<Duration>) {
+        self.optimization_budget = budget;
+    }
+
+    pub fn get_optimization_budget(&self) -> Option<Duration> {
+        self.optimization_budget
+    }
+
+    /// Whether an expensive optimization may apply, namely, the budget, if any, is not used up yet.
+    /// Otherwise, the optimization is recorded to be skipped.
+    pub fn admit_expensive_optimization(&mut self) -> bool {
+        match self.optimization_budget {
+            Some(budget) if self.optimization_elapsed >= budget => {
+                self.is_optimization_skipped = true;
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Charge the time spent on an expensive optimization to the budget
+    pub fn charge_optimization_time(&mut self, elapsed: Duration) {
+        self.optimization_elapsed += elapsed;
+    }
+
+    pub fn get_optimization_stage(&self) -> OptimizationStage {
+        if self.is_optimization_skipped {
+            OptimizationStage::Cheap
+        } else {
+            OptimizationStage::Full
+        }
+    }
+
+    pub fn set_store_meta(&mut self, store_meta: Option<Arc<StoreMeta>>) {
+        self.store_meta = store_meta;
+    }
+
+    pub fn get_store_meta(&self) -> Option<Arc<StoreMeta>> {
+        self.store_meta.clone()
+    }
+
+    /// Run `f` on the metadata of the store that the plan is built against, namely, the one given by
+    /// the session of the plan, or the global [`STORE_META`] otherwise, where `f` is not run if the
+    /// latter can not be read.
+    pub fn with_store_meta<T, F: FnOnce(&StoreMeta) -> T>(&self, f: F) -> Option<T> {
+        match &self.store_meta {
+            Some(store_meta) => Some(f(store_meta)),
+            None => STORE_META
+                .read()
+                .ok()
+                .map(|store_meta| f(&store_meta)),
+        }
+    }
+
+    pub fn set_node_ordering(&mut self, node: NodeId, ordering: Vec<pb::order_by::OrderingPair>) {
+        self.node_orderings.insert(node, ordering);
+    }
+
+    /// Get the ordering of the output of the given node, if it outputs in order
+    pub fn get_node_ordering(&self, node: NodeId) -> Option<&[pb::order_by::OrderingPair]> {
+        self.node_orderings
+            .get(&node)
+            .map(|ordering| ordering.as_slice())
+    }
+
+    pub fn set_node_tags(&mut self, node: NodeId, tags: BTreeSet<TagId>) {
+        self.node_tags.insert(node, tags);
+    }
+
+    /// Get the tags that present in the output of the given node
+    pub fn get_node_tags(&self, node: NodeId) -> BTreeSet<TagId> {
+        self.node_tags
+            .get(&node)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Set the data type of the values referred by the tag, which must be set after referring
+    /// the tag to its nodes via [`Self::set_tag_nodes`].
+    pub fn set_tag_type(&mut self, tag: TagId, data_type: common_pb::DataType) {
+        self.tag_types.insert(tag, data_type);
+    }
+
+    /// Get the data type of the values referred by the tag, which is `None` if the tag refers
+    /// to the graph elements, or the data type is unknown.
+    pub fn get_tag_type(&self, tag: TagId) -> Option<common_pb::DataType> {
+        self.tag_types.get(&tag).cloned()
+    }
 }
@@ -13,8 +13,12 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+pub mod bundle;
+pub mod eval;
 pub mod ffi;
 pub mod logical;
 pub mod meta;
+pub mod normalize;
 pub mod patmat;
 pub mod physical;
+pub mod upgrade;
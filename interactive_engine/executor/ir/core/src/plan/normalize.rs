@@ -0,0 +1,567 @@
+//
+//! Copyright 2023 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The normalization of the operators of a logical plan, which rewrites the semantically identical
+//! operators produced by different frontends into the same form, e.g., `a.age > 10 && a.name == "x"`
+//! and `"x" == a.name && 10 < a.age`, such that the normalized plans can be fingerprinted and
+//! compared (see [`LogicalPlan::normalize`]). Besides, the predicates can be reordered by the
+//! estimated costs of their terms (see [`reorder_predicate`]).
+//!
+//! [`LogicalPlan::normalize`]: crate::plan::logical::LogicalPlan::normalize
+
+use std::collections::BTreeMap;
+
+use ir_common::expr_parse::error::ExprError;
+use ir_common::expr_parse::to_suffix_expr;
+use ir_common::generated::algebra as pb;
+use ir_common::generated::common as common_pb;
+use prost::Message;
+
+use crate::error::{IrError, IrResult};
+use crate::plan::meta::TagId;
+
+/// An expression parsed as a tree of its operators.
+enum ExprTree {
+    Leaf(common_pb::ExprOpr),
+    Unary(common_pb::ExprOpr, Box<ExprTree>),
+    Binary(common_pb::ExprOpr, Box<ExprTree>, Box<ExprTree>),
+}
+
+fn get_logical(opr: &common_pb::ExprOpr) -> Option<common_pb::Logical> {
+    match opr.item {
+        Some(common_pb::expr_opr::Item::Logical(logical)) => common_pb::Logical::from_i32(logical),
+        _ => None,
+    }
+}
+
+impl ExprTree {
+    fn parse(expr: &common_pb::Expression) -> IrResult<ExprTree> {
+        use common_pb::expr_opr::Item;
+
+        let invalid =
+            || IrError::ParseExprError(ExprError::OtherErr(format!("invalid expression {:?}", expr)));
+        let mut stack: Vec<ExprTree> = vec![];
+        let pop = |stack: &mut Vec<ExprTree>| stack.pop().ok_or_else(invalid);
+        for opr in to_suffix_expr(expr.operators.clone())? {
+            let tree = match &opr.item {
+                Some(Item::Logical(_)) if get_logical(&opr) == Some(common_pb::Logical::Not) => {
+                    ExprTree::Unary(opr, Box::new(pop(&mut stack)?))
+                }
+                Some(Item::Logical(_)) | Some(Item::Arith(_)) => {
+                    let right = pop(&mut stack)?;
+                    let left = pop(&mut stack)?;
+                    ExprTree::Binary(opr, Box::new(left), Box::new(right))
+                }
+                Some(Item::Brace(_)) | None => return Err(invalid()),
+                _ => ExprTree::Leaf(opr),
+            };
+            stack.push(tree);
+        }
+        let tree = pop(&mut stack)?;
+        if stack.is_empty() {
+            Ok(tree)
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// Write the tree as infix operators, where the operands that are not leaves are braced.
+    fn write_infix(&self, operators: &mut Vec<common_pb::ExprOpr>) {
+        fn write_operand(tree: &ExprTree, operators: &mut Vec<common_pb::ExprOpr>) {
+            if let ExprTree::Leaf(opr) = tree {
+                operators.push(opr.clone());
+            } else {
+                operators.push(common_pb::ExprOpr {
+                    item: Some(common_pb::expr_opr::Item::Brace(0)),
+                    node_type: None,
+                });
+                tree.write_infix(operators);
+                operators.push(common_pb::ExprOpr {
+                    item: Some(common_pb::expr_opr::Item::Brace(1)),
+                    node_type: None,
+                });
+            }
+        }
+
+        match self {
+            ExprTree::Leaf(opr) => operators.push(opr.clone()),
+            ExprTree::Unary(opr, operand) => {
+                operators.push(opr.clone());
+                write_operand(operand, operators);
+            }
+            ExprTree::Binary(opr, left, right) => {
+                write_operand(left, operators);
+                operators.push(opr.clone());
+                write_operand(right, operators);
+            }
+        }
+    }
+
+    /// The key to order the operands of a commutative operator, which refers to the tags by their
+    /// names, as the ids of the tags are assigned in the order of appending the operators. The
+    /// constants go after the others, e.g., `a.age > 10` rather than `10 < a.age`.
+    fn sort_key(&self, tag_names: &BTreeMap<TagId, String>) -> (bool, Vec<u8>) {
+        let mut operators = vec![];
+        self.write_infix(&mut operators);
+        for opr in operators.iter_mut() {
+            match opr.item.as_mut() {
+                Some(common_pb::expr_opr::Item::Var(var)) => name_var_tag(var, tag_names),
+                Some(common_pb::expr_opr::Item::Vars(vars))
+                | Some(common_pb::expr_opr::Item::VarMap(vars)) => {
+                    for var in vars.keys.iter_mut() {
+                        name_var_tag(var, tag_names);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let is_const = match self {
+            ExprTree::Leaf(opr) => matches!(opr.item, Some(common_pb::expr_opr::Item::Const(_))),
+            _ => false,
+        };
+
+        (is_const, common_pb::Expression { operators }.encode_to_vec())
+    }
+
+    fn normalize(self, tag_names: &BTreeMap<TagId, String>) -> ExprTree {
+        use common_pb::Logical;
+
+        match self {
+            ExprTree::Leaf(_) => self,
+            ExprTree::Unary(opr, operand) => ExprTree::Unary(opr, Box::new(operand.normalize(tag_names))),
+            ExprTree::Binary(opr, left, right) => match get_logical(&opr) {
+                Some(Logical::And) | Some(Logical::Or) => {
+                    // flatten the chain of the same operator, e.g., `(a && b) && c`, and order its operands
+                    let mut operands = vec![];
+                    flatten(*left, &opr, &mut operands);
+                    flatten(*right, &opr, &mut operands);
+                    let mut operands: Vec<((bool, Vec<u8>), ExprTree)> = operands
+                        .into_iter()
+                        .map(|operand| {
+                            let operand = operand.normalize(tag_names);
+                            (operand.sort_key(tag_names), operand)
+                        })
+                        .collect();
+                    operands.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+                    let mut operands = operands.into_iter().map(|(_, operand)| operand);
+                    let first = operands.next().unwrap();
+                    operands.fold(first, |left, right| {
+                        ExprTree::Binary(opr.clone(), Box::new(left), Box::new(right))
+                    })
+                }
+                Some(logical) => {
+                    let (left, right) = (left.normalize(tag_names), right.normalize(tag_names));
+                    let mirrored = match logical {
+                        Logical::Eq | Logical::Ne => Some(logical),
+                        Logical::Lt => Some(Logical::Gt),
+                        Logical::Le => Some(Logical::Ge),
+                        Logical::Gt => Some(Logical::Lt),
+                        Logical::Ge => Some(Logical::Le),
+                        _ => None,
+                    };
+                    match mirrored {
+                        Some(mirrored) if right.sort_key(tag_names) < left.sort_key(tag_names) => {
+                            let opr = common_pb::ExprOpr {
+                                item: Some(common_pb::expr_opr::Item::Logical(mirrored as i32)),
+                                node_type: opr.node_type,
+                            };
+                            ExprTree::Binary(opr, Box::new(right), Box::new(left))
+                        }
+                        _ => ExprTree::Binary(opr, Box::new(left), Box::new(right)),
+                    }
+                }
+                // the arithmetic operators are left as they are, e.g., `+` also concatenates the strings
+                None => ExprTree::Binary(
+                    opr,
+                    Box::new(left.normalize(tag_names)),
+                    Box::new(right.normalize(tag_names)),
+                ),
+            },
+        }
+    }
+}
+
+fn flatten(tree: ExprTree, opr: &common_pb::ExprOpr, operands: &mut Vec<ExprTree>) {
+    match tree {
+        ExprTree::Binary(this, left, right) if this.item == opr.item => {
+            flatten(*left, opr, operands);
+            flatten(*right, opr, operands);
+        }
+        _ => operands.push(tree),
+    }
+}
+
+fn name_var_tag(var: &mut common_pb::Variable, tag_names: &BTreeMap<TagId, String>) {
+    if let Some(common_pb::name_or_id::Item::Id(id)) = var
+        .tag
+        .as_ref()
+        .and_then(|tag| tag.item.as_ref())
+    {
+        if let Some(name) = tag_names.get(&(*id as TagId)) {
+            var.tag =
+                Some(common_pb::NameOrId { item: Some(common_pb::name_or_id::Item::Name(name.clone())) });
+        }
+    }
+}
+
+/// Normalize the expression, in which the operands of `&&` and `||` (including those of a chain,
+/// e.g., `a && b && c`), and of `==` and `!=` are ordered, and the comparisons are mirrored
+/// accordingly, e.g., `10 < a.age` is normalized as `a.age > 10`. The operands that are not leaves
+/// are braced in the normalized expression. The tags of the variables are ordered by their names
+/// in `tag_names`, if any.
+pub fn normalize_expression(
+    expr: &common_pb::Expression, tag_names: &BTreeMap<TagId, String>,
+) -> IrResult<common_pb::Expression> {
+    if expr.operators.is_empty() {
+        return Ok(expr.clone());
+    }
+    let mut operators = Vec::with_capacity(expr.operators.len());
+    ExprTree::parse(expr)?
+        .normalize(tag_names)
+        .write_infix(&mut operators);
+
+    Ok(common_pb::Expression { operators })
+}
+
+/// The estimated cost of evaluating a leaf of an expression, where reading a property of an entity
+/// costs more than its label or id, which are carried by the entity itself.
+fn get_leaf_cost(opr: &common_pb::ExprOpr) -> f64 {
+    use common_pb::expr_opr::Item;
+    use common_pb::property::Item as Key;
+
+    match &opr.item {
+        Some(Item::Const(_)) | Some(Item::Param(_)) => 0.0,
+        Some(Item::Var(var)) => match var
+            .property
+            .as_ref()
+            .and_then(|property| property.item.as_ref())
+        {
+            None | Some(Key::Id(_)) | Some(Key::Label(_)) => 1.0,
+            Some(Key::Len(_)) => 2.0,
+            Some(Key::Key(_)) => 4.0,
+            _ => 8.0,
+        },
+        _ => 8.0,
+    }
+}
+
+/// The estimated selectivity of a `within`, by the number of the candidates if they are constants.
+fn get_within_selectivity(candidates: &ExprTree) -> f64 {
+    use common_pb::value::Item;
+
+    let len = match candidates {
+        ExprTree::Leaf(common_pb::ExprOpr {
+            item: Some(common_pb::expr_opr::Item::Const(common_pb::Value { item: Some(value) })),
+            ..
+        }) => match value {
+            Item::I32Array(array) => Some(array.item.len()),
+            Item::I64Array(array) => Some(array.item.len()),
+            Item::F64Array(array) => Some(array.item.len()),
+            Item::StrArray(array) => Some(array.item.len()),
+            _ => None,
+        },
+        _ => None,
+    };
+    len.map(|len| (0.1 * len as f64).min(0.9))
+        .unwrap_or(0.3)
+}
+
+impl ExprTree {
+    /// Estimate the cost of evaluating the expression, and its selectivity as a predicate, i.e., the
+    /// ratio of the records that satisfy it, where `&&` and `||` are evaluated in short circuit.
+    fn estimate(&self) -> (f64, f64) {
+        use common_pb::Logical;
+
+        match self {
+            ExprTree::Leaf(opr) => (get_leaf_cost(opr), 0.5),
+            ExprTree::Unary(_, operand) => {
+                let (cost, selectivity) = operand.estimate();
+                (cost, 1.0 - selectivity)
+            }
+            ExprTree::Binary(opr, left, right) => {
+                let (left_cost, left_selectivity) = left.estimate();
+                let (right_cost, right_selectivity) = right.estimate();
+                match get_logical(opr) {
+                    Some(Logical::And) => {
+                        (left_cost + left_selectivity * right_cost, left_selectivity * right_selectivity)
+                    }
+                    Some(Logical::Or) => (
+                        left_cost + (1.0 - left_selectivity) * right_cost,
+                        left_selectivity + right_selectivity - left_selectivity * right_selectivity,
+                    ),
+                    Some(logical) => {
+                        let (cost, selectivity) = match logical {
+                            Logical::Eq => (1.0, 0.1),
+                            Logical::Ne => (1.0, 0.9),
+                            Logical::Within => (2.0, get_within_selectivity(right)),
+                            Logical::Without => (2.0, 1.0 - get_within_selectivity(right)),
+                            Logical::Startswith | Logical::Endswith => (3.0, 0.25),
+                            _ => (1.0, 1.0 / 3.0),
+                        };
+                        (left_cost + right_cost + cost, selectivity)
+                    }
+                    None => (left_cost + right_cost + 1.0, 0.5),
+                }
+            }
+        }
+    }
+
+    /// Reorder the operands of the chains of `&&` and `||` by their estimated costs and selectivities,
+    /// such that the cheap operands that likely short-circuit the chain go first. The order of the
+    /// operands of the top-level `&&`, if any, is recorded in `order`, and `changed` is set if any
+    /// chain is reordered.
+    fn reorder(self, order: &mut Vec<usize>, changed: &mut bool) -> ExprTree {
+        use common_pb::Logical;
+
+        match self {
+            ExprTree::Leaf(_) => self,
+            ExprTree::Unary(opr, operand) => {
+                ExprTree::Unary(opr, Box::new(operand.reorder(&mut vec![], changed)))
+            }
+            ExprTree::Binary(opr, left, right) => match get_logical(&opr) {
+                Some(logical @ Logical::And) | Some(logical @ Logical::Or) => {
+                    let mut operands = vec![];
+                    flatten(*left, &opr, &mut operands);
+                    flatten(*right, &opr, &mut operands);
+                    let mut operands: Vec<(usize, f64, ExprTree)> = operands
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, operand)| {
+                            let operand = operand.reorder(&mut vec![], changed);
+                            let (cost, selectivity) = operand.estimate();
+                            // the expected cost of an operand per record that it short-circuits, namely,
+                            // that fails an `&&`, or satisfies an `||`
+                            let short_circuited =
+                                if logical == Logical::And { 1.0 - selectivity } else { selectivity };
+                            let rank =
+                                if short_circuited > 0.0 { cost / short_circuited } else { f64::MAX };
+                            (index, rank, operand)
+                        })
+                        .collect();
+                    // the sort is stable, which keeps the operands of the same rank as they are
+                    operands.sort_by(|(_, rank1, _), (_, rank2, _)| {
+                        rank1
+                            .partial_cmp(rank2)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    });
+                    let indices: Vec<usize> = operands
+                        .iter()
+                        .map(|(index, _, _)| *index)
+                        .collect();
+                    if indices
+                        .iter()
+                        .enumerate()
+                        .any(|(i, index)| i != *index)
+                    {
+                        *changed = true;
+                    }
+                    if logical == Logical::And {
+                        *order = indices;
+                    }
+                    let mut operands = operands
+                        .into_iter()
+                        .map(|(_, _, operand)| operand);
+                    let first = operands.next().unwrap();
+                    operands.fold(first, |left, right| {
+                        ExprTree::Binary(opr.clone(), Box::new(left), Box::new(right))
+                    })
+                }
+                _ => ExprTree::Binary(
+                    opr,
+                    Box::new(left.reorder(&mut vec![], changed)),
+                    Box::new(right.reorder(&mut vec![], changed)),
+                ),
+            },
+        }
+    }
+}
+
+/// Reorder the operands of `&&` (including those of a chain, e.g., `a && b && c`) and `||` of the
+/// predicate by their estimated costs and selectivities, e.g., a cheap check of the label goes before
+/// a `startsWith` over a property, such that the filter short-circuits as early as possible. The
+/// predicate is rewritten (with the operands that are not leaves braced) only if it is reordered, in
+/// which case the order of the operands of its top-level `&&`, if any, is returned, e.g., `[1, 0]`
+/// if the second operand goes first.
+pub fn reorder_predicate(expr: &mut common_pb::Expression) -> IrResult<Option<Vec<usize>>> {
+    if expr.operators.is_empty() {
+        return Ok(None);
+    }
+    let (mut order, mut changed) = (vec![], false);
+    let tree = ExprTree::parse(expr)?.reorder(&mut order, &mut changed);
+    if !changed {
+        return Ok(None);
+    }
+    let mut operators = Vec::with_capacity(expr.operators.len());
+    tree.write_infix(&mut operators);
+    expr.operators = operators;
+
+    Ok(Some(order))
+}
+
+/// Normalize an optional range of a size limit, where a range that does not limit anything, namely,
+/// `[0, i32::MAX)`, is normalized as no range. The invalid ranges are left to be reported while
+/// building the physical plan.
+pub fn normalize_limit(range: &mut Option<pb::Range>) {
+    if matches!(range, Some(pb::Range { lower: 0, upper: i32::MAX })) {
+        *range = None;
+    }
+}
+
+fn normalize_params(params: &mut pb::QueryParams, tag_names: &BTreeMap<TagId, String>) -> IrResult<()> {
+    if let Some(predicate) = params.predicate.as_mut() {
+        *predicate = normalize_expression(predicate, tag_names)?;
+    }
+    normalize_limit(&mut params.limit);
+    Ok(())
+}
+
+/// Normalize the predicates and the ranges of the operator, see [`normalize_expression`] and
+/// [`normalize_limit`]. Besides, the parents of a `Union` are ordered by their ids.
+pub fn normalize_operator(
+    opr: &mut pb::logical_plan::Operator, tag_names: &BTreeMap<TagId, String>,
+) -> IrResult<()> {
+    use pb::logical_plan::operator::Opr;
+
+    match opr.opr.as_mut() {
+        Some(Opr::Select(select)) => {
+            if let Some(predicate) = select.predicate.as_mut() {
+                *predicate = normalize_expression(predicate, tag_names)?;
+            }
+        }
+        Some(Opr::Scan(scan)) => {
+            if let Some(params) = scan.params.as_mut() {
+                normalize_params(params, tag_names)?;
+            }
+        }
+        Some(Opr::Edge(edgexpd)) => {
+            if let Some(params) = edgexpd.params.as_mut() {
+                normalize_params(params, tag_names)?;
+            }
+        }
+        Some(Opr::Vertex(getv)) => {
+            if let Some(params) = getv.params.as_mut() {
+                normalize_params(params, tag_names)?;
+            }
+        }
+        Some(Opr::Path(pathxpd)) => {
+            if let Some(condition) = pathxpd.condition.as_mut() {
+                *condition = normalize_expression(condition, tag_names)?;
+            }
+        }
+        Some(Opr::OrderBy(order)) => normalize_limit(&mut order.limit),
+        Some(Opr::Union(union)) => union.parents.sort_unstable(),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ir_common::expr_parse::str_to_expr_pb;
+
+    use super::*;
+
+    fn normalize(expr: &str) -> common_pb::Expression {
+        normalize_expression(&str_to_expr_pb(expr.to_string()).unwrap(), &BTreeMap::new()).unwrap()
+    }
+
+    #[test]
+    fn normalize_commutative_operands() {
+        assert_eq!(
+            normalize("@a.age > 10 && @a.name == \"x\""),
+            normalize("\"x\" == @a.name && 10 < @a.age")
+        );
+        assert_eq!(normalize("@a.age <= 10"), normalize("10 >= @a.age"));
+        // the chains are flattened regardless of the braces
+        assert_eq!(
+            normalize("(@.x == 1 || @.y == 2) || @.z == 3"),
+            normalize("@.z == 3 || (@.y == 2 || @.x == 1)")
+        );
+        assert_ne!(normalize("@.x == 1 && @.y == 2"), normalize("@.x == 1 || @.y == 2"));
+        // the arithmetic operators are left as they are
+        assert_ne!(normalize("@.x + 1 == 2"), normalize("1 + @.x == 2"));
+        assert_eq!(normalize("@.x + 1 == 2"), normalize("2 == @.x + 1"));
+        assert_eq!(normalize("!(@.x == 1)"), normalize("!(1 == @.x)"));
+        // the normalization is idempotent
+        let normalized = normalize("10 < @a.age && @a.name == \"x\" || @.z");
+        assert_eq!(normalize_expression(&normalized, &BTreeMap::new()).unwrap(), normalized);
+    }
+
+    #[test]
+    fn normalize_by_tag_names() {
+        let tag_names = |names: &[&str]| -> BTreeMap<TagId, String> {
+            names
+                .iter()
+                .enumerate()
+                .map(|(id, name)| (id as TagId, name.to_string()))
+                .collect()
+        };
+        // the tag `a` is of id 0 in one plan, and of id 1 in the other
+        let normalize_ids = |expr: &str, names: &[&str]| {
+            let normalized =
+                normalize_expression(&str_to_expr_pb(expr.to_string()).unwrap(), &tag_names(names))
+                    .unwrap();
+            normalized
+                .operators
+                .iter()
+                .filter_map(|opr| match &opr.item {
+                    Some(common_pb::expr_opr::Item::Var(var)) => var.tag.clone(),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(normalize_ids("@0.x == 1 && @1.x == 1", &["a", "b"]), vec![0.into(), 1.into()]);
+        assert_eq!(normalize_ids("@0.x == 1 && @1.x == 1", &["b", "a"]), vec![1.into(), 0.into()]);
+    }
+
+    #[test]
+    fn normalize_limits() {
+        let mut range = Some(pb::Range { lower: 0, upper: i32::MAX });
+        normalize_limit(&mut range);
+        assert!(range.is_none());
+        let mut range = Some(pb::Range { lower: 0, upper: 10 });
+        normalize_limit(&mut range);
+        assert_eq!(range, Some(pb::Range { lower: 0, upper: 10 }));
+    }
+
+    #[test]
+    fn reorder_predicate_terms() {
+        let reorder = |expr: &str| {
+            let mut expr = str_to_expr_pb(expr.to_string()).unwrap();
+            let order = reorder_predicate(&mut expr).unwrap();
+            (expr, order)
+        };
+        let expr = |expr: &str| str_to_expr_pb(expr.to_string()).unwrap();
+
+        // a check of the label goes before a string match over a property
+        assert_eq!(
+            reorder("@.name StartsWith \"J\" && @.~label == 1 && @.age > 10"),
+            (expr("((@.~label == 1) && (@.age > 10)) && (@.name StartsWith \"J\")"), Some(vec![1, 2, 0]))
+        );
+        // the more selective goes first among the terms of the same cost
+        assert_eq!(
+            reorder("@.age > 10 && @.name == \"John\""),
+            (expr("(@.name == \"John\") && (@.age > 10)"), Some(vec![1, 0]))
+        );
+        // the terms that are likely satisfied go first in `||`
+        assert_eq!(
+            reorder("@.age > 10 || @.name != \"John\""),
+            (expr("(@.name != \"John\") || (@.age > 10)"), Some(vec![]))
+        );
+        // kept as it is if it is already in order
+        assert_eq!(reorder("@.~label == 1 && @.age > 10"), (expr("@.~label == 1 && @.age > 10"), None));
+    }
+}
@@ -15,7 +15,7 @@
 //!
 
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, VecDeque};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::rc::Rc;
@@ -30,6 +30,7 @@ use crate::error::{IrError, IrResult};
 use crate::glogue::error::IrPatternResult;
 use crate::glogue::pattern::Pattern;
 use crate::plan::meta::PlanMeta;
+use crate::plan::upgrade::PLAN_FORMAT_VERSION;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd)]
 #[repr(i32)]
@@ -202,7 +203,18 @@ impl TryFrom<pb::pattern::Sentence> for BaseSentence {
 
 impl MatchingStrategy for BaseSentence {
     fn build_logical_plan(&self) -> IrResult<pb::LogicalPlan> {
-        let mut plan = pb::LogicalPlan { nodes: vec![], roots: vec![0] };
+        let mut plan = pb::LogicalPlan {
+            nodes: vec![],
+            roots: vec![0],
+            annotations: vec![],
+            allow_partial: false,
+            super_node_policy: None,
+            format_version: PLAN_FORMAT_VERSION,
+            origin: None,
+            continuous: None,
+            read_scope: None,
+            view: None,
+        };
         let size = self.operators.len();
         if size == 0 {
             Err(IrError::InvalidPattern("empty sentence".to_string()))
@@ -213,6 +225,10 @@ impl MatchingStrategy for BaseSentence {
                     // pb::NameOrId -> NameOrId never fails.
                     opr: Some(pb::As { alias: self.start_tag.clone().try_into().ok() }.into()),
                     children: vec![1],
+                    annotations: vec![],
+                    display_name: "".to_string(),
+                    accesses: vec![],
+                    attributes: HashMap::new(),
                 });
                 child_offset += 1;
             }
@@ -221,12 +237,33 @@ impl MatchingStrategy for BaseSentence {
                 let child_id = idx as i32 + child_offset;
                 let node = if idx != size - 1 {
                     // A sentence is definitely a chain
-                    pb::logical_plan::Node { opr: Some(opr.clone()), children: vec![child_id] }
+                    pb::logical_plan::Node {
+                        opr: Some(opr.clone()),
+                        children: vec![child_id],
+                        annotations: vec![],
+                        display_name: "".to_string(),
+                        accesses: vec![],
+                        attributes: HashMap::new(),
+                    }
                 } else {
                     if self.end_tag.is_some() {
-                        pb::logical_plan::Node { opr: Some(opr.clone()), children: vec![child_id] }
+                        pb::logical_plan::Node {
+                            opr: Some(opr.clone()),
+                            children: vec![child_id],
+                            annotations: vec![],
+                            display_name: "".to_string(),
+                            accesses: vec![],
+                            attributes: HashMap::new(),
+                        }
                     } else {
-                        pb::logical_plan::Node { opr: Some(opr.clone()), children: vec![] }
+                        pb::logical_plan::Node {
+                            opr: Some(opr.clone()),
+                            children: vec![],
+                            annotations: vec![],
+                            display_name: "".to_string(),
+                            accesses: vec![],
+                            attributes: HashMap::new(),
+                        }
                     }
                 };
                 plan.nodes.push(node);
@@ -236,6 +273,10 @@ impl MatchingStrategy for BaseSentence {
                     // pb::NameOrId -> NameOrId never fails.
                     opr: Some(pb::As { alias: end_tag.try_into().ok() }.into()),
                     children: vec![],
+                    annotations: vec![],
+                    display_name: "".to_string(),
+                    accesses: vec![],
+                    attributes: HashMap::new(),
                 });
             }
 
@@ -635,6 +676,10 @@ fn preprocess_plan(plan: &mut pb::LogicalPlan) -> IrResult<()> {
         let new_root = pb::logical_plan::Node {
             opr: Some(pb::As { alias: None }.into()),
             children: plan.roots.iter().map(|id| *id + 1).collect(),
+            annotations: vec![],
+            display_name: "".to_string(),
+            accesses: vec![],
+            attributes: HashMap::new(),
         };
         let mut i = plan.nodes.len();
         plan.nodes.push(plan.nodes[i - 1].clone());
@@ -679,6 +724,10 @@ impl MatchingStrategy for CompoSentence {
                         })),
                     }),
                     children: vec![],
+                    annotations: vec![],
+                    display_name: "".to_string(),
+                    accesses: vec![],
+                    attributes: HashMap::new(),
                 };
                 if let Some(n) = plan.nodes.get_mut(last_node as usize) {
                     n.children.push(last_node as i32 + 1);
@@ -893,10 +942,15 @@ impl MatchingStrategy for JoinSentence {
                         left_keys: keys.clone(),
                         right_keys: keys,
                         kind: unsafe { std::mem::transmute(self.join_kind) },
+                        renames: vec![],
                     }
                     .into(),
                 ),
                 children: vec![],
+                annotations: vec![],
+                display_name: "".to_string(),
+                accesses: vec![],
+                attributes: HashMap::new(),
             });
         }
 
@@ -1227,6 +1281,7 @@ mod test {
             predicate: None,
             sample_ratio: 1.0,
             extra: HashMap::new(),
+            degree_predicates: vec![],
         }
     }
 
@@ -1242,6 +1297,11 @@ mod test {
                     alias: None,
                     expand_opt: if is_edge { 1 } else { 0 },
                     meta_data: None,
+                    dedup_both: false,
+                    exclude_self_loops: false,
+                    collapse_parallel_edges: false,
+                    vertex_tables: vec![],
+                    is_optional: false,
                 })),
             }],
             end: y.and_then(|s| s.try_into().ok()),
@@ -1265,6 +1325,11 @@ mod test {
                     expand_opt: 0,
                     alias: None,
                     meta_data: None,
+                    dedup_both: false,
+                    exclude_self_loops: false,
+                    collapse_parallel_edges: false,
+                    vertex_tables: vec![],
+                    is_optional: false,
                 })),
             }],
             end: y.and_then(|s| s.try_into().ok()),
@@ -1285,6 +1350,8 @@ mod test {
                     params: Some(query_params()),
                     alias: None,
                     meta_data: None,
+                    is_optional: false,
+                    end_alias: None,
                 })),
             }],
             end: y.and_then(|s| s.try_into().ok()),
@@ -1420,7 +1487,8 @@ mod test {
                     common_pb::Variable { tag: Some("a".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("b".into()), property: None, node_type: None }
                 ],
-                kind: 0
+                kind: 0,
+                renames: vec![],
             }
             .into()
         );
@@ -1493,7 +1561,8 @@ mod test {
                     common_pb::Variable { tag: Some("b".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("c".into()), property: None, node_type: None }
                 ],
-                kind: 0
+                kind: 0,
+                renames: vec![],
             }
             .into()
         );
@@ -1533,7 +1602,8 @@ mod test {
                     common_pb::Variable { tag: Some("a".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("c".into()), property: None, node_type: None }
                 ],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1567,7 +1637,12 @@ mod test {
                 params: Some(query_params()),
                 expand_opt: 0,
                 alias: None,
-                meta_data: None
+                meta_data: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges: false,
+                vertex_tables: vec![],
+                is_optional: false,
             }
             .into()
         );
@@ -1584,7 +1659,8 @@ mod test {
                     common_pb::Variable { tag: Some("a".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("d".into()), property: None, node_type: None },
                 ],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1601,7 +1677,8 @@ mod test {
                     common_pb::Variable { tag: Some("b".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("d".into()), property: None, node_type: None }
                 ],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1640,7 +1717,8 @@ mod test {
                     common_pb::Variable { tag: Some("a".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("c".into()), property: None, node_type: None },
                 ],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1657,7 +1735,8 @@ mod test {
                     common_pb::Variable { tag: Some("b".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("d".into()), property: None, node_type: None }
                 ],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1688,7 +1767,8 @@ mod test {
                     common_pb::Variable { tag: Some("a".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("b".into()), property: None, node_type: None },
                 ],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1721,7 +1801,8 @@ mod test {
                     common_pb::Variable { tag: Some("a".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("b".into()), property: None, node_type: None },
                 ],
-                kind: 5 // anti join
+                kind: 5, // anti join
+                renames: vec![],
             }
             .into()
         );
@@ -1750,7 +1831,12 @@ mod test {
                 params: Some(query_params()),
                 expand_opt: 0,
                 alias: None,
-                meta_data: None
+                meta_data: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges: false,
+                vertex_tables: vec![],
+                is_optional: false,
             }
             .into()
         );
@@ -1765,7 +1851,8 @@ mod test {
                     common_pb::Variable { tag: Some("a".into()), property: None, node_type: None },
                     common_pb::Variable { tag: Some("c".into()), property: None, node_type: None },
                 ],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1805,7 +1892,8 @@ mod test {
                     property: None,
                     node_type: None
                 },],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1825,7 +1913,8 @@ mod test {
                     property: None,
                     node_type: None
                 },],
-                kind: 0 // inner join
+                kind: 0, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -1869,7 +1958,8 @@ mod test {
                     property: None,
                     node_type: None
                 }],
-                kind: 5 // inner join
+                kind: 5, // inner join
+                renames: vec![],
             }
             .into()
         );
@@ -19,6 +19,7 @@
 //! protobuf structure.
 //!
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryInto;
 
 use ir_common::error::ParsePbError;
@@ -33,7 +34,7 @@ use ir_physical_client::physical_builder::PlanBuilder;
 use crate::error::{IrError, IrResult};
 use crate::glogue::combine_get_v_by_query_params;
 use crate::plan::logical::{LogicalPlan, NodeType};
-use crate::plan::meta::PlanMeta;
+use crate::plan::meta::{ColumnsOpt, PlanMeta, TagId};
 
 /// A trait for building physical plan (pegasus) from the logical plan
 pub trait AsPhysical {
@@ -83,44 +84,72 @@ fn post_process_vars(
                         params: None,
                         alias: tag_pb,
                         meta_data: None,
+                        is_optional: false,
+                        end_alias: None,
                     };
                     builder.get_v(auxilia);
                 }
             } else if len != 0 {
-                for (tag, columns_opt) in tag_columns.into_iter() {
-                    if columns_opt.len() > 0 {
-                        let tag_pb = tag.map(|tag_id| (tag_id as KeyId).into());
-                        builder.shuffle(tag_pb.clone());
-                        let params = pb::QueryParams {
-                            tables: vec![],
-                            columns: columns_opt
-                                .get()
-                                .into_iter()
-                                .map(|column| column.into())
-                                .collect(),
-                            is_all_columns: columns_opt.is_all(),
-                            limit: None,
-                            predicate: None,
-                            sample_ratio: 1.0,
-                            extra: Default::default(),
-                        };
-                        // opt = 4 denotes that to get vertex itself. The same as the followings.
-                        let auxilia = pb::GetV {
-                            tag: tag_pb.clone(),
-                            opt: 4,
-                            params: Some(params),
-                            alias: tag_pb.clone(),
-                            meta_data: None,
-                        };
-                        builder.get_v(auxilia);
-                    }
-                }
+                fetch_columns_by_tag(builder, tag_columns, true);
             }
         }
     }
     Ok(())
 }
 
+// Fetch properties in bulk before used in Project, Order and Group, if `plan_meta.is_bulk_fetch()`.
+// Rather than lazily fetching each property from the storage while evaluating, the properties referred by the
+// operator are grouped by tags, and the properties of each tag are fetched by a single `Auxilia` (after shuffling
+// to where the tag locates, if the storage is distributed), e.g., g.V().out().as("a").select("a").by(valueMap("name", "age"))
+// fetches both "name" and "age" of "a" at once.
+fn post_process_vars_in_bulk(builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
+    if let Some(node_meta) = plan_meta.get_curr_node_meta() {
+        let tag_columns = node_meta.get_tag_columns();
+        fetch_columns_by_tag(builder, tag_columns, plan_meta.is_partition());
+    }
+    Ok(())
+}
+
+// Add an `Auxilia` per tag to fetch and cache the columns of the tag, which is preceded by a shuffle to where
+// the tag locates if `is_partition`.
+fn fetch_columns_by_tag(
+    builder: &mut PlanBuilder, tag_columns: BTreeMap<Option<TagId>, ColumnsOpt>, is_partition: bool,
+) {
+    for (tag, columns_opt) in tag_columns.into_iter() {
+        if columns_opt.len() > 0 {
+            let tag_pb = tag.map(|tag_id| (tag_id as KeyId).into());
+            if is_partition {
+                builder.shuffle(tag_pb.clone());
+            }
+            let params = pb::QueryParams {
+                tables: vec![],
+                columns: columns_opt
+                    .get()
+                    .into_iter()
+                    .map(|column| column.into())
+                    .collect(),
+                is_all_columns: columns_opt.is_all(),
+                limit: None,
+                predicate: None,
+                sample_ratio: 1.0,
+                extra: Default::default(),
+                degree_predicates: vec![],
+            };
+            // opt = 4 denotes that to get vertex itself. The same as the followings.
+            let auxilia = pb::GetV {
+                tag: tag_pb.clone(),
+                opt: 4,
+                params: Some(params),
+                alias: tag_pb.clone(),
+                meta_data: None,
+                is_optional: false,
+                end_alias: None,
+            };
+            builder.get_v(auxilia);
+        }
+    }
+}
+
 impl AsPhysical for pb::Project {
     fn add_job_builder(&self, builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
         let mut project = self.clone();
@@ -130,7 +159,11 @@ impl AsPhysical for pb::Project {
     }
 
     fn post_process(&mut self, builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        post_process_vars(builder, plan_meta, false)?;
+        if plan_meta.is_bulk_fetch() {
+            post_process_vars_in_bulk(builder, plan_meta)?;
+        } else {
+            post_process_vars(builder, plan_meta, false)?;
+        }
         Ok(())
     }
 }
@@ -161,6 +194,7 @@ impl AsPhysical for pb::Select {
                     predicate: self.predicate.clone(),
                     sample_ratio: 1.0,
                     extra: Default::default(),
+                    degree_predicates: vec![],
                 };
                 let auxilia = pb::GetV {
                     tag: tag_pb.clone(),
@@ -168,6 +202,8 @@ impl AsPhysical for pb::Select {
                     params: Some(params),
                     alias: tag_pb,
                     meta_data: None,
+                    is_optional: false,
+                    end_alias: None,
                 };
                 builder.get_v(auxilia);
                 return Ok(());
@@ -187,13 +223,62 @@ impl AsPhysical for pb::Select {
 }
 
 impl AsPhysical for pb::Scan {
-    fn add_job_builder(&self, builder: &mut PlanBuilder, _plan_meta: &mut PlanMeta) -> IrResult<()> {
+    fn add_job_builder(&self, builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
+        // the runtime sources a relational table from a registered result only, while the external
+        // tables of the connectors are left to the engines that can read them
+        if self.scan_opt == pb::scan::ScanOpt::Table as i32 && self.named_result.is_empty() {
+            let uri = self
+                .connector
+                .as_ref()
+                .map(|connector| connector.uri.as_str())
+                .unwrap_or_default();
+            return Err(IrError::Unsupported(format!(
+                "scan the table {:?} that is not a named result in the job plan",
+                uri
+            )));
+        }
         let scan = self.clone();
-        builder.add_scan_source(scan);
+        let partitions =
+            if plan_meta.is_partition() { get_scan_partitions(self, plan_meta) } else { vec![] };
+        builder.add_partitioned_scan_source(scan, partitions);
         Ok(())
     }
 }
 
+/// Get the partitions that hold the vertices to scan, if the store declares the partitions of each of
+/// the labels to scan, so that the scan is only done by the workers accessing them rather than by all
+/// the workers. It is empty, i.e., to scan by all the workers, if the labels are not given or any of
+/// them is not declared.
+fn get_scan_partitions(scan: &pb::Scan, plan_meta: &PlanMeta) -> Vec<u32> {
+    let tables = scan
+        .params
+        .as_ref()
+        .map(|params| params.tables.as_slice())
+        .unwrap_or_default();
+    if scan.scan_opt != pb::scan::ScanOpt::Vertex as i32 || tables.is_empty() {
+        return vec![];
+    }
+    plan_meta
+        .with_store_meta(|store_meta| {
+            let schema = store_meta.schema.as_ref()?;
+            let mut partitions = BTreeSet::new();
+            for table in tables {
+                match table.item.as_ref() {
+                    Some(common_pb::name_or_id::Item::Id(label)) => partitions.extend(
+                        schema
+                            .get_label_partitions(*label)?
+                            .iter()
+                            .cloned(),
+                    ),
+                    _ => return None,
+                }
+            }
+            Some(partitions.into_iter().collect())
+        })
+        .flatten()
+        .unwrap_or_default()
+}
+
 impl AsPhysical for pb::EdgeExpand {
     fn add_job_builder(&self, builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
         let mut xpd = self.clone();
@@ -309,21 +394,20 @@ impl AsPhysical for pb::PathExpand {
 // 1. the previous op is ExpandE, and with no alias (which means that the edges won't be accessed later).
 // 2. `GetV` is GetV(Adj) (i.e., opt=Start/End/Other) without any filters or further query semantics.
 // 3. the direction should be: outE + inV = out; inE + outV = in; and bothE + otherV = both
+// 4. both are optional, or neither is, as the missing edges and the missing vertices are then alike.
 // In addition, if PathExpand + GetV, make opt of GetV to be `End`.
 fn build_and_try_fuse_get_v(builder: &mut PlanBuilder, mut get_v: pb::GetV) -> IrResult<()> {
     if get_v.opt == 4 {
         return Err(IrError::Unsupported("Try to fuse GetV with Opt=Self into ExpandE".to_string()));
     }
+    let mut vertex_tables = vec![];
     if let Some(params) = get_v.params.as_mut() {
         if params.is_queryable() {
             return Err(IrError::Unsupported("Try to fuse GetV with predicates into ExpandE".to_string()));
-        } else if !params.tables.is_empty() {
-            // although this doesn't need query, it cannot be fused into ExpandExpand since we cannot specify vertex labels in ExpandV
-            builder.get_v(get_v);
-            return Ok(());
         }
+        vertex_tables = params.tables.clone();
     }
-    // Try to fuse: ExpandE + GetV(Adj) = ExpandV
+    // Try to fuse: ExpandE + GetV(Adj) = ExpandV, where the labels of GetV are pushed down to ExpandV
     if let Some(last_op) = builder.get_last_op_mut() {
         let op_kind = last_op
             .opr
@@ -333,7 +417,11 @@ fn build_and_try_fuse_get_v(builder: &mut PlanBuilder, mut get_v: pb::GetV) -> I
             .as_mut()
             .ok_or(IrError::MissingData(format!("PhysicalOpr OpKind")))?;
         if let physical_pb::physical_opr::operator::OpKind::Edge(ref mut edge) = op_kind {
-            if edge.alias.is_none() {
+            // the labels cannot be fused if the adjacent vertices of ExpandE have already been constrained
+            if edge.alias.is_none()
+                && edge.is_optional == get_v.is_optional
+                && (vertex_tables.is_empty() || edge.vertex_tables.is_empty())
+            {
                 // outE + inV || inE + outV || bothE + otherV
                 if (edge.direction == 0 && get_v.opt == 1)
                     || (edge.direction == 1 && get_v.opt == 0)
@@ -343,12 +431,17 @@ fn build_and_try_fuse_get_v(builder: &mut PlanBuilder, mut get_v: pb::GetV) -> I
                         .alias
                         .map(|alias| alias.try_into().unwrap());
                     edge.expand_opt = 0; // expandV
+                    if !vertex_tables.is_empty() {
+                        edge.vertex_tables = vertex_tables;
+                    }
                     return Ok(());
                 }
             }
         } else if let physical_pb::physical_opr::operator::OpKind::Path(ref _path) = op_kind {
-            // make opt of getV after path expand as End.
-            get_v.opt = unsafe { std::mem::transmute(physical_pb::get_v::VOpt::End) };
+            if vertex_tables.is_empty() {
+                // make opt of getV after path expand as End.
+                get_v.opt = unsafe { std::mem::transmute(physical_pb::get_v::VOpt::End) };
+            }
         }
     }
     builder.get_v(get_v);
@@ -370,17 +463,26 @@ impl AsPhysical for pb::GetV {
         // If GetV(Adj) with filter, translate GetV into GetV(GetAdj) + Shuffle (if on distributed storage) + GetV(Self)
         if let Some(params) = getv.params.as_mut() {
             if params.is_queryable() {
+                if getv.opt == pb::get_v::VOpt::Endpoints as i32 {
+                    // both vertices must be filtered at once, which can not be done by GetV(Self)
+                    return Err(IrError::Unsupported(
+                        "filtering the vertices in GetV(Endpoints)".to_string(),
+                    ));
+                }
                 let auxilia = pb::GetV {
                     tag: None,
                     opt: 4, //ItSelf
                     params: Some(params.clone()),
                     alias: getv.alias,
                     meta_data: None,
+                    is_optional: getv.is_optional,
+                    end_alias: None,
                 };
                 params.tables.clear();
                 params.predicate.take();
                 params.is_all_columns = false;
                 params.columns.clear();
+                params.degree_predicates.clear();
                 getv.alias = None;
                 // GetV(Adj) and try to fuse it with ExpandE
                 build_and_try_fuse_get_v(builder, getv)?;
@@ -414,7 +516,7 @@ impl AsPhysical for pb::As {
 }
 
 impl AsPhysical for pb::Limit {
-    fn add_job_builder(&self, builder: &mut PlanBuilder, _plan_meta: &mut PlanMeta) -> IrResult<()> {
+    fn add_job_builder(&self, builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
         let range = self
             .range
             .as_ref()
@@ -422,7 +524,16 @@ impl AsPhysical for pb::Limit {
         if range.upper <= range.lower || range.lower < 0 || range.upper <= 0 {
             Err(IrError::InvalidRange(range.lower, range.upper))?
         }
-        builder.limit(self.clone());
+        if plan_meta.is_partition() && !self.per_partition {
+            // A global limit over the partitions: limit each partition to the first `upper` results,
+            // which are then gathered into one partition to apply the range as a whole.
+            let local_range = Some(pb::Range { lower: 0, upper: range.upper });
+            builder.limit(pb::Limit { range: local_range, per_partition: true });
+            builder.gather();
+            builder.limit(pb::Limit { range: self.range.clone(), per_partition: true });
+        } else {
+            builder.limit(self.clone());
+        }
         Ok(())
     }
 }
@@ -436,19 +547,71 @@ impl AsPhysical for pb::OrderBy {
         }
         let mut order = self.clone();
         order.post_process(builder, plan_meta)?;
-        builder.order(order);
+        match order.limit.clone() {
+            Some(range) if plan_meta.is_partition() && !order.per_partition => {
+                // A global topk over the partitions: the topk of each partition, which are then
+                // gathered into one partition to take the topk as a whole, as is done for `Limit`.
+                let mut local_order = order.clone();
+                local_order.limit = Some(pb::Range { lower: 0, upper: range.upper });
+                local_order.per_partition = true;
+                order.per_partition = true;
+                builder.order(local_order);
+                builder.gather();
+                builder.order(order);
+            }
+            _ => {
+                builder.order(order);
+            }
+        }
         Ok(())
     }
 
     fn post_process(&mut self, builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        post_process_vars(builder, plan_meta, true)?;
+        if plan_meta.is_bulk_fetch() {
+            post_process_vars_in_bulk(builder, plan_meta)?;
+        } else {
+            post_process_vars(builder, plan_meta, true)?;
+        }
         Ok(())
     }
 }
 
+// Try to apply the optimize rule: ExpandV + Dedup = ExpandV(distinct) + Dedup, if it satisfies:
+// 1. the previous op is ExpandV, which is not optional, as the missing vertices are all alike.
+// 2. `Dedup` is by the expanded vertices alone, i.e., the head or the alias of ExpandV without any property.
+// 3. it is not in the subtask of an `Apply`, as the expansion would then skip the vertices that have
+// been expanded to in the sub-scopes of the other input records.
+// The expansion then skips the vertices that have been expanded to, instead of yielding the duplicates.
+// Note that `Dedup` is kept, as the vertices are only distinct among the records of the same worker.
+fn try_fuse_distinct_expand(builder: &mut PlanBuilder, plan_meta: &PlanMeta, dedup: &pb::Dedup) {
+    if plan_meta.is_in_subtask() || dedup.keys.len() != 1 || dedup.keys[0].property.is_some() {
+        return;
+    }
+    if let Some(physical_pb::physical_opr::operator::OpKind::Edge(edge)) = builder
+        .get_last_op_mut()
+        .and_then(|op| op.opr.as_mut())
+        .and_then(|opr| opr.op_kind.as_mut())
+    {
+        let is_expanded_key = match dedup.keys[0].tag.as_ref() {
+            None => true,
+            Some(tag) => edge
+                .alias
+                .map(|alias| common_pb::NameOrId::from(alias) == *tag)
+                .unwrap_or(false),
+        };
+        if edge.expand_opt == physical_pb::edge_expand::ExpandOpt::Vertex as i32
+            && !edge.is_optional
+            && is_expanded_key
+        {
+            edge.is_distinct = true;
+        }
+    }
+}
+
 impl AsPhysical for pb::Dedup {
     fn add_job_builder(&self, builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
         let mut dedup = self.clone();
+        try_fuse_distinct_expand(builder, plan_meta, &dedup);
         dedup.post_process(builder, plan_meta)?;
         builder.dedup(dedup);
         Ok(())
@@ -468,7 +631,26 @@ impl AsPhysical for pb::GroupBy {
         Ok(())
     }
     fn post_process(&mut self, builder: &mut PlanBuilder, plan_meta: &mut PlanMeta) -> IrResult<()> {
-        post_process_vars(builder, plan_meta, true)?;
+        if plan_meta.is_bulk_fetch() {
+            post_process_vars_in_bulk(builder, plan_meta)?;
+        } else {
+            post_process_vars(builder, plan_meta, true)?;
+        }
+        Ok(())
+    }
+}
+
+impl AsPhysical for pb::Count {
+    fn add_job_builder(&self, builder: &mut PlanBuilder, _plan_meta: &mut PlanMeta) -> IrResult<()> {
+        if let Some(tag) = &self.tag {
+            // Only the records where the tag is present are counted
+            let var = common_pb::Variable { tag: Some(tag.clone()), property: None, node_type: None };
+            builder.select(pb::Select {
+                predicate: Some(common_pb::Expression { operators: vec![var.into()] }),
+            });
+        }
+        // Lowered to a distributed counter in the runtime, rather than a `GroupBy` to fold the records
+        builder.count(pb::Count { tag: None, alias: self.alias.clone() });
         Ok(())
     }
 }
@@ -492,7 +674,7 @@ impl AsPhysical for pb::Sink {
             .as_ref()
             .ok_or(IrError::MissingData("Sink::sink_target::Inner".to_string()))?
         {
-            pb::sink::sink_target::Inner::SinkDefault(_) => {
+            pb::sink::sink_target::Inner::SinkDefault(sink_default) => {
                 let tag_id_mapping = plan_meta
                     .get_tag_id_mappings()
                     .iter()
@@ -505,25 +687,34 @@ impl AsPhysical for pb::Sink {
                 let sink_target = pb::sink::SinkTarget {
                     inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
                         id_name_mappings: tag_id_mapping,
+                        layout: sink_default.layout,
                     })),
                 };
                 sink_opr.sink_target = Some(sink_target);
             }
             pb::sink::sink_target::Inner::SinkVineyard(sink_vineyard) => {
-                use crate::plan::meta::STORE_META;
                 let graph_name = sink_vineyard.graph_name.clone();
-                loop {
-                    if let Ok(meta) = STORE_META.try_read() {
-                        let sink_target = pb::sink::SinkTarget {
-                            inner: Some(pb::sink::sink_target::Inner::SinkVineyard(pb::SinkVineyard {
-                                graph_name,
-                                graph_schema: meta.schema.clone().map(|schema| schema.into()),
-                            })),
-                        };
-                        sink_opr.sink_target = Some(sink_target);
-                        break;
-                    }
-                }
+                let graph_schema = plan_meta
+                    .with_store_meta(|meta| meta.schema.clone())
+                    .flatten();
+                let sink_target = pb::sink::SinkTarget {
+                    inner: Some(pb::sink::sink_target::Inner::SinkVineyard(pb::SinkVineyard {
+                        graph_name,
+                        graph_schema: graph_schema.map(|schema| schema.into()),
+                    })),
+                };
+                sink_opr.sink_target = Some(sink_target);
+            }
+            pb::sink::sink_target::Inner::SinkResult(_) => {}
+            pb::sink::sink_target::Inner::SinkBulkLoad(bulk_load) => {
+                let mut bulk_load = bulk_load.clone();
+                bulk_load.graph_schema = plan_meta
+                    .with_store_meta(|meta| meta.schema.clone())
+                    .flatten()
+                    .map(|schema| schema.into());
+                sink_opr.sink_target = Some(pb::sink::SinkTarget {
+                    inner: Some(pb::sink::sink_target::Inner::SinkBulkLoad(bulk_load)),
+                });
             }
         };
 
@@ -548,6 +739,7 @@ impl AsPhysical for pb::logical_plan::Operator {
                 As(as_opr) => as_opr.add_job_builder(builder, plan_meta),
                 Dedup(dedup) => dedup.add_job_builder(builder, plan_meta),
                 GroupBy(groupby) => groupby.add_job_builder(builder, plan_meta),
+                Count(count) => count.add_job_builder(builder, plan_meta),
                 Sink(sink) => sink.add_job_builder(builder, plan_meta),
                 Union(_) => Ok(()),
                 Intersect(_) => Ok(()),
@@ -566,6 +758,7 @@ impl AsPhysical for NodeType {
         self.borrow()
             .opr
             .add_job_builder(builder, plan_meta)
+            .map_err(|err| IrError::NodeError(self.borrow().to_string(), Box::new(err)))
     }
 }
 
@@ -610,6 +803,35 @@ impl AsPhysical for LogicalPlan {
         let mut curr_node_opt = self.get_first_node();
         debug!("plan: {:#?}", self);
         debug!("is_partition: {:?}", self.meta.is_partition());
+        if self.allow_partial {
+            builder.allow_partial(true);
+        }
+        if let Some(policy) = self.super_node_policy.as_ref() {
+            if policy.threshold <= 0 {
+                return Err(IrError::Unsupported(format!(
+                    "super node policy with a non-positive threshold {}",
+                    policy.threshold
+                )));
+            }
+            builder.super_node_policy(policy.clone());
+        }
+        if let Some(origin) = self.origin.as_ref() {
+            builder.origin(origin.clone());
+        }
+        if let Some(mode) = self.continuous.as_ref() {
+            // the query is registered, and thus deregistered, by its name
+            if mode.name.is_empty() {
+                return Err(IrError::MissingData("ContinuousMode::name".to_string()));
+            }
+            if mode.emit_interval_ms < 0 {
+                return Err(IrError::Unsupported(format!(
+                    "continuous query with a negative emit interval {}",
+                    mode.emit_interval_ms
+                )));
+            }
+            builder.continuous(mode.clone());
+        }
+        self.check_read_scope()?;
         while curr_node_opt.is_some() {
             let curr_node = curr_node_opt.as_ref().unwrap();
             let curr_node_id = curr_node.borrow().id;
@@ -671,12 +893,25 @@ impl AsPhysical for LogicalPlan {
                             meta_data: vec![],
                         });
                     } else {
-                        subplan.add_job_builder(&mut sub_bldr, plan_meta)?;
+                        let is_in_subtask = plan_meta.is_in_subtask();
+                        plan_meta.set_in_subtask(true);
+                        let result = subplan.add_job_builder(&mut sub_bldr, plan_meta);
+                        plan_meta.set_in_subtask(is_in_subtask);
+                        result?;
                         builder.apply(
                             unsafe { std::mem::transmute(apply_opr.join_kind) },
                             sub_bldr,
                             apply_opr.alias.clone(),
                         );
+                        if apply_opr.timeout_ms > 0 {
+                            if let Some(physical_pb::physical_opr::operator::OpKind::Apply(apply)) = builder
+                                .get_last_op_mut()
+                                .and_then(|op| op.opr.as_mut())
+                                .and_then(|opr| opr.op_kind.as_mut())
+                            {
+                                apply.timeout_ms = apply_opr.timeout_ms;
+                            }
+                        }
                     }
                 } else {
                     return Err(IrError::MissingData("Apply::subplan".to_string()));
@@ -716,17 +951,29 @@ impl AsPhysical for LogicalPlan {
                                     "joining more than two branches".to_string(),
                                 ));
                             }
-                            let left_plan = plans.get(0).unwrap().clone();
-                            let right_plan = plans.get(1).unwrap().clone();
+                            let mut left_plan = plans.get(0).unwrap().clone();
+                            let mut right_plan = plans.get(1).unwrap().clone();
+                            if plan_meta.is_project_join_keys() {
+                                // drop the columns that are unused after the join before shuffling
+                                let (left_project, right_project) =
+                                    self.get_join_key_projections(merge_node.borrow().id);
+                                if let Some(project) = left_project {
+                                    left_plan.project(project);
+                                }
+                                if let Some(project) = right_project {
+                                    right_plan.project(project);
+                                }
+                            }
 
                             post_process_vars(builder, plan_meta, false)?;
 
-                            builder.join(
+                            builder.join_with_renames(
                                 unsafe { std::mem::transmute(join_opr.kind) },
                                 left_plan,
                                 right_plan,
                                 join_opr.left_keys.clone(),
                                 join_opr.right_keys.clone(),
+                                join_opr.renames.clone(),
                             );
                         }
                         None => {
@@ -917,9 +1164,112 @@ fn add_intersect_job_builder(
     Ok(())
 }
 
+/// The values bound to the named parameters of a plan at submission, where each parameter
+/// is bound to a list of values, e.g., the ids to look up.
+pub type PlanParams = HashMap<String, Vec<common_pb::Value>>;
+
+/// Bind the parameters referred by the index predicates of the scans of the physical plan, including
+/// the scans of its subplans, such that a plan can be built once and rebound per submission. The
+/// `AndPredicate` of a parameter is expanded into one per value bound to the parameter.
+///
+/// # Return
+///   * `IrError::MissingData` if a parameter is not bound, or bound to an empty list of values
+pub fn bind_plan_params(plan: &mut physical_pb::PhysicalPlan, params: &PlanParams) -> IrResult<()> {
+    use physical_pb::physical_opr::operator::OpKind;
+
+    for opr in plan.plan.iter_mut() {
+        let op_kind = opr
+            .opr
+            .as_mut()
+            .and_then(|opr| opr.op_kind.as_mut());
+        match op_kind {
+            Some(OpKind::Scan(scan)) => {
+                if let Some(idx_pred) = scan.idx_predicate.as_mut() {
+                    bind_index_predicate_params(idx_pred, params)?;
+                }
+            }
+            Some(OpKind::Apply(apply)) => {
+                if let Some(sub_plan) = apply.sub_plan.as_mut() {
+                    bind_plan_params(sub_plan, params)?;
+                }
+            }
+            Some(OpKind::Join(join)) => {
+                for sub_plan in join
+                    .left_plan
+                    .iter_mut()
+                    .chain(join.right_plan.iter_mut())
+                {
+                    bind_plan_params(sub_plan, params)?;
+                }
+            }
+            Some(OpKind::Union(union)) => {
+                for sub_plan in union.sub_plans.iter_mut() {
+                    bind_plan_params(sub_plan, params)?;
+                }
+            }
+            Some(OpKind::Intersect(intersect)) => {
+                for sub_plan in intersect.sub_plans.iter_mut() {
+                    bind_plan_params(sub_plan, params)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn bind_index_predicate_params(idx_pred: &mut pb::IndexPredicate, params: &PlanParams) -> IrResult<()> {
+    let mut or_predicates = Vec::with_capacity(idx_pred.or_predicates.len());
+    for and_pred in idx_pred.or_predicates.drain(..) {
+        // the cartesian product of the values of the parameters in the `AndPredicate`
+        let mut bound_preds: Vec<Vec<pb::index_predicate::Triplet>> = vec![vec![]];
+        for triplet in and_pred.predicates {
+            if let Some(param) = triplet.param.as_ref() {
+                let values = params
+                    .get(&param.name)
+                    .filter(|values| !values.is_empty())
+                    .ok_or_else(|| {
+                        IrError::MissingData(format!("the values of the parameter {:?}", param.name))
+                    })?;
+                let triplet = &triplet;
+                bound_preds = bound_preds
+                    .into_iter()
+                    .flat_map(|preds| {
+                        values.iter().map(move |value| {
+                            let mut preds = preds.clone();
+                            preds.push(pb::index_predicate::Triplet {
+                                key: triplet.key.clone(),
+                                value: Some(value.clone()),
+                                cmp: triplet.cmp.clone(),
+                                param: None,
+                            });
+                            preds
+                        })
+                    })
+                    .collect();
+            } else {
+                for preds in bound_preds.iter_mut() {
+                    preds.push(triplet.clone());
+                }
+            }
+        }
+        or_predicates.extend(
+            bound_preds
+                .into_iter()
+                .map(|predicates| pb::index_predicate::AndPredicate { predicates }),
+        );
+    }
+    idx_pred.or_predicates = or_predicates;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use std::sync::Arc;
 
     use ir_common::expr_parse::str_to_expr_pb;
     use ir_common::generated::algebra as pb;
@@ -928,6 +1278,7 @@ mod test {
 
     use super::*;
     use crate::plan::logical::Node;
+    use crate::plan::meta::{Schema, StoreMeta};
 
     #[allow(dead_code)]
     fn query_params(
@@ -941,6 +1292,7 @@ mod test {
             predicate: None,
             sample_ratio: 1.0,
             extra: HashMap::new(),
+            degree_predicates: vec![],
         }
     }
 
@@ -952,6 +1304,8 @@ mod test {
             params: Some(query_params(vec![], columns)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         }
     }
 
@@ -967,12 +1321,25 @@ mod test {
             alias,
             expand_opt,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         }
     }
 
     #[allow(dead_code)]
     fn build_getv(alias: Option<common_pb::NameOrId>) -> pb::GetV {
-        pb::GetV { tag: None, opt: 1, params: Some(query_params(vec![], vec![])), alias, meta_data: None }
+        pb::GetV {
+            tag: None,
+            opt: 1,
+            params: Some(query_params(vec![], vec![])),
+            alias,
+            meta_data: None,
+            is_optional: false,
+            end_alias: None,
+        }
     }
 
     #[allow(dead_code)]
@@ -984,14 +1351,22 @@ mod test {
     fn build_auxilia_with_predicates(expr: &str) -> pb::GetV {
         let mut params = query_params(vec![], vec![]);
         params.predicate = str_to_expr_pb(expr.to_string()).ok();
-        pb::GetV { tag: None, opt: 4, params: Some(params), alias: None, meta_data: None }
+        pb::GetV {
+            tag: None,
+            opt: 4,
+            params: Some(params),
+            alias: None,
+            meta_data: None,
+            is_optional: false,
+            end_alias: None,
+        }
     }
 
     #[allow(dead_code)]
     fn build_auxilia_with_params(
         params: Option<pb::QueryParams>, alias: Option<common_pb::NameOrId>,
     ) -> pb::GetV {
-        pb::GetV { tag: None, opt: 4, params, alias, meta_data: None }
+        pb::GetV { tag: None, opt: 4, params, alias, meta_data: None, is_optional: false, end_alias: None }
     }
 
     #[allow(dead_code)]
@@ -1000,10 +1375,26 @@ mod test {
         columns: Vec<common_pb::NameOrId>,
     ) -> pb::GetV {
         if columns.is_empty() {
-            pb::GetV { tag, opt: 4, params: None, alias, meta_data: None }
+            pb::GetV {
+                tag,
+                opt: 4,
+                params: None,
+                alias,
+                meta_data: None,
+                is_optional: false,
+                end_alias: None,
+            }
         } else {
             let params = query_params(vec![], columns);
-            pb::GetV { tag, opt: 4, params: Some(params), alias, meta_data: None }
+            pb::GetV {
+                tag,
+                opt: 4,
+                params: Some(params),
+                alias,
+                meta_data: None,
+                is_optional: false,
+                end_alias: None,
+            }
         }
     }
 
@@ -1026,6 +1417,7 @@ mod test {
             sink_target: Some(pb::sink::SinkTarget {
                 inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
                     id_name_mappings: vec![],
+                    layout: 0,
                 })),
             }),
         }
@@ -1112,6 +1504,59 @@ mod test {
         assert_eq!(job_builder, expected_builder);
     }
 
+    #[test]
+    fn edgexpd_dedup_both_as_physical() {
+        // g.V().both(), which yields each self-loop once
+        let mut expand = build_edgexpd(0, vec![], None);
+        expand.direction = 2;
+        expand.dedup_both = true;
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(expand.clone().into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(build_sink().into(), vec![1])
+            .unwrap();
+        let mut job_builder = PlanBuilder::default();
+        let mut plan_meta = plan.meta.clone().with_partition();
+        plan.add_job_builder(&mut job_builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(build_scan(vec![]));
+        expected_builder.shuffle(None);
+        expected_builder.edge_expand(expand);
+        expected_builder.sink(build_sink());
+
+        assert_eq!(job_builder, expected_builder);
+    }
+
+    #[test]
+    fn edgexpd_skip_loops_and_parallels_as_physical() {
+        // g.V().outE() that skips the self-loops and collapses the parallel edges
+        let mut expand = build_edgexpd(1, vec![], None);
+        expand.exclude_self_loops = true;
+        expand.collapse_parallel_edges = true;
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(expand.clone().into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(build_sink().into(), vec![1])
+            .unwrap();
+        let mut job_builder = PlanBuilder::default();
+        let mut plan_meta = plan.meta.clone();
+        plan.add_job_builder(&mut job_builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(build_scan(vec![]));
+        expected_builder.edge_expand(expand);
+        expected_builder.sink(build_sink());
+
+        assert_eq!(job_builder, expected_builder);
+    }
+
     #[test]
     fn post_process_edgexpd_label_filter() {
         // g.V().out().filter(@.~label == "person")
@@ -1242,6 +1687,8 @@ mod test {
             params: None,
             alias: Some(0.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         });
         expected_builder.project(build_project("{@0.name, @0.id, @0.age}"));
         expected_builder.sink(build_sink());
@@ -1249,6 +1696,59 @@ mod test {
         assert_eq!(job_builder, expected_builder);
     }
 
+    #[test]
+    fn post_process_project_bulk_fetch() {
+        // g.V().out().as(0).select(0).by(valueMap("name", "id", "age")
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(build_edgexpd(0, vec![], Some(0.into())).into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(build_project("{@0.name, @0.id, @0.age}").into(), vec![1])
+            .unwrap();
+        plan.append_operator_as_node(build_sink().into(), vec![2])
+            .unwrap();
+        let mut job_builder = PlanBuilder::default();
+        let mut plan_meta = plan.meta.clone().with_bulk_fetch();
+        plan.add_job_builder(&mut job_builder, &mut plan_meta)
+            .unwrap();
+
+        // the three properties are fetched at once
+        let auxilia = build_auxilia_with_tag_alias_columns(
+            Some(0.into()),
+            Some(0.into()),
+            vec!["age".into(), "id".into(), "name".into()],
+        );
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(build_scan(vec![]));
+        expected_builder.edge_expand(build_edgexpd(0, vec![], Some(0.into())));
+        expected_builder.get_v(auxilia.clone());
+        expected_builder.project(build_project("{@0.name, @0.id, @0.age}"));
+        expected_builder.sink(build_sink());
+
+        assert_eq!(job_builder, expected_builder);
+
+        let mut job_builder = PlanBuilder::default();
+        let mut plan_meta = plan
+            .meta
+            .clone()
+            .with_partition()
+            .with_bulk_fetch();
+        plan.add_job_builder(&mut job_builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(build_scan(vec![]));
+        expected_builder.shuffle(None);
+        expected_builder.edge_expand(build_edgexpd(0, vec![], Some(0.into())));
+        expected_builder.shuffle(Some(0.into()));
+        expected_builder.get_v(auxilia);
+        expected_builder.project(build_project("{@0.name, @0.id, @0.age}"));
+        expected_builder.sink(build_sink());
+
+        assert_eq!(job_builder, expected_builder);
+    }
+
     #[test]
     fn post_process_edgexpd_tag_no_auxilia() {
         // g.V().out().as('a').select('a')
@@ -1381,8 +1881,8 @@ mod test {
     }
 
     #[test]
-    fn post_process_getv_auxilia_filter() {
-        // g.V().outE().inV().filter('age > 10')
+    fn post_process_getv_label_fuse() {
+        // g.V().outE().inV().hasLabel(1)
         let mut plan = LogicalPlan::default();
         plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
             .unwrap();
@@ -1392,17 +1892,11 @@ mod test {
             pb::GetV {
                 tag: None,
                 opt: 1,
-                params: Some(pb::QueryParams {
-                    tables: vec![],
-                    columns: vec![],
-                    is_all_columns: false,
-                    limit: None,
-                    predicate: str_to_expr_pb("@.age > 10".to_string()).ok(),
-                    sample_ratio: 1.0,
-                    extra: Default::default(),
-                }),
+                params: Some(query_params(vec![1.into()], vec![])),
                 alias: None,
                 meta_data: None,
+                is_optional: false,
+                end_alias: None,
             }
             .into(),
             vec![1],
@@ -1417,48 +1911,340 @@ mod test {
 
         let mut expected_builder = PlanBuilder::default();
         expected_builder.add_scan_source(build_scan(vec![]));
-        // a fused ExpandV
-        expected_builder.edge_expand(build_edgexpd(0, vec![], None));
-        expected_builder.get_v(build_auxilia_with_predicates("@.age > 10"));
+        // a fused ExpandV, with the labels of the adjacent vertices
+        let mut fused_expand = build_edgexpd(0, vec![], None);
+        fused_expand.vertex_tables = vec![1.into()];
+        expected_builder.edge_expand(fused_expand);
         expected_builder.sink(build_sink());
         assert_eq!(job_builder, expected_builder);
     }
 
     #[test]
-    fn poc_plan_as_physical() {
-        // g.V().hasLabel("person").has("id", 10).out("knows").limit(10)
-        let source_opr = pb::Scan {
-            scan_opt: 0,
-            alias: None,
-            params: Some(query_params(vec!["person".into()], vec![])),
-            idx_predicate: None,
-            meta_data: None,
-        };
-        let select_opr = pb::Select { predicate: str_to_expr_pb("@.id == 10".to_string()).ok() };
-        let expand_opr = pb::EdgeExpand {
-            v_tag: None,
-            direction: 0,
-            params: Some(query_params(vec!["knows".into()], vec![])),
-            expand_opt: 0,
-            alias: None,
-            meta_data: None,
+    fn post_process_getv_optional_fuse() {
+        // g.V().outE().inV(), where the expansion and/or the getting vertex are optional
+        let build_plan = |is_edge_optional: bool, is_getv_optional: bool| {
+            let mut plan = LogicalPlan::default();
+            plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
+                .unwrap();
+            let mut expand = build_edgexpd(1, vec![], None);
+            expand.is_optional = is_edge_optional;
+            plan.append_operator_as_node(expand.into(), vec![0])
+                .unwrap();
+            let mut getv = build_getv(None);
+            getv.is_optional = is_getv_optional;
+            plan.append_operator_as_node(getv.into(), vec![1])
+                .unwrap();
+            plan.append_operator_as_node(build_sink().into(), vec![2])
+                .unwrap();
+            let mut job_builder = PlanBuilder::default();
+            let mut plan_meta = plan.meta.clone();
+            plan.add_job_builder(&mut job_builder, &mut plan_meta)
+                .unwrap();
+            job_builder
         };
-        let limit_opr = pb::Limit { range: Some(pb::Range { lower: 10, upper: 11 }) };
 
-        let mut logical_plan = LogicalPlan::default();
+        // both are optional, which are fused into an optional ExpandV
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(build_scan(vec![]));
+        let mut fused_expand = build_edgexpd(0, vec![], None);
+        fused_expand.is_optional = true;
+        expected_builder.edge_expand(fused_expand);
+        expected_builder.sink(build_sink());
+        assert_eq!(build_plan(true, true), expected_builder);
 
-        logical_plan
-            .append_operator_as_node(source_opr.clone().into(), vec![])
-            .unwrap(); // node 0
-        logical_plan
-            .append_operator_as_node(select_opr.clone().into(), vec![0])
-            .unwrap(); // node 1
-        logical_plan
-            .append_operator_as_node(expand_opr.clone().into(), vec![1])
-            .unwrap(); // node 2
-        logical_plan
-            .append_operator_as_node(limit_opr.clone().into(), vec![2])
-            .unwrap(); // node 3
+        // only the getting vertex is optional, which cannot be fused
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(build_scan(vec![]));
+        expected_builder.edge_expand(build_edgexpd(1, vec![], None));
+        let mut getv = build_getv(None);
+        getv.is_optional = true;
+        expected_builder.get_v(getv);
+        expected_builder.sink(build_sink());
+        assert_eq!(build_plan(false, true), expected_builder);
+    }
+
+    #[test]
+    fn post_process_dedup_distinct_fuse() {
+        // g.V().out().as('0').dedup(key), where the expansion is optional or not
+        let build_plan = |key: &str, is_optional: bool| {
+            let mut plan = LogicalPlan::default();
+            plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
+                .unwrap();
+            let mut expand = build_edgexpd(0, vec![], Some(0.into()));
+            expand.is_optional = is_optional;
+            plan.append_operator_as_node(expand.into(), vec![0])
+                .unwrap();
+            plan.append_operator_as_node(
+                pb::Dedup { keys: vec![common_pb::Variable::from(key.to_string())] }.into(),
+                vec![1],
+            )
+            .unwrap();
+            let mut job_builder = PlanBuilder::default();
+            let mut plan_meta = plan.meta.clone();
+            plan.add_job_builder(&mut job_builder, &mut plan_meta)
+                .unwrap();
+            job_builder
+        };
+        let build_expected = |key: common_pb::Variable, is_optional: bool, is_distinct: bool| {
+            let mut expected_builder = PlanBuilder::default();
+            expected_builder.add_scan_source(build_scan(vec![]));
+            let mut expand = build_edgexpd(0, vec![], Some(0.into()));
+            expand.is_optional = is_optional;
+            expected_builder.edge_expand(expand);
+            if let Some(physical_pb::physical_opr::operator::OpKind::Edge(edge)) = expected_builder
+                .get_last_op_mut()
+                .and_then(|op| op.opr.as_mut())
+                .and_then(|opr| opr.op_kind.as_mut())
+            {
+                edge.is_distinct = is_distinct;
+            }
+            expected_builder.dedup(pb::Dedup { keys: vec![key] });
+            expected_builder
+        };
+
+        // dedup by the expanded vertices, either the alias or the head
+        assert_eq!(
+            build_plan("@0", false),
+            build_expected(common_pb::Variable::from("@0".to_string()), false, true)
+        );
+        assert_eq!(
+            build_plan("@", false),
+            build_expected(common_pb::Variable::from("@".to_string()), false, true)
+        );
+        // the optional expansion is not distinct
+        assert_eq!(
+            build_plan("@0", true),
+            build_expected(common_pb::Variable::from("@0".to_string()), true, false)
+        );
+    }
+
+    #[test]
+    fn post_process_dedup_distinct_fuse_in_apply() {
+        // g.V().where(out().as('0').dedup('0')), of which the subtask runs in a sub-scope per vertex
+        let mut plan = LogicalPlan::default();
+        let opr_id = plan
+            .append_operator_as_node(build_scan(vec![]).into(), vec![])
+            .unwrap();
+        let root_id = plan
+            .append_operator_as_node(build_edgexpd(0, vec![], Some(0.into())).into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(
+            pb::Dedup { keys: vec![common_pb::Variable::from("@0".to_string())] }.into(),
+            vec![root_id],
+        )
+        .unwrap();
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: root_id as i32,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
+        plan.append_operator_as_node(apply.into(), vec![opr_id])
+            .unwrap();
+
+        let mut builder = PlanBuilder::default();
+        let mut meta = plan.meta.clone();
+        plan.add_job_builder(&mut builder, &mut meta)
+            .unwrap();
+        assert!(!meta.is_in_subtask());
+        let distinct: Vec<bool> = builder
+            .build()
+            .plan
+            .into_iter()
+            .filter_map(|op| match op.opr.and_then(|opr| opr.op_kind) {
+                Some(physical_pb::physical_opr::operator::OpKind::Apply(apply)) => Some(apply),
+                _ => None,
+            })
+            .flat_map(|apply| apply.sub_plan.unwrap().plan)
+            .filter_map(|op| match op.opr.and_then(|opr| opr.op_kind) {
+                Some(physical_pb::physical_opr::operator::OpKind::Edge(edge)) => Some(edge.is_distinct),
+                _ => None,
+            })
+            .collect();
+        // the vertices expanded to in the sub-scope of one vertex must not be skipped in another's
+        assert_eq!(distinct, vec![false]);
+    }
+
+    #[test]
+    fn post_process_sink_layout() {
+        // g.V(), of which the results are returned in the columnar layout
+        let mut sink = build_sink();
+        if let Some(pb::sink::sink_target::Inner::SinkDefault(sink_default)) = sink
+            .sink_target
+            .as_mut()
+            .and_then(|target| target.inner.as_mut())
+        {
+            sink_default.layout = pb::sink_default::Layout::Columnar as i32;
+        }
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(sink.into(), vec![0])
+            .unwrap();
+        let mut job_builder = PlanBuilder::default();
+        let mut plan_meta = plan.meta.clone();
+        plan.add_job_builder(&mut job_builder, &mut plan_meta)
+            .unwrap();
+
+        let layout = match job_builder
+            .last()
+            .and_then(|op| op.opr.as_ref())
+            .and_then(|opr| opr.op_kind.as_ref())
+        {
+            Some(physical_pb::physical_opr::operator::OpKind::Sink(sink)) => {
+                match sink
+                    .sink_target
+                    .as_ref()
+                    .and_then(|target| target.inner.as_ref())
+                {
+                    Some(pb::sink::sink_target::Inner::SinkDefault(sink_default)) => {
+                        Some(sink_default.layout)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        assert_eq!(layout, Some(pb::sink_default::Layout::Columnar as i32));
+    }
+
+    #[test]
+    fn post_process_getv_degree_predicate() {
+        // g.V().outE().inV(), only to the vertices of less than 1000 outgoing edges
+        let mut params = query_params(vec![], vec![]);
+        params
+            .degree_predicates
+            .push(pb::DegreePredicate {
+                direction: 0,
+                tables: vec![],
+                range: Some(pb::Range { lower: 0, upper: 1000 }),
+            });
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(build_edgexpd(1, vec![], None).into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(
+            pb::GetV {
+                tag: None,
+                opt: 1,
+                params: Some(params.clone()),
+                alias: None,
+                meta_data: None,
+                is_optional: false,
+                end_alias: None,
+            }
+            .into(),
+            vec![1],
+        )
+        .unwrap();
+        plan.append_operator_as_node(build_sink().into(), vec![2])
+            .unwrap();
+        let mut job_builder = PlanBuilder::default();
+        let mut plan_meta = plan.meta.clone();
+        plan.add_job_builder(&mut job_builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(build_scan(vec![]));
+        // a fused ExpandV, followed by filtering the degree of the vertex itself
+        expected_builder.edge_expand(build_edgexpd(0, vec![], None));
+        expected_builder.get_v(build_auxilia_with_params(Some(params), None));
+        expected_builder.sink(build_sink());
+        assert_eq!(job_builder, expected_builder);
+    }
+
+    #[test]
+    fn post_process_getv_auxilia_filter() {
+        // g.V().outE().inV().filter('age > 10')
+        let mut plan = LogicalPlan::default();
+        plan.append_operator_as_node(build_scan(vec![]).into(), vec![])
+            .unwrap();
+        plan.append_operator_as_node(build_edgexpd(1, vec![], None).into(), vec![0])
+            .unwrap();
+        plan.append_operator_as_node(
+            pb::GetV {
+                tag: None,
+                opt: 1,
+                params: Some(pb::QueryParams {
+                    tables: vec![],
+                    columns: vec![],
+                    is_all_columns: false,
+                    limit: None,
+                    predicate: str_to_expr_pb("@.age > 10".to_string()).ok(),
+                    sample_ratio: 1.0,
+                    extra: Default::default(),
+                    degree_predicates: vec![],
+                }),
+                alias: None,
+                meta_data: None,
+                is_optional: false,
+                end_alias: None,
+            }
+            .into(),
+            vec![1],
+        )
+        .unwrap();
+        plan.append_operator_as_node(build_sink().into(), vec![2])
+            .unwrap();
+        let mut job_builder = PlanBuilder::default();
+        let mut plan_meta = plan.meta.clone();
+        plan.add_job_builder(&mut job_builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(build_scan(vec![]));
+        // a fused ExpandV
+        expected_builder.edge_expand(build_edgexpd(0, vec![], None));
+        expected_builder.get_v(build_auxilia_with_predicates("@.age > 10"));
+        expected_builder.sink(build_sink());
+        assert_eq!(job_builder, expected_builder);
+    }
+
+    #[test]
+    fn poc_plan_as_physical() {
+        // g.V().hasLabel("person").has("id", 10).out("knows").limit(10)
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec!["person".into()], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let select_opr = pb::Select { predicate: str_to_expr_pb("@.id == 10".to_string()).ok() };
+        let expand_opr = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec!["knows".into()], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let limit_opr = pb::Limit { range: Some(pb::Range { lower: 10, upper: 11 }), per_partition: false };
+
+        let mut logical_plan = LogicalPlan::default();
+
+        logical_plan
+            .append_operator_as_node(source_opr.clone().into(), vec![])
+            .unwrap(); // node 0
+        logical_plan
+            .append_operator_as_node(select_opr.clone().into(), vec![0])
+            .unwrap(); // node 1
+        logical_plan
+            .append_operator_as_node(expand_opr.clone().into(), vec![1])
+            .unwrap(); // node 2
+        logical_plan
+            .append_operator_as_node(limit_opr.clone().into(), vec![2])
+            .unwrap(); // node 3
         logical_plan
             .append_operator_as_node(build_sink().into(), vec![3])
             .unwrap();
@@ -1486,6 +2272,8 @@ mod test {
             params: Some(query_params(vec!["person".into()], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let project_opr = pb::Project {
@@ -1524,6 +2312,8 @@ mod test {
             params: Some(query_params(vec!["person".into()], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let edge_expand = pb::EdgeExpand {
@@ -1533,6 +2323,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let path_opr = pb::PathExpand {
@@ -1543,6 +2338,7 @@ mod test {
             path_opt: 0,
             result_opt: 0,
             condition: None,
+            timeout_ms: 0,
         };
 
         let mut logical_plan = LogicalPlan::with_root(Node::new(0, source_opr.clone().into()));
@@ -1587,6 +2383,8 @@ mod test {
             params: Some(query_params(vec!["person".into()], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let edge_expand = pb::EdgeExpand {
@@ -1596,6 +2394,11 @@ mod test {
             expand_opt: 1, // expand edge
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let getv = pb::GetV {
@@ -1604,6 +2407,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let path_opr = pb::PathExpand {
@@ -1614,6 +2419,7 @@ mod test {
             path_opt: 0,
             result_opt: 0,
             condition: None,
+            timeout_ms: 0,
         };
 
         let fused_edge_expand = pb::EdgeExpand {
@@ -1623,6 +2429,11 @@ mod test {
             expand_opt: 0, // expand vertex
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let fused_path_opr = pb::PathExpand {
             base: Some(fused_edge_expand.into()),
@@ -1632,6 +2443,7 @@ mod test {
             path_opt: 0,
             result_opt: 0,
             condition: None,
+            timeout_ms: 0,
         };
 
         let mut logical_plan = LogicalPlan::with_root(Node::new(0, source_opr.clone().into()));
@@ -1676,6 +2488,8 @@ mod test {
             params: Some(query_params(vec!["person".into()], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let edge_expand = pb::EdgeExpand {
@@ -1685,6 +2499,11 @@ mod test {
             expand_opt: 1, // expand edge
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let getv = pb::GetV {
@@ -1698,9 +2517,12 @@ mod test {
                 predicate: str_to_expr_pb("@.age > 10".to_string()).ok(),
                 sample_ratio: 1.0,
                 extra: HashMap::new(),
+                degree_predicates: vec![],
             }),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let path_opr = pb::PathExpand {
@@ -1711,6 +2533,7 @@ mod test {
             path_opt: 0,
             result_opt: 0,
             condition: None,
+            timeout_ms: 0,
         };
 
         let fused_edge_expand = pb::EdgeExpand {
@@ -1720,6 +2543,11 @@ mod test {
             expand_opt: 0, // expand vertex
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let fused_getv_with_filter = pb::GetV {
             tag: None,
@@ -1732,9 +2560,12 @@ mod test {
                 predicate: str_to_expr_pb("@.age > 10".to_string()).ok(),
                 sample_ratio: 1.0,
                 extra: HashMap::new(),
+                degree_predicates: vec![],
             }),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let expected_path_opr = pb::PathExpand {
             base: Some((fused_edge_expand, fused_getv_with_filter).into()),
@@ -1744,6 +2575,7 @@ mod test {
             path_opt: 0,
             result_opt: 0,
             condition: None,
+            timeout_ms: 0,
         };
 
         let mut logical_plan = LogicalPlan::with_root(Node::new(0, source_opr.clone().into()));
@@ -1788,9 +2620,15 @@ mod test {
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
-        let topby_opr = pb::OrderBy { pairs: vec![], limit: Some(pb::Range { lower: 10, upper: 11 }) };
+        let topby_opr = pb::OrderBy {
+            pairs: vec![],
+            limit: Some(pb::Range { lower: 10, upper: 11 }),
+            per_partition: false,
+        };
 
         let mut logical_plan = LogicalPlan::with_root(Node::new(0, source_opr.clone().into()));
         logical_plan
@@ -1807,6 +2645,464 @@ mod test {
         assert_eq!(builder, expected_builder);
     }
 
+    #[test]
+    fn limit_scope_as_physical() {
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let limit_opr = |per_partition: bool| pb::Limit {
+            range: Some(pb::Range { lower: 10, upper: 11 }),
+            per_partition,
+        };
+        let topby_opr = |per_partition: bool| pb::OrderBy {
+            pairs: vec![],
+            limit: Some(pb::Range { lower: 10, upper: 11 }),
+            per_partition,
+        };
+
+        let mut logical_plan = LogicalPlan::with_root(Node::new(0, source_opr.clone().into()));
+        logical_plan
+            .append_operator_as_node(limit_opr(false).into(), vec![0])
+            .unwrap(); // node 1
+        logical_plan
+            .append_operator_as_node(topby_opr(false).into(), vec![1])
+            .unwrap(); // node 2
+
+        // Case without partition
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(source_opr.clone());
+        expected_builder.limit(limit_opr(false));
+        expected_builder.order(topby_opr(false));
+        assert_eq!(builder, expected_builder);
+
+        // Case with partition, where the global ranges are applied after gathering the partitions
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default().with_partition();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(source_opr.clone());
+        expected_builder
+            .limit(pb::Limit { range: Some(pb::Range { lower: 0, upper: 11 }), per_partition: true });
+        expected_builder.gather();
+        expected_builder.limit(limit_opr(true));
+        expected_builder.order(pb::OrderBy {
+            pairs: vec![],
+            limit: Some(pb::Range { lower: 0, upper: 11 }),
+            per_partition: true,
+        });
+        expected_builder.gather();
+        expected_builder.order(topby_opr(true));
+        assert_eq!(builder, expected_builder);
+
+        // Case with partition, where the ranges are applied per partition
+        let mut logical_plan = LogicalPlan::with_root(Node::new(0, source_opr.clone().into()));
+        logical_plan
+            .append_operator_as_node(limit_opr(true).into(), vec![0])
+            .unwrap(); // node 1
+        logical_plan
+            .append_operator_as_node(topby_opr(true).into(), vec![1])
+            .unwrap(); // node 2
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default().with_partition();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(source_opr);
+        expected_builder.limit(limit_opr(true));
+        expected_builder.order(topby_opr(true));
+        assert_eq!(builder, expected_builder);
+    }
+
+    #[test]
+    fn count_as_physical() {
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: Some(0.into()),
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let count_opr = pb::Count { tag: Some(0.into()), alias: Some(1.into()) };
+
+        let mut logical_plan = LogicalPlan::default();
+        logical_plan
+            .append_operator_as_node(source_opr.clone().into(), vec![])
+            .unwrap(); // node 0
+        logical_plan
+            .append_operator_as_node(count_opr.clone().into(), vec![0])
+            .unwrap(); // node 1
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(source_opr);
+        expected_builder.select(pb::Select { predicate: Some(str_to_expr_pb("@0".to_string()).unwrap()) });
+        expected_builder.count(pb::Count { tag: None, alias: Some(1.into()) });
+
+        assert_eq!(builder, expected_builder);
+    }
+
+    #[test]
+    fn table_scan_as_physical() {
+        let mut scan = build_scan(vec![]);
+        scan.scan_opt = pb::scan::ScanOpt::Table as i32;
+        scan.connector = Some(pb::scan::Connector {
+            uri: "hdfs://path/to/orders.csv".to_string(),
+            format: pb::scan::connector::Format::Csv as i32,
+            columns: vec![],
+            options: HashMap::new(),
+        });
+        let mut logical_plan = LogicalPlan::default();
+        logical_plan
+            .append_operator_as_node(scan.clone().into(), vec![])
+            .unwrap();
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        // an external table has no source in the job plan
+        assert!(logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .is_err());
+
+        scan.connector = None;
+        scan.named_result = "persons".to_string();
+        let mut logical_plan = LogicalPlan::default();
+        logical_plan
+            .append_operator_as_node(scan.clone().into(), vec![])
+            .unwrap();
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(scan);
+        assert_eq!(builder, expected_builder);
+    }
+
+    // e.g., g.V().values('name').count()
+    #[test]
+    fn count_after_fetch_as_physical() {
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let project_opr = pb::Project {
+            mappings: vec![pb::project::ExprAlias {
+                expr: str_to_expr_pb("@.name".to_string()).ok(),
+                alias: None,
+            }],
+            is_append: false,
+            meta_data: vec![],
+        };
+        let count_opr = pb::Count { tag: None, alias: None };
+
+        let mut logical_plan = LogicalPlan::default();
+        logical_plan
+            .append_operator_as_node(source_opr.clone().into(), vec![])
+            .unwrap(); // node 0
+        logical_plan
+            .append_operator_as_node(project_opr.into(), vec![0])
+            .unwrap(); // node 1
+        logical_plan
+            .append_operator_as_node(count_opr.clone().into(), vec![1])
+            .unwrap(); // node 2
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+
+        // the property is not fetched to count the vertices
+        let mut expected_builder = PlanBuilder::default();
+        expected_builder.add_scan_source(source_opr);
+        expected_builder.count(count_opr);
+
+        assert_eq!(builder, expected_builder);
+    }
+
+    #[test]
+    fn allow_partial_as_physical() {
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut logical_plan = LogicalPlan::default();
+        logical_plan
+            .append_operator_as_node(source_opr.clone().into(), vec![])
+            .unwrap();
+        logical_plan.set_allow_partial(true);
+        // the flag is preserved while serializing the plan
+        let plan_pb: pb::LogicalPlan = logical_plan.clone().into();
+        assert!(plan_pb.allow_partial);
+        let logical_plan = LogicalPlan::try_from(plan_pb).unwrap();
+        assert!(logical_plan.is_partial_allowed());
+
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+        let physical_plan = builder.build();
+        assert!(physical_plan.allow_partial);
+        assert_eq!(physical_plan.plan.len(), 1);
+    }
+
+    #[test]
+    fn plan_origin_as_physical() {
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let origin = pb::PlanOrigin {
+            query: "g.V()".to_string(),
+            frontend: "gremlin".to_string(),
+            compiler_version: "0.1.0".to_string(),
+        };
+        let mut logical_plan = LogicalPlan::default();
+        logical_plan
+            .append_operator_as_node(source_opr.into(), vec![])
+            .unwrap();
+        logical_plan.set_origin(origin.clone());
+        // the origin is preserved while serializing the plan, and the query is redacted for logging
+        let plan_pb: pb::LogicalPlan = logical_plan.clone().into();
+        assert_eq!(plan_pb.origin, Some(origin.clone()));
+        let logical_plan = LogicalPlan::try_from(plan_pb).unwrap();
+        assert_eq!(logical_plan.get_origin(), Some(&origin));
+        assert!(!logical_plan
+            .to_debug_string_redacted()
+            .unwrap()
+            .contains("g.V()"));
+
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+        assert_eq!(builder.build().origin, Some(origin));
+    }
+
+    #[test]
+    fn continuous_plan_as_physical() {
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut mode = pb::ContinuousMode {
+            name: String::new(),
+            emit: pb::continuous_mode::Emit::DeltaWithRetractions as i32,
+            emit_interval_ms: 1000,
+        };
+        let mut logical_plan = LogicalPlan::default();
+        logical_plan
+            .append_operator_as_node(source_opr.into(), vec![])
+            .unwrap();
+        assert!(!logical_plan.is_continuous());
+        logical_plan.set_continuous(mode.clone());
+        // a continuous query must be named to be registered
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        assert!(matches!(
+            logical_plan.add_job_builder(&mut builder, &mut plan_meta),
+            Err(IrError::MissingData(_))
+        ));
+
+        mode.name = "fraud_rings".to_string();
+        logical_plan.set_continuous(mode.clone());
+        let plan_pb: pb::LogicalPlan = logical_plan.clone().into();
+        assert_eq!(plan_pb.continuous, Some(mode.clone()));
+        let logical_plan = LogicalPlan::try_from(plan_pb).unwrap();
+        assert_eq!(logical_plan.get_continuous(), Some(&mode));
+
+        let mut builder = PlanBuilder::default();
+        let mut plan_meta = PlanMeta::default();
+        logical_plan
+            .add_job_builder(&mut builder, &mut plan_meta)
+            .unwrap();
+        assert_eq!(builder.build().continuous, Some(mode));
+    }
+
+    #[test]
+    fn apply_with_timeout_as_physical() {
+        let mut plan = LogicalPlan::default();
+        // g.V().where(out()), of which the subtask times out in 1s
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let opr_id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let root_id = plan
+            .append_operator_as_node(expand.into(), vec![])
+            .unwrap();
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: root_id as i32,
+            alias: None,
+            timeout_ms: 1000,
+            captures: vec![],
+        };
+        plan.append_operator_as_node(apply.into(), vec![opr_id])
+            .unwrap();
+
+        let mut builder = PlanBuilder::default();
+        let mut meta = plan.meta.clone();
+        plan.add_job_builder(&mut builder, &mut meta)
+            .unwrap();
+        let timeouts: Vec<u64> = builder
+            .take()
+            .into_iter()
+            .filter_map(|op| match op.opr.and_then(|opr| opr.op_kind) {
+                Some(physical_pb::physical_opr::operator::OpKind::Apply(apply)) => Some(apply.timeout_ms),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(timeouts, vec![1000]);
+    }
+
+    #[test]
+    fn super_node_policy_as_physical() {
+        let mut plan = LogicalPlan::default();
+        // g.V().where(out()), of which the super nodes are truncated to 10 neighbors
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let opr_id = plan
+            .append_operator_as_node(scan.into(), vec![])
+            .unwrap();
+        let expand = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![])),
+            expand_opt: 0,
+            alias: None,
+            meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
+        };
+        let root_id = plan
+            .append_operator_as_node(expand.into(), vec![])
+            .unwrap();
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: root_id as i32,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
+        plan.append_operator_as_node(apply.into(), vec![opr_id])
+            .unwrap();
+        let policy = pb::SuperNodePolicy { action: 0, threshold: 10 };
+        plan.set_super_node_policy(policy.clone());
+        // the policy is preserved while serializing the plan
+        let plan_pb: pb::LogicalPlan = plan.clone().into();
+        assert_eq!(plan_pb.super_node_policy, Some(policy.clone()));
+        let plan = LogicalPlan::try_from(plan_pb).unwrap();
+        assert_eq!(plan.get_super_node_policy(), Some(&policy));
+
+        let mut builder = PlanBuilder::default();
+        let mut meta = plan.meta.clone();
+        plan.add_job_builder(&mut builder, &mut meta)
+            .unwrap();
+        let physical_plan = builder.build();
+        assert_eq!(physical_plan.super_node_policy, Some(policy.clone()));
+        let policies: Vec<Option<pb::SuperNodePolicy>> = physical_plan
+            .plan
+            .into_iter()
+            .filter_map(|op| match op.opr.and_then(|opr| opr.op_kind) {
+                Some(physical_pb::physical_opr::operator::OpKind::Apply(apply)) => Some(apply),
+                _ => None,
+            })
+            .flat_map(|apply| apply.sub_plan.unwrap().plan)
+            .filter_map(|op| match op.opr.and_then(|opr| opr.op_kind) {
+                Some(physical_pb::physical_opr::operator::OpKind::Edge(edge)) => {
+                    Some(edge.super_node_policy)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(policies, vec![Some(policy)]);
+
+        let mut plan = plan;
+        plan.set_super_node_policy(pb::SuperNodePolicy { action: 0, threshold: 0 });
+        let mut builder = PlanBuilder::default();
+        let mut meta = plan.meta.clone();
+        assert!(plan
+            .add_job_builder(&mut builder, &mut meta)
+            .is_err());
+    }
+
     #[test]
     fn apply_as_physical_case1() {
         let mut plan = LogicalPlan::default();
@@ -1820,6 +3116,8 @@ mod test {
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let opr_id = plan
@@ -1834,6 +3132,11 @@ mod test {
             expand_opt: 0,
             alias: Some(1.into()),
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let root_id = plan
@@ -1845,7 +3148,14 @@ mod test {
         plan.append_operator_as_node(select.clone().into(), vec![root_id])
             .unwrap();
 
-        let apply = pb::Apply { join_kind: 4, tags: vec![], subtask: root_id as i32, alias: None };
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: root_id as i32,
+            alias: None,
+            timeout_ms: 0,
+            captures: vec![],
+        };
         let opr_id = plan
             .append_operator_as_node(apply.clone().into(), vec![opr_id])
             .unwrap();
@@ -1907,6 +3217,8 @@ mod test {
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let opr_id = plan
@@ -1920,8 +3232,14 @@ mod test {
             .unwrap();
 
         // Select("0").by()
-        let apply =
-            pb::Apply { join_kind: 4, tags: vec![], subtask: subplan_id as i32, alias: Some(1.into()) };
+        let apply = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: subplan_id as i32,
+            alias: Some(1.into()),
+            timeout_ms: 0,
+            captures: vec![],
+        };
         plan.append_operator_as_node(apply.clone().into(), vec![opr_id])
             .unwrap();
 
@@ -1970,6 +3288,8 @@ mod test {
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let opr_id = plan
@@ -1993,9 +3313,15 @@ mod test {
             .unwrap();
 
         // Select().by()
-        let apply: pb::logical_plan::Operator =
-            pb::Apply { join_kind: 4, tags: vec![], subtask: subplan_id as i32, alias: Some(1.into()) }
-                .into();
+        let apply: pb::logical_plan::Operator = pb::Apply {
+            join_kind: 4,
+            tags: vec![],
+            subtask: subplan_id as i32,
+            alias: Some(1.into()),
+            timeout_ms: 0,
+            captures: vec![],
+        }
+        .into();
         plan.append_operator_as_node(apply.clone(), vec![opr_id])
             .unwrap();
 
@@ -2038,6 +3364,8 @@ mod test {
             params: Some(query_params(vec![], vec![])),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
         let expand_opr = pb::EdgeExpand {
             v_tag: None,
@@ -2046,9 +3374,14 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
-        let join_opr = pb::Join { left_keys: vec![], right_keys: vec![], kind: 0 };
-        let limit_opr = pb::Limit { range: Some(pb::Range { lower: 10, upper: 11 }) };
+        let join_opr = pb::Join { left_keys: vec![], right_keys: vec![], kind: 0, renames: vec![] };
+        let limit_opr = pb::Limit { range: Some(pb::Range { lower: 10, upper: 11 }), per_partition: false };
 
         let mut logical_plan = LogicalPlan::with_root(Node::new(0, source_opr.clone().into()));
         logical_plan
@@ -2097,6 +3430,8 @@ mod test {
             params: None,
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         // extend 0->1
@@ -2107,6 +3442,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let get_b = pb::GetV {
@@ -2115,6 +3455,8 @@ mod test {
             params: None,
             alias: Some(1.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         // extend 0->2, 1->2, and intersect on 2
@@ -2125,6 +3467,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let mut expand_ac_opr_vertex = expand_ac_opr_edge.clone();
@@ -2138,6 +3485,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let mut expand_bc_opr_vertex = expand_bc_opr_edge.clone();
@@ -2150,6 +3502,8 @@ mod test {
             params: None,
             alias: Some(2.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         // parents are expand_ac_opr and expand_bc_opr
@@ -2192,6 +3546,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Vertex as i32,
             alias: Some(1.into()),
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let mut expected_builder = PlanBuilder::default();
         expected_builder.add_scan_source(source_opr);
@@ -2214,6 +3573,8 @@ mod test {
             params: None,
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         // extend 0->1
@@ -2224,6 +3585,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let get_b = pb::GetV {
@@ -2232,6 +3598,8 @@ mod test {
             params: None,
             alias: Some(1.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         // extend 0->2, 1->2, and intersect on 2
@@ -2242,6 +3610,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let mut expand_ac_opr_vertex = expand_ac_opr_edge.clone();
@@ -2255,6 +3628,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let mut expand_bc_opr_vertex = expand_bc_opr_edge.clone();
@@ -2267,6 +3645,8 @@ mod test {
             params: Some(query_params(vec!["person".into()], vec![])),
             alias: Some(2.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let mut get_c_filter = get_c.clone();
@@ -2313,6 +3693,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Vertex as i32,
             alias: Some(1.into()),
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let mut expected_builder = PlanBuilder::default();
         expected_builder.add_scan_source(source_opr);
@@ -2327,4 +3712,113 @@ mod test {
         expected_builder.get_v(get_c_filter);
         assert_eq!(builder, expected_builder);
     }
+
+    #[test]
+    fn bind_plan_params_of_scan() {
+        let id_triplet = |param: &str| pb::index_predicate::Triplet {
+            key: Some(common_pb::Property {
+                item: Some(common_pb::property::Item::Id(common_pb::IdKey {})),
+            }),
+            value: None,
+            cmp: None,
+            param: Some(common_pb::DynamicParam { name: param.to_string(), index: 0, data_type: None }),
+        };
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![])),
+            idx_predicate: Some(pb::IndexPredicate {
+                or_predicates: vec![pb::index_predicate::AndPredicate {
+                    predicates: vec![id_triplet("ids")],
+                }],
+            }),
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let mut builder = PlanBuilder::default();
+        builder.add_scan_source(source_opr);
+        let plan = builder.build();
+
+        let mut params = PlanParams::new();
+        params.insert("ids".to_string(), vec![1i64.into(), 2i64.into(), 3i64.into()]);
+        let mut bound_plan = plan.clone();
+        bind_plan_params(&mut bound_plan, &params).unwrap();
+        let bound_values: Vec<Option<common_pb::Value>> = match bound_plan.plan[0]
+            .opr
+            .as_ref()
+            .and_then(|opr| opr.op_kind.as_ref())
+        {
+            Some(physical_pb::physical_opr::operator::OpKind::Scan(scan)) => scan
+                .idx_predicate
+                .as_ref()
+                .unwrap()
+                .or_predicates
+                .iter()
+                .map(|and_pred| {
+                    assert_eq!(and_pred.predicates.len(), 1);
+                    assert!(and_pred.predicates[0].param.is_none());
+                    and_pred.predicates[0].value.clone()
+                })
+                .collect(),
+            _ => panic!("should be a scan"),
+        };
+        assert_eq!(bound_values, vec![Some(1i64.into()), Some(2i64.into()), Some(3i64.into())]);
+
+        // the plan is rebound per submission
+        params.insert("ids".to_string(), vec![4i64.into()]);
+        let mut bound_plan = plan.clone();
+        bind_plan_params(&mut bound_plan, &params).unwrap();
+        assert_ne!(bound_plan, plan);
+
+        // unbound parameter
+        let mut bound_plan = plan.clone();
+        let result = bind_plan_params(&mut bound_plan, &PlanParams::new());
+        assert!(matches!(result, Err(IrError::MissingData(_))));
+    }
+
+    #[test]
+    fn scan_partitions() {
+        let schema = Schema::new(
+            vec![("person".to_string(), 0), ("software".to_string(), 1), ("place".to_string(), 2)],
+            vec![],
+            vec![],
+        )
+        .with_label_partitions(vec![(0, vec![1, 0]), (1, vec![2])]);
+        let store_meta = Arc::new(StoreMeta { schema: Some(schema), version: 0 });
+        let scan_partitions = |tables: Vec<&str>, is_partition: bool| {
+            let mut plan = LogicalPlan::default();
+            plan.meta
+                .set_store_meta(Some(store_meta.clone()));
+            let mut scan = build_scan(vec![]);
+            scan.params.as_mut().unwrap().tables = tables
+                .into_iter()
+                .map(|table| table.into())
+                .collect();
+            plan.append_operator_as_node(scan.into(), vec![])
+                .unwrap();
+            let mut job_builder = PlanBuilder::default();
+            let mut plan_meta = plan.meta.clone();
+            if is_partition {
+                plan_meta = plan_meta.with_partition();
+            }
+            plan.add_job_builder(&mut job_builder, &mut plan_meta)
+                .unwrap();
+            match job_builder[0]
+                .opr
+                .as_ref()
+                .and_then(|opr| opr.op_kind.as_ref())
+            {
+                Some(physical_pb::physical_opr::operator::OpKind::Scan(scan)) => scan.partitions.clone(),
+                _ => panic!("should be a scan"),
+            }
+        };
+
+        assert_eq!(scan_partitions(vec!["person"], true), vec![0, 1]);
+        assert_eq!(scan_partitions(vec!["person", "software"], true), vec![0, 1, 2]);
+        // not partitioned, or some label of undeclared partitions, or all the labels
+        assert!(scan_partitions(vec!["person"], false).is_empty());
+        assert!(scan_partitions(vec!["person", "place"], true).is_empty());
+        assert!(scan_partitions(vec![], true).is_empty());
+    }
 }
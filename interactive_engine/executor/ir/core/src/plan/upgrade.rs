@@ -0,0 +1,170 @@
+//
+//! Copyright 2023 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The upgrade of the pb-`LogicalPlan`s serialized by the former versions of the library into the
+//! current format, such that the plans cached by the callers survive the upgrades of the library.
+//!
+//! A plan is stamped with [`PLAN_FORMAT_VERSION`] once it is serialized, and is upgraded on load by
+//! the steps from its version onwards, where the step of a version translates a plan of the version
+//! into one of the next version. Once an operator evolves such that a plan of the former format no
+//! longer means the same, e.g., a field gets a default other than its zero value, or is superseded
+//! by another field, the version is bumped, and a step of the former version is appended to
+//! [`UPGRADE_STEPS`], which must never change afterwards.
+
+use ir_common::error::ParsePbError;
+use ir_common::generated::algebra as pb;
+
+/// The version of the format of the plans serialized by the library.
+pub const PLAN_FORMAT_VERSION: u32 = 1;
+
+/// A step translating a plan of a version into one of the next version.
+type UpgradeStep = fn(&mut pb::LogicalPlan) -> Result<(), ParsePbError>;
+
+/// The steps of upgrading the plans, where the step of the index `v` upgrades a plan of the
+/// version `v` to the version `v + 1`.
+const UPGRADE_STEPS: [UpgradeStep; PLAN_FORMAT_VERSION as usize] = [upgrade_unversioned];
+
+/// Upgrade the plan to the current format, which returns whether it has been upgraded, or fails
+/// if the plan is serialized by a newer version of the library, as it cannot be downgraded.
+pub fn upgrade_plan(plan: &mut pb::LogicalPlan) -> Result<bool, ParsePbError> {
+    if plan.format_version > PLAN_FORMAT_VERSION {
+        return Err(ParsePbError::ParseError(format!(
+            "the plan of the format version {} is newer than the supported version {}",
+            plan.format_version, PLAN_FORMAT_VERSION
+        )));
+    }
+    let from_version = plan.format_version;
+    for step in &UPGRADE_STEPS[from_version as usize..] {
+        step(plan)?;
+    }
+    plan.format_version = PLAN_FORMAT_VERSION;
+
+    Ok(from_version < PLAN_FORMAT_VERSION)
+}
+
+/// The plans serialized before the format was versioned may leave the sample ratio of the query
+/// parameters unset, i.e., 0, which is taken as no sampling, i.e., the documented default 1.0.
+fn upgrade_unversioned(plan: &mut pb::LogicalPlan) -> Result<(), ParsePbError> {
+    for node in plan.nodes.iter_mut() {
+        if let Some(opr) = node.opr.as_mut() {
+            for_each_params_mut(opr, &mut |params| {
+                if params.sample_ratio == 0.0 {
+                    params.sample_ratio = 1.0;
+                }
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `f` on each query parameters of the operator, including those of the binders of a pattern.
+fn for_each_params_mut<F: FnMut(&mut pb::QueryParams)>(opr: &mut pb::logical_plan::Operator, f: &mut F) {
+    use pb::logical_plan::operator::Opr;
+    use pb::pattern::binder::Item;
+
+    match opr.opr.as_mut() {
+        Some(Opr::Scan(scan)) => scan.params.iter_mut().for_each(f),
+        Some(Opr::Edge(edgexpd)) => edgexpd.params.iter_mut().for_each(f),
+        Some(Opr::Vertex(getv)) => getv.params.iter_mut().for_each(f),
+        Some(Opr::Path(pathxpd)) => path_params_mut(pathxpd, f),
+        Some(Opr::Pattern(pattern)) => {
+            for binder in pattern
+                .sentences
+                .iter_mut()
+                .flat_map(|sentence| sentence.binders.iter_mut())
+            {
+                match binder.item.as_mut() {
+                    Some(Item::Edge(edgexpd)) => edgexpd.params.iter_mut().for_each(&mut *f),
+                    Some(Item::Vertex(getv)) => getv.params.iter_mut().for_each(&mut *f),
+                    Some(Item::Path(pathxpd)) => path_params_mut(pathxpd, f),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn path_params_mut<F: FnMut(&mut pb::QueryParams)>(pathxpd: &mut pb::PathExpand, f: &mut F) {
+    if let Some(base) = pathxpd.base.as_mut() {
+        if let Some(edgexpd) = base.edge_expand.as_mut() {
+            edgexpd.params.iter_mut().for_each(&mut *f);
+        }
+        if let Some(getv) = base.get_v.as_mut() {
+            getv.params.iter_mut().for_each(&mut *f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scan_plan(sample_ratio: f64, format_version: u32) -> pb::LogicalPlan {
+        let scan = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(pb::QueryParams {
+                tables: vec![],
+                columns: vec![],
+                is_all_columns: false,
+                limit: None,
+                predicate: None,
+                sample_ratio,
+                extra: Default::default(),
+                degree_predicates: vec![],
+            }),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        pb::LogicalPlan {
+            nodes: vec![pb::logical_plan::Node {
+                opr: Some(scan.into()),
+                children: vec![],
+                annotations: vec![],
+                display_name: "".to_string(),
+                accesses: vec![],
+                attributes: Default::default(),
+            }],
+            roots: vec![0],
+            annotations: vec![],
+            allow_partial: false,
+            super_node_policy: None,
+            format_version,
+            origin: None,
+            continuous: None,
+            read_scope: None,
+            view: None,
+        }
+    }
+
+    #[test]
+    fn upgrade_unversioned_plan() {
+        let mut plan = scan_plan(0.0, 0);
+        assert!(upgrade_plan(&mut plan).unwrap());
+        assert_eq!(plan, scan_plan(1.0, PLAN_FORMAT_VERSION));
+
+        // a plan of the current format is left as it is
+        let mut plan = scan_plan(0.0, PLAN_FORMAT_VERSION);
+        assert!(!upgrade_plan(&mut plan).unwrap());
+        assert_eq!(plan, scan_plan(0.0, PLAN_FORMAT_VERSION));
+
+        let mut plan = scan_plan(1.0, PLAN_FORMAT_VERSION + 1);
+        assert!(upgrade_plan(&mut plan).is_err());
+    }
+}
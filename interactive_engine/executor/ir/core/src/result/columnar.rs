@@ -0,0 +1,557 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+use std::convert::TryFrom;
+
+use ir_common::error::ParsePbError;
+use ir_common::generated::common as common_pb;
+use ir_common::generated::results as result_pb;
+use ir_common::NameOrId;
+
+use crate::error::{IrError, IrResult};
+use crate::result::ResultRecord;
+
+/// The data of a column, in which the `i`-th item belongs to the `i`-th record of the batch.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnData {
+    Vertex {
+        ids: Vec<i64>,
+        labels: Vec<Option<common_pb::NameOrId>>,
+    },
+    Edge {
+        ids: Vec<i64>,
+        labels: Vec<Option<common_pb::NameOrId>>,
+        src_ids: Vec<i64>,
+        dst_ids: Vec<i64>,
+    },
+    Boolean(Vec<bool>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    F64(Vec<f64>),
+    Str(Vec<String>),
+    /// A column of the given number of entries that are all null, of which the type is unknown.
+    Null(usize),
+}
+
+impl ColumnData {
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnData::Vertex { ids, .. } => ids.len(),
+            ColumnData::Edge { ids, .. } => ids.len(),
+            ColumnData::Boolean(data) => data.len(),
+            ColumnData::I32(data) => data.len(),
+            ColumnData::I64(data) => data.len(),
+            ColumnData::F64(data) => data.len(),
+            ColumnData::Str(data) => data.len(),
+            ColumnData::Null(len) => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn with_element(element: &result_pb::Element) -> IrResult<Self> {
+        let data = match &element.inner {
+            Some(result_pb::element::Inner::Vertex(_)) => {
+                ColumnData::Vertex { ids: vec![], labels: vec![] }
+            }
+            Some(result_pb::element::Inner::Edge(_)) => {
+                ColumnData::Edge { ids: vec![], labels: vec![], src_ids: vec![], dst_ids: vec![] }
+            }
+            Some(result_pb::element::Inner::Object(o)) => match &o.item {
+                Some(common_pb::value::Item::Boolean(_)) => ColumnData::Boolean(vec![]),
+                Some(common_pb::value::Item::I32(_)) => ColumnData::I32(vec![]),
+                Some(common_pb::value::Item::I64(_)) => ColumnData::I64(vec![]),
+                Some(common_pb::value::Item::F64(_)) => ColumnData::F64(vec![]),
+                Some(common_pb::value::Item::Str(_)) => ColumnData::Str(vec![]),
+                _ => Err(IrError::Unsupported(format!("columnar layout of object {:?}", o)))?,
+            },
+            _ => Err(IrError::Unsupported(format!("columnar layout of element {:?}", element)))?,
+        };
+
+        Ok(data)
+    }
+
+    /// Push a placeholder of a null entry, i.e., the default value of the type of the column.
+    fn push_null(&mut self) {
+        match self {
+            ColumnData::Vertex { ids, labels } => {
+                ids.push(0);
+                labels.push(None);
+            }
+            ColumnData::Edge { ids, labels, src_ids, dst_ids } => {
+                ids.push(0);
+                labels.push(None);
+                src_ids.push(0);
+                dst_ids.push(0);
+            }
+            ColumnData::Boolean(data) => data.push(false),
+            ColumnData::I32(data) => data.push(0),
+            ColumnData::I64(data) => data.push(0),
+            ColumnData::F64(data) => data.push(0.0),
+            ColumnData::Str(data) => data.push(String::new()),
+            ColumnData::Null(len) => *len += 1,
+        }
+    }
+
+    /// An empty column of the same type as `self`.
+    fn empty_like(&self) -> Self {
+        match self {
+            ColumnData::Vertex { .. } => ColumnData::Vertex { ids: vec![], labels: vec![] },
+            ColumnData::Edge { .. } => {
+                ColumnData::Edge { ids: vec![], labels: vec![], src_ids: vec![], dst_ids: vec![] }
+            }
+            ColumnData::Boolean(_) => ColumnData::Boolean(vec![]),
+            ColumnData::I32(_) => ColumnData::I32(vec![]),
+            ColumnData::I64(_) => ColumnData::I64(vec![]),
+            ColumnData::F64(_) => ColumnData::F64(vec![]),
+            ColumnData::Str(_) => ColumnData::Str(vec![]),
+            ColumnData::Null(_) => ColumnData::Null(0),
+        }
+    }
+
+    fn push(&mut self, element: &result_pb::Element) -> IrResult<()> {
+        use common_pb::value::Item;
+        use result_pb::element::Inner;
+
+        match (self, &element.inner) {
+            (ColumnData::Vertex { ids, labels }, Some(Inner::Vertex(v))) => {
+                ids.push(v.id);
+                labels.push(v.label.clone());
+            }
+            (ColumnData::Edge { ids, labels, src_ids, dst_ids }, Some(Inner::Edge(e))) => {
+                ids.push(e.id);
+                labels.push(e.label.clone());
+                src_ids.push(e.src_id);
+                dst_ids.push(e.dst_id);
+            }
+            (column, Some(Inner::Object(o))) => match (column, &o.item) {
+                (ColumnData::Boolean(data), Some(Item::Boolean(b))) => data.push(*b),
+                (ColumnData::I32(data), Some(Item::I32(i))) => data.push(*i),
+                (ColumnData::I64(data), Some(Item::I64(i))) => data.push(*i),
+                (ColumnData::F64(data), Some(Item::F64(f))) => data.push(*f),
+                (ColumnData::Str(data), Some(Item::Str(s))) => data.push(s.clone()),
+                _ => Err(ParsePbError::ParseError(format!("mixed-typed column with object {:?}", o)))?,
+            },
+            _ => Err(ParsePbError::ParseError(format!("mixed-typed column with element {:?}", element)))?,
+        }
+
+        Ok(())
+    }
+
+    fn append(&mut self, other: ColumnData) -> IrResult<()> {
+        // the entries of an all-null column are typed as the other column
+        if let ColumnData::Null(len) = *self {
+            if !matches!(other, ColumnData::Null(_)) {
+                let mut data = other.empty_like();
+                for _ in 0..len {
+                    data.push_null();
+                }
+                *self = data;
+            }
+        }
+        match (self, other) {
+            (
+                ColumnData::Vertex { ids, labels },
+                ColumnData::Vertex { ids: other_ids, labels: other_labels },
+            ) => {
+                ids.extend(other_ids);
+                labels.extend(other_labels);
+            }
+            (
+                ColumnData::Edge { ids, labels, src_ids, dst_ids },
+                ColumnData::Edge {
+                    ids: other_ids,
+                    labels: other_labels,
+                    src_ids: other_src_ids,
+                    dst_ids: other_dst_ids,
+                },
+            ) => {
+                ids.extend(other_ids);
+                labels.extend(other_labels);
+                src_ids.extend(other_src_ids);
+                dst_ids.extend(other_dst_ids);
+            }
+            (ColumnData::Boolean(data), ColumnData::Boolean(other)) => data.extend(other),
+            (ColumnData::I32(data), ColumnData::I32(other)) => data.extend(other),
+            (ColumnData::I64(data), ColumnData::I64(other)) => data.extend(other),
+            (ColumnData::F64(data), ColumnData::F64(other)) => data.extend(other),
+            (ColumnData::Str(data), ColumnData::Str(other)) => data.extend(other),
+            (data, ColumnData::Null(len)) => {
+                for _ in 0..len {
+                    data.push_null();
+                }
+            }
+            (_, other) => Err(ParsePbError::ParseError(format!("mixed-typed column with {:?}", other)))?,
+        }
+
+        Ok(())
+    }
+}
+
+/// A batch of records in the column-oriented layout, where each column holds the entries
+/// of the same tag across all records, and the column without a tag refers to the head.
+/// All the records in a batch must share the same tags, and the entries of a tag must be
+/// of the same type. Collections and paths are not supported in this layout.
+/// A record without an entry of the column is null in the column, which holds the default
+/// value of the type of the column in place.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultBatch {
+    num_rows: usize,
+    columns: Vec<(Option<NameOrId>, ColumnData)>,
+    /// The nulls of each column, in the order of the columns, where the `i`-th flag is set
+    /// if the `i`-th entry is null, or empty if no entry of the column is null.
+    nulls: Vec<Vec<bool>>,
+}
+
+impl ResultBatch {
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn columns(&self) -> &[(Option<NameOrId>, ColumnData)] {
+        &self.columns
+    }
+
+    pub fn get_column_index(&self, tag: Option<&NameOrId>) -> IrResult<usize> {
+        self.columns
+            .iter()
+            .position(|(t, _)| t.as_ref() == tag)
+            .ok_or_else(|| match tag {
+                Some(t) => IrError::TagNotExist(t.clone()),
+                None => IrError::MissingData("the head of the batch".to_string()),
+            })
+    }
+
+    pub fn get_column(&self, tag: Option<&NameOrId>) -> IrResult<&ColumnData> {
+        Ok(&self.columns[self.get_column_index(tag)?].1)
+    }
+
+    /// Get the nulls of the column at `index`, which is empty if no entry of the column is null.
+    pub fn get_nulls(&self, index: usize) -> &[bool] {
+        &self.nulls[index]
+    }
+
+    pub fn from_records(records: &[ResultRecord]) -> IrResult<Self> {
+        let mut batch = ResultBatch { num_rows: records.len(), columns: vec![], nulls: vec![] };
+        if let Some(first) = records.first() {
+            for column in &first.record.columns {
+                let tag = column
+                    .name_or_id
+                    .clone()
+                    .map(NameOrId::try_from)
+                    .transpose()?;
+                let data = ColumnData::with_element(first.get_element(tag.as_ref())?)?;
+                batch.columns.push((tag, data));
+                batch.nulls.push(vec![]);
+            }
+        }
+        for record in records {
+            if record.len() != batch.num_columns() {
+                return Err(ParsePbError::ParseError(format!(
+                    "expect {} columns in each record, but got {}",
+                    batch.num_columns(),
+                    record.len()
+                ))
+                .into());
+            }
+            for (tag, data) in batch.columns.iter_mut() {
+                data.push(record.get_element(tag.as_ref())?)?;
+            }
+        }
+
+        Ok(batch)
+    }
+
+    /// Decode a batch returned by a sink of the columnar layout, where an entry without `inner`
+    /// refers to a record without the column, and is thus decoded as null.
+    pub fn from_column_batch(column_batch: result_pb::ColumnBatch) -> IrResult<Self> {
+        let num_rows = column_batch.num_rows as usize;
+        let mut batch = ResultBatch { num_rows, columns: vec![], nulls: vec![] };
+        for column in column_batch.columns {
+            if column.entries.len() != num_rows {
+                return Err(ParsePbError::ParseError(format!(
+                    "expect {} entries in each column, but got {}",
+                    num_rows,
+                    column.entries.len()
+                ))
+                .into());
+            }
+            let tag = column
+                .name_or_id
+                .map(NameOrId::try_from)
+                .transpose()?;
+            let elements = column
+                .entries
+                .iter()
+                .map(|entry| match &entry.inner {
+                    Some(result_pb::entry::Inner::Element(element)) => Ok(Some(element)),
+                    None => Ok(None),
+                    _ => Err(IrError::Unsupported(format!("columnar layout of entry {:?}", entry))),
+                })
+                .collect::<IrResult<Vec<_>>>()?;
+            let mut data = match elements.iter().flatten().next() {
+                Some(first) => ColumnData::with_element(first)?,
+                None => ColumnData::Null(0),
+            };
+            let mut nulls = vec![];
+            for (i, element) in elements.iter().enumerate() {
+                match element {
+                    Some(element) => data.push(element)?,
+                    None => {
+                        if nulls.is_empty() {
+                            nulls.resize(num_rows, false);
+                        }
+                        nulls[i] = true;
+                        data.push_null();
+                    }
+                }
+            }
+            if num_rows > 0 {
+                batch.columns.push((tag, data));
+                batch.nulls.push(nulls);
+            }
+        }
+
+        Ok(batch)
+    }
+
+    /// Append the records of `other` to the batch, which must be of the same tags in the same order.
+    fn append(&mut self, other: ResultBatch) -> IrResult<()> {
+        if other.num_rows == 0 {
+            return Ok(());
+        }
+        if self.num_rows == 0 {
+            *self = other;
+            return Ok(());
+        }
+        if self.columns.len() != other.columns.len()
+            || self
+                .columns
+                .iter()
+                .zip(other.columns.iter())
+                .any(|((tag, _), (other_tag, _))| tag != other_tag)
+        {
+            return Err(ParsePbError::ParseError(format!(
+                "expect the columns {:?} in each batch",
+                self.columns
+                    .iter()
+                    .map(|(tag, _)| tag)
+                    .collect::<Vec<_>>()
+            ))
+            .into());
+        }
+        for (nulls, other_nulls) in self.nulls.iter_mut().zip(other.nulls) {
+            if !nulls.is_empty() || !other_nulls.is_empty() {
+                nulls.resize(self.num_rows, false);
+                if other_nulls.is_empty() {
+                    nulls.resize(self.num_rows + other.num_rows, false);
+                } else {
+                    nulls.extend(other_nulls);
+                }
+            }
+        }
+        self.num_rows += other.num_rows;
+        for ((_, data), (_, other_data)) in self.columns.iter_mut().zip(other.columns) {
+            data.append(other_data)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<result_pb::CollectiveResults> for ResultBatch {
+    type Error = IrError;
+
+    /// Decode the results of either layout, where the consecutive records of the row layout are
+    /// gathered into a batch, and the batches are concatenated in order.
+    fn try_from(results: result_pb::CollectiveResults) -> IrResult<Self> {
+        let mut batch = ResultBatch::default();
+        let mut records = vec![];
+        for results in results.results {
+            match results.inner {
+                Some(result_pb::results::Inner::ColumnBatch(column_batch)) => {
+                    batch.append(Self::from_records(&std::mem::take(&mut records))?)?;
+                    batch.append(Self::from_column_batch(column_batch)?)?;
+                }
+                _ => records.push(ResultRecord::try_from(results)?),
+            }
+        }
+        batch.append(Self::from_records(&records)?)?;
+
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn object_column(tag: &str, value: common_pb::Value) -> result_pb::Column {
+        result_pb::Column {
+            name_or_id: Some(tag.into()),
+            entry: Some(result_pb::Entry {
+                inner: Some(result_pb::entry::Inner::Element(result_pb::Element {
+                    inner: Some(result_pb::element::Inner::Object(value)),
+                })),
+            }),
+        }
+    }
+
+    fn vertex_column(id: i64) -> result_pb::Column {
+        result_pb::Column {
+            name_or_id: None,
+            entry: Some(result_pb::Entry {
+                inner: Some(result_pb::entry::Inner::Element(result_pb::Element {
+                    inner: Some(result_pb::element::Inner::Vertex(result_pb::Vertex {
+                        id,
+                        label: Some(0.into()),
+                        properties: vec![],
+                    })),
+                })),
+            }),
+        }
+    }
+
+    #[test]
+    fn records_to_columns() {
+        let records: Vec<ResultRecord> = (0..3)
+            .map(|i| {
+                result_pb::Record {
+                    columns: vec![
+                        vertex_column(i),
+                        object_column("a", (i * 10).into()),
+                        object_column("b", format!("{}", i).into()),
+                    ],
+                }
+                .into()
+            })
+            .collect();
+        let batch = ResultBatch::from_records(&records).unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), 3);
+        assert_eq!(
+            batch.get_column(None).unwrap(),
+            &ColumnData::Vertex { ids: vec![0, 1, 2], labels: vec![Some(0.into()); 3] }
+        );
+        assert_eq!(batch.get_column(Some(&"a".into())).unwrap(), &ColumnData::I64(vec![0, 10, 20]));
+        assert_eq!(
+            batch.get_column(Some(&"b".into())).unwrap(),
+            &ColumnData::Str(vec!["0".to_string(), "1".to_string(), "2".to_string()])
+        );
+        assert!(batch.get_column(Some(&"c".into())).is_err());
+    }
+
+    #[test]
+    fn column_batches_to_columns() {
+        let column_batch = |ids: Vec<i64>| result_pb::Results {
+            inner: Some(result_pb::results::Inner::ColumnBatch(result_pb::ColumnBatch {
+                columns: vec![result_pb::column_batch::Column {
+                    name_or_id: None,
+                    entries: ids
+                        .iter()
+                        .filter_map(|&id| vertex_column(id).entry)
+                        .collect(),
+                }],
+                num_rows: ids.len() as i32,
+            })),
+        };
+        let record = |id: i64| result_pb::Results {
+            inner: Some(result_pb::results::Inner::Record(result_pb::Record {
+                columns: vec![vertex_column(id)],
+            })),
+        };
+        // the batches and the records in between are concatenated in order
+        let results = result_pb::CollectiveResults {
+            results: vec![column_batch(vec![0, 1]), record(2), column_batch(vec![]), column_batch(vec![3])],
+            meta: None,
+        };
+        let batch = ResultBatch::try_from(results).unwrap();
+        assert_eq!(batch.num_rows(), 4);
+        assert_eq!(
+            batch.get_column(None).unwrap(),
+            &ColumnData::Vertex { ids: vec![0, 1, 2, 3], labels: vec![Some(0.into()); 4] }
+        );
+
+        // the number of entries does not match that of the records
+        let mut invalid = column_batch(vec![0, 1]);
+        if let Some(result_pb::results::Inner::ColumnBatch(column_batch)) = invalid.inner.as_mut() {
+            column_batch.num_rows = 3;
+        }
+        assert!(ResultBatch::try_from(result_pb::CollectiveResults { results: vec![invalid], meta: None })
+            .is_err());
+    }
+
+    #[test]
+    fn column_batches_with_nulls() {
+        let column_batch = |entries: Vec<Option<i64>>| result_pb::Results {
+            inner: Some(result_pb::results::Inner::ColumnBatch(result_pb::ColumnBatch {
+                num_rows: entries.len() as i32,
+                columns: vec![result_pb::column_batch::Column {
+                    name_or_id: Some("a".into()),
+                    entries: entries
+                        .into_iter()
+                        .map(|value| match value {
+                            Some(value) => object_column("a", value.into()).entry.unwrap(),
+                            None => result_pb::Entry::default(),
+                        })
+                        .collect(),
+                }],
+            })),
+        };
+        // an all-null batch ahead of the typed ones is typed as them
+        let results = result_pb::CollectiveResults {
+            results: vec![
+                column_batch(vec![None]),
+                column_batch(vec![Some(1), None]),
+                column_batch(vec![Some(3)]),
+            ],
+            meta: None,
+        };
+        let batch = ResultBatch::try_from(results).unwrap();
+        assert_eq!(batch.num_rows(), 4);
+        assert_eq!(batch.get_column(Some(&"a".into())).unwrap(), &ColumnData::I64(vec![0, 1, 0, 3]));
+        assert_eq!(batch.get_nulls(0), &[true, false, true, false]);
+
+        let batch = ResultBatch::try_from(result_pb::CollectiveResults {
+            results: vec![column_batch(vec![Some(1), Some(2)])],
+            meta: None,
+        })
+        .unwrap();
+        assert!(batch.get_nulls(0).is_empty());
+
+        let batch = ResultBatch::try_from(result_pb::CollectiveResults {
+            results: vec![column_batch(vec![None, None])],
+            meta: None,
+        })
+        .unwrap();
+        assert_eq!(batch.get_column(Some(&"a".into())).unwrap(), &ColumnData::Null(2));
+        assert_eq!(batch.get_nulls(0), &[true, true]);
+    }
+
+    #[test]
+    fn mixed_typed_columns() {
+        let records: Vec<ResultRecord> = vec![
+            result_pb::Record { columns: vec![object_column("a", 1_i64.into())] }.into(),
+            result_pb::Record { columns: vec![object_column("a", "1".to_string().into())] }.into(),
+        ];
+        assert!(ResultBatch::from_records(&records).is_err());
+    }
+}
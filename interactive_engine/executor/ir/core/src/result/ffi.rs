@@ -0,0 +1,522 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The C-like apis for the Gaia client to access the records returned from the engine.
+//! A record is decoded from the bytes of a `results::Results` via [`decode_result_record`],
+//! after which its columns can be fetched by tag as vertices, edges, paths or objects.
+//!
+//! Alternatively, a batch of records can be decoded via [`decode_result_batch`] in the
+//! column-oriented layout, where each column is fetched as arrays at once.
+//!
+//! Any string (e.g., the name of a label) returned from these apis is owned by the record
+//! (or the path) that it is fetched from, which remains valid until the record (or the path)
+//! is destroyed. The caller **must not** deallocate these strings.
+//!
+//! The records, the paths and the batches are given to the caller as handles (see
+//! `plan::ffi::handle`), such that a handle that is destroyed, or is of another type, is reported
+//! as `InvalidHandleError`, or as the last error by the apis returning a plain value.
+
+use std::convert::{TryFrom, TryInto};
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+
+use ir_common::generated::common as common_pb;
+use ir_common::generated::results as result_pb;
+use ir_common::NameOrId;
+
+use crate::error::IrError;
+use crate::plan::ffi::{
+    catch_panic, destroy_handle, handle, ptr_to_pb, set_output, FfiConst, FfiDataType, FfiNameIdOpt,
+    FfiNameOrId, FfiPbPointer, FfiResult, ResultCode,
+};
+use crate::result::columnar::{ColumnData, ResultBatch};
+use crate::result::ResultRecord;
+
+/// Keep the C-like strings that have been handed out to the caller alive.
+#[derive(Default)]
+struct CStrPool {
+    cstrs: Vec<CString>,
+}
+
+impl CStrPool {
+    fn alloc(&mut self, str: &str) -> Result<*const c_char, FfiResult> {
+        let cstr = CString::new(str).map_err(|_| {
+            FfiResult::new(ResultCode::CStringError, "error parsing Rust string into C string".to_string())
+        })?;
+        let ptr = cstr.as_ptr();
+        self.cstrs.push(cstr);
+
+        Ok(ptr)
+    }
+
+    fn name_or_id(&mut self, pb: Option<&common_pb::NameOrId>) -> Result<FfiNameOrId, FfiResult> {
+        let mut ffi = FfiNameOrId::default();
+        match pb.and_then(|p| p.item.as_ref()) {
+            Some(common_pb::name_or_id::Item::Name(name)) => {
+                ffi.opt = FfiNameIdOpt::Name;
+                ffi.name = self.alloc(name)?;
+            }
+            Some(common_pb::name_or_id::Item::Id(id)) => {
+                ffi.opt = FfiNameIdOpt::Id;
+                ffi.name_id = *id;
+            }
+            None => {}
+        }
+
+        Ok(ffi)
+    }
+
+    fn vertex(&mut self, v: &result_pb::Vertex) -> Result<FfiVertex, FfiResult> {
+        Ok(FfiVertex { id: v.id, label: self.name_or_id(v.label.as_ref())? })
+    }
+
+    fn edge(&mut self, e: &result_pb::Edge) -> Result<FfiEdge, FfiResult> {
+        Ok(FfiEdge {
+            id: e.id,
+            label: self.name_or_id(e.label.as_ref())?,
+            src_id: e.src_id,
+            src_label: self.name_or_id(e.src_label.as_ref())?,
+            dst_id: e.dst_id,
+            dst_label: self.name_or_id(e.dst_label.as_ref())?,
+        })
+    }
+
+    fn object(&mut self, value: &common_pb::Value) -> Result<FfiConst, FfiResult> {
+        let mut ffi = FfiConst::default();
+        match &value.item {
+            Some(common_pb::value::Item::Boolean(b)) => {
+                ffi.data_type = FfiDataType::Boolean;
+                ffi.boolean = *b;
+            }
+            Some(common_pb::value::Item::I32(i)) => {
+                ffi.data_type = FfiDataType::I32;
+                ffi.int32 = *i;
+            }
+            Some(common_pb::value::Item::I64(i)) => {
+                ffi.data_type = FfiDataType::I64;
+                ffi.int64 = *i;
+            }
+            Some(common_pb::value::Item::F64(f)) => {
+                ffi.data_type = FfiDataType::F64;
+                ffi.float64 = *f;
+            }
+            Some(common_pb::value::Item::Str(s)) => {
+                ffi.data_type = FfiDataType::Str;
+                ffi.cstr = self.alloc(s)?;
+            }
+            // TODO: add support for other type
+            _ => {
+                return Err(FfiResult::new(
+                    ResultCode::UnknownTypeError,
+                    format!("unsupported object type {:?}", value.item),
+                ))
+            }
+        }
+
+        Ok(ffi)
+    }
+}
+
+struct FfiRecord {
+    record: ResultRecord,
+    pool: CStrPool,
+}
+
+struct FfiPath {
+    path: result_pb::GraphPath,
+    pool: CStrPool,
+}
+
+#[repr(C)]
+#[derive(Default)]
+pub struct FfiVertex {
+    id: i64,
+    label: FfiNameOrId,
+}
+
+#[repr(C)]
+#[derive(Default)]
+pub struct FfiEdge {
+    id: i64,
+    label: FfiNameOrId,
+    src_id: i64,
+    src_label: FfiNameOrId,
+    dst_id: i64,
+    dst_label: FfiNameOrId,
+}
+
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FfiPathElementOpt {
+    Vertex = 0,
+    Edge = 1,
+}
+
+/// An element of a path, which is a vertex if `opt` is `Vertex`, or an edge otherwise.
+#[repr(C)]
+pub struct FfiPathElement {
+    opt: FfiPathElementOpt,
+    vertex: FfiVertex,
+    edge: FfiEdge,
+}
+
+fn ffi_to_tag(tag: FfiNameOrId) -> Result<Option<NameOrId>, FfiResult> {
+    let tag_pb: Option<common_pb::NameOrId> = tag.try_into()?;
+    Ok(tag_pb
+        .map(NameOrId::try_from)
+        .transpose()
+        .map_err(IrError::from)?)
+}
+
+/// Decode a record from the bytes of a `results::Results` returned from the engine. The pointer
+/// of the record is owned by Rust, which must be released via [`destroy_result_record`].
+#[no_mangle]
+pub extern "C" fn decode_result_record(
+    pb_results: FfiPbPointer, ptr_record: *mut *const c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = ptr_to_pb::<result_pb::Results>(pb_results).and_then(|results| {
+            let record = FfiRecord { record: ResultRecord::try_from(results)?, pool: CStrPool::default() };
+            Ok(handle::register::<_, c_void>(record))
+        });
+        set_output(ptr_record, result)
+    })
+}
+
+/// To destroy a record, as well as the strings fetched from it.
+#[no_mangle]
+pub extern "C" fn destroy_result_record(ptr_record: *const c_void) {
+    catch_panic(|| destroy_handle::<FfiRecord>(ptr_record))
+}
+
+/// Get the number of columns of a record, or -1 if the record is invalid.
+#[no_mangle]
+pub extern "C" fn get_record_len(ptr_record: *const c_void) -> i32 {
+    catch_panic(|| match handle::borrow::<FfiRecord>(ptr_record) {
+        Ok(record) => record.record.len() as i32,
+        Err(e) => {
+            e.discard();
+            -1
+        }
+    })
+}
+
+fn record_get<T, F>(ptr_record: *const c_void, tag: FfiNameOrId, out: *mut T, get: F) -> FfiResult
+where
+    F: FnOnce(&mut FfiRecord, Option<NameOrId>) -> Result<T, FfiResult>,
+{
+    let result = handle::borrow_mut::<FfiRecord>(ptr_record)
+        .and_then(|mut record| ffi_to_tag(tag).and_then(|tag| get(&mut *record, tag)));
+    set_output(out, result)
+}
+
+/// Get the vertex of the given tag from a record, where the tag of `None` refers to the head.
+#[no_mangle]
+pub extern "C" fn record_get_vertex(
+    ptr_record: *const c_void, tag: FfiNameOrId, vertex: *mut FfiVertex,
+) -> FfiResult {
+    catch_panic(|| {
+        record_get(ptr_record, tag, vertex, |r, tag| {
+            let v = r.record.get_vertex(tag.as_ref())?;
+            r.pool.vertex(v)
+        })
+    })
+}
+
+/// Get the edge of the given tag from a record, where the tag of `None` refers to the head.
+#[no_mangle]
+pub extern "C" fn record_get_edge(
+    ptr_record: *const c_void, tag: FfiNameOrId, edge: *mut FfiEdge,
+) -> FfiResult {
+    catch_panic(|| {
+        record_get(ptr_record, tag, edge, |r, tag| {
+            let e = r.record.get_edge(tag.as_ref())?;
+            r.pool.edge(e)
+        })
+    })
+}
+
+/// Get the object (e.g., a property value) of the given tag from a record, where
+/// the tag of `None` refers to the head.
+#[no_mangle]
+pub extern "C" fn record_get_object(
+    ptr_record: *const c_void, tag: FfiNameOrId, object: *mut FfiConst,
+) -> FfiResult {
+    catch_panic(|| {
+        record_get(ptr_record, tag, object, |r, tag| {
+            let o = r.record.get_object(tag.as_ref())?;
+            r.pool.object(o)
+        })
+    })
+}
+
+/// Get the path of the given tag from a record, where the tag of `None` refers to the head.
+/// The pointer of the path is owned by Rust, which must be released via [`destroy_result_path`].
+#[no_mangle]
+pub extern "C" fn record_get_path(
+    ptr_record: *const c_void, tag: FfiNameOrId, ptr_path: *mut *const c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        record_get(ptr_record, tag, ptr_path, |r, tag| {
+            let path = r.record.get_path(tag.as_ref())?.clone();
+            Ok(handle::register::<_, c_void>(FfiPath { path, pool: CStrPool::default() }))
+        })
+    })
+}
+
+/// To destroy a path, as well as the strings fetched from it.
+#[no_mangle]
+pub extern "C" fn destroy_result_path(ptr_path: *const c_void) {
+    catch_panic(|| destroy_handle::<FfiPath>(ptr_path))
+}
+
+/// Get the number of elements (vertices and edges) in a path, or -1 if the path is invalid.
+#[no_mangle]
+pub extern "C" fn get_path_len(ptr_path: *const c_void) -> i32 {
+    catch_panic(|| match handle::borrow::<FfiPath>(ptr_path) {
+        Ok(path) => path.path.path.len() as i32,
+        Err(e) => {
+            e.discard();
+            -1
+        }
+    })
+}
+
+/// Get the `index`-th element of a path, which is either a vertex or an edge.
+#[no_mangle]
+pub extern "C" fn path_get_element(
+    ptr_path: *const c_void, index: i32, element: *mut FfiPathElement,
+) -> FfiResult {
+    catch_panic(|| {
+        if index < 0 {
+            return FfiResult::new(
+                ResultCode::NegativeIndexError,
+                format!("the index {:?} is negative", index),
+            );
+        }
+        let mut path = match handle::borrow_mut::<FfiPath>(ptr_path) {
+            Ok(path) => path,
+            Err(e) => return e,
+        };
+        let FfiPath { path: path_pb, pool } = &mut *path;
+        let result = match path_pb
+            .path
+            .get(index as usize)
+            .and_then(|e| e.inner.as_ref())
+        {
+            Some(result_pb::graph_path::vertex_or_edge::Inner::Vertex(v)) => pool.vertex(v).map(|vertex| {
+                FfiPathElement { opt: FfiPathElementOpt::Vertex, vertex, edge: FfiEdge::default() }
+            }),
+            Some(result_pb::graph_path::vertex_or_edge::Inner::Edge(e)) => pool.edge(e).map(|edge| {
+                FfiPathElement { opt: FfiPathElementOpt::Edge, vertex: FfiVertex::default(), edge }
+            }),
+            None => Err(FfiResult::new(
+                ResultCode::MissingDataError,
+                format!("the {:?}-th element of the path does not exist", index),
+            )),
+        };
+        set_output(element, result)
+    })
+}
+
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FfiColumnType {
+    Vertex = 0,
+    Edge = 1,
+    Boolean = 2,
+    I32 = 3,
+    I64 = 4,
+    F64 = 5,
+    Str = 6,
+    Null = 7,
+}
+
+/// A column of a batch, of which the arrays are owned by the batch. For a column of vertices
+/// or edges, `data` refers to an `int64` array of their ids, and `labels` refers to their labels,
+/// while `src_ids` and `dst_ids` are further given for edges. Otherwise, `data` refers to an array
+/// of the values as the `data_type`, where a string is given as a `char*`. A column of which all
+/// the entries are null is of the `Null` type, where `data` is null.
+#[repr(C)]
+pub struct FfiColumn {
+    data_type: FfiColumnType,
+    len: usize,
+    data: *const c_void,
+    labels: *const FfiNameOrId,
+    src_ids: *const i64,
+    dst_ids: *const i64,
+}
+
+impl FfiColumn {
+    fn new(data_type: FfiColumnType, len: usize, data: *const c_void) -> Self {
+        FfiColumn {
+            data_type,
+            len,
+            data,
+            labels: std::ptr::null(),
+            src_ids: std::ptr::null(),
+            dst_ids: std::ptr::null(),
+        }
+    }
+}
+
+struct FfiBatch {
+    batch: ResultBatch,
+    /// The labels of the vertex (edge) columns, or the strings of the string columns,
+    /// in the order of the columns of the batch
+    labels: Vec<Vec<FfiNameOrId>>,
+    strs: Vec<Vec<*const c_char>>,
+    /// Keep the strings referred by `labels` and `strs` alive
+    #[allow(dead_code)]
+    pool: CStrPool,
+}
+
+impl FfiBatch {
+    fn new(batch: ResultBatch) -> Result<Self, FfiResult> {
+        let mut pool = CStrPool::default();
+        let mut labels = Vec::with_capacity(batch.num_columns());
+        let mut strs = Vec::with_capacity(batch.num_columns());
+        for (_, data) in batch.columns() {
+            let (column_labels, column_strs) = match data {
+                ColumnData::Vertex { labels, .. } | ColumnData::Edge { labels, .. } => (
+                    labels
+                        .iter()
+                        .map(|l| pool.name_or_id(l.as_ref()))
+                        .collect::<Result<Vec<_>, _>>()?,
+                    vec![],
+                ),
+                ColumnData::Str(data) => (
+                    vec![],
+                    data.iter()
+                        .map(|s| pool.alloc(s))
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                _ => (vec![], vec![]),
+            };
+            labels.push(column_labels);
+            strs.push(column_strs);
+        }
+
+        Ok(FfiBatch { batch, labels, strs, pool })
+    }
+
+    fn get_column(&self, tag: Option<NameOrId>) -> Result<FfiColumn, FfiResult> {
+        let idx = self.batch.get_column_index(tag.as_ref())?;
+        let data = &self.batch.columns()[idx].1;
+        let len = data.len();
+        let column = match data {
+            ColumnData::Vertex { ids, .. } => {
+                let mut column = FfiColumn::new(FfiColumnType::Vertex, len, ids.as_ptr() as *const c_void);
+                column.labels = self.labels[idx].as_ptr();
+                column
+            }
+            ColumnData::Edge { ids, src_ids, dst_ids, .. } => {
+                let mut column = FfiColumn::new(FfiColumnType::Edge, len, ids.as_ptr() as *const c_void);
+                column.labels = self.labels[idx].as_ptr();
+                column.src_ids = src_ids.as_ptr();
+                column.dst_ids = dst_ids.as_ptr();
+                column
+            }
+            ColumnData::Boolean(data) => {
+                FfiColumn::new(FfiColumnType::Boolean, len, data.as_ptr() as *const c_void)
+            }
+            ColumnData::I32(data) => {
+                FfiColumn::new(FfiColumnType::I32, len, data.as_ptr() as *const c_void)
+            }
+            ColumnData::I64(data) => {
+                FfiColumn::new(FfiColumnType::I64, len, data.as_ptr() as *const c_void)
+            }
+            ColumnData::F64(data) => {
+                FfiColumn::new(FfiColumnType::F64, len, data.as_ptr() as *const c_void)
+            }
+            ColumnData::Str(_) => {
+                FfiColumn::new(FfiColumnType::Str, len, self.strs[idx].as_ptr() as *const c_void)
+            }
+            ColumnData::Null(_) => FfiColumn::new(FfiColumnType::Null, len, std::ptr::null()),
+        };
+
+        Ok(column)
+    }
+
+    fn get_nulls(&self, tag: Option<NameOrId>) -> Result<*const bool, FfiResult> {
+        let nulls = self
+            .batch
+            .get_nulls(self.batch.get_column_index(tag.as_ref())?);
+        Ok(if nulls.is_empty() { std::ptr::null() } else { nulls.as_ptr() })
+    }
+}
+
+/// Decode a batch of records in the column-oriented layout from the bytes of a
+/// `results::CollectiveResults`, which saves the per-record calls when fetching a large number
+/// of records. The results are either records, or the batches returned by a sink of the columnar
+/// layout (see `set_sink_layout()`), which are concatenated in order. The pointer of the batch is owned by Rust, which must be released via
+/// [`destroy_result_batch`].
+#[no_mangle]
+pub extern "C" fn decode_result_batch(
+    pb_results: FfiPbPointer, ptr_batch: *mut *const c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = ptr_to_pb::<result_pb::CollectiveResults>(pb_results).and_then(|results| {
+            let batch = FfiBatch::new(ResultBatch::try_from(results)?)?;
+            Ok(handle::register::<_, c_void>(batch))
+        });
+        set_output(ptr_batch, result)
+    })
+}
+
+/// To destroy a batch, as well as the arrays fetched from it.
+#[no_mangle]
+pub extern "C" fn destroy_result_batch(ptr_batch: *const c_void) {
+    catch_panic(|| destroy_handle::<FfiBatch>(ptr_batch))
+}
+
+/// Get the number of records in a batch, or -1 if the batch is invalid.
+#[no_mangle]
+pub extern "C" fn get_batch_num_rows(ptr_batch: *const c_void) -> i32 {
+    catch_panic(|| match handle::borrow::<FfiBatch>(ptr_batch) {
+        Ok(batch) => batch.batch.num_rows() as i32,
+        Err(e) => {
+            e.discard();
+            -1
+        }
+    })
+}
+
+/// Get the column of the given tag from a batch, where the tag of `None` refers to the head.
+#[no_mangle]
+pub extern "C" fn batch_get_column(
+    ptr_batch: *const c_void, tag: FfiNameOrId, column: *mut FfiColumn,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = handle::borrow::<FfiBatch>(ptr_batch)
+            .and_then(|batch| ffi_to_tag(tag).and_then(|tag| batch.get_column(tag)));
+        set_output(column, result)
+    })
+}
+
+/// Get the nulls of the column of the given tag from a batch, as an array of the length of the
+/// column that is owned by the batch, where the `i`-th flag is set if the `i`-th entry is null,
+/// i.e., the record has no entry of the column, and thus the column holds a default value in place.
+/// The array is null if no entry of the column is null.
+#[no_mangle]
+pub extern "C" fn batch_get_column_nulls(
+    ptr_batch: *const c_void, tag: FfiNameOrId, nulls: *mut *const bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = handle::borrow::<FfiBatch>(ptr_batch)
+            .and_then(|batch| ffi_to_tag(tag).and_then(|tag| batch.get_nulls(tag)));
+        set_output(nulls, result)
+    })
+}
@@ -0,0 +1,189 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The result module helps the client to access the records returned from the engine,
+//! without having to parse the raw protobuf structures.
+
+use std::convert::TryFrom;
+
+use ir_common::error::ParsePbError;
+use ir_common::generated::common as common_pb;
+use ir_common::generated::results as result_pb;
+use ir_common::NameOrId;
+
+use crate::error::{IrError, IrResult};
+
+pub mod columnar;
+pub mod ffi;
+
+/// A record returned from the engine, in which each column is referred by a tag,
+/// while the column without a tag is regarded as the head of the record.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultRecord {
+    record: result_pb::Record,
+}
+
+impl From<result_pb::Record> for ResultRecord {
+    fn from(record: result_pb::Record) -> Self {
+        Self { record }
+    }
+}
+
+impl TryFrom<result_pb::Results> for ResultRecord {
+    type Error = IrError;
+
+    fn try_from(results: result_pb::Results) -> IrResult<Self> {
+        match results.inner {
+            Some(result_pb::results::Inner::Record(record)) => Ok(record.into()),
+            Some(result_pb::results::Inner::ColumnBatch(_)) => {
+                Err(IrError::Unsupported("a record of the columnar layout".to_string()))
+            }
+            None => Err(ParsePbError::EmptyFieldError("Results::inner".to_string()).into()),
+        }
+    }
+}
+
+fn mismatch_error(expected: &str, actual: &dyn std::fmt::Debug) -> IrError {
+    ParsePbError::ParseError(format!("expect {} but got {:?}", expected, actual)).into()
+}
+
+impl ResultRecord {
+    pub fn len(&self) -> usize {
+        self.record.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.record.columns.is_empty()
+    }
+
+    /// Get the entry referred by the given tag, or the head entry if the tag is `None`.
+    pub fn get_entry(&self, tag: Option<&NameOrId>) -> IrResult<&result_pb::Entry> {
+        let tag_pb = tag.cloned().map(common_pb::NameOrId::from);
+        self.record
+            .columns
+            .iter()
+            .find(|column| column.name_or_id == tag_pb)
+            .and_then(|column| column.entry.as_ref())
+            .ok_or_else(|| match tag {
+                Some(t) => IrError::TagNotExist(t.clone()),
+                None => IrError::MissingData("the head of the record".to_string()),
+            })
+    }
+
+    /// Get the element referred by the given tag, which must not be a collection.
+    pub fn get_element(&self, tag: Option<&NameOrId>) -> IrResult<&result_pb::Element> {
+        match &self.get_entry(tag)?.inner {
+            Some(result_pb::entry::Inner::Element(element)) => Ok(element),
+            Some(result_pb::entry::Inner::Collection(collection)) => {
+                Err(mismatch_error("an element", collection))
+            }
+            None => Err(ParsePbError::EmptyFieldError("Entry::inner".to_string()).into()),
+        }
+    }
+
+    pub fn get_vertex(&self, tag: Option<&NameOrId>) -> IrResult<&result_pb::Vertex> {
+        match &self.get_element(tag)?.inner {
+            Some(result_pb::element::Inner::Vertex(v)) => Ok(v),
+            other => Err(mismatch_error("a vertex", other)),
+        }
+    }
+
+    pub fn get_edge(&self, tag: Option<&NameOrId>) -> IrResult<&result_pb::Edge> {
+        match &self.get_element(tag)?.inner {
+            Some(result_pb::element::Inner::Edge(e)) => Ok(e),
+            other => Err(mismatch_error("an edge", other)),
+        }
+    }
+
+    pub fn get_path(&self, tag: Option<&NameOrId>) -> IrResult<&result_pb::GraphPath> {
+        match &self.get_element(tag)?.inner {
+            Some(result_pb::element::Inner::GraphPath(p)) => Ok(p),
+            other => Err(mismatch_error("a path", other)),
+        }
+    }
+
+    pub fn get_object(&self, tag: Option<&NameOrId>) -> IrResult<&common_pb::Value> {
+        match &self.get_element(tag)?.inner {
+            Some(result_pb::element::Inner::Object(o)) => Ok(o),
+            other => Err(mismatch_error("an object", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vertex_element(id: i64, label: i32) -> result_pb::Element {
+        result_pb::Element {
+            inner: Some(result_pb::element::Inner::Vertex(result_pb::Vertex {
+                id,
+                label: Some(label.into()),
+                properties: vec![],
+            })),
+        }
+    }
+
+    fn column(tag: Option<NameOrId>, element: result_pb::Element) -> result_pb::Column {
+        result_pb::Column {
+            name_or_id: tag.map(|t| t.into()),
+            entry: Some(result_pb::Entry { inner: Some(result_pb::entry::Inner::Element(element)) }),
+        }
+    }
+
+    #[test]
+    fn get_typed_entries() {
+        let record = ResultRecord::from(result_pb::Record {
+            columns: vec![
+                column(None, vertex_element(1, 0)),
+                column(Some("a".into()), vertex_element(2, 1)),
+                column(
+                    Some("b".into()),
+                    result_pb::Element { inner: Some(result_pb::element::Inner::Object(10_i64.into())) },
+                ),
+            ],
+        });
+        assert_eq!(record.len(), 3);
+        assert_eq!(record.get_vertex(None).unwrap().id, 1);
+        assert_eq!(record.get_vertex(Some(&"a".into())).unwrap().id, 2);
+        assert_eq!(
+            record
+                .get_object(Some(&"b".into()))
+                .unwrap()
+                .clone(),
+            10_i64.into()
+        );
+        // type mismatch
+        assert!(record.get_edge(Some(&"a".into())).is_err());
+        assert!(record.get_path(Some(&"b".into())).is_err());
+        // tag not exist
+        match record.get_vertex(Some(&"c".into())) {
+            Err(IrError::TagNotExist(tag)) => assert_eq!(tag, "c".into()),
+            _ => panic!("should report tag not exist"),
+        }
+    }
+
+    #[test]
+    fn record_from_results() {
+        let results = result_pb::Results {
+            inner: Some(result_pb::results::Inner::Record(result_pb::Record {
+                columns: vec![column(None, vertex_element(1, 0))],
+            })),
+        };
+        let record = ResultRecord::try_from(results).unwrap();
+        assert_eq!(record.get_vertex(None).unwrap().label, Some(0.into()));
+        assert!(ResultRecord::try_from(result_pb::Results { inner: None }).is_err());
+    }
+}
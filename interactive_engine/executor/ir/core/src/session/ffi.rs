@@ -0,0 +1,486 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The apis of the sessions, where the plans are created from a session, and then built, prepared
+//! and submitted as usual, as:
+//!
+//! # Example
+//!
+//! # FfiSessionConfig config = default_session_config();
+//! # config.schema_json = schema;
+//! # FfiSession* session = NULL;
+//! # create_session(config, &session);
+//! # connect_session_server(session, 0, "http://127.0.0.1:1234");
+//! # FfiLogicalPlan* plan = init_session_plan(session);
+//! # // append the operators to the plan, and build the physical plan
+//! # submit_session_job(session, default_job_conf(), physical, &job);
+//! # destroy_logical_plan_v2(plan);
+//! # destroy_session(session);
+
+use std::convert::TryFrom;
+use std::ffi::c_void;
+use std::os::raw::c_char;
+
+use ir_common::generated::algebra as pb;
+use pegasus::JobConf;
+
+use crate::client::ffi::FfiJobConf;
+use crate::client::SubmitOptions;
+use crate::plan::ffi::handle;
+use crate::plan::ffi::{
+    catch_panic, check_not_null, cstr_to_expr_pb, cstr_to_string, destroy_handle, set_last_error,
+    set_output, FfiLogicalPlan, FfiPbPointer, FfiResult, FromPanic, ResultCode,
+};
+use crate::plan::logical::LogicalPlan;
+use crate::plan::meta::{PlanMeta, STORE_META};
+use crate::session::Session;
+
+/// The opaque handle of a session, which is owned by Rust, and must be released
+/// via [`destroy_session`].
+#[repr(C)]
+pub struct FfiSession {
+    _private: [u8; 0],
+}
+
+/// The configuration of creating a session, which is expected to be modified from the
+/// [`default_session_config`] as needed.
+#[repr(C)]
+pub struct FfiSessionConfig {
+    /// The schema of the store (including its statistics, if any) as a json, or null to take the
+    /// schema set via `set_schema()`, if any, as of the creation of the session
+    pub schema_json: *const c_char,
+    /// Whether to partition the tasks of the plans
+    pub is_partition: bool,
+    /// Whether to fetch the properties of the plans in bulk
+    pub is_bulk_fetch: bool,
+    /// The maximal number of hops of the path expansions of the plans, where a non-positive
+    /// maximum means no limit
+    pub max_hops: i32,
+    /// The threshold of the estimated complexity of the plans, where a non-positive threshold
+    /// means no threshold
+    pub complexity_threshold: f64,
+}
+
+impl Default for FfiSessionConfig {
+    fn default() -> Self {
+        FfiSessionConfig {
+            schema_json: std::ptr::null(),
+            is_partition: false,
+            is_bulk_fetch: false,
+            max_hops: 0,
+            complexity_threshold: 0.0,
+        }
+    }
+}
+
+impl FromPanic for FfiSessionConfig {
+    fn from_panic(msg: String) -> Self {
+        set_last_error(ResultCode::InternalError, &msg);
+        FfiSessionConfig::default()
+    }
+}
+
+/// The default configuration of a session.
+#[no_mangle]
+pub extern "C" fn default_session_config() -> FfiSessionConfig {
+    catch_panic(FfiSessionConfig::default)
+}
+
+impl TryFrom<FfiSessionConfig> for Session {
+    type Error = FfiResult;
+
+    fn try_from(config: FfiSessionConfig) -> Result<Self, Self::Error> {
+        let mut session = if config.schema_json.is_null() {
+            let schema = STORE_META
+                .read()
+                .ok()
+                .and_then(|meta| meta.schema.clone());
+            Session::new(schema)
+        } else {
+            Session::from_schema_json(cstr_to_string(config.schema_json)?.as_bytes())?
+        };
+        let mut settings = PlanMeta::default();
+        if config.is_partition {
+            settings = settings.with_partition();
+        }
+        settings.set_bulk_fetch(config.is_bulk_fetch);
+        settings.set_max_hops(if config.max_hops > 0 { Some(config.max_hops) } else { None });
+        settings.set_complexity_threshold(if config.complexity_threshold > 0.0 {
+            Some(config.complexity_threshold)
+        } else {
+            None
+        });
+        session.set_settings(&settings);
+
+        Ok(session)
+    }
+}
+
+/// Run `f` on the session referred by the handle, without taking its ownership, which
+/// fails with `InvalidHandleError` if the handle does not refer to a live session.
+fn with_session<T, F: FnOnce(&mut Session) -> T>(session: *mut FfiSession, f: F) -> Result<T, FfiResult> {
+    let mut session = handle::borrow_mut::<Session>(session)?;
+    Ok(f(&mut *session))
+}
+
+/// Create a session of the given configuration, which is written to `session`, and must be
+/// released via [`destroy_session`].
+#[no_mangle]
+pub extern "C" fn create_session(config: FfiSessionConfig, session: *mut *mut FfiSession) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(session, "output session") {
+            return e;
+        }
+        // the session is shared by the threads of a service, e.g., while its schema is reloaded
+        let result = Session::try_from(config)
+            .map(|session| handle::register_concurrent::<_, FfiSession>(session) as *mut FfiSession);
+        set_output(session, result)
+    })
+}
+
+/// To destroy a session, which also disconnects it from the engine, while the plans created from
+/// the session remain valid.
+#[no_mangle]
+pub extern "C" fn destroy_session(session: *mut FfiSession) {
+    catch_panic(|| destroy_handle::<Session>(session))
+}
+
+/// Replace the schema of the session, given as a json, e.g., once its statistics are refreshed,
+/// which applies to the plans created from the session afterwards.
+#[no_mangle]
+pub extern "C" fn set_session_schema(session: *mut FfiSession, cstr_json: *const c_char) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(cstr_json).and_then(|json| {
+            let schema = super::parse_schema(json.as_bytes())?;
+            with_session(session, |session| session.set_schema(Some(schema)))
+        });
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    })
+}
+
+/// Reload the schema of the session, given as a json, e.g., once the store is altered by a DDL, which
+/// is swapped atomically with respect to the other apis on the session, and leaves the session
+/// unchanged if the json is invalid. Unlike [`set_session_schema`], the plans prepared in the session
+/// are removed, as they are built against the former schema, and the version of the schema is bumped,
+/// such that the plans created afterwards have other fingerprints than those created before. The
+/// number of the removed plans is written to `num_removed`, unless it is null.
+#[no_mangle]
+pub extern "C" fn reload_session_schema(
+    session: *mut FfiSession, cstr_json: *const c_char, num_removed: *mut usize,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(cstr_json).and_then(|json| {
+            let schema = super::parse_schema(json.as_bytes())?;
+            with_session(session, |session| session.reload_schema(Some(schema)))
+        });
+        match result {
+            Ok(removed) => {
+                if !num_removed.is_null() {
+                    unsafe { *num_removed = removed.len() };
+                }
+                FfiResult::success()
+            }
+            Err(e) => e,
+        }
+    })
+}
+
+/// Get the version of the schema of the session, which is bumped once the schema is reloaded via
+/// [`reload_session_schema`], and is written to `version`.
+#[no_mangle]
+pub extern "C" fn get_session_schema_version(session: *mut FfiSession, version: *mut u64) -> FfiResult {
+    catch_panic(|| {
+        let result = with_session(session, |session| session.get_schema_version());
+        set_output(version, result)
+    })
+}
+
+/// Initialize a logical plan that inherits the schema and the settings of the session, or null if
+/// the session is invalid. The plan is released via `destroy_logical_plan_v2()`.
+#[no_mangle]
+pub extern "C" fn init_session_plan(session: *mut FfiSession) -> *mut FfiLogicalPlan {
+    catch_panic(|| {
+        with_session(session, |session| {
+            handle::register::<_, FfiLogicalPlan>(session.new_plan()) as *mut FfiLogicalPlan
+        })
+        .unwrap_or(std::ptr::null_mut())
+    })
+}
+
+/// Prepare (a copy of) the logical plan in the session under the given name, which replaces the
+/// plan prepared under the same name before, if any, while the given plan is kept valid.
+#[no_mangle]
+pub extern "C" fn prepare_session_plan(
+    session: *mut FfiSession, name: *const c_char, plan: *mut FfiLogicalPlan,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(name).and_then(|name| {
+            let plan = handle::borrow::<LogicalPlan>(plan)?.clone_plan();
+            with_session(session, |session| session.prepare(name, plan))
+        });
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    })
+}
+
+/// Get (a copy of) the logical plan prepared in the session under the given name, which is written
+/// to `plan`, and must be released via `destroy_logical_plan_v2()`.
+#[no_mangle]
+pub extern "C" fn get_prepared_plan(
+    session: *mut FfiSession, name: *const c_char, plan: *mut *mut FfiLogicalPlan,
+) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(plan, "output plan") {
+            return e;
+        }
+        let result = cstr_to_string(name).and_then(|name| {
+            with_session(session, |session| {
+                session
+                    .get_prepared(&name)
+                    .map(LogicalPlan::clone_plan)
+            })?
+            .map(|prepared| handle::register::<_, FfiLogicalPlan>(prepared) as *mut FfiLogicalPlan)
+            .ok_or_else(|| {
+                FfiResult::new(
+                    ResultCode::MissingDataError,
+                    format!("the plan {:?} is not prepared in the session", name),
+                )
+            })
+        });
+        set_output(plan, result)
+    })
+}
+
+/// Remove the logical plan prepared in the session under the given name, if any.
+#[no_mangle]
+pub extern "C" fn remove_prepared_plan(session: *mut FfiSession, name: *const c_char) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(name)
+            .and_then(|name| with_session(session, |session| session.remove_prepared(&name)));
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    })
+}
+
+/// Define an empty view of the graph in the session under the given name, which replaces the view
+/// defined under the same name before, if any. The view is then narrowed down via
+/// `add_session_view_label()` and `set_session_view_predicate()`, and the plans are created within the
+/// view via `init_session_plan_in_view()`.
+#[no_mangle]
+pub extern "C" fn define_session_view(session: *mut FfiSession, cstr_name: *const c_char) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(cstr_name).and_then(|name| {
+            with_session(session, |session| {
+                session.define_view(pb::GraphView { name, ..Default::default() })
+            })
+        });
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    })
+}
+
+/// Run `f` on (a copy of) the view of the given name, which is defined back to the session afterwards.
+fn update_session_view<F: FnOnce(&mut pb::GraphView)>(
+    session: *mut FfiSession, cstr_view: *const c_char, f: F,
+) -> FfiResult {
+    let result = cstr_to_string(cstr_view).and_then(|name| {
+        with_session(session, |session| match session.get_view(&name).cloned() {
+            Some(mut view) => {
+                f(&mut view);
+                session.define_view(view);
+                Ok(())
+            }
+            None => Err(FfiResult::new(
+                ResultCode::MissingDataError,
+                format!("the view {:?} is not defined in the session", name),
+            )),
+        })?
+    });
+    match result {
+        Ok(_) => FfiResult::success(),
+        Err(e) => e,
+    }
+}
+
+/// Add a label of the vertices, or of the edges if `is_edge`, to the view of the given name, which is
+/// then constrained to the vertices (or the edges) of its labels.
+#[no_mangle]
+pub extern "C" fn add_session_view_label(
+    session: *mut FfiSession, cstr_view: *const c_char, cstr_label: *const c_char, is_edge: bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let label = match cstr_to_string(cstr_label) {
+            Ok(label) => label,
+            Err(e) => return e,
+        };
+        update_session_view(session, cstr_view, |view| {
+            if is_edge {
+                view.edge_labels.push(label.into());
+            } else {
+                view.vertex_labels.push(label.into());
+            }
+        })
+    })
+}
+
+/// Set the predicate that the vertices, or the edges if `is_edge`, of the view of the given name
+/// satisfy, which refers to the vertex (or the edge) as the head, e.g., `@.tenant == "a"`.
+#[no_mangle]
+pub extern "C" fn set_session_view_predicate(
+    session: *mut FfiSession, cstr_view: *const c_char, cstr_predicate: *const c_char, is_edge: bool,
+) -> FfiResult {
+    catch_panic(|| {
+        let predicate = match cstr_to_expr_pb(cstr_predicate) {
+            Ok(predicate) => predicate,
+            Err(e) => return e,
+        };
+        update_session_view(session, cstr_view, |view| {
+            if is_edge {
+                view.edge_predicate = Some(predicate);
+            } else {
+                view.vertex_predicate = Some(predicate);
+            }
+        })
+    })
+}
+
+/// Remove the view defined in the session under the given name, if any, while the plans created
+/// within the view remain constrained by it.
+#[no_mangle]
+pub extern "C" fn remove_session_view(session: *mut FfiSession, cstr_view: *const c_char) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(cstr_view)
+            .and_then(|name| with_session(session, |session| session.remove_view(&name)));
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    })
+}
+
+/// Initialize a logical plan as `init_session_plan()` within the view of the given name, of which the
+/// scans and the expansions are constrained by the view. The plan is written to `plan`, and must be
+/// released via `destroy_logical_plan_v2()`.
+#[no_mangle]
+pub extern "C" fn init_session_plan_in_view(
+    session: *mut FfiSession, cstr_view: *const c_char, plan: *mut *mut FfiLogicalPlan,
+) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(plan, "output plan") {
+            return e;
+        }
+        let result = cstr_to_string(cstr_view).and_then(|name| {
+            let view_plan = with_session(session, |session| session.new_plan_in_view(&name))??;
+            Ok(handle::register::<_, FfiLogicalPlan>(view_plan) as *mut FfiLogicalPlan)
+        });
+        set_output(plan, result)
+    })
+}
+
+/// Connect the session to the server of `server_id`, which can be accessed via the `cstr_url`.
+#[no_mangle]
+pub extern "C" fn connect_session_server(
+    session: *mut FfiSession, server_id: u64, cstr_url: *const c_char,
+) -> FfiResult {
+    catch_panic(|| {
+        let result = cstr_to_string(cstr_url).and_then(|url| {
+            with_session(session, |session| session.connect(server_id, url))?.map_err(FfiResult::from)
+        });
+        match result {
+            Ok(_) => FfiResult::success(),
+            Err(e) => e,
+        }
+    })
+}
+
+/// Submit a physical plan, given as the bytes built via `build_physical_plan_v2()`, as a job via
+/// the connection of the session. The pointer of the job is owned by Rust, and must be released
+/// via `destroy_job()`.
+#[no_mangle]
+pub extern "C" fn submit_session_job(
+    session: *mut FfiSession, conf: FfiJobConf, plan: FfiPbPointer, ptr_job: *mut *const c_void,
+) -> FfiResult {
+    catch_panic(|| {
+        if let Err(e) = check_not_null(ptr_job, "output job") {
+            return e;
+        }
+        let result = <(JobConf, SubmitOptions)>::try_from(conf).and_then(|(conf, options)| {
+            let plan = plan.as_bytes()?.to_vec();
+            let job = with_session(session, |session| session.submit(conf, options, plan))??;
+            Ok(handle::register::<_, c_void>(job))
+        });
+        set_output(ptr_job, result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+
+    fn append_limit(plan: *mut FfiLogicalPlan) {
+        let mut plan = handle::borrow_mut::<LogicalPlan>(plan).unwrap();
+        let parents = plan.get_sinks();
+        plan.append_operator_as_node(
+            pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false }.into(),
+            parents,
+        )
+        .unwrap();
+    }
+
+    fn plan_len(plan: *mut FfiLogicalPlan) -> usize {
+        handle::borrow::<LogicalPlan>(plan)
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn prepared_plans_are_copied() {
+        let mut session = std::ptr::null_mut();
+        assert_eq!(create_session(default_session_config(), &mut session), FfiResult::success());
+        let name = CString::new("limit").unwrap();
+        let plan = init_session_plan(session);
+        append_limit(plan);
+        assert_eq!(prepare_session_plan(session, name.as_ptr(), plan), FfiResult::success());
+        // extending the given plan leaves the prepared one intact
+        append_limit(plan);
+        assert_eq!(plan_len(plan), 2);
+
+        let mut fetched = std::ptr::null_mut();
+        assert_eq!(get_prepared_plan(session, name.as_ptr(), &mut fetched), FfiResult::success());
+        assert_eq!(plan_len(fetched), 1);
+        // extending a fetched plan leaves the prepared one intact as well
+        append_limit(fetched);
+        assert_eq!(plan_len(fetched), 2);
+        let mut refetched = std::ptr::null_mut();
+        assert_eq!(get_prepared_plan(session, name.as_ptr(), &mut refetched), FfiResult::success());
+        assert_eq!(plan_len(refetched), 1);
+
+        for plan in [plan, fetched, refetched] {
+            crate::plan::ffi::v2::destroy_logical_plan_v2(plan);
+        }
+        destroy_session(session);
+    }
+}
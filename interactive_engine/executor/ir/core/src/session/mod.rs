@@ -0,0 +1,303 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The session module bundles what the queries of a client share, namely, the metadata of the store
+//! (the schema and its statistics), the settings of the plans, the prepared plans, the views of the
+//! graph and the connection to the engine, such that the plans created from a session inherit all of
+//! them, rather than each of them living in a global of its own, e.g., the [`STORE_META`] of the schema.
+//!
+//! [`STORE_META`]: crate::plan::meta::STORE_META
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Arc;
+
+use ir_common::error::ParsePbError;
+use ir_common::generated::algebra as pb;
+use pegasus::JobConf;
+
+use crate::client::{Job, JobClient, SubmitOptions};
+use crate::error::{IrError, IrResult};
+use crate::plan::logical::LogicalPlan;
+use crate::plan::meta::{PlanMeta, Schema, StoreMeta};
+use crate::JsonIO;
+
+pub mod ffi;
+
+/// A session of building and submitting the plans against the same store.
+pub struct Session {
+    /// The metadata of the store, which is shared by the plans created from the session
+    store_meta: Arc<StoreMeta>,
+    /// The settings of the plans created from the session, e.g., whether to partition the task
+    settings: PlanMeta,
+    /// The prepared plans by their names, which are reused rather than rebuilt per submission
+    prepared: BTreeMap<String, LogicalPlan>,
+    /// The views of the graph by their names, which the plans can be created within
+    views: BTreeMap<String, pb::GraphView>,
+    /// The client submitting the jobs of the session, once it connects to the engine
+    client: Option<JobClient>,
+}
+
+impl Session {
+    /// A session of the given schema, or without a schema if not given.
+    pub fn new(schema: Option<Schema>) -> Self {
+        Session {
+            store_meta: Arc::new(StoreMeta { schema, version: 0 }),
+            settings: PlanMeta::default(),
+            prepared: BTreeMap::new(),
+            views: BTreeMap::new(),
+            client: None,
+        }
+    }
+
+    /// A session of the schema given as a json.
+    pub fn from_schema_json<R: io::Read>(read: R) -> IrResult<Self> {
+        Ok(Self::new(Some(parse_schema(read)?)))
+    }
+
+    pub fn get_store_meta(&self) -> &StoreMeta {
+        &self.store_meta
+    }
+
+    /// Replace the schema of the session, e.g., once its statistics are refreshed, which applies to
+    /// the plans created from the session afterwards, while the existing plans keep the former one.
+    pub fn set_schema(&mut self, schema: Option<Schema>) {
+        self.store_meta = Arc::new(StoreMeta { schema, version: self.store_meta.version });
+    }
+
+    /// Reload the schema of the session, e.g., once the store is altered by a DDL, which, unlike
+    /// [`Session::set_schema`], bumps the version of the schema, such that the plans created
+    /// afterwards have other fingerprints than those created before, and removes the prepared
+    /// plans, as they are built against the former schema. It returns the names of the removed plans.
+    pub fn reload_schema(&mut self, schema: Option<Schema>) -> Vec<String> {
+        let version = self.store_meta.version + 1;
+        self.store_meta = Arc::new(StoreMeta { schema, version });
+        std::mem::take(&mut self.prepared)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// The version of the schema of the session, which starts from 0, and is bumped once the schema
+    /// is reloaded via [`Session::reload_schema`].
+    pub fn get_schema_version(&self) -> u64 {
+        self.store_meta.version
+    }
+
+    /// Set the settings of the plans created from the session afterwards, e.g., whether to partition
+    /// the task, which are kept from the given plan meta as `PlanMeta::with_settings_of`.
+    pub fn set_settings(&mut self, settings: &PlanMeta) {
+        self.settings = PlanMeta::with_settings_of(settings);
+    }
+
+    /// Create a plan that inherits the store meta and the settings of the session.
+    pub fn new_plan(&self) -> LogicalPlan {
+        let mut meta = PlanMeta::with_settings_of(&self.settings);
+        meta.set_store_meta(Some(self.store_meta.clone()));
+
+        LogicalPlan { meta, ..Default::default() }
+    }
+
+    /// Prepare the plan under the given name, which replaces and returns the one prepared before, if any.
+    pub fn prepare(&mut self, name: String, plan: LogicalPlan) -> Option<LogicalPlan> {
+        self.prepared.insert(name, plan)
+    }
+
+    pub fn get_prepared(&self, name: &str) -> Option<&LogicalPlan> {
+        self.prepared.get(name)
+    }
+
+    pub fn remove_prepared(&mut self, name: &str) -> Option<LogicalPlan> {
+        self.prepared.remove(name)
+    }
+
+    /// Define the view under its name, which replaces and returns the one defined before, if any, while
+    /// the plans created within the former view remain constrained by it.
+    pub fn define_view(&mut self, view: pb::GraphView) -> Option<pb::GraphView> {
+        self.views.insert(view.name.clone(), view)
+    }
+
+    pub fn get_view(&self, name: &str) -> Option<&pb::GraphView> {
+        self.views.get(name)
+    }
+
+    pub fn remove_view(&mut self, name: &str) -> Option<pb::GraphView> {
+        self.views.remove(name)
+    }
+
+    /// Create a plan as [`Session::new_plan`] within the view of the given name, such that its scans
+    /// and expansions are constrained by the view, see `LogicalPlan::set_view`.
+    pub fn new_plan_in_view(&self, name: &str) -> IrResult<LogicalPlan> {
+        let view = self
+            .views
+            .get(name)
+            .ok_or_else(|| IrError::MissingData(format!("the view {:?} of the session", name)))?;
+        let mut plan = self.new_plan();
+        plan.set_view(view.clone())?;
+
+        Ok(plan)
+    }
+
+    /// Connect the session to the server of `server_id`, which can be accessed via the `url`, where
+    /// the client of the session is created once it connects to the first server.
+    pub fn connect(&mut self, server_id: u64, url: String) -> IrResult<()> {
+        if self.client.is_none() {
+            self.client = Some(JobClient::new()?);
+        }
+        self.client
+            .as_mut()
+            .unwrap()
+            .connect(server_id, url)
+    }
+
+    /// The client of the session, if it has connected to the engine, which can be configured as
+    /// needed, e.g., via `JobClient::set_retry_policy`.
+    pub fn get_client_mut(&mut self) -> Option<&mut JobClient> {
+        self.client.as_mut()
+    }
+
+    /// Submit a physical plan, given as the bytes of a `PhysicalPlan`, as a job via the client of
+    /// the session, which fails if the session has not connected to the engine.
+    pub fn submit(&mut self, conf: JobConf, options: SubmitOptions, plan: Vec<u8>) -> IrResult<Job> {
+        self.client
+            .as_mut()
+            .ok_or_else(|| {
+                IrError::SubmitJobError("the session is not connected to the engine".to_string())
+            })?
+            .submit(conf, options, plan)
+    }
+}
+
+fn parse_schema<R: io::Read>(read: R) -> IrResult<Schema> {
+    Schema::from_json(read)
+        .map_err(|e| IrError::ParsePbError(ParsePbError::SerdeError(format!("invalid schema: {}", e))))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn session_plans_inherit() {
+        let mut session = Session::new(None);
+        let mut settings = PlanMeta::default().with_partition();
+        settings.set_max_hops(Some(3));
+        session.set_settings(&settings);
+
+        let plan = session.new_plan();
+        assert!(plan.meta.is_partition());
+        assert_eq!(plan.meta.get_max_hops(), Some(3));
+        assert!(Arc::ptr_eq(&plan.meta.get_store_meta().unwrap(), &session.store_meta));
+
+        // the plans created before keep the former store meta
+        session.set_schema(None);
+        assert!(!Arc::ptr_eq(&plan.meta.get_store_meta().unwrap(), &session.store_meta));
+    }
+
+    #[test]
+    fn session_prepared_plans() {
+        let mut session = Session::new(None);
+        let mut plan = session.new_plan();
+        plan.append_operator_as_node(
+            pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false }.into(),
+            vec![],
+        )
+        .unwrap();
+
+        assert!(session
+            .prepare("limit".to_string(), plan)
+            .is_none());
+        assert_eq!(session.get_prepared("limit").unwrap().len(), 1);
+        assert!(session.get_prepared("absent").is_none());
+        let empty = session.new_plan();
+        assert!(session
+            .prepare("limit".to_string(), empty)
+            .is_some());
+        assert_eq!(session.get_prepared("limit").unwrap().len(), 0);
+        assert!(session.remove_prepared("limit").is_some());
+        assert!(session.get_prepared("limit").is_none());
+    }
+
+    #[test]
+    fn session_views() {
+        let mut session = Session::new(None);
+        let view = pb::GraphView {
+            name: "a".to_string(),
+            vertex_labels: vec!["person".into()],
+            vertex_predicate: None,
+            edge_labels: vec![],
+            edge_predicate: None,
+        };
+        assert!(session.define_view(view.clone()).is_none());
+        assert_eq!(session.get_view("a"), Some(&view));
+        assert!(matches!(session.new_plan_in_view("absent"), Err(IrError::MissingData(_))));
+
+        let plan = session.new_plan_in_view("a").unwrap();
+        assert_eq!(plan.get_view(), Some(&view));
+        assert!(Arc::ptr_eq(&plan.meta.get_store_meta().unwrap(), &session.store_meta));
+        // the plans created within the view remain constrained by it once it is removed
+        assert!(session.remove_view("a").is_some());
+        assert!(session.get_view("a").is_none());
+        assert_eq!(plan.get_view(), Some(&view));
+    }
+
+    #[test]
+    fn session_reload_schema() {
+        let mut session = Session::new(None);
+        let mut plan = session.new_plan();
+        plan.append_operator_as_node(
+            pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false }.into(),
+            vec![],
+        )
+        .unwrap();
+        session.prepare("limit".to_string(), plan.clone());
+
+        // refreshing the schema keeps the prepared plans and the fingerprints
+        session.set_schema(None);
+        assert_eq!(session.get_schema_version(), 0);
+        assert!(session.get_prepared("limit").is_some());
+        let mut same_plan = session.new_plan();
+        same_plan
+            .append_operator_as_node(
+                pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false }.into(),
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(plan.fingerprint().unwrap(), same_plan.fingerprint().unwrap());
+
+        assert_eq!(session.reload_schema(None), vec!["limit".to_string()]);
+        assert_eq!(session.get_schema_version(), 1);
+        assert!(session.get_prepared("limit").is_none());
+        let mut reloaded_plan = session.new_plan();
+        reloaded_plan
+            .append_operator_as_node(
+                pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false }.into(),
+                vec![],
+            )
+            .unwrap();
+        assert_ne!(plan.fingerprint().unwrap(), reloaded_plan.fingerprint().unwrap());
+        // the plans are still equivalent, as the fingerprints tell the versions apart only
+        assert!(plan.is_equivalent(&reloaded_plan).unwrap());
+    }
+
+    #[test]
+    fn session_submit_without_connection() {
+        let mut session = Session::new(None);
+        match session.submit(JobConf::default(), SubmitOptions::default(), vec![]) {
+            Err(IrError::SubmitJobError(_)) => {}
+            _ => panic!("should fail to submit without connection"),
+        }
+    }
+}
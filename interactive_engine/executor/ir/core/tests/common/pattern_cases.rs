@@ -70,6 +70,7 @@ pub fn query_params(
         predicate,
         sample_ratio: 1.0,
         extra: HashMap::new(),
+        degree_predicates: vec![],
     }
 }
 
@@ -467,6 +468,11 @@ pub fn build_ldbc_pattern_from_pb_case1() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let pattern = pb::Pattern {
         sentences: vec![
@@ -517,6 +523,11 @@ pub fn build_ldbc_pattern_from_pb_case2() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr2 = pb::EdgeExpand {
         v_tag: None,
@@ -525,6 +536,11 @@ pub fn build_ldbc_pattern_from_pb_case2() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr3 = pb::EdgeExpand {
         v_tag: None,
@@ -533,6 +549,11 @@ pub fn build_ldbc_pattern_from_pb_case2() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let pattern = pb::Pattern {
         sentences: vec![
@@ -579,6 +600,11 @@ pub fn build_ldbc_pattern_from_pb_case3() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let pattern = pb::Pattern {
         sentences: vec![
@@ -655,6 +681,11 @@ pub fn build_ldbc_pattern_from_pb_case4() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr2 = pb::EdgeExpand {
         v_tag: None,
@@ -663,6 +694,11 @@ pub fn build_ldbc_pattern_from_pb_case4() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr3 = pb::EdgeExpand {
         v_tag: None,
@@ -671,6 +707,11 @@ pub fn build_ldbc_pattern_from_pb_case4() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr4 = pb::EdgeExpand {
         v_tag: None,
@@ -679,6 +720,11 @@ pub fn build_ldbc_pattern_from_pb_case4() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let pattern = pb::Pattern {
         sentences: vec![
@@ -739,6 +785,11 @@ pub fn build_ldbc_pattern_from_pb_case5() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr1 = pb::EdgeExpand {
         v_tag: None,
@@ -747,6 +798,11 @@ pub fn build_ldbc_pattern_from_pb_case5() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let pattern = pb::Pattern {
         sentences: vec![
@@ -806,6 +862,11 @@ pub fn build_ldbc_pattern_from_pb_case6() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr1 = pb::EdgeExpand {
         v_tag: None,
@@ -814,6 +875,11 @@ pub fn build_ldbc_pattern_from_pb_case6() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr2 = pb::EdgeExpand {
         v_tag: None,
@@ -822,6 +888,11 @@ pub fn build_ldbc_pattern_from_pb_case6() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr3 = pb::EdgeExpand {
         v_tag: None,
@@ -830,6 +901,11 @@ pub fn build_ldbc_pattern_from_pb_case6() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let expand_opr4 = pb::EdgeExpand {
         v_tag: None,
@@ -838,6 +914,11 @@ pub fn build_ldbc_pattern_from_pb_case6() -> IrPatternResult<Pattern> {
         expand_opt: 0,
         alias: None,
         meta_data: None,
+        dedup_both: false,
+        exclude_self_loops: false,
+        collapse_parallel_edges: false,
+        vertex_tables: vec![],
+        is_optional: false,
     };
     let pattern = pb::Pattern {
         sentences: vec![
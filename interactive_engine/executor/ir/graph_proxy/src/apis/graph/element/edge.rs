@@ -111,6 +111,17 @@ impl Edge {
         self.dst_label.as_ref()
     }
 
+    /// Whether the edge is obtained from its source vertex, i.e., as an outgoing edge
+    pub fn is_from_src(&self) -> bool {
+        self.from_src
+    }
+
+    /// Whether the edge is a self-loop that is obtained as an incoming edge, which is also obtained
+    /// as an outgoing edge when expanding both directions
+    pub fn is_incoming_self_loop(&self) -> bool {
+        !self.from_src && self.src_id == self.dst_id
+    }
+
     pub fn get_other_id(&self) -> ID {
         if self.from_src {
             self.dst_id
@@ -137,6 +137,39 @@ impl GraphPath {
         }
     }
 
+    /// Get the vertices of the path in order, which are unavailable if only the end of the path is preserved.
+    pub fn get_path_vertices(&self) -> Option<Vec<&Vertex>> {
+        match self {
+            GraphPath::AllPath(ref p) | GraphPath::SimpleAllPath(ref p) => {
+                Some(p.iter().filter_map(|e| e.as_vertex()).collect())
+            }
+            GraphPath::EndV(_) | GraphPath::SimpleEndV(_) => None,
+        }
+    }
+
+    /// Get the element at the position of `index` of the path, where a negative `index` counts
+    /// backwards from the end of the path, e.g., -1 refers to the end. Only the end is available
+    /// if only the end of the path is preserved.
+    pub fn get_path_element(&self, index: i32) -> Option<&VertexOrEdge> {
+        match self {
+            GraphPath::AllPath(ref p) | GraphPath::SimpleAllPath(ref p) => {
+                let index = if index < 0 { p.len() as i64 + index as i64 } else { index as i64 };
+                if index < 0 {
+                    None
+                } else {
+                    p.get(index as usize)
+                }
+            }
+            GraphPath::EndV((ref e, _)) | GraphPath::SimpleEndV((ref e, _)) => {
+                if index == -1 {
+                    Some(e)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
     pub fn take_path(self) -> Option<Vec<VertexOrEdge>> {
         match self {
             GraphPath::AllPath(p) | GraphPath::SimpleAllPath(p) => Some(p),
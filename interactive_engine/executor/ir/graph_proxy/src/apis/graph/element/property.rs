@@ -34,6 +34,10 @@ pub enum PropKey {
     Len,
     All,
     Key(NameOrId),
+    /// The vertices of a path
+    Vertices,
+    /// The element at the given position of a path, counting backwards from the end if negative
+    Element(i32),
 }
 
 impl TryFrom<pb::Property> for PropKey {
@@ -51,6 +55,8 @@ impl TryFrom<pb::Property> for PropKey {
                 Item::Len(_) => Ok(PropKey::Len),
                 Item::All(_) => Ok(PropKey::All),
                 Item::Key(k) => Ok(PropKey::Key(NameOrId::try_from(k)?)),
+                Item::Vertices(_) => Ok(PropKey::Vertices),
+                Item::Element(e) => Ok(PropKey::Element(e.index)),
             }
         } else {
             Err(ParsePbError::from("empty content provided"))
@@ -77,6 +83,13 @@ impl Encode for PropKey {
                 writer.write_u8(4)?;
                 key.write_to(writer)?;
             }
+            PropKey::Vertices => {
+                writer.write_u8(5)?;
+            }
+            PropKey::Element(index) => {
+                writer.write_u8(6)?;
+                writer.write_i32(*index)?;
+            }
         }
         Ok(())
     }
@@ -94,6 +107,11 @@ impl Decode for PropKey {
                 let key = <NameOrId>::read_from(reader)?;
                 Ok(PropKey::Key(key))
             }
+            5 => Ok(PropKey::Vertices),
+            6 => {
+                let index = reader.read_i32()?;
+                Ok(PropKey::Element(index))
+            }
             _ => Err(std::io::Error::new(std::io::ErrorKind::Other, "unreachable")),
         }
     }
@@ -24,7 +24,7 @@ use dyn_type::{BorrowObject, Object};
 use ir_common::error::{ParsePbError, ParsePbResult};
 use ir_common::expr_parse::to_suffix_expr;
 use ir_common::generated::common as common_pb;
-use ir_common::{NameOrId, ALL_KEY, ID_KEY, LABEL_KEY, LENGTH_KEY};
+use ir_common::{NameOrId, ALL_KEY, ELEMENT_KEY_PREFIX, ID_KEY, LABEL_KEY, LENGTH_KEY, VERTICES_KEY};
 
 use crate::apis::{Element, PropKey};
 use crate::utils::expr::eval_pred::EvalPred;
@@ -497,6 +497,12 @@ impl Evaluate for Operand {
                                         .ok_or(ExprEvalError::OtherErr(
                                             "cannot get `Object` from `BorrowObject`".to_string(),
                                         ))?,
+                                    PropKey::Vertices | PropKey::Element(_) => {
+                                        return Err(ExprEvalError::Unsupported(format!(
+                                            "evaluating {:?} of a path in an expression",
+                                            property
+                                        )))
+                                    }
                                 }
                             }
                         } else {
@@ -542,6 +548,10 @@ impl Evaluate for Operand {
                                     PropKey::Label => obj2 = object!(LABEL_KEY),
                                     PropKey::Len => obj2 = object!(LENGTH_KEY),
                                     PropKey::All => obj2 = object!(ALL_KEY),
+                                    PropKey::Vertices => obj2 = object!(VERTICES_KEY),
+                                    PropKey::Element(index) => {
+                                        obj2 = object!(format!("{}{}", ELEMENT_KEY_PREFIX, index))
+                                    }
                                     PropKey::Key(key) => match key {
                                         NameOrId::Str(str) => obj2 = object!(str.as_str()),
                                         NameOrId::Id(id) => obj2 = object!(*id),
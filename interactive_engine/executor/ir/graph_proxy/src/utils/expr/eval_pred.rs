@@ -278,6 +278,12 @@ impl EvalPred for Operand {
                                         PropKey::Key(key) => {
                                             result = graph_element.get_property(key).is_some()
                                         }
+                                        PropKey::Vertices | PropKey::Element(_) => {
+                                            return Err(ExprEvalError::Unsupported(format!(
+                                                "evaluating {:?} of a path in a predicate",
+                                                key
+                                            )))
+                                        }
                                     }
                                 } else {
                                     result = false
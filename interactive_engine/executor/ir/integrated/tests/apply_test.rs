@@ -39,6 +39,8 @@ mod test {
             params: Some(query_params(vec![PERSON_LABEL.into()], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let expand_opr = pb::EdgeExpand {
@@ -48,6 +50,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let mut job_builder = JobBuilder::default();
@@ -149,6 +156,8 @@ mod test {
             params: Some(query_params(vec![PERSON_LABEL.into()], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let expand_opr = pb::EdgeExpand {
@@ -158,6 +167,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let fold_opr = pb::GroupBy {
@@ -166,8 +180,12 @@ mod test {
                 vars: vec![common_pb::Variable::from("@".to_string())],
                 aggregate: 3, // count
                 alias: None,
+                equality: 0,
+                overflow: 0,
+                approximation: None,
             }],
             meta_data: vec![],
+            null_key_opt: 0,
         };
 
         let sink_opr = pb::Sink {
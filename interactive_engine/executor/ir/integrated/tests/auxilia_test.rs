@@ -43,7 +43,15 @@ mod test {
     fn source_gen(alias: Option<KeyId>) -> Box<dyn Iterator<Item = Record> + Send> {
         let graph = create_exp_store(Arc::new(TestCluster {}));
         register_graph(graph);
-        let scan_opr_pb = pb::Scan { scan_opt: 0, alias, params: None, idx_predicate: None };
+        let scan_opr_pb = pb::Scan {
+            scan_opt: 0,
+            alias,
+            params: None,
+            idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
+        };
         let source = SourceOperator::new(scan_opr_pb.into(), Arc::new(TestRouter::default())).unwrap();
         source.gen_source(0).unwrap()
     }
@@ -57,6 +65,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let auxilia_opr = pb::GetV {
@@ -64,6 +79,8 @@ mod test {
             opt: 4,
             params: Some(query_params(vec![], vec![], None)),
             alias: Some(TAG_A.into()),
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("auxilia_simple_alias_test");
@@ -101,6 +118,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let auxilia_opr = pb::GetV {
@@ -108,6 +132,8 @@ mod test {
             opt: 4,
             params: Some(query_params(vec![], vec!["name".into()], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("auxilia_get_property_test");
@@ -152,6 +178,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let auxilia_opr = pb::GetV {
@@ -159,6 +192,8 @@ mod test {
             opt: 4,
             params: Some(query_params(vec![], vec!["name".into()], None)),
             alias: Some(TAG_A.into()),
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("auxilia_get_property_with_none_tag_input_test");
@@ -203,6 +238,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let auxilia_opr = pb::GetV {
@@ -214,6 +256,8 @@ mod test {
                 str_to_expr_pb("@.name==\"vadas\"".to_string()).ok(),
             )),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("auxilia_filter_test");
@@ -258,6 +302,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let auxilia_opr = pb::GetV {
@@ -269,6 +320,8 @@ mod test {
                 str_to_expr_pb("@.name==\"vadas\"".to_string()).ok(),
             )),
             alias: Some(TAG_A.into()),
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("auxilia_alias_test");
@@ -305,12 +358,16 @@ mod test {
             opt: 4,
             params: Some(query_params(vec![], vec!["id".into()], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
         let auxilia_opr_2 = pb::GetV {
             tag: None,
             opt: 4,
             params: Some(query_params(vec![], vec!["name".into()], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("auxilia_update_test");
@@ -367,6 +424,8 @@ mod test {
             opt: 4,
             params: Some(query_params(vec![], vec!["name".into()], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("auxilia_update_on_lazy_vertex_test");
@@ -421,6 +480,13 @@ mod test {
             params: Some(query_param),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let auxilia_opr = pb::GetV {
@@ -428,6 +494,8 @@ mod test {
             opt: 4,
             params: Some(query_params(vec![], vec!["name".into()], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("auxilia_update_on_empty_vertex_test");
@@ -475,6 +543,13 @@ mod test {
             params: None,
             expand_opt: 0,
             alias: Some(TAG_B.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let project_opr = pb::Project {
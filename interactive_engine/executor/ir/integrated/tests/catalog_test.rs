@@ -68,6 +68,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v = pb::GetV {
             tag: None,
@@ -75,6 +80,8 @@ mod test {
             params: None,
             alias: Some(TAG_B.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![pb::pattern::Sentence {
@@ -104,6 +111,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_b = pb::GetV {
             tag: None,
@@ -111,6 +123,8 @@ mod test {
             params: None,
             alias: Some(TAG_B.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_c = pb::GetV {
             tag: None,
@@ -122,6 +136,8 @@ mod test {
             )),
             alias: Some(TAG_C.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![pb::pattern::Sentence {
@@ -151,6 +167,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v = pb::GetV {
             tag: None,
@@ -158,6 +179,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -204,6 +227,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_b = pb::GetV {
             tag: None,
@@ -215,6 +243,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_c = pb::GetV {
             tag: None,
@@ -222,6 +252,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -268,6 +300,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr_in = pb::EdgeExpand {
             v_tag: None,
@@ -276,6 +313,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_start = pb::GetV {
             tag: None,
@@ -283,6 +325,8 @@ mod test {
             params: None,
             alias: Some(TAG_A.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_end = pb::GetV {
             tag: None,
@@ -290,6 +334,8 @@ mod test {
             params: None,
             alias: Some(TAG_A.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![pb::pattern::Sentence {
@@ -327,6 +373,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr_in = pb::EdgeExpand {
             v_tag: None,
@@ -335,6 +386,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_a = pb::GetV {
             tag: None,
@@ -346,6 +402,8 @@ mod test {
             )),
             alias: Some(TAG_A.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_c = pb::GetV {
             tag: None,
@@ -353,6 +411,8 @@ mod test {
             params: None,
             alias: Some(TAG_C.into()),
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![pb::pattern::Sentence {
@@ -386,6 +446,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v = pb::GetV {
             tag: None,
@@ -393,6 +458,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -439,6 +506,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_b = pb::GetV {
             tag: None,
@@ -446,6 +518,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_c = pb::GetV {
             tag: None,
@@ -457,6 +531,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -503,6 +579,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v = pb::GetV {
             tag: None,
@@ -510,6 +591,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -571,6 +654,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr_a_c = pb::EdgeExpand {
             v_tag: None,
@@ -579,6 +667,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr_b_c = pb::EdgeExpand {
             v_tag: None,
@@ -587,6 +680,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_b = pb::GetV {
             tag: None,
@@ -598,6 +696,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_c_1 = pb::GetV {
             tag: None,
@@ -609,6 +709,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_c_2 = pb::GetV {
             tag: None,
@@ -620,6 +722,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let select_marko =
             pb::Select { predicate: Some(str_to_expr_pb("@.name == \"marko\"".to_string()).unwrap()) };
@@ -694,6 +798,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v = pb::GetV {
             tag: None,
@@ -701,6 +810,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let path_expand = pb::PathExpand {
             base: Some(pb::path_expand::ExpandBase {
@@ -713,6 +824,7 @@ mod test {
             path_opt: pb::path_expand::PathOpt::Simple as i32,
             result_opt: pb::path_expand::ResultOpt::EndV as i32,
             condition: None,
+            timeout_ms: 0,
         };
         let pattern = pb::Pattern {
             sentences: vec![pb::pattern::Sentence {
@@ -742,6 +854,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v = pb::GetV {
             tag: None,
@@ -749,6 +866,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let path_expand = pb::PathExpand {
             base: Some(pb::path_expand::ExpandBase {
@@ -761,6 +880,7 @@ mod test {
             path_opt: pb::path_expand::PathOpt::Simple as i32,
             result_opt: pb::path_expand::ResultOpt::EndV as i32,
             condition: None,
+            timeout_ms: 0,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -798,6 +918,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v = pb::GetV {
             tag: None,
@@ -805,6 +930,8 @@ mod test {
             params: Some(query_params(vec![], vec!["name".into()], None)),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_b = pb::GetV {
             tag: None,
@@ -816,6 +943,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let path_expand = pb::PathExpand {
             base: Some(pb::path_expand::ExpandBase {
@@ -828,6 +957,7 @@ mod test {
             path_opt: pb::path_expand::PathOpt::Simple as i32,
             result_opt: pb::path_expand::ResultOpt::EndV as i32,
             condition: None,
+            timeout_ms: 0,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -865,6 +995,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr_in = pb::EdgeExpand {
             v_tag: None,
@@ -873,6 +1008,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_end = pb::GetV {
             tag: None,
@@ -880,6 +1020,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_start = pb::GetV {
             tag: None,
@@ -887,6 +1029,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![pb::pattern::Sentence {
@@ -936,6 +1080,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr_in = pb::EdgeExpand {
             v_tag: None,
@@ -944,6 +1093,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_end = pb::GetV {
             tag: None,
@@ -951,6 +1105,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_start = pb::GetV {
             tag: None,
@@ -958,6 +1114,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_ripple = pb::GetV {
             tag: None,
@@ -969,6 +1127,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let select_vadas =
             pb::Select { predicate: Some(str_to_expr_pb("@.name == \"vadas\"".to_string()).unwrap()) };
@@ -1024,6 +1184,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr_in = pb::EdgeExpand {
             v_tag: None,
@@ -1032,6 +1197,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_end = pb::GetV {
             tag: None,
@@ -1039,6 +1209,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_start = pb::GetV {
             tag: None,
@@ -1046,6 +1218,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -1105,6 +1279,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr_in = pb::EdgeExpand {
             v_tag: None,
@@ -1113,6 +1292,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_lop = pb::GetV {
             tag: None,
@@ -1124,6 +1308,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_ripple = pb::GetV {
             tag: None,
@@ -1135,6 +1321,8 @@ mod test {
             )),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let get_v_start = pb::GetV {
             tag: None,
@@ -1142,6 +1330,8 @@ mod test {
             params: None,
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let select_vadas =
             pb::Select { predicate: Some(str_to_expr_pb("@.name == \"vadas\"".to_string()).unwrap()) };
@@ -1210,6 +1400,11 @@ mod test {
             expand_opt: pb::edge_expand::ExpandOpt::Edge as i32,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let get_v_software = pb::GetV {
             tag: None,
@@ -1217,6 +1412,8 @@ mod test {
             params: Some(query_params(vec![1.into()], vec![], None)),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
         let pattern = pb::Pattern {
             sentences: vec![pb::pattern::Sentence {
@@ -1250,6 +1447,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let select_person =
             pb::Select { predicate: Some(str_to_expr_pb("@.~label == 1".to_string()).unwrap()) };
@@ -1311,6 +1513,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr2 = pb::EdgeExpand {
             v_tag: None,
@@ -1319,6 +1526,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr3 = pb::EdgeExpand {
             v_tag: None,
@@ -1327,6 +1539,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let select_person =
             pb::Select { predicate: Some(str_to_expr_pb("@.~label == 1".to_string()).unwrap()) };
@@ -1379,6 +1596,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -1451,6 +1673,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr2 = pb::EdgeExpand {
             v_tag: None,
@@ -1459,6 +1686,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr3 = pb::EdgeExpand {
             v_tag: None,
@@ -1467,6 +1699,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr4 = pb::EdgeExpand {
             v_tag: None,
@@ -1475,6 +1712,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -1525,6 +1767,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr1 = pb::EdgeExpand {
             v_tag: None,
@@ -1533,6 +1780,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let expand_opr2 = pb::EdgeExpand {
             v_tag: None,
@@ -1541,6 +1793,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
         let pattern = pb::Pattern {
             sentences: vec![
@@ -1632,6 +1889,7 @@ mod test {
             sink_target: Some(pb::sink::SinkTarget {
                 inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
                     id_name_mappings: vec![],
+                    layout: 0,
                 })),
             }),
         }
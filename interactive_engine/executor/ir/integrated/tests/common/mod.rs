@@ -23,7 +23,7 @@ pub mod test {
     use std::convert::{TryFrom, TryInto};
     use std::sync::{Arc, Once};
 
-    use graph_proxy::apis::partitioner::PartitionKeyId;
+    use graph_proxy::apis::partitioner::{PartitionId, PartitionKeyId};
     use graph_proxy::apis::{
         register_graph, ClusterInfo, DynDetails, Edge, PegasusClusterInfo, Vertex, ID,
     };
@@ -147,6 +147,7 @@ pub mod test {
             predicate,
             sample_ratio: 1.0,
             extra: HashMap::new(),
+            degree_predicates: vec![],
         }
     }
 
@@ -162,6 +163,7 @@ pub mod test {
             predicate,
             sample_ratio: 1.0,
             extra: HashMap::new(),
+            degree_predicates: vec![],
         }
     }
 
@@ -223,6 +225,7 @@ pub mod test {
             sink_target: Some(pb::sink::SinkTarget {
                 inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
                     id_name_mappings: vec![],
+                    layout: 0,
                 })),
             }),
         }
@@ -232,6 +235,7 @@ pub mod test {
         Some(pb::sink::SinkTarget {
             inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
                 id_name_mappings: vec![],
+                layout: 0,
             })),
         })
     }
@@ -252,6 +256,10 @@ pub mod test {
         fn route(&self, data: PartitionKeyId) -> GraphProxyResult<u64> {
             Ok((data as usize % self.num_workers) as u64)
         }
+
+        fn route_partition(&self, partition_id: PartitionId) -> GraphProxyResult<Vec<u64>> {
+            Ok(vec![(partition_id as usize % self.num_workers) as u64])
+        }
     }
 
     pub struct TestCluster {}
@@ -27,6 +27,7 @@ mod test {
     use graph_store::ldbc::LDBCVertexParser;
     use graph_store::prelude::DefaultId;
     use ir_common::expr_parse::str_to_expr_pb;
+    use ir_common::generated::algebra as algebra_pb;
     use ir_common::generated::physical as pb;
     use ir_common::KeyId;
     use pegasus::api::{Map, Sink};
@@ -43,7 +44,15 @@ mod test {
 
     // g.V()
     fn source_gen(alias: Option<KeyId>) -> Box<dyn Iterator<Item = Record> + Send> {
-        source_gen_with_scan_opr(pb::Scan { scan_opt: 0, alias, params: None, idx_predicate: None })
+        source_gen_with_scan_opr(pb::Scan {
+            scan_opt: 0,
+            alias,
+            params: None,
+            idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
+        })
     }
 
     fn source_gen_with_scan_opr(scan_opr_pb: pb::Scan) -> Box<dyn Iterator<Item = Record> + Send> {
@@ -70,7 +79,14 @@ mod test {
 
     fn expand_degree_opt_test(expand: pb::EdgeExpand) -> ResultStream<Record> {
         let conf = JobConf::new("expand_degree_fused_test");
-        let getv = pb::GetV { tag: None, opt: 4, params: None, alias: Some(TAG_A) };
+        let getv = pb::GetV {
+            tag: None,
+            opt: 4,
+            params: None,
+            alias: Some(TAG_A),
+            is_optional: false,
+            end_alias: None,
+        };
         let expand = expand.clone();
         let project = pb::Project {
             mappings: vec![pb::project::ExprAlias {
@@ -119,8 +135,20 @@ mod test {
     // g.V().out()
     #[test]
     fn expand_outv_test() {
-        let expand_opr_pb =
-            pb::EdgeExpand { v_tag: None, direction: 0, params: None, expand_opt: 0, alias: None };
+        let expand_opr_pb = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: None,
+            expand_opt: 0,
+            alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
+        };
         let mut result = expand_test(expand_opr_pb);
         let mut result_ids = vec![];
         let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
@@ -138,6 +166,242 @@ mod test {
         assert_eq!(result_ids, expected_ids)
     }
 
+    // g.V().out(), skipping self-loops and collapsing parallel edges,
+    // where the modern graph contains neither of them
+    #[test]
+    fn expand_outv_skip_loops_and_parallels_test() {
+        let expand_opr_pb = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: None,
+            expand_opt: 0,
+            alias: None,
+            dedup_both: false,
+            exclude_self_loops: true,
+            collapse_parallel_edges: true,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
+        };
+        let mut result = expand_test(expand_opr_pb);
+        let mut result_ids = vec![];
+        let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
+        let v3: DefaultId = LDBCVertexParser::to_global_id(3, 1);
+        let v4: DefaultId = LDBCVertexParser::to_global_id(4, 0);
+        let v5: DefaultId = LDBCVertexParser::to_global_id(5, 1);
+        let mut expected_ids = vec![v2, v3, v3, v3, v4, v5];
+        while let Some(Ok(record)) = result.next() {
+            if let Some(element) = record.get(None).unwrap().as_vertex() {
+                result_ids.push(element.id() as usize)
+            }
+        }
+        result_ids.sort();
+        expected_ids.sort();
+        assert_eq!(result_ids, expected_ids)
+    }
+
+    // g.V().outE().inV().hasLabel("software"), with the label of the adjacent vertices in the expansion
+    #[test]
+    fn expand_outv_with_vertex_label_test() {
+        let expand_opr_pb = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: None,
+            expand_opt: 0,
+            alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![SOFTWARE_LABEL.into()],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
+        };
+        let mut result = expand_test(expand_opr_pb);
+        let mut result_ids = vec![];
+        let v3: DefaultId = LDBCVertexParser::to_global_id(3, 1);
+        let v5: DefaultId = LDBCVertexParser::to_global_id(5, 1);
+        let mut expected_ids = vec![v3, v3, v3, v5];
+        while let Some(Ok(record)) = result.next() {
+            if let Some(element) = record.get(None).unwrap().as_vertex() {
+                result_ids.push(element.id() as usize)
+            }
+        }
+        result_ids.sort();
+        expected_ids.sort();
+        assert_eq!(result_ids, expected_ids)
+    }
+
+    // g.V().out(), only from the vertices of out-degree < 2
+    #[test]
+    fn expand_outv_with_degree_predicate_test() {
+        let mut query_param = query_params(vec![], vec![], None);
+        query_param
+            .degree_predicates
+            .push(algebra_pb::DegreePredicate {
+                direction: 0,
+                tables: vec![],
+                range: Some(algebra_pb::Range { lower: 0, upper: 2 }),
+            });
+        let expand_opr_pb = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_param),
+            expand_opt: 0,
+            alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
+        };
+        let mut result = expand_test(expand_opr_pb);
+        let mut result_ids = vec![];
+        // v1 and v4 are of out-degree 3 and 2, respectively, while v6 is of out-degree 1
+        let v3: DefaultId = LDBCVertexParser::to_global_id(3, 1);
+        let expected_ids = vec![v3];
+        while let Some(Ok(record)) = result.next() {
+            if let Some(element) = record.get(None).unwrap().as_vertex() {
+                result_ids.push(element.id() as usize)
+            }
+        }
+        assert_eq!(result_ids, expected_ids)
+    }
+
+    // g.V().out('created'), optionally, where the vertices that created nothing are kept with null
+    #[test]
+    fn expand_outv_optional_test() {
+        let expand_opr_pb = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![CREATED_LABEL.into()], vec![], None)),
+            expand_opt: 0,
+            alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: true,
+            is_distinct: false,
+        };
+        let mut result = expand_test(expand_opr_pb);
+        let mut result_ids = vec![];
+        let mut null_count = 0;
+        let v3: DefaultId = LDBCVertexParser::to_global_id(3, 1);
+        let v5: DefaultId = LDBCVertexParser::to_global_id(5, 1);
+        let mut expected_ids = vec![v3, v3, v3, v5];
+        while let Some(Ok(record)) = result.next() {
+            let entry = record.get(None).unwrap();
+            if let Some(element) = entry.as_vertex() {
+                result_ids.push(element.id() as usize)
+            } else if entry.is_none() {
+                null_count += 1;
+            }
+        }
+        result_ids.sort();
+        expected_ids.sort();
+        assert_eq!(result_ids, expected_ids);
+        // v2, v3 and v5 created nothing
+        assert_eq!(null_count, 3);
+    }
+
+    // g.V().out().dedup(), fused as a distinct expansion, where the edges are explored, or not
+    // while collapsing the parallel edges
+    #[test]
+    fn expand_outv_distinct_test() {
+        for collapse_parallel_edges in [false, true] {
+            let expand_opr_pb = pb::EdgeExpand {
+                v_tag: None,
+                direction: 0,
+                params: None,
+                expand_opt: 0,
+                alias: None,
+                dedup_both: false,
+                exclude_self_loops: false,
+                collapse_parallel_edges,
+                vertex_tables: vec![],
+                super_node_policy: None,
+                is_optional: false,
+                is_distinct: true,
+            };
+            let mut result = expand_test(expand_opr_pb);
+            let mut result_ids = vec![];
+            let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
+            let v3: DefaultId = LDBCVertexParser::to_global_id(3, 1);
+            let v4: DefaultId = LDBCVertexParser::to_global_id(4, 0);
+            let v5: DefaultId = LDBCVertexParser::to_global_id(5, 1);
+            let mut expected_ids = vec![v2, v3, v4, v5];
+            while let Some(Ok(record)) = result.next() {
+                if let Some(element) = record.get(None).unwrap().as_vertex() {
+                    result_ids.push(element.id() as usize)
+                }
+            }
+            result_ids.sort();
+            expected_ids.sort();
+            assert_eq!(result_ids, expected_ids)
+        }
+    }
+
+    // g.V().out(), with the super nodes of more than 1 neighbors truncated
+    #[test]
+    fn expand_outv_with_super_node_truncated_test() {
+        let expand_opr_pb = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![], None)),
+            expand_opt: 0,
+            alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: Some(algebra_pb::SuperNodePolicy { action: 0, threshold: 1 }),
+            is_optional: false,
+            is_distinct: false,
+        };
+        let mut result = expand_test(expand_opr_pb);
+        let mut result_count = 0;
+        while let Some(Ok(record)) = result.next() {
+            if record.get(None).unwrap().as_vertex().is_some() {
+                result_count += 1;
+            }
+        }
+        // v1, v4 and v6 are of out-degree 3, 2 and 1, each yielding one neighbor at most
+        assert_eq!(result_count, 3)
+    }
+
+    // g.V().out(), which fails on the super nodes of more than 2 neighbors
+    #[test]
+    fn expand_outv_with_super_node_failed_test() {
+        let expand_opr_pb = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![], vec![], None)),
+            expand_opt: 0,
+            alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: Some(algebra_pb::SuperNodePolicy { action: 2, threshold: 2 }),
+            is_optional: false,
+            is_distinct: false,
+        };
+        let mut result = expand_test(expand_opr_pb);
+        let mut has_error = false;
+        while let Some(record) = result.next() {
+            if record.is_err() {
+                has_error = true;
+            }
+        }
+        // v1 is of out-degree 3
+        assert!(has_error)
+    }
+
     // g.V().outE().hasLabel("knows")
     #[test]
     fn expand_oute_with_label_test() {
@@ -148,6 +412,13 @@ mod test {
             params: Some(query_param),
             expand_opt: 1,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut result = expand_test(expand_opr_pb);
         let mut result_edges = vec![];
@@ -174,6 +445,13 @@ mod test {
             params: Some(query_param),
             expand_opt: 1,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut result = expand_test(expand_opr_pb);
         let mut result_edges = vec![];
@@ -204,6 +482,13 @@ mod test {
             params: Some(query_param),
             expand_opt: 1,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut result = expand_test(expand_opr_pb);
         let mut result_ids_with_prop = vec![];
@@ -235,6 +520,13 @@ mod test {
             params: Some(query_param),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut result = expand_test(expand_opr_pb);
         let mut cnt = 0;
@@ -255,6 +547,13 @@ mod test {
             params: Some(query_param),
             expand_opt: 0,
             alias: Some(TAG_B.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut result = expand_test_with_source_tag(TAG_A.into(), expand_opr_pb);
         let mut result_ids = vec![];
@@ -288,6 +587,13 @@ mod test {
             params: Some(query_param),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let conf = JobConf::new("expand_test");
@@ -329,9 +635,23 @@ mod test {
             params: Some(edge_query_param),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let vertex_query_param = query_params(vec![], vec![], str_to_expr_pb("@.id == 2".to_string()).ok());
-        let auxilia_opr_pb = pb::GetV { tag: None, opt: 4, params: Some(vertex_query_param), alias: None };
+        let auxilia_opr_pb = pb::GetV {
+            tag: None,
+            opt: 4,
+            params: Some(vertex_query_param),
+            alias: None,
+            is_optional: false,
+            end_alias: None,
+        };
 
         let conf = JobConf::new("expand_getv_test");
         let mut result = pegasus::run(conf, || {
@@ -370,6 +690,13 @@ mod test {
             params: Some(query_param),
             expand_opt: 0,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut result = expand_test(expand_opr_pb);
         let mut result_ids = vec![];
@@ -392,6 +719,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             expand_opt: 1,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let getv_opr = pb::GetV {
@@ -399,6 +733,8 @@ mod test {
             opt: 1, // EndV
             params: Some(query_params(vec![], vec![], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("expand_oute_inv_test");
@@ -427,6 +763,59 @@ mod test {
         assert_eq!(result_ids, expected_ids)
     }
 
+    // g.V().outE('knows').as('e').select('e').by(outV().as('a'), inV().as('b')) in one GetV
+    #[test]
+    fn expand_oute_endpoints_test() {
+        let expand_opr = pb::EdgeExpand {
+            v_tag: None,
+            direction: 0,
+            params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
+            expand_opt: 1,
+            alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
+        };
+
+        let getv_opr = pb::GetV {
+            tag: None,
+            opt: 5, // Endpoints
+            params: Some(query_params(vec![], vec![], None)),
+            alias: Some(TAG_A.into()),
+            is_optional: false,
+            end_alias: Some(TAG_B.into()),
+        };
+
+        let conf = JobConf::new("expand_oute_endpoints_test");
+        let mut result = pegasus::run(conf, || {
+            let expand = expand_opr.clone();
+            let getv = getv_opr.clone();
+            |input, output| {
+                let mut stream = input.input_from(source_gen(None))?;
+                let flatmap_func = expand.gen_flat_map().unwrap();
+                stream = stream.flat_map(move |input| flatmap_func.exec(input))?;
+                let filter_map_func = getv.gen_filter_map().unwrap();
+                stream = stream.filter_map(move |input| filter_map_func.exec(input))?;
+                stream.sink_into(output)
+            }
+        })
+        .expect("build job failure");
+
+        let expected_ids = vec![(1, 2), (1, 4)];
+        let mut result_ids = vec![];
+        while let Some(Ok(record)) = result.next() {
+            let start = record.get(Some(TAG_A)).unwrap().id() as usize;
+            let end = record.get(Some(TAG_B)).unwrap().id() as usize;
+            result_ids.push((start, end));
+        }
+        result_ids.sort();
+        assert_eq!(result_ids, expected_ids)
+    }
+
     // g.V().inE('created').outV()
     #[test]
     fn expand_ine_outv_test() {
@@ -436,6 +825,13 @@ mod test {
             params: Some(query_params(vec![CREATED_LABEL.into()], vec![], None)),
             expand_opt: 1,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let getv_opr = pb::GetV {
@@ -443,6 +839,8 @@ mod test {
             opt: 0, // StartV
             params: Some(query_params(vec![], vec![], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("expand_ine_outv_test");
@@ -480,6 +878,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             expand_opt: 1,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let getv_opr = pb::GetV {
@@ -487,6 +892,8 @@ mod test {
             opt: 2, // OtherV
             params: Some(query_params(vec![], vec![], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("expand_bothe_otherv_test");
@@ -524,6 +931,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             expand_opt: 1,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let getv_opr = pb::GetV {
@@ -531,6 +945,8 @@ mod test {
             opt: 3, // BothV
             params: Some(query_params(vec![], vec![], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("expand_oute_bothv_test");
@@ -568,6 +984,13 @@ mod test {
             params: None,
             expand_opt: 2,
             alias: Some(1.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut pegasus_result = expand_degree_opt_test(expand_opr_pb);
         let mut results = vec![];
@@ -600,6 +1023,13 @@ mod test {
             params: None,
             expand_opt: 2,
             alias: Some(1.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut pegasus_result = expand_degree_opt_test(expand_opr_pb);
         let mut results = vec![];
@@ -632,6 +1062,13 @@ mod test {
             params: None,
             expand_opt: 2,
             alias: Some(1.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
         let mut pegasus_result = expand_degree_opt_test(expand_opr_pb);
         let mut results = vec![];
@@ -666,6 +1103,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_B.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // marko (A) -> josh (C): expand C;
@@ -675,6 +1119,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let conf = JobConf::new("expand_and_intersection_expand_test");
@@ -688,6 +1139,9 @@ mod test {
                     alias: Some(TAG_A.into()),
                     params: None,
                     idx_predicate: Some(vec![1].into()),
+                    connector: None,
+                    named_result: String::new(),
+                    partitions: vec![],
                 });
                 let mut stream = input.input_from(source_iter)?;
                 let flatmap_func1 = expand1.gen_flat_map().unwrap();
@@ -735,6 +1189,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_B.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // marko (A) -> josh (C): expand C;
@@ -744,6 +1205,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // lop (B) <- josh (C): expand C and intersect on C;
@@ -753,6 +1221,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let conf = JobConf::new("expand_and_intersection_intersect_test");
@@ -767,6 +1242,9 @@ mod test {
                     alias: Some(TAG_A.into()),
                     params: None,
                     idx_predicate: Some(vec![1].into()),
+                    connector: None,
+                    named_result: String::new(),
+                    partitions: vec![],
                 });
                 let mut stream = input.input_from(source_iter)?;
                 let flatmap_func1 = expand1.gen_flat_map().unwrap();
@@ -812,6 +1290,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_B.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // marko (A) -> josh (C): expand C;
@@ -821,6 +1306,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // lop (B) <- josh (C): expand C and intersect on C;
@@ -830,6 +1322,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // unfold tag C
@@ -848,6 +1347,9 @@ mod test {
                     alias: Some(TAG_A.into()),
                     params: None,
                     idx_predicate: Some(vec![1].into()),
+                    connector: None,
+                    named_result: String::new(),
+                    partitions: vec![],
                 });
                 let mut stream = input.input_from(source_iter)?;
                 let flatmap_func1 = expand1.gen_flat_map().unwrap();
@@ -884,6 +1386,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_B.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // A <-> C: expand C;
@@ -893,6 +1402,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // B <-> C: expand C and intersect on C;
@@ -902,6 +1418,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // unfold tag C
@@ -954,6 +1477,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_B.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // A <-> C: expand C;
@@ -967,6 +1497,13 @@ mod test {
             )),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // B <-> C: expand C and intersect on C;
@@ -976,6 +1513,13 @@ mod test {
             params: Some(query_params(vec![KNOWS_LABEL.into(), CREATED_LABEL.into()], vec![], None)),
             expand_opt: 0,
             alias: Some(TAG_C.into()),
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         // unfold tag C
@@ -994,6 +1538,9 @@ mod test {
                     alias: Some(TAG_A.into()),
                     params: None,
                     idx_predicate: Some(vec![1].into()),
+                    connector: None,
+                    named_result: String::new(),
+                    partitions: vec![],
                 });
                 let mut stream = input.input_from(source_iter)?;
                 let flatmap_func1 = expand1.gen_flat_map().unwrap();
@@ -1031,6 +1578,13 @@ mod test {
             params: Some(query_params(vec![], vec![], None)),
             expand_opt: 1,
             alias: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            super_node_policy: None,
+            is_optional: false,
+            is_distinct: false,
         };
 
         let getv_opr = pb::GetV {
@@ -1038,6 +1592,8 @@ mod test {
             opt: 1, // EndV
             params: Some(query_params(vec![PERSON_LABEL.into()], vec![], None)),
             alias: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let conf = JobConf::new("expand_ine_outv_haslabel_test");
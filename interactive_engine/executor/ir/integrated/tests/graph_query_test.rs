@@ -38,6 +38,8 @@ mod test {
             params: Some(query_params(vec![PERSON_LABEL.into()], vec!["id".into()], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
         let select_opr = pb::Select { predicate: Some(str_to_expr_pb("@.id == 1".to_string()).unwrap()) };
         let expand_opr = pb::EdgeExpand {
@@ -47,6 +49,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let mut job_builder = JobBuilder::default();
@@ -54,7 +61,7 @@ mod test {
         job_builder.select(select_opr);
         job_builder.shuffle(None);
         job_builder.edge_expand(expand_opr);
-        job_builder.limit(pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }) });
+        job_builder.limit(pb::Limit { range: Some(pb::Range { lower: 0, upper: 10 }), per_partition: false });
         job_builder.sink(default_sink_pb());
 
         job_builder.build().unwrap()
@@ -101,6 +108,8 @@ mod test {
             params: Some(query_params_all_columns(vec![PERSON_LABEL.into()], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let project_opr = pb::Project {
@@ -127,6 +136,8 @@ mod test {
             params: Some(query_params_all_columns(vec![], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let expand_opr = pb::EdgeExpand {
@@ -136,6 +147,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let project_opr = pb::Project {
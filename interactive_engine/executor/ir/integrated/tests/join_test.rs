@@ -40,6 +40,8 @@ mod test {
             params: Some(query_params(vec![], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         // person vertices
@@ -49,6 +51,8 @@ mod test {
             params: Some(query_params(vec![PERSON_LABEL.into()], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let mut job_builder = JobBuilder::default();
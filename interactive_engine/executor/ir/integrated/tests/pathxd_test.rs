@@ -31,12 +31,21 @@ mod test {
     // g.V().hasLabel("person").both("lower..upper", "knows")
     // result_opt: 0: EndV, 1: AllV, 2: AllVE;  path_opt: 0: Arbitrary, 1: Simple
     fn init_path_expand_request(range: pb::Range, result_opt: i32, path_opt: i32) -> JobRequest {
+        init_path_expand_with_timeout_request(range, result_opt, path_opt, 0)
+    }
+
+    // g.V().hasLabel("person").both("lower..upper", "knows") with a soft timeout of the expansion
+    fn init_path_expand_with_timeout_request(
+        range: pb::Range, result_opt: i32, path_opt: i32, timeout_ms: u64,
+    ) -> JobRequest {
         let source_opr = pb::Scan {
             scan_opt: 0,
             alias: None,
             params: Some(query_params(vec![PERSON_LABEL.into()], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let edge_expand = pb::EdgeExpand {
@@ -46,6 +55,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let path_expand_opr = pb::PathExpand {
@@ -56,6 +70,7 @@ mod test {
             path_opt,
             result_opt,
             condition: None,
+            timeout_ms,
         };
 
         let mut job_builder = JobBuilder::default();
@@ -86,6 +101,8 @@ mod test {
             params: Some(query_params(vec![PERSON_LABEL.into()], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let edge_expand = pb::EdgeExpand {
@@ -95,6 +112,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let path_expand_opr = pb::PathExpand {
@@ -105,6 +127,7 @@ mod test {
             path_opt: 0,
             result_opt: 1,
             condition: str_to_expr_pb("@.name == \"marko\"".to_string()).ok(),
+            timeout_ms: 0,
         };
 
         let mut job_builder = JobBuilder::default();
@@ -125,6 +148,8 @@ mod test {
             params: Some(query_params(vec![PERSON_LABEL.into()], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let edge_expand = pb::EdgeExpand {
@@ -134,6 +159,11 @@ mod test {
             expand_opt: 0,
             alias: None,
             meta_data: None,
+            dedup_both: false,
+            exclude_self_loops: false,
+            collapse_parallel_edges: false,
+            vertex_tables: vec![],
+            is_optional: false,
         };
 
         let getv = pb::GetV {
@@ -142,6 +172,8 @@ mod test {
             params: Some(query_params(vec![], vec![], str_to_expr_pb("@.age >28".to_string()).ok())),
             alias: None,
             meta_data: None,
+            is_optional: false,
+            end_alias: None,
         };
 
         let path_expand_opr = pb::PathExpand {
@@ -152,6 +184,7 @@ mod test {
             path_opt: 0,
             result_opt: if is_whole_path { 1 } else { 0 },
             condition: None,
+            timeout_ms: 0,
         };
 
         let mut job_builder = JobBuilder::default();
@@ -249,6 +282,40 @@ mod test {
         path_expand_end_query(2)
     }
 
+    // both(1..3), with a timeout that is long enough for the expansion to complete
+    fn path_expand_end_with_timeout_query(num_workers: u32) {
+        initialize();
+        let request = init_path_expand_with_timeout_request(pb::Range { lower: 1, upper: 3 }, 0, 0, 60_000);
+        let mut results = submit_query(request, num_workers);
+        let mut result_collection = vec![];
+        let expected_result_path_ends = vec![1, 1, 1, 1, 2, 2, 2, 4, 4, 4];
+        while let Some(result) = results.next() {
+            match result {
+                Ok(res) => {
+                    let entry = parse_result(res).unwrap();
+                    if let Some(path) = entry.get(None).unwrap().as_graph_path() {
+                        result_collection.push(path.get_path_end().id());
+                    }
+                }
+                Err(e) => {
+                    panic!("err result {:?}", e);
+                }
+            }
+        }
+        result_collection.sort();
+        assert_eq!(result_collection, expected_result_path_ends)
+    }
+
+    #[test]
+    fn path_expand_end_with_timeout_query_test() {
+        path_expand_end_with_timeout_query(1)
+    }
+
+    #[test]
+    fn path_expand_end_with_timeout_query_w2_test() {
+        path_expand_end_with_timeout_query(2)
+    }
+
     // both(2..3)
     fn path_expand_exactly_whole_query(worker_num: u32) {
         initialize();
@@ -43,8 +43,15 @@ mod test {
     // g.V()
     #[test]
     fn scan_test() {
-        let source_iter =
-            scan_gen(pb::Scan { scan_opt: 0, alias: None, params: None, idx_predicate: None });
+        let source_iter = scan_gen(pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: None,
+            idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
+        });
         let mut result_ids = vec![];
         let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
         let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
@@ -71,6 +78,9 @@ mod test {
             alias: None,
             params: Some(query_params(vec![PERSON_LABEL.into()], vec![], None)),
             idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
         });
         let mut result_ids = vec![];
         let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
@@ -96,6 +106,9 @@ mod test {
             alias: None,
             params: Some(query_params(vec![PERSON_LABEL.into(), SOFTWARE_LABEL.into()], vec![], None)),
             idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
         });
         let mut result_ids = vec![];
         let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
@@ -123,6 +136,9 @@ mod test {
             alias: None,
             params: None,
             idx_predicate: Some(vec![1].into()),
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
         });
 
         let mut result_ids = vec![];
@@ -146,6 +162,9 @@ mod test {
             alias: None,
             params: None,
             idx_predicate: Some(vec![1, 2].into()),
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
         });
 
         let mut result_ids = vec![];
@@ -167,8 +186,15 @@ mod test {
     fn scan_sample_test() {
         let mut params = query_params(vec![], vec![], None);
         params.sample_ratio = 0.1;
-        let source_iter =
-            scan_gen(pb::Scan { scan_opt: 0, alias: None, params: Some(params), idx_predicate: None });
+        let source_iter = scan_gen(pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(params),
+            idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
+        });
         let mut result_count = 0;
         for record in source_iter {
             if let Some(_element) = record.get(None).unwrap().as_vertex() {
@@ -182,8 +208,15 @@ mod test {
     // g.E()
     #[test]
     fn scan_edge_test() {
-        let source_iter =
-            scan_gen(pb::Scan { scan_opt: 1, alias: None, params: None, idx_predicate: None });
+        let source_iter = scan_gen(pb::Scan {
+            scan_opt: 1,
+            alias: None,
+            params: None,
+            idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
+        });
         let mut result_ids = vec![];
         let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
         let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
@@ -210,6 +243,9 @@ mod test {
             alias: None,
             params: Some(query_params(vec![KNOWS_LABEL.into()], vec![], None)),
             idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
         });
         let mut result_ids = vec![];
         let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
@@ -231,8 +267,15 @@ mod test {
     fn scan_edge_sample_test() {
         let mut params = query_params(vec![], vec![], None);
         params.sample_ratio = 0.1;
-        let source_iter =
-            scan_gen(pb::Scan { scan_opt: 1, alias: None, params: Some(params), idx_predicate: None });
+        let source_iter = scan_gen(pb::Scan {
+            scan_opt: 1,
+            alias: None,
+            params: Some(params),
+            idx_predicate: None,
+            connector: None,
+            named_result: String::new(),
+            partitions: vec![],
+        });
         let mut result_count = 0;
         for record in source_iter {
             if let Some(_element) = record.get(None).unwrap().as_edge() {
@@ -25,9 +25,11 @@ mod test {
     use graph_store::ldbc::LDBCVertexParser;
     use ir_common::generated::algebra as pb;
     use ir_common::generated::common as common_pb;
+    use ir_common::generated::results as result_pb;
     use ir_common::KeyId;
     use ir_physical_client::physical_builder::JobBuilder;
     use pegasus_server::JobRequest;
+    use prost::Message;
     use runtime::process::entry::Entry;
 
     use crate::common::test::*;
@@ -42,6 +44,8 @@ mod test {
             params: Some(query_params(vec![], vec![], None)),
             idx_predicate: None,
             meta_data: None,
+            connector: None,
+            named_result: String::new(),
         };
 
         let sink_opr = pb::Sink { tags: sink_keys, sink_target: default_sink_target() };
@@ -213,4 +217,130 @@ mod test {
             }
         }
     }
+
+    // g.V() + Sink(None), of which the results are returned in the columnar layout
+    #[test]
+    fn sink_with_columnar_layout() {
+        initialize();
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![], vec![], None)),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let sink_opr = pb::Sink {
+            tags: vec![common_pb::NameOrIdKey { key: None }],
+            sink_target: Some(pb::sink::SinkTarget {
+                inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
+                    id_name_mappings: vec![],
+                    layout: pb::sink_default::Layout::Columnar as i32,
+                })),
+            }),
+        };
+        let mut job_builder = JobBuilder::default();
+        job_builder.add_scan_source(source_opr);
+        job_builder.sink(sink_opr);
+        let request = job_builder.build().unwrap();
+
+        let mut results = submit_query(request, 2);
+        let mut result_collection = vec![];
+        let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+        let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
+        let v3: DefaultId = LDBCVertexParser::to_global_id(3, 1);
+        let v4: DefaultId = LDBCVertexParser::to_global_id(4, 0);
+        let v5: DefaultId = LDBCVertexParser::to_global_id(5, 1);
+        let v6: DefaultId = LDBCVertexParser::to_global_id(6, 0);
+        let mut expected_result_ids = vec![v1, v2, v3, v4, v5, v6];
+        while let Some(result) = results.next() {
+            match result {
+                Ok(res) => {
+                    let result = result_pb::Results::decode(res.as_slice()).unwrap();
+                    match result.inner {
+                        Some(result_pb::results::Inner::ColumnBatch(batch)) => {
+                            // a single column of the head
+                            assert_eq!(batch.columns.len(), 1);
+                            assert!(batch.columns[0].name_or_id.is_none());
+                            assert_eq!(batch.columns[0].entries.len(), batch.num_rows as usize);
+                            for entry in &batch.columns[0].entries {
+                                if let Some(result_pb::entry::Inner::Element(result_pb::Element {
+                                    inner: Some(result_pb::element::Inner::Vertex(vertex)),
+                                })) = entry.inner.as_ref()
+                                {
+                                    result_collection.push(vertex.id as usize);
+                                }
+                            }
+                        }
+                        _ => panic!("expect a column batch but got {:?}", result),
+                    }
+                }
+                Err(e) => {
+                    panic!("err result {:?}", e);
+                }
+            }
+        }
+        expected_result_ids.sort();
+        result_collection.sort();
+        assert_eq!(result_collection, expected_result_ids)
+    }
+
+    // g.V().hasLabel("person") + Sink as "sink_test_persons", then scan "sink_test_persons" as 0 + Sink(0)
+    #[test]
+    fn sink_result_then_scan() {
+        initialize();
+        let source_opr = pb::Scan {
+            scan_opt: 0,
+            alias: None,
+            params: Some(query_params(vec![0.into()], vec![], None)),
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: String::new(),
+        };
+        let sink_opr = pb::Sink {
+            tags: vec![],
+            sink_target: Some(pb::sink::SinkTarget {
+                inner: Some(pb::sink::sink_target::Inner::SinkResult(pb::SinkResult {
+                    name: "sink_test_persons".to_string(),
+                })),
+            }),
+        };
+        let mut job_builder = JobBuilder::default();
+        job_builder.add_scan_source(source_opr);
+        job_builder.sink(sink_opr);
+        for result in submit_query(job_builder.build().unwrap(), 2) {
+            result.unwrap();
+        }
+
+        let scan_opr = pb::Scan {
+            scan_opt: 2,
+            alias: Some(0.into()),
+            params: None,
+            idx_predicate: None,
+            meta_data: None,
+            connector: None,
+            named_result: "sink_test_persons".to_string(),
+        };
+        let mut job_builder = JobBuilder::default();
+        job_builder.add_scan_source(scan_opr);
+        job_builder.sink(pb::Sink {
+            tags: vec![common_pb::NameOrIdKey { key: Some(0.into()) }],
+            sink_target: default_sink_target(),
+        });
+        let mut result_collection = vec![];
+        for result in submit_query(job_builder.build().unwrap(), 2) {
+            let entry = parse_result(result.unwrap()).unwrap();
+            if let Some(vertex) = entry.get(Some(0)).unwrap().as_vertex() {
+                result_collection.push(vertex.id() as usize);
+            }
+        }
+        let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+        let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
+        let v4: DefaultId = LDBCVertexParser::to_global_id(4, 0);
+        let v6: DefaultId = LDBCVertexParser::to_global_id(6, 0);
+        result_collection.sort();
+        assert_eq!(result_collection, vec![v1, v2, v4, v6]);
+    }
 }
@@ -15,18 +15,19 @@
 
 use std::convert::TryInto;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use graph_proxy::apis::cluster_info::ClusterInfo;
 use graph_proxy::apis::partitioner::PartitionInfo;
 use ir_common::error::ParsePbError;
 use ir_common::generated::algebra as algebra_pb;
-use ir_common::generated::algebra::join::JoinKind;
+use ir_common::generated::algebra::join::{rename::Side, JoinKind};
 use ir_common::generated::physical as pb;
 use ir_common::generated::physical::physical_opr::operator::OpKind;
 use pegasus::api::function::*;
 use pegasus::api::{
     Collect, CorrelatedSubTask, Count, Dedup, EmitKind, Filter, Fold, FoldByKey, HasAny, IterCondition,
-    Iteration, Join, KeyBy, Limit, Map, Merge, PartitionByKey, Sink, SortBy, SortLimitBy,
+    Iteration, Join, KeyBy, Limit, Map, Merge, PartitionByKey, Sink, SortBy, SortLimitBy, Unary,
 };
 use pegasus::stream::Stream;
 use pegasus::{BuildJobError, Worker};
@@ -39,9 +40,12 @@ use crate::process::functions::{ApplyGen, CompareFunction, FoldGen, GroupGen, Jo
 use crate::process::operator::accum::accumulator::Accumulator;
 use crate::process::operator::filter::FilterFuncGen;
 use crate::process::operator::flatmap::FlatMapFuncGen;
+use crate::process::operator::group::gen_count_map;
+use crate::process::operator::join::gen_join_renames;
 use crate::process::operator::keyed::KeyFunctionGen;
 use crate::process::operator::map::{FilterMapFuncGen, MapFuncGen};
 use crate::process::operator::shuffle::RecordRouter;
+use crate::process::operator::sink::sink_result::release_partition;
 use crate::process::operator::sink::{SinkGen, Sinker};
 use crate::process::operator::sort::CompareFunctionGen;
 use crate::process::operator::source::SourceOperator;
@@ -66,11 +70,13 @@ pub struct IRJobAssembly<P: PartitionInfo, C: ClusterInfo> {
 
 struct FnGenerator<P: PartitionInfo, C: ClusterInfo> {
     router: Arc<dyn Router<P = P, C = C>>,
+    /// The scope of the named results registered and scanned by the job
+    result_scope: u64,
 }
 
 impl<P: PartitionInfo, C: ClusterInfo> Clone for FnGenerator<P, C> {
     fn clone(&self) -> Self {
-        Self { router: self.router.clone() }
+        Self { router: self.router.clone(), result_scope: self.result_scope }
     }
 }
 
@@ -78,17 +84,22 @@ impl<P: PartitionInfo, C: ClusterInfo> Clone for FnGenerator<P, C> {
 /// which generates the udf that can be executed by the engine.
 impl<P: PartitionInfo, C: ClusterInfo> FnGenerator<P, C> {
     fn new(router: Arc<dyn Router<P = P, C = C>>) -> Self {
-        FnGenerator { router }
+        FnGenerator { router, result_scope: 0 }
     }
 
     fn with(partition_info: Arc<P>, cluster_info: Arc<C>) -> Self {
         let router = Arc::new(DefaultRouter::new(partition_info, cluster_info));
-        FnGenerator { router }
+        FnGenerator { router, result_scope: 0 }
+    }
+
+    fn with_result_scope(&self, result_scope: u64) -> Self {
+        FnGenerator { router: self.router.clone(), result_scope }
     }
 
     fn gen_source(&self, opr: pb::PhysicalOpr) -> FnGenResult<DynIter<Record>> {
         let worker_id = pegasus::get_current_worker();
-        let source_opr = SourceOperator::new(opr, self.router.clone())?;
+        let mut source_opr = SourceOperator::new(opr, self.router.clone())?;
+        source_opr.set_result_scope(self.result_scope);
         Ok(source_opr.gen_source(worker_id.index as usize)?)
     }
 
@@ -122,6 +133,10 @@ impl<P: PartitionInfo, C: ClusterInfo> FnGenerator<P, C> {
         Ok(Box::new(opr))
     }
 
+    fn gen_count(&self, opr: algebra_pb::Count) -> FnGenResult<Box<dyn MapFunction<u64, Record>>> {
+        gen_count_map(opr)
+    }
+
     fn gen_apply(&self, opr: pb::Apply) -> FnGenResult<RecordLeftJoin> {
         Ok(Box::new(opr))
     }
@@ -159,7 +174,11 @@ impl<P: PartitionInfo, C: ClusterInfo> FnGenerator<P, C> {
     }
 
     fn gen_sink(&self, opr: pb::PhysicalOpr) -> FnGenResult<Sinker> {
-        Ok(opr.gen_sink()?)
+        let mut sinker = opr.gen_sink()?;
+        if let Sinker::ResultSinker(result_sinker) = &mut sinker {
+            result_sinker.set_scope(self.result_scope);
+        }
+        Ok(sinker)
     }
 }
 
@@ -174,6 +193,19 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
         IRJobAssembly { udf_gen }
     }
 
+    /// Install the `plan` on the records that arrive before the `deadline` if any, while the
+    /// others are dropped, which acts as a soft timeout of the (sub-)computation.
+    fn install_until(
+        &self, stream: Stream<Record>, plan: &[pb::PhysicalOpr], deadline: Option<Instant>,
+    ) -> Result<Stream<Record>, BuildJobError> {
+        let stream = if let Some(deadline) = deadline {
+            stream.filter(move |_| Ok(Instant::now() < deadline))?
+        } else {
+            stream
+        };
+        self.install(stream, plan)
+    }
+
     fn install(
         &self, mut stream: Stream<Record>, plan: &[pb::PhysicalOpr],
     ) -> Result<Stream<Record>, BuildJobError> {
@@ -194,6 +226,7 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                             stream = stream.repartition(move |t| router.route(t));
                         }
                         pb::repartition::Strategy::ToOthers(_) => stream = stream.broadcast(),
+                        pb::repartition::Strategy::ToOne(_) => stream = stream.aggregate(),
                     }
                 }
                 OpKind::Project(project) => {
@@ -221,7 +254,11 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                             range
                         ))))?;
                     }
-                    stream = stream.limit(range.upper as u32)?;
+                    stream = if limit.per_partition {
+                        stream.limit_partition(range.upper as u32)?
+                    } else {
+                        stream.limit(range.upper as u32)?
+                    };
                 }
                 OpKind::OrderBy(order) => {
                     let cmp = self.udf_gen.gen_cmp(order.clone())?;
@@ -232,7 +269,11 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                                 range
                             ))))?;
                         }
-                        stream = stream.sort_limit_by(range.upper as u32, move |a, b| cmp.compare(a, b))?;
+                        stream = if order.per_partition {
+                            stream.sort_limit_by_partition(range.upper as u32, move |a, b| cmp.compare(a, b))?
+                        } else {
+                            stream.sort_limit_by(range.upper as u32, move |a, b| cmp.compare(a, b))?
+                        };
                     } else {
                         stream = stream.sort_by(move |a, b| cmp.compare(a, b))?;
                     }
@@ -265,8 +306,11 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                         let group_key = group.gen_group_key()?;
                         let group_accum = group.gen_group_accum()?;
                         let group_map = group.gen_group_map()?;
-                        stream = stream
-                            .key_by(move |record| group_key.get_kv(record))?
+                        let mut keyed_stream = stream.key_by(move |record| group_key.get_kv(record))?;
+                        if group.is_null_key_dropped() {
+                            keyed_stream = keyed_stream.filter(|pair| Ok(!pair.key.has_none()))?;
+                        }
+                        stream = keyed_stream
                             .fold_by_key(group_accum, || {
                                 |mut accumulator, next| {
                                     accumulator.accum(next)?;
@@ -285,6 +329,13 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                             .map(move |key_value| group_map.exec(key_value))?;
                     }
                 }
+                OpKind::Count(count) => {
+                    let count_map = self.udf_gen.gen_count(count)?;
+                    stream = stream
+                        .count()?
+                        .map(move |cnt| count_map.exec(cnt))?
+                        .into_stream()?;
+                }
                 OpKind::Dedup(dedup) => {
                     let selector = self.udf_gen.gen_dedup(dedup)?;
                     stream = stream
@@ -313,11 +364,12 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                             .sub_plan
                             .as_ref()
                             .ok_or(BuildJobError::Unsupported("Task is missing in Apply".to_string()))?;
+                        let deadline = timeout_to_deadline(apply.timeout_ms);
                         stream = match join_kind {
                             JoinKind::Semi => stream
                                 .apply(|sub_start| {
                                     let has_sub = self
-                                        .install(sub_start, &sub_task.plan[..])?
+                                        .install_until(sub_start, &sub_task.plan[..], deadline)?
                                         .any()?;
                                     Ok(has_sub)
                                 })?
@@ -333,7 +385,7 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                             JoinKind::Anti => stream
                                 .apply(|sub_start| {
                                     let has_sub = self
-                                        .install(sub_start, &sub_task.plan[..])?
+                                        .install_until(sub_start, &sub_task.plan[..], deadline)?
                                         .any()?;
                                     Ok(has_sub)
                                 })?
@@ -349,7 +401,7 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                             JoinKind::Inner | JoinKind::LeftOuter => stream
                                 .apply(|sub_start| {
                                     let sub_end = self
-                                        .install(sub_start, &sub_task.plan[..])?
+                                        .install_until(sub_start, &sub_task.plan[..], deadline)?
                                         .collect::<Vec<Record>>()?;
                                     Ok(sub_end)
                                 })?
@@ -369,6 +421,8 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                     let left_key_selector = joiner.gen_left_kv_fn()?;
                     let right_key_selector = joiner.gen_right_kv_fn()?;
                     let join_kind = joiner.get_join_kind();
+                    let left_renames = gen_join_renames(&join, Side::Left)?;
+                    let right_renames = gen_join_renames(&join, Side::Right)?;
                     let left_task = join
                         .left_plan
                         .as_ref()
@@ -378,13 +432,29 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                         .as_ref()
                         .ok_or("right_task is missing in merge")?;
                     let (left_stream, right_stream) = stream.copied()?;
-                    let left_stream = self
-                        .install(left_stream, &left_task.plan[..])?
+                    let mut left_stream = self.install(left_stream, &left_task.plan[..])?;
+                    if !left_renames.is_empty() {
+                        left_stream = left_stream.map(move |mut record| {
+                            for (from, to) in &left_renames {
+                                record.rename(*from, *to);
+                            }
+                            Ok(record)
+                        })?;
+                    }
+                    let left_stream = left_stream
                         .key_by(move |record| left_key_selector.get_kv(record))?
                         // TODO(bingqing): remove this when new keyed-join in gaia-x is ready;
                         .partition_by_key();
-                    let right_stream = self
-                        .install(right_stream, &right_task.plan[..])?
+                    let mut right_stream = self.install(right_stream, &right_task.plan[..])?;
+                    if !right_renames.is_empty() {
+                        right_stream = right_stream.map(move |mut record| {
+                            for (from, to) in &right_renames {
+                                record.rename(*from, *to);
+                            }
+                            Ok(record)
+                        })?;
+                    }
+                    let right_stream = right_stream
                         .key_by(move |record| right_key_selector.get_kv(record))?
                         // TODO(bingqing): remove this when new keyed-join in gaia-x is ready;
                         .partition_by_key();
@@ -564,6 +634,8 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                                 tag: None,
                                 params: None,
                                 alias,
+                                is_optional: false,
+                                end_alias: None,
                             };
                             base_expand_plan.push(edge_expand_e.into());
                             base_expand_plan.push(get_v.into());
@@ -593,8 +665,9 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                         base_expand_plan.push(getv.clone().into());
                     }
 
+                    let deadline = timeout_to_deadline(path.timeout_ms);
                     for _ in 0..range.lower {
-                        stream = self.install(stream, &base_expand_plan)?;
+                        stream = self.install_until(stream, &base_expand_plan, deadline)?;
                     }
                     let times = range.upper - range.lower - 1;
                     if times > 0 {
@@ -605,11 +678,12 @@ impl<P: PartitionInfo, C: ClusterInfo> IRJobAssembly<P, C> {
                                 .gen_filter(algebra_pb::Select { predicate: Some(condition.clone()) })?;
                             until.set_until(func);
                             // Notice that if UNTIL condition set, we expand path without `Emit`
-                            stream = stream
-                                .iterate_until(until, |start| self.install(start, &base_expand_plan[..]))?;
+                            stream = stream.iterate_until(until, |start| {
+                                self.install_until(start, &base_expand_plan[..], deadline)
+                            })?;
                         } else {
                             stream = stream.iterate_emit_until(until, EmitKind::Before, |start| {
-                                self.install(start, &base_expand_plan[..])
+                                self.install_until(start, &base_expand_plan[..], deadline)
                             })?;
                         }
                     }
@@ -646,26 +720,83 @@ impl<P: PartitionInfo, C: ClusterInfo> JobAssembly<Record> for IRJobAssembly<P,
     fn assemble(&self, plan: &JobDesc, worker: &mut Worker<Record, Vec<u8>>) -> Result<(), BuildJobError> {
         worker.dataflow(move |input, output| {
             let physical_plan = decode::<pb::PhysicalPlan>(&plan.plan)?;
+            // the job is assembled in the scope of its named results
+            let assembly = IRJobAssembly { udf_gen: self.udf_gen.with_result_scope(physical_plan.result_scope) };
+            if let Some(mode) = physical_plan.continuous.as_ref() {
+                // a continuous query is registered to the incremental runtime, rather than run once
+                Err(FnGenError::unsupported_error(&format!(
+                    "continuous query {:?} submitted as a one-off job",
+                    mode.name
+                )))?
+            }
             let source_opr = physical_plan
                 .plan
                 .first()
                 .ok_or(FnGenError::from(ParsePbError::EmptyFieldError("empty job plan".to_string())))?;
-            let source_iter = self.udf_gen.gen_source(source_opr.clone())?;
+            let source_iter = assembly.udf_gen.gen_source(source_opr.clone())?;
             let source = input.input_from(source_iter)?;
-            if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
-                debug!("{:#?}", physical_plan);
+            let worker = pegasus::get_current_worker();
+            if worker.index == 0 {
+                // to correlate the (slow) job back to the query of the user
+                if let Some(origin) = physical_plan.origin.as_ref() {
+                    info!(
+                        "job {} originates from the query {:?} of the frontend {:?} (compiler version {:?})",
+                        worker.job_id, origin.query, origin.frontend, origin.compiler_version
+                    );
+                }
+                if log_enabled!(log::Level::Debug) {
+                    debug!("{:#?}", physical_plan);
+                }
             }
             let plan_len = physical_plan.plan.len();
-            let stream = self.install(source, &physical_plan.plan[1..plan_len - 1])?;
+            let stream = assembly.install(source, &physical_plan.plan[1..plan_len - 1])?;
             let sink_opr = physical_plan
                 .plan
                 .last()
                 .ok_or(FnGenError::from(ParsePbError::EmptyFieldError("empty job plan".to_string())))?;
-            let ec = self.udf_gen.gen_sink(sink_opr.clone())?;
+            let ec = assembly.udf_gen.gen_sink(sink_opr.clone())?;
+            // the registered results have been read while generating the source, and are released
+            // if this is the last query to read them
+            for name in physical_plan.released_results.iter() {
+                release_partition(physical_plan.result_scope, name, worker.index, worker.total_peers())
+                    .map_err(|e| FnGenError::unsupported_error(&e.to_string()))?;
+            }
             match ec {
                 Sinker::DefaultSinker(default_sinker) => stream
                     .map(move |record| default_sinker.exec(record))?
                     .sink_into(output),
+                Sinker::ColumnarSinker(mut columnar_sinker) => stream
+                    .unary("columnar_sink", |_info| {
+                        move |input, output| {
+                            input.for_each_batch(|batch| {
+                                let mut session = output.new_session(&batch.tag)?;
+                                for record in batch.drain() {
+                                    if let Some(column_batch) = columnar_sinker.encode(record) {
+                                        session.give(column_batch)?;
+                                    }
+                                }
+                                if let Some(end) = batch.take_end() {
+                                    match columnar_sinker.flush() {
+                                        Some(column_batch) => session.give_last(column_batch, end)?,
+                                        None => session.notify_end(end)?,
+                                    }
+                                }
+                                Ok(())
+                            })
+                        }
+                    })?
+                    .sink_into(output),
+                Sinker::ResultSinker(result_sinker) => stream
+                    .fold_partition(result_sinker, || {
+                        |mut accumulator, next| {
+                            accumulator.accum(next)?;
+                            Ok(accumulator)
+                        }
+                    })?
+                    .map(|mut accumulator| Ok(accumulator.finalize()?))?
+                    .into_stream()?
+                    .map(|_r| Ok(vec![]))?
+                    .sink_into(output),
                 #[cfg(feature = "with_v6d")]
                 Sinker::GraphSinker(graph_sinker) => {
                     return stream
@@ -689,3 +820,14 @@ impl<P: PartitionInfo, C: ClusterInfo> JobAssembly<Record> for IRJobAssembly<P,
 fn decode<T: Message + Default>(binary: &[u8]) -> FnGenResult<T> {
     Ok(T::decode(binary)?)
 }
+
+/// The deadline of a soft timeout (in milliseconds) that starts from now, or none if the
+/// timeout is 0.
+#[inline]
+fn timeout_to_deadline(timeout_ms: u64) -> Option<Instant> {
+    if timeout_ms > 0 {
+        Some(Instant::now() + Duration::from_millis(timeout_ms))
+    } else {
+        None
+    }
+}
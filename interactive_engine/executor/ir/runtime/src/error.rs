@@ -13,6 +13,7 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+use graph_proxy::apis::ID;
 use graph_proxy::utils::expr::ExprEvalError;
 use graph_proxy::GraphProxyError;
 use ir_common::error::ParsePbError;
@@ -121,6 +122,8 @@ pub enum FnExecError {
     AccumError(String),
     /// Not supported error
     UnSupported(String),
+    /// Expanding from a super node, i.e., the vertex of the id, while the super node policy is to fail
+    SuperNodeError(ID, usize),
     /// Unreachable error
     Unreachable,
 }
@@ -153,6 +156,9 @@ impl std::fmt::Display for FnExecError {
             FnExecError::UnExpectedData(e) => write!(f, "Unexpected data type in exec {}", e),
             FnExecError::AccumError(e) => write!(f, "Accum error in exec {}", e),
             FnExecError::UnSupported(e) => write!(f, "Op not supported error in exec {}", e),
+            FnExecError::SuperNodeError(id, threshold) => {
+                write!(f, "Expand from super node {} of more than {} adjacent edges in exec", id, threshold)
+            }
             FnExecError::Unreachable => write!(f, "Unreachable error in exec"),
         }
     }
@@ -24,6 +24,8 @@ pub mod router;
 #[macro_use]
 extern crate dyn_type;
 #[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate log;
 
 use std::sync::Arc;
@@ -44,6 +44,10 @@ pub trait GroupGen<D, K, V>: Send + 'static {
     fn gen_group_accum(&self) -> FnGenResult<RecordAccumulator>;
 
     fn gen_group_map(&self) -> FnGenResult<Box<dyn MapFunction<(K, V), D>>>;
+
+    /// Whether to drop the records of which any grouping key is missing (null), instead of
+    /// grouping them under the null key
+    fn is_null_key_dropped(&self) -> bool;
 }
 
 pub trait FoldGen<I, O>: Send + 'static {
@@ -107,6 +111,10 @@ mod box_impl {
         fn gen_group_map(&self) -> FnGenResult<Box<dyn MapFunction<(K, V), D>>> {
             (**self).gen_group_map()
         }
+
+        fn is_null_key_dropped(&self) -> bool {
+            (**self).is_null_key_dropped()
+        }
     }
 
     impl<I, O, F: FoldGen<I, O> + ?Sized> FoldGen<I, O> for Box<F> {
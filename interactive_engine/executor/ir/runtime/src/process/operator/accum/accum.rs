@@ -15,19 +15,23 @@
 
 use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
 use std::ops::Div;
 
 use dyn_type::{Object, Primitives};
+use graph_proxy::apis::Element;
 use ir_common::error::ParsePbError;
+use ir_common::generated::algebra::group_by::agg_func::{Equality, Overflow};
 use ir_common::generated::physical as pb;
 use ir_common::generated::physical::group_by::agg_func::Aggregate;
 use ir_common::KeyId;
 use pegasus::codec::{Decode, Encode, ReadExt, WriteExt};
 
 use crate::error::{FnExecError, FnExecResult, FnGenError, FnGenResult};
-use crate::process::entry::{CollectionEntry, DynEntry, Entry};
+use crate::process::entry::{CollectionEntry, DynEntry, Entry, EntryType};
 use crate::process::operator::accum::accumulator::{
-    Accumulator, Count, DistinctCount, Maximum, Minimum, Sum, ToList, ToSet,
+    Accumulator, ApproxDistinctCount, Count, DistinctCount, Maximum, Minimum, Sum, ToList, ToSet,
+    MAX_HLL_PRECISION, MIN_HLL_PRECISION,
 };
 use crate::process::operator::accum::AccumFactoryGen;
 use crate::process::operator::TagKey;
@@ -41,8 +45,56 @@ pub enum EntryAccumulator {
     ToMax(Maximum<DynEntry>),
     ToSet(ToSet<DynEntry>),
     ToDistinctCount(DistinctCount<DynEntry>),
-    ToSum(Sum<Primitives>),
-    ToAvg(Sum<Primitives>, Count<()>),
+    ToSum(Sum<Primitives>, Overflow),
+    ToAvg(Sum<Primitives>, Count<()>, Overflow),
+    ToSetByValue(ToSet<ValueEntry>),
+    ToDistinctCountByValue(DistinctCount<ValueEntry>),
+    ToApproxDistinctCount(ApproxDistinctCount<DynEntry>),
+}
+
+/// An entry that is compared by its full value, namely, two graph elements are equal only if
+/// they have the same id, label and properties, while other entries are compared as they are.
+#[derive(Debug, Clone)]
+pub struct ValueEntry(DynEntry);
+
+impl ValueEntry {
+    fn is_graph_element(&self) -> bool {
+        matches!(self.0.get_type(), EntryType::Vertex | EntryType::Edge)
+    }
+}
+
+impl Hash for ValueEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // the entries of equal values must have the same id, thus hashing as the entry suffices
+        self.0.hash(state)
+    }
+}
+
+impl PartialEq for ValueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        if self.0 != other.0 {
+            false
+        } else if self.is_graph_element() && other.is_graph_element() {
+            let (this, that) = (self.0.as_graph_element().unwrap(), other.0.as_graph_element().unwrap());
+            this.label() == that.label() && this.get_all_properties() == that.get_all_properties()
+        } else {
+            true
+        }
+    }
+}
+
+impl Eq for ValueEntry {}
+
+impl Encode for ValueEntry {
+    fn write_to<W: WriteExt>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.0.write_to(writer)
+    }
+}
+
+impl Decode for ValueEntry {
+    fn read_from<R: ReadExt>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(ValueEntry(<DynEntry>::read_from(reader)?))
+    }
 }
 
 /// Accumulator for Record, including multiple accumulators for entries(columns) in Record.
@@ -83,7 +135,12 @@ impl Accumulator<DynEntry, DynEntry> for EntryAccumulator {
                 EntryAccumulator::ToMax(max) => max.accum(next),
                 EntryAccumulator::ToSet(set) => set.accum(next),
                 EntryAccumulator::ToDistinctCount(distinct_count) => distinct_count.accum(next),
-                EntryAccumulator::ToSum(sum) => {
+                EntryAccumulator::ToSetByValue(set) => set.accum(ValueEntry(next)),
+                EntryAccumulator::ToDistinctCountByValue(distinct_count) => {
+                    distinct_count.accum(ValueEntry(next))
+                }
+                EntryAccumulator::ToApproxDistinctCount(distinct_count) => distinct_count.accum(next),
+                EntryAccumulator::ToSum(sum, overflow) => {
                     let primitive = next
                         .as_object()
                         .ok_or(FnExecError::unexpected_data_error("DynEntry is not a object type `Sum`"))?
@@ -94,9 +151,9 @@ impl Accumulator<DynEntry, DynEntry> for EntryAccumulator {
                                 e
                             ))
                         })?;
-                    sum.accum(primitive)
+                    accum_sum(sum, primitive, *overflow)
                 }
-                EntryAccumulator::ToAvg(sum, count) => {
+                EntryAccumulator::ToAvg(sum, count, overflow) => {
                     let primitive = next
                         .as_object()
                         .ok_or(FnExecError::unexpected_data_error("DynEntry is not a object type `ToAvg`"))?
@@ -107,7 +164,7 @@ impl Accumulator<DynEntry, DynEntry> for EntryAccumulator {
                                 e
                             ))
                         })?;
-                    sum.accum(primitive)?;
+                    accum_sum(sum, primitive, *overflow)?;
                     count.accum(())
                 }
             }
@@ -140,13 +197,29 @@ impl Accumulator<DynEntry, DynEntry> for EntryAccumulator {
                 let cnt = distinct_count.finalize()?;
                 Ok(DynEntry::new(object!(cnt)))
             }
-            EntryAccumulator::ToSum(sum) => {
+            EntryAccumulator::ToSetByValue(set) => {
+                let inner = set
+                    .finalize()?
+                    .into_iter()
+                    .map(|entry| entry.0)
+                    .collect();
+                Ok(DynEntry::new(CollectionEntry { inner }))
+            }
+            EntryAccumulator::ToDistinctCountByValue(distinct_count) => {
+                let cnt = distinct_count.finalize()?;
+                Ok(DynEntry::new(object!(cnt)))
+            }
+            EntryAccumulator::ToApproxDistinctCount(distinct_count) => {
+                let cnt = distinct_count.finalize()?;
+                Ok(DynEntry::new(object!(cnt)))
+            }
+            EntryAccumulator::ToSum(sum, _) => {
                 let primitive = sum
                     .finalize()?
                     .ok_or(FnExecError::accum_error("sum_entry is none"))?;
                 Ok(DynEntry::new(object!(primitive)))
             }
-            EntryAccumulator::ToAvg(sum, count) => {
+            EntryAccumulator::ToAvg(sum, count, _) => {
                 let sum_primitive = sum
                     .finalize()?
                     .ok_or(FnExecError::accum_error("sum_entry is none"))?;
@@ -165,6 +238,54 @@ impl Accumulator<DynEntry, DynEntry> for EntryAccumulator {
     }
 }
 
+/// Add the next value to the sum, where the overflow of the integers is dealt with as given, while
+/// the other values are added as they are.
+fn accum_sum(sum: &mut Sum<Primitives>, next: Primitives, overflow: Overflow) -> FnExecResult<()> {
+    use dyn_type::Primitives::*;
+
+    let result = match (sum.seed.take(), next) {
+        (None, next) => next,
+        (Some(Integer(a)), Integer(b)) => match overflow {
+            Overflow::Wrap => Integer(a.wrapping_add(b)),
+            Overflow::Saturate => Integer(a.saturating_add(b)),
+            Overflow::Error => Integer(
+                a.checked_add(b)
+                    .ok_or_else(|| overflow_error(a, b))?,
+            ),
+            Overflow::Promote => a
+                .checked_add(b)
+                .map(Integer)
+                .unwrap_or(Float(a as f64 + b as f64)),
+        },
+        (Some(Integer(a)), Long(b)) => add_longs(a as i64, b, overflow)?,
+        (Some(Long(a)), Integer(b)) => add_longs(a, b as i64, overflow)?,
+        (Some(Long(a)), Long(b)) => add_longs(a, b, overflow)?,
+        (Some(seed), next) => seed + next,
+    };
+    sum.seed = Some(result);
+
+    Ok(())
+}
+
+fn add_longs(a: i64, b: i64, overflow: Overflow) -> FnExecResult<Primitives> {
+    match overflow {
+        Overflow::Wrap => Ok(Primitives::Long(a.wrapping_add(b))),
+        Overflow::Saturate => Ok(Primitives::Long(a.saturating_add(b))),
+        Overflow::Error => a
+            .checked_add(b)
+            .map(Primitives::Long)
+            .ok_or_else(|| overflow_error(a, b)),
+        Overflow::Promote => Ok(a
+            .checked_add(b)
+            .map(Primitives::Long)
+            .unwrap_or(Primitives::Float(a as f64 + b as f64))),
+    }
+}
+
+fn overflow_error<T: std::fmt::Display>(a: T, b: T) -> FnExecError {
+    FnExecError::accum_error(&format!("integer overflow while summing {} and {}", a, b))
+}
+
 impl AccumFactoryGen for pb::GroupBy {
     fn gen_accum(self) -> FnGenResult<RecordAccumulator> {
         let mut accum_ops = Vec::with_capacity(self.functions.len());
@@ -189,23 +310,63 @@ impl AccumFactoryGen for pb::GroupBy {
             if multi_accum_flag && agg_func.alias.is_none() {
                 Err(ParsePbError::from("accum value alias is missing in MultiAccum"))?
             }
+            // by default, the graph elements are deduplicated by their ids
+            let is_by_value = agg_func.equality == Equality::ByValue as i32;
+            let overflow = Overflow::from_i32(agg_func.overflow).ok_or_else(|| {
+                ParsePbError::from(format!(
+                    "invalid overflow {:?} of the aggregate function",
+                    agg_func.overflow
+                ))
+            })?;
+            // the distinct count is approximated by a HyperLogLog sketch of the given precision
+            let precision = agg_func
+                .approximation
+                .as_ref()
+                .map(|approx| {
+                    if approx.precision < MIN_HLL_PRECISION as i32
+                        || approx.precision > MAX_HLL_PRECISION as i32
+                    {
+                        Err(ParsePbError::from(format!(
+                            "invalid precision {:?} of the approximate aggregate function",
+                            approx.precision
+                        )))
+                    } else {
+                        Ok(approx.precision as u8)
+                    }
+                })
+                .transpose()?;
             let entry_accumulator = match agg_kind {
                 Aggregate::First => {
                     //not implemented
                     Err(FnGenError::unsupported_error("aggregate `First` is not implemented"))?
-                },
+                }
                 Aggregate::Count => EntryAccumulator::ToCount(Count { value: 0, _ph: Default::default() }),
                 Aggregate::ToList => EntryAccumulator::ToList(ToList { inner: vec![] }),
                 Aggregate::Min => EntryAccumulator::ToMin(Minimum { min: None }),
                 Aggregate::Max => EntryAccumulator::ToMax(Maximum { max: None }),
+                Aggregate::ToSet if is_by_value => {
+                    EntryAccumulator::ToSetByValue(ToSet { inner: HashSet::new() })
+                }
+                // the sketch hashes the graph elements by their ids, which cannot tell their values apart
+                Aggregate::CountDistinct if precision.is_some() && is_by_value => {
+                    Err(FnGenError::unsupported_error("approximating the distinct count by value"))?
+                }
+                Aggregate::CountDistinct if precision.is_some() => {
+                    EntryAccumulator::ToApproxDistinctCount(ApproxDistinctCount::new(precision.unwrap()))
+                }
+                Aggregate::CountDistinct if is_by_value => {
+                    EntryAccumulator::ToDistinctCountByValue(DistinctCount { inner: HashSet::new() })
+                }
                 Aggregate::ToSet => EntryAccumulator::ToSet(ToSet { inner: HashSet::new() }),
                 Aggregate::CountDistinct => {
                     EntryAccumulator::ToDistinctCount(DistinctCount { inner: HashSet::new() })
                 }
-                Aggregate::Sum => EntryAccumulator::ToSum(Sum { seed: None }),
-                Aggregate::Avg => {
-                    EntryAccumulator::ToAvg(Sum { seed: None }, Count { value: 0, _ph: Default::default() })
-                }
+                Aggregate::Sum => EntryAccumulator::ToSum(Sum { seed: None }, overflow),
+                Aggregate::Avg => EntryAccumulator::ToAvg(
+                    Sum { seed: None },
+                    Count { value: 0, _ph: Default::default() },
+                    overflow,
+                ),
             };
             accum_ops.push((entry_accumulator, tag_key, agg_func.alias));
         }
@@ -243,14 +404,28 @@ impl Encode for EntryAccumulator {
                 writer.write_u8(5)?;
                 distinct_count.write_to(writer)?;
             }
-            EntryAccumulator::ToSum(sum) => {
+            EntryAccumulator::ToSum(sum, overflow) => {
                 writer.write_u8(6)?;
                 sum.write_to(writer)?;
+                writer.write_i32(*overflow as i32)?;
             }
-            EntryAccumulator::ToAvg(sum, count) => {
+            EntryAccumulator::ToAvg(sum, count, overflow) => {
                 writer.write_u8(7)?;
                 sum.write_to(writer)?;
                 count.write_to(writer)?;
+                writer.write_i32(*overflow as i32)?;
+            }
+            EntryAccumulator::ToSetByValue(set) => {
+                writer.write_u8(8)?;
+                set.write_to(writer)?;
+            }
+            EntryAccumulator::ToDistinctCountByValue(distinct_count) => {
+                writer.write_u8(9)?;
+                distinct_count.write_to(writer)?;
+            }
+            EntryAccumulator::ToApproxDistinctCount(distinct_count) => {
+                writer.write_u8(10)?;
+                distinct_count.write_to(writer)?;
             }
         }
         Ok(())
@@ -287,18 +462,39 @@ impl Decode for EntryAccumulator {
             }
             6 => {
                 let sum = <Sum<Primitives>>::read_from(reader)?;
-                Ok(EntryAccumulator::ToSum(sum))
+                let overflow = read_overflow(reader)?;
+                Ok(EntryAccumulator::ToSum(sum, overflow))
             }
             7 => {
                 let sum = <Sum<Primitives>>::read_from(reader)?;
                 let count = <Count<()>>::read_from(reader)?;
-                Ok(EntryAccumulator::ToAvg(sum, count))
+                let overflow = read_overflow(reader)?;
+                Ok(EntryAccumulator::ToAvg(sum, count, overflow))
+            }
+            8 => {
+                let set = <ToSet<ValueEntry>>::read_from(reader)?;
+                Ok(EntryAccumulator::ToSetByValue(set))
+            }
+            9 => {
+                let distinct_count = <DistinctCount<ValueEntry>>::read_from(reader)?;
+                Ok(EntryAccumulator::ToDistinctCountByValue(distinct_count))
+            }
+            10 => {
+                let distinct_count = <ApproxDistinctCount<DynEntry>>::read_from(reader)?;
+                Ok(EntryAccumulator::ToApproxDistinctCount(distinct_count))
             }
             _ => Err(std::io::Error::new(std::io::ErrorKind::Other, "unreachable")),
         }
     }
 }
 
+fn read_overflow<R: ReadExt>(reader: &mut R) -> std::io::Result<Overflow> {
+    let overflow = reader.read_i32()?;
+    Overflow::from_i32(overflow).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::Other, format!("invalid overflow {}", overflow))
+    })
+}
+
 impl Encode for RecordAccumulator {
     fn write_to<W: WriteExt>(&self, writer: &mut W) -> std::io::Result<()> {
         writer.write_u32(self.accum_ops.len() as u32)?;
@@ -330,6 +526,8 @@ mod tests {
 
     use std::cmp::Ordering;
 
+    use graph_proxy::apis::{DynDetails, GraphElement, Vertex};
+    use ir_common::generated::algebra as algebra_pb;
     use ir_common::generated::common as common_pb;
     use ir_common::generated::physical as pb;
     use pegasus::api::{Fold, Sink};
@@ -375,8 +573,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // to_list
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(init_source(), fold_opr_pb);
         let mut fold_result = CollectionEntry::default().into();
         let expected_result =
@@ -396,8 +597,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // to_list
             alias: None,
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(init_source(), fold_opr_pb);
         let mut fold_result = CollectionEntry::default().into();
         let expected_result =
@@ -417,8 +621,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 3, // count
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(init_source(), fold_opr_pb);
         let mut cnt = 0;
         if let Some(Ok(record)) = result.next() {
@@ -436,13 +643,20 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // to_list
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let function_2 = pb::group_by::AggFunc {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 3, // Count
             alias: Some(TAG_B.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function_1, function_2] };
+        let fold_opr_pb =
+            pb::GroupBy { mappings: vec![], functions: vec![function_1, function_2], null_key_opt: 0 };
         let mut result = fold_test(init_source(), fold_opr_pb);
         let mut fold_result: (DynEntry, DynEntry) = (CollectionEntry::default().into(), object!(0).into());
         let expected_result: (DynEntry, DynEntry) = (
@@ -466,8 +680,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 1, // min
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(vec![r1, r2], fold_opr_pb);
         let mut res = 0.into();
         if let Some(Ok(record)) = result.next() {
@@ -487,8 +704,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 2, // max
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(vec![r1, r2], fold_opr_pb);
         let mut res = "".into();
         if let Some(Ok(record)) = result.next() {
@@ -513,8 +733,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 4, // distinct_count
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(vec![r1, r2, r3, r4], fold_opr_pb);
         let mut cnt = 0;
         if let Some(Ok(record)) = result.next() {
@@ -525,6 +748,45 @@ mod tests {
         assert_eq!(cnt, 2);
     }
 
+    // g.V().distinct_count().as("a"), where the vertex 1 presents with different properties
+    #[test]
+    fn distinct_count_equality_test() {
+        let v1 = init_vertex1();
+        let mut properties = v1.get_all_properties().unwrap();
+        properties.insert("age".into(), object!(30));
+        let v1_updated = Vertex::new(v1.id(), v1.label(), DynDetails::new(properties));
+        let source = vec![
+            Record::new(v1.clone(), None),
+            Record::new(v1_updated, None),
+            Record::new(v1, None),
+            Record::new(init_vertex2(), None),
+        ];
+
+        let distinct_count = |equality: i32| {
+            let function = pb::group_by::AggFunc {
+                vars: vec![common_pb::Variable::from("@".to_string())],
+                aggregate: 4, // distinct_count
+                alias: Some(TAG_A),
+                equality,
+                overflow: 0,
+                approximation: None,
+            };
+            let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
+            let mut result = fold_test(source.clone(), fold_opr_pb);
+            let mut cnt = 0;
+            if let Some(Ok(record)) = result.next() {
+                if let Some(entry) = record.get(Some(TAG_A)) {
+                    cnt = entry.as_object().unwrap().as_u64().unwrap();
+                }
+            }
+            cnt
+        };
+        // by id
+        assert_eq!(distinct_count(1), 2);
+        // by value
+        assert_eq!(distinct_count(2), 3);
+    }
+
     // g.V().fold().as("a") // fold by set
     #[test]
     fn fold_to_set_test() {
@@ -536,8 +798,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 6, // to_set
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(source, fold_opr_pb);
         let mut fold_result = CollectionEntry::default();
         let expected_result = CollectionEntry { inner: vec![init_vertex1().into(), init_vertex2().into()] };
@@ -568,8 +833,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 0, // sum
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(vec![r1, r2, r3], fold_opr_pb);
         let mut res = "".into();
         if let Some(Ok(record)) = result.next() {
@@ -590,8 +858,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 7, // avg
             alias: None,
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = fold_test(vec![r1, r2, r3], fold_opr_pb);
         let mut res = "".into();
         if let Some(Ok(record)) = result.next() {
@@ -601,4 +872,90 @@ mod tests {
         }
         assert_eq!(res, object!(20));
     }
+
+    // g.V().values('age').sum(), where the sum overflows
+    #[test]
+    fn sum_overflow_test() {
+        let sum = |overflow: i32| {
+            let function = pb::group_by::AggFunc {
+                vars: vec![common_pb::Variable::from("@".to_string())],
+                aggregate: 0, // sum
+                alias: None,
+                equality: 0,
+                overflow,
+                approximation: None,
+            };
+            let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
+            let source = vec![Record::new(object!(i32::MAX), None), Record::new(object!(1), None)];
+            fold_test(source, fold_opr_pb)
+                .next()
+                .unwrap()
+                .map(|record| {
+                    record
+                        .get(None)
+                        .unwrap()
+                        .as_object()
+                        .unwrap()
+                        .clone()
+                })
+        };
+        // wrap
+        assert_eq!(sum(0).unwrap(), object!(i32::MIN));
+        // saturate
+        assert_eq!(sum(1).unwrap(), object!(i32::MAX));
+        // error
+        assert!(sum(2).is_err());
+        // promote
+        assert_eq!(sum(3).unwrap(), object!(i32::MAX as f64 + 1.0));
+    }
+
+    // g.V().values('id').dedup().count(), approximated
+    #[test]
+    fn approx_count_distinct_test() {
+        let count_distinct = |values: Vec<i64>| {
+            let function = pb::group_by::AggFunc {
+                vars: vec![common_pb::Variable::from("@".to_string())],
+                aggregate: 4, // count_distinct
+                alias: None,
+                equality: 0,
+                overflow: 0,
+                approximation: Some(algebra_pb::group_by::agg_func::Approximation { precision: 12 }),
+            };
+            let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
+            let source = values
+                .into_iter()
+                .map(|value| Record::new(object!(value), None))
+                .collect();
+            let record = fold_test(source, fold_opr_pb)
+                .next()
+                .unwrap()
+                .unwrap();
+            record
+                .get(None)
+                .unwrap()
+                .as_object()
+                .unwrap()
+                .as_u64()
+                .unwrap()
+        };
+        // the small cardinalities are almost exact
+        assert_eq!(count_distinct(vec![1, 2, 3, 1, 2, 3]), 3);
+        // while a standard error of about 1.6% of the precision 12
+        let estimate = count_distinct((0..100000).chain(0..100000).collect());
+        assert!(estimate > 95000 && estimate < 105000);
+    }
+
+    #[test]
+    fn approx_count_distinct_by_value_test() {
+        let function = pb::group_by::AggFunc {
+            vars: vec![common_pb::Variable::from("@".to_string())],
+            aggregate: 4, // count_distinct
+            alias: None,
+            equality: 2, // by value
+            overflow: 0,
+            approximation: Some(algebra_pb::group_by::agg_func::Approximation { precision: 12 }),
+        };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
+        assert!(fold_opr_pb.gen_accum().is_err());
+    }
 }
@@ -13,9 +13,10 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
 use std::fmt::Debug;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 use std::io;
 use std::ops::Add;
 
@@ -336,3 +337,100 @@ impl<D: Decode + Eq + Hash> Decode for DistinctCount<D> {
         Ok(DistinctCount { inner })
     }
 }
+
+/// The range of the precision of `ApproxDistinctCount`, namely, of 16 to 262144 registers.
+pub const MIN_HLL_PRECISION: u8 = 4;
+pub const MAX_HLL_PRECISION: u8 = 18;
+
+/// An approximate distinct count by a HyperLogLog sketch of `2^precision` registers, which takes
+/// `2^precision` bytes regardless of how many distinct values there are, at the cost of a standard
+/// error of about `1.04 / sqrt(2^precision)`, e.g., 0.81% for the precision 14.
+#[derive(Clone)]
+pub struct ApproxDistinctCount<D> {
+    precision: u8,
+    registers: Vec<u8>,
+    _ph: std::marker::PhantomData<D>,
+}
+
+impl<D> ApproxDistinctCount<D> {
+    pub fn new(precision: u8) -> Self {
+        let precision = precision.clamp(MIN_HLL_PRECISION, MAX_HLL_PRECISION);
+        ApproxDistinctCount { precision, registers: vec![0; 1 << precision], _ph: std::marker::PhantomData }
+    }
+
+    fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|rank| 2f64.powi(-(*rank as i32)))
+            .sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|rank| **rank == 0)
+            .count();
+        // the linear counting corrects the estimation of small cardinalities, while the large ones
+        // need no correction as the hash is of 64 bits
+        let estimate = if raw <= 2.5 * m && zeros > 0 { m * (m / zeros as f64).ln() } else { raw };
+        estimate.round() as u64
+    }
+}
+
+impl<D> Debug for ApproxDistinctCount<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "approx_distinct_count={}", self.estimate())
+    }
+}
+
+impl<D: Hash + Send + 'static> Accumulator<D, u64> for ApproxDistinctCount<D> {
+    fn accum(&mut self, next: D) -> FnExecResult<()> {
+        let mut hasher = DefaultHasher::new();
+        next.hash(&mut hasher);
+        let hash = hasher.finish();
+        // the leading `precision` bits index the register, and the rank is the position of the
+        // leftmost 1-bit among the others
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision).leading_zeros() + 1).min(64 - self.precision as u32 + 1) as u8;
+        if self.registers[index] < rank {
+            self.registers[index] = rank;
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> FnExecResult<u64> {
+        Ok(self.estimate())
+    }
+}
+
+impl<D> Encode for ApproxDistinctCount<D> {
+    fn write_to<W: WriteExt>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u8(self.precision)?;
+        self.registers.write_to(writer)?;
+        Ok(())
+    }
+}
+
+impl<D> Decode for ApproxDistinctCount<D> {
+    fn read_from<R: ReadExt>(reader: &mut R) -> io::Result<Self> {
+        let precision = reader.read_u8()?;
+        let registers = <Vec<u8>>::read_from(reader)?;
+        if precision < MIN_HLL_PRECISION
+            || precision > MAX_HLL_PRECISION
+            || registers.len() != 1 << precision
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} registers of the precision {}", registers.len(), precision),
+            ));
+        }
+        Ok(ApproxDistinctCount { precision, registers, _ph: std::marker::PhantomData })
+    }
+}
@@ -13,19 +13,23 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
-use std::convert::TryInto;
+use std::collections::HashSet;
+use std::convert::{TryFrom, TryInto};
+use std::sync::{Arc, Mutex};
 
+use dyn_type::Object;
 use graph_proxy::apis::{
-    get_graph, Direction, DynDetails, GraphElement, QueryParams, Statement, Vertex, ID,
+    get_graph, Direction, DynDetails, Element, GraphElement, QueryParams, Statement, Vertex, ID,
 };
 use ir_common::generated::algebra::edge_expand::ExpandOpt;
 use ir_common::generated::physical as pb;
-use ir_common::KeyId;
+use ir_common::{KeyId, LabelId};
 use pegasus::api::function::{DynIter, FlatMapFunction, FnResult};
 
 use crate::error::{FnExecError, FnGenError, FnGenResult};
 use crate::process::entry::{Entry, EntryType};
 use crate::process::operator::flatmap::FlatMapFuncGen;
+use crate::process::operator::{DegreeFilter, SuperNodePolicy};
 use crate::process::record::{Record, RecordExpandIter, RecordPathExpandIter};
 
 pub struct EdgeExpandOperator<E: Entry> {
@@ -33,6 +37,90 @@ pub struct EdgeExpandOperator<E: Entry> {
     alias: Option<KeyId>,
     stmt: Box<dyn Statement<ID, E>>,
     expand_opt: ExpandOpt,
+    /// Whether to yield a self-loop once in a `BOTH` expansion, where the statement explores edges
+    dedup_both: bool,
+    /// Whether to skip the self-loops, where the statement explores edges
+    exclude_self_loops: bool,
+    /// Whether to collapse the parallel edges, where the statement explores edges
+    collapse_parallel_edges: bool,
+    /// The labels that the adjacent vertices must be of, where the statement explores edges
+    vertex_labels: Vec<LabelId>,
+    /// The filters on the degree of the start vertex
+    degree_filters: Vec<DegreeFilter>,
+    /// The policy applied while expanding from a super node
+    super_node_policy: Option<SuperNodePolicy>,
+    /// Whether to keep the record with the alias bound to null if nothing is expanded from the vertex
+    is_optional: bool,
+    /// The adjacent vertices that have been expanded to, if each vertex is expanded to at most once,
+    /// which is shared with the expanding iterators, as they are consumed after `exec()` returns
+    visited: Option<Arc<Mutex<HashSet<ID>>>>,
+}
+
+impl<E: Entry + 'static> EdgeExpandOperator<E> {
+    /// Explore the adjacent edges (or vertices) of the vertex of `id`, where the incoming copy of a
+    /// self-loop is skipped if `dedup_both`, the self-loops are skipped if `exclude_self_loops`, and
+    /// only the first of the parallel edges is kept if `collapse_parallel_edges`. Besides, the edges
+    /// of which the adjacent vertices are not of `vertex_labels` (if any) are skipped. Nothing is explored
+    /// if the degree of the vertex does not satisfy the `degree_filters`, and the `super_node_policy` (if any)
+    /// applies before all the others.
+    fn explore(&self, id: ID) -> FnResult<DynIter<E>> {
+        if !DegreeFilter::test_all(&self.degree_filters, id)? {
+            return Ok(Box::new(std::iter::empty()));
+        }
+        let mut iter = self.stmt.exec(id)?;
+        if let Some(policy) = self.super_node_policy.as_ref() {
+            iter = policy.apply(id, iter)?;
+        }
+        if !self.dedup_both
+            && !self.exclude_self_loops
+            && !self.collapse_parallel_edges
+            && self.vertex_labels.is_empty()
+        {
+            return Ok(iter);
+        }
+        let dedup_both = self.dedup_both;
+        let exclude_self_loops = self.exclude_self_loops;
+        let vertex_labels = self.vertex_labels.clone();
+        let mut parallel_edges = if self.collapse_parallel_edges { Some(HashSet::new()) } else { None };
+        Ok(Box::new(iter.filter(move |e| {
+            if let Some(e) = e.as_edge() {
+                if (exclude_self_loops && e.src_id == e.dst_id) || (dedup_both && e.is_incoming_self_loop())
+                {
+                    return false;
+                }
+                if !vertex_labels.is_empty()
+                    && !e
+                        .get_other_label()
+                        .map(|label| vertex_labels.contains(label))
+                        .unwrap_or(false)
+                {
+                    return false;
+                }
+                if let Some(parallel_edges) = parallel_edges.as_mut() {
+                    return parallel_edges.insert((e.label(), e.src_id, e.dst_id));
+                }
+            }
+            true
+        })))
+    }
+
+    /// Skip the adjacent vertices that have been expanded to, if the expansion is distinct, where a
+    /// vertex is marked as visited once it is yielded.
+    fn skip_visited<T: Entry + 'static>(&self, iter: DynIter<T>) -> DynIter<T> {
+        match self.visited.clone() {
+            Some(visited) => Box::new(iter.filter(move |e| {
+                e.as_graph_element()
+                    .map(|e| {
+                        visited
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .insert(e.id())
+                    })
+                    .unwrap_or(true)
+            })),
+            None => iter,
+        }
+    }
 }
 
 impl<E: Entry + 'static> FlatMapFunction<Record, Record> for EdgeExpandOperator<E> {
@@ -40,10 +128,23 @@ impl<E: Entry + 'static> FlatMapFunction<Record, Record> for EdgeExpandOperator<
 
     fn exec(&self, mut input: Record) -> FnResult<Self::Target> {
         if let Some(entry) = input.get(self.start_v_tag) {
+            // a null start vertex, e.g., bound by a preceding optional expansion, expands to nothing
+            if entry.is_none() {
+                return if self.is_optional {
+                    input.append(Object::None, self.alias);
+                    Ok(Box::new(vec![input].into_iter()))
+                } else {
+                    Ok(Box::new(vec![].into_iter()))
+                };
+            }
             match entry.get_type() {
                 EntryType::Vertex => {
                     let id = entry.id();
-                    let iter = self.stmt.exec(id)?;
+                    let mut iter = self.explore(id)?.peekable();
+                    if self.is_optional && self.expand_opt != ExpandOpt::Degree && iter.peek().is_none() {
+                        input.append(Object::None, self.alias);
+                        return Ok(Box::new(vec![input].into_iter()));
+                    }
                     match self.expand_opt {
                         // the case of expand edge, and get end vertex;
                         ExpandOpt::Vertex => {
@@ -61,13 +162,15 @@ impl<E: Entry + 'static> FlatMapFunction<Record, Record> for EdgeExpandOperator<
                             Ok(Box::new(RecordExpandIter::new(
                                 input,
                                 self.alias.as_ref(),
-                                Box::new(neighbors_iter),
+                                self.skip_visited(Box::new(neighbors_iter)),
                             )))
                         }
                         // the case of expand neighbors, including edges/vertices
-                        ExpandOpt::Edge => {
-                            Ok(Box::new(RecordExpandIter::new(input, self.alias.as_ref(), iter)))
-                        }
+                        ExpandOpt::Edge => Ok(Box::new(RecordExpandIter::new(
+                            input,
+                            self.alias.as_ref(),
+                            self.skip_visited(Box::new(iter)),
+                        ))),
                         // the case of get degree. TODO: this case should be a `Map`
                         ExpandOpt::Degree => {
                             let degree = iter.count();
@@ -80,7 +183,7 @@ impl<E: Entry + 'static> FlatMapFunction<Record, Record> for EdgeExpandOperator<
                     let graph_path = entry
                         .as_graph_path()
                         .ok_or(FnExecError::Unreachable)?;
-                    let iter = self.stmt.exec(graph_path.get_path_end().id())?;
+                    let iter = self.explore(graph_path.get_path_end().id())?;
                     let curr_path = graph_path.clone();
                     Ok(Box::new(RecordPathExpandIter::new(input, curr_path, iter)))
                 }
@@ -104,19 +207,44 @@ impl FlatMapFuncGen for pb::EdgeExpand {
         let edge_or_end_v_tag = self.alias;
         let direction_pb: pb::edge_expand::Direction = unsafe { ::std::mem::transmute(self.direction) };
         let direction = Direction::from(direction_pb);
+        let degree_filters = DegreeFilter::from_params(self.params.as_ref())?;
         let query_params: QueryParams = self.params.try_into()?;
         let expand_opt: ExpandOpt = unsafe { ::std::mem::transmute(self.expand_opt) };
+        // a super node does no harm while counting its degree
+        let super_node_policy = self
+            .super_node_policy
+            .filter(|_| expand_opt != ExpandOpt::Degree)
+            .map(SuperNodePolicy::try_from)
+            .transpose()?;
+        let dedup_both = self.dedup_both && direction == Direction::Both;
+        let exclude_self_loops = self.exclude_self_loops;
+        let collapse_parallel_edges = self.collapse_parallel_edges;
+        let vertex_labels = self
+            .vertex_tables
+            .into_iter()
+            .map(|label| label.try_into())
+            .collect::<Result<Vec<LabelId>, _>>()?;
+        let is_optional = self.is_optional;
+        // only the adjacent vertices can be distinct
+        let visited =
+            if self.is_distinct && expand_opt == ExpandOpt::Vertex { Some(Arc::default()) } else { None };
         if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
             debug!(
-                "Runtime expand operator of edge with start_v_tag {:?}, end_tag {:?}, direction {:?}, query_params {:?}, expand_opt {:?}",
-                start_v_tag, edge_or_end_v_tag, direction, query_params, expand_opt
+                "Runtime expand operator of edge with start_v_tag {:?}, end_tag {:?}, direction {:?}, query_params {:?}, expand_opt {:?}, dedup_both {:?}, exclude_self_loops {:?}, collapse_parallel_edges {:?}, vertex_labels {:?}, degree_filters {:?}, super_node_policy {:?}, is_optional {:?}, is_distinct {:?}",
+                start_v_tag, edge_or_end_v_tag, direction, query_params, expand_opt, dedup_both, exclude_self_loops, collapse_parallel_edges, vertex_labels, degree_filters, super_node_policy, is_optional, visited.is_some()
             );
         }
 
         match expand_opt {
             ExpandOpt::Vertex => {
-                if query_params.filter.is_some() {
-                    // Expand vertices with filters on edges.
+                if query_params.filter.is_some()
+                    || dedup_both
+                    || exclude_self_loops
+                    || collapse_parallel_edges
+                    || !vertex_labels.is_empty()
+                {
+                    // Expand vertices with filters on edges (or filtering the self-loops, parallel edges or
+                    // labels of the adjacent vertices).
                     // This can be regarded as a combination of EdgeExpand (with is_edge = true) + GetV
                     let stmt = graph.prepare_explore_edge(direction, &query_params)?;
                     let edge_expand_operator = EdgeExpandOperator {
@@ -124,6 +252,14 @@ impl FlatMapFuncGen for pb::EdgeExpand {
                         alias: edge_or_end_v_tag,
                         stmt,
                         expand_opt: ExpandOpt::Vertex,
+                        dedup_both,
+                        exclude_self_loops,
+                        collapse_parallel_edges,
+                        vertex_labels,
+                        degree_filters,
+                        super_node_policy,
+                        is_optional,
+                        visited,
                     };
                     Ok(Box::new(edge_expand_operator))
                 } else {
@@ -134,6 +270,14 @@ impl FlatMapFuncGen for pb::EdgeExpand {
                         alias: edge_or_end_v_tag,
                         stmt,
                         expand_opt: ExpandOpt::Edge,
+                        dedup_both: false,
+                        exclude_self_loops: false,
+                        collapse_parallel_edges: false,
+                        vertex_labels: vec![],
+                        degree_filters,
+                        super_node_policy,
+                        is_optional,
+                        visited,
                     };
                     Ok(Box::new(edge_expand_operator))
                 }
@@ -141,8 +285,20 @@ impl FlatMapFuncGen for pb::EdgeExpand {
             _ => {
                 // Expand edges or degree
                 let stmt = graph.prepare_explore_edge(direction, &query_params)?;
-                let edge_expand_operator =
-                    EdgeExpandOperator { start_v_tag, alias: edge_or_end_v_tag, stmt, expand_opt };
+                let edge_expand_operator = EdgeExpandOperator {
+                    start_v_tag,
+                    alias: edge_or_end_v_tag,
+                    stmt,
+                    expand_opt,
+                    dedup_both,
+                    exclude_self_loops,
+                    collapse_parallel_edges,
+                    vertex_labels,
+                    degree_filters,
+                    super_node_policy,
+                    is_optional,
+                    visited,
+                };
                 Ok(Box::new(edge_expand_operator))
             }
         }
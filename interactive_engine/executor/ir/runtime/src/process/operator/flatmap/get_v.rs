@@ -13,6 +13,7 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+use dyn_type::Object;
 use graph_proxy::apis::{DynDetails, Vertex};
 use ir_common::error::ParsePbError;
 use ir_common::generated::algebra::get_v::VOpt;
@@ -29,14 +30,23 @@ use crate::process::record::{Record, RecordExpandIter};
 struct GetBothVOperator {
     start_tag: Option<KeyId>,
     alias: Option<KeyId>,
+    /// Whether to keep the record with the alias bound to null if the edge is null
+    is_optional: bool,
 }
 
 impl FlatMapFunction<Record, Record> for GetBothVOperator {
     type Target = DynIter<Record>;
 
-    fn exec(&self, input: Record) -> FnResult<Self::Target> {
+    fn exec(&self, mut input: Record) -> FnResult<Self::Target> {
         if let Some(entry) = input.get(self.start_tag) {
-            if let Some(e) = entry.as_edge() {
+            if entry.is_none() {
+                if self.is_optional {
+                    input.append(Object::None, self.alias);
+                    Ok(Box::new(vec![input].into_iter()))
+                } else {
+                    Ok(Box::new(vec![].into_iter()))
+                }
+            } else if let Some(e) = entry.as_edge() {
                 let src_vertex =
                     Vertex::new(e.src_id, e.get_src_label().map(|l| l.clone()), DynDetails::default());
                 let dst_vertex =
@@ -70,7 +80,8 @@ impl FlatMapFuncGen for pb::GetV {
                 opt
             )))?,
         }
-        let get_both_v_operator = GetBothVOperator { start_tag: self.tag, alias: self.alias };
+        let get_both_v_operator =
+            GetBothVOperator { start_tag: self.tag, alias: self.alias, is_optional: self.is_optional };
         if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
             debug!("Runtime get_both_v operator: {:?}", get_both_v_operator);
         }
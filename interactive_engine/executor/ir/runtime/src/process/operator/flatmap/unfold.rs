@@ -156,8 +156,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // ToList
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let unfold_opr_pb = pb::Unfold { tag: Some(TAG_A.into()), alias: None };
         let mut result = fold_unfold_test(fold_opr_pb, unfold_opr_pb);
 
@@ -179,8 +182,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // ToList
             alias: None,
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let unfold_opr_pb = pb::Unfold { tag: None, alias: None };
         let mut result = fold_unfold_test(fold_opr_pb, unfold_opr_pb);
 
@@ -203,8 +209,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // ToList
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let unfold_opr_pb = pb::Unfold { tag: None, alias: None };
 
         let mut result = fold_unfold_test(fold_opr_pb, unfold_opr_pb);
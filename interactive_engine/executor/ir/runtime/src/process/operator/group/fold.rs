@@ -13,6 +13,9 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+use std::convert::TryFrom;
+
+use ir_common::generated::algebra as algebra_pb;
 use ir_common::generated::physical as pb;
 use ir_common::KeyId;
 use pegasus::api::function::{FnResult, MapFunction};
@@ -52,6 +55,13 @@ impl FoldGen<u64, Record> for pb::GroupBy {
     }
 }
 
+/// Bind the count of the records to the alias of the `Count`, which is counted as the fold of a single
+/// `Count` without a `GroupBy`.
+pub fn gen_count_map(count: algebra_pb::Count) -> FnGenResult<Box<dyn MapFunction<u64, Record>>> {
+    let alias = count.alias.map(KeyId::try_from).transpose()?;
+    Ok(Box::new(CountAlias { alias }))
+}
+
 #[derive(Debug)]
 struct CountAlias {
     alias: Option<KeyId>,
@@ -107,8 +117,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 3, // count
             alias: None,
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = count_test(init_source(), fold_opr_pb);
         let mut cnt = 0;
         if let Some(Ok(record)) = result.next() {
@@ -126,8 +139,11 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 3, // count
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
-        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function] };
+        let fold_opr_pb = pb::GroupBy { mappings: vec![], functions: vec![function], null_key_opt: 0 };
         let mut result = count_test(init_source(), fold_opr_pb);
         let mut cnt = 0;
         if let Some(Ok(record)) = result.next() {
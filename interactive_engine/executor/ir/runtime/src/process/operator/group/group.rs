@@ -14,6 +14,7 @@
 //! limitations under the License.
 
 use ir_common::error::ParsePbError;
+use ir_common::generated::algebra as algebra_pb;
 use ir_common::generated::physical as pb;
 use ir_common::KeyId;
 use pegasus::api::function::{FnResult, MapFunction};
@@ -47,6 +48,10 @@ impl GroupGen<Record, RecordKey, Record> for pb::GroupBy {
         }
         Ok(Box::new(group_map))
     }
+
+    fn is_null_key_dropped(&self) -> bool {
+        self.null_key_opt == algebra_pb::group_by::NullKeyOpt::Drop as i32
+    }
 }
 
 #[derive(Debug)]
@@ -84,7 +89,7 @@ mod tests {
     use ir_common::generated::common as common_pb;
     use ir_common::generated::physical as pb;
     use ir_common::NameOrId;
-    use pegasus::api::{FoldByKey, KeyBy, Map, Sink};
+    use pegasus::api::{Filter, FoldByKey, KeyBy, Map, Sink};
     use pegasus::result::ResultStream;
     use pegasus::JobConf;
 
@@ -122,8 +127,11 @@ mod tests {
                 let group_key = group_opr_pb.gen_group_key()?;
                 let group_accum = group_opr_pb.gen_group_accum()?;
                 let group_map = group_opr_pb.gen_group_map()?;
+                let mut stream = stream.key_by(move |record| group_key.get_kv(record))?;
+                if group_opr_pb.is_null_key_dropped() {
+                    stream = stream.filter(|pair| Ok(!pair.key.has_none()))?;
+                }
                 let res_stream = stream
-                    .key_by(move |record| group_key.get_kv(record))?
                     .fold_by_key(group_accum, || {
                         |mut accumulator, next| {
                             accumulator.accum(next).unwrap();
@@ -151,12 +159,16 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // ToList
             alias: Some(TAG_B.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let key_alias = pb::group_by::KeyAlias {
             key: Some(common_pb::Variable::from("@".to_string())),
             alias: Some(TAG_A.into()),
         };
-        let group_opr_pb = pb::GroupBy { mappings: vec![key_alias], functions: vec![function] };
+        let group_opr_pb =
+            pb::GroupBy { mappings: vec![key_alias], functions: vec![function], null_key_opt: 0 };
         let mut result = group_test(group_opr_pb);
         let mut group_result = HashSet::new();
         let expected_result: HashSet<(DynEntry, DynEntry)> = [
@@ -183,12 +195,16 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // ToList
             alias: Some(TAG_B.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let key_alias = pb::group_by::KeyAlias {
             key: Some(common_pb::Variable::from("@.name".to_string())),
             alias: Some(TAG_A.into()),
         };
-        let group_opr_pb = pb::GroupBy { mappings: vec![key_alias], functions: vec![function] };
+        let group_opr_pb =
+            pb::GroupBy { mappings: vec![key_alias], functions: vec![function], null_key_opt: 0 };
         let mut result = group_test(group_opr_pb);
         let mut group_result = vec![];
         let mut expected_result: Vec<(Object, DynEntry)> = vec![
@@ -224,6 +240,9 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // ToList
             alias: Some(TAG_C.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let key_alias_1 = pb::group_by::KeyAlias {
             key: Some(common_pb::Variable::from("@.id".to_string())),
@@ -233,8 +252,11 @@ mod tests {
             key: Some(common_pb::Variable::from("@.name".to_string())),
             alias: Some(TAG_B.into()),
         };
-        let group_opr_pb =
-            pb::GroupBy { mappings: vec![key_alias_1, key_alias_2], functions: vec![function] };
+        let group_opr_pb = pb::GroupBy {
+            mappings: vec![key_alias_1, key_alias_2],
+            functions: vec![function],
+            null_key_opt: 0,
+        };
         let mut result = group_test(group_opr_pb);
         let mut group_result = HashSet::new();
         let expected_result: HashSet<((DynEntry, DynEntry), DynEntry)> = [
@@ -271,18 +293,27 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 5, // ToList
             alias: Some(TAG_A.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let function_2 = pb::group_by::AggFunc {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 3, // Count
             alias: Some(TAG_B.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let key_alias = pb::group_by::KeyAlias {
             key: Some(common_pb::Variable::from("@".to_string())),
             alias: Some(TAG_C.into()),
         };
-        let group_opr_pb =
-            pb::GroupBy { mappings: vec![key_alias], functions: vec![function_1, function_2] };
+        let group_opr_pb = pb::GroupBy {
+            mappings: vec![key_alias],
+            functions: vec![function_1, function_2],
+            null_key_opt: 0,
+        };
         let mut result = group_test(group_opr_pb);
         let mut group_result = HashSet::new();
         let expected_result: HashSet<(DynEntry, (DynEntry, DynEntry))> = [
@@ -312,6 +343,37 @@ mod tests {
         assert_eq!(group_result, expected_result);
     }
 
+    // g.V().group().by("gender").by(count()), where "gender" is missing in all vertices
+    #[test]
+    fn group_by_null_key_test() {
+        let function = pb::group_by::AggFunc {
+            vars: vec![common_pb::Variable::from("@".to_string())],
+            aggregate: 3, // Count
+            alias: Some(TAG_B),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
+        };
+        let key_alias = pb::group_by::KeyAlias {
+            key: Some(common_pb::Variable::from("@.gender".to_string())),
+            alias: Some(TAG_A),
+        };
+        let mut group_opr_pb =
+            pb::GroupBy { mappings: vec![key_alias], functions: vec![function], null_key_opt: 0 };
+        let mut result = group_test(group_opr_pb.clone());
+        let mut group_result = vec![];
+        while let Some(Ok(result)) = result.next() {
+            let key = result.get(Some(TAG_A)).unwrap();
+            let val = result.get(Some(TAG_B)).unwrap();
+            group_result.push((key.clone(), val.clone()));
+        }
+        assert_eq!(group_result, vec![(Object::None.into(), object!(3u64).into())]);
+
+        group_opr_pb.null_key_opt = 1; // Drop
+        let mut result = group_test(group_opr_pb);
+        assert!(result.next().is_none());
+    }
+
     // g.V().groupCount() with key as 'a', value as "b"
     #[test]
     fn group_count_test() {
@@ -319,12 +381,16 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 3, // Count
             alias: Some(TAG_B.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let key_alias = pb::group_by::KeyAlias {
             key: Some(common_pb::Variable::from("@".to_string())),
             alias: Some(TAG_A.into()),
         };
-        let group_opr_pb = pb::GroupBy { mappings: vec![key_alias], functions: vec![function] };
+        let group_opr_pb =
+            pb::GroupBy { mappings: vec![key_alias], functions: vec![function], null_key_opt: 0 };
         let mut result = group_test(group_opr_pb);
         let mut group_result = HashSet::new();
         let expected_result: HashSet<(DynEntry, DynEntry)> = [
@@ -350,12 +416,16 @@ mod tests {
             vars: vec![common_pb::Variable::from("@".to_string())],
             aggregate: 3, // Count
             alias: Some(TAG_B.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let key_alias = pb::group_by::KeyAlias {
             key: Some(common_pb::Variable::from("@.name".to_string())),
             alias: Some(TAG_A.into()),
         };
-        let group_opr_pb = pb::GroupBy { mappings: vec![key_alias], functions: vec![function] };
+        let group_opr_pb =
+            pb::GroupBy { mappings: vec![key_alias], functions: vec![function], null_key_opt: 0 };
         let mut result = group_test(group_opr_pb);
         let mut group_result = HashSet::new();
         let expected_result: HashSet<(DynEntry, DynEntry)> = [
@@ -380,12 +450,16 @@ mod tests {
             vars: vec![common_pb::Variable::from("@.age".to_string())],
             aggregate: 1, // min
             alias: Some(TAG_B.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let key_alias = pb::group_by::KeyAlias {
             key: Some(common_pb::Variable::from("@.name".to_string())),
             alias: Some(TAG_A.into()),
         };
-        let group_opr_pb = pb::GroupBy { mappings: vec![key_alias], functions: vec![function] };
+        let group_opr_pb =
+            pb::GroupBy { mappings: vec![key_alias], functions: vec![function], null_key_opt: 0 };
         let mut result = group_test(group_opr_pb);
         let mut group_result = HashSet::new();
         let expected_result: HashSet<(DynEntry, DynEntry)> =
@@ -409,12 +483,16 @@ mod tests {
             vars: vec![common_pb::Variable::from("@.age".to_string())],
             aggregate: 2, // max
             alias: Some(TAG_B.into()),
+            equality: 0,
+            overflow: 0,
+            approximation: None,
         };
         let key_alias = pb::group_by::KeyAlias {
             key: Some(common_pb::Variable::from("@.name".to_string())),
             alias: Some(TAG_A.into()),
         };
-        let group_opr_pb = pb::GroupBy { mappings: vec![key_alias], functions: vec![function] };
+        let group_opr_pb =
+            pb::GroupBy { mappings: vec![key_alias], functions: vec![function], null_key_opt: 0 };
         let mut result = group_test(group_opr_pb);
         let mut group_result = HashSet::new();
         let expected_result: HashSet<(DynEntry, DynEntry)> =
@@ -16,6 +16,8 @@
 mod fold;
 mod group;
 
+pub use fold::gen_count_map;
+
 use crate::error::FnGenResult;
 use crate::process::functions::{FoldGen, GroupGen};
 use crate::process::record::{Record, RecordKey};
@@ -13,8 +13,12 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
-use ir_common::generated::algebra::join::JoinKind;
+use std::convert::TryInto;
+
+use ir_common::error::ParsePbError;
+use ir_common::generated::algebra::join::{rename::Side, JoinKind};
 use ir_common::generated::physical as pb;
+use ir_common::KeyId;
 
 use crate::error::FnGenResult;
 use crate::process::functions::{JoinKeyGen, KeyFunction};
@@ -47,16 +51,46 @@ impl JoinKeyGen<Record, RecordKey, Record> for pb::Join {
     }
 }
 
+/// Generate the renames of the tags of the given side of the join, as pairs of `(from, to)`,
+/// which are applied to each record of the side before joining.
+pub fn gen_join_renames(join: &pb::Join, side: Side) -> FnGenResult<Vec<(KeyId, KeyId)>> {
+    let mut renames = Vec::new();
+    for rename in join
+        .renames
+        .iter()
+        .filter(|rename| rename.side == side as i32)
+    {
+        let from: KeyId = rename
+            .from
+            .clone()
+            .ok_or(ParsePbError::from("from tag is missing in `Join::Rename`"))?
+            .try_into()?;
+        let to: KeyId = rename
+            .to
+            .clone()
+            .ok_or(ParsePbError::from("to tag is missing in `Join::Rename`"))?
+            .try_into()?;
+        renames.push((from, to));
+    }
+    if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
+        debug!("Runtime join operator renames of {:?} side {:?}", side, renames);
+    }
+
+    Ok(renames)
+}
+
 #[cfg(test)]
 mod tests {
     use graph_proxy::apis::GraphElement;
     use graph_proxy::apis::{DynDetails, Vertex, ID};
-    use ir_common::generated::algebra::join::JoinKind;
+    use ir_common::generated::algebra as algebra_pb;
+    use ir_common::generated::algebra::join::{rename::Side, JoinKind};
     use ir_common::generated::common as common_pb;
     use ir_common::generated::physical as pb;
     use pegasus::api::{Join, KeyBy, Map, PartitionByKey, Sink};
     use pegasus::JobConf;
 
+    use super::gen_join_renames;
     use crate::process::entry::Entry;
     use crate::process::functions::JoinKeyGen;
     use crate::process::record::Record;
@@ -89,6 +123,7 @@ mod tests {
                     join_kind,
                     left_plan: None,
                     right_plan: None,
+                    renames: vec![],
                 };
                 let left_key_selector = join_opr_pb.gen_left_kv_fn()?;
                 let right_key_selector = join_opr_pb.gen_right_kv_fn()?;
@@ -200,4 +235,47 @@ mod tests {
         let expected_ids = vec![2];
         join_test(5, expected_ids);
     }
+
+    #[test]
+    fn join_renames_test() {
+        let join_opr_pb = pb::Join {
+            left_keys: vec![],
+            right_keys: vec![],
+            join_kind: 0,
+            left_plan: None,
+            right_plan: None,
+            renames: vec![algebra_pb::join::Rename { side: 1, from: Some(0.into()), to: Some(1.into()) }],
+        };
+        assert!(gen_join_renames(&join_opr_pb, Side::Left)
+            .unwrap()
+            .is_empty());
+        let right_renames = gen_join_renames(&join_opr_pb, Side::Right).unwrap();
+        assert_eq!(right_renames, vec![(0, 1)]);
+
+        // both sides define the tag 0, which is renamed as 1 on the right side
+        let left = Record::new(Vertex::new(1, None, DynDetails::default()), Some(0));
+        let mut right = Record::new(Vertex::new(2, None, DynDetails::default()), Some(0));
+        for (from, to) in right_renames {
+            right.rename(from, to);
+        }
+        let joined = left.join(right, None);
+        assert_eq!(
+            joined
+                .get(Some(0))
+                .unwrap()
+                .as_vertex()
+                .unwrap()
+                .id(),
+            1
+        );
+        assert_eq!(
+            joined
+                .get(Some(1))
+                .unwrap()
+                .as_vertex()
+                .unwrap()
+                .id(),
+            2
+        );
+    }
 }
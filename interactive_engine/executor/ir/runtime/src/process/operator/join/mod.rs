@@ -15,6 +15,8 @@
 
 mod join;
 
+pub use join::gen_join_renames;
+
 use crate::error::FnGenResult;
 use crate::process::functions::JoinKeyGen;
 use crate::process::record::{Record, RecordKey};
@@ -22,7 +22,7 @@ use graph_proxy::apis::graph::element::GraphElement;
 use graph_proxy::apis::{Direction, Element, QueryParams, Statement, ID};
 use ir_common::error::ParsePbError;
 use ir_common::generated::physical as pb;
-use ir_common::KeyId;
+use ir_common::{KeyId, LabelId};
 use pegasus::api::function::{FilterMapFunction, FnResult};
 use pegasus::codec::{Decode, Encode, ReadExt, WriteExt};
 use pegasus_common::downcast::*;
@@ -40,6 +40,8 @@ struct ExpandOrIntersect<E: Entry> {
     start_v_tag: Option<KeyId>,
     edge_or_end_v_tag: KeyId,
     stmt: Box<dyn Statement<ID, E>>,
+    /// The labels that the neighbors must be of, where the statement explores edges
+    vertex_labels: Vec<LabelId>,
 }
 
 /// An optimized entry implementation for intersection, which denotes a collection of vertices;
@@ -157,15 +159,25 @@ impl<E: Entry + 'static> FilterMapFunction<Record, Record> for ExpandOrIntersect
         match entry.get_type() {
             EntryType::Vertex => {
                 let id = entry.id();
-                let iter = self.stmt.exec(id)?.map(|e| {
-                    if let Some(vertex) = e.as_vertex() {
-                        vertex.id() as ID
-                    } else if let Some(edge) = e.as_edge() {
-                        edge.get_other_id() as ID
-                    } else {
-                        unreachable!()
-                    }
-                });
+                let iter = self
+                    .stmt
+                    .exec(id)?
+                    .filter(|e| {
+                        self.vertex_labels.is_empty()
+                            || e.as_edge()
+                                .and_then(|e| e.get_other_label())
+                                .map(|label| self.vertex_labels.contains(label))
+                                .unwrap_or(false)
+                    })
+                    .map(|e| {
+                        if let Some(vertex) = e.as_vertex() {
+                            vertex.id() as ID
+                        } else if let Some(edge) = e.as_edge() {
+                            edge.get_other_id() as ID
+                        } else {
+                            unreachable!()
+                        }
+                    });
                 if let Some(pre_entry) = input.get_mut(Some(self.edge_or_end_v_tag)) {
                     // the case of expansion and intersection
                     let pre_intersection = pre_entry
@@ -211,26 +223,44 @@ impl FilterMapFuncGen for pb::EdgeExpand {
             .ok_or(ParsePbError::from("`EdgeExpand::alias` cannot be empty for intersection"))?;
         let direction_pb: pb::edge_expand::Direction = unsafe { ::std::mem::transmute(self.direction) };
         let direction = Direction::from(direction_pb);
+        let has_degree_predicates = self
+            .params
+            .as_ref()
+            .map(|params| !params.degree_predicates.is_empty())
+            .unwrap_or(false);
         let query_params: QueryParams = self.params.try_into()?;
+        let vertex_labels = self
+            .vertex_tables
+            .into_iter()
+            .map(|label| label.try_into())
+            .collect::<Result<Vec<LabelId>, _>>()?;
         if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
             debug!(
-                "Runtime expand collection operator of edge with start_v_tag {:?}, end_tag {:?}, direction {:?}, query_params {:?}",
-                start_v_tag, edge_or_end_v_tag, direction, query_params
+                "Runtime expand collection operator of edge with start_v_tag {:?}, end_tag {:?}, direction {:?}, query_params {:?}, vertex_labels {:?}",
+                start_v_tag, edge_or_end_v_tag, direction, query_params, vertex_labels
             );
         }
         if self.expand_opt != pb::edge_expand::ExpandOpt::Vertex as i32 {
             Err(FnGenError::unsupported_error("expand edges in ExpandIntersection"))
+        } else if self.is_optional {
+            Err(FnGenError::unsupported_error("optional expansion in ExpandIntersection"))
+        } else if self.is_distinct {
+            Err(FnGenError::unsupported_error("distinct expansion in ExpandIntersection"))
+        } else if has_degree_predicates {
+            Err(FnGenError::unsupported_error("degree predicates in ExpandIntersection"))
         } else {
-            if query_params.filter.is_some() {
-                // Expand vertices with filters on edges.
+            if query_params.filter.is_some() || !vertex_labels.is_empty() {
+                // Expand vertices with filters on edges (or on the labels of the adjacent vertices).
                 // This can be regarded as a combination of EdgeExpand (with expand_opt as Edge) + GetV
                 let stmt = graph.prepare_explore_edge(direction, &query_params)?;
-                let edge_expand_operator = ExpandOrIntersect { start_v_tag, edge_or_end_v_tag, stmt };
+                let edge_expand_operator =
+                    ExpandOrIntersect { start_v_tag, edge_or_end_v_tag, stmt, vertex_labels };
                 Ok(Box::new(edge_expand_operator))
             } else {
                 // Expand vertices without any filters
                 let stmt = graph.prepare_explore_vertex(direction, &query_params)?;
-                let edge_expand_operator = ExpandOrIntersect { start_v_tag, edge_or_end_v_tag, stmt };
+                let edge_expand_operator =
+                    ExpandOrIntersect { start_v_tag, edge_or_end_v_tag, stmt, vertex_labels: vec![] };
                 Ok(Box::new(edge_expand_operator))
             }
         }
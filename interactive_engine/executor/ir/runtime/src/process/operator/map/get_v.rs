@@ -15,6 +15,7 @@
 
 use std::convert::TryInto;
 
+use dyn_type::Object;
 use graph_proxy::apis::GraphElement;
 use graph_proxy::apis::{get_graph, DynDetails, GraphPath, QueryParams, Vertex};
 use ir_common::error::ParsePbError;
@@ -26,6 +27,7 @@ use pegasus::api::function::{FilterMapFunction, FnResult};
 use crate::error::{FnExecError, FnExecResult, FnGenError, FnGenResult};
 use crate::process::entry::{DynEntry, Entry, EntryType};
 use crate::process::operator::map::FilterMapFuncGen;
+use crate::process::operator::DegreeFilter;
 use crate::process::record::Record;
 
 #[derive(Debug)]
@@ -33,7 +35,29 @@ struct GetVertexOperator {
     start_tag: Option<KeyId>,
     opt: VOpt,
     alias: Option<KeyId>,
+    /// The alias of the end vertex, if getting both the vertices of an edge as `VOpt::Endpoints`
+    end_alias: Option<KeyId>,
     query_labels: Vec<LabelId>,
+    /// Whether to keep the record with the alias bound to null if the vertex is absent or unsatisfied
+    is_optional: bool,
+}
+
+/// The result of a record of which the vertex is absent, or does not satisfy the conditions, which is kept
+/// with the alias (or the head, if no alias) bound to null if `is_optional`, and is dropped otherwise.
+fn bind_null_if_optional(mut input: Record, alias: Option<KeyId>, is_optional: bool) -> Option<Record> {
+    if is_optional {
+        if let Some(alias) = alias {
+            // append without moving head
+            input
+                .get_columns_mut()
+                .insert(alias as usize, DynEntry::new(Object::None));
+        } else {
+            input.append(Object::None, None);
+        }
+        Some(input)
+    } else {
+        None
+    }
 }
 
 impl GetVertexOperator {
@@ -52,12 +76,36 @@ impl GetVertexOperator {
             }
         }
     }
+
+    /// Bind both the aliases to null for `VOpt::Endpoints`, and only the alias otherwise
+    fn bind_null_if_optional(&self, input: Record) -> Option<Record> {
+        let input = bind_null_if_optional(input, self.alias, self.is_optional)?;
+        if self.opt == VOpt::Endpoints {
+            bind_null_if_optional(input, self.end_alias, self.is_optional)
+        } else {
+            Some(input)
+        }
+    }
 }
 
 impl FilterMapFunction<Record, Record> for GetVertexOperator {
     fn exec(&self, mut input: Record) -> FnResult<Option<Record>> {
         if let Some(entry) = input.get(self.start_tag) {
-            if let Some(e) = entry.as_edge() {
+            if entry.is_none() {
+                Ok(self.bind_null_if_optional(input))
+            } else if let Some(e) = entry.as_edge() {
+                if self.opt == VOpt::Endpoints {
+                    let (src_label, dst_label) = (e.get_src_label(), e.get_dst_label());
+                    if self.contains_label(src_label)? && self.contains_label(dst_label)? {
+                        let src = Vertex::new(e.src_id, src_label.cloned(), DynDetails::default());
+                        let dst = Vertex::new(e.dst_id, dst_label.cloned(), DynDetails::default());
+                        input.append(src, self.alias.clone());
+                        input.append(dst, self.end_alias.clone());
+                        return Ok(Some(input));
+                    } else {
+                        return Ok(self.bind_null_if_optional(input));
+                    }
+                }
                 let (id, label) = match self.opt {
                     VOpt::Start => (e.src_id, e.get_src_label()),
                     VOpt::End => (e.dst_id, e.get_dst_label()),
@@ -69,7 +117,7 @@ impl FilterMapFunction<Record, Record> for GetVertexOperator {
                     input.append(vertex, self.alias.clone());
                     Ok(Some(input))
                 } else {
-                    Ok(None)
+                    Ok(bind_null_if_optional(input, self.alias, self.is_optional))
                 }
             } else if let Some(graph_path) = entry.as_graph_path() {
                 // we check VOpt here:
@@ -118,7 +166,7 @@ impl FilterMapFunction<Record, Record> for GetVertexOperator {
                             input.append(path_end_vertex, self.alias.clone());
                             Ok(Some(input))
                         } else {
-                            Ok(None)
+                            Ok(bind_null_if_optional(input, self.alias, self.is_optional))
                         }
                     }
                     _ => Err(FnExecError::unsupported_error(&format!(
@@ -145,11 +193,29 @@ struct AuxiliaOperator {
     tag: Option<KeyId>,
     query_params: QueryParams,
     alias: Option<KeyId>,
+    /// The filters on the degree of the vertex
+    degree_filters: Vec<DegreeFilter>,
+    /// Whether to keep the record with the alias bound to null if the vertex is absent or unsatisfied
+    is_optional: bool,
+}
+
+impl AuxiliaOperator {
+    /// Whether the operator filters the entities, rather than only fetching their properties
+    fn is_filter(&self) -> bool {
+        !self.query_params.labels.is_empty()
+            || self.query_params.filter.is_some()
+            || !self.degree_filters.is_empty()
+    }
 }
 
 impl FilterMapFunction<Record, Record> for AuxiliaOperator {
     fn exec(&self, mut input: Record) -> FnResult<Option<Record>> {
         if let Some(entry) = input.get(self.tag) {
+            // a null entry, e.g., bound by a preceding optional expansion, has no properties to fetch,
+            // and thus is kept unless it is filtered
+            if entry.is_none() {
+                return Ok(bind_null_if_optional(input, self.alias, self.is_optional || !self.is_filter()));
+            }
             // Note that we need to guarantee the requested column if it has any alias,
             // e.g., for g.V().out().as("a").has("name", "marko"), we should compile as:
             // g.V().out().auxilia(as("a"))... where we give alias in auxilia,
@@ -161,7 +227,7 @@ impl FilterMapFunction<Record, Record> for AuxiliaOperator {
                     .labels
                     .contains(&entry.label().unwrap())
                 {
-                    return Ok(None);
+                    return Ok(bind_null_if_optional(input, self.alias, self.is_optional));
                 }
             }
             // 2. further fetch properties, e.g., filter by columns.
@@ -169,6 +235,9 @@ impl FilterMapFunction<Record, Record> for AuxiliaOperator {
                 EntryType::Vertex => {
                     let graph = get_graph().ok_or(FnExecError::NullGraphError)?;
                     let id = entry.id();
+                    if !DegreeFilter::test_all(&self.degree_filters, id)? {
+                        return Ok(bind_null_if_optional(input, self.alias, self.is_optional));
+                    }
                     if let Some(vertex) = graph
                         .get_vertex(&[id], &self.query_params)?
                         .next()
@@ -183,7 +252,7 @@ impl FilterMapFunction<Record, Record> for AuxiliaOperator {
                             input.append(vertex, self.alias.clone());
                         }
                     } else {
-                        return Ok(None);
+                        return Ok(bind_null_if_optional(input, self.alias, self.is_optional));
                     }
                 }
                 EntryType::Edge => {
@@ -191,6 +260,9 @@ impl FilterMapFunction<Record, Record> for AuxiliaOperator {
                     // Currently, when getting properties from an edge,
                     // we assume that it has already been carried in the edge (when the first time queried the edge)
                     // since on most storages, query edges by eid is not supported yet.
+                    if !self.degree_filters.is_empty() {
+                        Err(FnExecError::unsupported_error("filter an edge by the degree in `Auxilia`"))?
+                    }
                     if self.tag.eq(&self.alias) {
                         // do nothing as we assume properties is already carried
                     } else {
@@ -213,6 +285,9 @@ impl FilterMapFunction<Record, Record> for AuxiliaOperator {
                     let path_end = graph_path.get_path_end();
                     let graph = get_graph().ok_or(FnExecError::NullGraphError)?;
                     let id = path_end.id();
+                    if !DegreeFilter::test_all(&self.degree_filters, id)? {
+                        return Ok(None);
+                    }
                     if graph
                         .get_vertex(&[id], &self.query_params)?
                         .next()
@@ -240,7 +315,7 @@ impl FilterMapFuncGen for pb::GetV {
             VOpt::Both => Err(ParsePbError::from(
                 "the `GetV` operator is not a `FilterMap`, which has GetV::VOpt::Both",
             ))?,
-            VOpt::Start | VOpt::End | VOpt::Other => {
+            VOpt::Start | VOpt::End | VOpt::Other | VOpt::Endpoints => {
                 let mut tables_condition: Vec<LabelId> = vec![];
                 if let Some(params) = self.params {
                     if params.is_queryable() {
@@ -258,7 +333,9 @@ impl FilterMapFuncGen for pb::GetV {
                     start_tag: self.tag,
                     opt,
                     alias: self.alias,
+                    end_alias: self.end_alias,
                     query_labels: tables_condition,
+                    is_optional: self.is_optional,
                 };
                 if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
                     debug!("Runtime GetVertexOperator: {:?}", get_vertex_operator);
@@ -266,8 +343,15 @@ impl FilterMapFuncGen for pb::GetV {
                 Ok(Box::new(get_vertex_operator))
             }
             VOpt::Itself => {
+                let degree_filters = DegreeFilter::from_params(self.params.as_ref())?;
                 let query_params: QueryParams = self.params.try_into()?;
-                let auxilia_operator = AuxiliaOperator { tag: self.tag, query_params, alias: self.alias };
+                let auxilia_operator = AuxiliaOperator {
+                    tag: self.tag,
+                    query_params,
+                    alias: self.alias,
+                    degree_filters,
+                    is_optional: self.is_optional,
+                };
                 if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
                     debug!("Runtime AuxiliaOperator: {:?}", auxilia_operator);
                 }
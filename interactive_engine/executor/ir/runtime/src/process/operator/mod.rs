@@ -26,17 +26,24 @@ pub mod sort;
 pub mod source;
 pub mod subtask;
 
-use std::convert::TryFrom;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
 
 use dyn_type::Object;
-use graph_proxy::apis::{Element, PropKey};
+use graph_proxy::apis::{
+    get_graph, Direction, Edge, Element, PropKey, QueryParams, Statement, VertexOrEdge, ID,
+};
 use ir_common::error::ParsePbError;
+use ir_common::generated::algebra as algebra_pb;
 use ir_common::generated::common as common_pb;
+use ir_common::generated::physical as physical_pb;
 use ir_common::{KeyId, NameOrId};
+use pegasus::api::function::DynIter;
 use pegasus::codec::{Decode, Encode, ReadExt, WriteExt};
+use rand::Rng;
 
-use crate::error::{FnExecError, FnExecResult};
-use crate::process::entry::DynEntry;
+use crate::error::{FnExecError, FnExecResult, FnGenError, FnGenResult};
+use crate::process::entry::{CollectionEntry, DynEntry, Entry};
 use crate::process::record::Record;
 
 #[derive(Clone, Debug, Default)]
@@ -64,6 +71,8 @@ impl TagKey {
         if let PropKey::Len = prop_key {
             let obj: Object = (entry.len() as u64).into();
             Ok(DynEntry::new(obj))
+        } else if let PropKey::Vertices | PropKey::Element(_) = prop_key {
+            self.get_path_key(entry, prop_key)
         } else {
             if let Some(element) = entry.as_graph_element() {
                 let prop_obj = match prop_key {
@@ -72,7 +81,7 @@ impl TagKey {
                         .label()
                         .map(|label| label.into())
                         .unwrap_or(Object::None),
-                    PropKey::Len => unreachable!(),
+                    PropKey::Len | PropKey::Vertices | PropKey::Element(_) => unreachable!(),
                     PropKey::All => {
                         if let Some(properties) = element.get_all_properties() {
                             properties
@@ -113,6 +122,37 @@ impl TagKey {
             }
         }
     }
+
+    /// Get the vertices, or the element at a given position, of a path, where the element that is
+    /// out of the range of the path is `Object::None`.
+    fn get_path_key(&self, entry: &DynEntry, prop_key: &PropKey) -> FnExecResult<DynEntry> {
+        let path = entry
+            .as_graph_path()
+            .ok_or(FnExecError::unexpected_data_error(&format!(
+                "Get {:?} failed since the entry {:?} is not a path",
+                prop_key, entry
+            )))?;
+        match prop_key {
+            PropKey::Vertices => {
+                let vertices = path
+                    .get_path_vertices()
+                    .ok_or(FnExecError::unexpected_data_error(&format!(
+                        "Get vertices failed since only the end of the path {:?} is preserved",
+                        path
+                    )))?
+                    .into_iter()
+                    .map(|v| DynEntry::new(v.clone()))
+                    .collect();
+                Ok(DynEntry::new(CollectionEntry { inner: vertices }))
+            }
+            PropKey::Element(index) => match path.get_path_element(*index) {
+                Some(VertexOrEdge::V(v)) => Ok(DynEntry::new(v.clone())),
+                Some(VertexOrEdge::E(e)) => Ok(DynEntry::new(e.clone())),
+                None => Ok(DynEntry::new(Object::None)),
+            },
+            _ => unreachable!(),
+        }
+    }
 }
 
 impl TryFrom<common_pb::Variable> for TagKey {
@@ -172,15 +212,159 @@ impl Decode for TagKey {
     }
 }
 
+/// A filter on the degree of a vertex, i.e., the number of its adjacent edges of the given labels
+/// (of any label if empty) in the given direction, which must fall into `[lower, upper)`.
+pub struct DegreeFilter {
+    direction: Direction,
+    query_params: QueryParams,
+    lower: usize,
+    upper: usize,
+    stmt: Box<dyn Statement<ID, Edge>>,
+}
+
+impl DegreeFilter {
+    pub fn new(degree_pred: algebra_pb::DegreePredicate) -> FnGenResult<Self> {
+        let graph = get_graph().ok_or(FnGenError::NullGraphError)?;
+        let direction_pb = physical_pb::edge_expand::Direction::from_i32(degree_pred.direction)
+            .ok_or(ParsePbError::from("invalid direction of `DegreePredicate`"))?;
+        let direction = Direction::from(direction_pb);
+        let range = degree_pred
+            .range
+            .ok_or(ParsePbError::EmptyFieldError("DegreePredicate::range".to_string()))?;
+        if range.lower < 0 || range.lower >= range.upper {
+            Err(ParsePbError::from(format!("invalid range of `DegreePredicate`: {:?}", range)))?
+        }
+        let query_params: QueryParams = Some(algebra_pb::QueryParams {
+            tables: degree_pred.tables,
+            sample_ratio: 1.0,
+            ..Default::default()
+        })
+        .try_into()?;
+        let stmt = graph.prepare_explore_edge(direction, &query_params)?;
+        Ok(DegreeFilter {
+            direction,
+            query_params,
+            lower: range.lower as usize,
+            upper: range.upper as usize,
+            stmt,
+        })
+    }
+
+    /// Build the filters of all the degree predicates in the query parameters.
+    pub fn from_params(params: Option<&algebra_pb::QueryParams>) -> FnGenResult<Vec<DegreeFilter>> {
+        params
+            .map(|params| params.degree_predicates.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(DegreeFilter::new)
+            .collect()
+    }
+
+    /// Whether the degree of the vertex of `id` falls into the range, where the adjacent edges
+    /// are counted no further than the upper bound, to avoid traversing those of a super node.
+    pub fn test(&self, id: ID) -> FnExecResult<bool> {
+        let degree = self.stmt.exec(id)?.take(self.upper).count();
+        Ok(degree >= self.lower && degree < self.upper)
+    }
+
+    /// Whether the degree of the vertex of `id` satisfies all the `filters`.
+    pub fn test_all(filters: &[DegreeFilter], id: ID) -> FnExecResult<bool> {
+        for filter in filters {
+            if !filter.test(id)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl fmt::Debug for DegreeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DegreeFilter")
+            .field("direction", &self.direction)
+            .field("labels", &self.query_params.labels)
+            .field("lower", &self.lower)
+            .field("upper", &self.upper)
+            .finish()
+    }
+}
+
+/// The policy to handle a super node, i.e., a vertex of more than the threshold adjacent edges,
+/// while expanding from it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SuperNodePolicy {
+    /// Expand the first `threshold` adjacent edges only
+    Truncate(usize),
+    /// Expand `threshold` adjacent edges sampled uniformly at random
+    Sample(usize),
+    /// Fail the query
+    Fail(usize),
+}
+
+impl TryFrom<algebra_pb::SuperNodePolicy> for SuperNodePolicy {
+    type Error = ParsePbError;
+
+    fn try_from(policy: algebra_pb::SuperNodePolicy) -> Result<Self, Self::Error> {
+        if policy.threshold <= 0 {
+            return Err(ParsePbError::from(format!(
+                "invalid threshold of `SuperNodePolicy`: {:?}",
+                policy
+            )));
+        }
+        let threshold = policy.threshold as usize;
+        match algebra_pb::super_node_policy::Action::from_i32(policy.action) {
+            Some(algebra_pb::super_node_policy::Action::Truncate) => {
+                Ok(SuperNodePolicy::Truncate(threshold))
+            }
+            Some(algebra_pb::super_node_policy::Action::Sample) => Ok(SuperNodePolicy::Sample(threshold)),
+            Some(algebra_pb::super_node_policy::Action::Fail) => Ok(SuperNodePolicy::Fail(threshold)),
+            None => Err(ParsePbError::from(format!("invalid action of `SuperNodePolicy`: {:?}", policy))),
+        }
+    }
+}
+
+impl SuperNodePolicy {
+    /// Apply the policy to the adjacent edges (or vertices) of the vertex of `id`.
+    pub fn apply<E: Send + 'static>(&self, id: ID, mut iter: DynIter<E>) -> FnExecResult<DynIter<E>> {
+        match *self {
+            SuperNodePolicy::Truncate(threshold) => Ok(Box::new(iter.take(threshold))),
+            SuperNodePolicy::Sample(threshold) => {
+                // reservoir sampling
+                let mut rng = rand::thread_rng();
+                let mut reservoir = Vec::with_capacity(threshold);
+                for (i, item) in iter.enumerate() {
+                    if i < threshold {
+                        reservoir.push(item);
+                    } else {
+                        let j = rng.gen_range(0..=i);
+                        if j < threshold {
+                            reservoir[j] = item;
+                        }
+                    }
+                }
+                Ok(Box::new(reservoir.into_iter()))
+            }
+            SuperNodePolicy::Fail(threshold) => {
+                let head: Vec<E> = iter.by_ref().take(threshold).collect();
+                if iter.next().is_some() {
+                    Err(FnExecError::SuperNodeError(id, threshold))
+                } else {
+                    Ok(Box::new(head.into_iter()))
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use ahash::HashMap;
     use dyn_type::Object;
-    use graph_proxy::apis::{DynDetails, GraphElement, Vertex};
+    use graph_proxy::apis::{DynDetails, GraphElement, GraphPath, Vertex};
     use ir_common::{KeyId, LabelId};
 
     use super::*;
-    use crate::process::entry::Entry;
+    use crate::process::entry::{Entry, EntryType};
 
     pub const TAG_A: KeyId = 0;
     pub const TAG_B: KeyId = 1;
@@ -319,4 +503,57 @@ pub(crate) mod tests {
 
         assert_eq!(entry, object!(expected));
     }
+
+    #[test]
+    fn test_get_path_key_entry() {
+        let mut path = GraphPath::new(
+            init_vertex1(),
+            algebra_pb::path_expand::PathOpt::Arbitrary,
+            algebra_pb::path_expand::ResultOpt::AllV,
+        );
+        path.append(init_vertex2());
+        let record = Record::new(path, Some(TAG_A.into()));
+
+        let tag_key = TagKey { tag: Some(TAG_A.into()), key: Some(PropKey::Vertices) };
+        let entry = tag_key.get_arc_entry(&record).unwrap();
+        assert_eq!(entry.get_type(), EntryType::Collection);
+        assert_eq!(entry.len(), 2);
+
+        let tag_key = TagKey { tag: Some(TAG_A.into()), key: Some(PropKey::Element(-1)) };
+        let entry = tag_key.get_arc_entry(&record).unwrap();
+        assert_eq!(entry.as_vertex().unwrap().id(), init_vertex2().id());
+
+        let tag_key = TagKey { tag: Some(TAG_A.into()), key: Some(PropKey::Element(2)) };
+        let entry = tag_key.get_arc_entry(&record).unwrap();
+        assert!(entry.is_none());
+    }
+
+    #[test]
+    fn test_super_node_policy() {
+        let neighbors = || -> DynIter<ID> { Box::new(0..10) };
+        let truncated: Vec<ID> = SuperNodePolicy::Truncate(3)
+            .apply(1, neighbors())
+            .unwrap()
+            .collect();
+        assert_eq!(truncated, vec![0, 1, 2]);
+
+        let mut sampled: Vec<ID> = SuperNodePolicy::Sample(3)
+            .apply(1, neighbors())
+            .unwrap()
+            .collect();
+        sampled.sort();
+        sampled.dedup();
+        assert_eq!(sampled.len(), 3);
+        assert!(sampled.iter().all(|id| *id < 10));
+
+        assert!(SuperNodePolicy::Fail(3)
+            .apply(1, neighbors())
+            .is_err());
+        // not a super node
+        let all: Vec<ID> = SuperNodePolicy::Fail(10)
+            .apply(1, neighbors())
+            .unwrap()
+            .collect();
+        assert_eq!(all, (0..10).collect::<Vec<ID>>());
+    }
 }
@@ -13,6 +13,7 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 mod sink;
+pub mod sink_result;
 #[cfg(feature = "with_v6d")]
 mod sink_vineyard;
 
@@ -23,12 +24,15 @@ use ir_common::generated::algebra as algebra_pb;
 use ir_common::generated::physical as pb;
 
 use crate::error::FnGenResult;
-use crate::process::operator::sink::sink::{DefaultSinkOp, RecordSinkEncoder};
+use crate::process::operator::sink::sink::{ColumnarSinkEncoder, DefaultSinkOp, RecordSinkEncoder};
+use crate::process::operator::sink::sink_result::{ResultSinkEncoder, SinkResultOp};
 #[cfg(feature = "with_v6d")]
 use crate::process::operator::sink::sink_vineyard::{GraphSinkEncoder, SinkVineyardOp};
 
 pub enum Sinker {
     DefaultSinker(RecordSinkEncoder),
+    ColumnarSinker(ColumnarSinkEncoder),
+    ResultSinker(ResultSinkEncoder),
     #[cfg(feature = "with_v6d")]
     GraphSinker(GraphSinkEncoder),
 }
@@ -60,10 +64,20 @@ impl SinkGen for pb::Sink {
                 .collect();
             match inner {
                 algebra_pb::sink::sink_target::Inner::SinkDefault(sink_default) => {
+                    let layout = algebra_pb::sink_default::Layout::from_i32(sink_default.layout)
+                        .ok_or_else(|| {
+                            ParsePbError::from(format!(
+                                "invalid layout {:?} of the sink",
+                                sink_default.layout
+                            ))
+                        })?;
                     let default_sink_op =
-                        DefaultSinkOp { tags, id_name_mappings: sink_default.id_name_mappings };
+                        DefaultSinkOp { tags, id_name_mappings: sink_default.id_name_mappings, layout };
                     default_sink_op.gen_sink()
                 }
+                algebra_pb::sink::sink_target::Inner::SinkResult(sink_result) => {
+                    SinkResultOp { tags, name: sink_result.name }.gen_sink()
+                }
                 algebra_pb::sink::sink_target::Inner::SinkVineyard(_sink_vineyard) => {
                     #[cfg(feature = "with_v6d")]
                     {
@@ -78,6 +92,12 @@ impl SinkGen for pb::Sink {
                     Err(graph_proxy::GraphProxyError::UnSupported(
                                     "sink_target of Vineyard is not as a feature. Try \'cargo build --features with_v6d\'".to_string()))?
                 }
+                algebra_pb::sink::sink_target::Inner::SinkBulkLoad(sink_bulk_load) => {
+                    Err(graph_proxy::GraphProxyError::UnSupported(format!(
+                        "bulk load from {:?} into the graph",
+                        sink_bulk_load.uri
+                    )))?
+                }
             }
         } else {
             Err(ParsePbError::EmptyFieldError("sink_target is missing".to_string()))?
@@ -34,7 +34,7 @@ use crate::process::operator::map::IntersectionEntry;
 use crate::process::operator::sink::{SinkGen, Sinker};
 use crate::process::record::Record;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct RecordSinkEncoder {
     /// the given column tags to sink;
     sink_keys: Vec<Option<KeyId>>,
@@ -222,10 +222,8 @@ impl RecordSinkEncoder {
         }
         result_pb::GraphPath { path: graph_path_pb }
     }
-}
 
-impl MapFunction<Record, Vec<u8>> for RecordSinkEncoder {
-    fn exec(&self, mut input: Record) -> FnResult<Vec<u8>> {
+    fn record_to_pb(&self, input: &mut Record) -> result_pb::Record {
         let mut sink_columns = Vec::with_capacity(self.sink_keys.len());
         if self.sink_keys.is_empty() {
             // the case of sink all **tagged** columns by default.
@@ -253,15 +251,86 @@ impl MapFunction<Record, Vec<u8>> for RecordSinkEncoder {
             }
         }
 
-        let record_pb = result_pb::Record { columns: sink_columns };
+        result_pb::Record { columns: sink_columns }
+    }
+}
+
+impl MapFunction<Record, Vec<u8>> for RecordSinkEncoder {
+    fn exec(&self, mut input: Record) -> FnResult<Vec<u8>> {
+        let record_pb = self.record_to_pb(&mut input);
         let results = result_pb::Results { inner: Some(result_pb::results::Inner::Record(record_pb)) };
         Ok(results.encode_to_vec())
     }
 }
 
+/// The number of records in a batch of the columnar layout
+const COLUMN_BATCH_SIZE: usize = 1024;
+
+/// Encode the records into the batches of the columnar layout, each of which holds up to
+/// `COLUMN_BATCH_SIZE` records, and is returned once full, such that the batches are streamed
+/// to the client while the records are still being produced.
+#[derive(Clone, Debug)]
+pub struct ColumnarSinkEncoder {
+    encoder: RecordSinkEncoder,
+    records: Vec<result_pb::Record>,
+}
+
+impl ColumnarSinkEncoder {
+    /// Encode the next record, and return the batch once `COLUMN_BATCH_SIZE` records are pending.
+    pub fn encode(&mut self, mut next: Record) -> Option<Vec<u8>> {
+        let record_pb = self.encoder.record_to_pb(&mut next);
+        self.records.push(record_pb);
+        if self.records.len() >= COLUMN_BATCH_SIZE {
+            self.flush()
+        } else {
+            None
+        }
+    }
+
+    /// Turn the pending records into a batch, where the columns are ordered as they first appear,
+    /// and a record without a column has an empty entry in it, which is decoded as null.
+    pub fn flush(&mut self) -> Option<Vec<u8>> {
+        if self.records.is_empty() {
+            return None;
+        }
+        let records = std::mem::take(&mut self.records);
+        let num_rows = records.len();
+        let mut columns: Vec<result_pb::column_batch::Column> = vec![];
+        for (row, record) in records.into_iter().enumerate() {
+            for column in record.columns {
+                let index = match columns
+                    .iter()
+                    .position(|c| c.name_or_id == column.name_or_id)
+                {
+                    Some(index) => index,
+                    None => {
+                        columns.push(result_pb::column_batch::Column {
+                            name_or_id: column.name_or_id,
+                            entries: vec![],
+                        });
+                        columns.len() - 1
+                    }
+                };
+                let entries = &mut columns[index].entries;
+                entries.resize(row, result_pb::Entry::default());
+                entries.push(column.entry.unwrap_or_default());
+            }
+        }
+        for column in columns.iter_mut() {
+            column
+                .entries
+                .resize(num_rows, result_pb::Entry::default());
+        }
+        let batch = result_pb::ColumnBatch { columns, num_rows: num_rows as i32 };
+        let results = result_pb::Results { inner: Some(result_pb::results::Inner::ColumnBatch(batch)) };
+        Some(results.encode_to_vec())
+    }
+}
+
 pub struct DefaultSinkOp {
     pub tags: Vec<Option<KeyId>>,
     pub id_name_mappings: Vec<algebra_pb::sink_default::IdNameMapping>,
+    pub layout: algebra_pb::sink_default::Layout,
 }
 
 impl SinkGen for DefaultSinkOp {
@@ -276,8 +345,61 @@ impl SinkGen for DefaultSinkOp {
             schema_map: if schema_map.is_empty() { None } else { Some(schema_map) },
         };
         if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
-            debug!("Runtime sink operator: {:?}", record_sinker);
+            debug!("Runtime sink operator: {:?} of layout {:?}", record_sinker, self.layout);
+        }
+        match self.layout {
+            algebra_pb::sink_default::Layout::Row => Ok(Sinker::DefaultSinker(record_sinker)),
+            algebra_pb::sink_default::Layout::Columnar => {
+                Ok(Sinker::ColumnarSinker(ColumnarSinkEncoder { encoder: record_sinker, records: vec![] }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::operator::tests::{init_vertex1, init_vertex2};
+
+    fn decode_batch(bytes: Vec<u8>) -> result_pb::ColumnBatch {
+        match result_pb::Results::decode(bytes.as_slice())
+            .unwrap()
+            .inner
+        {
+            Some(result_pb::results::Inner::ColumnBatch(batch)) => batch,
+            inner => panic!("expect a column batch but got {:?}", inner),
         }
-        Ok(Sinker::DefaultSinker(record_sinker))
+    }
+
+    #[test]
+    fn columnar_sink_streams_batches() {
+        let mut encoder = ColumnarSinkEncoder {
+            encoder: RecordSinkEncoder { sink_keys: vec![None, Some(0)], schema_map: None },
+            records: vec![],
+        };
+        // a batch is returned once full, ahead of the end of the records
+        for _ in 0..COLUMN_BATCH_SIZE - 1 {
+            assert!(encoder
+                .encode(Record::new(init_vertex1(), None))
+                .is_none());
+        }
+        let batch = decode_batch(
+            encoder
+                .encode(Record::new(init_vertex1(), None))
+                .unwrap(),
+        );
+        assert_eq!(batch.num_rows as usize, COLUMN_BATCH_SIZE);
+        assert!(encoder.flush().is_none());
+
+        // a record without the column has an empty entry in it
+        let mut record = Record::new(init_vertex1(), None);
+        record.append(init_vertex2(), Some(0));
+        encoder.encode(Record::new(init_vertex1(), None));
+        encoder.encode(record);
+        let batch = decode_batch(encoder.flush().unwrap());
+        assert_eq!(batch.num_rows, 2);
+        assert_eq!(batch.columns.len(), 2);
+        assert!(batch.columns[1].entries[0].inner.is_none());
+        assert!(batch.columns[1].entries[1].inner.is_some());
     }
 }
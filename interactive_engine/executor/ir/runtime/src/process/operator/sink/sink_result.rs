@@ -0,0 +1,235 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//! The results registered by name (i.e., sunk via `SinkResult`), which are kept in the memory of
+//! the server until being replaced or released, and are read back by the subsequent queries via
+//! `Scan.named_result`. The results are registered in the scope of `PhysicalPlan.result_scope`, e.g.,
+//! of a bundle, and a query only reads those of its own scope, such that the results of the same
+//! name in different scopes never clash. A registered result is partitioned as the workers of the
+//! job that produces it, and the `i`-th partition is read back by the worker of the index `i % n`
+//! of the `n` workers reading it. A result is released once the query listing it in
+//! `PhysicalPlan.released_results` has read it, or via [`release_result`].
+
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+use ir_common::KeyId;
+
+use crate::error::{FnExecError, FnExecResult, FnGenError, FnGenResult};
+use crate::process::entry::DynEntry;
+use crate::process::operator::accum::accumulator::Accumulator;
+use crate::process::operator::sink::{SinkGen, Sinker};
+use crate::process::record::Record;
+
+/// The partitions of a registered result, as registered by the workers of the job of `job_id`.
+#[derive(Default)]
+struct NamedResult {
+    job_id: u64,
+    partitions: HashMap<u32, Vec<DynEntry>>,
+}
+
+/// The key of a registered result, as its scope and name.
+type ResultKey = (u64, String);
+
+lazy_static! {
+    static ref NAMED_RESULTS: RwLock<HashMap<ResultKey, NamedResult>> = RwLock::new(HashMap::new());
+}
+
+fn poisoned_error<T>(_: PoisonError<T>) -> FnExecError {
+    FnExecError::unexpected_data_error("the named results are poisoned by a panic while updating them")
+}
+
+/// Register the entries as the partition of the worker of `worker_index` in the result `name` of
+/// the `scope`, where the job of `job_id` that registers the result replaces all the partitions
+/// registered by another job before.
+pub fn register_result(
+    scope: u64, name: String, job_id: u64, worker_index: u32, entries: Vec<DynEntry>,
+) -> FnExecResult<()> {
+    let mut results = NAMED_RESULTS.write().map_err(poisoned_error)?;
+    let result = results.entry((scope, name)).or_default();
+    if result.job_id != job_id {
+        *result = NamedResult { job_id, partitions: HashMap::new() };
+    }
+    result.partitions.insert(worker_index, entries);
+    Ok(())
+}
+
+/// Whether the partition of `index` is read by the worker of `worker_index` of the `total_peers`
+/// workers, such that the partitions of more workers than those reading them are all read.
+fn is_read_by(index: u32, worker_index: u32, total_peers: u32) -> bool {
+    index % total_peers.max(1) == worker_index
+}
+
+/// Get the partitions read by the worker of `worker_index` of the `total_peers` workers in the
+/// result `name` of the `scope`, which is empty if none of them is registered, or none if the
+/// result is not registered.
+pub fn get_result(
+    scope: u64, name: &str, worker_index: u32, total_peers: u32,
+) -> FnExecResult<Option<Vec<DynEntry>>> {
+    let results = NAMED_RESULTS.read().map_err(poisoned_error)?;
+    Ok(results
+        .get(&(scope, name.to_string()))
+        .map(|result| {
+            let mut indices: Vec<_> = result
+                .partitions
+                .keys()
+                .filter(|&&index| is_read_by(index, worker_index, total_peers))
+                .collect();
+            indices.sort_unstable();
+            indices
+                .into_iter()
+                .flat_map(|index| result.partitions[index].iter().cloned())
+                .collect()
+        }))
+}
+
+/// Release the partitions read by the worker of `worker_index` of the `total_peers` workers in the
+/// result `name` of the `scope`. The result is removed once all its partitions are released.
+pub fn release_partition(scope: u64, name: &str, worker_index: u32, total_peers: u32) -> FnExecResult<()> {
+    let mut results = NAMED_RESULTS.write().map_err(poisoned_error)?;
+    let key = (scope, name.to_string());
+    if let Some(result) = results.get_mut(&key) {
+        result
+            .partitions
+            .retain(|&index, _| !is_read_by(index, worker_index, total_peers));
+        if result.partitions.is_empty() {
+            results.remove(&key);
+        }
+    }
+    Ok(())
+}
+
+/// Release all the partitions of the result `name` of the `scope`, and return whether it is registered.
+pub fn release_result(scope: u64, name: &str) -> FnExecResult<bool> {
+    let mut results = NAMED_RESULTS.write().map_err(poisoned_error)?;
+    Ok(results
+        .remove(&(scope, name.to_string()))
+        .is_some())
+}
+
+#[derive(Clone, Debug)]
+pub struct ResultSinkEncoder {
+    scope: u64,
+    name: String,
+    sink_key: Option<KeyId>,
+    entries: Vec<DynEntry>,
+}
+
+impl Accumulator<Record, Record> for ResultSinkEncoder {
+    fn accum(&mut self, mut next: Record) -> FnExecResult<()> {
+        let entry = next
+            .take(self.sink_key.as_ref())
+            .ok_or(FnExecError::get_tag_error(&format!(
+                "tag {:?} in ResultSinker on {:?}",
+                self.sink_key, next
+            )))?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> FnExecResult<Record> {
+        let worker = pegasus::get_current_worker();
+        register_result(
+            self.scope,
+            self.name.clone(),
+            worker.job_id,
+            worker.index,
+            std::mem::take(&mut self.entries),
+        )?;
+        Ok(Record::default())
+    }
+}
+
+impl ResultSinkEncoder {
+    /// Register the result in the given scope rather than the default one.
+    pub fn set_scope(&mut self, scope: u64) {
+        self.scope = scope;
+    }
+}
+
+pub struct SinkResultOp {
+    pub tags: Vec<Option<KeyId>>,
+    pub name: String,
+}
+
+impl SinkGen for SinkResultOp {
+    fn gen_sink(self) -> FnGenResult<Sinker> {
+        if self.tags.len() > 1 {
+            Err(FnGenError::unsupported_error("register more than one column as a named result"))?
+        }
+        let result_sink_encoder = ResultSinkEncoder {
+            scope: 0,
+            name: self.name,
+            sink_key: self.tags.into_iter().next().flatten(),
+            entries: vec![],
+        };
+        if log_enabled!(log::Level::Debug) && pegasus::get_current_worker().index == 0 {
+            debug!("Runtime sink result operator: {:?}", result_sink_encoder);
+        }
+        Ok(Sinker::ResultSinker(result_sink_encoder))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dyn_type::Object;
+
+    use super::*;
+    use crate::process::entry::Entry;
+
+    fn entries(values: Vec<i64>) -> Vec<DynEntry> {
+        values
+            .into_iter()
+            .map(|value| DynEntry::new(Object::from(value)))
+            .collect()
+    }
+
+    fn values(entries: Option<Vec<DynEntry>>) -> Vec<i64> {
+        entries
+            .unwrap()
+            .iter()
+            .map(|entry| entry.as_object().unwrap().as_i64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn named_results_of_scopes() {
+        register_result(1, "scoped".to_string(), 1, 0, entries(vec![1])).unwrap();
+        register_result(2, "scoped".to_string(), 2, 0, entries(vec![2])).unwrap();
+        assert_eq!(values(get_result(1, "scoped", 0, 1).unwrap()), vec![1]);
+        assert_eq!(values(get_result(2, "scoped", 0, 1).unwrap()), vec![2]);
+        assert!(get_result(3, "scoped", 0, 1).unwrap().is_none());
+
+        assert!(release_result(1, "scoped").unwrap());
+        assert!(get_result(1, "scoped", 0, 1).unwrap().is_none());
+        assert_eq!(values(get_result(2, "scoped", 0, 1).unwrap()), vec![2]);
+        assert!(release_result(2, "scoped").unwrap());
+    }
+
+    #[test]
+    fn named_results_of_more_writers() {
+        // registered by 4 workers, and read by 2 workers
+        for index in 0..4 {
+            register_result(3, "partitioned".to_string(), 3, index, entries(vec![index as i64])).unwrap();
+        }
+        assert_eq!(values(get_result(3, "partitioned", 0, 2).unwrap()), vec![0, 2]);
+        assert_eq!(values(get_result(3, "partitioned", 1, 2).unwrap()), vec![1, 3]);
+
+        release_partition(3, "partitioned", 0, 2).unwrap();
+        assert_eq!(values(get_result(3, "partitioned", 1, 2).unwrap()), vec![1, 3]);
+        release_partition(3, "partitioned", 1, 2).unwrap();
+        assert!(!release_result(3, "partitioned").unwrap());
+    }
+}
@@ -125,6 +125,7 @@ mod tests {
                 order: 1, // ascending
             }],
             limit: None,
+            per_partition: false,
         };
         let mut result = sort_test(init_source(), sort_opr);
         let mut result_ids = vec![];
@@ -146,6 +147,7 @@ mod tests {
                 order: 2, // descending
             }],
             limit: None,
+            per_partition: false,
         };
         let mut result = sort_test(init_source(), sort_opr);
         let mut result_ids = vec![];
@@ -167,6 +169,7 @@ mod tests {
                 order: 2, // descending
             }],
             limit: None,
+            per_partition: false,
         };
         let mut result = sort_test(init_source(), sort_opr);
         let mut result_name = vec![];
@@ -208,6 +211,7 @@ mod tests {
                 },
             ],
             limit: None,
+            per_partition: false,
         };
         let mut result = sort_test(source, sort_opr);
         let mut result_name_ages = vec![];
@@ -244,6 +248,7 @@ mod tests {
                 order: 2, // descending
             }],
             limit: None,
+            per_partition: false,
         };
         let mut result = sort_test(init_source_with_tag(), sort_opr);
         let mut result_ids = vec![];
@@ -265,6 +270,7 @@ mod tests {
                 order: 2, // descending
             }],
             limit: None,
+            per_partition: false,
         };
         let mut result = sort_test(init_source_with_tag(), sort_opr);
         let mut result_ids = vec![];
@@ -13,7 +13,7 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 
@@ -27,6 +27,7 @@ use ir_common::generated::physical as pb;
 use ir_common::{KeyId, NameOrId};
 
 use crate::error::{FnGenError, FnGenResult};
+use crate::process::operator::sink::sink_result::get_result;
 use crate::process::record::Record;
 use crate::router::Router;
 
@@ -35,6 +36,8 @@ pub enum SourceType {
     Vertex,
     Edge,
     Table,
+    /// Scanning the result registered of the name
+    NamedResult(String),
     Dummy,
 }
 
@@ -43,9 +46,13 @@ pub enum SourceType {
 pub struct SourceOperator {
     query_params: QueryParams,
     src: Option<HashMap<u64, Vec<ID>>>,
+    /// The workers that do scan, as those accessing the partitions of the data, or all if not given
+    workers: Option<HashSet<u64>>,
     primary_key_values: Option<PKV>,
     alias: Option<KeyId>,
     source_type: SourceType,
+    /// The scope of the named result to scan, see `PhysicalPlan.result_scope`
+    result_scope: u64,
 }
 
 impl Default for SourceOperator {
@@ -53,9 +60,11 @@ impl Default for SourceOperator {
         SourceOperator {
             query_params: QueryParams::default(),
             src: None,
+            workers: None,
             primary_key_values: None,
             alias: None,
             source_type: SourceType::Dummy,
+            result_scope: 0,
         }
     }
 }
@@ -67,6 +76,7 @@ impl SourceOperator {
         let op_kind = op.try_into()?;
         match op_kind {
             pb::physical_opr::operator::OpKind::Scan(mut scan) => {
+                let partitions = std::mem::take(&mut scan.partitions);
                 if let Some(index_predicate) = scan.idx_predicate.take() {
                     let ip = index_predicate.clone();
                     let ip2 = index_predicate.clone();
@@ -83,11 +93,13 @@ impl SourceOperator {
                         // query by indexed_scan
                         let primary_key_values = <Vec<(NameOrId, Object)>>::try_from(ip2)?;
                         source_op.primary_key_values = Some(PKV::from(primary_key_values));
+                        source_op.set_workers(partitions, partitioner)?;
                         debug!("Runtime source op of indexed scan {:?}", source_op);
                     }
                     Ok(source_op)
                 } else {
-                    let source_op = SourceOperator::try_from(scan)?;
+                    let mut source_op = SourceOperator::try_from(scan)?;
+                    source_op.set_workers(partitions, partitioner)?;
                     debug!("Runtime source op of scan {:?}", source_op);
                     Ok(source_op)
                 }
@@ -119,11 +131,42 @@ impl SourceOperator {
         self.src = Some(partitions);
         Ok(())
     }
+
+    /// Restrict the scan to the workers that access the given partitions, if any
+    fn set_workers<P: PartitionInfo, C: ClusterInfo>(
+        &mut self, partitions: Vec<u32>, partitioner: Arc<dyn Router<P = P, C = C>>,
+    ) -> ParsePbResult<()> {
+        if !partitions.is_empty() {
+            let mut workers = HashSet::new();
+            for partition in partitions {
+                match partitioner.route_partition(partition) {
+                    Ok(wids) => workers.extend(wids),
+                    Err(err) => Err(ParsePbError::Unsupported(format!(
+                        "get the workers of partition {} failed in source op {:?}",
+                        partition, err
+                    )))?,
+                }
+            }
+            self.workers = Some(workers);
+        }
+        Ok(())
+    }
 }
 
 impl SourceOperator {
+    /// Scan the named result in the given scope rather than the default one.
+    pub fn set_result_scope(&mut self, result_scope: u64) {
+        self.result_scope = result_scope;
+    }
+
     pub fn gen_source(self, worker_index: usize) -> FnGenResult<Box<dyn Iterator<Item = Record> + Send>> {
         let graph = get_graph().ok_or(FnGenError::NullGraphError)?;
+        if let Some(ref workers) = self.workers {
+            if !workers.contains(&(worker_index as u64)) {
+                // the worker accesses none of the partitions that hold the data to scan
+                return Ok(Box::new(std::iter::empty()));
+            }
+        }
 
         match self.source_type {
             SourceType::Vertex => {
@@ -169,9 +212,27 @@ impl SourceOperator {
                 }
                 Ok(Box::new(e_source.map(move |e| Record::new(e, self.alias.clone()))))
             }
+            // an external table is rejected while building the job plan, see `AsPhysical for pb::Scan`
             SourceType::Table => Err(FnGenError::unsupported_error(
-                "neither `Edge` nor `Vertex` but `Table` type `Source` opr",
+                "`Table` type `Source` opr that is not a named result",
             ))?,
+            SourceType::NamedResult(name) => {
+                let total_peers = pegasus::get_current_worker().total_peers();
+                let entries = get_result(self.result_scope, &name, worker_index as u32, total_peers)
+                    .map_err(|e| FnGenError::unsupported_error(&e.to_string()))?
+                    .ok_or_else(|| {
+                        FnGenError::unsupported_error(&format!(
+                            "scan the result {:?}, which is not registered",
+                            name
+                        ))
+                    })?;
+                let alias = self.alias;
+                Ok(Box::new(entries.into_iter().map(move |entry| {
+                    let mut record = Record::default();
+                    record.append_arc_entry(entry, alias);
+                    record
+                })))
+            }
             SourceType::Dummy => {
                 // a dummy record to trigger the computation
                 Ok(Box::new(vec![Record::new(Object::None, None)].into_iter())
@@ -189,15 +250,20 @@ impl TryFrom<pb::Scan> for SourceOperator {
         let source_type = match scan_opt {
             algebra_pb::scan::ScanOpt::Vertex => SourceType::Vertex,
             algebra_pb::scan::ScanOpt::Edge => SourceType::Edge,
+            algebra_pb::scan::ScanOpt::Table if !scan_pb.named_result.is_empty() => {
+                SourceType::NamedResult(scan_pb.named_result)
+            }
             algebra_pb::scan::ScanOpt::Table => SourceType::Table,
         };
         let query_params = QueryParams::try_from(scan_pb.params)?;
         Ok(SourceOperator {
             query_params,
             src: None,
+            workers: None,
             primary_key_values: None,
             alias: scan_pb.alias,
             source_type,
+            result_scope: 0,
         })
     }
 }
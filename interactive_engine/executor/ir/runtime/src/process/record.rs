@@ -97,6 +97,13 @@ impl Record {
         }
     }
 
+    /// To rename the column of tag `from` as `to` if any, which replaces the existing column of `to`
+    pub fn rename(&mut self, from: KeyId, to: KeyId) {
+        if let Some(entry) = self.columns.remove(from as usize) {
+            self.columns.insert(to as usize, entry);
+        }
+    }
+
     /// To join this record with `other` record. After the join, the columns
     /// from both sides will be merged (and deduplicated). The `curr` entry of the joined
     /// record will be specified according to `is_left_opt`, namely, if
@@ -159,6 +166,13 @@ impl RecordKey {
     pub fn take(self) -> Vec<DynEntry> {
         self.key_fields
     }
+
+    /// Whether any field of the key is missing (null)
+    pub fn has_none(&self) -> bool {
+        self.key_fields
+            .iter()
+            .any(|field| field.is_none())
+    }
 }
 
 pub struct RecordExpandIter<E> {
@@ -16,7 +16,7 @@
 use std::sync::Arc;
 
 use graph_proxy::apis::cluster_info::ClusterInfo;
-use graph_proxy::apis::partitioner::{PartitionInfo, PartitionKeyId};
+use graph_proxy::apis::partitioner::{PartitionId, PartitionInfo, PartitionKeyId};
 use graph_proxy::GraphProxyResult;
 
 pub type WorkerId = u64;
@@ -31,6 +31,8 @@ pub trait Router: Send + Sync + 'static {
     /// A route function that given the data's partition key id, return the worker id that is going to do the query.
     /// Here, partition key id is for locating the data's partition given in `PartitionInfo`
     fn route(&self, data: PartitionKeyId) -> GraphProxyResult<WorkerId>;
+    /// Given the partition id, return the ids of the workers that are able to access the partition.
+    fn route_partition(&self, partition_id: PartitionId) -> GraphProxyResult<Vec<WorkerId>>;
 }
 
 /// A `DefaultRouter` is a default implementation of `Router` that can be used in most distributed cases.
@@ -70,4 +72,15 @@ impl<P: PartitionInfo, C: ClusterInfo> Router for DefaultRouter<P, C> {
         // to do the computation.
         Ok((server_id * workers_num + magic_num % workers_num) as WorkerId)
     }
+
+    fn route_partition(&self, partition_id: PartitionId) -> GraphProxyResult<Vec<WorkerId>> {
+        let server_id = self
+            .partition_info
+            .get_server_id(partition_id)?;
+        let workers_num = self.cluster_info.get_local_worker_num()?;
+        // All the workers in the machine that holds the partition are able to access it
+        Ok((server_id * workers_num..(server_id + 1) * workers_num)
+            .map(|worker_id| worker_id as WorkerId)
+            .collect())
+    }
 }